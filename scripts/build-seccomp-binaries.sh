@@ -51,17 +51,19 @@ build_platform() {
     local output_dir="$ROOT_DIR/vendor/seccomp/$vendor_dir"
     local bpf_file="$output_dir/unix-block.bpf"
     local apply_seccomp_bin="$output_dir/apply-seccomp"
+    local apply_landlock_bin="$output_dir/apply-landlock"
 
     echo ""
     echo "=========================================="
     echo "Building for: $vendor_dir ($docker_platform)"
     echo "=========================================="
 
-    # Check if both BPF file and apply-seccomp binary already exist
-    if [ -f "$bpf_file" ] && [ -f "$apply_seccomp_bin" ]; then
+    # Check if the BPF file and helper binaries already exist
+    if [ -f "$bpf_file" ] && [ -f "$apply_seccomp_bin" ] && [ -f "$apply_landlock_bin" ]; then
         echo "⊙ Files already exist, skipping build:"
         echo "  - BPF filter: $bpf_file ($(ls -lh "$bpf_file" | awk '{print $5}'))"
         echo "  - apply-seccomp: $apply_seccomp_bin ($(ls -lh "$apply_seccomp_bin" | awk '{print $5}'))"
+        echo "  - apply-landlock: $apply_landlock_bin ($(ls -lh "$apply_landlock_bin" | awk '{print $5}'))"
         return 0
     fi
 
@@ -119,6 +121,21 @@ build_platform() {
 
             echo 'Binary size:'
             ls -lh /output/apply-seccomp
+
+            echo ''
+            echo 'Building apply-landlock (no library dependencies)...'
+            gcc -o /output/apply-landlock /src/apply-landlock.c \
+                -static \
+                -O2 -Wall -Wextra
+
+            echo 'Stripping apply-landlock...'
+            strip /output/apply-landlock
+
+            echo 'Setting permissions...'
+            chmod +x /output/apply-landlock
+
+            echo 'Verifying apply-landlock binary...'
+            file /output/apply-landlock
         " || {
             echo "Error: Build failed for $vendor_dir"
             return 1
@@ -153,10 +170,11 @@ build_platform() {
     rm -f "$output_dir/seccomp-unix-block"
 
     # Verify final state
-    if [ -f "$bpf_file" ] && [ -f "$apply_seccomp_bin" ]; then
-        echo "✓ Success: BPF filter and apply-seccomp binary ready for $vendor_dir"
+    if [ -f "$bpf_file" ] && [ -f "$apply_seccomp_bin" ] && [ -f "$apply_landlock_bin" ]; then
+        echo "✓ Success: BPF filter and helper binaries ready for $vendor_dir"
         echo "  - BPF filter: $(ls -lh "$bpf_file" | awk '{print $5}')"
         echo "  - apply-seccomp: $(ls -lh "$apply_seccomp_bin" | awk '{print $5}')"
+        echo "  - apply-landlock: $(ls -lh "$apply_landlock_bin" | awk '{print $5}')"
         return 0
     else
         if [ ! -f "$bpf_file" ]; then
@@ -165,6 +183,9 @@ build_platform() {
         if [ ! -f "$apply_seccomp_bin" ]; then
             echo "✗ Error: apply-seccomp binary not found in $output_dir"
         fi
+        if [ ! -f "$apply_landlock_bin" ]; then
+            echo "✗ Error: apply-landlock binary not found in $output_dir"
+        fi
         return 1
     fi
 }
@@ -198,11 +219,14 @@ if [ ${#FAILED_PLATFORMS[@]} -eq 0 ]; then
     echo "Generated apply-seccomp binaries:"
     find "$ROOT_DIR/vendor/seccomp" -name "apply-seccomp" | sort
     echo ""
+    echo "Generated apply-landlock binaries:"
+    find "$ROOT_DIR/vendor/seccomp" -name "apply-landlock" | sort
+    echo ""
     echo "Total size:"
     du -sh "$ROOT_DIR/vendor/seccomp"
     echo ""
     echo "File sizes:"
-    find "$ROOT_DIR/vendor/seccomp" \( -name "*.bpf" -o -name "apply-seccomp" \) -exec ls -lh {} \; | awk '{print $9 ": " $5}'
+    find "$ROOT_DIR/vendor/seccomp" \( -name "*.bpf" -o -name "apply-seccomp" -o -name "apply-landlock" \) -exec ls -lh {} \; | awk '{print $9 ": " $5}'
     exit 0
 else
     echo "✗ Build failed for: ${FAILED_PLATFORMS[*]}"
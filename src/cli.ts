@@ -1,48 +1,158 @@
 #!/usr/bin/env node
 import { Command } from 'commander'
-import { SandboxManager } from './index.js'
+import {
+  SandboxManager,
+  SandboxTimeoutError,
+  SandboxViolationKillError,
+} from './index.js'
 import {
   SandboxRuntimeConfigSchema,
   type SandboxRuntimeConfig,
 } from './sandbox/sandbox-config.js'
-import { spawn } from 'child_process'
 import { logForDebugging } from './utils/debug.js'
+import {
+  ConfigExtendsError,
+  findProjectConfigPath,
+  getConfigLayerPaths,
+  getUserConfigPath,
+  mergeConfigLayers,
+  PROJECT_CONFIG_FILE,
+  readConfigLayers,
+  type ConfigLayer,
+} from './sandbox/config-layers.js'
+import {
+  ConfigInterpolationError,
+  interpolateConfigLayer,
+  type ConfigInterpolationContext,
+} from './sandbox/config-interpolation.js'
+import {
+  CONFIG_PRESET_NAMES,
+  type ConfigPresetName,
+} from './sandbox/config-presets.js'
+import {
+  buildStarterConfig,
+  detectProjects,
+  formatStarterConfigProposal,
+} from './sandbox/config-init.js'
+import {
+  forwardBrokerViolations,
+  spawnSandboxBroker,
+  startSandboxBroker,
+  type BrokerClient,
+} from './sandbox/sandbox-broker.js'
+import { startSandboxDaemon } from './sandbox/sandbox-daemon.js'
+import {
+  formatLearningReport,
+  getLearningConfig,
+  PolicyLearner,
+} from './sandbox/policy-learning.js'
+import { parseEnvAssignment, parseEnvFile } from './sandbox/sandbox-env.js'
+import { NetworkPrompter } from './sandbox/network-prompt.js'
+import { suspendProcessTree } from './sandbox/sandbox-execute.js'
+import {
+  addConfigListEntry,
+  ALLOW_KINDS,
+  describeBlockedAccesses,
+  formatConfigNote,
+  resolveConfigListEntry,
+} from './sandbox/config-edit.js'
+import {
+  formatConfigWithSources,
+  resolveConfigSources,
+} from './sandbox/config-sources.js'
+import {
+  formatPolicyDecision,
+  POLICY_ACTIONS,
+  simulatePolicy,
+  type PolicyAction,
+} from './sandbox/policy-simulation.js'
+import {
+  formatRunEvent,
+  RUN_OUTPUT_FORMATS,
+  SANDBOX_EXIT_CODES,
+  type RunEvent,
+  type RunResult,
+} from './sandbox/run-output.js'
+import { runSelftest, formatSelftestTable } from './sandbox/selftest.js'
+import {
+  describeSandboxWrapper,
+  formatSandboxWrapper,
+} from './sandbox/sandbox-profile.js'
+import {
+  formatDoctorReport,
+  hasUsableBackend,
+  runDoctor,
+} from './sandbox/doctor.js'
+import {
+  exportViolations,
+  getViolationExportFormat,
+  VIOLATION_EXPORT_FORMATS,
+  type ViolationExportFormat,
+} from './sandbox/violation-export.js'
+import {
+  clearViolationJournal,
+  readViolationJournal,
+} from './sandbox/violation-journal.js'
+import { followViolationFeed } from './sandbox/violation-feed.js'
+import {
+  classifyViolation,
+  VIOLATION_CATEGORIES,
+  type ViolationCategory,
+} from './sandbox/violation-policy.js'
+import {
+  matchesViolationQuery,
+  queryViolations,
+  type ViolationQuery,
+} from './sandbox/sandbox-violation-store.js'
+import type { SandboxViolationEvent } from './sandbox/macos-sandbox-utils.js'
+import {
+  formatViolationSummary,
+  ViolationSummaryBuilder,
+} from './sandbox/violation-summary.js'
+import {
+  selfUpdate,
+  rollbackSelfUpdate,
+  compareVersions,
+  getInstalledVersion,
+  getChannelVersion,
+  type ReleaseChannel,
+} from './utils/self-update.js'
 import * as fs from 'fs'
-import * as path from 'path'
 import * as os from 'os'
+import * as path from 'path'
+import * as readline from 'readline/promises'
+import * as tty from 'tty'
 
 /**
- * Load and validate sandbox configuration from a file
+ * Read one settings file and replace its placeholders, or null when it is
+ * unreadable. An empty file is an empty layer.
  */
-function loadConfig(filePath: string): SandboxRuntimeConfig | null {
+function readConfigLayer(
+  filePath: string,
+  context: ConfigInterpolationContext,
+): Record<string, unknown> | null {
   try {
-    if (!fs.existsSync(filePath)) {
-      return null
-    }
     const content = fs.readFileSync(filePath, 'utf-8')
     if (content.trim() === '') {
-      return null
+      return {}
     }
 
     // Parse JSON
     const parsed = JSON.parse(content)
-
-    // Validate with zod schema
-    const result = SandboxRuntimeConfigSchema.safeParse(parsed)
-
-    if (!result.success) {
-      console.error(`Invalid configuration in ${filePath}:`)
-      result.error.issues.forEach(issue => {
-        const path = issue.path.join('.')
-        console.error(`  - ${path}: ${issue.message}`)
-      })
+    if (
+      typeof parsed !== 'object' ||
+      parsed === null ||
+      Array.isArray(parsed)
+    ) {
+      console.error(`Invalid configuration in ${filePath}: expected an object`)
       return null
     }
-
-    return result.data
+    return interpolateConfigLayer(parsed, context)
   } catch (error) {
     // Log parse errors to help users debug invalid config files
-    if (error instanceof SyntaxError) {
+    if (error instanceof ConfigInterpolationError) {
+      console.error(`Invalid configuration in ${filePath}: ${error.message}`)
+    } else if (error instanceof SyntaxError) {
       console.error(`Invalid JSON in config file ${filePath}: ${error.message}`)
     } else {
       console.error(`Failed to load config from ${filePath}: ${error}`)
@@ -52,10 +162,108 @@ function loadConfig(filePath: string): SandboxRuntimeConfig | null {
 }
 
 /**
- * Get default config path
+ * Read a settings layer with the files it extends, lowest precedence first,
+ * or null when it or one of them is invalid
  */
-function getDefaultConfigPath(): string {
-  return path.join(os.homedir(), '.srt-settings.json')
+function readConfigLayerWithExtends(
+  filePath: string,
+  context: ConfigInterpolationContext,
+): ConfigLayer[] | null {
+  try {
+    return readConfigLayers(filePath, file => readConfigLayer(file, context))
+  } catch (error) {
+    if (error instanceof ConfigExtendsError) {
+      console.error(`Invalid configuration in ${filePath}: ${error.message}`)
+      return null
+    }
+    throw error
+  }
+}
+
+/**
+ * The settings file decisions are saved to: the project file when there is
+ * one, otherwise the user (or --settings) file
+ */
+function getEditableConfigPath(settingsPath?: string): string {
+  return (
+    findProjectConfigPath(process.cwd()) ?? settingsPath ?? getUserConfigPath()
+  )
+}
+
+/**
+ * Ask questions on the controlling terminal, even when stdin or stderr is
+ * redirected. The terminal stays in line mode and is only read while a
+ * question waits, so the sandboxed command keeps its input otherwise.
+ * Throws when there is no controlling terminal.
+ */
+function openTerminalPrompt(): {
+  question: (prompt: string) => Promise<string>
+  output: tty.WriteStream
+} {
+  const input = new tty.ReadStream(fs.openSync('/dev/tty', 'r'))
+  const output = new tty.WriteStream(fs.openSync('/dev/tty', 'w'))
+  input.pause()
+  const question = async (prompt: string): Promise<string> => {
+    const rl = readline.createInterface({ input, output, terminal: false })
+    try {
+      return await rl.question(prompt)
+    } finally {
+      rl.close()
+      input.pause()
+    }
+  }
+  return { question, output }
+}
+
+/**
+ * Load the system, user (or --settings) and project settings files, merge
+ * them and validate the result, along with the files it was merged from.
+ * Returns null when no layer has settings.
+ */
+function loadConfigWithLayers(
+  settingsPath?: string,
+): { config: SandboxRuntimeConfig; layers: ConfigLayer[] } | null {
+  const layerPaths = getConfigLayerPaths({ settingsPath })
+  const projectConfigPath = findProjectConfigPath(process.cwd())
+  const context: ConfigInterpolationContext = {
+    home: os.homedir(),
+    projectDir: projectConfigPath
+      ? path.dirname(projectConfigPath)
+      : process.cwd(),
+    env: process.env,
+  }
+  const layers = layerPaths
+    .map(layerPath => readConfigLayerWithExtends(layerPath, context))
+    .filter((layer): layer is ConfigLayer[] => layer !== null)
+    .flat()
+  if (layers.length === 0) {
+    return null
+  }
+  logForDebugging(`Loaded settings from ${layerPaths.join(', ')}`)
+
+  // Layers may set only part of the config, so merge them over the defaults
+  const merged = mergeConfigLayers([
+    getDefaultConfig() as Record<string, unknown>,
+    ...layers.map(layer => layer.config),
+  ])
+
+  // Validate with zod schema
+  const result = SandboxRuntimeConfigSchema.safeParse(merged)
+
+  if (!result.success) {
+    console.error(`Invalid configuration in ${layerPaths.join(', ')}:`)
+    result.error.issues.forEach(issue => {
+      const path = issue.path.join('.')
+      console.error(`  - ${path}: ${issue.message}`)
+    })
+    return null
+  }
+
+  return { config: result.data, layers }
+}
+
+function loadConfig(settingsPath?: string): SandboxRuntimeConfig | null {
+  return loadConfigWithLayers(settingsPath)?.config ?? null
 }
 
 /**
@@ -75,6 +283,118 @@ function getDefaultConfig(): SandboxRuntimeConfig {
   }
 }
 
+/** Collect the values of an option that can be repeated */
+function collectOptionValues(value: string, previous: string[]): string[] {
+  return [...previous, value]
+}
+
+/**
+ * Variables set with --env-file and --env, later ones winning
+ */
+function loadCommandEnv(
+  envFiles: string[],
+  assignments: string[],
+): Record<string, string> {
+  const env: Record<string, string> = {}
+  for (const envFile of envFiles) {
+    try {
+      Object.assign(env, parseEnvFile(fs.readFileSync(envFile, 'utf8')))
+    } catch (error) {
+      throw new Error(
+        `Invalid env file ${envFile}: ${error instanceof Error ? error.message : String(error)}`,
+      )
+    }
+  }
+  for (const assignment of assignments) {
+    const [name, value] = parseEnvAssignment(assignment)
+    env[name] = value
+  }
+  return env
+}
+
+interface ViolationQueryOptions {
+  settings?: string
+  log?: string
+  category?: string
+  since?: string
+  until?: string
+  command?: string
+  target?: string
+  process?: string
+}
+
+function addViolationQueryOptions(command: Command): Command {
+  return command
+    .option(
+      '-s, --settings <path>',
+      'path to user config file (default: ~/.srt-settings.json)',
+    )
+    .option(
+      '--log <path>',
+      'violation log to read (default: violationLogPath from the config)',
+    )
+    .option(
+      '--category <kinds>',
+      `comma-separated kinds: ${VIOLATION_CATEGORIES.join(', ')}`,
+    )
+    .option('--since <time>', 'only violations at or after this ISO 8601 time')
+    .option('--until <time>', 'only violations before this ISO 8601 time')
+    .option('--command <pattern>', 'only violations of matching commands')
+    .option('--target <pattern>', 'only violations of matching paths or hosts')
+    .option('--process <name>', 'only violations by this process (macOS only)')
+}
+
+function parseTime(
+  option: string,
+  value: string | undefined,
+): Date | undefined {
+  if (value === undefined) {
+    return undefined
+  }
+  const time = new Date(value)
+  if (Number.isNaN(time.getTime())) {
+    throw new Error(`${option} must be an ISO 8601 time, got '${value}'`)
+  }
+  return time
+}
+
+function parseViolationQuery(options: ViolationQueryOptions): ViolationQuery {
+  const categories = options.category?.split(',').map(c => c.trim())
+  for (const category of categories ?? []) {
+    if (!VIOLATION_CATEGORIES.includes(category as ViolationCategory)) {
+      throw new Error(
+        `Unknown violation kind '${category}'. Use ${VIOLATION_CATEGORIES.join(', ')}.`,
+      )
+    }
+  }
+  return {
+    category: categories as ViolationCategory[] | undefined,
+    since: parseTime('--since', options.since),
+    until: parseTime('--until', options.until),
+    command: options.command,
+    target: options.target,
+    processName: options.process,
+  }
+}
+
+function getViolationLogPath(
+  options: ViolationQueryOptions,
+  config: SandboxRuntimeConfig,
+): string {
+  const logPath = options.log ?? config.violationLogPath
+  if (!logPath) {
+    throw new Error(
+      'No violation log configured. Set violationLogPath or pass --log <path>.',
+    )
+  }
+  return logPath
+}
+
+function formatViolationLine(violation: SandboxViolationEvent): string {
+  const category = classifyViolation(violation) ?? 'audit'
+  return `${violation.timestamp.toISOString()}  ${category.padEnd(10)}  ${violation.line}`
+}
+
 async function main(): Promise<void> {
   const program = new Command()
 
@@ -91,17 +411,100 @@ async function main(): Promise<void> {
     .option('-d, --debug', 'enable debug logging')
     .option(
       '-s, --settings <path>',
-      'path to config file (default: ~/.srt-settings.json)',
+      'path to user config file (default: ~/.srt-settings.json)',
     )
     .option(
       '-c <command>',
       'run command string directly (like sh -c), no escaping applied',
     )
+    .option(
+      '--preset <name>',
+      `built-in policy to apply: ${CONFIG_PRESET_NAMES.join(', ')}`,
+    )
+    .option(
+      '--broker',
+      'run proxies and policy enforcement in a separate broker process',
+    )
+    .option(
+      '--timeout <seconds>',
+      'kill the command and its process group after this many seconds',
+    )
+    .option(
+      '--export-violations <path>',
+      'write the violations recorded during the run to a file',
+    )
+    .option(
+      '--violations-format <format>',
+      'format of --export-violations: json, csv or sarif (default: from the file extension)',
+    )
+    .option(
+      '--report <path>',
+      'write a JSON summary of the violations recorded during the run',
+    )
+    .option('--no-summary', 'do not print the violation summary after the run')
+    .option(
+      '--violation-feed <path>',
+      'stream violations as NDJSON to clients of this Unix socket during the run',
+    )
+    .option(
+      '--format <format>',
+      'output format: text, or json for one JSON result on stdout after the run',
+    )
+    .option(
+      '--json-output <path>',
+      'write the JSON result to this file instead of stdout, keeping it apart from the command output',
+    )
+    .option(
+      '--events <format>',
+      'stream lifecycle and violation events to stderr (ndjson)',
+    )
+    .option(
+      '-e, --env <name=value>',
+      'set a variable for the command, or pass NAME from this environment (repeatable)',
+      collectOptionValues,
+      [],
+    )
+    .option(
+      '--env-file <path>',
+      'set the variables in a NAME=value file for the command (repeatable)',
+      collectOptionValues,
+      [],
+    )
+    .option(
+      '-w, --workdir <dir>',
+      'run in this directory, as if srt was started there',
+    )
+    .option(
+      '--ask',
+      'ask in the terminal before allowing connections to domains the config does not allow',
+    )
     .allowUnknownOption()
+    // srt's options end at the command, so `srt grep -e x` or `srt ls -d`
+    // pass -e and -d to the command
+    .passThroughOptions()
     .action(
       async (
         commandArgs: string[],
-        options: { debug?: boolean; settings?: string; c?: string },
+        options: {
+          debug?: boolean
+          settings?: string
+          c?: string
+          preset?: string
+          broker?: boolean
+          timeout?: string
+          exportViolations?: string
+          violationsFormat?: string
+          report?: string
+          summary: boolean
+          violationFeed?: string
+          format?: string
+          jsonOutput?: string
+          events?: string
+          env: string[]
+          envFile: string[]
+          workdir?: string
+          ask?: boolean
+        },
       ) => {
         try {
           // Enable debug logging if requested
@@ -109,20 +512,220 @@ async function main(): Promise<void> {
             process.env.DEBUG = 'true'
           }
 
-          // Load config from file
-          const configPath = options.settings || getDefaultConfigPath()
-          let runtimeConfig = loadConfig(configPath)
+          let commandEnv: Record<string, string>
+          try {
+            commandEnv = loadCommandEnv(options.envFile, options.env)
+          } catch (error) {
+            console.error(
+              `Error: ${error instanceof Error ? error.message : String(error)}`,
+            )
+            process.exit(1)
+          }
 
-          if (!runtimeConfig) {
-            logForDebugging(
-              `No config found at ${configPath}, using default config`,
+          // Everything runs from the working directory, including the
+          // project config lookup, but paths given here stay relative to
+          // where srt was started
+          if (options.workdir) {
+            const stat = fs.statSync(options.workdir, { throwIfNoEntry: false })
+            if (!stat?.isDirectory()) {
+              console.error(
+                `Error: --workdir ${options.workdir} is not a directory`,
+              )
+              process.exit(1)
+            }
+            const absolute = (file?: string) => file && path.resolve(file)
+            options.settings = absolute(options.settings)
+            options.exportViolations = absolute(options.exportViolations)
+            options.report = absolute(options.report)
+            options.violationFeed = absolute(options.violationFeed)
+            options.jsonOutput = absolute(options.jsonOutput)
+            process.chdir(options.workdir)
+          }
+
+          const outputFormat = options.format ?? 'text'
+          if (!RUN_OUTPUT_FORMATS.includes(outputFormat as never)) {
+            console.error(
+              `Error: Unknown output format '${outputFormat}'. Use text or json.`,
             )
+            process.exit(1)
+          }
+          if (options.events !== undefined && options.events !== 'ndjson') {
+            console.error(
+              `Error: Unknown events format '${options.events}'. Use ndjson.`,
+            )
+            process.exit(1)
+          }
+          // Machine-readable output replaces the human-oriented messages
+          const jsonOutput = outputFormat === 'json'
+          // The result can go to a file while the messages stay readable
+          const collectResult = jsonOutput || options.jsonOutput !== undefined
+          const emitEvent = (event: RunEvent) => {
+            if (options.events) {
+              process.stderr.write(formatRunEvent(event))
+            }
+          }
+          const warnings: string[] = []
+          const warn = (message: string) => {
+            warnings.push(message)
+            emitEvent({ event: 'warning', time: new Date(), message })
+            if (!jsonOutput) {
+              console.error(`Warning: ${message}`)
+            }
+          }
+
+          // Load config from the system, user and project settings files
+          let runtimeConfig = loadConfig(options.settings)
+
+          if (!runtimeConfig) {
+            logForDebugging('No config found, using default config')
             runtimeConfig = getDefaultConfig()
           }
 
+          if (options.preset) {
+            if (!CONFIG_PRESET_NAMES.includes(options.preset as never)) {
+              console.error(
+                `Error: Unknown preset '${options.preset}'. Use ${CONFIG_PRESET_NAMES.join(', ')}.`,
+              )
+              process.exit(1)
+            }
+            runtimeConfig = {
+              ...runtimeConfig,
+              preset: options.preset as ConfigPresetName,
+            }
+          }
+
+          // Connections to domains the config does not allow wait for an
+          // answer on the terminal instead of being denied. The command is
+          // stopped while a question is shown, so its output doesn't run
+          // over the prompt and it can't read the answer.
+          let prompter: NetworkPrompter | undefined
+          let commandPid: number | undefined
+          if (options.ask) {
+            if (options.broker) {
+              console.error(
+                'Error: --ask cannot be used with --broker, whose proxies decide on their own',
+              )
+              process.exit(1)
+            }
+            let terminal: ReturnType<typeof openTerminalPrompt>
+            try {
+              terminal = openTerminalPrompt()
+            } catch {
+              console.error('Error: --ask needs a terminal to ask on')
+              process.exit(1)
+            }
+            const settingsPath = getEditableConfigPath(options.settings)
+            const question = async (prompt: string): Promise<string> => {
+              const resume =
+                commandPid !== undefined
+                  ? suspendProcessTree(commandPid)
+                  : undefined
+              try {
+                return await terminal.question(prompt)
+              } finally {
+                resume?.()
+              }
+            }
+            prompter = new NetworkPrompter(question, host => {
+              try {
+                const list = 'network.allowedDomains'
+                const added = addConfigListEntry(
+                  settingsPath,
+                  list,
+                  host,
+                  formatConfigNote(list, host, 'allowed at an --ask prompt'),
+                )
+                if (added) {
+                  terminal.output.write(
+                    `Added ${host} to network.allowedDomains in ${settingsPath}\n`,
+                  )
+                }
+              } catch (error) {
+                warn(
+                  `could not save ${host}: ${error instanceof Error ? error.message : String(error)}`,
+                )
+              }
+            })
+            // Ask even when the config allows no domains at all
+            runtimeConfig = {
+              ...runtimeConfig,
+              network: { ...runtimeConfig.network, askUnlistedDomains: true },
+            }
+          }
+
+          // In broker mode, the broker process owns the proxies and policy.
+          // This process only points the sandbox at the broker's proxy ports
+          // and copies the proxies' violations into its own store.
+          let brokerClient: BrokerClient | undefined
+          if (options.broker) {
+            logForDebugging('Starting sandbox broker...')
+            const broker = await spawnSandboxBroker(process.argv[1], {
+              settingsPath: options.settings,
+              preset: options.preset,
+            })
+            brokerClient = broker.client
+            const ports = await brokerClient.getProxyPorts()
+            runtimeConfig = {
+              ...runtimeConfig,
+              network: { ...runtimeConfig.network, ...ports },
+            }
+          }
+
+          if (options.violationFeed) {
+            runtimeConfig = {
+              ...runtimeConfig,
+              violationFeedSocket: options.violationFeed,
+            }
+          }
+
+          // --env and --env-file override the config's env.set
+          if (Object.keys(commandEnv).length > 0) {
+            runtimeConfig = {
+              ...runtimeConfig,
+              env: {
+                ...runtimeConfig.env,
+                set: { ...runtimeConfig.env?.set, ...commandEnv },
+              },
+            }
+          }
+
+          let exportFormat: ViolationExportFormat | undefined
+          if (options.exportViolations) {
+            const format =
+              options.violationsFormat ??
+              getViolationExportFormat(options.exportViolations)
+            if (!VIOLATION_EXPORT_FORMATS.includes(format as never)) {
+              console.error(
+                `Error: Unknown violations format '${format}'. Use json, csv or sarif.`,
+              )
+              process.exit(1)
+            }
+            exportFormat = format as ViolationExportFormat
+          }
+
           // Initialize sandbox with config
+          // (exporting violations, reports, the violation feed, violation
+          // policies and notifications need the monitor)
           logForDebugging('Initializing sandbox...')
-          await SandboxManager.initialize(runtimeConfig)
+          await SandboxManager.initialize(
+            runtimeConfig,
+            prompter?.ask,
+            exportFormat !== undefined ||
+              options.report !== undefined ||
+              runtimeConfig.violationFeedSocket !== undefined ||
+              runtimeConfig.violationPolicy !== undefined ||
+              runtimeConfig.notifications !== undefined ||
+              collectResult ||
+              options.events !== undefined,
+          )
+          SandboxManager.getTemporaryRuleWarnings().forEach(warn)
+          SandboxManager.getLimitWarnings().forEach(warn)
+          if (SandboxManager.isDegradedSandbox()) {
+            warn(
+              'sandbox dependencies are missing, running in the degraded sandbox. ' +
+                'Filesystem and network access are NOT isolated.',
+            )
+          }
 
           // Determine command string based on mode
           let command: string
@@ -149,37 +752,888 @@ async function main(): Promise<void> {
             ),
           )
 
-          // Wrap the command with sandbox restrictions
-          const sandboxedCommand = await SandboxManager.wrapWithSandbox(command)
+          const timeoutSeconds =
+            options.timeout !== undefined ? Number(options.timeout) : undefined
+          if (
+            timeoutSeconds !== undefined &&
+            !(Number.isFinite(timeoutSeconds) && timeoutSeconds > 0)
+          ) {
+            console.error('Error: --timeout must be a positive number of seconds')
+            process.exit(1)
+          }
 
-          // Execute the sandboxed command
-          const child = spawn(sandboxedCommand, {
-            shell: true,
-            stdio: 'inherit',
-          })
+          // Pass the signal srt receives on to the command
+          const abortController = new AbortController()
+          for (const signal of ['SIGINT', 'SIGTERM', 'SIGHUP'] as const) {
+            process.on(signal, () => abortController.abort(signal))
+          }
 
-          // Handle process exit
-          child.on('exit', (code, signal) => {
+          // Summarize the violations of this run as they are recorded
+          const summaryBuilder = new ViolationSummaryBuilder()
+          const runViolations: SandboxViolationEvent[] = []
+          const stopSummary = new AbortController()
+          const violationStore = SandboxManager.getSandboxViolationStore()
+          const summaryDone = (async () => {
+            for await (const violation of violationStore.stream({
+              signal: stopSummary.signal,
+            })) {
+              summaryBuilder.add(violation)
+              if (collectResult) {
+                runViolations.push(violation)
+              }
+              emitEvent({ event: 'violation', time: new Date(), violation })
+            }
+          })()
+
+          // The proxies' denials are recorded by the broker
+          const stopForwarding = brokerClient
+            ? forwardBrokerViolations(brokerClient, violationStore)
+            : undefined
+
+          // Wrap and run the command with sandbox restrictions
+          const backend = SandboxManager.getSandboxBackend()
+          const startedAt = Date.now()
+          emitEvent({ event: 'start', time: new Date(), command, backend })
+          // Human-oriented messages are left out of JSON output
+          const report = (message: string) => {
+            if (!jsonOutput) {
+              console.error(message)
+            }
+          }
+          // "warn" in the violation policy prints the violation
+          SandboxManager.addHooks({
+            onViolationWarning: ({ violation }) =>
+              report(`Sandbox violation: ${violation.line}`),
+          })
+          let exitCode: number
+          let exitSignal: NodeJS.Signals | null = null
+          let killedBy: RunResult['killedBy']
+          let executeError: string | undefined
+          try {
+            const { code, signal } = await SandboxManager.execute(command, {
+              abortSignal: abortController.signal,
+              timeoutSeconds,
+              onSpawn: child => {
+                commandPid = child.pid
+              },
+            })
             if (signal) {
-              console.error(`Process killed by signal: ${signal}`)
-              process.exit(1)
+              report(`Process killed by signal: ${signal}`)
+            }
+            exitSignal = signal
+            exitCode = signal ? 1 : (code ?? 0)
+          } catch (error) {
+            if (error instanceof SandboxTimeoutError) {
+              report(
+                `Command timed out after ${error.timeoutSeconds}s and was killed`,
+              )
+              exitCode = SANDBOX_EXIT_CODES.timeout
+              killedBy = 'timeout'
+            } else if (error instanceof SandboxViolationKillError) {
+              report(
+                `Command killed by the violation policy: ${error.violation.line}`,
+              )
+              exitCode = SANDBOX_EXIT_CODES.violationKill
+              killedBy = 'violation-policy'
+            } else {
+              executeError =
+                error instanceof Error ? error.message : String(error)
+              report(`Failed to execute command: ${executeError}`)
+              exitCode = SANDBOX_EXIT_CODES.setupFailure
             }
-            process.exit(code ?? 0)
+          }
+          const durationMs = Date.now() - startedAt
+
+          await stopForwarding?.()
+          stopSummary.abort()
+          await summaryDone
+          emitEvent({
+            event: 'exit',
+            time: new Date(),
+            exitCode,
+            signal: exitSignal,
+            durationMs,
           })
+          const summary = summaryBuilder.build()
+          if (options.summary && summary.total > 0 && !jsonOutput) {
+            console.error(formatViolationSummary(summary))
+          }
+          if (options.report) {
+            try {
+              fs.writeFileSync(
+                options.report,
+                JSON.stringify(summary, null, 2) + '\n',
+              )
+            } catch (error) {
+              console.error(
+                `Failed to write report: ${error instanceof Error ? error.message : String(error)}`,
+              )
+            }
+          }
+
+          if (options.exportViolations && exportFormat) {
+            try {
+              fs.writeFileSync(
+                options.exportViolations,
+                violationStore.export(exportFormat),
+              )
+            } catch (error) {
+              console.error(
+                `Failed to export violations: ${error instanceof Error ? error.message : String(error)}`,
+              )
+            }
+          }
 
-          child.on('error', error => {
-            console.error(`Failed to execute command: ${error.message}`)
+          await brokerClient?.shutdown().catch(error => {
+            logForDebugging(`Failed to shut down broker: ${error}`, {
+              level: 'error',
+            })
+          })
+          if (collectResult) {
+            const result: RunResult = {
+              command,
+              exitCode,
+              signal: exitSignal,
+              durationMs,
+              backend,
+              killedBy,
+              error: executeError,
+              warnings,
+              violations: runViolations,
+              summary,
+            }
+            const serialized = JSON.stringify(result) + '\n'
+            if (options.jsonOutput) {
+              try {
+                fs.writeFileSync(options.jsonOutput, serialized)
+              } catch (error) {
+                console.error(
+                  `Failed to write JSON result: ${error instanceof Error ? error.message : String(error)}`,
+                )
+              }
+            } else {
+              process.stdout.write(serialized)
+            }
+          }
+          process.exit(exitCode)
+        } catch (error) {
+          console.error(
+            `Error: ${error instanceof Error ? error.message : String(error)}`,
+          )
+          // Loading the config, starting the broker or initializing failed
+          process.exit(SANDBOX_EXIT_CODES.setupFailure)
+        }
+      },
+    )
+
+  program
+    .command('broker')
+    .description(
+      'run the sandbox broker (proxies, policy, violations) behind a control socket',
+    )
+    .requiredOption('--socket <path>', 'path of the control socket to create')
+    .option(
+      '-s, --settings <path>',
+      'path to user config file (default: ~/.srt-settings.json)',
+    )
+    .option('--preset <name>', 'apply a preset, as srt --preset would')
+    .action(
+      async (options: {
+        socket: string
+        settings?: string
+        preset?: string
+      }) => {
+        try {
+          let runtimeConfig =
+            loadConfig(options.settings) ?? getDefaultConfig()
+          if (options.preset) {
+            if (!CONFIG_PRESET_NAMES.includes(options.preset as never)) {
+              console.error(
+                `Error: Unknown preset '${options.preset}'. Use ${CONFIG_PRESET_NAMES.join(', ')}.`,
+              )
+              process.exit(1)
+            }
+            runtimeConfig = {
+              ...runtimeConfig,
+              preset: options.preset as ConfigPresetName,
+            }
+          }
+          await startSandboxBroker(runtimeConfig, options.socket)
+          logForDebugging(`Broker ready on ${options.socket}`)
+        } catch (error) {
+          console.error(
+            `Error: ${error instanceof Error ? error.message : String(error)}`,
+          )
+          process.exit(1)
+        }
+      },
+    )
+
+  program
+    .command('init')
+    .description(
+      `inspect the current project and write a starter ${PROJECT_CONFIG_FILE}`,
+    )
+    .option('-y, --yes', 'write the proposed policy without asking')
+    .option('--force', `overwrite an existing ${PROJECT_CONFIG_FILE}`)
+    .action(async (options: { yes?: boolean; force?: boolean }) => {
+      try {
+        const configPath = path.join(process.cwd(), PROJECT_CONFIG_FILE)
+        if (fs.existsSync(configPath) && !options.force) {
+          console.error(
+            `Error: ${configPath} already exists. Use --force to overwrite it.`,
+          )
+          process.exit(1)
+        }
+
+        const projects = detectProjects(process.cwd())
+        const starterConfig = buildStarterConfig(projects)
+        console.log(formatStarterConfigProposal(projects, starterConfig))
+
+        if (!options.yes) {
+          if (!process.stdin.isTTY) {
+            console.error(
+              'Error: Cannot ask for confirmation without a terminal. Use --yes to write the policy.',
+            )
             process.exit(1)
+          }
+          const rl = readline.createInterface({
+            input: process.stdin,
+            output: process.stdout,
           })
+          const answer = await rl.question(`\nWrite ${configPath}? [y/N] `)
+          rl.close()
+          if (!/^y(es)?$/i.test(answer.trim())) {
+            console.log('Nothing written')
+            return
+          }
+        }
+
+        fs.writeFileSync(
+          configPath,
+          JSON.stringify(starterConfig, null, 2) + '\n',
+        )
+        console.log(`Wrote ${configPath}`)
+      } catch (error) {
+        console.error(
+          `Error: ${error instanceof Error ? error.message : String(error)}`,
+        )
+        process.exit(1)
+      }
+    })
+
+  program
+    .command('daemon')
+    .description(
+      'keep the sandbox initialized and run commands sent over a Unix socket (NDJSON)',
+    )
+    .requiredOption('--socket <path>', 'path of the socket to create')
+    .option(
+      '-s, --settings <path>',
+      'path to user config file (default: ~/.srt-settings.json)',
+    )
+    .action(async (options: { socket: string; settings?: string }) => {
+      try {
+        const runtimeConfig = loadConfig(options.settings) ?? getDefaultConfig()
+        const daemon = await startSandboxDaemon(runtimeConfig, options.socket)
+        for (const warning of SandboxManager.getTemporaryRuleWarnings()) {
+          console.error(`Warning: ${warning}`)
+        }
+        console.error(`srt daemon listening on ${options.socket}`)
+        process.on('SIGINT', () => void daemon.close())
+        process.on('SIGTERM', () => void daemon.close())
+        await daemon.closed
+        process.exit(0)
+      } catch (error) {
+        console.error(
+          `Error: ${error instanceof Error ? error.message : String(error)}`,
+        )
+        process.exit(1)
+      }
+    })
+
+  program
+    .command('selftest')
+    .description(
+      'run probe commands in the sandbox and check the current config is enforced',
+    )
+    .option(
+      '-s, --settings <path>',
+      'path to user config file (default: ~/.srt-settings.json)',
+    )
+    .option('--json', 'print results as JSON instead of a table')
+    .action(async (options: { settings?: string; json?: boolean }) => {
+      try {
+        const runtimeConfig = loadConfig(options.settings) ?? getDefaultConfig()
+        await SandboxManager.initialize(runtimeConfig)
+        const results = await runSelftest(runtimeConfig)
+        await SandboxManager.reset()
+
+        console.log(
+          options.json
+            ? JSON.stringify(results, null, 2)
+            : formatSelftestTable(results),
+        )
+        process.exit(results.some(r => r.outcome === 'fail') ? 1 : 0)
+      } catch (error) {
+        console.error(
+          `Error: ${error instanceof Error ? error.message : String(error)}`,
+        )
+        process.exit(1)
+      }
+    })
+
+  const violations = program
+    .command('violations')
+    .description(
+      'list, follow, clear and export the violations recorded in the violation log',
+    )
+
+  addViolationQueryOptions(
+    violations
+      .command('list')
+      .description('print recorded violations, oldest first')
+      .option('--limit <n>', 'only the newest n matching violations')
+      .option('--json', 'print JSON (NDJSON with --follow)')
+      .option(
+        '-f, --follow',
+        'keep printing violations as another run reports them',
+      )
+      .option(
+        '--socket <path>',
+        'violation feed to follow (default: violationFeedSocket from the config)',
+      ),
+  ).action(
+    async (
+      options: ViolationQueryOptions & {
+        limit?: string
+        json?: boolean
+        follow?: boolean
+        socket?: string
+      },
+    ) => {
+      try {
+        const config = loadConfig(options.settings) ?? getDefaultConfig()
+        const query = parseViolationQuery(options)
+        const limit =
+          options.limit !== undefined ? Number(options.limit) : undefined
+        if (limit !== undefined && !(Number.isInteger(limit) && limit > 0)) {
+          throw new Error('--limit must be a positive integer')
+        }
+
+        // Following a feed doesn't need the history of a log
+        const logPath =
+          options.follow && !options.log && !config.violationLogPath
+            ? undefined
+            : getViolationLogPath(options, config)
+        const history = logPath
+          ? queryViolations(readViolationJournal(logPath), query).violations
+          : []
+        const shown = limit !== undefined ? history.slice(-limit) : history
+
+        if (options.json && !options.follow) {
+          console.log(JSON.stringify(shown, null, 2))
+          return
+        }
+        const print = (violation: SandboxViolationEvent) =>
+          console.log(
+            options.json
+              ? JSON.stringify(violation)
+              : formatViolationLine(violation),
+          )
+        shown.forEach(print)
+        if (!options.follow) {
+          return
+        }
+
+        const socketPath = options.socket ?? config.violationFeedSocket
+        if (!socketPath) {
+          throw new Error(
+            'No violation feed configured. Set violationFeedSocket or pass --socket <path>.',
+          )
+        }
+        const abortController = new AbortController()
+        process.on('SIGINT', () => abortController.abort())
+        try {
+          for await (const violation of followViolationFeed(
+            socketPath,
+            abortController.signal,
+          )) {
+            if (matchesViolationQuery(violation, query)) {
+              print(violation)
+            }
+          }
+        } catch (error) {
+          if (!abortController.signal.aborted) {
+            throw error
+          }
+        }
+      } catch (error) {
+        console.error(
+          `Error: ${error instanceof Error ? error.message : String(error)}`,
+        )
+        process.exit(1)
+      }
+    },
+  )
+
+  addViolationQueryOptions(
+    violations
+      .command('export')
+      .description('write recorded violations as JSON, CSV or SARIF')
+      .argument('<path>', 'file to write, or - for stdout')
+      .option(
+        '--format <format>',
+        'json, csv or sarif (default: from the file extension)',
+      ),
+  ).action(
+    (
+      outputPath: string,
+      options: ViolationQueryOptions & { format?: string },
+    ) => {
+      try {
+        const config = loadConfig(options.settings) ?? getDefaultConfig()
+        const query = parseViolationQuery(options)
+        const format = options.format ?? getViolationExportFormat(outputPath)
+        if (!VIOLATION_EXPORT_FORMATS.includes(format as never)) {
+          throw new Error(
+            `Unknown violations format '${format}'. Use json, csv or sarif.`,
+          )
+        }
+        const logPath = getViolationLogPath(options, config)
+        const { violations: matches } = queryViolations(
+          readViolationJournal(logPath),
+          query,
+        )
+        const output = exportViolations(matches, format as ViolationExportFormat)
+        if (outputPath === '-') {
+          process.stdout.write(output)
+        } else {
+          fs.writeFileSync(outputPath, output)
+          console.error(
+            `Exported ${matches.length} violations to ${outputPath}`,
+          )
+        }
+      } catch (error) {
+        console.error(
+          `Error: ${error instanceof Error ? error.message : String(error)}`,
+        )
+        process.exit(1)
+      }
+    },
+  )
+
+  violations
+    .command('clear')
+    .description('remove every violation from the violation log')
+    .option(
+      '-s, --settings <path>',
+      'path to user config file (default: ~/.srt-settings.json)',
+    )
+    .option(
+      '--log <path>',
+      'violation log to clear (default: violationLogPath from the config)',
+    )
+    .action((options: { settings?: string; log?: string }) => {
+      try {
+        const config = loadConfig(options.settings) ?? getDefaultConfig()
+        const logPath = getViolationLogPath(options, config)
+        const count = readViolationJournal(logPath).length
+        clearViolationJournal(logPath)
+        console.log(`Cleared ${count} violations from ${logPath}`)
+      } catch (error) {
+        console.error(
+          `Error: ${error instanceof Error ? error.message : String(error)}`,
+        )
+        process.exit(1)
+      }
+    })
 
-          // Handle cleanup on interrupt
-          process.on('SIGINT', () => {
-            child.kill('SIGINT')
+  program
+    .command('learn')
+    .description(
+      'run a command with open allow lists, record what it accesses and print a minimal config covering it',
+    )
+    .argument('[command...]', 'command to learn from')
+    .option(
+      '-s, --settings <path>',
+      'path to user config file (default: ~/.srt-settings.json)',
+    )
+    .option('-c <command>', 'run a command string (like sh -c)')
+    .option('-o, --output <path>', 'write the config to a file, not stdout')
+    .action(
+      async (
+        commandArgs: string[],
+        options: { settings?: string; c?: string; output?: string },
+      ) => {
+        try {
+          const command = options.c ?? commandArgs.join(' ')
+          if (!command) {
+            console.error(
+              'Error: No command specified. Use -c <command> or provide command arguments.',
+            )
+            process.exit(1)
+          }
+          const baseConfig = loadConfig(options.settings) ?? getDefaultConfig()
+          const learner = new PolicyLearner()
+          await SandboxManager.initialize(
+            getLearningConfig(baseConfig),
+            async ({ host, port }) => {
+              learner.recordConnection(host, port)
+              return true
+            },
+            true,
+          )
+
+          // Violations and the audited file accesses are recorded apart
+          const stopLearning = new AbortController()
+          const learningDone = Promise.all(
+            [
+              SandboxManager.getSandboxViolationStore(),
+              SandboxManager.getFileAccessStore(),
+            ].map(async store => {
+              for await (const event of store.stream({
+                signal: stopLearning.signal,
+              })) {
+                learner.add(event)
+              }
+            }),
+          )
+          // The command's output goes to stderr, so stdout is only the config
+          const { code, signal } = await SandboxManager.execute(command, {
+            stdio: ['inherit', process.stderr, 'inherit'],
           })
+          stopLearning.abort()
+          await learningDone
+          await SandboxManager.reset()
 
-          process.on('SIGTERM', () => {
-            child.kill('SIGTERM')
+          const learned = JSON.stringify(
+            learner.buildConfig(baseConfig),
+            null,
+            2,
+          )
+          if (options.output) {
+            fs.writeFileSync(options.output, learned + '\n')
+          } else {
+            console.log(learned)
+          }
+          console.error(formatLearningReport(learner))
+          if (signal || code !== 0) {
+            console.error(
+              `Warning: the command failed (${signal ?? `exit ${code}`}), so the config may not cover everything it needs`,
+            )
+          }
+          process.exit(signal ? 1 : (code ?? 0))
+        } catch (error) {
+          console.error(
+            `Error: ${error instanceof Error ? error.message : String(error)}`,
+          )
+          process.exit(1)
+        }
+      },
+    )
+
+  program
+    .command('profile')
+    .description(
+      'print the sandbox wrapper (bwrap arguments or seatbelt profile) for a command without running it',
+    )
+    .argument('[command...]', 'command to describe')
+    .option(
+      '-s, --settings <path>',
+      'path to user config file (default: ~/.srt-settings.json)',
+    )
+    .option('-c <command>', 'describe a command string (like sh -c)')
+    .option('--dry-run', 'only print the wrapper (always the case)')
+    .option('--json', 'print the wrapper as JSON')
+    .action(
+      async (
+        commandArgs: string[],
+        options: { settings?: string; c?: string; json?: boolean },
+      ) => {
+        try {
+          const command = options.c ?? commandArgs.join(' ')
+          if (!command) {
+            console.error(
+              'Error: No command specified. Use -c <command> or provide command arguments.',
+            )
+            process.exit(1)
+          }
+          const runtimeConfig =
+            loadConfig(options.settings) ?? getDefaultConfig()
+          await SandboxManager.initialize(runtimeConfig)
+          const description = await describeSandboxWrapper(command)
+          await SandboxManager.reset()
+
+          console.log(
+            options.json
+              ? JSON.stringify(description, null, 2)
+              : formatSandboxWrapper(description),
+          )
+          process.exit(0)
+        } catch (error) {
+          console.error(
+            `Error: ${error instanceof Error ? error.message : String(error)}`,
+          )
+          process.exit(1)
+        }
+      },
+    )
+
+  const configCommand = program
+    .command('config')
+    .description('inspect the configuration srt runs with')
+
+  configCommand
+    .command('show')
+    .description(
+      'print the effective config after merging settings files and the preset, with where each value came from',
+    )
+    .option(
+      '-s, --settings <path>',
+      'path to user config file (default: ~/.srt-settings.json)',
+    )
+    .option('--preset <name>', 'apply a preset, as srt --preset would')
+    .option('--json', 'print the config and its sources as JSON')
+    .action(
+      (options: { settings?: string; preset?: string; json?: boolean }) => {
+        try {
+          const loaded = loadConfigWithLayers(options.settings)
+          let runtimeConfig = loaded?.config ?? getDefaultConfig()
+          if (options.preset) {
+            if (!CONFIG_PRESET_NAMES.includes(options.preset as never)) {
+              console.error(
+                `Error: Unknown preset '${options.preset}'. Use ${CONFIG_PRESET_NAMES.join(', ')}.`,
+              )
+              process.exit(1)
+            }
+            runtimeConfig = {
+              ...runtimeConfig,
+              preset: options.preset as ConfigPresetName,
+            }
+          }
+          const { config, sources } = resolveConfigSources(
+            runtimeConfig,
+            loaded?.layers ?? [],
+          )
+          if (options.preset) {
+            sources.set('preset', '--preset')
+          }
+          console.log(
+            options.json
+              ? JSON.stringify(
+                  { config, sources: Object.fromEntries(sources) },
+                  null,
+                  2,
+                )
+              : formatConfigWithSources(config, sources),
+          )
+        } catch (error) {
+          console.error(
+            `Error: ${error instanceof Error ? error.message : String(error)}`,
+          )
+          process.exit(1)
+        }
+      },
+    )
+
+  program
+    .command('test-policy')
+    .description(
+      'check whether the config allows reading or writing a path or connecting to a host, without running anything',
+    )
+    .argument('<action>', 'read, write or connect')
+    .argument('<target>', 'path to read or write, or host:port to connect to')
+    .option(
+      '-s, --settings <path>',
+      'path to user config file (default: ~/.srt-settings.json)',
+    )
+    .option('--json', 'print the decision as JSON')
+    .action(
+      (
+        action: string,
+        target: string,
+        options: { settings?: string; json?: boolean },
+      ) => {
+        try {
+          if (!POLICY_ACTIONS.includes(action as never)) {
+            console.error(
+              `Error: Unknown action '${action}'. Use read, write or connect.`,
+            )
+            process.exit(1)
+          }
+          const runtimeConfig =
+            loadConfig(options.settings) ?? getDefaultConfig()
+          const decision = simulatePolicy(
+            runtimeConfig,
+            action as PolicyAction,
+            target,
+          )
+          console.log(
+            options.json
+              ? JSON.stringify(decision, null, 2)
+              : formatPolicyDecision(decision),
+          )
+          process.exit(decision.allowed ? 0 : 1)
+        } catch (error) {
+          console.error(
+            `Error: ${error instanceof Error ? error.message : String(error)}`,
+          )
+          process.exit(1)
+        }
+      },
+    )
+
+  program
+    .command('allow')
+    .description(
+      `add a domain or writable path to the project ${PROJECT_CONFIG_FILE}, with a note of when and why`,
+    )
+    .argument('<kind>', 'domain or write')
+    .argument(
+      '<entry>',
+      'domain (e.g. registry.npmjs.org) or path (e.g. ./dist)',
+    )
+    .option(
+      '-s, --settings <path>',
+      `user config file to edit when there is no ${PROJECT_CONFIG_FILE} (default: ~/.srt-settings.json)`,
+    )
+    .option(
+      '--reason <text>',
+      'why the entry is needed (default: the violations it would have allowed)',
+    )
+    .option(
+      '--log <path>',
+      'violation log to take the default reason from (default: violationLogPath from the config)',
+    )
+    .action(
+      (
+        kind: string,
+        given: string,
+        options: { settings?: string; reason?: string; log?: string },
+      ) => {
+        try {
+          const list = ALLOW_KINDS[kind]
+          if (!list) {
+            console.error(`Error: Unknown kind '${kind}'. Use domain or write.`)
+            process.exit(1)
+          }
+          const entry = resolveConfigListEntry(list, given)
+          const runtimeConfig =
+            loadConfig(options.settings) ?? getDefaultConfig()
+          const logPath = options.log ?? runtimeConfig.violationLogPath
+          const reason =
+            options.reason ??
+            (logPath &&
+              describeBlockedAccesses(
+                readViolationJournal(logPath),
+                list,
+                entry,
+              )) ??
+            'added with srt allow'
+          const settingsPath = getEditableConfigPath(options.settings)
+          const added = addConfigListEntry(
+            settingsPath,
+            list,
+            entry,
+            formatConfigNote(list, entry, reason),
+          )
+          console.log(
+            added
+              ? `Added ${entry} to ${list} in ${settingsPath}`
+              : `${entry} is already in ${list} in ${settingsPath}`,
+          )
+        } catch (error) {
+          console.error(
+            `Error: ${error instanceof Error ? error.message : String(error)}`,
+          )
+          process.exit(1)
+        }
+      },
+    )
+
+  program
+    .command('doctor')
+    .description(
+      'check the tools and kernel features the sandbox needs and report which backends are usable',
+    )
+    .option('--json', 'print the report as JSON')
+    .action((options: { json?: boolean }) => {
+      const report = runDoctor()
+      console.log(
+        options.json
+          ? JSON.stringify(report, null, 2)
+          : formatDoctorReport(report),
+      )
+      process.exit(hasUsableBackend(report) ? 0 : 1)
+    })
+
+  program
+    .command('self-update')
+    .description(
+      'update srt to the latest signed release on a channel (installed globally via npm)',
+    )
+    .option('--channel <channel>', 'release channel: stable or beta', 'stable')
+    .option('--check', 'only report whether an update is available')
+    .option('--rollback', 'reinstall the version active before the last update')
+    .option(
+      '--allow-downgrade',
+      'install the channel release even if it is older than the installed one',
+    )
+    .action(
+      async (options: {
+        channel: string
+        check?: boolean
+        rollback?: boolean
+        allowDowngrade?: boolean
+      }) => {
+        try {
+          if (options.channel !== 'stable' && options.channel !== 'beta') {
+            console.error(
+              `Error: Unknown channel '${options.channel}'. Use 'stable' or 'beta'.`,
+            )
+            process.exit(1)
+          }
+          const channel: ReleaseChannel = options.channel
+
+          if (options.rollback) {
+            const result = await rollbackSelfUpdate()
+            console.log(
+              `Rolled back from ${result.previousVersion} to ${result.installedVersion}`,
+            )
+            return
+          }
+
+          if (options.check) {
+            const currentVersion = getInstalledVersion()
+            const latestVersion = await getChannelVersion(channel)
+            const comparison = compareVersions(latestVersion, currentVersion)
+            if (comparison === 0) {
+              console.log(`srt ${currentVersion} is up to date (${channel})`)
+            } else if (comparison > 0) {
+              console.log(
+                `Update available on ${channel}: ${currentVersion} -> ${latestVersion}`,
+              )
+            } else {
+              console.log(
+                `Older release on ${channel}: ${currentVersion} -> ${latestVersion} (needs --allow-downgrade)`,
+              )
+            }
+            return
+          }
+
+          const result = await selfUpdate(channel, {
+            allowDowngrade: options.allowDowngrade,
           })
+          if (result.updated) {
+            console.log(
+              `Updated srt from ${result.previousVersion} to ${result.installedVersion} (${channel})`,
+            )
+          } else {
+            console.log(
+              `srt ${result.installedVersion} is already the latest ${channel} release`,
+            )
+          }
         } catch (error) {
           console.error(
             `Error: ${error instanceof Error ? error.message : String(error)}`,
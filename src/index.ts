@@ -1,6 +1,219 @@
 // Library exports
 export { SandboxManager } from './sandbox/sandbox-manager.js'
-export { SandboxViolationStore } from './sandbox/sandbox-violation-store.js'
+export {
+  SandboxConfigValidationError,
+  SandboxManagerBuilder,
+} from './sandbox/sandbox-manager-builder.js'
+export type {
+  BuiltinSandboxBackendName,
+  SandboxBackend,
+  SandboxBackendName,
+  SandboxWrapContext,
+} from './sandbox/sandbox-backend.js'
+export {
+  SandboxOutputLimitError,
+  SandboxTimeoutError,
+  SandboxViolationKillError,
+  SandboxWrapperError,
+  type SandboxCommandOutput,
+  type SandboxExecuteOptions,
+  type SandboxExecuteResult,
+  type SandboxExecutionHandle,
+  type SandboxOutputOptions,
+  type SandboxStdinInput,
+} from './sandbox/sandbox-execute.js'
+export {
+  OutputLineStream,
+  type SandboxStreamingExecution,
+} from './sandbox/execution-stream.js'
+export { NamedSandbox } from './sandbox/named-sandbox.js'
+export { GlobExpansionLimitError } from './sandbox/glob-expansion.js'
+export {
+  SandboxCommandVetoedError,
+  type SandboxAfterExecuteContext,
+  type SandboxBeforeExecuteContext,
+  type SandboxViolationWarningContext,
+  type SandboxExecutionHooks,
+} from './sandbox/execution-hooks.js'
+export {
+  SandboxSession,
+  SandboxSessionClosedError,
+  type SandboxSessionOptions,
+  type SandboxSessionRunOptions,
+} from './sandbox/sandbox-session.js'
+export {
+  SandboxViolationStore,
+  queryViolations,
+  matchesViolationQuery,
+  type ViolationStreamOptions,
+  type ViolationQuery,
+  type ViolationQueryResult,
+} from './sandbox/sandbox-violation-store.js'
+export {
+  ViolationJournal,
+  readViolationJournal,
+  clearViolationJournal,
+  type JournalRecoveryResult,
+} from './sandbox/violation-journal.js'
+export { followViolationFeed } from './sandbox/violation-feed.js'
+export {
+  applyConfigPreset,
+  CONFIG_PRESETS,
+  CONFIG_PRESET_NAMES,
+  type ConfigPreset,
+  type ConfigPresetName,
+} from './sandbox/config-presets.js'
+export {
+  buildStarterConfig,
+  detectProjects,
+  formatStarterConfigProposal,
+  type DetectedProject,
+} from './sandbox/config-init.js'
+export {
+  ConfigExtendsError,
+  findProjectConfigPath,
+  getConfigLayerPaths,
+  getSystemConfigPaths,
+  loadConfigWithExtends,
+  mergeConfigLayers,
+  readConfigLayers,
+  type ConfigLayer,
+} from './sandbox/config-layers.js'
+export {
+  DEFAULT_CONFIG_SOURCE,
+  formatConfigWithSources,
+  resolveConfigSources,
+  type ConfigSources,
+} from './sandbox/config-sources.js'
+export {
+  interpolateConfigLayer,
+  ConfigInterpolationError,
+  type ConfigInterpolationContext,
+} from './sandbox/config-interpolation.js'
+export {
+  findCommandPolicy,
+  type CommandPolicyMatch,
+} from './sandbox/command-policy.js'
+export {
+  resolveSandboxEnv,
+  CREDENTIAL_ENV_VAR_PATTERN,
+  parseEnvAssignment,
+  parseEnvFile,
+  getSpawnEnv,
+} from './sandbox/sandbox-env.js'
+export {
+  createRedactor,
+  redactViolation,
+  REDACTED,
+  type Redactor,
+} from './sandbox/redaction.js'
+export {
+  applyAllowListExclusions,
+  splitAllowList,
+} from './sandbox/allow-list-exclusions.js'
+export {
+  applyTemporaryRules,
+  TemporaryRuleTracker,
+} from './sandbox/temporary-rules.js'
+export {
+  exportViolations,
+  type ViolationExportFormat,
+} from './sandbox/violation-export.js'
+
+export {
+  BrokerClient,
+  createBrokerServer,
+  forwardBrokerViolations,
+  startSandboxBroker,
+  spawnSandboxBroker,
+} from './sandbox/sandbox-broker.js'
+export type {
+  BrokerHandlers,
+  BrokerProxyPorts,
+  BrokerServer,
+} from './sandbox/sandbox-broker.js'
+
+export {
+  formatLearningReport,
+  getLearningConfig,
+  PolicyLearner,
+} from './sandbox/policy-learning.js'
+export {
+  formatNetworkPrompt,
+  NetworkPrompter,
+  parseNetworkPromptAnswer,
+  type NetworkPromptAnswer,
+} from './sandbox/network-prompt.js'
+export {
+  addConfigListEntry,
+  ALLOW_KINDS,
+  describeBlockedAccesses,
+  formatConfigNote,
+  resolveConfigListEntry,
+  type EditableConfigList,
+} from './sandbox/config-edit.js'
+export {
+  formatPolicyDecision,
+  parseConnectTarget,
+  POLICY_ACTIONS,
+  simulatePolicy,
+  type PolicyAction,
+  type PolicyDecision,
+} from './sandbox/policy-simulation.js'
+
+export {
+  formatRunEvent,
+  RUN_OUTPUT_FORMATS,
+  SANDBOX_EXIT_CODES,
+  type RunEvent,
+  type RunOutputFormat,
+  type RunResult,
+} from './sandbox/run-output.js'
+
+export {
+  DaemonClient,
+  startSandboxDaemon,
+} from './sandbox/sandbox-daemon.js'
+export type {
+  DaemonExecuteOptions,
+  DaemonExecuteParams,
+  DaemonExecuteResult,
+  DaemonMethod,
+  DaemonOutputStream,
+  DaemonServer,
+} from './sandbox/sandbox-daemon.js'
+
+export {
+  runSelftest,
+  buildSelftestProbes,
+  formatSelftestTable,
+} from './sandbox/selftest.js'
+export type {
+  SelftestProbe,
+  SelftestResult,
+  ProbeExpectation,
+} from './sandbox/selftest.js'
+
+export {
+  describeSandboxWrapper,
+  formatSandboxWrapper,
+  splitWrappedCommand,
+  type SandboxWrapperDescription,
+} from './sandbox/sandbox-profile.js'
+export {
+  runDoctor,
+  runDoctorChecks,
+  getBackendStatuses,
+  hasUsableBackend,
+  formatDoctorReport,
+} from './sandbox/doctor.js'
+export type {
+  DoctorReport,
+  DoctorCheck,
+  DoctorCheckName,
+  DoctorBackend,
+  DoctorBackendName,
+} from './sandbox/doctor.js'
 
 // Configuration types and schemas
 export type {
@@ -8,6 +221,24 @@ export type {
   NetworkConfig,
   FilesystemConfig,
   IgnoreViolationsConfig,
+  CommandPolicyConfig,
+  TemporaryRuleConfig,
+  ResourceLimitsConfig,
+  RlimitsConfig,
+  LinuxFilesystemBackend,
+  LinuxSandboxBackend,
+  LinuxCapability,
+  UserMappingConfig,
+  SeccompConfig,
+  ScratchConfig,
+  EnvConfig,
+  ViolationRetentionConfig,
+  ViolationPolicyConfig,
+  NotificationsConfig,
+  RedactionConfig,
+  ViolationStoreConfig,
+  DesktopConfig,
+  SeatbeltConfig,
 } from './sandbox/sandbox-config.js'
 
 export {
@@ -15,7 +246,22 @@ export {
   NetworkConfigSchema,
   FilesystemConfigSchema,
   IgnoreViolationsConfigSchema,
+  CommandPolicyConfigSchema,
+  TemporaryRuleConfigSchema,
   RipgrepConfigSchema,
+  ResourceLimitsConfigSchema,
+  RlimitsConfigSchema,
+  UserMappingConfigSchema,
+  SeccompConfigSchema,
+  ScratchConfigSchema,
+  EnvConfigSchema,
+  ViolationRetentionConfigSchema,
+  ViolationPolicyConfigSchema,
+  NotificationsConfigSchema,
+  RedactionConfigSchema,
+  ViolationStoreConfigSchema,
+  DesktopConfigSchema,
+  SeatbeltConfigSchema,
 } from './sandbox/sandbox-config.js'
 
 // Schema types and utilities
@@ -28,7 +274,11 @@ export type {
 } from './sandbox/sandbox-schemas.js'
 
 // Platform-specific utilities
-export type { SandboxViolationEvent } from './sandbox/macos-sandbox-utils.js'
+export type {
+  SandboxViolationEvent,
+  NetworkViolationSubtype,
+} from './sandbox/macos-sandbox-utils.js'
+export { DEFAULT_MACH_SERVICES } from './sandbox/macos-sandbox-utils.js'
 
 // Utility functions
 export { getDefaultWritePaths } from './sandbox/sandbox-utils.js'
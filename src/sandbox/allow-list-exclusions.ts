@@ -0,0 +1,72 @@
+import type { SandboxRuntimeConfig } from './sandbox-config.js'
+
+/**
+ * Exclusions inside allow lists
+ *
+ * An allow list entry starting with `!` carves an exception out of the broad
+ * grants next to it: `allowWrite: [".", "!./.git"]` allows writing to the
+ * working directory except .git, and `allowedDomains: ["*.example.com",
+ * "!admin.example.com"]` allows every subdomain but one. Exclusions are moved
+ * to the matching deny list (denyWrite, deniedDomains), which already takes
+ * precedence over the allow list, so everything that reads the lists sees
+ * plain entries.
+ */
+
+const EXCLUSION_PREFIX = '!'
+
+/**
+ * Split an allow list into its grants and its `!` exclusions (without the `!`)
+ */
+export function splitAllowList(entries: string[]): {
+  allow: string[]
+  exclude: string[]
+} {
+  const allow: string[] = []
+  const exclude: string[] = []
+  for (const entry of entries) {
+    if (entry.startsWith(EXCLUSION_PREFIX)) {
+      exclude.push(entry.slice(EXCLUSION_PREFIX.length))
+    } else {
+      allow.push(entry)
+    }
+  }
+  return { allow, exclude }
+}
+
+function hasExclusions(entries: string[]): boolean {
+  return entries.some(entry => entry.startsWith(EXCLUSION_PREFIX))
+}
+
+/**
+ * Move the `!` exclusions of allowedDomains and allowWrite to deniedDomains
+ * and denyWrite
+ */
+export function applyAllowListExclusions<
+  T extends Pick<Partial<SandboxRuntimeConfig>, 'network' | 'filesystem'>,
+>(config: T): T {
+  const { network, filesystem } = config
+  let result = config
+  if (network && hasExclusions(network.allowedDomains)) {
+    const { allow, exclude } = splitAllowList(network.allowedDomains)
+    result = {
+      ...result,
+      network: {
+        ...network,
+        allowedDomains: allow,
+        deniedDomains: [...new Set([...network.deniedDomains, ...exclude])],
+      },
+    }
+  }
+  if (filesystem && hasExclusions(filesystem.allowWrite)) {
+    const { allow, exclude } = splitAllowList(filesystem.allowWrite)
+    result = {
+      ...result,
+      filesystem: {
+        ...filesystem,
+        allowWrite: allow,
+        denyWrite: [...new Set([...filesystem.denyWrite, ...exclude])],
+      },
+    }
+  }
+  return result
+}
@@ -0,0 +1,195 @@
+import shellquote from 'shell-quote'
+import type {
+  CommandPolicyConfig,
+  SandboxRuntimeConfig,
+} from './sandbox-config.js'
+import { containsGlobChars, globToRegex } from './sandbox-utils.js'
+import { applyAllowListExclusions } from './allow-list-exclusions.js'
+
+/**
+ * Per-command policy overrides
+ *
+ * The `commands` config maps binary names or globs (e.g. "git*", "npm") to
+ * changes of the base policy. A wrapped command string gets an override only
+ * when every command in it (split on ;, &&, ||, | and &) runs a matching
+ * binary, so `git pull && curl ...` does not get git's extra domains. Strings
+ * with command substitution never match. The first matching key, in config
+ * order, wins.
+ */
+
+export interface CommandPolicyMatch {
+  pattern: string
+  policy: CommandPolicyConfig
+}
+
+/** Operators that start a new command in a shell string */
+const COMMAND_SEPARATORS = new Set([';', '&&', '||', '|', '|&', '&'])
+
+/** Redirections, followed by a file name rather than a command */
+const REDIRECTIONS = new Set(['>', '>>', '<', '>&', '<&', '<>', '>|'])
+
+const ENV_ASSIGNMENT = /^[A-Za-z_][A-Za-z0-9_]*=/
+
+/**
+ * Binary names of the commands in a shell string, or undefined when it
+ * cannot be parsed reliably (substitutions, subshells, newlines, which
+ * shell-quote reads as spaces) or runs a binary by path: `./git` is not git
+ */
+export function getCommandBinaries(command: string): string[] | undefined {
+  if (
+    command.includes('$(') ||
+    command.includes('`') ||
+    /[\r\n]/.test(command)
+  ) {
+    return undefined
+  }
+
+  const binaries: string[] = []
+  let atCommandStart = true
+  let afterRedirection = false
+  for (const entry of shellquote.parse(command, name => `$${name}`)) {
+    if (typeof entry === 'string') {
+      if (afterRedirection) {
+        afterRedirection = false
+      } else if (atCommandStart && !ENV_ASSIGNMENT.test(entry)) {
+        if (entry.includes('/')) {
+          return undefined
+        }
+        binaries.push(entry)
+        atCommandStart = false
+      }
+      continue
+    }
+    if ('comment' in entry) {
+      break
+    }
+    if (COMMAND_SEPARATORS.has(entry.op)) {
+      atCommandStart = true
+    } else if (REDIRECTIONS.has(entry.op)) {
+      afterRedirection = true
+    } else {
+      // Subshells, process substitution and glob words as the binary
+      return undefined
+    }
+  }
+  return binaries
+}
+
+/**
+ * Whether a binary name matches a `commands` key (exact name or glob)
+ */
+export function matchesCommandPattern(
+  pattern: string,
+  binary: string,
+): boolean {
+  if (!containsGlobChars(pattern)) {
+    return binary === pattern
+  }
+  return new RegExp(globToRegex(pattern)).test(binary)
+}
+
+/**
+ * The `commands` entry that applies to a wrapped command string, if any
+ */
+export function findCommandPolicy(
+  commands: Record<string, CommandPolicyConfig> | undefined,
+  command: string,
+): CommandPolicyMatch | undefined {
+  if (!commands) {
+    return undefined
+  }
+  const binaries = getCommandBinaries(command)
+  if (!binaries || binaries.length === 0) {
+    return undefined
+  }
+  for (const [pattern, policy] of Object.entries(commands)) {
+    if (binaries.every(binary => matchesCommandPattern(pattern, binary))) {
+      return { pattern, policy }
+    }
+  }
+  return undefined
+}
+
+/**
+ * Whether an override changes the domain lists, which needs proxies of its
+ * own since the shared proxies apply the base lists
+ */
+export function commandPolicyChangesNetwork(
+  policy: CommandPolicyConfig,
+): boolean {
+  return (
+    policy.allowedDomains !== undefined || policy.deniedDomains !== undefined
+  )
+}
+
+function mergeLists(base: string[], extra: string[] | undefined): string[] {
+  return extra ? [...new Set([...base, ...extra])] : base
+}
+
+/**
+ * Put overrides on top of a base set of overrides, e.g. a caller's
+ * customConfig on a command policy. Settings of the overrides replace those
+ * of the base, except the deny lists (deniedDomains, denyRead, denyWrite),
+ * which are merged so overrides can't drop what the base denies.
+ */
+export function mergeConfigOverrides(
+  base: Partial<SandboxRuntimeConfig>,
+  overrides: Partial<SandboxRuntimeConfig> | undefined,
+): Partial<SandboxRuntimeConfig> {
+  if (!overrides) {
+    return base
+  }
+  const merged = { ...base, ...overrides }
+  if (base.network && overrides.network) {
+    merged.network = {
+      ...overrides.network,
+      deniedDomains: mergeLists(
+        overrides.network.deniedDomains ?? [],
+        base.network.deniedDomains,
+      ),
+    }
+  }
+  if (base.filesystem && overrides.filesystem) {
+    merged.filesystem = {
+      ...overrides.filesystem,
+      denyRead: mergeLists(
+        overrides.filesystem.denyRead ?? [],
+        base.filesystem.denyRead,
+      ),
+      denyWrite: mergeLists(
+        overrides.filesystem.denyWrite ?? [],
+        base.filesystem.denyWrite,
+      ),
+    }
+  }
+  return merged
+}
+
+/**
+ * The network and filesystem config of a command with an override applied
+ * Domain and deny lists are extended; allowWrite replaces the base list.
+ */
+export function applyCommandPolicy(
+  config: SandboxRuntimeConfig,
+  policy: CommandPolicyConfig,
+): Pick<SandboxRuntimeConfig, 'network' | 'filesystem'> {
+  return applyAllowListExclusions({
+    network: {
+      ...config.network,
+      allowedDomains: mergeLists(
+        config.network.allowedDomains,
+        policy.allowedDomains,
+      ),
+      deniedDomains: mergeLists(
+        config.network.deniedDomains,
+        policy.deniedDomains,
+      ),
+    },
+    filesystem: {
+      ...config.filesystem,
+      allowWrite: policy.allowWrite ?? config.filesystem.allowWrite,
+      denyRead: mergeLists(config.filesystem.denyRead, policy.denyRead),
+      denyWrite: mergeLists(config.filesystem.denyWrite, policy.denyWrite),
+    },
+  })
+}
@@ -0,0 +1,170 @@
+import * as fs from 'node:fs'
+import * as path from 'node:path'
+import type { SandboxViolationEvent } from './macos-sandbox-utils.js'
+import { matchesPathPattern } from './policy-simulation.js'
+import {
+  FilesystemConfigSchema,
+  NetworkConfigSchema,
+} from './sandbox-config.js'
+import { matchesDomainPattern } from './sandbox-manager.js'
+import { classifyViolation } from './violation-policy.js'
+
+/**
+ * Editing settings files in place (`srt allow`, `srt --ask`)
+ *
+ * Used by the CLI to save decisions to a settings file. The file is parsed,
+ * the entry appended to the list and the file rewritten as JSON with two
+ * space indentation, through a temporary file renamed over it so a crash or
+ * a concurrent reader never sees it half written. Missing files, sections
+ * and lists are created. JSON has
+ * no comments, so when and why an entry was added goes to the `notes` list.
+ */
+
+/** Lists of a settings file that entries can be added to */
+export type EditableConfigList =
+  | 'network.allowedDomains'
+  | 'filesystem.allowWrite'
+
+/** What `srt allow <kind>` adds to */
+export const ALLOW_KINDS: Record<string, EditableConfigList> = {
+  domain: 'network.allowedDomains',
+  write: 'filesystem.allowWrite',
+}
+
+const ENTRY_SCHEMAS = {
+  'network.allowedDomains': NetworkConfigSchema.shape.allowedDomains.element,
+  'filesystem.allowWrite': FilesystemConfigSchema.shape.allowWrite.element,
+}
+
+function isPlainObject(value: unknown): value is Record<string, unknown> {
+  return typeof value === 'object' && value !== null && !Array.isArray(value)
+}
+
+function readSettingsFile(file: string): Record<string, unknown> {
+  if (!fs.existsSync(file)) {
+    return {}
+  }
+  const content = fs.readFileSync(file, 'utf8')
+  if (content.trim() === '') {
+    return {}
+  }
+  let parsed: unknown
+  try {
+    parsed = JSON.parse(content)
+  } catch (error) {
+    throw new Error(
+      `Cannot edit ${file}: ${error instanceof Error ? error.message : String(error)}`,
+    )
+  }
+  if (!isPlainObject(parsed)) {
+    throw new Error(`Cannot edit ${file}: not a JSON object`)
+  }
+  return parsed
+}
+
+/**
+ * Add an entry to a list of a settings file, and a note to its `notes`
+ * @returns false when the list already had the entry, and the file was left
+ * as is
+ */
+export function addConfigListEntry(
+  file: string,
+  list: EditableConfigList,
+  entry: string,
+  note?: string,
+): boolean {
+  const result = ENTRY_SCHEMAS[list].safeParse(entry)
+  if (!result.success) {
+    throw new Error(
+      `Invalid ${list} entry '${entry}': ${result.error.issues[0]?.message}`,
+    )
+  }
+
+  const settings = readSettingsFile(file)
+  const [sectionKey, listKey] = list.split('.')
+  const section = settings[sectionKey] ?? {}
+  if (!isPlainObject(section)) {
+    throw new Error(`Cannot edit ${file}: ${sectionKey} is not an object`)
+  }
+  const entries = section[listKey] ?? []
+  if (!Array.isArray(entries)) {
+    throw new Error(`Cannot edit ${file}: ${list} is not a list`)
+  }
+  const notes = settings.notes ?? []
+  if (!Array.isArray(notes)) {
+    throw new Error(`Cannot edit ${file}: notes is not a list`)
+  }
+  if (entries.includes(entry)) {
+    return false
+  }
+
+  settings[sectionKey] = { ...section, [listKey]: [...entries, entry] }
+  if (note !== undefined) {
+    settings.notes = [...notes, note]
+  }
+  const tmpPath = `${file}.${process.pid}.tmp`
+  try {
+    fs.writeFileSync(tmpPath, JSON.stringify(settings, null, 2) + '\n')
+    fs.renameSync(tmpPath, file)
+  } catch (error) {
+    fs.rmSync(tmpPath, { force: true })
+    throw error
+  }
+  return true
+}
+
+/**
+ * The entry to save to a list: relative paths are resolved against the
+ * working directory, since in a settings file they would be resolved
+ * against wherever srt runs later. `~` paths and domains are kept as given.
+ */
+export function resolveConfigListEntry(
+  list: EditableConfigList,
+  entry: string,
+  cwd: string = process.cwd(),
+): string {
+  if (list !== 'filesystem.allowWrite' || entry.startsWith('~')) {
+    return entry
+  }
+  return path.resolve(cwd, entry)
+}
+
+/**
+ * A note recording when an entry was added and why, e.g.
+ * `2026-10-17: added example.com to network.allowedDomains (blocked once, ...)`
+ */
+export function formatConfigNote(
+  list: EditableConfigList,
+  entry: string,
+  reason: string,
+  date: Date = new Date(),
+): string {
+  return `${date.toISOString().slice(0, 10)}: added ${entry} to ${list} (${reason})`
+}
+
+/**
+ * Describe the recorded violations an entry would have allowed, e.g.
+ * `blocked 3 times, last at 2026-10-17T09:12:00.000Z`, or undefined when
+ * there are none
+ */
+export function describeBlockedAccesses(
+  violations: SandboxViolationEvent[],
+  list: EditableConfigList,
+  entry: string,
+): string | undefined {
+  const blocked = violations.filter(violation =>
+    list === 'network.allowedDomains'
+      ? classifyViolation(violation) === 'network' &&
+        violation.host !== undefined &&
+        matchesDomainPattern(violation.host, entry)
+      : classifyViolation(violation) === 'file-write' &&
+        violation.path !== undefined &&
+        matchesPathPattern(violation.path, entry),
+  )
+  const last = blocked[blocked.length - 1]
+  if (!last) {
+    return undefined
+  }
+  const times = blocked.length === 1 ? 'once' : `${blocked.length} times`
+  return `blocked ${times}, last at ${last.timestamp.toISOString()}`
+}
@@ -0,0 +1,81 @@
+import * as fs from 'node:fs'
+import * as path from 'node:path'
+import { CONFIG_PRESETS, type ConfigPresetName } from './config-presets.js'
+import type { SandboxRuntimeConfig } from './sandbox-config.js'
+
+/**
+ * Starter policies for `srt init`
+ *
+ * The project directory is inspected for the manifests of known ecosystems
+ * and the matching presets are merged into one explicit config, so the
+ * written `.srt.json` lists every registry and write path and can be edited
+ * directly. Projects without a known manifest get the git-only preset.
+ */
+
+/** Manifest files and the preset each one implies */
+const PROJECT_MARKERS: Array<{ file: string; preset: ConfigPresetName }> = [
+  { file: 'package.json', preset: 'node-dev' },
+  { file: 'Cargo.toml', preset: 'rust-build' },
+  { file: 'requirements.txt', preset: 'python-dev' },
+  { file: 'pyproject.toml', preset: 'python-dev' },
+]
+
+export interface DetectedProject {
+  /** Manifest file found in the project directory */
+  file: string
+  preset: ConfigPresetName
+}
+
+/**
+ * The known manifests present in a project directory
+ */
+export function detectProjects(dir: string): DetectedProject[] {
+  return PROJECT_MARKERS.filter(marker =>
+    fs.existsSync(path.join(dir, marker.file)),
+  )
+}
+
+function mergeLists(lists: string[][]): string[] {
+  return [...new Set(lists.flat())]
+}
+
+/**
+ * A config allowing what the detected projects need to install and build
+ */
+export function buildStarterConfig(
+  projects: DetectedProject[],
+): SandboxRuntimeConfig {
+  const presetNames = projects.length
+    ? [...new Set(projects.map(project => project.preset))]
+    : (['git-only'] as ConfigPresetName[])
+  const presets = presetNames.map(name => CONFIG_PRESETS[name])
+  return {
+    network: {
+      allowedDomains: mergeLists(presets.map(p => p.allowedDomains)),
+      deniedDomains: [],
+    },
+    filesystem: {
+      denyRead: mergeLists(presets.map(p => p.denyRead)),
+      allowWrite: mergeLists(presets.map(p => p.allowWrite)),
+      denyWrite: mergeLists(presets.map(p => p.denyWrite)),
+    },
+  }
+}
+
+/**
+ * Describe a proposed starter config for confirmation
+ */
+export function formatStarterConfigProposal(
+  projects: DetectedProject[],
+  config: SandboxRuntimeConfig,
+): string {
+  const detected = projects.length
+    ? `Detected ${projects.map(p => `${p.file} (${p.preset})`).join(', ')}`
+    : 'No package manifest found, proposing the git-only policy'
+  return [
+    detected,
+    '',
+    'Proposed policy:',
+    JSON.stringify(config, null, 2),
+  ].join('\n')
+}
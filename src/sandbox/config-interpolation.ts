@@ -0,0 +1,109 @@
+/**
+ * Placeholders in settings files
+ *
+ * Strings in the `network`, `filesystem` and `commands` sections may contain
+ * `${HOME}`, `${PROJECT_DIR}` and `${env:VAR}`, replaced when the file is
+ * loaded, so one settings file works across machines. Undefined variables and unknown
+ * placeholders are errors rather than empty strings, which could silently
+ * turn a path like `${env:CACHE}/npm` into `/npm`.
+ */
+
+export interface ConfigInterpolationContext {
+  /** Value of ${HOME} */
+  home: string
+  /** Value of ${PROJECT_DIR} */
+  projectDir: string
+  /** Variables available as ${env:VAR} */
+  env: NodeJS.ProcessEnv
+}
+
+export class ConfigInterpolationError extends Error {
+  constructor(
+    readonly location: string,
+    message: string,
+  ) {
+    super(`${location}: ${message}`)
+    this.name = 'ConfigInterpolationError'
+  }
+}
+
+/** Config sections whose strings are paths, domains or mounts */
+const INTERPOLATED_SECTIONS = ['network', 'filesystem', 'commands']
+
+const PLACEHOLDER_PATTERN = /\$\{([^}]*)\}/g
+
+/**
+ * Replace the placeholders in one config string
+ * location names the value in errors, e.g. filesystem.allowWrite[0].
+ */
+export function interpolateConfigString(
+  value: string,
+  context: ConfigInterpolationContext,
+  location: string,
+): string {
+  return value.replace(PLACEHOLDER_PATTERN, (placeholder, name: string) => {
+    if (name === 'HOME') {
+      return context.home
+    }
+    if (name === 'PROJECT_DIR') {
+      return context.projectDir
+    }
+    if (name.startsWith('env:')) {
+      const variable = name.slice('env:'.length)
+      const resolved = context.env[variable]
+      if (resolved === undefined) {
+        throw new ConfigInterpolationError(
+          location,
+          `environment variable ${variable} in ${placeholder} is not defined`,
+        )
+      }
+      return resolved
+    }
+    throw new ConfigInterpolationError(
+      location,
+      `unknown placeholder ${placeholder} (use \${HOME}, \${PROJECT_DIR} or \${env:VAR})`,
+    )
+  })
+}
+
+function interpolateValue(
+  value: unknown,
+  context: ConfigInterpolationContext,
+  location: string,
+): unknown {
+  if (typeof value === 'string') {
+    return interpolateConfigString(value, context, location)
+  }
+  if (Array.isArray(value)) {
+    return value.map((item, index) =>
+      interpolateValue(item, context, `${location}[${index}]`),
+    )
+  }
+  if (typeof value === 'object' && value !== null) {
+    return Object.fromEntries(
+      Object.entries(value).map(([key, item]) => [
+        key,
+        interpolateValue(item, context, `${location}.${key}`),
+      ]),
+    )
+  }
+  return value
+}
+
+/**
+ * Replace the placeholders in the network, filesystem and commands sections
+ * of a parsed settings file. Throws ConfigInterpolationError on undefined
+ * variables and unknown placeholders.
+ */
+export function interpolateConfigLayer(
+  layer: Record<string, unknown>,
+  context: ConfigInterpolationContext,
+): Record<string, unknown> {
+  const result = { ...layer }
+  for (const section of INTERPOLATED_SECTIONS) {
+    if (section in result) {
+      result[section] = interpolateValue(result[section], context, section)
+    }
+  }
+  return result
+}
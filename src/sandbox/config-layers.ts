@@ -0,0 +1,274 @@
+import * as fs from 'node:fs'
+import * as os from 'node:os'
+import * as path from 'node:path'
+
+/**
+ * Hierarchical settings files
+ *
+ * Settings are read from up to three layers, lowest precedence first: the
+ * system files, the user file and the closest project file. Objects are merged
+ * key by key, lists are concatenated (duplicates removed) and for any other
+ * value the later layer wins. Teams can commit a per-repo `.srt.json` while
+ * users keep personal defaults in `~/.srt-settings.json`.
+ *
+ * Any settings file may also list shared building blocks in `extends`: paths
+ * (relative to the file) or names of policies in the policy directories.
+ * Extended files are merged depth-first in listed order, then the file itself
+ * on top, so the result does not depend on anything but the files.
+ */
+
+/** Directory of the machine-wide settings, typically managed by an administrator */
+export const SYSTEM_CONFIG_DIR = '/etc/srt'
+
+/** System settings files: config.json, config.<name> and config.<name>.json */
+const SYSTEM_CONFIG_FILE = /^config\.[\w-]+(\.json)?$/
+
+/** Settings file name looked up from the working directory upwards */
+export const PROJECT_CONFIG_FILE = '.srt.json'
+
+/** Directories searched for named policies in `extends`, in order */
+export function getPolicyDirectories(): string[] {
+  return [path.join(os.homedir(), '.srt', 'policies'), '/etc/srt/policies']
+}
+
+export class ConfigExtendsError extends Error {
+  constructor(message: string) {
+    super(message)
+    this.name = 'ConfigExtendsError'
+  }
+}
+
+export function getUserConfigPath(): string {
+  return path.join(os.homedir(), '.srt-settings.json')
+}
+
+/**
+ * The system settings files in directory: config.json, then the others in
+ * name order, so drop-ins such as config.50-proxy.json are merged over it.
+ * All are parsed as JSON. Backups like config.json.bak are skipped.
+ */
+export function getSystemConfigPaths(
+  directory: string = SYSTEM_CONFIG_DIR,
+): string[] {
+  let entries: string[]
+  try {
+    entries = fs.readdirSync(directory)
+  } catch {
+    return []
+  }
+  return entries
+    .filter(entry => SYSTEM_CONFIG_FILE.test(entry))
+    .sort((a, b) =>
+      a === 'config.json' ? -1 : b === 'config.json' ? 1 : a < b ? -1 : 1,
+    )
+    .map(entry => path.join(directory, entry))
+}
+
+/**
+ * Where the search for a project settings file stops: the closest directory
+ * with a .git entry, otherwise the home directory when cwd is inside it,
+ * otherwise cwd itself. A .srt.json in a shared parent such as /tmp is never
+ * picked up.
+ */
+function getProjectSearchRoot(cwd: string): string {
+  const home = os.homedir()
+  for (let dir = cwd; ; dir = path.dirname(dir)) {
+    if (fs.existsSync(path.join(dir, '.git')) || dir === home) {
+      return dir
+    }
+    if (path.dirname(dir) === dir) {
+      return cwd
+    }
+  }
+}
+
+/**
+ * The closest project settings file in cwd or one of its parents, up to the
+ * repository root (see getProjectSearchRoot)
+ */
+export function findProjectConfigPath(cwd: string): string | undefined {
+  const start = path.resolve(cwd)
+  const root = getProjectSearchRoot(start)
+  for (let dir = start; ; dir = path.dirname(dir)) {
+    const candidate = path.join(dir, PROJECT_CONFIG_FILE)
+    if (fs.existsSync(candidate)) {
+      return candidate
+    }
+    if (dir === root) {
+      return undefined
+    }
+  }
+}
+
+/**
+ * Paths of the settings layers that exist, lowest precedence first
+ * An explicit settings path replaces the user file.
+ */
+export function getConfigLayerPaths(
+  options: {
+    settingsPath?: string
+    cwd?: string
+    systemConfigDir?: string
+  } = {},
+): string[] {
+  const candidates = [
+    ...getSystemConfigPaths(options.systemConfigDir),
+    options.settingsPath ?? getUserConfigPath(),
+    findProjectConfigPath(options.cwd ?? process.cwd()),
+  ]
+  // The project file may be the user file when running from the home directory
+  return [
+    ...new Set(
+      candidates.filter(
+        (candidate): candidate is string =>
+          candidate !== undefined && fs.existsSync(candidate),
+      ),
+    ),
+  ]
+}
+
+function isPlainObject(value: unknown): value is Record<string, unknown> {
+  return typeof value === 'object' && value !== null && !Array.isArray(value)
+}
+
+function mergeValues(base: unknown, override: unknown): unknown {
+  if (Array.isArray(base) && Array.isArray(override)) {
+    return [...new Set([...base, ...override])]
+  }
+  if (isPlainObject(base) && isPlainObject(override)) {
+    const merged: Record<string, unknown> = { ...base }
+    for (const [key, value] of Object.entries(override)) {
+      merged[key] = key in merged ? mergeValues(merged[key], value) : value
+    }
+    return merged
+  }
+  return override
+}
+
+/**
+ * Merge parsed settings layers, lowest precedence first
+ * The result is not validated: each layer may only set part of the config.
+ */
+export function mergeConfigLayers(
+  layers: Record<string, unknown>[],
+): Record<string, unknown> {
+  return layers.reduce<Record<string, unknown>>(
+    (merged, layer) => mergeValues(merged, layer) as Record<string, unknown>,
+    {},
+  )
+}
+
+const POLICY_NAME = /^[A-Za-z0-9][A-Za-z0-9._-]*$/
+
+/**
+ * The file an `extends` entry refers to
+ * Entries starting with ./, ../, / or ~/ are paths, relative to the file
+ * that contains them; anything else names a policy file `<name>.json` in
+ * one of the policy directories.
+ */
+export function resolveExtendsReference(
+  reference: string,
+  fromFile: string,
+): string {
+  if (/^(\.{1,2}\/|\/|~\/)/.test(reference)) {
+    const resolved = reference.startsWith('~/')
+      ? path.join(os.homedir(), reference.slice(2))
+      : path.resolve(path.dirname(fromFile), reference)
+    if (!fs.existsSync(resolved)) {
+      throw new ConfigExtendsError(
+        `extends "${reference}" in ${fromFile}: ${resolved} does not exist`,
+      )
+    }
+    return resolved
+  }
+
+  if (!POLICY_NAME.test(reference)) {
+    throw new ConfigExtendsError(
+      `extends "${reference}" in ${fromFile}: use a path starting with ./, ../, / or ~/, or a policy name`,
+    )
+  }
+  const directories = getPolicyDirectories()
+  const found = directories
+    .map(directory => path.join(directory, `${reference}.json`))
+    .find(candidate => fs.existsSync(candidate))
+  if (!found) {
+    throw new ConfigExtendsError(
+      `extends "${reference}" in ${fromFile}: no ${reference}.json in ${directories.join(' or ')}`,
+    )
+  }
+  return found
+}
+
+function getExtendsReferences(value: unknown, fromFile: string): string[] {
+  if (value === undefined) {
+    return []
+  }
+  const references = typeof value === 'string' ? [value] : value
+  if (
+    !Array.isArray(references) ||
+    !references.every(reference => typeof reference === 'string')
+  ) {
+    throw new ConfigExtendsError(
+      `extends in ${fromFile} must be a string or a list of strings`,
+    )
+  }
+  return references
+}
+
+/** One settings file's own settings, without its `extends` */
+export interface ConfigLayer {
+  file: string
+  config: Record<string, unknown>
+}
+
+/**
+ * Read a settings file and the files it extends, lowest precedence first
+ * readLayer parses one file and returns null (after reporting why) when it
+ * is invalid. Returns null when filePath itself is invalid, and throws
+ * ConfigExtendsError for cycles and missing or invalid extended files.
+ */
+export function readConfigLayers(
+  filePath: string,
+  readLayer: (filePath: string) => Record<string, unknown> | null,
+  chain: string[] = [],
+): ConfigLayer[] | null {
+  const resolved = path.resolve(filePath)
+  if (chain.includes(resolved)) {
+    throw new ConfigExtendsError(
+      `extends cycle: ${[...chain, resolved].join(' -> ')}`,
+    )
+  }
+
+  const layer = readLayer(resolved)
+  if (!layer) {
+    if (chain.length === 0) {
+      return null
+    }
+    throw new ConfigExtendsError(
+      `${resolved}, extended by ${chain[chain.length - 1]}, is invalid`,
+    )
+  }
+
+  const { extends: references, ...own } = layer
+  const bases = getExtendsReferences(references, resolved).flatMap(
+    reference =>
+      readConfigLayers(
+        resolveExtendsReference(reference, resolved),
+        readLayer,
+        [...chain, resolved],
+      ) ?? [],
+  )
+  return [...bases, { file: resolved, config: own }]
+}
+
+/**
+ * Read a settings file and the files it extends, merged in order
+ * Returns null when filePath itself is invalid (see readConfigLayers).
+ */
+export function loadConfigWithExtends(
+  filePath: string,
+  readLayer: (filePath: string) => Record<string, unknown> | null,
+): Record<string, unknown> | null {
+  const layers = readConfigLayers(filePath, readLayer)
+  return layers && mergeConfigLayers(layers.map(layer => layer.config))
+}
@@ -0,0 +1,124 @@
+import type { SandboxRuntimeConfig } from './sandbox-config.js'
+
+/**
+ * Built-in named policies for common workflows
+ *
+ * A preset contributes curated allowed domains and filesystem rules. They are
+ * merged into the config's own lists (preset entries first, duplicates
+ * removed), so a config can extend a preset but not remove its entries.
+ */
+
+export const CONFIG_PRESET_NAMES = [
+  'node-dev',
+  'python-dev',
+  'rust-build',
+  'git-only',
+] as const
+
+export type ConfigPresetName = (typeof CONFIG_PRESET_NAMES)[number]
+
+export interface ConfigPreset {
+  allowedDomains: string[]
+  denyRead: string[]
+  allowWrite: string[]
+  denyWrite: string[]
+}
+
+/** Code hosts package managers fetch git dependencies and release assets from */
+const GIT_HOSTS = [
+  'github.com',
+  'codeload.github.com',
+  'objects.githubusercontent.com',
+  'gitlab.com',
+  'bitbucket.org',
+]
+
+/** Cloud and signing credentials no build needs */
+const CREDENTIAL_PATHS = [
+  '~/.aws',
+  '~/.azure',
+  '~/.config/gcloud',
+  '~/.gnupg',
+  '~/.kube',
+  '~/.docker/config.json',
+]
+
+/** Secrets kept in the project directory */
+const PROJECT_SECRETS = ['.env', '.env.local']
+
+export const CONFIG_PRESETS: Record<ConfigPresetName, ConfigPreset> = {
+  'node-dev': {
+    allowedDomains: [
+      'registry.npmjs.org',
+      'registry.yarnpkg.com',
+      'nodejs.org',
+      ...GIT_HOSTS,
+    ],
+    denyRead: ['~/.ssh', ...CREDENTIAL_PATHS],
+    allowWrite: [
+      '.',
+      '/tmp',
+      '~/.npm',
+      '~/.cache/yarn',
+      '~/.local/share/pnpm',
+    ],
+    denyWrite: PROJECT_SECRETS,
+  },
+  'python-dev': {
+    allowedDomains: ['pypi.org', 'files.pythonhosted.org', ...GIT_HOSTS],
+    denyRead: ['~/.ssh', ...CREDENTIAL_PATHS],
+    allowWrite: ['.', '/tmp', '~/.cache/pip', '~/.cache/uv'],
+    denyWrite: PROJECT_SECRETS,
+  },
+  'rust-build': {
+    allowedDomains: [
+      'crates.io',
+      'index.crates.io',
+      'static.crates.io',
+      'static.rust-lang.org',
+      ...GIT_HOSTS,
+    ],
+    denyRead: ['~/.ssh', ...CREDENTIAL_PATHS],
+    allowWrite: ['.', '/tmp', '~/.cargo/registry', '~/.cargo/git'],
+    denyWrite: PROJECT_SECRETS,
+  },
+  // git over SSH needs the keys in ~/.ssh, so they stay readable
+  'git-only': {
+    allowedDomains: GIT_HOSTS,
+    denyRead: CREDENTIAL_PATHS,
+    allowWrite: ['.'],
+    denyWrite: PROJECT_SECRETS,
+  },
+}
+
+function mergeLists(preset: string[], own: string[]): string[] {
+  return [...new Set([...preset, ...own])]
+}
+
+/**
+ * Merge the config's preset, if any, into its network and filesystem lists
+ */
+export function applyConfigPreset(
+  config: SandboxRuntimeConfig,
+): SandboxRuntimeConfig {
+  if (!config.preset) {
+    return config
+  }
+  const preset = CONFIG_PRESETS[config.preset]
+  return {
+    ...config,
+    network: {
+      ...config.network,
+      allowedDomains: mergeLists(
+        preset.allowedDomains,
+        config.network.allowedDomains,
+      ),
+    },
+    filesystem: {
+      ...config.filesystem,
+      denyRead: mergeLists(preset.denyRead, config.filesystem.denyRead),
+      allowWrite: mergeLists(preset.allowWrite, config.filesystem.allowWrite),
+      denyWrite: mergeLists(preset.denyWrite, config.filesystem.denyWrite),
+    },
+  }
+}
@@ -0,0 +1,155 @@
+import type { ConfigLayer } from './config-layers.js'
+import { applyConfigPreset } from './config-presets.js'
+import type { SandboxRuntimeConfig } from './sandbox-config.js'
+
+/**
+ * Where the effective config came from (`srt config show`)
+ *
+ * Settings files are traced in the order they are merged. A list entry comes
+ * from the first file that lists it, since merging keeps the first copy of
+ * duplicates, and any other value from the last file that sets it. Entries a
+ * preset adds are attributed to the preset, and values no file sets are
+ * schema defaults.
+ */
+
+/** Source of a value: a settings file, `preset <name>`, or `default` */
+export type ConfigSources = Map<string, string>
+
+export const DEFAULT_CONFIG_SOURCE = 'default'
+
+function isPlainObject(value: unknown): value is Record<string, unknown> {
+  return typeof value === 'object' && value !== null && !Array.isArray(value)
+}
+
+/** Key of a list entry in a ConfigSources map */
+function entryKey(path: string, entry: unknown): string {
+  return `${path}[${JSON.stringify(entry)}]`
+}
+
+function traceValue(
+  value: unknown,
+  path: string,
+  source: string,
+  sources: ConfigSources,
+): void {
+  if (Array.isArray(value)) {
+    sources.set(path, source)
+    for (const entry of value) {
+      const key = entryKey(path, entry)
+      if (!sources.has(key)) {
+        sources.set(key, source)
+      }
+    }
+  } else if (isPlainObject(value)) {
+    for (const [key, child] of Object.entries(value)) {
+      traceValue(child, path ? `${path}.${key}` : key, source, sources)
+    }
+  } else {
+    sources.set(path, source)
+  }
+}
+
+/**
+ * Resolve the config with its preset applied and trace each of its values
+ * to the settings file (or preset) it came from
+ * @param layers The settings files in merge order, as read by loadConfig
+ */
+export function resolveConfigSources(
+  config: SandboxRuntimeConfig,
+  layers: ConfigLayer[],
+): { config: SandboxRuntimeConfig; sources: ConfigSources } {
+  const sources: ConfigSources = new Map()
+  for (const layer of layers) {
+    traceValue(layer.config, '', layer.file, sources)
+  }
+  const resolved = applyConfigPreset(config)
+  if (config.preset) {
+    // Entries no file lists were added by the preset
+    const presetSources: ConfigSources = new Map()
+    traceValue(resolved, '', `preset ${config.preset}`, presetSources)
+    for (const [key, source] of presetSources) {
+      if (key.endsWith(']') && !sources.has(key)) {
+        sources.set(key, source)
+      }
+    }
+  }
+  return { config: resolved, sources }
+}
+
+interface AnnotatedLine {
+  text: string
+  source?: string
+}
+
+/** Add commas after every child but the last */
+function joinWithCommas(children: AnnotatedLine[][]): AnnotatedLine[] {
+  return children.flatMap((lines, index) =>
+    lines.map((line, lineIndex) =>
+      index < children.length - 1 && lineIndex === lines.length - 1
+        ? { ...line, text: line.text + ',' }
+        : line,
+    ),
+  )
+}
+
+function annotate(
+  value: unknown,
+  path: string,
+  sources: ConfigSources,
+  indent: string,
+): AnnotatedLine[] {
+  const inner = indent + '  '
+
+  if (Array.isArray(value)) {
+    if (value.length === 0) {
+      return [{ text: '[]' }]
+    }
+    // Entries validation rewrote (e.g. objects) fall back to the list's source
+    const entries = value.map(entry => [
+      {
+        text: inner + JSON.stringify(entry),
+        source:
+          sources.get(entryKey(path, entry)) ??
+          sources.get(path) ??
+          DEFAULT_CONFIG_SOURCE,
+      },
+    ])
+    return [{ text: '[' }, ...joinWithCommas(entries), { text: indent + ']' }]
+  }
+
+  if (isPlainObject(value)) {
+    const children = Object.entries(value)
+      .filter(([, child]) => child !== undefined)
+      .map(([key, child]) => {
+        const childPath = path ? `${path}.${key}` : key
+        const prefix = `${inner}${JSON.stringify(key)}: `
+        return annotate(child, childPath, sources, inner).map((line, index) =>
+          index === 0 ? { ...line, text: prefix + line.text } : line,
+        )
+      })
+    if (children.length === 0) {
+      return [{ text: '{}' }]
+    }
+    return [{ text: '{' }, ...joinWithCommas(children), { text: indent + '}' }]
+  }
+
+  return [
+    {
+      text: JSON.stringify(value),
+      source: sources.get(path) ?? DEFAULT_CONFIG_SOURCE,
+    },
+  ]
+}
+
+/**
+ * Format a config as JSON with the source of each value in a trailing
+ * `//` comment
+ */
+export function formatConfigWithSources(
+  config: SandboxRuntimeConfig,
+  sources: ConfigSources,
+): string {
+  return annotate(config, '', sources, '')
+    .map(line => (line.source ? `${line.text}  // ${line.source}` : line.text))
+    .join('\n')
+}
@@ -0,0 +1,174 @@
+import { randomBytes } from 'node:crypto'
+import { spawn, type ChildProcess } from 'node:child_process'
+import * as fs from 'node:fs'
+import { tmpdir } from 'node:os'
+import { join } from 'node:path'
+import { logForDebugging } from '../utils/debug.js'
+import type { DesktopConfig } from './sandbox-config.js'
+
+/**
+ * X11, Wayland and session D-Bus isolation for the Linux sandbox
+ *
+ * The read-only root bind leaves the host's display and session bus sockets
+ * in place, and their addresses are inherited through the environment. By
+ * default the sockets are masked and the variables removed, so a headless
+ * workload can't screenshot the desktop or drive host services. The session
+ * bus can instead be exposed through xdg-dbus-proxy, which only lets the
+ * sandbox talk to an allowlist of bus names.
+ */
+
+const X11_SOCKET_DIR = '/tmp/.X11-unix'
+
+export interface DesktopIsolation {
+  /** bwrap arguments that mask or replace the sockets */
+  bwrapArgs: string[]
+  /** Environment variables removed before the sandbox starts */
+  unsetEnv: string[]
+}
+
+export interface DbusProxyContext {
+  socketPath: string
+  process: ChildProcess
+}
+
+function getRuntimeDir(env: NodeJS.ProcessEnv): string | undefined {
+  return env.XDG_RUNTIME_DIR
+}
+
+/**
+ * Path of the session bus socket, from DBUS_SESSION_BUS_ADDRESS or the
+ * $XDG_RUNTIME_DIR/bus default. Abstract sockets have no path; they are only
+ * unreachable when the network namespace is unshared.
+ */
+export function getSessionBusSocketPath(
+  env: NodeJS.ProcessEnv = process.env,
+): string | undefined {
+  const address = env.DBUS_SESSION_BUS_ADDRESS
+  if (address) {
+    const match = /(?:^|;)unix:(?:[^;]*,)?path=([^,;]+)/.exec(address)
+    return match?.[1]
+  }
+  const runtimeDir = getRuntimeDir(env)
+  return runtimeDir ? join(runtimeDir, 'bus') : undefined
+}
+
+export function getWaylandSocketPath(
+  env: NodeJS.ProcessEnv = process.env,
+): string | undefined {
+  const display = env.WAYLAND_DISPLAY ?? 'wayland-0'
+  if (display.startsWith('/')) {
+    return display
+  }
+  const runtimeDir = getRuntimeDir(env)
+  return runtimeDir ? join(runtimeDir, display) : undefined
+}
+
+/**
+ * Build the mounts and environment changes for the configured desktop access
+ * Sockets that don't exist on this host are skipped.
+ */
+export function buildDesktopIsolation(
+  desktop: DesktopConfig | undefined,
+  dbusProxySocketPath?: string,
+  env: NodeJS.ProcessEnv = process.env,
+): DesktopIsolation {
+  const bwrapArgs: string[] = []
+  const unsetEnv: string[] = []
+  const mask = (socketPath: string | undefined) => {
+    if (socketPath && fs.existsSync(socketPath)) {
+      bwrapArgs.push('--ro-bind', '/dev/null', socketPath)
+    }
+  }
+
+  if (!desktop?.x11) {
+    if (fs.existsSync(X11_SOCKET_DIR)) {
+      bwrapArgs.push('--tmpfs', X11_SOCKET_DIR)
+    }
+    unsetEnv.push('DISPLAY', 'XAUTHORITY')
+  }
+
+  if (!desktop?.wayland) {
+    mask(getWaylandSocketPath(env))
+    unsetEnv.push('WAYLAND_DISPLAY')
+  }
+
+  const dbus = desktop?.dbus ?? 'none'
+  const busSocketPath = getSessionBusSocketPath(env)
+  if (dbus === 'none') {
+    mask(busSocketPath)
+    unsetEnv.push('DBUS_SESSION_BUS_ADDRESS')
+  } else if (dbus === 'proxy') {
+    if (!dbusProxySocketPath || !busSocketPath) {
+      throw new Error(
+        'desktop.dbus is "proxy" but the D-Bus proxy is not running or the session bus has no socket path',
+      )
+    }
+    // The proxy socket takes the place of the real bus socket
+    bwrapArgs.push(
+      '--bind',
+      dbusProxySocketPath,
+      busSocketPath,
+      '--setenv',
+      'DBUS_SESSION_BUS_ADDRESS',
+      `unix:path=${busSocketPath}`,
+    )
+  }
+
+  return { bwrapArgs, unsetEnv }
+}
+
+/**
+ * Start xdg-dbus-proxy in front of the session bus (host side)
+ * Only the bus names in talkNames are visible through the proxy socket.
+ */
+export async function startDbusProxy(
+  talkNames: string[],
+  env: NodeJS.ProcessEnv = process.env,
+): Promise<DbusProxyContext> {
+  const busSocketPath = getSessionBusSocketPath(env)
+  if (!busSocketPath) {
+    throw new Error('Cannot start the D-Bus proxy: no session bus socket path')
+  }
+
+  const socketPath = join(
+    tmpdir(),
+    `srt-dbus-${randomBytes(8).toString('hex')}.sock`,
+  )
+  const args = [
+    `unix:path=${busSocketPath}`,
+    socketPath,
+    '--filter',
+    ...talkNames.map(name => `--talk=${name}`),
+  ]
+  logForDebugging(`Starting D-Bus proxy: xdg-dbus-proxy ${args.join(' ')}`)
+
+  const proxyProcess = spawn('xdg-dbus-proxy', args, { stdio: 'ignore' })
+  proxyProcess.on('error', err => {
+    logForDebugging(`D-Bus proxy process error: ${err}`, { level: 'error' })
+  })
+
+  const maxAttempts = 10
+  for (let i = 0; i < maxAttempts; i++) {
+    if (fs.existsSync(socketPath)) {
+      return { socketPath, process: proxyProcess }
+    }
+    if (proxyProcess.exitCode !== null || !proxyProcess.pid) {
+      break
+    }
+    await new Promise(resolve => setTimeout(resolve, 100))
+  }
+
+  proxyProcess.kill('SIGTERM')
+  throw new Error(
+    'Failed to start xdg-dbus-proxy. Is it installed (package xdg-dbus-proxy)?',
+  )
+}
+
+export function stopDbusProxy(context: DbusProxyContext): void {
+  context.process.kill('SIGTERM')
+  try {
+    fs.rmSync(context.socketPath, { force: true })
+  } catch {
+    // Ignore cleanup errors
+  }
+}
@@ -0,0 +1,365 @@
+import { spawnSync } from 'node:child_process'
+import * as fs from 'node:fs'
+import { getPlatform, type Platform } from '../utils/platform.js'
+import {
+  getApplySeccompBinaryPath,
+  getVendorArchitecture,
+} from './generate-seccomp-filter.js'
+import { getLandlockAbiVersion } from './landlock-sandbox.js'
+import { hasCgroupResourceControlSync } from './linux-sandbox-utils.js'
+import { getApplyPledgeBinaryPath } from './openbsd-sandbox-utils.js'
+
+/**
+ * Environment diagnostics (`srt doctor`)
+ *
+ * Checks the host for the tools and kernel features the sandbox backends rely
+ * on and reports which backends are usable here, and for the others, which
+ * checks failed. Optional features (seccomp, Landlock, cgroups) don't decide
+ * whether a backend is usable but are listed as warnings when missing. Docker,
+ * podman and slirp4netns are not used by any backend; they are reported
+ * because a container runtime explains most namespace failures.
+ */
+
+export type DoctorCheckName =
+  | 'ripgrep'
+  | 'bwrap'
+  | 'nsjail'
+  | 'socat'
+  | 'user-namespaces'
+  | 'seccomp'
+  | 'landlock'
+  | 'cgroups'
+  | 'sandbox-exec'
+  | 'apply-pledge'
+  | 'docker'
+  | 'podman'
+  | 'slirp4netns'
+
+export interface DoctorCheck {
+  name: DoctorCheckName
+  ok: boolean
+  /** Where the tool was found, or why the check failed */
+  detail: string
+}
+
+export type DoctorBackendName =
+  | 'bwrap'
+  | 'nsjail'
+  | 'degraded'
+  | 'sandbox-exec'
+  | 'pledge'
+
+export interface DoctorBackend {
+  name: DoctorBackendName
+  usable: boolean
+  /** Failed required checks, empty when usable */
+  reasons: string[]
+  /** Restrictions missing when the backend runs here */
+  warnings: string[]
+}
+
+export interface DoctorReport {
+  platform: Platform
+  checks: DoctorCheck[]
+  backends: DoctorBackend[]
+}
+
+function findExecutable(command: string): string | null {
+  try {
+    const result = spawnSync('which', [command], {
+      encoding: 'utf8',
+      timeout: 1000,
+    })
+    return result.status === 0 && result.stdout.trim()
+      ? result.stdout.trim()
+      : null
+  } catch {
+    return null
+  }
+}
+
+function checkExecutable(
+  name: DoctorCheckName,
+  command: string = name,
+): DoctorCheck {
+  const found = findExecutable(command)
+  return found
+    ? { name, ok: true, detail: found }
+    : { name, ok: false, detail: `${command} not found on PATH` }
+}
+
+function isInsideContainer(): boolean {
+  return fs.existsSync('/.dockerenv') || fs.existsSync('/run/.containerenv')
+}
+
+function readSysctl(file: string): string | undefined {
+  try {
+    return fs.readFileSync(file, 'utf8').trim()
+  } catch {
+    return undefined
+  }
+}
+
+/**
+ * Whether unprivileged processes can create the namespaces the Linux
+ * backends use, tried with bwrap (or unshare) when installed
+ */
+function checkUserNamespaces(): DoctorCheck {
+  const name = 'user-namespaces'
+  if (readSysctl('/proc/sys/kernel/unprivileged_userns_clone') === '0') {
+    return {
+      name,
+      ok: false,
+      detail: 'disabled by kernel.unprivileged_userns_clone=0',
+    }
+  }
+  if (readSysctl('/proc/sys/user/max_user_namespaces') === '0') {
+    return {
+      name,
+      ok: false,
+      detail: 'disabled by user.max_user_namespaces=0',
+    }
+  }
+
+  const probe = findExecutable('bwrap')
+    ? ['bwrap', '--ro-bind', '/', '/', '--unshare-all', 'true']
+    : findExecutable('unshare')
+      ? ['unshare', '--user', '--net', 'true']
+      : null
+  if (!probe) {
+    return {
+      name,
+      ok: true,
+      detail: 'enabled (not tried: no bwrap or unshare)',
+    }
+  }
+  const [command, ...args] = probe as [string, ...string[]]
+  const result = spawnSync(command, args, { encoding: 'utf8', timeout: 5000 })
+  if (result.status === 0) {
+    return { name, ok: true, detail: `${command} created namespaces` }
+  }
+  const error = result.stderr?.trim().split('\n')[0] || `exit ${result.status}`
+  return {
+    name,
+    ok: false,
+    detail: `${command} failed: ${error}${getUserNamespaceHint()}`,
+  }
+}
+
+/** The usual cause of namespace creation failing, if it applies here */
+function getUserNamespaceHint(): string {
+  if (isInsideContainer()) {
+    return '; inside a container, start it with --privileged or a seccomp profile that allows unshare'
+  }
+  const apparmor = '/proc/sys/kernel/apparmor_restrict_unprivileged_userns'
+  if (readSysctl(apparmor) === '1') {
+    return '; AppArmor restricts unprivileged user namespaces (kernel.apparmor_restrict_unprivileged_userns=1)'
+  }
+  return ''
+}
+
+function checkSeccomp(): DoctorCheck {
+  const name = 'seccomp'
+  if (!getVendorArchitecture()) {
+    return {
+      name,
+      ok: false,
+      detail: `unsupported architecture ${process.arch}`,
+    }
+  }
+  const binaryPath = getApplySeccompBinaryPath()
+  return binaryPath
+    ? { name, ok: true, detail: binaryPath }
+    : { name, ok: false, detail: 'apply-seccomp binary not found' }
+}
+
+function checkLandlock(): DoctorCheck {
+  const version = getLandlockAbiVersion()
+  return version > 0
+    ? { name: 'landlock', ok: true, detail: `ABI version ${version}` }
+    : {
+        name: 'landlock',
+        ok: false,
+        detail: 'not supported by the kernel, or apply-landlock not found',
+      }
+}
+
+function checkCgroups(): DoctorCheck {
+  return hasCgroupResourceControlSync()
+    ? { name: 'cgroups', ok: true, detail: 'cgroup v2 and systemd-run' }
+    : {
+        name: 'cgroups',
+        ok: false,
+        detail: 'needs the unified cgroup v2 hierarchy and systemd-run',
+      }
+}
+
+function checkContainerRuntime(name: 'docker' | 'podman'): DoctorCheck {
+  const check = checkExecutable(name)
+  return check.ok && isInsideContainer()
+    ? { ...check, detail: `${check.detail} (srt is running in a container)` }
+    : check
+}
+
+/**
+ * Run the checks that apply to a platform
+ */
+export function runDoctorChecks(
+  platform: Platform = getPlatform(),
+): DoctorCheck[] {
+  switch (platform) {
+    case 'linux':
+      return [
+        checkExecutable('ripgrep', 'rg'),
+        checkExecutable('bwrap'),
+        checkExecutable('nsjail'),
+        checkExecutable('socat'),
+        checkUserNamespaces(),
+        checkSeccomp(),
+        checkLandlock(),
+        checkCgroups(),
+        checkContainerRuntime('docker'),
+        checkContainerRuntime('podman'),
+        checkExecutable('slirp4netns'),
+      ]
+    case 'macos':
+      return [
+        checkExecutable('ripgrep', 'rg'),
+        checkExecutable('sandbox-exec'),
+        checkContainerRuntime('docker'),
+        checkContainerRuntime('podman'),
+      ]
+    case 'openbsd': {
+      const pledgePath = getApplyPledgeBinaryPath()
+      return [
+        checkExecutable('ripgrep', 'rg'),
+        pledgePath
+          ? { name: 'apply-pledge', ok: true, detail: pledgePath }
+          : {
+              name: 'apply-pledge',
+              ok: false,
+              detail: 'apply-pledge binary not found',
+            },
+      ]
+    }
+    default:
+      return []
+  }
+}
+
+function backendStatus(
+  name: DoctorBackendName,
+  checks: DoctorCheck[],
+  required: DoctorCheckName[],
+  optional: Partial<Record<DoctorCheckName, string>> = {},
+): DoctorBackend {
+  const failed = (names: DoctorCheckName[]) =>
+    checks.filter(check => names.includes(check.name) && !check.ok)
+  return {
+    name,
+    usable: failed(required).length === 0,
+    reasons: failed(required).map(check => `${check.name}: ${check.detail}`),
+    warnings: failed(Object.keys(optional) as DoctorCheckName[]).map(
+      check => optional[check.name] ?? check.name,
+    ),
+  }
+}
+
+const LINUX_OPTIONAL_FEATURES: Partial<Record<DoctorCheckName, string>> = {
+  seccomp: 'Unix socket creation is not blocked (no seccomp filter)',
+  cgroups: 'resourceLimits memory, CPU and PID caps are not available',
+}
+
+/**
+ * Which backends the checks allow on a platform, and why the others don't
+ */
+export function getBackendStatuses(
+  platform: Platform,
+  checks: DoctorCheck[],
+): DoctorBackend[] {
+  switch (platform) {
+    case 'linux': {
+      const degraded = backendStatus(
+        'degraded',
+        checks,
+        [],
+        LINUX_OPTIONAL_FEATURES,
+      )
+      return [
+        backendStatus(
+          'bwrap',
+          checks,
+          ['ripgrep', 'bwrap', 'socat', 'user-namespaces'],
+          LINUX_OPTIONAL_FEATURES,
+        ),
+        backendStatus(
+          'nsjail',
+          checks,
+          ['ripgrep', 'nsjail', 'socat', 'user-namespaces'],
+          LINUX_OPTIONAL_FEATURES,
+        ),
+        {
+          ...degraded,
+          warnings: [
+            'no filesystem or network isolation (degradedMode only)',
+            ...degraded.warnings,
+          ],
+        },
+      ]
+    }
+    case 'macos':
+      return [
+        backendStatus('sandbox-exec', checks, ['ripgrep', 'sandbox-exec']),
+      ]
+    case 'openbsd':
+      return [backendStatus('pledge', checks, ['ripgrep', 'apply-pledge'])]
+    default:
+      return []
+  }
+}
+
+/**
+ * Check the host and report which sandbox backends are usable
+ */
+export function runDoctor(platform: Platform = getPlatform()): DoctorReport {
+  const checks = runDoctorChecks(platform)
+  return { platform, checks, backends: getBackendStatuses(platform, checks) }
+}
+
+/**
+ * Whether any backend that isolates the filesystem and network is usable
+ */
+export function hasUsableBackend(report: DoctorReport): boolean {
+  return report.backends.some(
+    backend => backend.usable && backend.name !== 'degraded',
+  )
+}
+
+/**
+ * Format a report as a checks table followed by the backend verdicts
+ */
+export function formatDoctorReport(report: DoctorReport): string {
+  if (report.backends.length === 0) {
+    return `Platform ${report.platform} is not supported: srt sandboxes commands on Linux, macOS and OpenBSD`
+  }
+  const nameWidth = Math.max(5, ...report.checks.map(c => c.name.length))
+  const lines = [
+    `${'CHECK'.padEnd(nameWidth)}  STATUS   DETAIL`,
+    ...report.checks.map(
+      c =>
+        `${c.name.padEnd(nameWidth)}  ${(c.ok ? 'ok' : 'missing').padEnd(7)}  ${c.detail}`,
+    ),
+    '',
+    'Backends:',
+  ]
+  for (const backend of report.backends) {
+    lines.push(
+      `  ${backend.name}: ${backend.usable ? 'usable' : 'not usable'}`,
+      ...backend.reasons.map(reason => `    - ${reason}`),
+      ...(backend.usable ? backend.warnings : []).map(
+        warning => `    ! ${warning}`,
+      ),
+    )
+  }
+  return lines.join('\n')
+}
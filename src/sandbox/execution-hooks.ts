@@ -0,0 +1,69 @@
+import type { SandboxViolationEvent } from './macos-sandbox-utils.js'
+import type { SandboxRuntimeConfig } from './sandbox-config.js'
+import type { SandboxExecuteResult } from './sandbox-execute.js'
+
+/**
+ * Execution hooks (`SandboxManager.addHooks`)
+ *
+ * Callbacks run around every command the manager runs itself (execute,
+ * start, executeWithOutput, executeStreaming and named sandboxes), e.g. for
+ * logging, metrics or vetoing commands. Hooks run in the order they were
+ * added. beforeExecute runs before the command is wrapped and can stop it;
+ * afterExecute runs once it has exited, failed or been killed. A session is
+ * seen as its shell, from openSession until the shell exits, and as each
+ * run. Commands only wrapped with wrapWithSandbox are not seen by hooks.
+ */
+
+export interface SandboxBeforeExecuteContext {
+  command: string
+  /**
+   * The settings the command runs with: the config, with the command's
+   * `commands` policy and the caller's customConfig on top (undefined
+   * before initialization)
+   */
+  policy: SandboxRuntimeConfig | undefined
+  /** The caller's overrides of the config */
+  customConfig?: Partial<SandboxRuntimeConfig>
+}
+
+export interface SandboxAfterExecuteContext
+  extends SandboxBeforeExecuteContext {
+  /** Set when the command exited */
+  result?: SandboxExecuteResult
+  /** Set when it could not run or was killed, e.g. a SandboxTimeoutError */
+  error?: unknown
+  durationMs: number
+}
+
+export interface SandboxExecutionHooks {
+  /**
+   * Called before the command is wrapped. Return false to veto it (execute
+   * then rejects with SandboxCommandVetoedError), or throw to fail it with
+   * that error.
+   */
+  beforeExecute?(
+    context: SandboxBeforeExecuteContext,
+  ): void | boolean | Promise<void | boolean>
+  /** Called after the command; errors thrown here are logged and ignored */
+  afterExecute?(context: SandboxAfterExecuteContext): void | Promise<void>
+  /**
+   * Called for a violation the violation policy says to warn about; without
+   * such a hook the warning only goes to the debug log
+   */
+  onViolationWarning?(context: SandboxViolationWarningContext): void
+}
+
+export interface SandboxViolationWarningContext {
+  command: string
+  violation: SandboxViolationEvent
+}
+
+/**
+ * Raised when a beforeExecute hook vetoes a command
+ */
+export class SandboxCommandVetoedError extends Error {
+  constructor(readonly command: string) {
+    super(`Sandboxed command vetoed by a beforeExecute hook: ${command}`)
+    this.name = 'SandboxCommandVetoedError'
+  }
+}
@@ -0,0 +1,152 @@
+import type { Readable } from 'node:stream'
+import type {
+  SandboxExecuteResult,
+  SandboxExecutionHandle,
+} from './sandbox-execute.js'
+
+/**
+ * Streaming execution (`SandboxManager.executeStreaming`)
+ *
+ * The command's stdout and stderr are split into lines as they arrive and
+ * yielded by two async iterators, so callers can show output while the
+ * command runs and react to it. Lines are buffered until they are read; once
+ * more than highWaterMark characters are waiting, the command's output is
+ * paused until some are read, so a slow reader holds the command back
+ * instead of the buffer growing. Returning from an iterator (e.g. breaking
+ * out of `for await`) drops the rest of that stream.
+ */
+
+/** Default characters of unread output buffered before the source pauses */
+export const DEFAULT_LINE_STREAM_HIGH_WATER_MARK = 1024 * 1024
+
+/**
+ * Lines of one output stream of a command, without their line endings
+ */
+export class OutputLineStream implements AsyncIterableIterator<string> {
+  private readonly buffer: string[] = []
+  private readonly waiting: ((result: IteratorResult<string>) => void)[] = []
+  private buffered = 0
+  private partial = ''
+  private done = false
+  private source: Readable | undefined
+
+  /**
+   * @param highWaterMark Characters of unread output to buffer before the
+   *   source is paused; a line longer than this is yielded in pieces
+   */
+  constructor(
+    private readonly highWaterMark = DEFAULT_LINE_STREAM_HIGH_WATER_MARK,
+  ) {}
+
+  /**
+   * Read the lines of a readable, pausing it while more than highWaterMark
+   * characters are waiting to be read
+   */
+  readFrom(source: Readable): void {
+    this.source = source
+    source.setEncoding('utf8')
+    source.on('data', (chunk: string) => {
+      this.write(chunk)
+      if (this.buffered > this.highWaterMark && !this.done) {
+        source.pause()
+      }
+    })
+  }
+
+  /**
+   * Stop pausing the source, reading the rest of its output into the buffer,
+   * e.g. once the command exited and no more can be held back
+   */
+  readToEnd(): void {
+    const source = this.source
+    this.source = undefined
+    source?.resume()
+  }
+
+  /** Add a chunk of output; a line is yielded once its newline arrives */
+  write(chunk: string): void {
+    if (this.done) {
+      return
+    }
+    const lines = (this.partial + chunk).split('\n')
+    this.partial = lines.pop() ?? ''
+    for (const line of lines) {
+      this.push(line.endsWith('\r') ? line.slice(0, -1) : line)
+    }
+    while (this.partial.length > this.highWaterMark) {
+      this.push(this.partial.slice(0, this.highWaterMark))
+      this.partial = this.partial.slice(this.highWaterMark)
+    }
+  }
+
+  /** End the stream, yielding a last line left without a newline */
+  end(): void {
+    if (this.done) {
+      return
+    }
+    if (this.partial !== '') {
+      this.push(this.partial)
+      this.partial = ''
+    }
+    this.done = true
+    for (const resolve of this.waiting.splice(0)) {
+      resolve({ value: undefined, done: true })
+    }
+  }
+
+  private push(line: string): void {
+    const resolve = this.waiting.shift()
+    if (resolve) {
+      resolve({ value: line, done: false })
+      return
+    }
+    this.buffer.push(line)
+    this.buffered += line.length
+  }
+
+  next(): Promise<IteratorResult<string>> {
+    const line = this.buffer.shift()
+    if (line !== undefined) {
+      this.buffered -= line.length
+      if (this.buffered <= this.highWaterMark) {
+        this.source?.resume()
+      }
+      return Promise.resolve({ value: line, done: false })
+    }
+    if (this.done) {
+      return Promise.resolve({ value: undefined, done: true })
+    }
+    return new Promise(resolve => {
+      this.waiting.push(resolve)
+    })
+  }
+
+  return(): Promise<IteratorResult<string>> {
+    this.buffer.length = 0
+    this.buffered = 0
+    this.partial = ''
+    this.end()
+    // The rest of the output is read and dropped
+    this.readToEnd()
+    return Promise.resolve({ value: undefined, done: true })
+  }
+
+  [Symbol.asyncIterator](): AsyncIterableIterator<string> {
+    return this
+  }
+}
+
+/**
+ * A command started with SandboxManager.executeStreaming
+ */
+export interface SandboxStreamingExecution extends SandboxExecutionHandle {
+  /** Lines the command writes to stdout */
+  stdout: AsyncIterableIterator<string>
+  /** Lines the command writes to stderr */
+  stderr: AsyncIterableIterator<string>
+  /**
+   * Settles once the command has exited and its output has closed, the way
+   * execute settles; the line streams end by then
+   */
+  exited: Promise<SandboxExecuteResult>
+}
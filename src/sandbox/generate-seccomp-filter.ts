@@ -1,13 +1,20 @@
+import { createHash } from 'node:crypto'
 import { join, dirname } from 'node:path'
+import { tmpdir } from 'node:os'
 import { fileURLToPath } from 'node:url'
 import * as fs from 'node:fs'
 import { logForDebugging } from '../utils/debug.js'
+import {
+  buildSyscallDenyFilter,
+  type SeccompArch,
+  type SyscallName,
+} from './seccomp-bpf.js'
 
 /**
  * Map Node.js process.arch to our vendor directory architecture names
  * Returns null for unsupported architectures
  */
-function getVendorArchitecture(): string | null {
+export function getVendorArchitecture(): SeccompArch | null {
   const arch = process.arch as string
   switch (arch) {
     case 'x64':
@@ -150,10 +157,72 @@ export function getApplySeccompBinaryPath(): string | null {
   return null
 }
 
+// Filters written by this process, keyed by content hash
+const generatedFilters = new Map<string, string>()
+
+// Private (0700) directory the filters are written to, created on first use
+let filterDir: string | undefined
+
+/**
+ * The directory to write filters to, so no other user can swap a filter
+ * between writing it and apply-seccomp loading it
+ */
+function getFilterDir(): string {
+  if (!filterDir || !fs.existsSync(filterDir)) {
+    filterDir = fs.mkdtempSync(join(tmpdir(), 'srt-seccomp-'))
+  }
+  return filterDir
+}
+
+/**
+ * Write a BPF program to a temp file for apply-seccomp to load
+ * Identical programs share one file, so repeated calls return the same path.
+ * Returns null if the file cannot be written.
+ */
+export function writeSeccompFilter(filter: Buffer): string | null {
+  const hash = createHash('sha256').update(filter).digest('hex').slice(0, 16)
+  const existing = generatedFilters.get(hash)
+  if (existing && fs.existsSync(existing)) {
+    return existing
+  }
+
+  let filterPath: string | undefined
+  try {
+    filterPath = join(getFilterDir(), `${hash}.bpf`)
+    // A leftover from a failed cleanup is replaced, never written through
+    fs.rmSync(filterPath, { force: true })
+    fs.writeFileSync(filterPath, filter, { flag: 'wx', mode: 0o600 })
+  } catch (error) {
+    logForDebugging(
+      `[SeccompFilter] Failed to write BPF filter to ${filterPath ?? tmpdir()}: ${error}`,
+      { level: 'error' },
+    )
+    return null
+  }
+
+  generatedFilters.set(hash, filterPath)
+  logForDebugging(
+    `[SeccompFilter] Wrote BPF filter (${filter.length / 8} instructions): ${filterPath}`,
+  )
+  return filterPath
+}
+
+export interface SeccompFilterOptions {
+  /** Block socket(AF_UNIX, ...) (default: true) */
+  blockUnixSockets?: boolean
+  /** Additional syscalls that fail with EPERM */
+  denySyscalls?: readonly SyscallName[]
+}
+
 /**
- * Get the path to a pre-generated seccomp BPF filter that blocks Unix domain socket creation
+ * Build a seccomp BPF filter that blocks Unix domain socket creation
  * Returns the path to the BPF filter file, or null if not available
  *
+ * The filter is assembled in-process (see seccomp-bpf.ts) and written to a temp
+ * file; no compiler or pre-generated BPF blob is needed at runtime. With the
+ * default options it matches the vendor/seccomp/{arch}/unix-block.bpf files
+ * byte for byte. options.denySyscalls adds syscalls from the seccomp config.
+ *
  * The filter blocks socket(AF_UNIX, ...) syscalls while allowing all other syscalls.
  * This prevents creation of new Unix domain socket file descriptors.
  *
@@ -168,32 +237,50 @@ export function getApplySeccompBinaryPath(): string | null {
  * read user-space memory to inspect socket paths).
  *
  * Requirements:
- * - Pre-generated BPF filters included for x64 and ARM64 only
+ * - x64 and ARM64 only
  * - Other architectures are not supported
  *
- * @returns Path to the pre-generated BPF filter file, or null if not available
+ * @returns Path to the generated BPF filter file, or null if not available
  */
-export function generateSeccompFilter(): string | null {
-  const preGeneratedBpf = getPreGeneratedBpfPath()
-  if (preGeneratedBpf) {
-    logForDebugging('[SeccompFilter] Using pre-generated BPF filter')
-    return preGeneratedBpf
+export function generateSeccompFilter(
+  options: SeccompFilterOptions = {},
+): string | null {
+  const { blockUnixSockets = true, denySyscalls = [] } = options
+
+  const arch = getVendorArchitecture()
+  if (!arch) {
+    logForDebugging(
+      '[SeccompFilter] BPF filter not available for this architecture. ' +
+        'Only x64 and arm64 are supported.',
+      { level: 'error' },
+    )
+    return null
   }
 
-  logForDebugging(
-    '[SeccompFilter] Pre-generated BPF filter not available for this architecture. ' +
-      'Only x64 and arm64 are supported.',
-    { level: 'error' },
+  return writeSeccompFilter(
+    buildSyscallDenyFilter(arch, denySyscalls, blockUnixSockets),
   )
-  return null
 }
 
 /**
  * Clean up a seccomp filter file
- * Since we only use pre-generated BPF files from vendor/, this is a no-op.
- * Pre-generated files are never deleted.
- * Kept for backward compatibility with existing code that calls it.
+ * Only files written by writeSeccompFilter() are deleted; any other path
+ * (including pre-generated files under vendor/) is left alone. Never throws.
  */
-export function cleanupSeccompFilter(_filterPath: string): void {
-  // No-op: pre-generated BPF files are never cleaned up
+export function cleanupSeccompFilter(filterPath: string): void {
+  for (const [hash, path] of generatedFilters) {
+    if (path !== filterPath) {
+      continue
+    }
+    generatedFilters.delete(hash)
+    try {
+      fs.rmSync(filterPath, { force: true })
+      if (generatedFilters.size === 0 && filterDir) {
+        fs.rmSync(filterDir, { recursive: true, force: true })
+        filterDir = undefined
+      }
+    } catch {
+      // Ignore: the temp directory is cleaned up eventually
+    }
+  }
 }
@@ -0,0 +1,252 @@
+import * as fs from 'node:fs'
+import * as path from 'node:path'
+import { logForDebugging } from '../utils/debug.js'
+import {
+  containsGlobChars,
+  globToRegex,
+  normalizePathForSandbox,
+  removeTrailingGlobSuffix,
+} from './sandbox-utils.js'
+
+/**
+ * Glob expansion for platforms without glob support in the sandbox
+ *
+ * The macOS profile matches globs itself. bwrap, Landlock and unveil only
+ * take paths, so on Linux and OpenBSD a glob becomes the paths that match it
+ * when a command is wrapped. Paths created later are not covered until the
+ * next command is wrapped.
+ *
+ * `*`, `?` and `[...]` match within one path segment (dotfiles included),
+ * and `**` matches any number of directories. `**` does not follow symlinks,
+ * so links cannot make it loop or leave the tree, and it does not descend
+ * into a directory that already matched, since rules cover whole subtrees.
+ * Symlinks in the directories before the first glob are resolved like any
+ * other configured path.
+ *
+ * A walk is cached with the directories it depends on, and reused while none
+ * of them changed (same inode and mtime), so wrapping many commands does not
+ * read the same trees again. A walk that hit the directory limit is
+ * incomplete: that only narrows an allow rule, but a deny rule could miss
+ * paths, so expanding one throws instead.
+ */
+
+/** Directory levels `**` descends */
+const MAX_GLOBSTAR_DEPTH = 8
+
+/** Directories read per pattern, to bound patterns like /**\/name */
+const MAX_DIRECTORIES_READ = 10000
+
+/** Walks kept for reuse */
+const MAX_CACHED_WALKS = 256
+
+interface GlobWalk {
+  matches: string[]
+  matched: Set<string>
+  directoriesLeft: number
+  /** Directories whose entries decided the result, with their versions */
+  dependencies: Map<string, string | undefined>
+}
+
+interface CachedWalk {
+  matches: string[]
+  truncated: boolean
+  dependencies: Map<string, string | undefined>
+}
+
+const walkCache = new Map<string, CachedWalk>()
+
+/**
+ * Raised when a deny rule's glob matches more than the expansion reads, so
+ * the paths it denies are not all known
+ */
+export class GlobExpansionLimitError extends Error {
+  constructor(readonly pattern: string) {
+    super(
+      `Stopped expanding ${pattern} after reading ${MAX_DIRECTORIES_READ} directories, ` +
+        'so it cannot be enforced. Use a literal directory or a narrower pattern.',
+    )
+    this.name = 'GlobExpansionLimitError'
+  }
+}
+
+/** Identifies a directory's entries: they change with its inode or mtime */
+function getDirectoryVersion(dir: string): string | undefined {
+  try {
+    const stats = fs.statSync(dir, { bigint: true })
+    return `${stats.ino}:${stats.mtimeNs}`
+  } catch {
+    return undefined
+  }
+}
+
+function addDependency(dir: string, walk: GlobWalk): void {
+  if (!walk.dependencies.has(dir)) {
+    walk.dependencies.set(dir, getDirectoryVersion(dir))
+  }
+}
+
+function readDirectory(dir: string, walk: GlobWalk): fs.Dirent[] {
+  if (walk.directoriesLeft <= 0) {
+    return []
+  }
+  walk.directoriesLeft--
+  addDependency(dir, walk)
+  try {
+    return fs.readdirSync(dir, { withFileTypes: true })
+  } catch {
+    // Unreadable or not a directory
+    return []
+  }
+}
+
+function isDirectory(entryPath: string, entry?: fs.Dirent): boolean {
+  if (entry && !entry.isSymbolicLink()) {
+    return entry.isDirectory()
+  }
+  try {
+    return fs.statSync(entryPath).isDirectory()
+  } catch {
+    return false
+  }
+}
+
+function addMatch(matchPath: string, walk: GlobWalk): void {
+  if (!walk.matched.has(matchPath)) {
+    walk.matched.add(matchPath)
+    walk.matches.push(matchPath)
+  }
+}
+
+function walkSegments(
+  dir: string,
+  segments: string[],
+  walk: GlobWalk,
+  globstarDepth = 0,
+): void {
+  const [segment, ...rest] = segments
+  if (segment === undefined) {
+    addMatch(dir, walk)
+    return
+  }
+
+  if (segment === '**') {
+    // Zero directories first, so matches here prune the descent below
+    walkSegments(dir, rest, walk)
+    if (globstarDepth >= MAX_GLOBSTAR_DEPTH) {
+      return
+    }
+    for (const entry of readDirectory(dir, walk)) {
+      const entryPath = path.join(dir, entry.name)
+      if (entry.isDirectory() && !walk.matched.has(entryPath)) {
+        walkSegments(entryPath, segments, walk, globstarDepth + 1)
+      }
+    }
+    return
+  }
+
+  if (!containsGlobChars(segment)) {
+    const entryPath = path.join(dir, segment)
+    addDependency(dir, walk)
+    if (rest.length === 0) {
+      if (fs.existsSync(entryPath)) {
+        addMatch(entryPath, walk)
+      }
+    } else if (isDirectory(entryPath)) {
+      walkSegments(entryPath, rest, walk)
+    }
+    return
+  }
+
+  const regex = new RegExp(globToRegex(segment))
+  for (const entry of readDirectory(dir, walk)) {
+    if (!regex.test(entry.name)) {
+      continue
+    }
+    const entryPath = path.join(dir, entry.name)
+    if (rest.length === 0) {
+      addMatch(entryPath, walk)
+    } else if (isDirectory(entryPath, entry)) {
+      walkSegments(entryPath, rest, walk)
+    }
+  }
+}
+
+function isWalkCurrent(cached: CachedWalk): boolean {
+  for (const [dir, version] of cached.dependencies) {
+    if (getDirectoryVersion(dir) !== version) {
+      return false
+    }
+  }
+  return true
+}
+
+function walkPattern(normalized: string): CachedWalk {
+  const cached = walkCache.get(normalized)
+  if (cached && isWalkCurrent(cached)) {
+    return cached
+  }
+
+  const segments = normalized.split('/').filter(Boolean)
+  const firstGlob = segments.findIndex(segment => containsGlobChars(segment))
+  const base = '/' + segments.slice(0, firstGlob).join('/')
+  const walk: GlobWalk = {
+    matches: [],
+    matched: new Set(),
+    directoriesLeft: MAX_DIRECTORIES_READ,
+    dependencies: new Map(),
+  }
+  walkSegments(base, segments.slice(firstGlob), walk)
+
+  const result = {
+    matches: walk.matches,
+    truncated: walk.directoriesLeft <= 0,
+    dependencies: walk.dependencies,
+  }
+  if (walkCache.size >= MAX_CACHED_WALKS) {
+    walkCache.clear()
+  }
+  walkCache.set(normalized, result)
+  return result
+}
+
+/**
+ * The existing paths matching a filesystem rule
+ * Rules without glob characters (after dropping a trailing /**) are returned
+ * as they are, whether or not they exist.
+ * @param rule - 'deny' throws GlobExpansionLimitError when the walk stops
+ *   at the directory limit; for 'allow' rules the later matches are left out
+ */
+export function expandGlobPath(
+  pattern: string,
+  rule: 'allow' | 'deny' = 'allow',
+): string[] {
+  const trimmed = removeTrailingGlobSuffix(pattern)
+  if (!containsGlobChars(trimmed)) {
+    return [trimmed]
+  }
+
+  const { matches, truncated } = walkPattern(normalizePathForSandbox(trimmed))
+  if (truncated) {
+    if (rule === 'deny') {
+      throw new GlobExpansionLimitError(pattern)
+    }
+    logForDebugging(
+      `Stopped expanding ${pattern} after reading ${MAX_DIRECTORIES_READ} directories; later matches are not allowed`,
+      { level: 'warn' },
+    )
+  }
+  logForDebugging(`Expanded ${pattern} to ${matches.length} paths`)
+  return [...matches]
+}
+
+/**
+ * Expand every rule of a filesystem list, without duplicates
+ */
+export function expandGlobPaths(
+  patterns: string[],
+  rule: 'allow' | 'deny' = 'allow',
+): string[] {
+  return [
+    ...new Set(patterns.flatMap(pattern => expandGlobPath(pattern, rule))),
+  ]
+}
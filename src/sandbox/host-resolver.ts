@@ -0,0 +1,195 @@
+import { promises as dns, type LookupAddress } from 'node:dns'
+import { BlockList, isIP } from 'node:net'
+import { logForDebugging } from '../utils/debug.js'
+
+/** How long resolved addresses are reused before resolving again */
+export const DEFAULT_DNS_CACHE_TTL_MS = 30_000
+
+export type LookupFunction = (host: string) => Promise<LookupAddress[]>
+export type ReverseFunction = (address: string) => Promise<string[]>
+
+const defaultLookup: LookupFunction = host =>
+  dns.lookup(host, { all: true, verbatim: true })
+
+const defaultReverse: ReverseFunction = address => dns.reverse(address)
+
+interface CacheEntry {
+  addresses: LookupAddress[]
+  expiresAt: number
+}
+
+/**
+ * Resolves hostnames on behalf of the proxies with a TTL cache
+ *
+ * The proxies connect to the addresses resolved here instead of letting the
+ * connect call resolve the name again, so the address that was checked is the
+ * address that is used.
+ */
+export class HostResolver {
+  private cache = new Map<string, CacheEntry>()
+
+  constructor(
+    private readonly ttlMs: number = DEFAULT_DNS_CACHE_TTL_MS,
+    private readonly lookupFn: LookupFunction = defaultLookup,
+    private readonly reverseFn: ReverseFunction = defaultReverse,
+  ) {}
+
+  /**
+   * Resolve a hostname to its addresses (IP literals resolve to themselves)
+   */
+  async resolve(host: string): Promise<LookupAddress[]> {
+    const family = isIP(host)
+    if (family !== 0) {
+      return [{ address: host, family }]
+    }
+
+    const key = host.toLowerCase()
+    const cached = this.cache.get(key)
+    if (cached && cached.expiresAt > Date.now()) {
+      return cached.addresses
+    }
+
+    const addresses = await this.lookupFn(host)
+    this.cache.set(key, { addresses, expiresAt: Date.now() + this.ttlMs })
+    logForDebugging(
+      `[HostResolver] Resolved ${host} -> ${addresses.map(a => a.address).join(', ')}`,
+    )
+    return addresses
+  }
+
+  /**
+   * Find a hostname that an IP literal legitimately belongs to
+   *
+   * Uses forward-confirmed reverse DNS: the PTR name must match isAllowedHost and
+   * must itself resolve back to the same IP. Returns undefined if no name qualifies.
+   */
+  async verifyIpTarget(
+    address: string,
+    isAllowedHost: (host: string) => boolean,
+  ): Promise<string | undefined> {
+    let hostnames: string[]
+    try {
+      hostnames = await this.reverseFn(address)
+    } catch {
+      logForDebugging(`[HostResolver] Reverse lookup failed for ${address}`)
+      return undefined
+    }
+
+    for (const hostname of hostnames) {
+      if (!isAllowedHost(hostname)) {
+        continue
+      }
+      try {
+        const addresses = await this.resolve(hostname)
+        if (addresses.some(a => a.address === address)) {
+          return hostname
+        }
+      } catch {
+        // Try the next PTR name
+      }
+    }
+
+    return undefined
+  }
+
+  clear(): void {
+    this.cache.clear()
+  }
+}
+
+// Ranges that reach the host or its local network rather than the internet
+const PRIVATE_RANGES: Array<[string, number, 'ipv4' | 'ipv6']> = [
+  ['0.0.0.0', 8, 'ipv4'], // "this" network
+  ['10.0.0.0', 8, 'ipv4'], // RFC 1918
+  ['100.64.0.0', 10, 'ipv4'], // carrier-grade NAT
+  ['127.0.0.0', 8, 'ipv4'], // loopback
+  ['169.254.0.0', 16, 'ipv4'], // link-local
+  ['172.16.0.0', 12, 'ipv4'], // RFC 1918
+  ['192.0.0.0', 24, 'ipv4'], // IETF protocol assignments
+  ['192.168.0.0', 16, 'ipv4'], // RFC 1918
+  ['198.18.0.0', 15, 'ipv4'], // benchmarking
+  ['224.0.0.0', 4, 'ipv4'], // multicast
+  ['240.0.0.0', 4, 'ipv4'], // reserved and broadcast
+  ['::', 128, 'ipv6'], // unspecified
+  ['::1', 128, 'ipv6'], // loopback
+  ['fc00::', 7, 'ipv6'], // unique local
+  ['fe80::', 10, 'ipv6'], // link-local
+  ['ff00::', 8, 'ipv6'], // multicast
+]
+
+const privateRanges = new BlockList()
+for (const [network, prefix, type] of PRIVATE_RANGES) {
+  privateRanges.addSubnet(network, prefix, type)
+}
+
+/**
+ * Check whether an IP address is private, loopback, link-local or otherwise
+ * not publicly routable. IPv4-mapped IPv6 addresses are checked as IPv4.
+ */
+export function isPrivateAddress(address: string): boolean {
+  const family = isIP(address)
+  if (family === 0) {
+    return false
+  }
+  return privateRanges.check(address, family === 4 ? 'ipv4' : 'ipv6')
+}
+
+// Instance metadata services that hand out cloud credentials
+const CLOUD_METADATA_ADDRESSES: Array<[string, 'ipv4' | 'ipv6']> = [
+  ['169.254.169.254', 'ipv4'], // AWS, GCP, Azure, DigitalOcean, OpenStack, ...
+  ['169.254.170.2', 'ipv4'], // AWS ECS task metadata
+  ['fd00:ec2::254', 'ipv6'], // AWS IMDS over IPv6
+  ['100.100.100.200', 'ipv4'], // Alibaba Cloud
+  ['192.0.0.192', 'ipv4'], // Oracle Cloud
+]
+
+const CLOUD_METADATA_HOSTNAMES = [
+  'metadata',
+  'metadata.google.internal',
+  'metadata.goog',
+  'metadata.azure.internal',
+  'instance-data',
+  'instance-data.ec2.internal',
+  'metadata.tencentyun.com',
+]
+
+const cloudMetadataAddresses = new BlockList()
+for (const [address, type] of CLOUD_METADATA_ADDRESSES) {
+  cloudMetadataAddresses.addAddress(address, type)
+}
+
+/**
+ * Check whether a hostname or IP address is a cloud instance metadata endpoint
+ */
+export function isCloudMetadataHost(host: string): boolean {
+  const family = isIP(host)
+  if (family !== 0) {
+    return cloudMetadataAddresses.check(host, family === 4 ? 'ipv4' : 'ipv6')
+  }
+  const hostname = host.toLowerCase().replace(/\.$/, '')
+  return CLOUD_METADATA_HOSTNAMES.includes(hostname)
+}
+
+/**
+ * Build a dns.lookup-compatible function that always returns a pinned address
+ * Used so connections keep the original hostname (SNI, Host header) while
+ * connecting to the address that was resolved and checked by the proxy.
+ */
+export function createPinnedLookup(address: string) {
+  const family = isIP(address)
+  return (
+    _hostname: string,
+    options: { all?: boolean },
+    callback: (
+      err: NodeJS.ErrnoException | null,
+      address: string | LookupAddress[],
+      family?: number,
+    ) => void,
+  ): void => {
+    if (options?.all) {
+      callback(null, [{ address, family }])
+    } else {
+      callback(null, address, family)
+    }
+  }
+}
@@ -6,6 +6,7 @@ import { request as httpsRequest } from 'node:https'
 import { connect } from 'node:net'
 import { URL } from 'node:url'
 import { logForDebugging } from '../utils/debug.js'
+import { createPinnedLookup } from './host-resolver.js'
 
 export interface HttpProxyServerOptions {
   filter(
@@ -13,6 +14,12 @@ export interface HttpProxyServerOptions {
     host: string,
     socket: Socket | Duplex,
   ): Promise<boolean> | boolean
+  /**
+   * Resolve the address to connect to for an allowed host.
+   * Returning undefined blocks the connection. When omitted, the host is
+   * resolved by the connect call itself.
+   */
+  resolveHost?(host: string, port: number): Promise<string | undefined>
 }
 
 export function createHttpProxyServer(options: HttpProxyServerOptions): Server {
@@ -52,7 +59,26 @@ export function createHttpProxyServer(options: HttpProxyServerOptions): Server {
         return
       }
 
-      const serverSocket = connect(port, hostname, () => {
+      const address = options.resolveHost
+        ? await options.resolveHost(hostname, port)
+        : hostname
+      if (!address) {
+        logForDebugging(
+          `Connection blocked to ${hostname}:${port} (resolution)`,
+          { level: 'error' },
+        )
+        socket.end(
+          'HTTP/1.1 403 Forbidden\r\n' +
+            'Content-Type: text/plain\r\n' +
+            'X-Proxy-Error: blocked-by-resolution\r\n' +
+            '\r\n' +
+            'Connection blocked: host resolved to a disallowed address',
+        )
+        return
+      }
+
+      // Connect to the resolved address, not whatever the name resolves to now
+      const serverSocket = connect({ port, host: address }, () => {
         socket.write('HTTP/1.1 200 Connection Established\r\n\r\n')
         serverSocket.pipe(socket)
         socket.pipe(serverSocket)
@@ -104,6 +130,22 @@ export function createHttpProxyServer(options: HttpProxyServerOptions): Server {
         return
       }
 
+      const address = options.resolveHost
+        ? await options.resolveHost(hostname, port)
+        : hostname
+      if (!address) {
+        logForDebugging(
+          `HTTP request blocked to ${hostname}:${port} (resolution)`,
+          { level: 'error' },
+        )
+        res.writeHead(403, {
+          'Content-Type': 'text/plain',
+          'X-Proxy-Error': 'blocked-by-resolution',
+        })
+        res.end('Connection blocked: host resolved to a disallowed address')
+        return
+      }
+
       // Choose http or https module
       const requestFn = url.protocol === 'https:' ? httpsRequest : httpRequest
 
@@ -113,6 +155,8 @@ export function createHttpProxyServer(options: HttpProxyServerOptions): Server {
           port,
           path: url.pathname + url.search,
           method: req.method,
+          // Keep the hostname for the Host header and TLS SNI, but pin the address
+          lookup: createPinnedLookup(address),
           headers: {
             ...req.headers,
             host: url.host,
@@ -0,0 +1,224 @@
+import { spawnSync } from 'node:child_process'
+import * as fs from 'node:fs'
+import { dirname, join } from 'node:path'
+import { fileURLToPath } from 'node:url'
+import { logForDebugging } from '../utils/debug.js'
+import { getVendorArchitecture } from './generate-seccomp-filter.js'
+
+/**
+ * Filesystem rules to enforce with Landlock
+ *
+ * Landlock only supports allow rules, so deny lists are turned into the set of
+ * paths that remain accessible (see expandAllowedPaths).
+ */
+export interface LandlockRules {
+  /** Paths that must not be readable. Empty means reads are unrestricted */
+  readDenyPaths: string[]
+  /** Paths that may be written. Undefined means writes are unrestricted */
+  writeAllowPaths?: string[]
+  /** Paths within writeAllowPaths that must stay read-only */
+  writeDenyPaths: string[]
+}
+
+/** Paths that always stay writable so /dev/null, ttys, etc. keep working */
+const ALWAYS_WRITABLE_PATHS = ['/dev']
+
+let cachedAbiVersion: number | undefined
+
+/**
+ * Get the path to the apply-landlock binary from the vendor directory
+ * Returns the path if it exists, null otherwise
+ *
+ * Uses the same lookup locations as apply-seccomp:
+ * - vendor/seccomp/{x64,arm64}/apply-landlock
+ */
+export function getApplyLandlockBinaryPath(): string | null {
+  const arch = getVendorArchitecture()
+  if (!arch) {
+    return null
+  }
+
+  // Path is relative to the compiled code location (dist/sandbox/)
+  const baseDir = dirname(fileURLToPath(import.meta.url))
+  const relativePath = join('vendor', 'seccomp', arch, 'apply-landlock')
+
+  const pathsToTry = [
+    join(baseDir, relativePath), // bundled
+    join(baseDir, '..', '..', relativePath), // package root: vendor/seccomp/...
+    join(baseDir, '..', relativePath), // dist: dist/vendor/seccomp/...
+  ]
+
+  for (const binaryPath of pathsToTry) {
+    if (fs.existsSync(binaryPath)) {
+      logForDebugging(`[Landlock] Found apply-landlock binary: ${binaryPath}`)
+      return binaryPath
+    }
+  }
+
+  logForDebugging(
+    `[Landlock] apply-landlock binary not found in any expected location (${arch})`,
+  )
+  return null
+}
+
+/**
+ * Detect the Landlock ABI version supported by the running kernel
+ * Returns 0 if Landlock is unavailable (kernel < 5.13, LSM disabled, or no helper binary).
+ * The result is cached for the lifetime of the process.
+ */
+export function getLandlockAbiVersion(): number {
+  if (cachedAbiVersion !== undefined) {
+    return cachedAbiVersion
+  }
+
+  cachedAbiVersion = 0
+  if (process.platform !== 'linux') {
+    return cachedAbiVersion
+  }
+
+  const binaryPath = getApplyLandlockBinaryPath()
+  if (!binaryPath) {
+    return cachedAbiVersion
+  }
+
+  // The helper asks the kernel directly, which covers both the kernel version
+  // and whether Landlock is enabled in the active LSM list
+  const result = spawnSync(binaryPath, ['--abi'], {
+    encoding: 'utf8',
+    timeout: 1000,
+  })
+  if (result.status === 0) {
+    const version = parseInt(result.stdout.trim(), 10)
+    if (version > 0) {
+      cachedAbiVersion = version
+    }
+  }
+
+  logForDebugging(`[Landlock] Detected ABI version: ${cachedAbiVersion}`)
+  return cachedAbiVersion
+}
+
+/**
+ * Check if Landlock filesystem restrictions can be applied on this system
+ */
+export function isLandlockSupported(): boolean {
+  return getLandlockAbiVersion() > 0
+}
+
+function isSameOrWithin(candidate: string, parent: string): boolean {
+  return (
+    candidate === parent ||
+    candidate.startsWith(parent === '/' ? '/' : parent + '/')
+  )
+}
+
+/**
+ * Expand "everything under root except excludedPaths" into a list of allowed paths
+ *
+ * Walks down from root along the excluded paths only: siblings of each path
+ * component are allowed as a whole, and the excluded paths themselves are left out.
+ * Symlinks are skipped since Landlock checks access against the resolved target.
+ */
+export function expandAllowedPaths(
+  root: string,
+  excludedPaths: string[],
+): string[] {
+  const relevant = excludedPaths.filter(p => isSameOrWithin(p, root))
+  if (relevant.length === 0) {
+    return [root]
+  }
+  if (relevant.includes(root)) {
+    return []
+  }
+
+  let entries: fs.Dirent[]
+  try {
+    entries = fs.readdirSync(root, { withFileTypes: true })
+  } catch (error) {
+    logForDebugging(
+      `[Landlock] Cannot list ${root}, leaving it denied: ${error}`,
+    )
+    return []
+  }
+
+  const allowed: string[] = []
+  for (const entry of entries) {
+    if (entry.isSymbolicLink()) {
+      continue
+    }
+    allowed.push(...expandAllowedPaths(join(root, entry.name), relevant))
+  }
+  return allowed
+}
+
+/**
+ * Build the apply-landlock arguments (without the binary and command) for a set of rules
+ * Throws when denyRead leaves nothing readable, since no read rule would mean
+ * unrestricted reads.
+ */
+export function buildLandlockArgs(rules: LandlockRules): string[] {
+  const args: string[] = []
+
+  if (rules.readDenyPaths.length > 0) {
+    const readPaths = expandAllowedPaths('/', rules.readDenyPaths)
+    // Without a --read rule the helper leaves reads unrestricted
+    if (readPaths.length === 0) {
+      throw new Error(
+        'Landlock cannot enforce denyRead: no readable path is left ' +
+          `(denyRead: ${rules.readDenyPaths.join(', ')})`,
+      )
+    }
+    for (const path of readPaths) {
+      args.push('--read', path)
+    }
+  }
+
+  if (rules.writeAllowPaths) {
+    const writePaths = new Set<string>(ALWAYS_WRITABLE_PATHS)
+    for (const allowPath of rules.writeAllowPaths) {
+      for (const path of expandAllowedPaths(allowPath, rules.writeDenyPaths)) {
+        writePaths.add(path)
+      }
+    }
+    for (const path of writePaths) {
+      args.push('--write', path)
+    }
+  }
+
+  return args
+}
+
+/**
+ * Build the command prefix that applies Landlock rules before exec'ing the command
+ * Returns an empty array when the rules do not restrict anything.
+ */
+export function buildLandlockCommandPrefix(rules: LandlockRules): string[] {
+  const args = buildLandlockArgs(rules)
+  if (args.length === 0) {
+    return []
+  }
+
+  const binaryPath = getApplyLandlockBinaryPath()
+  if (!binaryPath) {
+    throw new Error(
+      'apply-landlock binary not found. ' +
+        'Ensure vendor/seccomp/{x64,arm64}/apply-landlock binaries are included in the package.',
+    )
+  }
+
+  return [binaryPath, ...args, '--']
+}
+
+/**
+ * Landlock LSM backend for Linux filesystem restrictions
+ *
+ * Enforces read/write rules in the kernel (Linux >= 5.13) without relying on
+ * bwrap's bind-mount topology. Can run on its own inside bwrap's namespaces or
+ * be stacked on top of the bind-mount rules as a second layer.
+ */
+export const LandlockSandbox = {
+  isSupported: isLandlockSupported,
+  getAbiVersion: getLandlockAbiVersion,
+  buildArgs: buildLandlockArgs,
+  buildCommandPrefix: buildLandlockCommandPrefix,
+} as const
@@ -13,17 +13,38 @@ import {
   normalizeCaseForComparison,
   DANGEROUS_FILES,
   getDangerousDirectories,
+  getSrtSettingsPaths,
 } from './sandbox-utils.js'
 import type {
   FsReadRestrictionConfig,
   FsWriteRestrictionConfig,
 } from './sandbox-schemas.js'
+import type {
+  DesktopConfig,
+  LinuxCapability,
+  LinuxFilesystemBackend,
+  LinuxSandboxBackend,
+  ResourceLimitsConfig,
+  SeccompConfig,
+  UserMappingConfig,
+} from './sandbox-config.js'
 import {
   generateSeccompFilter,
   cleanupSeccompFilter,
-  getPreGeneratedBpfPath,
   getApplySeccompBinaryPath,
+  getVendorArchitecture,
 } from './generate-seccomp-filter.js'
+import {
+  buildLandlockCommandPrefix,
+  getLandlockAbiVersion,
+  type LandlockRules,
+} from './landlock-sandbox.js'
+import { STRICT_DENIED_SYSCALLS, type SyscallName } from './seccomp-bpf.js'
+import { bwrapArgsToNsjailArgs, isNsjailAvailable } from './nsjail-sandbox.js'
+import { buildDesktopIsolation } from './desktop-isolation.js'
+import { buildStracePrefix } from './linux-violation-monitor.js'
+import { expandGlobPaths } from './glob-expansion.js'
+import { CREDENTIAL_ENV_VAR_PATTERN, getUnsetEnvNames } from './sandbox-env.js'
 
 export interface LinuxNetworkBridgeContext {
   httpSocketPath: string
@@ -53,8 +74,58 @@ export interface LinuxSandboxParams {
   allowGitConfig?: boolean
   /** Abort signal to cancel the ripgrep scan */
   abortSignal?: AbortSignal
+  /** CPU, memory, and pids limits applied via a transient cgroup v2 scope */
+  resourceLimits?: ResourceLimitsConfig
+  /** How filesystem restrictions are enforced (default: 'bwrap') */
+  filesystemBackend?: LinuxFilesystemBackend
+  /** UID/GID seen inside a new user namespace (default: no user namespace mapping) */
+  userMapping?: UserMappingConfig
+  /** Syscall filter profile and extra denied syscalls (default: 'default' profile) */
+  seccomp?: SeccompConfig
+  /** Session scratch directory exposed as $SRT_SCRATCH_DIR and $TMPDIR */
+  scratchDir?: string
+  /** Program that creates the sandbox (default: 'bwrap') */
+  backend?: LinuxSandboxBackend
+  /** Size cap in MB for every tmpfs mount (default: kernel default, half of RAM) */
+  tmpfsSizeMB?: number
+  /** Directories replaced by a fresh writable tmpfs */
+  extraTmpfs?: string[]
+  /** Host paths bound at other locations, as "source:dest[:ro|rw]" */
+  mounts?: string[]
+  /** X11, Wayland and session D-Bus access (default: all hidden) */
+  desktop?: DesktopConfig
+  /** Socket of the host-side xdg-dbus-proxy when desktop.dbus is 'proxy' */
+  dbusProxySocketPath?: string
+  /** Capabilities kept inside the sandbox; all others are dropped (default: none) */
+  capabilities?: LinuxCapability[]
+  /** Leave sensitive /proc entries (kcore, sysrq-trigger, ...) visible (default: false) */
+  allowSensitiveProcEntries?: boolean
+  /** strace output prefix; when set the command runs under strace for violation monitoring */
+  violationTracePath?: string
+  /** Trace successful file syscalls too, for file access auditing */
+  auditFileAccess?: boolean
+  /** Environment of the command; srt's other variables are unset (default: srt's environment) */
+  environment?: Record<string, string>
+  /** Descriptor bwrap reports the command's start on (--json-status-fd); ignored by nsjail */
+  statusFd?: number
 }
 
+/** uid/gid of nobody/nogroup on most distributions */
+const NOBODY_ID = 65534
+
+/**
+ * /proc entries masked with /dev/null in the sandbox's fresh /proc
+ * They expose kernel memory, keyrings and host-wide scheduler/timer state,
+ * or (sysrq-trigger) act on the whole host.
+ */
+const MASKED_PROC_PATHS = [
+  '/proc/kcore',
+  '/proc/keys',
+  '/proc/timer_list',
+  '/proc/sched_debug',
+  '/proc/sysrq-trigger',
+]
+
 /** Default max depth for searching dangerous files */
 const DEFAULT_MANDATORY_DENY_SEARCH_DEPTH = 3
 
@@ -83,6 +154,8 @@ async function linuxGetMandatoryDenyPaths(
     ...dangerousDirectories.map(d => path.resolve(cwd, d)),
     // Git hooks always blocked for security
     path.resolve(cwd, '.git/hooks'),
+    // srt's own settings and state
+    ...getSrtSettingsPaths(),
   ]
 
   // Git config conditionally blocked based on allowGitConfig setting
@@ -192,34 +265,50 @@ function registerSeccompCleanupHandler(): void {
   exitHandlerRegistered = true
 }
 
+/**
+ * Check if the program that creates the sandbox (bwrap or nsjail) is installed
+ */
+export function hasLinuxSandboxBackendSync(
+  backend: LinuxSandboxBackend = 'bwrap',
+): boolean {
+  try {
+    return backend === 'nsjail'
+      ? isNsjailAvailable()
+      : spawnSync('which', ['bwrap'], {
+          stdio: 'ignore',
+          timeout: 1000,
+        }).status === 0
+  } catch {
+    return false
+  }
+}
+
 /**
  * Check if Linux sandbox dependencies are available (synchronous)
- * Returns true if bwrap and socat are installed.
+ * Returns true if the sandbox backend (bwrap or nsjail) and socat are installed.
  */
 export function hasLinuxSandboxDependenciesSync(
   allowAllUnixSockets = false,
+  backend: LinuxSandboxBackend = 'bwrap',
 ): boolean {
   try {
-    const bwrapResult = spawnSync('which', ['bwrap'], {
-      stdio: 'ignore',
-      timeout: 1000,
-    })
+    const hasBackend = hasLinuxSandboxBackendSync(backend)
     const socatResult = spawnSync('which', ['socat'], {
       stdio: 'ignore',
       timeout: 1000,
     })
 
-    const hasBasicDeps = bwrapResult.status === 0 && socatResult.status === 0
+    const hasBasicDeps = hasBackend && socatResult.status === 0
 
     // Check for seccomp dependencies (optional security feature)
     if (!allowAllUnixSockets) {
-      // Check if we have a pre-generated BPF filter for this architecture
-      const hasPreGeneratedBpf = getPreGeneratedBpfPath() !== null
+      // BPF filters are built in-process, so only the architecture matters
+      const hasSupportedArch = getVendorArchitecture() !== null
 
       // Check if we have the apply-seccomp binary for this architecture
       const hasApplySeccompBinary = getApplySeccompBinaryPath() !== null
 
-      if (!hasPreGeneratedBpf || !hasApplySeccompBinary) {
+      if (!hasSupportedArch || !hasApplySeccompBinary) {
         // Seccomp not available - log warning but continue with basic sandbox
         // The sandbox will gracefully fall back to allowAllUnixSockets mode
         logForDebugging(
@@ -237,6 +326,104 @@ export function hasLinuxSandboxDependenciesSync(
   }
 }
 
+/**
+ * Check if transient cgroup v2 scopes can be created for resource limits (synchronous)
+ * Requires the unified cgroup hierarchy and systemd-run to talk to the user manager.
+ */
+export function hasCgroupResourceControlSync(): boolean {
+  try {
+    if (!fs.existsSync('/sys/fs/cgroup/cgroup.controllers')) {
+      return false
+    }
+    const result = spawnSync('which', ['systemd-run'], {
+      stdio: 'ignore',
+      timeout: 1000,
+    })
+    return result.status === 0
+  } catch {
+    return false
+  }
+}
+
+/**
+ * Build the systemd-run prefix that places the bwrap process tree in a transient
+ * cgroup v2 scope with the configured limits. Returns an empty array when no
+ * limits are configured.
+ *
+ * Every process in the sandbox (including socat helpers) is accounted to the scope,
+ * so a runaway build is OOM-killed inside its own cgroup instead of taking down the host.
+ */
+export function buildResourceLimitArgs(
+  resourceLimits: ResourceLimitsConfig | undefined,
+): string[] {
+  if (!resourceLimits) {
+    return []
+  }
+
+  const properties: string[] = []
+  if (resourceLimits.cpus !== undefined) {
+    properties.push(`CPUQuota=${Math.round(resourceLimits.cpus * 100)}%`)
+  }
+  if (resourceLimits.memoryMb !== undefined) {
+    properties.push(`MemoryMax=${resourceLimits.memoryMb}M`)
+    // Without this the limit can be sidestepped by pushing pages to swap
+    properties.push('MemorySwapMax=0')
+  }
+  if (resourceLimits.pids !== undefined) {
+    properties.push(`TasksMax=${resourceLimits.pids}`)
+  }
+
+  if (properties.length === 0) {
+    return []
+  }
+
+  return [
+    'systemd-run',
+    '--user',
+    '--scope',
+    '--quiet',
+    ...properties.flatMap(property => ['-p', property]),
+    '--',
+  ]
+}
+
+/**
+ * Build the bwrap arguments that run the sandbox in a new user namespace with a
+ * fixed uid/gid mapping. Returns an empty array when no mapping is configured.
+ *
+ * Root is never mapped: 'current' falls back to nobody when invoked as root,
+ * and custom mappings reject uid/gid 0 at config validation time.
+ */
+export function buildUserNamespaceArgs(
+  userMapping: UserMappingConfig | undefined,
+): string[] {
+  if (!userMapping) {
+    return []
+  }
+
+  let uid = NOBODY_ID
+  let gid = NOBODY_ID
+  if (userMapping === 'current') {
+    const currentUid = process.getuid?.() ?? NOBODY_ID
+    const currentGid = process.getgid?.() ?? NOBODY_ID
+    if (currentUid === 0 || currentGid === 0) {
+      logForDebugging(
+        '[Sandbox Linux] userMapping "current" requested while running as root. ' +
+          'Mapping to nobody instead to avoid a privileged-looking uid inside the sandbox.',
+        { level: 'warn' },
+      )
+    } else {
+      uid = currentUid
+      gid = currentGid
+    }
+  } else if (typeof userMapping === 'object') {
+    uid = userMapping.uid
+    gid = userMapping.gid
+  }
+
+  return ['--unshare-user', '--uid', String(uid), '--gid', String(gid)]
+}
+
 /**
  * Initialize the Linux network bridge for sandbox networking
  *
@@ -400,6 +587,7 @@ function buildSandboxCommand(
   socksSocketPath: string,
   userCommand: string,
   seccompFilterPath: string | undefined,
+  execPrefix: string[],
   shell?: string,
 ): string {
   // Default to bash for backward compatibility
@@ -432,6 +620,7 @@ function buildSandboxCommand(
     }
 
     const applySeccompCmd = shellquote.quote([
+      ...execPrefix,
       applySeccompBinary,
       seccompFilterPath,
       shellPath,
@@ -441,6 +630,17 @@ function buildSandboxCommand(
 
     const innerScript = [...socatCommands, applySeccompCmd].join('\n')
     return `${shellPath} -c ${shellquote.quote([innerScript])}`
+  } else if (execPrefix.length > 0) {
+    // No seccomp filter - exec the user command through the prefix
+    const applyLandlockCmd = shellquote.quote([
+      ...execPrefix,
+      shellPath,
+      '-c',
+      userCommand,
+    ])
+
+    const innerScript = [...socatCommands, applyLandlockCmd].join('\n')
+    return `${shellPath} -c ${shellquote.quote([innerScript])}`
   } else {
     // No seccomp filter - run user command directly
     const innerScript = [
@@ -455,14 +655,12 @@ function buildSandboxCommand(
 /**
  * Generate filesystem bind mount arguments for bwrap
  */
-async function generateFilesystemArgs(
+function generateFilesystemArgs(
   readConfig: FsReadRestrictionConfig | undefined,
   writeConfig: FsWriteRestrictionConfig | undefined,
-  ripgrepConfig: { command: string; args?: string[] } = { command: 'rg' },
-  mandatoryDenySearchDepth: number = DEFAULT_MANDATORY_DENY_SEARCH_DEPTH,
-  allowGitConfig = false,
-  abortSignal?: AbortSignal,
-): Promise<string[]> {
+  mandatoryDenyPaths: string[],
+  tmpfsSizeMB?: number,
+): string[] {
   const args: string[] = []
   // fs already imported
 
@@ -502,12 +700,7 @@ async function generateFilesystemArgs(
     // Deny writes within allowed paths (user-specified + mandatory denies)
     const denyPaths = [
       ...(writeConfig.denyWithinAllow || []),
-      ...(await linuxGetMandatoryDenyPaths(
-        ripgrepConfig,
-        mandatoryDenySearchDepth,
-        allowGitConfig,
-        abortSignal,
-      )),
+      ...mandatoryDenyPaths,
     ]
 
     for (const pathPattern of denyPaths) {
@@ -568,7 +761,7 @@ async function generateFilesystemArgs(
 
     const readDenyStat = fs.statSync(normalizedPath)
     if (readDenyStat.isDirectory()) {
-      args.push('--tmpfs', normalizedPath)
+      args.push(...buildTmpfsArgs(normalizedPath, tmpfsSizeMB))
     } else {
       // For files, bind /dev/null instead of tmpfs
       args.push('--ro-bind', '/dev/null', normalizedPath)
@@ -578,6 +771,248 @@ async function generateFilesystemArgs(
   return args
 }
 
+/**
+ * Build a bwrap tmpfs mount, capped at sizeMB when set
+ * Without a cap the kernel allows up to half of host RAM per tmpfs.
+ */
+function buildTmpfsArgs(mountPath: string, sizeMB?: number): string[] {
+  return sizeMB === undefined
+    ? ['--tmpfs', mountPath]
+    : ['--size', String(sizeMB * 1024 * 1024), '--tmpfs', mountPath]
+}
+
+/**
+ * The existing directories an extraTmpfs list refers to, with globs expanded
+ */
+function resolveExtraTmpfs(extraTmpfs: string[]): string[] {
+  return expandGlobPaths(extraTmpfs)
+    .map(pathPattern => normalizePathForSandbox(pathPattern))
+    .filter(dir => {
+      if (fs.existsSync(dir) && fs.statSync(dir).isDirectory()) {
+        return true
+      }
+      logForDebugging(
+        `[Sandbox Linux] Skipping extra tmpfs path that is not an existing directory: ${dir}`,
+        { level: 'warn' },
+      )
+      return false
+    })
+}
+
+/**
+ * Mount a fresh writable tmpfs over each extra tmpfs directory
+ */
+function generateExtraTmpfsArgs(
+  tmpfsDirs: string[],
+  tmpfsSizeMB: number | undefined,
+): string[] {
+  return tmpfsDirs.flatMap(dir => buildTmpfsArgs(dir, tmpfsSizeMB))
+}
+
+export interface BindMount {
+  source: string
+  destination: string
+  readOnly: boolean
+}
+
+/**
+ * Parse a "source:dest[:ro|rw]" mount (default mode: ro)
+ * The source is normalized like other config paths; dest must be absolute.
+ */
+export function parseMountSpec(spec: string): BindMount {
+  const [source, destination, mode = 'ro', ...rest] = spec.split(':')
+  if (
+    !source ||
+    !destination ||
+    !path.isAbsolute(destination) ||
+    (mode !== 'ro' && mode !== 'rw') ||
+    rest.length > 0
+  ) {
+    throw new Error(
+      `Invalid mount "${spec}": expected "source:/absolute/dest" or "source:/absolute/dest:ro|rw"`,
+    )
+  }
+  return {
+    source: normalizePathForSandbox(source),
+    destination: path.normalize(destination),
+    readOnly: mode === 'ro',
+  }
+}
+
+/**
+ * Build bwrap binds for remapped mounts
+ *
+ * A mount must not become a way around the deny rules: its source may not
+ * overlap a denyRead path, and a writable mount may not overlap a denyWrite
+ * or mandatory deny path.
+ */
+function generateMountArgs(
+  mounts: BindMount[],
+  readDenyPaths: string[],
+  writeDenyPaths: string[],
+): string[] {
+  const overlaps = (a: string, b: string) =>
+    a === b || a.startsWith(b + '/') || b.startsWith(a + '/')
+
+  const args: string[] = []
+  for (const mount of mounts) {
+    const deniedPaths = mount.readOnly
+      ? readDenyPaths
+      : [...readDenyPaths, ...writeDenyPaths]
+    const deniedPath = deniedPaths
+      .map(deniedPath => normalizePathForSandbox(deniedPath))
+      .find(deniedPath => overlaps(mount.source, deniedPath))
+    if (deniedPath) {
+      throw new Error(
+        `Mount source ${mount.source} overlaps denied path ${deniedPath}`,
+      )
+    }
+    if (!fs.existsSync(mount.source)) {
+      throw new Error(`Mount source does not exist: ${mount.source}`)
+    }
+    args.push(
+      mount.readOnly ? '--ro-bind' : '--bind',
+      mount.source,
+      mount.destination,
+    )
+  }
+  return args
+}
+
+export const LANDLOCK_UNAVAILABLE_MESSAGE =
+  'linuxFilesystemBackend is "landlock", but Landlock is not available: it needs Linux 5.13+ ' +
+  'with Landlock enabled and the apply-landlock helper (built by scripts/build-seccomp-binaries.sh ' +
+  'into vendor/seccomp/{x64,arm64}/). Use "auto" to fall back to bwrap bind mounts.'
+
+/**
+ * Decide whether Landlock should enforce filesystem restrictions
+ * 'auto' falls back to bwrap bind mounts when the kernel or helper binary
+ * lacks support; an explicit 'landlock' throws, so it never runs with less
+ * than was asked for.
+ */
+function shouldUseLandlock(
+  filesystemBackend: LinuxFilesystemBackend | undefined,
+): boolean {
+  if (!filesystemBackend || filesystemBackend === 'bwrap') {
+    return false
+  }
+
+  if (getLandlockAbiVersion() > 0) {
+    return true
+  }
+
+  if (filesystemBackend === 'landlock') {
+    throw new Error(LANDLOCK_UNAVAILABLE_MESSAGE)
+  }
+  logForDebugging(
+    '[Sandbox Linux] Landlock is not supported here; enforcing filesystem rules with bwrap bind mounts only.',
+  )
+  return false
+}
+
+/**
+ * Convert filesystem restriction configs into Landlock rules
+ * Only existing paths are included, matching the bwrap bind mount behavior.
+ */
+function buildLandlockRules(
+  readConfig: FsReadRestrictionConfig | undefined,
+  writeConfig: FsWriteRestrictionConfig | undefined,
+  mandatoryDenyPaths: string[],
+): LandlockRules {
+  const existingPaths = (patterns: string[]) =>
+    patterns
+      .map(pathPattern => normalizePathForSandbox(pathPattern))
+      .filter(normalizedPath => fs.existsSync(normalizedPath))
+
+  return {
+    readDenyPaths: existingPaths(readConfig?.denyOnly || []),
+    writeAllowPaths: writeConfig
+      ? existingPaths(writeConfig.allowOnly || [])
+      : undefined,
+    writeDenyPaths: existingPaths([
+      ...(writeConfig?.denyWithinAllow || []),
+      ...mandatoryDenyPaths,
+    ]),
+  }
+}
+
+/**
+ * Resolve the full path of the user's shell (default: bash)
+ * The sandbox programs don't search $PATH for the command they exec.
+ */
+function resolveShellPath(binShell: string | undefined): string {
+  const shellName = binShell || 'bash'
+  const shellPathResult = spawnSync('which', [shellName], {
+    encoding: 'utf8',
+  })
+  if (shellPathResult.status !== 0) {
+    throw new Error(`Shell '${shellName}' not found in PATH`)
+  }
+  return shellPathResult.stdout.trim()
+}
+
+/**
+ * Generate the seccomp filter for a sandboxed command
+ * Returns no filter path when seccomp is disabled by config or unavailable on
+ * this architecture (unless syscalls were denied explicitly, which fails closed).
+ */
+function prepareSeccompFilter(
+  allowAllUnixSockets: boolean | undefined,
+  seccomp: SeccompConfig | undefined,
+): { filterPath?: string; profile: string; blockUnixSockets: boolean } {
+  // apply-seccomp loads a BPF filter that blocks Unix socket creation and any
+  // syscalls denied by the seccomp profile or deny list
+  //
+  // NOTE: Unix socket blocking is only enabled when allowAllUnixSockets is false
+  // (when true, Unix sockets are allowed) and the profile is not 'unconfined'
+  const seccompProfile = seccomp?.profile ?? 'default'
+  const denySyscalls: SyscallName[] = [
+    ...(seccompProfile === 'strict' ? STRICT_DENIED_SYSCALLS : []),
+    ...(seccomp?.deny ?? []),
+  ]
+  const blockUnixSockets =
+    !allowAllUnixSockets && seccompProfile !== 'unconfined'
+
+  let filterPath: string | undefined = undefined
+  if (blockUnixSockets || denySyscalls.length > 0) {
+    filterPath =
+      generateSeccompFilter({ blockUnixSockets, denySyscalls }) ?? undefined
+    if (!filterPath && denySyscalls.length > 0) {
+      // Denied syscalls were requested explicitly, so fail closed
+      throw new Error(
+        'Seccomp syscall filtering is configured but not available on this ' +
+          `architecture (${process.arch})`,
+      )
+    }
+    if (!filterPath) {
+      // Seccomp not available - log warning and continue without it
+      // This provides graceful degradation on systems without seccomp binaries
+      logForDebugging(
+        '[Sandbox Linux] Seccomp filter not available (missing binaries). ' +
+          'Continuing without Unix socket blocking - sandbox will still provide ' +
+          'filesystem and network isolation but Unix sockets will be allowed.',
+        { level: 'warn' },
+      )
+    } else {
+      // Track filter for cleanup and register exit handler
+      generatedSeccompFilters.add(filterPath)
+      registerSeccompCleanupHandler()
+
+      logForDebugging(
+        `[Sandbox Linux] Generated seccomp BPF filter (profile: ${seccompProfile}, ` +
+          `unix sockets blocked: ${blockUnixSockets}, denied syscalls: ${denySyscalls.length})`,
+      )
+    }
+  } else {
+    logForDebugging(
+      '[Sandbox Linux] Skipping seccomp filter - allowAllUnixSockets is enabled ' +
+        `or profile is unconfined (profile: ${seccompProfile})`,
+    )
+  }
+
+  return { filterPath, profile: seccompProfile, blockUnixSockets }
+}
+
 /**
  * Wrap a command with sandbox restrictions on Linux
  *
@@ -623,6 +1058,15 @@ async function generateFilesystemArgs(
  * - To use sandboxing without Unix socket blocking on unsupported architectures,
  *   set allowAllUnixSockets: true in your configuration
  * Dependencies are checked by hasLinuxSandboxDependenciesSync() before enabling the sandbox.
+ *
+ * LANDLOCK (OPTIONAL):
+ * With filesystemBackend 'landlock' or 'auto', apply-landlock runs right before the user
+ * command (ahead of apply-seccomp) and enforces the read/write rules with the Landlock LSM.
+ * - 'landlock': bwrap binds / read-write and only provides namespaces; Landlock enforces
+ *   all filesystem rules, independent of the bind-mount topology
+ * - 'auto': Landlock is stacked on top of the bwrap bind mounts as a second layer
+ * 'auto' falls back to bwrap-only when Landlock is unsupported (kernel < 5.13 or
+ * no apply-landlock binary); 'landlock' fails instead.
  */
 export async function wrapCommandWithSandboxLinux(
   params: LinuxSandboxParams,
@@ -643,6 +1087,23 @@ export async function wrapCommandWithSandboxLinux(
     mandatoryDenySearchDepth = DEFAULT_MANDATORY_DENY_SEARCH_DEPTH,
     allowGitConfig = false,
     abortSignal,
+    resourceLimits,
+    filesystemBackend,
+    userMapping,
+    seccomp,
+    scratchDir,
+    backend = 'bwrap',
+    allowSensitiveProcEntries = false,
+    capabilities = [],
+    tmpfsSizeMB,
+    extraTmpfs = [],
+    mounts = [],
+    desktop,
+    dbusProxySocketPath,
+    violationTracePath,
+    auditFileAccess = false,
+    statusFd,
+    environment,
   } = params
 
   // Determine if we have restrictions to apply
@@ -661,42 +1122,21 @@ export async function wrapCommandWithSandboxLinux(
   }
 
   const bwrapArgs: string[] = ['--new-session', '--die-with-parent']
+  if (statusFd !== undefined && backend !== 'nsjail') {
+    bwrapArgs.push('--json-status-fd', String(statusFd))
+  }
   let seccompFilterPath: string | undefined = undefined
 
   try {
-    // ========== SECCOMP FILTER (Unix Socket Blocking) ==========
-    // Use bwrap's --seccomp flag to apply BPF filter that blocks Unix socket creation
-    //
-    // NOTE: Seccomp filtering is only enabled when allowAllUnixSockets is false
-    // (when true, Unix sockets are allowed)
-    if (!allowAllUnixSockets) {
-      seccompFilterPath = generateSeccompFilter() ?? undefined
-      if (!seccompFilterPath) {
-        // Seccomp not available - log warning and continue without it
-        // This provides graceful degradation on systems without seccomp binaries
-        logForDebugging(
-          '[Sandbox Linux] Seccomp filter not available (missing binaries). ' +
-            'Continuing without Unix socket blocking - sandbox will still provide ' +
-            'filesystem and network isolation but Unix sockets will be allowed.',
-          { level: 'warn' },
-        )
-      } else {
-        // Track filter for cleanup and register exit handler
-        // Only track runtime-generated filters (not pre-generated ones from vendor/)
-        if (!seccompFilterPath.includes('/vendor/seccomp/')) {
-          generatedSeccompFilters.add(seccompFilterPath)
-          registerSeccompCleanupHandler()
-        }
+    // ========== SECCOMP FILTER (Unix Socket Blocking + Denied Syscalls) ==========
+    const {
+      filterPath,
+      profile: seccompProfile,
+      blockUnixSockets,
+    } = prepareSeccompFilter(allowAllUnixSockets, seccomp)
+    seccompFilterPath = filterPath
 
-        logForDebugging(
-          '[Sandbox Linux] Generated seccomp BPF filter for Unix socket blocking',
-        )
-      }
-    } else if (allowAllUnixSockets) {
-      logForDebugging(
-        '[Sandbox Linux] Skipping seccomp filter - allowAllUnixSockets is enabled',
-      )
-    }
+    const desktopIsolation = buildDesktopIsolation(desktop, dbusProxySocketPath)
 
     // ========== NETWORK RESTRICTIONS ==========
     if (needsNetworkRestriction) {
@@ -762,20 +1202,110 @@ export async function wrapCommandWithSandboxLinux(
       // If no sockets provided, network is completely blocked (--unshare-net without proxy)
     }
 
+    // ========== SCRATCH DIRECTORY ==========
+    // The scratch directory is on the write allowlist; point temp files at it
+    if (scratchDir) {
+      bwrapArgs.push('--setenv', 'TMPDIR', scratchDir)
+      bwrapArgs.push('--setenv', 'SRT_SCRATCH_DIR', scratchDir)
+    }
+
     // ========== FILESYSTEM RESTRICTIONS ==========
-    const fsArgs = await generateFilesystemArgs(
-      readConfig,
-      writeConfig,
-      ripgrepConfig,
-      mandatoryDenySearchDepth,
-      allowGitConfig,
-      abortSignal,
+    const mandatoryDenyPaths = hasWriteRestrictions
+      ? await linuxGetMandatoryDenyPaths(
+          ripgrepConfig,
+          mandatoryDenySearchDepth,
+          allowGitConfig,
+          abortSignal,
+        )
+      : []
+
+    const useLandlock =
+      (hasReadRestrictions || hasWriteRestrictions) &&
+      shouldUseLandlock(filesystemBackend)
+
+    if (useLandlock && filesystemBackend === 'landlock') {
+      // Landlock enforces the rules, bwrap only provides the namespaces
+      bwrapArgs.push('--bind', '/', '/')
+    } else {
+      bwrapArgs.push(
+        ...generateFilesystemArgs(
+          readConfig,
+          writeConfig,
+          mandatoryDenyPaths,
+          tmpfsSizeMB,
+        ),
+      )
+    }
+
+    // Extra tmpfs mounts go on top, so they stay writable whatever the binds say
+    const tmpfsDirs = resolveExtraTmpfs(extraTmpfs)
+    bwrapArgs.push(...generateExtraTmpfsArgs(tmpfsDirs, tmpfsSizeMB))
+
+    // Remapped mounts come last so they can land inside an extra tmpfs
+    const bindMounts = mounts.map(parseMountSpec)
+    bwrapArgs.push(
+      ...generateMountArgs(bindMounts, readConfig?.denyOnly ?? [], [
+        ...(writeConfig?.denyWithinAllow ?? []),
+        ...mandatoryDenyPaths,
+      ]),
     )
-    bwrapArgs.push(...fsArgs)
+
+    // ========== VIOLATION TRACING ==========
+    // strace runs inside the sandbox and writes its trace files to a host
+    // directory of the command's own, so it can't touch other commands' traces
+    const tracePrefix = violationTracePath
+      ? buildStracePrefix(violationTracePath, auditFileAccess)
+      : []
+    if (violationTracePath) {
+      const traceDir = path.dirname(violationTracePath)
+      bwrapArgs.push('--bind', traceDir, traceDir)
+    }
+
+    // ========== DESKTOP SESSION ==========
+    // Hide X11, Wayland and the session bus unless they are explicitly exposed
+    bwrapArgs.push(...desktopIsolation.bwrapArgs)
+
+    // Landlock must also allow writes to the extra tmpfs and writable mounts
+    const extraWritePaths = [
+      ...tmpfsDirs,
+      ...bindMounts.filter(m => !m.readOnly).map(m => m.destination),
+    ]
+    const landlockWriteConfig =
+      writeConfig && extraWritePaths.length > 0
+        ? {
+            ...writeConfig,
+            allowOnly: [...writeConfig.allowOnly, ...extraWritePaths],
+          }
+        : writeConfig
+    const landlockPrefix = useLandlock
+      ? buildLandlockCommandPrefix(
+          buildLandlockRules(
+            readConfig,
+            landlockWriteConfig,
+            mandatoryDenyPaths,
+          ),
+        )
+      : []
+    // strace goes first so it traces the Landlock and seccomp helpers' exec too
+    const execPrefix = [...tracePrefix, ...landlockPrefix]
 
     // Always bind /dev
     bwrapArgs.push('--dev', '/dev')
 
+    // ========== USER NAMESPACE MAPPING ==========
+    // Gives the sandboxed process a predictable uid/gid. Files it creates on bind-mounted
+    // paths are still owned by the invoking user on the host (the only mapped outer id).
+    const userNamespaceArgs = buildUserNamespaceArgs(userMapping)
+    bwrapArgs.push(...userNamespaceArgs)
+
+    // ========== CAPABILITIES ==========
+    // Drop every capability, then add back only the configured ones, so code
+    // running as root inside the user namespace can't use the rest
+    bwrapArgs.push('--cap-drop', 'ALL')
+    for (const capability of capabilities) {
+      bwrapArgs.push('--cap-add', capability)
+    }
+
     // ========== PID NAMESPACE ISOLATION ==========
     // IMPORTANT: These must come AFTER filesystem binds for nested bwrap to work
     // By default, always unshare PID namespace and mount fresh /proc.
@@ -787,19 +1317,21 @@ export async function wrapCommandWithSandboxLinux(
     if (!enableWeakerNestedSandbox) {
       // Mount fresh /proc if PID namespace is isolated (secure mode)
       bwrapArgs.push('--proc', '/proc')
+
+      // Mask entries of the fresh /proc that still describe the whole host
+      if (!allowSensitiveProcEntries) {
+        for (const procPath of MASKED_PROC_PATHS) {
+          if (fs.existsSync(procPath)) {
+            bwrapArgs.push('--ro-bind', '/dev/null', procPath)
+          }
+        }
+      }
     }
 
     // ========== COMMAND ==========
     // Use the user's shell (zsh, bash, etc.) to ensure aliases/snapshots work
     // Resolve the full path to the shell binary since bwrap doesn't use $PATH
-    const shellName = binShell || 'bash'
-    const shellPathResult = spawnSync('which', [shellName], {
-      encoding: 'utf8',
-    })
-    if (shellPathResult.status !== 0) {
-      throw new Error(`Shell '${shellName}' not found in PATH`)
-    }
-    const shell = shellPathResult.stdout.trim()
+    const shell = resolveShellPath(binShell)
     bwrapArgs.push('--', shell, '-c')
 
     // If we have network restrictions, use the network bridge setup with apply-seccomp for seccomp
@@ -812,6 +1344,7 @@ export async function wrapCommandWithSandboxLinux(
         socksSocketPath,
         command,
         seccompFilterPath,
+        execPrefix,
         shell,
       )
       bwrapArgs.push(sandboxCommand)
@@ -827,6 +1360,7 @@ export async function wrapCommandWithSandboxLinux(
       }
 
       const applySeccompCmd = shellquote.quote([
+        ...execPrefix,
         applySeccompBinary,
         seccompFilterPath,
         shell,
@@ -834,21 +1368,66 @@ export async function wrapCommandWithSandboxLinux(
         command,
       ])
       bwrapArgs.push(applySeccompCmd)
+    } else if (execPrefix.length > 0) {
+      // No seccomp - apply-landlock and/or strace exec the command
+      bwrapArgs.push(shellquote.quote([...execPrefix, shell, '-c', command]))
     } else {
       bwrapArgs.push(command)
     }
 
-    // Build the outer bwrap command
-    const wrappedCommand = shellquote.quote(['bwrap', ...bwrapArgs])
+    // ========== RESOURCE LIMITS ==========
+    // Run bwrap inside a transient cgroup scope so limits cover the whole process tree
+    // Limits are only ever set explicitly, so refuse to run without them
+    const resourceLimitArgs = buildResourceLimitArgs(resourceLimits)
+    if (resourceLimitArgs.length > 0 && !hasCgroupResourceControlSync()) {
+      throw new Error(
+        'Resource limits are configured but cgroup v2 or systemd-run is not available. ' +
+          'Remove the resources setting to run without CPU, memory and pids limits ' +
+          '(run `srt doctor` for details).',
+      )
+    }
+
+    // Build the outer bwrap (or equivalent nsjail) command
+    const sandboxArgs =
+      backend === 'nsjail'
+        ? ['nsjail', ...bwrapArgsToNsjailArgs(bwrapArgs)]
+        : ['bwrap', ...bwrapArgs]
+    // Variables are removed before the sandbox starts, which works for both
+    // backends. Only names are passed: values the env policy sets come from
+    // the environment the command is spawned with.
+    const unsetEnvNames = [
+      ...new Set([
+        ...desktopIsolation.unsetEnv,
+        ...(environment ? getUnsetEnvNames(environment) : []),
+      ]),
+    ]
+    const unsetEnvArgs =
+      unsetEnvNames.length > 0
+        ? ['env', ...unsetEnvNames.flatMap(name => ['-u', name])]
+        : []
+    const wrappedCommand = shellquote.quote([
+      ...resourceLimitArgs,
+      ...unsetEnvArgs,
+      ...sandboxArgs,
+    ])
 
     const restrictions = []
     if (needsNetworkRestriction) restrictions.push('network')
     if (hasReadRestrictions || hasWriteRestrictions)
       restrictions.push('filesystem')
-    if (seccompFilterPath) restrictions.push('seccomp(unix-block)')
+    if (landlockPrefix.length > 0)
+      restrictions.push(`landlock(abi ${getLandlockAbiVersion()})`)
+    if (seccompFilterPath)
+      restrictions.push(
+        blockUnixSockets
+          ? `seccomp(unix-block, ${seccompProfile})`
+          : `seccomp(${seccompProfile})`,
+      )
+    if (resourceLimitArgs.length > 0) restrictions.push('cgroup(resources)')
+    if (userNamespaceArgs.length > 0) restrictions.push('userns')
 
     logForDebugging(
-      `[Sandbox Linux] Wrapped command with bwrap (${restrictions.join(', ')} restrictions)`,
+      `[Sandbox Linux] Wrapped command with ${backend} (${restrictions.join(', ')} restrictions)`,
     )
 
     return wrappedCommand
@@ -869,3 +1448,160 @@ export async function wrapCommandWithSandboxLinux(
     throw error
   }
 }
+
+/**
+ * Environment variables removed from the environment of commands run in the
+ * degraded sandbox, which can't hide the host's agent sockets or credentials
+ */
+const SCRUBBED_ENV_VAR_NAMES = [
+  'SSH_AUTH_SOCK',
+  'GPG_AGENT_INFO',
+  'DBUS_SESSION_BUS_ADDRESS',
+]
+
+/**
+ * Names of the variables in env that the degraded sandbox scrubs
+ */
+export function getScrubbedEnvVarNames(
+  env: NodeJS.ProcessEnv = process.env,
+): string[] {
+  return Object.keys(env).filter(
+    name =>
+      SCRUBBED_ENV_VAR_NAMES.includes(name) ||
+      CREDENTIAL_ENV_VAR_PATTERN.test(name),
+  )
+}
+
+/**
+ * Build the shell ulimit commands that approximate resource limits with rlimits
+ * Core dumps are always disabled. memoryMb caps the virtual address space,
+ * which runtimes such as Node and the JVM reserve far beyond what they use.
+ * There is no rlimit for a CPU share, and RLIMIT_NPROC counts every process
+ * of the user, so cpus and pids are only enforced by a cgroup scope.
+ */
+export function buildRlimitCommands(
+  resourceLimits: ResourceLimitsConfig | undefined,
+): string[] {
+  const commands = ['ulimit -c 0']
+  if (resourceLimits?.memoryMb !== undefined) {
+    commands.push(`ulimit -v ${resourceLimits.memoryMb * 1024}`)
+  }
+  return commands
+}
+
+/**
+ * Wrap a command in the degraded Linux sandbox (allowDegradedSandbox)
+ *
+ * Used when bwrap/nsjail or socat is missing. Without namespaces there is no
+ * filesystem isolation and network access is not enforced; the command gets:
+ * - the seccomp filter (Unix socket blocking and denied syscalls) via apply-seccomp
+ * - the cgroup resource limits when systemd-run can create a scope, rlimits
+ *   otherwise
+ * - an environment scrubbed of credentials and agent sockets
+ * - proxy environment variables pointing at the host proxies, so tools that
+ *   honor them are still filtered
+ */
+export async function wrapCommandWithDegradedSandboxLinux(
+  params: LinuxSandboxParams,
+): Promise<string> {
+  const {
+    command,
+    needsNetworkRestriction,
+    httpProxyPort,
+    socksProxyPort,
+    readConfig,
+    writeConfig,
+    allowAllUnixSockets,
+    binShell,
+    resourceLimits,
+    seccomp,
+    scratchDir,
+    environment,
+  } = params
+
+  const hasReadRestrictions = readConfig && readConfig.denyOnly.length > 0
+  const hasWriteRestrictions = writeConfig !== undefined
+
+  if (
+    !needsNetworkRestriction &&
+    !hasReadRestrictions &&
+    !hasWriteRestrictions
+  ) {
+    return command
+  }
+
+  const { filterPath, profile } = prepareSeccompFilter(
+    allowAllUnixSockets,
+    seccomp,
+  )
+  const shell = resolveShellPath(binShell)
+
+  // Setuid binaries must not elevate: apply-seccomp sets PR_SET_NO_NEW_PRIVS,
+  // otherwise setpriv does (bwrap always sets it for the full sandbox)
+  let innerCommand = [shell, '-c', command]
+  if (filterPath) {
+    const applySeccompBinary = getApplySeccompBinaryPath()
+    if (!applySeccompBinary) {
+      throw new Error(
+        'apply-seccomp binary not found. ' +
+          'Ensure vendor/seccomp/{x64,arm64}/apply-seccomp binaries are included in the package.',
+      )
+    }
+    innerCommand = [applySeccompBinary, filterPath, ...innerCommand]
+  } else if (
+    spawnSync('which', ['setpriv'], { stdio: 'ignore' }).status === 0
+  ) {
+    innerCommand = ['setpriv', '--no-new-privs', ...innerCommand]
+  } else {
+    throw new Error(
+      'The degraded sandbox cannot set no_new_privs without a seccomp filter. ' +
+        'Install setpriv (util-linux) or enable the seccomp filter.',
+    )
+  }
+  // A cgroup scope does not need bwrap, so use it when the host has one
+  let resourceLimitArgs = buildResourceLimitArgs(resourceLimits)
+  if (resourceLimitArgs.length > 0 && !hasCgroupResourceControlSync()) {
+    resourceLimitArgs = []
+  }
+  const script = [
+    ...buildRlimitCommands(
+      resourceLimitArgs.length > 0 ? undefined : resourceLimits,
+    ),
+    `exec ${shellquote.quote(innerCommand)}`,
+  ].join(' && ')
+
+  // Agent sockets stay hidden even if the env policy would pass them through
+  const unsetEnvNames = environment
+    ? [
+        ...new Set([
+          ...getUnsetEnvNames(environment),
+          ...SCRUBBED_ENV_VAR_NAMES.filter(name => name in environment),
+        ]),
+      ]
+    : getScrubbedEnvVarNames()
+  const envArgs = [
+    ...unsetEnvNames.flatMap(name => ['-u', name]),
+    ...(needsNetworkRestriction
+      ? generateProxyEnvVars(httpProxyPort, socksProxyPort)
+      : []),
+    ...(scratchDir
+      ? [`TMPDIR=${scratchDir}`, `SRT_SCRATCH_DIR=${scratchDir}`]
+      : []),
+  ]
+
+  logForDebugging(
+    `[Sandbox Linux] Wrapped command in DEGRADED sandbox (${filterPath ? `seccomp(${profile}), ` : ''}` +
+      `${resourceLimitArgs.length > 0 ? 'cgroup scope' : 'rlimits'}, env scrubbing, proxy env). ` +
+      'Filesystem and network are NOT isolated.',
+    { level: 'warn' },
+  )
+
+  return shellquote.quote([
+    ...resourceLimitArgs,
+    'env',
+    ...envArgs,
+    shell,
+    '-c',
+    script,
+  ])
+}
@@ -0,0 +1,539 @@
+import { spawnSync } from 'node:child_process'
+import * as fs from 'node:fs'
+import { tmpdir } from 'node:os'
+import { join } from 'node:path'
+import { logForDebugging } from '../utils/debug.js'
+import type {
+  SandboxViolationCallback,
+  SandboxViolationEvent,
+  ViolationProcess,
+} from './macos-sandbox-utils.js'
+import type { IgnoreViolationsConfig } from './sandbox-config.js'
+import { encodeSandboxedCommand } from './sandbox-utils.js'
+import { isViolationIgnored } from './violation-filter.js'
+
+/**
+ * Violation monitoring for the Linux sandbox
+ *
+ * Linux has no equivalent of the macOS sandbox log, so each sandboxed command
+ * runs under strace, which writes one trace file per process into a directory
+ * of its own, the only one bound into its sandbox; the directory is deleted
+ * once the command has exited. Only failed file and network syscalls are
+ * traced, and
+ * the host polls the files for the ones that failed because of the sandbox
+ * (EACCES, EPERM, EROFS, and ENETUNREACH for connects). The trace directory is
+ * writable from inside the sandbox, so a hostile command can add or hide
+ * entries; the monitor is a debugging aid, not an audit log.
+ *
+ * In audit mode successful syscalls are traced too, and every file the command
+ * opens, executes or modifies is reported as an allowed access, to a callback
+ * of its own rather than with the denials. Successful
+ * execs and forks then also give each event the executable, argv and parent
+ * chain of its process; otherwise only the pid is known. fanotify and
+ * auditd would need CAP_SYS_ADMIN or root on the host, and auditd rules cannot
+ * be scoped to one process tree, so the unprivileged sandbox uses strace here
+ * as well.
+ */
+
+const POLL_INTERVAL_MS = 500
+
+/** errno values that mean the sandbox refused the access */
+const DENIED_ERRNOS = new Set(['EACCES', 'EPERM', 'EROFS'])
+
+/** Outbound traffic fails with ENETUNREACH in the isolated network namespace */
+const NETWORK_SYSCALLS = new Set(['connect', 'sendto', 'sendmsg'])
+
+const STRACE_LINE_REGEX = /^(\w+)\((.*)\)\s+=\s+-1\s+(E[A-Z]+)\b/
+
+const STRACE_SUCCESS_REGEX = /^(\w+)\((.*)\)\s+=\s+(\d+)\b/
+
+/** Syscalls that open or execute a file; open flags decide read or write */
+const OPEN_SYSCALLS = new Set(['open', 'openat', 'openat2', 'creat'])
+const EXEC_SYSCALLS = new Set(['execve', 'execveat'])
+
+/** Syscalls that modify the filesystem */
+const WRITE_SYSCALLS = new Set([
+  'mkdir',
+  'mkdirat',
+  'mknod',
+  'mknodat',
+  'rmdir',
+  'unlink',
+  'unlinkat',
+  'rename',
+  'renameat',
+  'renameat2',
+  'link',
+  'linkat',
+  'symlink',
+  'symlinkat',
+  'truncate',
+  'chmod',
+  'fchmodat',
+  'chown',
+  'lchown',
+  'fchownat',
+  'utimensat',
+  'setxattr',
+  'lsetxattr',
+  'removexattr',
+  'lremovexattr',
+])
+
+const OPEN_WRITE_FLAGS = /\b(?:O_WRONLY|O_RDWR|O_CREAT|O_TRUNC|O_APPEND)\b/
+
+/** Syscalls that create a process; their result is the child pid */
+const FORK_SYSCALLS = new Set(['clone', 'clone3', 'fork', 'vfork'])
+
+/** Parents listed per event, to bound the walk */
+const MAX_PARENTS = 16
+
+/** strace writes <command dir>/trace.<pid> */
+const TRACE_FILE_PREFIX = 'trace'
+
+export interface StraceDenial {
+  syscall: string
+  /** Path, socket path or address the syscall was denied on */
+  target: string
+  errno: string
+  /** Whether a denied file syscall would have read or modified the file */
+  access?: 'read' | 'write'
+}
+
+export interface StraceFileAccess {
+  syscall: string
+  path: string
+  access: 'read' | 'write'
+}
+
+export type StraceProcessEvent =
+  | { kind: 'exec'; executable: string; argv: string[] }
+  | { kind: 'fork'; child: number }
+
+interface TracedProcess {
+  parent?: number
+  executable?: string
+  argv?: string[]
+}
+
+/**
+ * strace arguments that run a command with its failed file and network syscalls
+ * written to `<outputPrefix>.<pid>`, one file per process. In audit mode the
+ * successful ones are written as well.
+ */
+export function buildStracePrefix(
+  outputPrefix: string,
+  audit = false,
+): string[] {
+  return [
+    'strace',
+    '-ff',
+    '-qq',
+    '-s',
+    '4096',
+    '-e',
+    audit ? 'trace=%file,%network,%process' : 'trace=%file,%network',
+    ...(audit ? [] : ['-e', 'status=failed']),
+    '-o',
+    outputPrefix,
+  ]
+}
+
+/**
+ * Parse one strace line, returning it only if the sandbox denied the syscall
+ */
+export function parseStraceLine(line: string): StraceDenial | undefined {
+  const match = STRACE_LINE_REGEX.exec(line.trim())
+  if (!match) {
+    return undefined
+  }
+  const [, syscall, args, errno] = match as unknown as [
+    string,
+    string,
+    string,
+    string,
+  ]
+
+  const isNetworkDenial =
+    errno === 'ENETUNREACH' && NETWORK_SYSCALLS.has(syscall)
+  if (!DENIED_ERRNOS.has(errno) && !isNetworkDenial) {
+    return undefined
+  }
+
+  // File syscalls and AF_UNIX addresses carry the path as the first string;
+  // inet addresses are reported as address:port
+  const quoted = /"((?:[^"\\]|\\.)*)"/.exec(args)?.[1]
+  const inetAddr = /inet_(?:addr|pton)\((?:AF_INET6?, )?"([^"]+)"/.exec(args)
+  const port = /htons\((\d+)\)/.exec(args)?.[1]
+  let target: string
+  if (inetAddr?.[1]) {
+    target = port ? `${inetAddr[1]}:${port}` : inetAddr[1]
+  } else if (quoted !== undefined) {
+    target = quoted
+  } else {
+    target = args.split(',')[0]?.trim() ?? ''
+  }
+
+  return { syscall, target, errno, access: getFileAccess(syscall, args) }
+}
+
+/**
+ * Whether a file syscall reads or modifies its file, or undefined for
+ * syscalls that are not file accesses
+ */
+function getFileAccess(
+  syscall: string,
+  args: string,
+): 'read' | 'write' | undefined {
+  if (OPEN_SYSCALLS.has(syscall)) {
+    return syscall === 'creat' || OPEN_WRITE_FLAGS.test(args)
+      ? 'write'
+      : 'read'
+  }
+  if (EXEC_SYSCALLS.has(syscall)) {
+    return 'read'
+  }
+  if (WRITE_SYSCALLS.has(syscall)) {
+    return 'write'
+  }
+  return undefined
+}
+
+/**
+ * Parse one strace line, returning it only if it is a successful open, exec or
+ * filesystem modification
+ */
+export function parseStraceFileAccess(
+  line: string,
+): StraceFileAccess | undefined {
+  const match = STRACE_SUCCESS_REGEX.exec(line.trim())
+  if (!match) {
+    return undefined
+  }
+  const [, syscall, args] = match as unknown as [string, string, string]
+
+  const access = getFileAccess(syscall, args)
+  if (!access) {
+    return undefined
+  }
+
+  const path = /"((?:[^"\\]|\\.)*)"/.exec(args)?.[1]
+  if (path === undefined) {
+    return undefined
+  }
+  return { syscall, path, access }
+}
+
+/**
+ * Parse one strace line, returning it only if it is a successful exec (with
+ * the new executable and argv) or fork (with the child pid)
+ */
+export function parseStraceProcessEvent(
+  line: string,
+): StraceProcessEvent | undefined {
+  const match = STRACE_SUCCESS_REGEX.exec(line.trim())
+  if (!match) {
+    return undefined
+  }
+  const [, syscall, args, result] = match as unknown as [
+    string,
+    string,
+    string,
+    string,
+  ]
+
+  if (FORK_SYSCALLS.has(syscall)) {
+    const child = Number.parseInt(result, 10)
+    return child > 0 ? { kind: 'fork', child } : undefined
+  }
+  if (!EXEC_SYSCALLS.has(syscall)) {
+    return undefined
+  }
+  const executable = /"((?:[^"\\]|\\.)*)"/.exec(args)?.[1]
+  const argvList = /\[((?:"(?:[^"\\]|\\.)*"(?:\.\.\.)?(?:, )?)*)\]/.exec(
+    args,
+  )?.[1]
+  if (executable === undefined) {
+    return undefined
+  }
+  const argv = [...(argvList ?? '').matchAll(/"((?:[^"\\]|\\.)*)"/g)].map(
+    ([, arg]) => arg ?? '',
+  )
+  return { kind: 'exec', executable, argv }
+}
+
+export class LinuxViolationMonitor {
+  /** Commands being traced, by the name of their trace directory */
+  private readonly commands = new Map<string, string>()
+  /** Processes seen in audit traces, by trace name (<id>.<pid>) */
+  private readonly processes = new Map<string, TracedProcess>()
+  private readonly offsets = new Map<string, number>()
+  private nextId = 0
+  private readonly timer: NodeJS.Timeout
+
+  private readonly audit: boolean
+
+  private constructor(
+    readonly traceDir: string,
+    private readonly callback: SandboxViolationCallback,
+    private readonly ignoreViolations: IgnoreViolationsConfig | undefined,
+    private readonly onFileAccess: SandboxViolationCallback | undefined,
+  ) {
+    this.audit = onFileAccess !== undefined
+    this.timer = setInterval(() => this.poll(), POLL_INTERVAL_MS)
+    this.timer.unref()
+  }
+
+  /**
+   * Start monitoring, or return undefined when strace is not installed.
+   * With onFileAccess set, allowed file accesses are traced too and reported
+   * to it, apart from the denials.
+   */
+  static start(
+    callback: SandboxViolationCallback,
+    ignoreViolations?: IgnoreViolationsConfig,
+    onFileAccess?: SandboxViolationCallback,
+  ): LinuxViolationMonitor | undefined {
+    if (spawnSync('which', ['strace'], { stdio: 'ignore' }).status !== 0) {
+      logForDebugging(
+        '[Sandbox Monitor] strace is not installed; Linux violations will not be reported',
+        { level: 'warn' },
+      )
+      return undefined
+    }
+    const traceDir = fs.mkdtempSync(join(tmpdir(), 'srt-trace-'))
+    return new LinuxViolationMonitor(
+      traceDir,
+      callback,
+      ignoreViolations,
+      onFileAccess,
+    )
+  }
+
+  /**
+   * Register a command about to be wrapped and return its strace output
+   * prefix, in a directory of its own that the sandbox binds
+   */
+  registerCommand(command: string): string | undefined {
+    const id = `cmd${this.nextId++}`
+    const dir = join(this.traceDir, id)
+    try {
+      fs.mkdirSync(dir, { mode: 0o700 })
+    } catch (error) {
+      logForDebugging(
+        `[Sandbox Monitor] Cannot create trace directory ${dir}: ${error}`,
+        { level: 'warn' },
+      )
+      return undefined
+    }
+    this.commands.set(id, command)
+    return join(dir, TRACE_FILE_PREFIX)
+  }
+
+  /**
+   * Report the rest of the traces of the commands wrapped into a command
+   * that has exited, then delete them
+   */
+  releaseCommand(wrappedCommand: string): void {
+    const ids = [...this.commands.keys()].filter(id =>
+      wrappedCommand.includes(join(this.traceDir, id, TRACE_FILE_PREFIX)),
+    )
+    if (ids.length === 0) {
+      return
+    }
+    this.poll()
+    for (const id of ids) {
+      this.commands.delete(id)
+      for (const map of [this.processes, this.offsets]) {
+        for (const key of map.keys()) {
+          if (key.startsWith(`${id}.`)) {
+            map.delete(key)
+          }
+        }
+      }
+      fs.rmSync(join(this.traceDir, id), { recursive: true, force: true })
+    }
+  }
+
+  /**
+   * Read the new lines of every trace file and report the denials, and the
+   * allowed file accesses in audit mode
+   */
+  poll(): void {
+    const traces: {
+      entry: string
+      command: string
+      pid: number
+      lines: string[]
+    }[] = []
+    for (const [id, command] of this.commands) {
+      let files: string[]
+      try {
+        files = fs.readdirSync(join(this.traceDir, id))
+      } catch {
+        continue
+      }
+      for (const file of files) {
+        const [prefix, pidText] = file.split('.')
+        if (prefix !== TRACE_FILE_PREFIX || !pidText) {
+          continue
+        }
+        const pid = Number.parseInt(pidText, 10)
+        const entry = `${id}.${pid}`
+        const lines = this.readNewLines(entry, join(this.traceDir, id, file))
+        traces.push({ entry, command, pid, lines })
+      }
+    }
+
+    // Record execs and forks first, so attribution does not depend on the
+    // order the trace files were read in
+    if (this.audit) {
+      for (const { entry, lines } of traces) {
+        for (const line of lines) {
+          this.recordProcessEvent(entry, line)
+        }
+      }
+    }
+
+    for (const { entry, command, pid, lines } of traces) {
+      const attribution = this.audit ? this.getAttribution(entry) : {}
+      for (const line of lines) {
+        const denial = parseStraceLine(line)
+        if (!denial) {
+          const fileAccess = this.onFileAccess
+            ? parseStraceFileAccess(line)
+            : undefined
+          if (fileAccess) {
+            this.onFileAccess?.({
+              line: `allow(${fileAccess.access}) ${fileAccess.syscall} ${fileAccess.path}`,
+              command,
+              encodedCommand: encodeSandboxedCommand(command),
+              timestamp: new Date(),
+              type: 'file-access',
+              access: fileAccess.access,
+              pid,
+              ...attribution,
+              path: fileAccess.path,
+            })
+          }
+          continue
+        }
+        const details = `deny(${denial.errno}) ${denial.syscall} ${denial.target}`
+        if (
+          isViolationIgnored(
+            { line: details, command, path: denial.target },
+            this.ignoreViolations,
+          )
+        ) {
+          continue
+        }
+        this.callback({
+          line: details,
+          command,
+          encodedCommand: encodeSandboxedCommand(command),
+          timestamp: new Date(),
+          pid,
+          ...attribution,
+          path: denial.target,
+          access: denial.access,
+        })
+      }
+    }
+  }
+
+  stop(): void {
+    logForDebugging('[Sandbox Monitor] Stopping Linux violation monitor')
+    clearInterval(this.timer)
+    this.poll()
+    fs.rmSync(this.traceDir, { recursive: true, force: true })
+  }
+
+  /**
+   * Track the executable and parent of each traced process
+   */
+  private recordProcessEvent(entry: string, line: string): void {
+    const event = parseStraceProcessEvent(line)
+    if (!event) {
+      return
+    }
+    if (event.kind === 'exec') {
+      const traced = this.processes.get(entry) ?? {}
+      traced.executable = event.executable
+      traced.argv = event.argv
+      this.processes.set(entry, traced)
+      return
+    }
+    // A forked child runs its parent's program until it execs
+    // (the child's own exec may already have been recorded)
+    const [id, pidText] = entry.split('.')
+    const parent = this.processes.get(entry)
+    const childEntry = `${id}.${event.child}`
+    const child = this.processes.get(childEntry)
+    this.processes.set(childEntry, {
+      parent: Number.parseInt(pidText ?? '', 10),
+      executable: child?.executable ?? parent?.executable,
+      argv: child?.argv ?? parent?.argv,
+    })
+  }
+
+  /**
+   * The executable, argv and parent chain of a traced process
+   */
+  private getAttribution(
+    entry: string,
+  ): Pick<
+    SandboxViolationEvent,
+    'processName' | 'executable' | 'argv' | 'parents'
+  > {
+    const traced = this.processes.get(entry)
+    if (!traced) {
+      return {}
+    }
+    const [id] = entry.split('.')
+    const parents: ViolationProcess[] = []
+    let parentPid = traced.parent
+    while (parentPid !== undefined && parents.length < MAX_PARENTS) {
+      const parent = this.processes.get(`${id}.${parentPid}`)
+      parents.push({
+        pid: parentPid,
+        executable: parent?.executable,
+        argv: parent?.argv,
+      })
+      parentPid = parent?.parent
+    }
+    return {
+      processName: traced.executable?.split('/').pop(),
+      executable: traced.executable,
+      argv: traced.argv,
+      parents,
+    }
+  }
+
+  /**
+   * Complete lines appended since the last poll; a trailing partial line is
+   * left for the next one
+   */
+  private readNewLines(entry: string, file: string): string[] {
+    const offset = this.offsets.get(entry) ?? 0
+    let data: Buffer
+    try {
+      const fd = fs.openSync(file, 'r')
+      try {
+        const size = fs.fstatSync(fd).size
+        data = Buffer.alloc(Math.max(0, size - offset))
+        fs.readSync(fd, data, 0, data.length, offset)
+      } finally {
+        fs.closeSync(fd)
+      }
+    } catch {
+      return []
+    }
+
+    const end = data.lastIndexOf('\n')
+    if (end === -1) {
+      return []
+    }
+    this.offsets.set(entry, offset + end + 1)
+    return data.subarray(0, end).toString('utf8').split('\n')
+  }
+}
@@ -0,0 +1,261 @@
+import { spawn, spawnSync } from 'node:child_process'
+import { readFileSync } from 'node:fs'
+import { logForDebugging } from '../utils/debug.js'
+import {
+  findSessionLogTag,
+  type SandboxViolationCallback,
+} from './macos-sandbox-utils.js'
+import { decodeSandboxedCommand } from './sandbox-utils.js'
+
+/**
+ * File access auditing on macOS through the Endpoint Security framework
+ *
+ * Endpoint Security clients need the com.apple.developer.endpoint-security.client
+ * entitlement, so instead of shipping a signed helper this streams events from
+ * eslogger (macOS 13+), Apple's entitled Endpoint Security client. It must run
+ * as root, and the terminal needs Full Disk Access.
+ *
+ * eslogger reports every process on the system. A process tree is followed
+ * from each sandbox-exec whose profile carries a log tag of this session, and
+ * only the file events of those processes are reported, with exact paths and
+ * pids. Endpoint Security only sees operations that were allowed, so denials
+ * still come from the sandbox log monitor.
+ */
+
+/** Events requested from eslogger */
+const ESLOGGER_EVENTS = [
+  'exec',
+  'fork',
+  'exit',
+  'open',
+  'create',
+  'write',
+  'unlink',
+  'rename',
+]
+
+/** fflag bit of an open for writing (FWRITE in sys/fcntl.h) */
+const FWRITE = 0x2
+
+interface EsFile {
+  path?: string
+}
+
+interface EsProcess {
+  audit_token?: { pid?: number }
+  executable?: EsFile
+}
+
+/**
+ * The parts of an eslogger JSON event the monitor uses
+ */
+export interface EsloggerEvent {
+  process?: EsProcess
+  event?: {
+    exec?: { target?: EsProcess; args?: string[] }
+    fork?: { child?: EsProcess }
+    exit?: unknown
+    open?: { file?: EsFile; fflag?: number }
+    create?: {
+      destination?: {
+        existing_file?: EsFile
+        new_path?: { dir?: EsFile; filename?: string }
+      }
+    }
+    write?: { target?: EsFile }
+    unlink?: { target?: EsFile }
+    rename?: { source?: EsFile }
+  }
+}
+
+export interface EsFileAccess {
+  syscall: 'open' | 'create' | 'write' | 'unlink' | 'rename'
+  path: string
+  access: 'read' | 'write'
+}
+
+/**
+ * The file access an eslogger event describes, if it is one
+ */
+export function getEsFileAccess(
+  event: EsloggerEvent,
+): EsFileAccess | undefined {
+  const e = event.event
+  if (e?.open?.file?.path) {
+    const write = ((e.open.fflag ?? 0) & FWRITE) !== 0
+    return {
+      syscall: 'open',
+      path: e.open.file.path,
+      access: write ? 'write' : 'read',
+    }
+  }
+  if (e?.create?.destination) {
+    const { existing_file, new_path } = e.create.destination
+    const path =
+      existing_file?.path ??
+      (new_path?.dir?.path && new_path.filename
+        ? `${new_path.dir.path}/${new_path.filename}`
+        : undefined)
+    return path ? { syscall: 'create', path, access: 'write' } : undefined
+  }
+  if (e?.write?.target?.path) {
+    return { syscall: 'write', path: e.write.target.path, access: 'write' }
+  }
+  if (e?.unlink?.target?.path) {
+    return { syscall: 'unlink', path: e.unlink.target.path, access: 'write' }
+  }
+  if (e?.rename?.source?.path) {
+    return { syscall: 'rename', path: e.rename.source.path, access: 'write' }
+  }
+  return undefined
+}
+
+/**
+ * The profile text of a sandbox-exec invocation, given inline with -p or as a
+ * file with -f
+ */
+function getSandboxExecProfile(args: string[]): string | undefined {
+  const inline = args.indexOf('-p')
+  if (inline !== -1) {
+    return args[inline + 1]
+  }
+  const file = args.indexOf('-f')
+  const profilePath = file !== -1 ? args[file + 1] : undefined
+  if (!profilePath) {
+    return undefined
+  }
+  try {
+    return readFileSync(profilePath, 'utf8')
+  } catch {
+    // Already cleaned up, or not readable
+    return undefined
+  }
+}
+
+/**
+ * Follows the process trees started by this session's sandbox-exec calls and
+ * turns their file events into file-access events
+ */
+export class EndpointSecurityEventHandler {
+  /** Sandboxed pids and the encoded command each one runs for */
+  private readonly tracked = new Map<number, string>()
+
+  constructor(private readonly callback: SandboxViolationCallback) {}
+
+  handle(event: EsloggerEvent): void {
+    const pid = event.process?.audit_token?.pid
+    if (pid === undefined) {
+      return
+    }
+    const e = event.event
+
+    if (e?.exec) {
+      // sandbox-exec is exec'd with the profile, which carries the log tag
+      const profile = getSandboxExecProfile(e.exec.args ?? [])
+      const encodedCommand = profile ? findSessionLogTag(profile) : undefined
+      if (encodedCommand) {
+        this.tracked.set(pid, encodedCommand)
+      }
+      return
+    }
+
+    const encodedCommand = this.tracked.get(pid)
+    if (encodedCommand === undefined) {
+      return
+    }
+    if (e?.fork) {
+      const childPid = e.fork.child?.audit_token?.pid
+      if (childPid !== undefined) {
+        this.tracked.set(childPid, encodedCommand)
+      }
+      return
+    }
+    if (e?.exit) {
+      this.tracked.delete(pid)
+      return
+    }
+
+    const fileAccess = getEsFileAccess(event)
+    if (!fileAccess) {
+      return
+    }
+    let command: string | undefined
+    try {
+      command = decodeSandboxedCommand(encodedCommand)
+    } catch {
+      // Failed to decode, continue without command
+    }
+    this.callback({
+      line: `allow(${fileAccess.access}) ${fileAccess.syscall} ${fileAccess.path}`,
+      command,
+      encodedCommand,
+      timestamp: new Date(),
+      type: 'file-access',
+      access: fileAccess.access,
+      pid,
+      processName: event.process?.executable?.path?.split('/').pop(),
+      path: fileAccess.path,
+    })
+  }
+}
+
+/**
+ * Start the Endpoint Security monitor, or return undefined when eslogger is
+ * missing or the process is not running as root
+ */
+export function startMacOSEndpointSecurityMonitor(
+  callback: SandboxViolationCallback,
+): (() => void) | undefined {
+  if (process.getuid?.() !== 0) {
+    logForDebugging(
+      '[Sandbox Monitor] Endpoint Security needs root; file accesses will not be audited',
+      { level: 'warn' },
+    )
+    return undefined
+  }
+  if (spawnSync('which', ['eslogger'], { stdio: 'ignore' }).status !== 0) {
+    logForDebugging(
+      '[Sandbox Monitor] eslogger is not available (macOS 13+); file accesses will not be audited',
+      { level: 'warn' },
+    )
+    return undefined
+  }
+
+  const handler = new EndpointSecurityEventHandler(callback)
+  const esProcess = spawn('eslogger', [...ESLOGGER_EVENTS, '--format', 'json'])
+
+  let partialLine = ''
+  esProcess.stdout?.on('data', (data: Buffer) => {
+    const lines = (partialLine + data.toString()).split('\n')
+    partialLine = lines.pop() ?? ''
+    for (const line of lines) {
+      if (!line.trim()) continue
+      try {
+        handler.handle(JSON.parse(line) as EsloggerEvent)
+      } catch (error) {
+        logForDebugging(
+          `[Sandbox Monitor] Failed to parse eslogger event: ${error}`,
+        )
+      }
+    }
+  })
+
+  esProcess.stderr?.on('data', (data: Buffer) => {
+    logForDebugging(`[Sandbox Monitor] eslogger stderr: ${data.toString()}`)
+  })
+
+  esProcess.on('error', (error: Error) => {
+    logForDebugging(
+      `[Sandbox Monitor] Failed to start eslogger: ${error.message}`,
+    )
+  })
+
+  esProcess.on('exit', (code: number | null) => {
+    logForDebugging(`[Sandbox Monitor] eslogger exited with code: ${code}`)
+  })
+
+  return () => {
+    logForDebugging('[Sandbox Monitor] Stopping Endpoint Security monitor')
+    esProcess.kill('SIGTERM')
+  }
+}
@@ -0,0 +1,167 @@
+import { execFile } from 'node:child_process'
+import { promisify } from 'node:util'
+import { logForDebugging } from '../utils/debug.js'
+import type {
+  SandboxViolationEvent,
+  ViolationProcess,
+} from './macos-sandbox-utils.js'
+
+/**
+ * Process attribution for macOS violations
+ *
+ * The sandbox log only names the process and its pid, so the executable,
+ * command line and parents are looked up with ps while the process is
+ * (likely) still running. One snapshot of the process table is taken
+ * asynchronously and shared by the violations of a burst, so the log stream
+ * is never blocked on ps. ps only reports the command line as one string,
+ * which can't be split back into argv reliably, so it is kept whole as
+ * commandLine.
+ */
+
+interface ProcessEntry {
+  ppid: number
+  executable: string
+  commandLine: string
+}
+
+/** Parents listed per violation, to bound the walk on a corrupt table */
+const MAX_PARENTS = 16
+
+/** A snapshot is reused for violations arriving in the same burst */
+const SNAPSHOT_TTL_MS = 1000
+
+/** Unknown pids refresh the snapshot at most this often */
+const MIN_REFRESH_INTERVAL_MS = 100
+
+const execFileAsync = promisify(execFile)
+
+let snapshot:
+  | { time: number; processes: Promise<Map<number, ProcessEntry>> }
+  | undefined
+
+/**
+ * Parse `ps -o pid=,<column>=` output into a map of pid to the column
+ */
+function parsePsColumn(output: string): Map<number, string> {
+  const result = new Map<number, string>()
+  for (const line of output.split('\n')) {
+    const match = /^\s*(\d+)\s(.*)$/.exec(line)
+    if (match?.[1]) {
+      result.set(Number.parseInt(match[1], 10), (match[2] ?? '').trim())
+    }
+  }
+  return result
+}
+
+/**
+ * Read the process table. The executable and the command line both may
+ * contain spaces, so each is read as the last column of its own ps run.
+ */
+async function readProcessTable(): Promise<Map<number, ProcessEntry>> {
+  const processes = new Map<number, ProcessEntry>()
+  const ps = async (columns: string) =>
+    (
+      await execFileAsync('ps', ['-A', '-ww', '-o', columns], {
+        encoding: 'utf8',
+        timeout: 1000,
+        maxBuffer: 64 * 1024 * 1024,
+      })
+    ).stdout
+  let tree: string
+  let commandLines: string
+  try {
+    ;[tree, commandLines] = await Promise.all([
+      ps('pid=,ppid=,comm='),
+      ps('pid=,args='),
+    ])
+  } catch (error) {
+    logForDebugging(
+      `[Sandbox Monitor] ps failed; processes not attributed: ${error}`,
+    )
+    return processes
+  }
+
+  const commandLineByPid = parsePsColumn(commandLines)
+  for (const [pid, rest] of parsePsColumn(tree)) {
+    const match = /^(\d+)\s+(.*)$/.exec(rest)
+    if (match?.[1]) {
+      processes.set(pid, {
+        ppid: Number.parseInt(match[1], 10),
+        executable: match[2] ?? '',
+        commandLine: commandLineByPid.get(pid) ?? '',
+      })
+    }
+  }
+  return processes
+}
+
+function refreshSnapshot(): Promise<Map<number, ProcessEntry>> {
+  snapshot = { time: Date.now(), processes: readProcessTable() }
+  return snapshot.processes
+}
+
+async function getProcessTable(
+  pid: number,
+): Promise<Map<number, ProcessEntry>> {
+  const current = snapshot
+  if (!current || Date.now() - current.time > SNAPSHOT_TTL_MS) {
+    return refreshSnapshot()
+  }
+  const processes = await current.processes
+  if (processes.has(pid)) {
+    return processes
+  }
+  // A process started after the snapshot: take a new one, but not more often
+  // than MIN_REFRESH_INTERVAL_MS, and share it with the others waiting
+  const wait = current.time + MIN_REFRESH_INTERVAL_MS - Date.now()
+  if (wait > 0) {
+    await new Promise(resolve => setTimeout(resolve, wait))
+  }
+  return snapshot && snapshot !== current
+    ? snapshot.processes
+    : refreshSnapshot()
+}
+
+function toViolationProcess(
+  pid: number,
+  entry: ProcessEntry,
+): ViolationProcess {
+  return {
+    pid,
+    executable: entry.executable,
+    commandLine: entry.commandLine,
+  }
+}
+
+/**
+ * The executable, command line and parents of a process, or nothing when it
+ * has already exited. Parents stop before this (srt) process.
+ */
+export async function getMacOSProcessAttribution(
+  pid: number,
+): Promise<
+  Pick<SandboxViolationEvent, 'executable' | 'commandLine' | 'parents'>
+> {
+  const processes = await getProcessTable(pid)
+  const entry = processes.get(pid)
+  if (!entry) {
+    return {}
+  }
+
+  const { executable, commandLine } = toViolationProcess(pid, entry)
+  const parents: ViolationProcess[] = []
+  let parentPid = entry.ppid
+  while (
+    parents.length < MAX_PARENTS &&
+    parentPid > 1 &&
+    parentPid !== process.pid
+  ) {
+    const parent = processes.get(parentPid)
+    if (!parent) {
+      break
+    }
+    parents.push(toViolationProcess(parentPid, parent))
+    parentPid = parent.ppid
+  }
+  return { executable, commandLine, parents }
+}
@@ -1,5 +1,8 @@
 import shellquote from 'shell-quote'
 import { spawn, spawnSync } from 'child_process'
+import { createHash } from 'crypto'
+import * as fs from 'fs'
+import * as os from 'os'
 import * as path from 'path'
 import { logForDebugging } from '../utils/debug.js'
 import {
@@ -8,14 +11,25 @@ import {
   encodeSandboxedCommand,
   decodeSandboxedCommand,
   containsGlobChars,
+  globToRegex,
+  expandUnicodeNormalizations,
   DANGEROUS_FILES,
   getDangerousDirectories,
+  getSrtSettingsPaths,
 } from './sandbox-utils.js'
 import type {
   FsReadRestrictionConfig,
   FsWriteRestrictionConfig,
 } from './sandbox-schemas.js'
-import type { IgnoreViolationsConfig } from './sandbox-config.js'
+import { isViolationIgnored } from './violation-filter.js'
+import { getMacOSProcessAttribution } from './macos-process-info.js'
+import { toEnvUnsetArgs } from './sandbox-env.js'
+import { encodeRedactedCommand, type Redactor } from './redaction.js'
+import type {
+  IgnoreViolationsConfig,
+  SeatbeltConfig,
+  SeatbeltTemplate,
+} from './sandbox-config.js'
 
 export interface MacOSSandboxParams {
   command: string
@@ -25,12 +39,22 @@ export interface MacOSSandboxParams {
   allowUnixSockets?: string[]
   allowAllUnixSockets?: boolean
   allowLocalBinding?: boolean
+  /** Allow resolving names directly instead of only through the proxies */
+  allowDns?: boolean
   readConfig: FsReadRestrictionConfig | undefined
   writeConfig: FsWriteRestrictionConfig | undefined
   ignoreViolations?: IgnoreViolationsConfig | undefined
   allowPty?: boolean
   allowGitConfig?: boolean
   binShell?: string
+  /** Session scratch directory exposed as $SRT_SCRATCH_DIR and $TMPDIR */
+  scratchDir?: string
+  /** Base template and raw SBPL rules merged into the profile */
+  seatbelt?: SeatbeltConfig
+  /** Environment of the command; srt's other variables are unset (default: srt's environment) */
+  environment?: Record<string, string>
+  /** Redacts the command before it is encoded in the log tag */
+  redact?: Redactor
 }
 
 /**
@@ -53,6 +77,9 @@ export function macGetMandatoryDenyPatterns(allowGitConfig = false): string[] {
     denyPaths.push(`**/${dirName}/**`)
   }
 
+  // srt's own settings and state
+  denyPaths.push(...getSrtSettingsPaths())
+
   // Git hooks are always blocked for security
   denyPaths.push(path.resolve(cwd, '.git/hooks'))
   denyPaths.push('**/.git/hooks/**')
@@ -66,11 +93,55 @@ export function macGetMandatoryDenyPatterns(allowGitConfig = false): string[] {
   return [...new Set(denyPaths)]
 }
 
+export type NetworkViolationSubtype =
+  | 'denied-domain'
+  | 'not-allowed'
+  | 'user-denied'
+  | 'dns-rebinding'
+  | 'cloud-metadata'
+  | 'smtp'
+
+/** A process in the parent chain of a violation */
+export interface ViolationProcess {
+  pid: number
+  executable?: string
+  argv?: string[]
+  /** Command line as one string, where argv is not known exactly (macOS) */
+  commandLine?: string
+}
+
 export interface SandboxViolationEvent {
   line: string
   command?: string
   encodedCommand?: string
   timestamp: Date
+  /**
+   * 'network' for violations reported by the proxies rather than the OS sandbox,
+   * 'file-access' for allowed accesses recorded in audit mode
+   */
+  type?: 'network' | 'file-access'
+  subtype?: NetworkViolationSubtype
+  host?: string
+  port?: number
+  /** Process that made the access */
+  pid?: number
+  /** Name of the process that made the access */
+  processName?: string
+  /** Executable of the process that made the access */
+  executable?: string
+  /** Arguments of the process that made the access */
+  argv?: string[]
+  /**
+   * Command line of the process that made the access as one string, where
+   * its arguments are not known exactly (macOS)
+   */
+  commandLine?: string
+  /** Parents of the process, nearest first, up to the sandboxed command */
+  parents?: ViolationProcess[]
+  /** Path or address that was accessed */
+  path?: string
+  /** Whether the file access read or modified the file, when known */
+  access?: 'read' | 'write'
 }
 
 export type SandboxViolationCallback = (
@@ -80,49 +151,24 @@ export type SandboxViolationCallback = (
 const sessionSuffix = `_${Math.random().toString(36).slice(2, 11)}_SBX`
 
 /**
- * Convert a glob pattern to a regular expression for macOS sandbox profiles
- *
- * This implements gitignore-style pattern matching to match the behavior of the
- * `ignore` library used by the permission system/
- *
- * Supported patterns:
- * - * matches any characters except / (e.g., *.ts matches foo.ts but not foo/bar.ts)
- * - ** matches any characters including / (e.g., src/** /*.ts matches all .ts files in src/)
- * - ? matches any single character except / (e.g., file?.txt matches file1.txt)
- * - [abc] matches any character in the set (e.g., file[0-9].txt matches file3.txt)
- *
- * Note: This is designed for macOS sandbox (regex ...) syntax. The resulting regex
- * will be used in sandbox profiles like: (deny file-write* (regex "pattern"))
- *
- * Exported for testing purposes.
+ * Generate a unique log tag for sandbox monitoring
+ * @param command - The command being executed (will be base64 encoded)
+ * @param redact - Applied before encoding, so the system log never sees secrets
  */
-export function globToRegex(globPattern: string): string {
-  return (
-    '^' +
-    globPattern
-      // Escape regex special characters (except glob chars * ? [ ])
-      .replace(/[.^$+{}()|\\]/g, '\\$&')
-      // Escape unclosed brackets (no matching ])
-      .replace(/\[([^\]]*?)$/g, '\\[$1')
-      // Convert glob patterns to regex (order matters - ** before *)
-      .replace(/\*\*\//g, '__GLOBSTAR_SLASH__') // Placeholder for **/
-      .replace(/\*\*/g, '__GLOBSTAR__') // Placeholder for **
-      .replace(/\*/g, '[^/]*') // * matches anything except /
-      .replace(/\?/g, '[^/]') // ? matches single character except /
-      // Restore placeholders
-      .replace(/__GLOBSTAR_SLASH__/g, '(.*/)?') // **/ matches zero or more dirs
-      .replace(/__GLOBSTAR__/g, '.*') + // ** matches anything including /
-    '$'
-  )
+function generateLogTag(command: string, redact?: Redactor): string {
+  const encodedCommand = redact
+    ? encodeRedactedCommand(command, redact)
+    : encodeSandboxedCommand(command)
+  return `CMD64_${encodedCommand}_END_${sessionSuffix}`
 }
 
 /**
- * Generate a unique log tag for sandbox monitoring
- * @param command - The command being executed (will be base64 encoded)
+ * Find a log tag generated by this session in a string (a profile or log
+ * line) and return the encoded command it carries
  */
-function generateLogTag(command: string): string {
-  const encodedCommand = encodeSandboxedCommand(command)
-  return `CMD64_${encodedCommand}_END_${sessionSuffix}`
+export function findSessionLogTag(text: string): string | undefined {
+  const match = /CMD64_([A-Za-z0-9+/=]+)_END_(_[a-z0-9]+_SBX)/.exec(text)
+  return match?.[2] === sessionSuffix ? match[1] : undefined
 }
 
 /**
@@ -240,8 +286,9 @@ function generateReadRules(
   // Start by allowing everything
   rules.push(`(allow file-read*)`)
 
-  // Then deny specific paths
-  for (const pathPattern of config.denyOnly || []) {
+  // Then deny specific paths (in every Unicode normalization form)
+  const denyPaths = expandUnicodeNormalizations(config.denyOnly || [])
+  for (const pathPattern of denyPaths) {
     const normalizedPath = normalizePathForSandbox(pathPattern)
 
     if (containsGlobChars(normalizedPath)) {
@@ -263,7 +310,7 @@ function generateReadRules(
   }
 
   // Block file movement to prevent bypass via mv/rename
-  rules.push(...generateMoveBlockingRules(config.denyOnly || [], logTag))
+  rules.push(...generateMoveBlockingRules(denyPaths, logTag))
 
   return rules
 }
@@ -294,7 +341,9 @@ function generateWriteRules(
   }
 
   // Generate allow rules
-  for (const pathPattern of config.allowOnly || []) {
+  for (const pathPattern of expandUnicodeNormalizations(
+    config.allowOnly || [],
+  )) {
     const normalizedPath = normalizePathForSandbox(pathPattern)
 
     if (containsGlobChars(normalizedPath)) {
@@ -316,10 +365,10 @@ function generateWriteRules(
   }
 
   // Combine user-specified and mandatory deny patterns (no ripgrep needed on macOS)
-  const denyPaths = [
+  const denyPaths = expandUnicodeNormalizations([
     ...(config.denyWithinAllow || []),
     ...macGetMandatoryDenyPatterns(allowGitConfig),
-  ]
+  ])
 
   for (const pathPattern of denyPaths) {
     const normalizedPath = normalizePathForSandbox(pathPattern)
@@ -348,6 +397,258 @@ function generateWriteRules(
   return rules
 }
 
+/**
+ * Generate the network rules of the profile
+ *
+ * Outbound connections are matched with `remote tcp` filters on the loopback
+ * ports the proxies listen on; the sandbox never binds or accepts on those
+ * ports itself. DNS and loopback access are denied unless the config allows
+ * them, since the proxies resolve names outside the sandbox.
+ */
+export function generateNetworkRules({
+  needsNetworkRestriction,
+  httpProxyPort,
+  socksProxyPort,
+  allowUnixSockets,
+  allowAllUnixSockets,
+  allowLocalBinding,
+  allowDns,
+}: {
+  needsNetworkRestriction: boolean
+  httpProxyPort?: number
+  socksProxyPort?: number
+  allowUnixSockets?: string[]
+  allowAllUnixSockets?: boolean
+  allowLocalBinding?: boolean
+  allowDns?: boolean
+}): string[] {
+  if (!needsNetworkRestriction) {
+    return ['(allow network*)']
+  }
+
+  const rules: string[] = []
+
+  // Local servers: listen on loopback, and connect to any loopback port
+  if (allowLocalBinding) {
+    rules.push('(allow network-bind (local ip "localhost:*"))')
+    rules.push('(allow network-inbound (local ip "localhost:*"))')
+    rules.push('(allow network-outbound (remote ip "localhost:*"))')
+  }
+
+  // Unix domain sockets for local IPC (SSH agent, Docker, etc.)
+  if (allowAllUnixSockets) {
+    // Allow all Unix socket paths
+    rules.push('(allow network* (subpath "/"))')
+  } else if (allowUnixSockets && allowUnixSockets.length > 0) {
+    // Allow binding (local) and connecting (remote) to specific socket paths.
+    // A symlinked socket (e.g. /var/run/docker.sock) is allowed under both names.
+    for (const socketPath of allowUnixSockets) {
+      const normalizedPath = normalizePathForSandbox(socketPath)
+      const paths = new Set([normalizedPath])
+      if (path.isAbsolute(socketPath) && !containsGlobChars(socketPath)) {
+        paths.add(socketPath)
+      }
+      for (const p of paths) {
+        const pathFilter = containsGlobChars(p)
+          ? `(path-regex ${escapePath(globToRegex(p))})`
+          : `(path-literal ${escapePath(p)})`
+        rules.push(
+          `(allow network* (local unix-socket ${pathFilter}) (remote unix-socket ${pathFilter}))`,
+        )
+      }
+    }
+  }
+  // If both allowAllUnixSockets and allowUnixSockets are false/undefined/empty, Unix sockets are blocked by default
+
+  // Direct name resolution through mDNSResponder and plain DNS servers
+  if (allowDns) {
+    rules.push('(allow mach-lookup (global-name "com.apple.dnssd.service"))')
+    rules.push(
+      '(allow network-outbound (remote unix-socket (path-literal "/private/var/run/mDNSResponder")))',
+    )
+    rules.push('(allow network-outbound (remote udp "*:53"))')
+    rules.push('(allow network-outbound (remote tcp "*:53"))')
+  }
+
+  // Connections to the HTTP and SOCKS proxies
+  for (const port of [httpProxyPort, socksProxyPort]) {
+    if (port !== undefined) {
+      rules.push(`(allow network-outbound (remote tcp "localhost:${port}"))`)
+    }
+  }
+
+  return rules
+}
+
+/**
+ * Mach services the default template lets sandboxed processes look up
+ */
+export const DEFAULT_MACH_SERVICES: readonly string[] = [
+  'com.apple.audio.systemsoundserver',
+  'com.apple.distributed_notifications@Uv3',
+  'com.apple.FontObjectsServer',
+  'com.apple.fonts',
+  'com.apple.logd',
+  'com.apple.lsd.mapdb',
+  'com.apple.PowerManagement.control',
+  'com.apple.system.logger',
+  'com.apple.system.notification_center',
+  'com.apple.trustd.agent',
+  'com.apple.system.opendirectoryd.libinfo',
+  'com.apple.system.opendirectoryd.membership',
+  'com.apple.bsd.dirhelper',
+  'com.apple.securityd.xpc',
+  'com.apple.coreservices.launchservicesd',
+  'com.apple.SecurityServer',
+]
+
+/**
+ * Generate the mach-lookup rule for the allowed services, one global-name
+ * filter each; there is no wildcard rule
+ */
+export function generateMachLookupRules(
+  services: readonly string[],
+): string[] {
+  if (services.length === 0) {
+    return []
+  }
+  return [
+    '; Mach IPC - specific services only (no wildcard)',
+    '(allow mach-lookup',
+    ...services.map(service => `  (global-name ${escapePath(service)})`),
+    ')',
+    '',
+  ]
+}
+
+/**
+ * Base rules of every template: processes, sysctl reads and device files
+ */
+const CORE_RULES: string[] = [
+  '; Essential permissions - based on Chrome sandbox policy',
+  '; Process permissions',
+  '(allow process-exec)',
+  '(allow process-fork)',
+  '(allow process-info* (target same-sandbox))',
+  '(allow signal (target same-sandbox))',
+  '(allow mach-priv-task-port (target same-sandbox))',
+  '',
+  '; sysctl - specific sysctls only',
+  '(allow sysctl-read',
+  '  (sysctl-name "hw.activecpu")',
+  '  (sysctl-name "hw.busfrequency_compat")',
+  '  (sysctl-name "hw.byteorder")',
+  '  (sysctl-name "hw.cacheconfig")',
+  '  (sysctl-name "hw.cachelinesize_compat")',
+  '  (sysctl-name "hw.cpufamily")',
+  '  (sysctl-name "hw.cpufrequency")',
+  '  (sysctl-name "hw.cpufrequency_compat")',
+  '  (sysctl-name "hw.cputype")',
+  '  (sysctl-name "hw.l1dcachesize_compat")',
+  '  (sysctl-name "hw.l1icachesize_compat")',
+  '  (sysctl-name "hw.l2cachesize_compat")',
+  '  (sysctl-name "hw.l3cachesize_compat")',
+  '  (sysctl-name "hw.logicalcpu")',
+  '  (sysctl-name "hw.logicalcpu_max")',
+  '  (sysctl-name "hw.machine")',
+  '  (sysctl-name "hw.memsize")',
+  '  (sysctl-name "hw.ncpu")',
+  '  (sysctl-name "hw.nperflevels")',
+  '  (sysctl-name "hw.packages")',
+  '  (sysctl-name "hw.pagesize_compat")',
+  '  (sysctl-name "hw.pagesize")',
+  '  (sysctl-name "hw.physicalcpu")',
+  '  (sysctl-name "hw.physicalcpu_max")',
+  '  (sysctl-name "hw.tbfrequency_compat")',
+  '  (sysctl-name "hw.vectorunit")',
+  '  (sysctl-name "kern.argmax")',
+  '  (sysctl-name "kern.bootargs")',
+  '  (sysctl-name "kern.hostname")',
+  '  (sysctl-name "kern.maxfiles")',
+  '  (sysctl-name "kern.maxfilesperproc")',
+  '  (sysctl-name "kern.maxproc")',
+  '  (sysctl-name "kern.ngroups")',
+  '  (sysctl-name "kern.osproductversion")',
+  '  (sysctl-name "kern.osrelease")',
+  '  (sysctl-name "kern.ostype")',
+  '  (sysctl-name "kern.osvariant_status")',
+  '  (sysctl-name "kern.osversion")',
+  '  (sysctl-name "kern.secure_kernel")',
+  '  (sysctl-name "kern.tcsm_available")',
+  '  (sysctl-name "kern.tcsm_enable")',
+  '  (sysctl-name "kern.usrstack64")',
+  '  (sysctl-name "kern.version")',
+  '  (sysctl-name "kern.willshutdown")',
+  '  (sysctl-name "machdep.cpu.brand_string")',
+  '  (sysctl-name "machdep.ptrauth_enabled")',
+  '  (sysctl-name "security.mac.lockdown_mode_state")',
+  '  (sysctl-name "sysctl.proc_cputype")',
+  '  (sysctl-name "vm.loadavg")',
+  '  (sysctl-name-prefix "hw.optional.arm")',
+  '  (sysctl-name-prefix "hw.optional.arm.")',
+  '  (sysctl-name-prefix "hw.optional.armv8_")',
+  '  (sysctl-name-prefix "hw.perflevel")',
+  '  (sysctl-name-prefix "kern.proc.all")',
+  '  (sysctl-name-prefix "kern.proc.pgrp.")',
+  '  (sysctl-name-prefix "kern.proc.pid.")',
+  '  (sysctl-name-prefix "machdep.cpu.")',
+  '  (sysctl-name-prefix "net.routetable.")',
+  ')',
+  '',
+  '; V8 thread calculations',
+  '(allow sysctl-write',
+  '  (sysctl-name "kern.tcsm_enable")',
+  ')',
+  '',
+  '; File I/O on device files',
+  '(allow file-ioctl (literal "/dev/null"))',
+  '(allow file-ioctl (literal "/dev/zero"))',
+  '(allow file-ioctl (literal "/dev/random"))',
+  '(allow file-ioctl (literal "/dev/urandom"))',
+  '(allow file-ioctl (literal "/dev/dtracehelper"))',
+  '(allow file-ioctl (literal "/dev/tty"))',
+  '',
+  '(allow file-ioctl file-read-data file-write-data',
+  '  (require-all',
+  '    (literal "/dev/null")',
+  '    (vnode-type CHARACTER-DEVICE)',
+  '  )',
+  ')',
+  '',
+]
+
+/**
+ * Rules the default template adds for preferences, fonts, system services,
+ * shared memory and IOKit, which many desktop tools need
+ */
+const DEFAULT_TEMPLATE_RULES: string[] = [
+  '; User preferences',
+  '(allow user-preference-read)',
+  '',
+  '; POSIX IPC - shared memory',
+  '(allow ipc-posix-shm)',
+  '',
+  '; POSIX IPC - semaphores for Python multiprocessing',
+  '(allow ipc-posix-sem)',
+  '',
+  '; IOKit - specific operations only',
+  '(allow iokit-open',
+  '  (iokit-registry-entry-class "IOSurfaceRootUserClient")',
+  '  (iokit-registry-entry-class "RootDomainUserClient")',
+  '  (iokit-user-client-class "IOSurfaceSendRight")',
+  ')',
+  '',
+  '; IOKit properties',
+  '(allow iokit-get-properties)',
+  '',
+  "; Specific safe system-sockets, doesn't allow network access",
+  '(allow system-socket (require-all (socket-domain AF_SYSTEM) (socket-protocol 2)))',
+  '',
+  '; Distributed notifications',
+  '(allow distributed-notification-post)',
+  '',
+]
+
 /**
  * Generate complete sandbox profile
  */
@@ -360,8 +661,13 @@ function generateSandboxProfile({
   allowUnixSockets,
   allowAllUnixSockets,
   allowLocalBinding,
+  allowDns,
   allowPty,
   allowGitConfig = false,
+  template = 'default',
+  machServices,
+  extraRules = [],
+  profileDir,
   logTag,
 }: {
   readConfig: FsReadRestrictionConfig | undefined
@@ -372,8 +678,13 @@ function generateSandboxProfile({
   allowUnixSockets?: string[]
   allowAllUnixSockets?: boolean
   allowLocalBinding?: boolean
+  allowDns?: boolean
   allowPty?: boolean
   allowGitConfig?: boolean
+  template?: SeatbeltTemplate
+  machServices?: string[]
+  extraRules?: string[]
+  profileDir?: string
   logTag: string
 }): string {
   const profile: string[] = [
@@ -382,195 +693,28 @@ function generateSandboxProfile({
     '',
     `; LogTag: ${logTag}`,
     '',
-    '; Essential permissions - based on Chrome sandbox policy',
-    '; Process permissions',
-    '(allow process-exec)',
-    '(allow process-fork)',
-    '(allow process-info* (target same-sandbox))',
-    '(allow signal (target same-sandbox))',
-    '(allow mach-priv-task-port (target same-sandbox))',
-    '',
-    '; User preferences',
-    '(allow user-preference-read)',
-    '',
-    '; Mach IPC - specific services only (no wildcard)',
-    '(allow mach-lookup',
-    '  (global-name "com.apple.audio.systemsoundserver")',
-    '  (global-name "com.apple.distributed_notifications@Uv3")',
-    '  (global-name "com.apple.FontObjectsServer")',
-    '  (global-name "com.apple.fonts")',
-    '  (global-name "com.apple.logd")',
-    '  (global-name "com.apple.lsd.mapdb")',
-    '  (global-name "com.apple.PowerManagement.control")',
-    '  (global-name "com.apple.system.logger")',
-    '  (global-name "com.apple.system.notification_center")',
-    '  (global-name "com.apple.trustd.agent")',
-    '  (global-name "com.apple.system.opendirectoryd.libinfo")',
-    '  (global-name "com.apple.system.opendirectoryd.membership")',
-    '  (global-name "com.apple.bsd.dirhelper")',
-    '  (global-name "com.apple.securityd.xpc")',
-    '  (global-name "com.apple.coreservices.launchservicesd")',
-    ')',
-    '',
-    '; POSIX IPC - shared memory',
-    '(allow ipc-posix-shm)',
-    '',
-    '; POSIX IPC - semaphores for Python multiprocessing',
-    '(allow ipc-posix-sem)',
-    '',
-    '; IOKit - specific operations only',
-    '(allow iokit-open',
-    '  (iokit-registry-entry-class "IOSurfaceRootUserClient")',
-    '  (iokit-registry-entry-class "RootDomainUserClient")',
-    '  (iokit-user-client-class "IOSurfaceSendRight")',
-    ')',
-    '',
-    '; IOKit properties',
-    '(allow iokit-get-properties)',
-    '',
-    "; Specific safe system-sockets, doesn't allow network access",
-    '(allow system-socket (require-all (socket-domain AF_SYSTEM) (socket-protocol 2)))',
-    '',
-    '; sysctl - specific sysctls only',
-    '(allow sysctl-read',
-    '  (sysctl-name "hw.activecpu")',
-    '  (sysctl-name "hw.busfrequency_compat")',
-    '  (sysctl-name "hw.byteorder")',
-    '  (sysctl-name "hw.cacheconfig")',
-    '  (sysctl-name "hw.cachelinesize_compat")',
-    '  (sysctl-name "hw.cpufamily")',
-    '  (sysctl-name "hw.cpufrequency")',
-    '  (sysctl-name "hw.cpufrequency_compat")',
-    '  (sysctl-name "hw.cputype")',
-    '  (sysctl-name "hw.l1dcachesize_compat")',
-    '  (sysctl-name "hw.l1icachesize_compat")',
-    '  (sysctl-name "hw.l2cachesize_compat")',
-    '  (sysctl-name "hw.l3cachesize_compat")',
-    '  (sysctl-name "hw.logicalcpu")',
-    '  (sysctl-name "hw.logicalcpu_max")',
-    '  (sysctl-name "hw.machine")',
-    '  (sysctl-name "hw.memsize")',
-    '  (sysctl-name "hw.ncpu")',
-    '  (sysctl-name "hw.nperflevels")',
-    '  (sysctl-name "hw.packages")',
-    '  (sysctl-name "hw.pagesize_compat")',
-    '  (sysctl-name "hw.pagesize")',
-    '  (sysctl-name "hw.physicalcpu")',
-    '  (sysctl-name "hw.physicalcpu_max")',
-    '  (sysctl-name "hw.tbfrequency_compat")',
-    '  (sysctl-name "hw.vectorunit")',
-    '  (sysctl-name "kern.argmax")',
-    '  (sysctl-name "kern.bootargs")',
-    '  (sysctl-name "kern.hostname")',
-    '  (sysctl-name "kern.maxfiles")',
-    '  (sysctl-name "kern.maxfilesperproc")',
-    '  (sysctl-name "kern.maxproc")',
-    '  (sysctl-name "kern.ngroups")',
-    '  (sysctl-name "kern.osproductversion")',
-    '  (sysctl-name "kern.osrelease")',
-    '  (sysctl-name "kern.ostype")',
-    '  (sysctl-name "kern.osvariant_status")',
-    '  (sysctl-name "kern.osversion")',
-    '  (sysctl-name "kern.secure_kernel")',
-    '  (sysctl-name "kern.tcsm_available")',
-    '  (sysctl-name "kern.tcsm_enable")',
-    '  (sysctl-name "kern.usrstack64")',
-    '  (sysctl-name "kern.version")',
-    '  (sysctl-name "kern.willshutdown")',
-    '  (sysctl-name "machdep.cpu.brand_string")',
-    '  (sysctl-name "machdep.ptrauth_enabled")',
-    '  (sysctl-name "security.mac.lockdown_mode_state")',
-    '  (sysctl-name "sysctl.proc_cputype")',
-    '  (sysctl-name "vm.loadavg")',
-    '  (sysctl-name-prefix "hw.optional.arm")',
-    '  (sysctl-name-prefix "hw.optional.arm.")',
-    '  (sysctl-name-prefix "hw.optional.armv8_")',
-    '  (sysctl-name-prefix "hw.perflevel")',
-    '  (sysctl-name-prefix "kern.proc.all")',
-    '  (sysctl-name-prefix "kern.proc.pgrp.")',
-    '  (sysctl-name-prefix "kern.proc.pid.")',
-    '  (sysctl-name-prefix "machdep.cpu.")',
-    '  (sysctl-name-prefix "net.routetable.")',
-    ')',
-    '',
-    '; V8 thread calculations',
-    '(allow sysctl-write',
-    '  (sysctl-name "kern.tcsm_enable")',
-    ')',
-    '',
-    '; Distributed notifications',
-    '(allow distributed-notification-post)',
-    '',
-    '; Specific mach-lookup permissions for security operations',
-    '(allow mach-lookup (global-name "com.apple.SecurityServer"))',
-    '',
-    '; File I/O on device files',
-    '(allow file-ioctl (literal "/dev/null"))',
-    '(allow file-ioctl (literal "/dev/zero"))',
-    '(allow file-ioctl (literal "/dev/random"))',
-    '(allow file-ioctl (literal "/dev/urandom"))',
-    '(allow file-ioctl (literal "/dev/dtracehelper"))',
-    '(allow file-ioctl (literal "/dev/tty"))',
-    '',
-    '(allow file-ioctl file-read-data file-write-data',
-    '  (require-all',
-    '    (literal "/dev/null")',
-    '    (vnode-type CHARACTER-DEVICE)',
-    '  )',
-    ')',
-    '',
+    ...(template === 'minimal'
+      ? CORE_RULES
+      : [...CORE_RULES, ...DEFAULT_TEMPLATE_RULES]),
+    // The minimal template looks up no services unless they are configured
+    ...generateMachLookupRules(
+      machServices ?? (template === 'minimal' ? [] : DEFAULT_MACH_SERVICES),
+    ),
   ]
 
   // Network rules
   profile.push('; Network')
-  if (!needsNetworkRestriction) {
-    profile.push('(allow network*)')
-  } else {
-    // Allow local binding if requested
-    if (allowLocalBinding) {
-      profile.push('(allow network-bind (local ip "localhost:*"))')
-      profile.push('(allow network-inbound (local ip "localhost:*"))')
-      profile.push('(allow network-outbound (local ip "localhost:*"))')
-    }
-    // Unix domain sockets for local IPC (SSH agent, Docker, etc.)
-    if (allowAllUnixSockets) {
-      // Allow all Unix socket paths
-      profile.push('(allow network* (subpath "/"))')
-    } else if (allowUnixSockets && allowUnixSockets.length > 0) {
-      // Allow specific Unix socket paths
-      for (const socketPath of allowUnixSockets) {
-        const normalizedPath = normalizePathForSandbox(socketPath)
-        profile.push(`(allow network* (subpath ${escapePath(normalizedPath)}))`)
-      }
-    }
-    // If both allowAllUnixSockets and allowUnixSockets are false/undefined/empty, Unix sockets are blocked by default
-
-    // Allow localhost TCP operations for the HTTP proxy
-    if (httpProxyPort !== undefined) {
-      profile.push(
-        `(allow network-bind (local ip "localhost:${httpProxyPort}"))`,
-      )
-      profile.push(
-        `(allow network-inbound (local ip "localhost:${httpProxyPort}"))`,
-      )
-      profile.push(
-        `(allow network-outbound (remote ip "localhost:${httpProxyPort}"))`,
-      )
-    }
-
-    // Allow localhost TCP operations for the SOCKS proxy
-    if (socksProxyPort !== undefined) {
-      profile.push(
-        `(allow network-bind (local ip "localhost:${socksProxyPort}"))`,
-      )
-      profile.push(
-        `(allow network-inbound (local ip "localhost:${socksProxyPort}"))`,
-      )
-      profile.push(
-        `(allow network-outbound (remote ip "localhost:${socksProxyPort}"))`,
-      )
-    }
-  }
+  profile.push(
+    ...generateNetworkRules({
+      needsNetworkRestriction,
+      httpProxyPort,
+      socksProxyPort,
+      allowUnixSockets,
+      allowAllUnixSockets,
+      allowLocalBinding,
+      allowDns,
+    }),
+  )
   profile.push('')
 
   // Read rules
@@ -581,6 +725,14 @@ function generateSandboxProfile({
   // Write rules
   profile.push('; File write')
   profile.push(...generateWriteRules(writeConfig, logTag, allowGitConfig))
+  // The sandboxed command must not rewrite profiles later commands reuse
+  if (profileDir) {
+    for (const dir of [profileDir, fs.realpathSync(profileDir)]) {
+      profile.push(
+        `(deny file-write* (subpath ${escapePath(dir)}) (with message "${logTag}"))`,
+      )
+    }
+  }
 
   // Pseudo-terminal (pty) support
   if (allowPty) {
@@ -597,14 +749,29 @@ function generateSandboxProfile({
     profile.push(')')
   }
 
+  // User rules come last so they override the generated ones
+  if (extraRules.length > 0) {
+    profile.push('')
+    profile.push('; Custom rules (seatbelt.extraRules)')
+    profile.push(...extraRules)
+  }
+
   return profile.join('\n')
 }
 
 /**
- * Escape path for sandbox profile using JSON.stringify for proper escaping
+ * Quote a path or regex as a sandbox profile (SBPL) string literal
+ * SBPL strings only understand \\ and \" escapes; JSON-style \uXXXX escapes
+ * would silently change the path, so control characters are rejected.
  */
-function escapePath(pathStr: string): string {
-  return JSON.stringify(pathStr)
+export function escapePath(pathStr: string): string {
+  // eslint-disable-next-line no-control-regex
+  if (/[\x00-\x1f\x7f]/.test(pathStr)) {
+    throw new Error(
+      `Path contains control characters and cannot be used in a sandbox profile: ${JSON.stringify(pathStr)}`,
+    )
+  }
+  return `"${pathStr.replace(/\\/g, '\\\\').replace(/"/g, '\\"')}"`
 }
 
 /**
@@ -632,6 +799,59 @@ function getTmpdirParentIfMacOSPattern(): string[] {
   return [parent]
 }
 
+/** Directory of this session's generated profiles, created on first use */
+let profileDir: string | undefined
+
+function getProfileDir(): string {
+  if (!profileDir) {
+    // mkdtemp creates the directory with mode 0700
+    profileDir = fs.mkdtempSync(path.join(os.tmpdir(), 'srt-profiles-'))
+  }
+  return profileDir
+}
+
+/**
+ * Write a profile to the session's profile directory, named by the hash of
+ * its content so identical profiles are written once. Passing sandbox-exec a
+ * file keeps large profiles under ARG_MAX and the denied paths out of ps.
+ */
+function writeProfileFile(profile: string): string {
+  const hash = createHash('sha256').update(profile).digest('hex')
+  const profilePath = path.join(getProfileDir(), `${hash.slice(0, 32)}.sb`)
+  try {
+    if (fs.readFileSync(profilePath, 'utf8') === profile) {
+      return profilePath
+    }
+  } catch {
+    // Not written yet
+  }
+  // Write to a temporary name and rename, so sandbox-exec never sees a
+  // partially written profile
+  const tmpPath = `${profilePath}.${process.pid}.tmp`
+  fs.writeFileSync(tmpPath, profile, { mode: 0o600 })
+  fs.renameSync(tmpPath, profilePath)
+  return profilePath
+}
+
+/**
+ * Remove the profiles generated by this session
+ */
+export function cleanupMacOSProfiles(): void {
+  if (!profileDir) {
+    return
+  }
+  try {
+    fs.rmSync(profileDir, { recursive: true, force: true })
+    logForDebugging(`[Sandbox macOS] Removed profile directory ${profileDir}`)
+  } catch (error) {
+    logForDebugging(
+      `[Sandbox macOS] Failed to remove profile directory ${profileDir}: ${error}`,
+      { level: 'error' },
+    )
+  }
+  profileDir = undefined
+}
+
 /**
  * Wrap command with macOS sandbox
  */
@@ -646,11 +866,16 @@ export function wrapCommandWithSandboxMacOS(
     allowUnixSockets,
     allowAllUnixSockets,
     allowLocalBinding,
+    allowDns,
     readConfig,
     writeConfig,
     allowPty,
     allowGitConfig = false,
     binShell,
+    scratchDir,
+    seatbelt,
+    environment,
+    redact,
   } = params
 
   // Determine if we have restrictions to apply
@@ -668,7 +893,7 @@ export function wrapCommandWithSandboxMacOS(
     return command
   }
 
-  const logTag = generateLogTag(command)
+  const logTag = generateLogTag(command, redact)
 
   const profile = generateSandboxProfile({
     readConfig,
@@ -679,13 +904,23 @@ export function wrapCommandWithSandboxMacOS(
     allowUnixSockets,
     allowAllUnixSockets,
     allowLocalBinding,
+    allowDns,
     allowPty,
     allowGitConfig,
+    template: seatbelt?.template,
+    machServices: seatbelt?.machServices,
+    extraRules: seatbelt?.extraRules,
+    profileDir: getProfileDir(),
     logTag,
   })
+  const profilePath = writeProfileFile(profile)
 
   // Generate proxy environment variables using shared utility
-  const proxyEnvArgs = generateProxyEnvVars(httpProxyPort, socksProxyPort)
+  const proxyEnvArgs = generateProxyEnvVars(
+    httpProxyPort,
+    socksProxyPort,
+    scratchDir,
+  )
 
   // Use the user's shell (zsh, bash, etc.) to ensure aliases/snapshots work
   // Resolve the full path to the shell binary
@@ -700,10 +935,11 @@ export function wrapCommandWithSandboxMacOS(
   // argument that shellquote handles properly, avoiding shell quoting issues
   const wrappedCommand = shellquote.quote([
     'env',
+    ...(environment ? toEnvUnsetArgs(environment) : []),
     ...proxyEnvArgs,
     'sandbox-exec',
-    '-p',
-    profile,
+    '-f',
+    profilePath,
     shell,
     '-c',
     command,
@@ -729,62 +965,62 @@ export function wrapCommandWithSandboxMacOS(
 }
 
 /**
- * Start monitoring macOS system logs for sandbox violations
- * Look for sandbox-related kernel deny events ending in {logTag}
+ * Process and operation of a seatbelt violation message,
+ * e.g. "curl(4242) deny(1) network-outbound 93.184.216.34:443"
  */
-export function startMacOSSandboxLogMonitor(
+export interface MacOSViolationDetails {
+  processName: string
+  pid: number
+  operation: string
+  target?: string
+}
+
+const violationDetailsRegex = /^(.+?)\((\d+)\)\s+deny\(\d+\)\s+(\S+)(?:\s+(.+))?$/
+
+export function parseMacOSViolationDetails(
+  details: string,
+): MacOSViolationDetails | undefined {
+  const match = violationDetailsRegex.exec(details.trim())
+  if (!match?.[1] || !match[2] || !match[3]) {
+    return undefined
+  }
+  return {
+    processName: match[1],
+    pid: Number.parseInt(match[2], 10),
+    operation: match[3],
+    target: match[4],
+  }
+}
+
+/**
+ * Build the `log stream` output handler that turns violations into events
+ *
+ * The kernel logs each violation as a "Sandbox: <process>(<pid>) deny(1) ..."
+ * line followed by the message from the profile rule, which is the log tag of
+ * the command. Only violations followed by a tag from this session are
+ * reported, so denials of unrelated processes never reach the store.
+ */
+export function createMacOSLogStreamHandler(
   callback: SandboxViolationCallback,
   ignoreViolations?: IgnoreViolationsConfig,
-): () => void {
+): (data: Buffer | string) => void {
   // Pre-compile regex patterns for better performance
   const cmdExtractRegex = /CMD64_(.+?)_END/
   const sandboxExtractRegex = /Sandbox:\s+(.+)$/
 
-  // Pre-process ignore patterns for faster lookup
-  const wildcardPaths = ignoreViolations?.['*'] || []
-  const commandPatterns = ignoreViolations
-    ? Object.entries(ignoreViolations).filter(([pattern]) => pattern !== '*')
-    : []
-
-  // Stream and filter kernel logs for all sandbox violations
-  // We can't filter by specific logTag since it's dynamic per command
-  const logProcess = spawn('log', [
-    'stream',
-    '--predicate',
-    `(eventMessage ENDSWITH "${sessionSuffix}")`,
-    '--style',
-    'compact',
-  ])
-
-  logProcess.stdout?.on('data', (data: Buffer) => {
-    const lines = data.toString().split('\n')
-
-    // Get violation and command lines
-    const violationLine = lines.find(
-      line => line.includes('Sandbox:') && line.includes('deny'),
-    )
-    const commandLine = lines.find(line => line.startsWith('CMD64_'))
-
-    if (!violationLine) return
-
-    // Extract violation details
-    const sandboxMatch = violationLine.match(sandboxExtractRegex)
-    if (!sandboxMatch?.[1]) return
-
-    const violationDetails = sandboxMatch[1]
+  // Chunks can end mid-line, and a violation's tag can arrive in the next one
+  let partialLine = ''
+  let pendingViolation: string | undefined
 
+  const report = (violationDetails: string, tagLine: string) => {
     // Try to get command
     let command: string | undefined
-    let encodedCommand: string | undefined
-    if (commandLine) {
-      const cmdMatch = commandLine.match(cmdExtractRegex)
-      encodedCommand = cmdMatch?.[1]
-      if (encodedCommand) {
-        try {
-          command = decodeSandboxedCommand(encodedCommand)
-        } catch {
-          // Failed to decode, continue without command
-        }
+    const encodedCommand = tagLine.match(cmdExtractRegex)?.[1]
+    if (encodedCommand) {
+      try {
+        command = decodeSandboxedCommand(encodedCommand)
+      } catch {
+        // Failed to decode, continue without command
       }
     }
 
@@ -798,34 +1034,82 @@ export function startMacOSSandboxLogMonitor(
     }
 
     // Check if we should ignore this violation
-    if (ignoreViolations && command) {
-      // Check wildcard patterns first
-      if (wildcardPaths.length > 0) {
-        const shouldIgnore = wildcardPaths.some(path =>
-          violationDetails.includes(path),
-        )
-        if (shouldIgnore) return
-      }
-
-      // Check command-specific patterns
-      for (const [pattern, paths] of commandPatterns) {
-        if (command.includes(pattern)) {
-          const shouldIgnore = paths.some(path =>
-            violationDetails.includes(path),
-          )
-          if (shouldIgnore) return
-        }
-      }
+    const parsed = parseMacOSViolationDetails(violationDetails)
+    if (
+      isViolationIgnored(
+        { line: violationDetails, command, path: parsed?.target },
+        ignoreViolations,
+      )
+    ) {
+      return
     }
 
-    // Not ignored - report the violation
+    // Not ignored - report the violation with the process that caused it
     callback({
       line: violationDetails,
       command,
       encodedCommand,
       timestamp: new Date(), // We could parse the timestamp from the log but this feels more reliable
+      pid: parsed?.pid,
+      processName: parsed?.processName,
+      path: parsed?.target,
     })
-  })
+  }
+
+  return data => {
+    const lines = (partialLine + data.toString()).split('\n')
+    partialLine = lines.pop() ?? ''
+
+    for (const line of lines) {
+      const sandboxMatch = line.includes('deny')
+        ? line.match(sandboxExtractRegex)
+        : null
+      if (sandboxMatch?.[1] && line.includes(sessionSuffix)) {
+        // Tag logged on the same line as the violation
+        report(sandboxMatch[1].replace(/\s*CMD64_\S+$/, ''), line)
+        pendingViolation = undefined
+      } else if (sandboxMatch?.[1]) {
+        // A violation without a tag of ours belongs to some other process
+        pendingViolation = sandboxMatch[1]
+      } else if (line.includes(sessionSuffix) && pendingViolation) {
+        report(pendingViolation, line)
+        pendingViolation = undefined
+      }
+    }
+  }
+}
+
+/**
+ * Start monitoring macOS system logs for sandbox violations
+ * Look for sandbox-related kernel deny events ending in {logTag}
+ */
+export function startMacOSSandboxLogMonitor(
+  callback: SandboxViolationCallback,
+  ignoreViolations?: IgnoreViolationsConfig,
+): () => void {
+  // Stream and filter kernel logs for all sandbox violations
+  // We can't filter by specific logTag since it's dynamic per command
+  const logProcess = spawn('log', [
+    'stream',
+    '--predicate',
+    `(eventMessage ENDSWITH "${sessionSuffix}")`,
+    '--style',
+    'compact',
+  ])
+
+  // Look the process up while it is likely still running
+  logProcess.stdout?.on(
+    'data',
+    createMacOSLogStreamHandler(violation => {
+      if (violation.pid === undefined) {
+        callback(violation)
+        return
+      }
+      void getMacOSProcessAttribution(violation.pid).then(attribution =>
+        callback({ ...violation, ...attribution }),
+      )
+    }, ignoreViolations),
+  )
 
   logProcess.stderr?.on('data', (data: Buffer) => {
     logForDebugging(`[Sandbox Monitor] Log stream stderr: ${data.toString()}`)
@@ -0,0 +1,114 @@
+import { mergeConfigOverrides } from './command-policy.js'
+import type { SandboxStreamingExecution } from './execution-stream.js'
+import type { SandboxRuntimeConfig } from './sandbox-config.js'
+import type {
+  SandboxCommandOutput,
+  SandboxExecuteOptions,
+  SandboxExecuteResult,
+  SandboxExecutionHandle,
+  SandboxOutputOptions,
+} from './sandbox-execute.js'
+import type { ISandboxManager } from './sandbox-manager.js'
+import type {
+  SandboxSession,
+  SandboxSessionOptions,
+} from './sandbox-session.js'
+
+/**
+ * Named sandboxes (`SandboxManager.sandbox(name)`)
+ *
+ * Several sandboxes under one initialized manager, e.g. one per step of a
+ * pipeline, each with its own policy overlay. The overlay is passed as the
+ * customConfig of every command, so its settings replace those of the config
+ * the way customConfig does. A customConfig given to a call replaces settings
+ * of the overlay, except the deny lists, which are merged so a call can't
+ * drop what the sandbox denies. The overlay of a sandbox never changes. The
+ * proxies, backend and violation store are shared, so adding a sandbox
+ * starts nothing.
+ */
+
+export class NamedSandbox {
+  constructor(
+    readonly name: string,
+    private readonly manager: ISandboxManager,
+    /** Settings of this sandbox on top of the manager's config */
+    readonly overlay: Partial<SandboxRuntimeConfig> = {},
+  ) {}
+
+  private withOverlay(
+    customConfig?: Partial<SandboxRuntimeConfig>,
+  ): Partial<SandboxRuntimeConfig> {
+    return mergeConfigOverrides(this.overlay, customConfig)
+  }
+
+  wrapWithSandbox(
+    command: string,
+    binShell?: string,
+    customConfig?: Partial<SandboxRuntimeConfig>,
+    abortSignal?: AbortSignal,
+  ): Promise<string> {
+    return this.manager.wrapWithSandbox(
+      command,
+      binShell,
+      this.withOverlay(customConfig),
+      abortSignal,
+    )
+  }
+
+  getCommandEnv(
+    command: string,
+    customConfig?: Partial<SandboxRuntimeConfig>,
+  ): NodeJS.ProcessEnv {
+    return this.manager.getCommandEnv(command, this.withOverlay(customConfig))
+  }
+
+  execute(
+    command: string,
+    options: SandboxExecuteOptions = {},
+  ): Promise<SandboxExecuteResult> {
+    return this.manager.execute(command, {
+      ...options,
+      customConfig: this.withOverlay(options.customConfig),
+    })
+  }
+
+  start(
+    command: string,
+    options: SandboxExecuteOptions = {},
+  ): SandboxExecutionHandle {
+    return this.manager.start(command, {
+      ...options,
+      customConfig: this.withOverlay(options.customConfig),
+    })
+  }
+
+  executeWithOutput(
+    command: string,
+    options: SandboxOutputOptions = {},
+  ): Promise<SandboxCommandOutput> {
+    return this.manager.executeWithOutput(command, {
+      ...options,
+      customConfig: this.withOverlay(options.customConfig),
+    })
+  }
+
+  executeStreaming(
+    command: string,
+    options: Omit<SandboxExecuteOptions, 'stdio' | 'onSpawn'> = {},
+  ): SandboxStreamingExecution {
+    return this.manager.executeStreaming(command, {
+      ...options,
+      customConfig: this.withOverlay(options.customConfig),
+    })
+  }
+
+  openSession(
+    options: Pick<SandboxExecuteOptions, 'binShell' | 'customConfig'> &
+      Pick<SandboxSessionOptions, 'maxBuffer'> = {},
+  ): Promise<SandboxSession> {
+    return this.manager.openSession({
+      ...options,
+      customConfig: this.withOverlay(options.customConfig),
+    })
+  }
+}
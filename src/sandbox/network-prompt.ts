@@ -0,0 +1,119 @@
+import type { NetworkHostPattern } from './sandbox-schemas.js'
+
+/**
+ * Interactive network prompts (`srt --ask`)
+ *
+ * An ask callback that asks the user about every connection to a domain the
+ * config does not allow. The proxy holds the connection until the answer:
+ * allow it once, allow the domain for the rest of the run, allow it and save
+ * it to a settings file, or deny it. Denied domains are not asked about
+ * again during the run. Prompts are shown one at a time, and connections
+ * waiting on the same domain share its prompt.
+ */
+
+export type NetworkPromptAnswer = 'once' | 'always' | 'save' | 'deny'
+
+const ANSWER_KEYS: Record<string, NetworkPromptAnswer> = {
+  y: 'once',
+  yes: 'once',
+  a: 'always',
+  always: 'always',
+  s: 'save',
+  save: 'save',
+  n: 'deny',
+  no: 'deny',
+  // Pressing enter denies
+  '': 'deny',
+}
+
+/**
+ * Parse an answer to a prompt, undefined when it is not one
+ */
+export function parseNetworkPromptAnswer(
+  input: string,
+): NetworkPromptAnswer | undefined {
+  return ANSWER_KEYS[input.trim().toLowerCase()]
+}
+
+/** Question shown for a connection */
+export function formatNetworkPrompt({
+  host,
+  port,
+}: NetworkHostPattern): string {
+  const target = port === undefined ? host : `${host}:${port}`
+  return `Allow connection to ${target}? [y] once, [a] always, [s] always and save, [N] deny: `
+}
+
+export class NetworkPrompter {
+  private readonly allowed = new Set<string>()
+  private readonly denied = new Set<string>()
+  private readonly pending = new Map<string, Promise<boolean>>()
+  private queue: Promise<unknown> = Promise.resolve()
+
+  /**
+   * @param question Shows a prompt and resolves to the line typed
+   * @param save Persists a domain answered with save
+   */
+  constructor(
+    private readonly question: (prompt: string) => Promise<string>,
+    private readonly save?: (host: string) => void,
+  ) {}
+
+  /** The ask callback to pass to SandboxManager.initialize */
+  readonly ask = async (params: NetworkHostPattern): Promise<boolean> => {
+    const host = params.host.toLowerCase()
+    const decided = this.getDecision(host)
+    if (decided !== undefined) {
+      return decided
+    }
+    const pending = this.pending.get(host)
+    if (pending) {
+      return pending
+    }
+    const decision = this.queue.then(() => this.prompt({ ...params, host }))
+    this.queue = decision.catch(() => undefined)
+    this.pending.set(host, decision)
+    try {
+      return await decision
+    } finally {
+      this.pending.delete(host)
+    }
+  }
+
+  private getDecision(host: string): boolean | undefined {
+    if (this.allowed.has(host)) {
+      return true
+    }
+    return this.denied.has(host) ? false : undefined
+  }
+
+  private async prompt(params: NetworkHostPattern): Promise<boolean> {
+    // A prompt answered while this one was queued may have decided it
+    const decided = this.getDecision(params.host)
+    if (decided !== undefined) {
+      return decided
+    }
+    for (;;) {
+      const answer = parseNetworkPromptAnswer(
+        await this.question(formatNetworkPrompt(params)),
+      )
+      if (!answer) {
+        continue
+      }
+      switch (answer) {
+        case 'once':
+          return true
+        case 'save':
+          this.save?.(params.host)
+          this.allowed.add(params.host)
+          return true
+        case 'always':
+          this.allowed.add(params.host)
+          return true
+        case 'deny':
+          this.denied.add(params.host)
+          return false
+      }
+    }
+  }
+}
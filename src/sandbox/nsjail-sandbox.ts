@@ -0,0 +1,170 @@
+import { spawnSync } from 'node:child_process'
+
+/**
+ * nsjail backend for the Linux sandbox
+ *
+ * The Linux wrapper always builds a bwrap argument list (mounts, environment,
+ * namespaces, and the command). For users who deploy nsjail instead of
+ * bubblewrap, that list is translated flag by flag into the equivalent nsjail
+ * invocation, so both backends enforce the same FilesystemConfig and
+ * NetworkConfig. Options without an nsjail equivalent are refused; only
+ * /dev/pts, which nsjail cannot mount, is missing from its /dev.
+ */
+
+/** Host device nodes bwrap's --dev exposes, bound into nsjail's /dev tmpfs */
+const MINIMAL_DEV_NODES = ['null', 'zero', 'full', 'random', 'urandom', 'tty']
+
+/** Symlinks bwrap's --dev creates, as [target, name within /dev] */
+const MINIMAL_DEV_SYMLINKS = [
+  ['/proc/self/fd', 'fd'],
+  ['/proc/self/fd/0', 'stdin'],
+  ['/proc/self/fd/1', 'stdout'],
+  ['/proc/self/fd/2', 'stderr'],
+]
+
+let nsjailAvailable: boolean | undefined
+
+/**
+ * Check if nsjail is installed (cached)
+ */
+export function isNsjailAvailable(): boolean {
+  if (nsjailAvailable === undefined) {
+    const result = spawnSync('which', ['nsjail'], {
+      stdio: 'ignore',
+      timeout: 1000,
+    })
+    nsjailAvailable = result.status === 0
+  }
+  return nsjailAvailable
+}
+
+/**
+ * Translate bwrap arguments into nsjail arguments
+ *
+ * nsjail defaults already match what the sandbox asks bwrap for: new user, PID,
+ * network, mount, IPC and UTS namespaces, setsid(), and the jail is killed with
+ * its parent. Its default rlimits and time limit are disabled so commands behave
+ * as they do under bwrap (resource limits come from the cgroup scope instead).
+ *
+ * Throws on bwrap options that have no nsjail equivalent, rather than silently
+ * dropping a restriction.
+ */
+export function bwrapArgsToNsjailArgs(
+  bwrapArgs: string[],
+  cwd: string = process.cwd(),
+): string[] {
+  const args = [
+    '--mode',
+    'o',
+    '--quiet',
+    '--keep_env',
+    '--disable_rlimits',
+    '--time_limit',
+    '0',
+    '--cwd',
+    cwd,
+  ]
+  let unshareNet = false
+  let mountProc = false
+
+  let i = 0
+  const next = (flag: string): string => {
+    const value = bwrapArgs[++i]
+    if (value === undefined) {
+      throw new Error(`bwrap option ${flag} is missing its argument`)
+    }
+    return value
+  }
+
+  for (; i < bwrapArgs.length; i++) {
+    const flag = bwrapArgs[i]
+    switch (flag) {
+      case '--new-session':
+      case '--die-with-parent':
+      case '--unshare-pid':
+      case '--unshare-user':
+        // nsjail default
+        break
+      case '--unshare-net':
+        unshareNet = true
+        break
+      case '--cap-drop':
+        if (next(flag) !== 'ALL') {
+          throw new Error('nsjail backend only supports --cap-drop ALL')
+        }
+        // nsjail default: all capabilities are dropped
+        break
+      case '--cap-add':
+        args.push('--cap', next(flag))
+        break
+      case '--uid':
+        args.push('--user', next(flag))
+        break
+      case '--gid':
+        args.push('--group', next(flag))
+        break
+      case '--ro-bind':
+        args.push('--bindmount_ro', `${next(flag)}:${next(flag)}`)
+        break
+      case '--bind':
+        args.push('--bindmount', `${next(flag)}:${next(flag)}`)
+        break
+      case '--size':
+        // nsjail has one size for all of its tmpfs mounts
+        args.push('--tmpfs_size', next(flag))
+        break
+      case '--tmpfs':
+        args.push('--tmpfsmount', next(flag))
+        break
+      case '--dev': {
+        // Like bwrap's minimal /dev: a tmpfs with only the harmless device
+        // nodes bound from the host, since a read-only bind of the host /dev
+        // would still let the command write to every device. There is no
+        // /dev/pts, so commands cannot open new pseudo-terminals
+        const dev = next(flag)
+        args.push('--tmpfsmount', dev)
+        for (const node of MINIMAL_DEV_NODES) {
+          args.push('--bindmount', `/dev/${node}:${dev}/${node}`)
+        }
+        for (const [target, link] of MINIMAL_DEV_SYMLINKS) {
+          args.push('--symlink', `${target}:${dev}/${link}`)
+        }
+        args.push('--tmpfsmount', `${dev}/shm`)
+        break
+      }
+      case '--proc':
+        if (next(flag) !== '/proc') {
+          throw new Error(
+            'nsjail backend only supports /proc as the proc mount',
+          )
+        }
+        mountProc = true
+        break
+      case '--clearenv': {
+        // Without --keep_env nsjail only passes the --env variables
+        const keepEnv = args.indexOf('--keep_env')
+        if (keepEnv !== -1) {
+          args.splice(keepEnv, 1)
+        }
+        break
+      }
+      case '--setenv': {
+        const key = next(flag)
+        args.push('--env', `${key}=${next(flag)}`)
+        break
+      }
+      case '--':
+        if (!unshareNet) {
+          args.push('--disable_clone_newnet')
+        }
+        if (!mountProc) {
+          args.push('--disable_proc')
+        }
+        return [...args, '--', ...bwrapArgs.slice(i + 1)]
+      default:
+        throw new Error(`nsjail backend does not support bwrap option ${flag}`)
+    }
+  }
+
+  throw new Error('bwrap arguments do not contain a command')
+}
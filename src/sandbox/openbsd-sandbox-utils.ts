@@ -0,0 +1,252 @@
+import shellquote from 'shell-quote'
+import { spawnSync } from 'node:child_process'
+import * as fs from 'node:fs'
+import { dirname, join } from 'node:path'
+import { fileURLToPath } from 'node:url'
+import { logForDebugging } from '../utils/debug.js'
+import { getVendorArchitecture } from './generate-seccomp-filter.js'
+import { macGetMandatoryDenyPatterns } from './macos-sandbox-utils.js'
+import { containsGlobChars, normalizePathForSandbox } from './sandbox-utils.js'
+import { expandGlobPath } from './glob-expansion.js'
+import { toEnvUnsetArgs } from './sandbox-env.js'
+import type {
+  FsReadRestrictionConfig,
+  FsWriteRestrictionConfig,
+} from './sandbox-schemas.js'
+
+/**
+ * OpenBSD sandboxing with unveil(2) and pledge(2)
+ *
+ * The apply-pledge helper unveils the filesystem view derived from the read
+ * and write config, then execs the command under a pledge promise set. Both
+ * are enforced by the kernel and inherited by every child.
+ *
+ * pledge can only allow or forbid sockets as a whole, so a restricted network
+ * cannot be narrowed to the filtering proxies: any network restriction drops
+ * the inet and dns promises and blocks the network entirely. Unix sockets
+ * work the same way: the unix promise is only granted when allowUnixSockets
+ * or allowAllUnixSockets allows them, and listed sockets are unveiled
+ * writable, which connecting to them needs.
+ */
+
+export interface OpenBSDSandboxParams {
+  command: string
+  needsNetworkRestriction: boolean
+  readConfig: FsReadRestrictionConfig | undefined
+  writeConfig: FsWriteRestrictionConfig | undefined
+  allowGitConfig?: boolean
+  /** Unix socket paths the command may connect to */
+  allowUnixSockets?: string[]
+  allowAllUnixSockets?: boolean
+  binShell?: string
+  /** Environment of the command; srt's other variables are unset (default: srt's environment) */
+  environment?: Record<string, string>
+}
+
+/** Promises every sandboxed command gets; network promises are added on top */
+const BASE_PROMISES = [
+  'stdio',
+  'rpath',
+  'wpath',
+  'cpath',
+  'dpath',
+  'fattr',
+  'flock',
+  'tty',
+  'proc',
+  'exec',
+  'prot_exec',
+  'getpw',
+  'sendfd',
+  'recvfd',
+  'ps',
+  'vminfo',
+]
+
+const NETWORK_PROMISES = ['inet', 'dns']
+
+/**
+ * Get the path to the apply-pledge binary
+ * Looks in vendor/openbsd/{x64,arm64}/apply-pledge, then on PATH
+ */
+export function getApplyPledgeBinaryPath(): string | null {
+  const arch = getVendorArchitecture()
+  if (arch) {
+    // Path is relative to the compiled code location (dist/sandbox/)
+    const baseDir = dirname(fileURLToPath(import.meta.url))
+    const relativePath = join('vendor', 'openbsd', arch, 'apply-pledge')
+
+    const pathsToTry = [
+      join(baseDir, relativePath), // bundled
+      join(baseDir, '..', '..', relativePath), // package root: vendor/openbsd/...
+      join(baseDir, '..', relativePath), // dist: dist/vendor/openbsd/...
+    ]
+
+    for (const binaryPath of pathsToTry) {
+      if (fs.existsSync(binaryPath)) {
+        logForDebugging(`[OpenBSD] Found apply-pledge binary: ${binaryPath}`)
+        return binaryPath
+      }
+    }
+  }
+
+  const result = spawnSync('which', ['apply-pledge'], {
+    encoding: 'utf8',
+    timeout: 1000,
+  })
+  if (result.status === 0 && result.stdout.trim()) {
+    return result.stdout.trim()
+  }
+
+  logForDebugging('[OpenBSD] apply-pledge binary not found')
+  return null
+}
+
+/**
+ * Check if the OpenBSD sandbox can be applied on this system
+ */
+export function hasOpenBSDSandboxDependenciesSync(): boolean {
+  return getApplyPledgeBinaryPath() !== null
+}
+
+/**
+ * The pledge promises of a sandboxed command
+ */
+export function getPledgePromises(
+  needsNetworkRestriction: boolean,
+  allowUnixSockets = false,
+): string {
+  return [
+    ...BASE_PROMISES,
+    ...(needsNetworkRestriction ? [] : NETWORK_PROMISES),
+    ...(allowUnixSockets ? ['unix'] : []),
+  ].join(' ')
+}
+
+/**
+ * Map the filesystem config onto unveil rules, as "permissions:path" specs
+ *
+ * "/" is unveiled read-only (or writable when writes are unrestricted), write
+ * paths are unveiled "rwxc", paths denied within them drop back to "rx" and
+ * denied reads are hidden with "". unveil applies the most specific rule, so
+ * the order does not matter. unveil only takes paths, so glob patterns
+ * (including the mandatory deny ones) are unveiled as the paths they match
+ * now. Allowed Unix sockets are unveiled "rw".
+ */
+export function buildUnveilRules(
+  readConfig: FsReadRestrictionConfig | undefined,
+  writeConfig: FsWriteRestrictionConfig | undefined,
+  allowGitConfig = false,
+  allowUnixSockets: string[] = [],
+): string[] {
+  // Later rules for the same path replace earlier ones
+  const rules = new Map<string, string>()
+  const add = (permissions: string, pathPattern: string) => {
+    const rule = permissions === '' || permissions === 'rx' ? 'deny' : 'allow'
+    const paths = containsGlobChars(pathPattern)
+      ? expandGlobPath(pathPattern, rule)
+      : [pathPattern]
+    for (const rulePath of paths) {
+      rules.set(normalizePathForSandbox(rulePath), permissions)
+    }
+  }
+
+  add(writeConfig ? 'rx' : 'rwxc', '/')
+
+  if (writeConfig) {
+    for (const allowPath of writeConfig.allowOnly) {
+      add('rwxc', allowPath)
+    }
+    for (const denyPath of [
+      ...writeConfig.denyWithinAllow,
+      ...macGetMandatoryDenyPatterns(allowGitConfig),
+    ]) {
+      add('rx', denyPath)
+    }
+  }
+
+  for (const socketPath of allowUnixSockets) {
+    add('rw', socketPath)
+  }
+
+  for (const denyPath of readConfig?.denyOnly ?? []) {
+    add('', denyPath)
+  }
+
+  return [...rules].map(
+    ([rulePath, permissions]) => `${permissions}:${rulePath}`,
+  )
+}
+
+/**
+ * Wrap command with the OpenBSD sandbox
+ */
+export function wrapCommandWithSandboxOpenBSD(
+  params: OpenBSDSandboxParams,
+): string {
+  const {
+    command,
+    needsNetworkRestriction,
+    readConfig,
+    writeConfig,
+    allowGitConfig = false,
+    allowUnixSockets = [],
+    allowAllUnixSockets = false,
+    binShell,
+    environment,
+  } = params
+
+  const hasReadRestrictions = readConfig && readConfig.denyOnly.length > 0
+  const hasWriteRestrictions = writeConfig !== undefined
+
+  // No sandboxing needed
+  if (
+    !needsNetworkRestriction &&
+    !hasReadRestrictions &&
+    !hasWriteRestrictions
+  ) {
+    return command
+  }
+
+  const binaryPath = getApplyPledgeBinaryPath()
+  if (!binaryPath) {
+    throw new Error(
+      'apply-pledge binary not found. Build it with ' +
+        '`cc -O2 -o apply-pledge vendor/openbsd-src/apply-pledge.c` and put it on PATH.',
+    )
+  }
+
+  const shellName = binShell || 'sh'
+  const shellPathResult = spawnSync('which', [shellName], { encoding: 'utf8' })
+  if (shellPathResult.status !== 0) {
+    throw new Error(`Shell '${shellName}' not found in PATH`)
+  }
+  const shell = shellPathResult.stdout.trim()
+
+  const unveilArgs = buildUnveilRules(
+    readConfig,
+    writeConfig,
+    allowGitConfig,
+    allowAllUnixSockets ? [] : allowUnixSockets,
+  ).flatMap(rule => ['--unveil', rule])
+
+  logForDebugging(
+    `[OpenBSD] Applied restrictions - network: ${needsNetworkRestriction ? 'blocked' : 'open'}, ` +
+      `unveil rules: ${unveilArgs.length / 2}`,
+  )
+
+  return shellquote.quote([
+    ...(environment ? ['env', ...toEnvUnsetArgs(environment)] : []),
+    binaryPath,
+    ...unveilArgs,
+    '--promises',
+    getPledgePromises(
+      needsNetworkRestriction,
+      allowAllUnixSockets || allowUnixSockets.length > 0,
+    ),
+    '--',
+    shell,
+    '-c',
+    command,
+  ])
+}
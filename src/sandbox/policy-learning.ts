@@ -0,0 +1,183 @@
+import * as os from 'node:os'
+import * as path from 'node:path'
+import type { SandboxViolationEvent } from './macos-sandbox-utils.js'
+import type { SandboxRuntimeConfig } from './sandbox-config.js'
+import { getDefaultWritePaths } from './sandbox-utils.js'
+
+/**
+ * Learning mode (`srt learn`)
+ *
+ * The command runs with the allow lists opened up and everything it does
+ * recorded: every connection goes through the proxies and is allowed by an
+ * ask callback that records the host, and writes are allowed everywhere
+ * (except the always-denied paths) with auditFileAccess on. The deny lists
+ * of the base config still apply. What was observed then becomes a minimal
+ * config: the domains connected to and the directories written to.
+ */
+
+/** Writes here are not configurable, so they are left out of allowWrite */
+const UNCONFIGURABLE_PATH_PREFIXES = ['/dev/', '/proc/', '/sys/']
+
+/**
+ * The config a learning run uses: the base config with its allow lists
+ * opened up, file access audited and no violation policy. allowedDomains is
+ * emptied and askUnlistedDomains set so every connection reaches the ask
+ * callback, and temporary rules are dropped, since domains they allow would
+ * bypass the recording.
+ */
+export function getLearningConfig(
+  base: SandboxRuntimeConfig,
+): SandboxRuntimeConfig {
+  return {
+    ...base,
+    network: {
+      ...base.network,
+      allowedDomains: [],
+      askUnlistedDomains: true,
+    },
+    filesystem: { ...base.filesystem, allowWrite: ['/'] },
+    auditFileAccess: true,
+    violationPolicy: undefined,
+    temporaryRules: undefined,
+  }
+}
+
+function isUnder(target: string, dir: string): boolean {
+  const prefix = dir.endsWith('/') ? dir : dir + '/'
+  return target === dir || target.startsWith(prefix)
+}
+
+/**
+ * Collects what a learning run did and turns it into a minimal config
+ */
+export class PolicyLearner {
+  private readonly connections = new Map<string, Set<number>>()
+  private readonly writes = new Set<string>()
+  private readonly blocked = new Set<string>()
+  private auditedAccesses = 0
+
+  constructor(private readonly cwd = process.cwd()) {}
+
+  /** Record a connection the proxies were asked to make */
+  recordConnection(host: string, port: number | undefined): void {
+    const ports = this.connections.get(host) ?? new Set<number>()
+    if (port !== undefined) {
+      ports.add(port)
+    }
+    this.connections.set(host, ports)
+  }
+
+  add(violation: SandboxViolationEvent): void {
+    if (violation.type !== 'file-access') {
+      // Denials by the base config's deny lists, cloud metadata, SMTP...
+      this.blocked.add(violation.line)
+      return
+    }
+    this.auditedAccesses++
+    if (violation.access === 'write' && violation.path !== undefined) {
+      this.writes.add(path.resolve(this.cwd, violation.path))
+    }
+  }
+
+  /** Hosts connected to, with the ports used */
+  getConnections(): { host: string; ports: number[] }[] {
+    return [...this.connections]
+      .sort(([a], [b]) => a.localeCompare(b))
+      .map(([host, ports]) => ({
+        host,
+        ports: [...ports].sort((a, b) => a - b),
+      }))
+  }
+
+  /** Violations recorded during the run, each once */
+  getBlocked(): string[] {
+    return [...this.blocked]
+  }
+
+  /** Whether file accesses were audited at all during the run */
+  hasAuditedAccesses(): boolean {
+    return this.auditedAccesses > 0
+  }
+
+  /**
+   * The directories written to, without those inside another one or
+   * already writable by default, as config paths (`./` for the working
+   * directory, `~/` for the home directory)
+   */
+  getWriteDirectories(): string[] {
+    const defaults = getDefaultWritePaths()
+    const dirs = [...this.writes]
+      .filter(
+        written =>
+          !UNCONFIGURABLE_PATH_PREFIXES.some(prefix =>
+            written.startsWith(prefix),
+          ) && !defaults.some(dir => isUnder(written, dir)),
+      )
+      .map(written => path.dirname(written))
+      .sort()
+    return [...new Set(dirs)]
+      .filter(
+        (dir, _, all) =>
+          !all.some(other => other !== dir && isUnder(dir, other)),
+      )
+      .map(dir => this.toConfigPath(dir))
+  }
+
+  private toConfigPath(dir: string): string {
+    if (isUnder(dir, this.cwd)) {
+      const relative = path.relative(this.cwd, dir)
+      return relative ? `./${relative}` : '.'
+    }
+    const home = os.homedir()
+    if (isUnder(dir, home)) {
+      return `~/${path.relative(home, dir)}`
+    }
+    return dir
+  }
+
+  /**
+   * The minimal config allowing what was observed, keeping the deny lists
+   * of the base config
+   */
+  buildConfig(base: SandboxRuntimeConfig): SandboxRuntimeConfig {
+    return {
+      network: {
+        allowedDomains: this.getConnections().map(({ host }) => host),
+        deniedDomains: base.network.deniedDomains,
+      },
+      filesystem: {
+        denyRead: base.filesystem.denyRead,
+        allowWrite: this.getWriteDirectories(),
+        denyWrite: base.filesystem.denyWrite,
+      },
+    }
+  }
+}
+
+/**
+ * Describe what a learning run observed, for stderr next to the config
+ */
+export function formatLearningReport(learner: PolicyLearner): string {
+  const connections = learner.getConnections()
+  const writes = learner.getWriteDirectories()
+  const lines = [
+    `Observed ${connections.length} domains and ${writes.length} write directories`,
+    ...connections.map(
+      ({ host, ports }) => `  connect ${host}:${ports.join(',') || '?'}`,
+    ),
+    ...writes.map(dir => `  write   ${dir}`),
+  ]
+  if (!learner.hasAuditedAccesses()) {
+    lines.push(
+      'Warning: no file accesses were audited (this needs strace on Linux, or root on macOS), so allowWrite is empty',
+    )
+  }
+  const blocked = learner.getBlocked()
+  if (blocked.length > 0) {
+    lines.push(
+      'Blocked during the run (not added to the config):',
+      ...blocked.map(line => `  ${line}`),
+    )
+  }
+  return lines.join('\n')
+}
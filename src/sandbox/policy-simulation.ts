@@ -0,0 +1,299 @@
+import { isIP } from 'node:net'
+import { applyAllowListExclusions } from './allow-list-exclusions.js'
+import { applyConfigPreset } from './config-presets.js'
+import { isCloudMetadataHost } from './host-resolver.js'
+import { macGetMandatoryDenyPatterns } from './macos-sandbox-utils.js'
+import type { SandboxRuntimeConfig } from './sandbox-config.js'
+import { matchesDomainPattern, SMTP_PORTS } from './sandbox-manager.js'
+import {
+  containsGlobChars,
+  getDefaultWritePaths,
+  globToRegex,
+  normalizePathForSandbox,
+  removeTrailingGlobSuffix,
+} from './sandbox-utils.js'
+import {
+  applyTemporaryRules,
+  getActiveTemporaryRules,
+} from './temporary-rules.js'
+
+/**
+ * Policy simulation (`srt test-policy`)
+ *
+ * Answers whether the config would let a sandboxed command read or write a
+ * path or connect to a host, and which rule decided, without starting the
+ * sandbox. The checks follow the order the sandbox applies them in. Names are
+ * not resolved, so the DNS rebinding check and the reverse lookup of IP
+ * targets are not simulated, and the mandatory deny patterns apply at any
+ * depth below the working directory. Temporary rules apply until they expire
+ * or have used up their runs; the simulation uses none of them.
+ */
+
+export type PolicyAction = 'read' | 'write' | 'connect'
+
+export const POLICY_ACTIONS: readonly PolicyAction[] = [
+  'read',
+  'write',
+  'connect',
+]
+
+export interface PolicyDecision {
+  action: PolicyAction
+  /** Absolute path, or host:port */
+  target: string
+  allowed: boolean
+  /** The setting that decided, e.g. filesystem.denyRead */
+  rule: string
+  /** The entry of that setting that matched, as written in the config */
+  entry?: string
+  reason: string
+}
+
+/**
+ * The config the sandbox would enforce: preset, active temporary rules and
+ * allow list exclusions applied
+ */
+function getEffectiveConfig(
+  config: SandboxRuntimeConfig,
+  now: Date,
+  temporaryRuleStatePath: string | undefined,
+): SandboxRuntimeConfig {
+  const activeRules = getActiveTemporaryRules(
+    config.temporaryRules,
+    now,
+    temporaryRuleStatePath,
+  )
+  return applyAllowListExclusions(
+    applyTemporaryRules(applyConfigPreset(config), activeRules),
+  )
+}
+
+/** Whether a path is one a rule names, or inside it */
+export function matchesPathPattern(target: string, pattern: string): boolean {
+  const normalized = normalizePathForSandbox(removeTrailingGlobSuffix(pattern))
+  if (containsGlobChars(normalized)) {
+    // Like plain paths, glob rules cover everything below what they match
+    const regex = globToRegex(normalized).replace(/\$$/, '(/.*)?$')
+    return new RegExp(regex).test(target)
+  }
+  return target === normalized || target.startsWith(normalized + '/')
+}
+
+function findPathEntry(target: string, entries: string[]): string | undefined {
+  return entries.find(entry => matchesPathPattern(target, entry))
+}
+
+function simulateRead(
+  config: SandboxRuntimeConfig,
+  target: string,
+): PolicyDecision {
+  const entry = findPathEntry(target, config.filesystem.denyRead)
+  return entry
+    ? {
+        action: 'read',
+        target,
+        allowed: false,
+        rule: 'filesystem.denyRead',
+        entry,
+        reason: `matches filesystem.denyRead entry "${entry}"`,
+      }
+    : {
+        action: 'read',
+        target,
+        allowed: true,
+        rule: 'filesystem.denyRead',
+        reason: 'reads are allowed outside filesystem.denyRead',
+      }
+}
+
+function simulateWrite(
+  config: SandboxRuntimeConfig,
+  target: string,
+): PolicyDecision {
+  const decision = (
+    allowed: boolean,
+    rule: string,
+    reason: string,
+    entry?: string,
+  ): PolicyDecision => ({
+    action: 'write',
+    target,
+    allowed,
+    rule,
+    entry,
+    reason,
+  })
+
+  // Files that run code or hold git settings are protected in every config
+  const mandatory = findPathEntry(
+    target,
+    macGetMandatoryDenyPatterns(config.filesystem.allowGitConfig),
+  )
+  if (mandatory) {
+    return decision(
+      false,
+      'mandatory deny',
+      `matches the always-denied path "${mandatory}"`,
+      mandatory,
+    )
+  }
+
+  const denied = findPathEntry(target, config.filesystem.denyWrite)
+  if (denied) {
+    return decision(
+      false,
+      'filesystem.denyWrite',
+      `matches filesystem.denyWrite entry "${denied}"`,
+      denied,
+    )
+  }
+
+  const allowed = findPathEntry(target, config.filesystem.allowWrite)
+  if (allowed) {
+    return decision(
+      true,
+      'filesystem.allowWrite',
+      `matches filesystem.allowWrite entry "${allowed}"`,
+      allowed,
+    )
+  }
+
+  const defaultPath = findPathEntry(target, getDefaultWritePaths())
+  if (defaultPath) {
+    return decision(
+      true,
+      'default write paths',
+      `is under the default writable path "${defaultPath}"`,
+      defaultPath,
+    )
+  }
+
+  return decision(
+    false,
+    'filesystem.allowWrite',
+    'not in filesystem.allowWrite',
+  )
+}
+
+function simulateConnect(
+  config: SandboxRuntimeConfig,
+  host: string,
+  port: number,
+): PolicyDecision {
+  const target = `${host}:${port}`
+  const { network } = config
+  const decision = (
+    allowed: boolean,
+    rule: string,
+    reason: string,
+    entry?: string,
+  ): PolicyDecision => ({
+    action: 'connect',
+    target,
+    allowed,
+    rule,
+    entry,
+    reason,
+  })
+
+  if (!network.allowCloudMetadata && isCloudMetadataHost(host)) {
+    return decision(
+      false,
+      'network.allowCloudMetadata',
+      'cloud metadata endpoints are blocked unless network.allowCloudMetadata is set',
+    )
+  }
+  if (!network.allowSmtp && SMTP_PORTS.includes(port)) {
+    return decision(
+      false,
+      'network.allowSmtp',
+      `port ${port} is an SMTP port, blocked unless network.allowSmtp is set`,
+    )
+  }
+
+  const denied = network.deniedDomains.find(pattern =>
+    matchesDomainPattern(host, pattern),
+  )
+  if (denied) {
+    return decision(
+      false,
+      'network.deniedDomains',
+      `matches network.deniedDomains entry "${denied}"`,
+      denied,
+    )
+  }
+
+  const allowed = network.allowedDomains.find(pattern =>
+    matchesDomainPattern(host, pattern),
+  )
+  if (allowed) {
+    return decision(
+      true,
+      'network.allowedDomains',
+      `matches network.allowedDomains entry "${allowed}"`,
+      allowed,
+    )
+  }
+
+  return decision(
+    false,
+    'network.allowedDomains',
+    isIP(host) !== 0
+      ? 'not in network.allowedDomains (IP targets are also allowed when they reverse-resolve to an allowed domain, which is not checked here)'
+      : 'not in network.allowedDomains',
+  )
+}
+
+/**
+ * Split a `host:port` target; IPv6 addresses are written in brackets
+ * (`[::1]:443`). Without a port, 443 is assumed.
+ */
+export function parseConnectTarget(target: string): {
+  host: string
+  port: number
+} {
+  const match =
+    /^\[([^\]]+)\](?::(\d+))?$/.exec(target) ??
+    /^([^:]+)(?::(\d+))?$/.exec(target)
+  const port = match?.[2] === undefined ? 443 : Number(match[2])
+  if (!match || !match[1] || !(port >= 1 && port <= 65535)) {
+    throw new Error(
+      `Invalid target '${target}'. Use host:port, e.g. api.github.com:443`,
+    )
+  }
+  return { host: match[1], port }
+}
+
+/**
+ * Decide an access against a config the way the sandbox would
+ * @param target A path for read and write, host:port for connect
+ * @param temporaryRuleStatePath Run counts of temporary rules with maxRuns
+ *   (default: ~/.srt/temporary-rules.json)
+ */
+export function simulatePolicy(
+  config: SandboxRuntimeConfig,
+  action: PolicyAction,
+  target: string,
+  now: Date = new Date(),
+  temporaryRuleStatePath?: string,
+): PolicyDecision {
+  const effective = getEffectiveConfig(config, now, temporaryRuleStatePath)
+  switch (action) {
+    case 'read':
+      return simulateRead(effective, normalizePathForSandbox(target))
+    case 'write':
+      return simulateWrite(effective, normalizePathForSandbox(target))
+    case 'connect': {
+      const { host, port } = parseConnectTarget(target)
+      return simulateConnect(effective, host, port)
+    }
+  }
+}
+
+/**
+ * Format a decision as one line, e.g.
+ * `deny read /home/me/.ssh/id_rsa: matches filesystem.denyRead entry "~/.ssh"`
+ */
+export function formatPolicyDecision(decision: PolicyDecision): string {
+  return `${decision.allowed ? 'allow' : 'deny'} ${decision.action} ${decision.target}: ${decision.reason}`
+}
@@ -0,0 +1,71 @@
+import type { SandboxViolationEvent } from './macos-sandbox-utils.js'
+import type { SandboxBackendName } from './sandbox-backend.js'
+import type { ViolationSummary } from './violation-summary.js'
+
+/**
+ * Machine-readable output of `srt` runs
+ *
+ * With `--format json` the CLI prints one RunResult as a single JSON line on
+ * stdout after the command exits, instead of its human-oriented messages.
+ * With `--events ndjson` it streams RunEvents to stderr, one JSON object per
+ * line, while the command runs. Times are ISO 8601 strings.
+ */
+
+/**
+ * Exit statuses srt uses when the sandbox, not the command, decided the
+ * outcome. Other statuses are the command's own. A command can exit with
+ * these too, so check `killedBy` and `error` in the JSON result to be sure.
+ */
+export const SANDBOX_EXIT_CODES = {
+  /** The command ran past its timeout, as with coreutils `timeout` */
+  timeout: 124,
+  /** The sandbox could not be set up or the command could not be started */
+  setupFailure: 125,
+  /** The violation policy killed the command */
+  violationKill: 126,
+} as const
+
+export type RunOutputFormat = 'text' | 'json'
+
+export const RUN_OUTPUT_FORMATS: readonly RunOutputFormat[] = ['text', 'json']
+
+export interface RunResult {
+  command: string
+  /** Exit status of srt, i.e. of the command unless the sandbox killed it */
+  exitCode: number
+  /** Signal that terminated the command, if any */
+  signal: NodeJS.Signals | null
+  durationMs: number
+  backend: SandboxBackendName
+  /** Set when the sandbox killed the command */
+  killedBy?: 'timeout' | 'violation-policy'
+  /** Set when the command could not be run */
+  error?: string
+  warnings: string[]
+  violations: SandboxViolationEvent[]
+  summary: ViolationSummary
+}
+
+export type RunEvent =
+  | {
+      event: 'start'
+      time: Date
+      command: string
+      backend: SandboxBackendName
+    }
+  | { event: 'warning'; time: Date; message: string }
+  | { event: 'violation'; time: Date; violation: SandboxViolationEvent }
+  | {
+      event: 'exit'
+      time: Date
+      exitCode: number
+      signal: NodeJS.Signals | null
+      durationMs: number
+    }
+
+/**
+ * Serialize an event as one NDJSON line (with the newline)
+ */
+export function formatRunEvent(event: RunEvent): string {
+  return JSON.stringify(event) + '\n'
+}
@@ -0,0 +1,114 @@
+import type {
+  ResourceLimitsConfig,
+  SandboxRuntimeConfig,
+} from './sandbox-config.js'
+import type {
+  SandboxExecuteOptions,
+  SandboxExecuteResult,
+} from './sandbox-execute.js'
+import type {
+  FsReadRestrictionConfig,
+  FsWriteRestrictionConfig,
+} from './sandbox-schemas.js'
+
+/**
+ * Sandbox backends
+ *
+ * A backend turns a command and the policy resolved for it into a command
+ * that runs it confined. The built-in backends (bubblewrap or nsjail and the
+ * degraded sandbox on Linux, sandbox-exec on macOS, pledge on OpenBSD) are
+ * picked by platform. A backend registered with
+ * SandboxManager.registerBackend() replaces them, e.g. to run commands in a
+ * VM. The manager still runs the proxies, the violation store and the
+ * violation policy; the backend has to route the command's traffic through
+ * the proxy ports it is given and keep it off the network otherwise.
+ */
+
+/**
+ * Names of the built-in backends. Before custom backends, this union was
+ * exported as SandboxBackend, which now names the backend interface.
+ */
+export type BuiltinSandboxBackendName =
+  | 'bwrap'
+  | 'nsjail'
+  | 'degraded'
+  | 'sandbox-exec'
+  | 'pledge'
+  | 'none'
+
+/**
+ * Name of a built-in backend, or of a registered one
+ * (the intersection keeps the built-in names as completions)
+ */
+export type SandboxBackendName =
+  | BuiltinSandboxBackendName
+  | (string & Record<never, never>)
+
+/**
+ * Everything a backend needs to wrap one command
+ */
+export interface SandboxWrapContext {
+  command: string
+  binShell?: string
+  readConfig: FsReadRestrictionConfig
+  writeConfig: FsWriteRestrictionConfig
+  network: {
+    /** Whether the network is restricted at all */
+    restricted: boolean
+    /** Whether the proxies filter the command's traffic; if not, block it */
+    proxied: boolean
+    /** Proxies of the command, when the network is initialized */
+    httpProxyPort?: number
+    socksProxyPort?: number
+    /** Unix sockets bridged to the proxies (Linux only) */
+    httpSocketPath?: string
+    socksSocketPath?: string
+  }
+  allowPty?: boolean
+  resourceLimits?: ResourceLimitsConfig
+  /**
+   * Variables the command gets, already filtered by the env settings. Unset
+   * the others by name; never put values on the command line, the command
+   * is spawned with the set ones.
+   */
+  environment: Record<string, string>
+  /** The caller's and the command policy's overrides of the config */
+  overrides?: Partial<SandboxRuntimeConfig>
+  abortSignal?: AbortSignal
+  /**
+   * Descriptor the command is spawned with for the wrapper to report on
+   * (bwrap's --json-status-fd), when srt runs it
+   */
+  statusFd?: number
+}
+
+export interface SandboxBackend {
+  /** Reported by SandboxManager.getSandboxBackend() and in run results */
+  readonly name: SandboxBackendName
+  /**
+   * Check the backend can run here and set up what it shares between
+   * commands. Called by SandboxManager.initialize(); throw to fail it.
+   */
+  prepare?(config: SandboxRuntimeConfig): Promise<void>
+  /** Build the shell command that runs the command in the sandbox */
+  wrap(context: SandboxWrapContext): Promise<string>
+  /**
+   * Run a wrapped command. Without it, the command is spawned with the
+   * shell on this host, as for the built-in backends. Kill it when
+   * abortSignal aborts, and reject with SandboxTimeoutError when it runs
+   * past timeoutSeconds. Spawn it with stdio and pass the process to
+   * onSpawn, or executeWithOutput captures nothing. Spawn it with env, which
+   * carries the variables the env settings set: wrapped commands only unset
+   * variables.
+   */
+  execute?(
+    wrappedCommand: string,
+    command: string,
+    options: Pick<
+      SandboxExecuteOptions,
+      'abortSignal' | 'timeoutSeconds' | 'stdio' | 'onSpawn'
+    > & { env?: NodeJS.ProcessEnv },
+  ): Promise<SandboxExecuteResult>
+  /** Release what prepare set up. Called by SandboxManager.reset() */
+  cleanup?(): Promise<void>
+}
@@ -0,0 +1,428 @@
+import { spawn, type ChildProcess } from 'node:child_process'
+import * as fs from 'node:fs'
+import * as net from 'node:net'
+import { tmpdir } from 'node:os'
+import { dirname, join } from 'node:path'
+import { logForDebugging } from '../utils/debug.js'
+import type { SandboxViolationEvent } from './macos-sandbox-utils.js'
+import type { SandboxRuntimeConfig } from './sandbox-config.js'
+import { SandboxManager } from './sandbox-manager.js'
+import type { SandboxViolationStore } from './sandbox-violation-store.js'
+
+/**
+ * Least-privilege broker
+ *
+ * The broker is a small, separate process that owns the proxies and the
+ * network policy. The front-end (CLI or library) only learns the proxy ports
+ * over a Unix control socket and points its sandbox at them, so a front-end
+ * compromised by hostile command output cannot rewrite the policy the proxies
+ * enforce. The proxies record their denials in the broker's store; the
+ * front-end copies them into its own (see forwardBrokerViolations).
+ *
+ * Protocol: newline-delimited JSON. Each request is { id, method } and each
+ * response is { id, result } or { id, error }.
+ */
+
+export type BrokerMethod = 'getProxyPorts' | 'getViolations' | 'shutdown'
+
+export interface BrokerProxyPorts {
+  httpProxyPort: number
+  socksProxyPort: number
+}
+
+export interface BrokerHandlers {
+  getProxyPorts(): BrokerProxyPorts | Promise<BrokerProxyPorts>
+  getViolations(): SandboxViolationEvent[] | Promise<SandboxViolationEvent[]>
+  shutdown(): void | Promise<void>
+}
+
+interface BrokerRequest {
+  id: number
+  method: BrokerMethod
+}
+
+interface BrokerResponse {
+  id: number
+  result?: unknown
+  error?: string
+}
+
+export interface BrokerServer {
+  socketPath: string
+  close(): Promise<void>
+}
+
+/** Requests are tiny; anything larger is a misbehaving client */
+const MAX_REQUEST_BYTES = 4096
+
+const BROKER_METHODS: readonly BrokerMethod[] = [
+  'getProxyPorts',
+  'getViolations',
+  'shutdown',
+]
+
+/**
+ * Generate a control socket path in a new private (0700) temp directory, so
+ * no other user can connect to the socket or replace it before it is bound
+ */
+export function generateBrokerSocketPath(): string {
+  return join(fs.mkdtempSync(join(tmpdir(), 'srt-broker-')), 'broker.sock')
+}
+
+function parseRequest(line: string): BrokerRequest | undefined {
+  try {
+    const parsed = JSON.parse(line)
+    if (
+      typeof parsed?.id === 'number' &&
+      BROKER_METHODS.includes(parsed.method)
+    ) {
+      return parsed as BrokerRequest
+    }
+  } catch {
+    // Fall through to undefined
+  }
+  return undefined
+}
+
+/**
+ * Start the control socket server
+ * The socket is only accessible to the current user (mode 0600).
+ */
+export async function createBrokerServer(
+  socketPath: string,
+  handlers: BrokerHandlers,
+): Promise<BrokerServer> {
+  const server = net.createServer(connection => {
+    let buffer = ''
+
+    const respond = (response: BrokerResponse) => {
+      if (!connection.destroyed) {
+        connection.write(JSON.stringify(response) + '\n')
+      }
+    }
+
+    connection.setEncoding('utf8')
+    connection.on('data', (chunk: string) => {
+      buffer += chunk
+      if (buffer.length > MAX_REQUEST_BYTES && !buffer.includes('\n')) {
+        logForDebugging('[Broker] Dropping client with oversized request', {
+          level: 'warn',
+        })
+        connection.destroy()
+        return
+      }
+
+      let newlineIndex: number
+      while ((newlineIndex = buffer.indexOf('\n')) !== -1) {
+        const line = buffer.slice(0, newlineIndex)
+        buffer = buffer.slice(newlineIndex + 1)
+
+        const request = parseRequest(line)
+        if (!request) {
+          respond({ id: -1, error: 'Invalid request' })
+          continue
+        }
+
+        void Promise.resolve()
+          .then(() => handlers[request.method]())
+          .then(
+            result => respond({ id: request.id, result: result ?? null }),
+            error =>
+              respond({
+                id: request.id,
+                error: error instanceof Error ? error.message : String(error),
+              }),
+          )
+      }
+    })
+    connection.on('error', error => {
+      logForDebugging(`[Broker] Control connection error: ${error.message}`)
+    })
+  })
+
+  // Remove a stale socket from a previous broker before binding
+  fs.rmSync(socketPath, { force: true })
+
+  await new Promise<void>((resolve, reject) => {
+    server.once('error', reject)
+    server.listen(socketPath, () => {
+      server.off('error', reject)
+      resolve()
+    })
+  })
+  fs.chmodSync(socketPath, 0o600)
+  logForDebugging(`[Broker] Control socket listening on ${socketPath}`)
+
+  return {
+    socketPath,
+    close: () =>
+      new Promise<void>(resolve => {
+        server.close(() => {
+          fs.rmSync(socketPath, { force: true })
+          resolve()
+        })
+      }),
+  }
+}
+
+/**
+ * Run the broker: start the proxies for the given policy and serve the control socket
+ * Resolves once the broker is ready. Intended to run in a dedicated process
+ * (`srt broker`), which exits after a shutdown request.
+ */
+export async function startSandboxBroker(
+  runtimeConfig: SandboxRuntimeConfig,
+  socketPath: string,
+): Promise<BrokerServer> {
+  // The broker also owns violation collection (macOS log monitor)
+  await SandboxManager.initialize(runtimeConfig, undefined, true)
+
+  const httpProxyPort = SandboxManager.getProxyPort()
+  const socksProxyPort = SandboxManager.getSocksProxyPort()
+  if (httpProxyPort === undefined || socksProxyPort === undefined) {
+    throw new Error('Broker failed to start proxy servers')
+  }
+
+  const brokerServer: BrokerServer = await createBrokerServer(socketPath, {
+    getProxyPorts: () => ({ httpProxyPort, socksProxyPort }),
+    getViolations: () =>
+      SandboxManager.getSandboxViolationStore().getViolations(),
+    shutdown: () => {
+      // Respond first, then tear down and exit the broker process
+      setImmediate(() => {
+        void brokerServer
+          .close()
+          .then(() => SandboxManager.reset())
+          .then(() => process.exit(0))
+      })
+    },
+  })
+  return brokerServer
+}
+
+/**
+ * Client for the broker control socket
+ */
+export class BrokerClient {
+  private buffer = ''
+  private nextId = 1
+  private pending = new Map<
+    number,
+    { resolve: (value: unknown) => void; reject: (error: Error) => void }
+  >()
+
+  private constructor(private readonly socket: net.Socket) {
+    socket.setEncoding('utf8')
+    socket.on('data', (chunk: string) => this.handleData(chunk))
+    socket.on('close', () => {
+      for (const { reject } of this.pending.values()) {
+        reject(new Error('Broker control socket closed'))
+      }
+      this.pending.clear()
+    })
+  }
+
+  static connect(socketPath: string): Promise<BrokerClient> {
+    return new Promise((resolve, reject) => {
+      const socket = net.createConnection(socketPath)
+      socket.once('error', reject)
+      socket.once('connect', () => {
+        socket.off('error', reject)
+        socket.on('error', error => {
+          logForDebugging(`[Broker] Client socket error: ${error.message}`)
+        })
+        resolve(new BrokerClient(socket))
+      })
+    })
+  }
+
+  private handleData(chunk: string): void {
+    this.buffer += chunk
+    let newlineIndex: number
+    while ((newlineIndex = this.buffer.indexOf('\n')) !== -1) {
+      const line = this.buffer.slice(0, newlineIndex)
+      this.buffer = this.buffer.slice(newlineIndex + 1)
+
+      let response: BrokerResponse
+      try {
+        response = JSON.parse(line)
+      } catch {
+        continue
+      }
+      const pending = this.pending.get(response.id)
+      if (!pending) {
+        continue
+      }
+      this.pending.delete(response.id)
+      if (response.error !== undefined) {
+        pending.reject(new Error(response.error))
+      } else {
+        pending.resolve(response.result)
+      }
+    }
+  }
+
+  request<T>(method: BrokerMethod): Promise<T> {
+    const id = this.nextId++
+    return new Promise<T>((resolve, reject) => {
+      this.pending.set(id, {
+        resolve: value => resolve(value as T),
+        reject,
+      })
+      this.socket.write(JSON.stringify({ id, method }) + '\n')
+    })
+  }
+
+  getProxyPorts(): Promise<BrokerProxyPorts> {
+    return this.request<BrokerProxyPorts>('getProxyPorts')
+  }
+
+  getViolations(): Promise<SandboxViolationEvent[]> {
+    return this.request<SandboxViolationEvent[]>('getViolations')
+  }
+
+  async shutdown(): Promise<void> {
+    await this.request<null>('shutdown')
+    this.close()
+  }
+
+  close(): void {
+    this.socket.end()
+  }
+}
+
+/** How often forwardBrokerViolations asks the broker for new violations */
+const BROKER_VIOLATION_POLL_MS = 500
+
+/**
+ * Copy the network violations the broker's proxies record into a store
+ * while a command runs, so summaries, exports and violation policies see
+ * them. Violations of the OS sandbox are left out, since the front-end's own
+ * monitor reports those. The returned function fetches a last time and stops.
+ */
+export function forwardBrokerViolations(
+  client: BrokerClient,
+  store: SandboxViolationStore,
+): () => Promise<void> {
+  const forwarded = new Set<string>()
+  const fetchViolations = async () => {
+    for (const violation of await client.getViolations()) {
+      const key = `${violation.timestamp}|${violation.line}`
+      if (violation.type !== 'network' || forwarded.has(key)) {
+        continue
+      }
+      forwarded.add(key)
+      store.addViolation({
+        ...violation,
+        // JSON carries the timestamp as a string
+        timestamp: new Date(violation.timestamp),
+      })
+    }
+  }
+  const logError = (error: unknown) => {
+    logForDebugging(`[Broker] Failed to fetch violations: ${error}`, {
+      level: 'error',
+    })
+  }
+
+  let fetching = Promise.resolve()
+  const timer = setInterval(() => {
+    fetching = fetching.then(fetchViolations).catch(logError)
+  }, BROKER_VIOLATION_POLL_MS)
+  timer.unref()
+  return async () => {
+    clearInterval(timer)
+    await fetching
+    await fetchViolations().catch(logError)
+  }
+}
+
+/** Signals that end this process and so also the broker it spawned */
+const BROKER_PARENT_SIGNALS: readonly NodeJS.Signals[] = [
+  'SIGINT',
+  'SIGTERM',
+  'SIGHUP',
+]
+
+/**
+ * Spawn a broker process and connect to its control socket
+ *
+ * The broker gets a minimal environment and never sees the sandboxed command or
+ * its output. cliPath is the srt entry point used to run `srt broker`; the
+ * settings file and preset are passed on so its proxies enforce the same
+ * network policy as the front-end's config.
+ */
+export async function spawnSandboxBroker(
+  cliPath: string,
+  { settingsPath, preset }: { settingsPath?: string; preset?: string } = {},
+  timeoutMs = 10000,
+): Promise<{ client: BrokerClient; process: ChildProcess }> {
+  const socketPath = generateBrokerSocketPath()
+  const args = [cliPath, 'broker', '--socket', socketPath]
+  if (settingsPath) {
+    args.push('--settings', settingsPath)
+  }
+  if (preset) {
+    args.push('--preset', preset)
+  }
+
+  const env: NodeJS.ProcessEnv = {
+    PATH: process.env.PATH,
+    HOME: process.env.HOME,
+    TMPDIR: process.env.TMPDIR,
+  }
+  if (process.env.SRT_DEBUG) {
+    env.SRT_DEBUG = process.env.SRT_DEBUG
+  }
+
+  const brokerProcess = spawn(process.execPath, args, {
+    env,
+    stdio: ['ignore', 'ignore', 'inherit'],
+  })
+
+  // The broker must not outlive this process, however it ends
+  const killBroker = () => {
+    if (brokerProcess.exitCode === null && brokerProcess.signalCode === null) {
+      brokerProcess.kill('SIGTERM')
+    }
+  }
+  const onSignal = (signal: NodeJS.Signals) => {
+    killBroker()
+    // Without other handlers the signal would have ended this process
+    if (process.listenerCount(signal) === 0) {
+      process.kill(process.pid, signal)
+    }
+  }
+  process.on('exit', killBroker)
+  for (const signal of BROKER_PARENT_SIGNALS) {
+    process.once(signal, onSignal)
+  }
+  brokerProcess.once('exit', () => {
+    process.off('exit', killBroker)
+    for (const signal of BROKER_PARENT_SIGNALS) {
+      process.off(signal, onSignal)
+    }
+    fs.rmSync(dirname(socketPath), { recursive: true, force: true })
+  })
+
+  const deadline = Date.now() + timeoutMs
+  while (Date.now() < deadline) {
+    if (brokerProcess.exitCode !== null) {
+      throw new Error(
+        `Broker exited during startup with code ${brokerProcess.exitCode}`,
+      )
+    }
+    if (fs.existsSync(socketPath)) {
+      try {
+        const client = await BrokerClient.connect(socketPath)
+        return { client, process: brokerProcess }
+      } catch {
+        // Socket exists but is not accepting yet
+      }
+    }
+    await new Promise(resolve => setTimeout(resolve, 50))
+  }
+
+  brokerProcess.kill('SIGTERM')
+  throw new Error(`Timed out waiting for broker control socket ${socketPath}`)
+}
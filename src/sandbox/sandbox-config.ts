@@ -4,6 +4,17 @@
  */
 
 import { z } from 'zod'
+import { SYSCALL_NAMES, type SyscallName } from './seccomp-bpf.js'
+import { CONFIG_PRESET_NAMES } from './config-presets.js'
+
+function isValidRegex(pattern: string): boolean {
+  try {
+    new RegExp(pattern)
+    return true
+  } catch {
+    return false
+  }
+}
 
 /**
  * Schema for domain patterns (e.g., "example.com", "*.npmjs.org")
@@ -55,20 +66,40 @@ const domainPatternSchema = z.string().refine(
  */
 const filesystemPathSchema = z.string().min(1, 'Path cannot be empty')
 
+/**
+ * Schema for allow list entries, which may start with "!" to exclude a domain
+ * or path from the rest of the list (e.g., "!admin.example.com", "!./.git")
+ */
+function allowListEntrySchema(schema: z.ZodType<string>) {
+  return z.string().superRefine((val, ctx) => {
+    const result = schema.safeParse(val.startsWith('!') ? val.slice(1) : val)
+    if (!result.success) {
+      for (const issue of result.error.issues) {
+        ctx.addIssue({ code: z.ZodIssueCode.custom, message: issue.message })
+      }
+    }
+  })
+}
+
+const allowedDomainSchema = allowListEntrySchema(domainPatternSchema)
+const allowedPathSchema = allowListEntrySchema(filesystemPathSchema)
+
 /**
  * Network configuration schema for validation
  */
 export const NetworkConfigSchema = z.object({
   allowedDomains: z
-    .array(domainPatternSchema)
-    .describe('List of allowed domains (e.g., ["github.com", "*.npmjs.org"])'),
+    .array(allowedDomainSchema)
+    .describe(
+      'List of allowed domains (e.g., ["github.com", "*.npmjs.org"]); entries starting with "!" are denied',
+    ),
   deniedDomains: z
     .array(domainPatternSchema)
     .describe('List of denied domains'),
   allowUnixSockets: z
     .array(z.string())
     .optional()
-    .describe('Unix socket paths that are allowed (macOS only)'),
+    .describe('Unix socket paths that are allowed (macOS and OpenBSD)'),
   allowAllUnixSockets: z
     .boolean()
     .optional()
@@ -78,7 +109,41 @@ export const NetworkConfigSchema = z.object({
   allowLocalBinding: z
     .boolean()
     .optional()
-    .describe('Whether to allow binding to local ports (default: false)'),
+    .describe(
+      'Whether to allow binding to local ports (default: false). On macOS this also allows ' +
+        'connecting to any loopback port',
+    ),
+  allowDns: z
+    .boolean()
+    .optional()
+    .describe(
+      'Allow sandboxed processes to resolve names themselves through mDNSResponder and port 53 ' +
+        '(macOS only, default: false). The proxies resolve names for proxied traffic either way.',
+    ),
+  allowPrivateRanges: z
+    .boolean()
+    .optional()
+    .describe(
+      'Allow allowed domains to resolve to private, loopback or link-local addresses (default: false). When false, such resolutions are blocked as DNS rebinding attempts.',
+    ),
+  allowCloudMetadata: z
+    .boolean()
+    .optional()
+    .describe(
+      'Allow access to cloud instance metadata endpoints such as 169.254.169.254 and metadata.google.internal (default: false). These are blocked even when an allowed domain matches them.',
+    ),
+  allowSmtp: z
+    .boolean()
+    .optional()
+    .describe(
+      'Allow outbound mail submission on ports 25, 465 and 587 (default: false). These ports are blocked even for allowed domains.',
+    ),
+  askUnlistedDomains: z
+    .boolean()
+    .optional()
+    .describe(
+      'Route traffic through the proxies even when allowedDomains is empty, so every connection reaches the ask callback instead of the network being blocked outright (default: false). Set by srt --ask and srt learn.',
+    ),
   httpProxyPort: z
     .number()
     .int()
@@ -105,8 +170,10 @@ export const NetworkConfigSchema = z.object({
 export const FilesystemConfigSchema = z.object({
   denyRead: z.array(filesystemPathSchema).describe('Paths denied for reading'),
   allowWrite: z
-    .array(filesystemPathSchema)
-    .describe('Paths allowed for writing'),
+    .array(allowedPathSchema)
+    .describe(
+      'Paths allowed for writing; entries starting with "!" are denied (e.g., [".", "!./.git"])',
+    ),
   denyWrite: z
     .array(filesystemPathSchema)
     .describe('Paths denied for writing (takes precedence over allowWrite)'),
@@ -116,8 +183,101 @@ export const FilesystemConfigSchema = z.object({
     .describe(
       'Allow writes to .git/config files (default: false). Enables git remote URL updates while keeping .git/hooks protected.',
     ),
+  tmpfsSizeMB: z
+    .number()
+    .int()
+    .positive()
+    .optional()
+    .describe(
+      'Size cap in MB for every tmpfs the Linux sandbox mounts (default: unlimited, i.e. half of host RAM)',
+    ),
+  extraTmpfs: z
+    .array(filesystemPathSchema)
+    .optional()
+    .describe(
+      'Existing directories replaced by a fresh, writable tmpfs inside the Linux sandbox (e.g., ["/tmp"])',
+    ),
+  mounts: z
+    .array(
+      z
+        .string()
+        .regex(
+          /^[^:]+:\/[^:]*(:(ro|rw))?$/,
+          'Mount must be "source:/absolute/dest" or "source:/absolute/dest:ro|rw"',
+        ),
+    )
+    .optional()
+    .describe(
+      'Host paths bound at a different location inside the Linux sandbox, as "source:dest[:ro|rw]" ' +
+        '(default mode: ro, e.g., ["/data/cache:/cache:ro"])',
+    ),
+})
+
+/**
+ * Changes to the base policy for commands matching a `commands` key
+ */
+export const CommandPolicyConfigSchema = z.object({
+  allowedDomains: z
+    .array(allowedDomainSchema)
+    .optional()
+    .describe('Domains allowed in addition to network.allowedDomains'),
+  deniedDomains: z
+    .array(domainPatternSchema)
+    .optional()
+    .describe('Domains denied in addition to network.deniedDomains'),
+  allowWrite: z
+    .array(allowedPathSchema)
+    .optional()
+    .describe(
+      'Paths allowed for writing instead of filesystem.allowWrite (e.g., a narrower list)',
+    ),
+  denyRead: z
+    .array(filesystemPathSchema)
+    .optional()
+    .describe('Paths denied for reading in addition to filesystem.denyRead'),
+  denyWrite: z
+    .array(filesystemPathSchema)
+    .optional()
+    .describe('Paths denied for writing in addition to filesystem.denyWrite'),
 })
 
+/**
+ * Allow rules that stop applying after a deadline or a number of runs
+ */
+export const TemporaryRuleConfigSchema = z
+  .object({
+    allowedDomains: z
+      .array(allowedDomainSchema)
+      .optional()
+      .describe('Domains allowed in addition to network.allowedDomains'),
+    allowWrite: z
+      .array(allowedPathSchema)
+      .optional()
+      .describe(
+        'Paths allowed for writing in addition to filesystem.allowWrite',
+      ),
+    expires: z
+      .string()
+      .datetime({ offset: true })
+      .optional()
+      .describe(
+        'ISO 8601 time after which the rule is ignored (e.g., "2026-11-01T18:00:00Z")',
+      ),
+    maxRuns: z
+      .number()
+      .int()
+      .positive()
+      .optional()
+      .describe('Number of sandbox sessions the rule applies to'),
+    reason: z
+      .string()
+      .optional()
+      .describe('Why the rule exists, shown when it expires'),
+  })
+  .refine(rule => rule.expires !== undefined || rule.maxRuns !== undefined, {
+    message: 'A temporary rule needs expires, maxRuns or both',
+  })
+
 /**
  * Configuration schema for ignoring specific sandbox violations
  * Maps command patterns to filesystem paths to ignore violations for.
@@ -125,7 +285,7 @@ export const FilesystemConfigSchema = z.object({
 export const IgnoreViolationsConfigSchema = z
   .record(z.string(), z.array(z.string()))
   .describe(
-    'Map of command patterns to filesystem paths to ignore violations for. Use "*" to match all commands',
+    'Map of command patterns to paths or hosts to ignore violations for. Use "*" to match all commands. Patterns without glob characters match as substrings; glob patterns must match the whole command, path or host',
   )
 
 /**
@@ -143,14 +303,441 @@ export const RipgrepConfigSchema = z.object({
     ),
 })
 
+/**
+ * Resource limits schema (Linux only)
+ * Enforced by running the sandbox inside a transient cgroup v2 scope via systemd-run
+ */
+export const ResourceLimitsConfigSchema = z.object({
+  cpus: z
+    .number()
+    .positive()
+    .optional()
+    .describe(
+      'Maximum CPU time as a number of CPUs (e.g., 1.5 = 150% of one core). Maps to CPUQuota',
+    ),
+  memoryMb: z
+    .number()
+    .int()
+    .positive()
+    .optional()
+    .describe(
+      'Maximum memory in megabytes, including page cache. Swap is disabled for the scope. Maps to MemoryMax',
+    ),
+  pids: z
+    .number()
+    .int()
+    .positive()
+    .optional()
+    .describe('Maximum number of processes and threads. Maps to TasksMax'),
+})
+
+/**
+ * Per-process rlimits schema, set with ulimit before the sandbox starts
+ */
+export const RlimitsConfigSchema = z.object({
+  nofile: z
+    .number()
+    .int()
+    .positive()
+    .optional()
+    .describe('Maximum number of open file descriptors (RLIMIT_NOFILE)'),
+  fsizeMb: z
+    .number()
+    .int()
+    .positive()
+    .optional()
+    .describe('Maximum size of a written file in megabytes (RLIMIT_FSIZE)'),
+  cpuSeconds: z
+    .number()
+    .int()
+    .positive()
+    .optional()
+    .describe('Maximum CPU time per process in seconds (RLIMIT_CPU)'),
+  coreMb: z
+    .number()
+    .int()
+    .nonnegative()
+    .optional()
+    .describe(
+      'Maximum core dump size in megabytes, 0 disables them (RLIMIT_CORE)',
+    ),
+  maxProcesses: z
+    .number()
+    .int()
+    .positive()
+    .optional()
+    .describe(
+      'Maximum number of processes, against fork bombs. A pids cgroup limit on Linux when ' +
+        'cgroup v2 and systemd-run are available, not enforced otherwise',
+    ),
+})
+
+/**
+ * Per-session scratch directory schema
+ */
+export const ScratchConfigSchema = z.object({
+  maxSizeMB: z
+    .number()
+    .int()
+    .positive()
+    .optional()
+    .describe(
+      'Soft size cap in megabytes (default: 1024). It is checked before each command ' +
+        'starts, so a running command can write past it; the next command then fails to start',
+    ),
+})
+
+/**
+ * Environment policy for sandboxed commands
+ */
+export const EnvConfigSchema = z.object({
+  passthrough: z
+    .array(z.string().min(1))
+    .optional()
+    .describe(
+      'Variable names or globs inherited from the host; when set, all other variables are dropped ' +
+        '(PATH, HOME, USER, LOGNAME, SHELL, TERM, LANG and TZ are always kept). Credential-like ' +
+        'variables are only inherited when listed by exact name',
+    ),
+  deny: z
+    .array(z.string().min(1))
+    .optional()
+    .describe('Variable names or globs never inherited from the host'),
+  set: z
+    .record(z.string().regex(/^[A-Za-z_][A-Za-z0-9_]*$/), z.string())
+    .optional()
+    .describe('Variables set for every command, overriding inherited values'),
+})
+
+/**
+ * Retention policy for the violation journal
+ */
+export const ViolationRetentionConfigSchema = z.object({
+  maxAgeDays: z
+    .number()
+    .positive()
+    .optional()
+    .describe('Drop journaled violations older than this many days'),
+  maxEntries: z
+    .number()
+    .int()
+    .positive()
+    .optional()
+    .describe('Keep at most this many journaled violations (the newest)'),
+})
+
+const ViolationActionSchema = z.enum(['record', 'warn', 'kill'])
+
+/**
+ * Action per kind of violation caused by a command run with execute()
+ */
+export const ViolationPolicyConfigSchema = z.object({
+  network: ViolationActionSchema.optional().describe(
+    'Blocked connections (proxy denials and network sandbox denials)',
+  ),
+  'file-read': ViolationActionSchema.optional().describe('Denied reads'),
+  'file-write': ViolationActionSchema.optional().describe('Denied writes'),
+  other: ViolationActionSchema.optional().describe(
+    'Other sandbox denials (process, IPC, sysctl, ...)',
+  ),
+})
+
+/**
+ * Caps on the violations held in memory by the violation store
+ */
+export const ViolationStoreConfigSchema = z.object({
+  maxEntries: z
+    .number()
+    .int()
+    .positive()
+    .optional()
+    .describe('Keep at most this many violations in memory (default: 100)'),
+  maxBytes: z
+    .number()
+    .int()
+    .positive()
+    .optional()
+    .describe(
+      'Keep at most about this many bytes of violations in memory (default: 1 MiB)',
+    ),
+})
+
+/**
+ * Redaction of secrets in violations and logs
+ */
+export const RedactionConfigSchema = z.object({
+  patterns: z
+    .array(
+      z.string().refine(isValidRegex, {
+        message: 'Each pattern must be a valid regular expression',
+      }),
+    )
+    .optional()
+    .describe(
+      'Regular expressions whose matches are replaced by [REDACTED]; use a lookbehind to keep ' +
+        'the context (e.g., "(?<=session=)[^&\\s]+")',
+    ),
+  builtinPatterns: z
+    .boolean()
+    .optional()
+    .describe(
+      'Also redact well-known token formats, URL credentials and secret query parameters (default: true)',
+    ),
+})
+
+/**
+ * Real-time alerts on violations, sent by the host process (not the sandbox)
+ */
+export const NotificationsConfigSchema = z.object({
+  webhook: z
+    .object({
+      url: z
+        .string()
+        .url()
+        .describe('URL each batch of violations is POSTed to as JSON'),
+      headers: z
+        .record(z.string(), z.string())
+        .optional()
+        .describe('Extra request headers (e.g. Authorization)'),
+    })
+    .optional()
+    .describe('HTTP webhook notified of violations'),
+  command: z
+    .string()
+    .optional()
+    .describe(
+      'Shell command run for each batch of violations, with the batch as JSON on stdin ' +
+        'and SRT_VIOLATION_SEVERITY, SRT_VIOLATION_CATEGORY, SRT_VIOLATION_LINE and ' +
+        'SRT_VIOLATION_COUNT set',
+    ),
+  minSeverity: z
+    .enum(['low', 'medium', 'high'])
+    .optional()
+    .describe(
+      'Only notify violations of at least this severity (default: "low"). Blocked ' +
+        'connections and writes are "high", reads "medium", other denials "low"',
+    ),
+})
+
+/**
+ * Seccomp syscall filtering schema (Linux only)
+ * - 'default': block Unix socket creation (unless allowAllUnixSockets is set)
+ * - 'strict': 'default' plus a built-in list of dangerous syscalls (ptrace, mount, keyctl, bpf, ...)
+ * - 'unconfined': no built-in rules; only syscalls listed in deny are blocked
+ */
+export const SeccompConfigSchema = z.object({
+  profile: z
+    .enum(['strict', 'default', 'unconfined'])
+    .optional()
+    .describe('Named syscall filter profile (default: "default")'),
+  deny: z
+    .array(z.enum(SYSCALL_NAMES as [SyscallName, ...SyscallName[]]))
+    .optional()
+    .describe(
+      'Additional syscalls that fail with EPERM (e.g., ["ptrace", "mount", "keyctl"])',
+    ),
+})
+
+/**
+ * Desktop session access schema (Linux only)
+ * Everything is hidden by default; each field exposes one part of the session.
+ */
+export const DesktopConfigSchema = z.object({
+  x11: z
+    .boolean()
+    .optional()
+    .describe('Expose the X11 sockets and $DISPLAY (default: false)'),
+  wayland: z
+    .boolean()
+    .optional()
+    .describe('Expose the Wayland socket and $WAYLAND_DISPLAY (default: false)'),
+  dbus: z
+    .enum(['none', 'host', 'proxy'])
+    .optional()
+    .describe(
+      'Session D-Bus access: "none" (default), "host" (direct), or "proxy" (through xdg-dbus-proxy, ' +
+        'limited to dbusTalk names)',
+    ),
+  dbusTalk: z
+    .array(z.string())
+    .optional()
+    .describe(
+      'Bus names the sandbox may talk to when dbus is "proxy" (e.g., ["org.freedesktop.Notifications"])',
+    ),
+})
+
+/**
+ * Check that a raw SBPL rule is a single balanced s-expression, so a snippet
+ * can't close the profile's own forms or leave them open
+ */
+function isBalancedSbplRule(rule: string): boolean {
+  const trimmed = rule.trim()
+  if (!trimmed.startsWith('(') || !trimmed.endsWith(')')) {
+    return false
+  }
+  let depth = 0
+  let inString = false
+  for (let i = 0; i < trimmed.length; i++) {
+    const char = trimmed[i]
+    if (inString) {
+      if (char === '\\') i++
+      else if (char === '"') inString = false
+    } else if (char === '"') {
+      inString = true
+    } else if (char === ';') {
+      return false
+    } else if (char === '(') {
+      depth++
+    } else if (char === ')') {
+      depth--
+      if (depth === 0 && i !== trimmed.length - 1) return false
+    }
+  }
+  return depth === 0 && !inString
+}
+
+/**
+ * Seatbelt profile customization schema (macOS only)
+ */
+export const SeatbeltConfigSchema = z.object({
+  template: z
+    .enum(['default', 'minimal'])
+    .optional()
+    .describe(
+      'Base rules of the profile: "default" (preferences, fonts, system services, shared memory, IOKit) ' +
+        'or "minimal" (processes, sysctl reads and device files only)',
+    ),
+  machServices: z
+    .array(
+      z
+        .string()
+        .regex(
+          /^[A-Za-z0-9][A-Za-z0-9._@-]*$/,
+          'Mach service names look like "com.apple.fonts"',
+        ),
+    )
+    .optional()
+    .describe(
+      'Mach services sandboxed processes may look up, replacing the default set of the template ' +
+        '(e.g., [...DEFAULT_MACH_SERVICES, "com.apple.pasteboard.1"])',
+    ),
+  extraRules: z
+    .array(
+      z
+        .string()
+        .refine(isBalancedSbplRule, {
+          message:
+            'Each rule must be one balanced SBPL form, e.g. (allow file-read* (literal "/x"))',
+        }),
+    )
+    .optional()
+    .describe(
+      'Raw SBPL rules appended to the generated profile, after all other rules so they take precedence',
+    ),
+})
+
+/**
+ * Linux capability names accepted by bwrap --cap-add
+ */
+export const LINUX_CAPABILITIES = [
+  'CAP_CHOWN',
+  'CAP_DAC_OVERRIDE',
+  'CAP_DAC_READ_SEARCH',
+  'CAP_FOWNER',
+  'CAP_FSETID',
+  'CAP_KILL',
+  'CAP_SETGID',
+  'CAP_SETUID',
+  'CAP_SETPCAP',
+  'CAP_LINUX_IMMUTABLE',
+  'CAP_NET_BIND_SERVICE',
+  'CAP_NET_BROADCAST',
+  'CAP_NET_ADMIN',
+  'CAP_NET_RAW',
+  'CAP_IPC_LOCK',
+  'CAP_IPC_OWNER',
+  'CAP_SYS_MODULE',
+  'CAP_SYS_RAWIO',
+  'CAP_SYS_CHROOT',
+  'CAP_SYS_PTRACE',
+  'CAP_SYS_PACCT',
+  'CAP_SYS_ADMIN',
+  'CAP_SYS_BOOT',
+  'CAP_SYS_NICE',
+  'CAP_SYS_RESOURCE',
+  'CAP_SYS_TIME',
+  'CAP_SYS_TTY_CONFIG',
+  'CAP_MKNOD',
+  'CAP_LEASE',
+  'CAP_AUDIT_WRITE',
+  'CAP_AUDIT_CONTROL',
+  'CAP_SETFCAP',
+  'CAP_MAC_OVERRIDE',
+  'CAP_MAC_ADMIN',
+  'CAP_SYSLOG',
+  'CAP_WAKE_ALARM',
+  'CAP_BLOCK_SUSPEND',
+  'CAP_AUDIT_READ',
+  'CAP_PERFMON',
+  'CAP_BPF',
+  'CAP_CHECKPOINT_RESTORE',
+] as const
+
+export type LinuxCapability = (typeof LINUX_CAPABILITIES)[number]
+
+/**
+ * User namespace UID/GID mapping schema (Linux only)
+ * - 'current': keep the invoking user's uid/gid inside the sandbox
+ * - 'nobody': map to nobody/nogroup (65534)
+ * - { uid, gid }: map to a custom non-root uid/gid
+ */
+export const UserMappingConfigSchema = z.union([
+  z.enum(['current', 'nobody']),
+  z.object({
+    uid: z
+      .number()
+      .int()
+      .positive('uid 0 (root) cannot be mapped inside the sandbox'),
+    gid: z
+      .number()
+      .int()
+      .positive('gid 0 (root) cannot be mapped inside the sandbox'),
+  }),
+])
+
 /**
  * Main configuration schema for Sandbox Runtime validation
  */
 export const SandboxRuntimeConfigSchema = z.object({
+  preset: z
+    .enum(CONFIG_PRESET_NAMES)
+    .optional()
+    .describe(
+      'Built-in policy merged into network and filesystem: "node-dev", "python-dev", "rust-build" or "git-only"',
+    ),
   network: NetworkConfigSchema.describe('Network restrictions configuration'),
   filesystem: FilesystemConfigSchema.describe(
     'Filesystem restrictions configuration',
   ),
+  commands: z
+    .record(z.string().min(1), CommandPolicyConfigSchema)
+    .optional()
+    .describe(
+      'Policy overrides keyed by binary name or glob (e.g., "git*", "npm"), applied to commands ' +
+        'whose every binary matches the key',
+    ),
+  temporaryRules: z
+    .array(TemporaryRuleConfigSchema)
+    .optional()
+    .describe(
+      'Allow rules that expire at a given time or after a number of runs, for temporary exceptions',
+    ),
+  notes: z
+    .array(z.string())
+    .optional()
+    .describe(
+      'Free-form notes, e.g. when and why entries were added (srt allow adds one per entry); ignored by the sandbox',
+    ),
   ignoreViolations: IgnoreViolationsConfigSchema.optional().describe(
     'Optional configuration for ignoring specific violations',
   ),
@@ -175,13 +762,140 @@ export const SandboxRuntimeConfigSchema = z.object({
     .boolean()
     .optional()
     .describe('Allow pseudo-terminal (pty) operations (macOS only)'),
+  seatbelt: SeatbeltConfigSchema.optional().describe(
+    'Base template and custom SBPL rules for the macOS sandbox profile',
+  ),
+  scratch: ScratchConfigSchema.optional().describe(
+    'Per-session writable scratch directory exposed as $SRT_SCRATCH_DIR and $TMPDIR, removed at session end',
+  ),
+  env: EnvConfigSchema.optional().describe(
+    'Environment of sandboxed commands (default: the host environment without credential-like variables)',
+  ),
+  violationLogPath: z
+    .string()
+    .optional()
+    .describe(
+      'File that violations are durably appended to and reloaded from on startup, so history survives restarts',
+    ),
+  violationFeedSocket: z
+    .string()
+    .optional()
+    .describe(
+      'Unix socket that streams violations to connected clients as NDJSON while the sandbox runs',
+    ),
+  violationRetention: ViolationRetentionConfigSchema.optional().describe(
+    'How much violation history violationLogPath keeps; older records are removed on startup and while appending',
+  ),
+  auditFileAccess: z
+    .boolean()
+    .optional()
+    .describe(
+      'Record every file the sandboxed command opens, executes or modifies, not just denials, ' +
+        'as "file-access" events in SandboxManager.getFileAccessStore(), apart from violations ' +
+        '(Linux with strace, or macOS 13+ as root ' +
+        'through Endpoint Security)',
+    ),
+  resources: ResourceLimitsConfigSchema.optional().describe(
+    'CPU, memory, and process limits for sandboxed commands (Linux only, requires systemd-run and cgroup v2)',
+  ),
+  timeoutSeconds: z
+    .number()
+    .positive()
+    .optional()
+    .describe(
+      'Kill commands run with SandboxManager.execute (and the CLI) and their process group after this many seconds',
+    ),
+  violationPolicy: ViolationPolicyConfigSchema.optional().describe(
+    'Per violation kind: "record" (default), "warn" to also print it, or "kill" to terminate ' +
+      'the command run with SandboxManager.execute that caused it',
+  ),
+  violationStore: ViolationStoreConfigSchema.optional().describe(
+    'Memory caps of the violation store; the oldest violations are evicted first',
+  ),
+  redaction: RedactionConfigSchema.optional().describe(
+    'Secrets replaced by [REDACTED] in violations, reports and logs before they are stored or printed',
+  ),
+  notifications: NotificationsConfigSchema.optional().describe(
+    'Webhook and shell hook alerted in real time when a violation occurs',
+  ),
+  limits: RlimitsConfigSchema.optional().describe(
+    'File descriptor, file size, CPU time, core dump and process count limits for sandboxed commands (Linux and macOS)',
+  ),
+  userMapping: UserMappingConfigSchema.optional().describe(
+    'UID/GID the sandboxed process sees inside a new user namespace (Linux only): ' +
+      '"current", "nobody", or { uid, gid }. Root (0) is never mapped.',
+  ),
+  linuxFilesystemBackend: z
+    .enum(['bwrap', 'landlock', 'auto'])
+    .optional()
+    .describe(
+      'How filesystem restrictions are enforced on Linux (default: "bwrap"). ' +
+        '"landlock" uses the Landlock LSM instead of bind mounts, "auto" stacks Landlock ' +
+        'on top of bwrap when the kernel supports it (>= 5.13). "auto" falls back to bwrap if Landlock is unavailable; ' +
+        '"landlock" fails instead.',
+    ),
+  backend: z
+    .enum(['bwrap', 'nsjail'])
+    .optional()
+    .describe(
+      'Linux sandbox backend (default: "bwrap"). "nsjail" runs the same mounts, namespaces and command under nsjail.',
+    ),
+  desktop: DesktopConfigSchema.optional().describe(
+    'X11, Wayland and session D-Bus access from the Linux sandbox (default: all hidden)',
+  ),
+  capabilities: z
+    .array(z.enum(LINUX_CAPABILITIES))
+    .optional()
+    .describe(
+      'Linux capabilities kept inside the sandbox (default: none). All others are dropped, ' +
+        'so even root inside the user namespace cannot regain them (e.g., ["CAP_NET_BIND_SERVICE"])',
+    ),
+  allowSensitiveProcEntries: z
+    .boolean()
+    .optional()
+    .describe(
+      'Leave /proc/kcore, /proc/keys, /proc/timer_list, /proc/sched_debug and /proc/sysrq-trigger ' +
+        'visible in the Linux sandbox instead of masking them with /dev/null (default: false)',
+    ),
+  allowDegradedSandbox: z
+    .boolean()
+    .optional()
+    .describe(
+      'On Linux, when bwrap (or nsjail) is missing, run commands with only seccomp, rlimits, ' +
+        'environment scrubbing and proxy env vars instead of failing (no filesystem or network isolation)',
+    ),
+  seccomp: SeccompConfigSchema.optional().describe(
+    'Syscall filtering applied to the sandboxed command (Linux only)',
+  ),
 })
 
 // Export inferred types
 export type NetworkConfig = z.infer<typeof NetworkConfigSchema>
 export type FilesystemConfig = z.infer<typeof FilesystemConfigSchema>
+export type CommandPolicyConfig = z.infer<typeof CommandPolicyConfigSchema>
+export type TemporaryRuleConfig = z.infer<typeof TemporaryRuleConfigSchema>
 export type IgnoreViolationsConfig = z.infer<
   typeof IgnoreViolationsConfigSchema
 >
 export type RipgrepConfig = z.infer<typeof RipgrepConfigSchema>
+export type ResourceLimitsConfig = z.infer<typeof ResourceLimitsConfigSchema>
+export type RlimitsConfig = z.infer<typeof RlimitsConfigSchema>
+export type UserMappingConfig = z.infer<typeof UserMappingConfigSchema>
+export type SeccompConfig = z.infer<typeof SeccompConfigSchema>
+export type ScratchConfig = z.infer<typeof ScratchConfigSchema>
+export type EnvConfig = z.infer<typeof EnvConfigSchema>
+export type ViolationPolicyConfig = z.infer<typeof ViolationPolicyConfigSchema>
+export type ViolationRetentionConfig = z.infer<
+  typeof ViolationRetentionConfigSchema
+>
+export type ViolationStoreConfig = z.infer<typeof ViolationStoreConfigSchema>
+export type NotificationsConfig = z.infer<typeof NotificationsConfigSchema>
+export type RedactionConfig = z.infer<typeof RedactionConfigSchema>
+export type DesktopConfig = z.infer<typeof DesktopConfigSchema>
+export type SeatbeltConfig = z.infer<typeof SeatbeltConfigSchema>
+export type SeatbeltTemplate = NonNullable<SeatbeltConfig['template']>
+export type LinuxFilesystemBackend = NonNullable<
+  SandboxRuntimeConfig['linuxFilesystemBackend']
+>
+export type LinuxSandboxBackend = NonNullable<SandboxRuntimeConfig['backend']>
 export type SandboxRuntimeConfig = z.infer<typeof SandboxRuntimeConfigSchema>
@@ -0,0 +1,499 @@
+import type { ChildProcess } from 'node:child_process'
+import { once } from 'node:events'
+import * as net from 'node:net'
+import { logForDebugging } from '../utils/debug.js'
+import type { SandboxViolationEvent } from './macos-sandbox-utils.js'
+import type { SandboxRuntimeConfig } from './sandbox-config.js'
+import {
+  SandboxTimeoutError,
+  SandboxViolationKillError,
+  type SandboxExecuteResult,
+} from './sandbox-execute.js'
+import { SandboxManager } from './sandbox-manager.js'
+import { listenOnPrivateSocket } from './unix-socket.js'
+
+/**
+ * Sandbox daemon (`srt daemon`)
+ *
+ * A long-running process that initializes the sandbox once (proxies, the
+ * Linux network bridge, seccomp filters, the macOS profile directory) and
+ * runs commands sent over a Unix socket, so editors and agent frameworks pay
+ * the startup cost once rather than for every command. Commands run with the
+ * daemon's config, in its working directory. There are no pre-started
+ * sandboxes: each command still gets its own sandbox process (bwrap,
+ * sandbox-exec, ...), which is what keeps commands apart.
+ *
+ * Protocol: newline-delimited JSON. Each request is { id, method, params }.
+ * While a command runs, its output is sent as { id, stream, data } (UTF-8
+ * text); every request ends with { id, result } or { id, error }. A command
+ * whose client reads its output slower than it is produced is paused until
+ * the client catches up. Commands still running when their client
+ * disconnects are terminated.
+ *
+ * Violations are attributed by command line, so runs of the same command
+ * line are queued one after another, and each result carries only the
+ * violations of its own run.
+ */
+
+export type DaemonMethod = 'execute' | 'cancel' | 'getViolations' | 'shutdown'
+
+export interface DaemonExecuteParams {
+  command: string
+  /** Written to the command's stdin, then closed (default: no stdin) */
+  stdin?: string
+  /** Kill the command's process group after this many seconds */
+  timeoutSeconds?: number
+}
+
+export interface DaemonExecuteResult extends SandboxExecuteResult {
+  durationMs: number
+  /** Violations recorded for the command while it ran */
+  violations: SandboxViolationEvent[]
+  /** Set when the sandbox killed the command */
+  killedBy?: 'timeout' | 'violation-policy'
+}
+
+export type DaemonOutputStream = 'stdout' | 'stderr'
+
+interface DaemonRequest {
+  id: number
+  method: DaemonMethod
+  params?: Record<string, unknown>
+}
+
+interface DaemonMessage {
+  id: number
+  result?: unknown
+  error?: string
+  stream?: DaemonOutputStream
+  data?: string
+}
+
+export interface DaemonServer {
+  socketPath: string
+  /** Resolves once the daemon has stopped, e.g. after a shutdown request */
+  closed: Promise<void>
+  close(): Promise<void>
+}
+
+/** Commands and their stdin are sent inline, so allow sizeable requests */
+const MAX_REQUEST_BYTES = 1024 * 1024
+
+const DAEMON_METHODS: readonly DaemonMethod[] = [
+  'execute',
+  'cancel',
+  'getViolations',
+  'shutdown',
+]
+
+function parseRequest(line: string): DaemonRequest | undefined {
+  try {
+    const parsed = JSON.parse(line)
+    if (
+      typeof parsed?.id === 'number' &&
+      DAEMON_METHODS.includes(parsed.method) &&
+      (parsed.params === undefined ||
+        (typeof parsed.params === 'object' && parsed.params !== null))
+    ) {
+      return parsed as DaemonRequest
+    }
+  } catch {
+    // Fall through to undefined
+  }
+  return undefined
+}
+
+function parseExecuteParams(
+  params: Record<string, unknown> = {},
+): DaemonExecuteParams {
+  const { command, stdin, timeoutSeconds } = params
+  if (typeof command !== 'string' || command.length === 0) {
+    throw new Error('execute needs a command string')
+  }
+  if (stdin !== undefined && typeof stdin !== 'string') {
+    throw new Error('stdin must be a string')
+  }
+  if (
+    timeoutSeconds !== undefined &&
+    !(typeof timeoutSeconds === 'number' && timeoutSeconds > 0)
+  ) {
+    throw new Error('timeoutSeconds must be a positive number')
+  }
+  return { command, stdin, timeoutSeconds }
+}
+
+/**
+ * Where a command's output goes
+ */
+interface DaemonOutputSink {
+  /** Returns false when the client's buffer is full */
+  send(stream: DaemonOutputStream, data: string): boolean
+  /** Resolves once the client has caught up (or is gone) */
+  drained(): Promise<void>
+}
+
+/**
+ * Run a command for a client, sending its output as it is produced
+ * @param previousRun Run of the same command line to wait for
+ */
+async function executeForClient(
+  params: DaemonExecuteParams,
+  output: DaemonOutputSink,
+  abortSignal: AbortSignal,
+  previousRun: Promise<unknown> | undefined,
+): Promise<DaemonExecuteResult> {
+  await previousRun?.catch(() => undefined)
+  const store = SandboxManager.getSandboxViolationStore()
+  const encodedCommand = store.encodeCommand(params.command)
+  const violations: SandboxViolationEvent[] = []
+  const stopCollecting = new AbortController()
+  const collecting = (async () => {
+    for await (const violation of store.stream({
+      signal: stopCollecting.signal,
+    })) {
+      if (violation.encodedCommand === encodedCommand) {
+        violations.push(violation)
+      }
+    }
+  })()
+
+  const onSpawn = (child: ChildProcess) => {
+    const streams = [child.stdout, child.stderr]
+    let paused = false
+    for (const stream of ['stdout', 'stderr'] as const) {
+      child[stream]?.setEncoding('utf8')
+      child[stream]?.on('data', (data: string) => {
+        if (output.send(stream, data) || paused) {
+          return
+        }
+        // The command blocks on its full pipes until the client catches up
+        paused = true
+        streams.forEach(s => s?.pause())
+        void output.drained().then(() => {
+          paused = false
+          streams.forEach(s => s?.resume())
+        })
+      })
+    }
+  }
+
+  const startedAt = Date.now()
+  let outcome: SandboxExecuteResult = { code: null, signal: 'SIGKILL' }
+  let killedBy: DaemonExecuteResult['killedBy']
+  try {
+    outcome = await SandboxManager.execute(params.command, {
+      abortSignal,
+      timeoutSeconds: params.timeoutSeconds,
+      stdio: ['ignore', 'pipe', 'pipe'],
+      stdin: params.stdin,
+      onSpawn,
+    })
+  } catch (error) {
+    if (error instanceof SandboxTimeoutError) {
+      killedBy = 'timeout'
+    } else if (error instanceof SandboxViolationKillError) {
+      killedBy = 'violation-policy'
+    } else {
+      throw error
+    }
+  } finally {
+    stopCollecting.abort()
+    await collecting
+  }
+  return {
+    ...outcome,
+    durationMs: Date.now() - startedAt,
+    violations,
+    killedBy,
+  }
+}
+
+/**
+ * Run the daemon: initialize the sandbox and serve execution requests
+ * Resolves once the socket is accepting requests.
+ */
+export async function startSandboxDaemon(
+  runtimeConfig: SandboxRuntimeConfig,
+  socketPath: string,
+): Promise<DaemonServer> {
+  // Violations are reported with each command's result
+  await SandboxManager.initialize(runtimeConfig, undefined, true)
+
+  // The latest run of each command line, across clients
+  const commandRuns = new Map<string, Promise<unknown>>()
+
+  const connections = new Set<net.Socket>()
+  const server = net.createServer(connection => {
+    connections.add(connection)
+    const running = new Map<number, AbortController>()
+    let buffer = ''
+
+    const send = (message: DaemonMessage): boolean =>
+      !connection.destroyed &&
+      connection.write(JSON.stringify(message) + '\n')
+    const drained = async (): Promise<void> => {
+      if (connection.destroyed || !connection.writableNeedDrain) {
+        return
+      }
+      const done = new AbortController()
+      const { signal } = done
+      await Promise.race([
+        once(connection, 'drain', { signal }),
+        once(connection, 'close', { signal }),
+      ]).finally(() => done.abort())
+    }
+
+    const handle = async (request: DaemonRequest): Promise<unknown> => {
+      switch (request.method) {
+        case 'execute': {
+          const params = parseExecuteParams(request.params)
+          if (running.has(request.id)) {
+            throw new Error(`Request ${request.id} is already running`)
+          }
+          const controller = new AbortController()
+          running.set(request.id, controller)
+          const previousRun = commandRuns.get(params.command)
+          const run = executeForClient(
+            params,
+            {
+              send: (stream, data) => send({ id: request.id, stream, data }),
+              drained,
+            },
+            controller.signal,
+            previousRun,
+          )
+          commandRuns.set(params.command, run)
+          try {
+            return await run
+          } finally {
+            running.delete(request.id)
+            if (commandRuns.get(params.command) === run) {
+              commandRuns.delete(params.command)
+            }
+          }
+        }
+        case 'cancel': {
+          const controller = running.get(request.params?.id as number)
+          controller?.abort()
+          return controller !== undefined
+        }
+        case 'getViolations':
+          return SandboxManager.getSandboxViolationStore().getViolations()
+        case 'shutdown':
+          // Respond first, then stop the daemon
+          setImmediate(() => void daemon.close())
+          return null
+      }
+    }
+
+    connection.setEncoding('utf8')
+    connection.on('data', (chunk: string) => {
+      buffer += chunk
+      if (buffer.length > MAX_REQUEST_BYTES && !buffer.includes('\n')) {
+        logForDebugging('[Daemon] Dropping client with oversized request', {
+          level: 'warn',
+        })
+        connection.destroy()
+        return
+      }
+
+      let newlineIndex: number
+      while ((newlineIndex = buffer.indexOf('\n')) !== -1) {
+        const line = buffer.slice(0, newlineIndex)
+        buffer = buffer.slice(newlineIndex + 1)
+
+        const request = parseRequest(line)
+        if (!request) {
+          send({ id: -1, error: 'Invalid request' })
+          continue
+        }
+        void handle(request).then(
+          result => send({ id: request.id, result: result ?? null }),
+          error =>
+            send({
+              id: request.id,
+              error: error instanceof Error ? error.message : String(error),
+            }),
+        )
+      }
+    })
+    connection.on('close', () => {
+      connections.delete(connection)
+      running.forEach(controller => controller.abort())
+    })
+    connection.on('error', error => {
+      logForDebugging(`[Daemon] Client connection error: ${error.message}`)
+    })
+  })
+
+  const removeSocket = await listenOnPrivateSocket(server, socketPath)
+  logForDebugging(`[Daemon] Listening on ${socketPath}`)
+
+  let resolveClosed: () => void = () => {}
+  const closed = new Promise<void>(resolve => (resolveClosed = resolve))
+  let closing: Promise<void> | undefined
+  const close = async (): Promise<void> => {
+    // Closing the connections terminates their running commands
+    connections.forEach(connection => connection.destroy())
+    await new Promise<void>(resolve => server.close(() => resolve()))
+    removeSocket()
+    await SandboxManager.reset()
+    resolveClosed()
+  }
+  const daemon: DaemonServer = {
+    socketPath,
+    closed,
+    close: () => {
+      if (!closing) {
+        closing = close()
+      }
+      return closing
+    },
+  }
+  return daemon
+}
+
+export interface DaemonExecuteOptions {
+  stdin?: string
+  timeoutSeconds?: number
+  /** Called with the command's output as it arrives */
+  onOutput?: (stream: DaemonOutputStream, data: string) => void
+  /** Aborting cancels the command */
+  signal?: AbortSignal
+}
+
+/**
+ * Client for the daemon socket
+ */
+export class DaemonClient {
+  private buffer = ''
+  private nextId = 1
+  private pending = new Map<
+    number,
+    {
+      resolve: (value: unknown) => void
+      reject: (error: Error) => void
+      onOutput?: (stream: DaemonOutputStream, data: string) => void
+    }
+  >()
+
+  private constructor(private readonly socket: net.Socket) {
+    socket.setEncoding('utf8')
+    socket.on('data', (chunk: string) => this.handleData(chunk))
+    socket.on('close', () => {
+      for (const { reject } of this.pending.values()) {
+        reject(new Error('Daemon socket closed'))
+      }
+      this.pending.clear()
+    })
+  }
+
+  static connect(socketPath: string): Promise<DaemonClient> {
+    return new Promise((resolve, reject) => {
+      const socket = net.createConnection(socketPath)
+      socket.once('error', reject)
+      socket.once('connect', () => {
+        socket.off('error', reject)
+        socket.on('error', error => {
+          logForDebugging(`[Daemon] Client socket error: ${error.message}`)
+        })
+        resolve(new DaemonClient(socket))
+      })
+    })
+  }
+
+  private handleData(chunk: string): void {
+    this.buffer += chunk
+    let newlineIndex: number
+    while ((newlineIndex = this.buffer.indexOf('\n')) !== -1) {
+      const line = this.buffer.slice(0, newlineIndex)
+      this.buffer = this.buffer.slice(newlineIndex + 1)
+
+      let message: DaemonMessage
+      try {
+        message = JSON.parse(line)
+      } catch {
+        continue
+      }
+      const pending = this.pending.get(message.id)
+      if (!pending) {
+        continue
+      }
+      if (message.stream !== undefined) {
+        pending.onOutput?.(message.stream, message.data ?? '')
+        continue
+      }
+      this.pending.delete(message.id)
+      if (message.error !== undefined) {
+        pending.reject(new Error(message.error))
+      } else {
+        pending.resolve(message.result)
+      }
+    }
+  }
+
+  private request<T>(
+    method: DaemonMethod,
+    params?: Record<string, unknown>,
+    onOutput?: (stream: DaemonOutputStream, data: string) => void,
+  ): { id: number; response: Promise<T> } {
+    const id = this.nextId++
+    const response = new Promise<T>((resolve, reject) => {
+      this.pending.set(id, {
+        resolve: value => resolve(value as T),
+        reject,
+        onOutput,
+      })
+      this.socket.write(JSON.stringify({ id, method, params }) + '\n')
+    })
+    return { id, response }
+  }
+
+  /**
+   * Run a command in the daemon's sandbox and wait for it to exit
+   */
+  async execute(
+    command: string,
+    options: DaemonExecuteOptions = {},
+  ): Promise<DaemonExecuteResult> {
+    const { stdin, timeoutSeconds, onOutput, signal } = options
+    const { id, response } = this.request<DaemonExecuteResult>(
+      'execute',
+      { command, stdin, timeoutSeconds },
+      onOutput,
+    )
+    const cancel = () => {
+      // The command may finish before the cancellation arrives
+      this.request('cancel', { id }).response.catch(() => {})
+    }
+    signal?.addEventListener('abort', cancel, { once: true })
+    if (signal?.aborted) {
+      cancel()
+    }
+    try {
+      const result = await response
+      return {
+        ...result,
+        violations: result.violations.map(violation => ({
+          ...violation,
+          timestamp: new Date(violation.timestamp),
+        })),
+      }
+    } finally {
+      signal?.removeEventListener('abort', cancel)
+    }
+  }
+
+  getViolations(): Promise<SandboxViolationEvent[]> {
+    return this.request<SandboxViolationEvent[]>('getViolations').response
+  }
+
+  async shutdown(): Promise<void> {
+    await this.request<null>('shutdown').response
+    this.close()
+  }
+
+  close(): void {
+    this.socket.end()
+  }
+}
@@ -0,0 +1,184 @@
+import type { EnvConfig } from './sandbox-config.js'
+import { containsGlobChars, globToRegex } from './sandbox-utils.js'
+
+/**
+ * Environment of sandboxed commands
+ *
+ * Sandboxed commands do not inherit srt's environment as it is. The `env`
+ * policy decides which host variables they get, computed when a command is
+ * wrapped:
+ * - with `passthrough`, only matching variables (plus a few every shell
+ *   expects) are inherited; otherwise all of them are
+ * - variables matching `deny` are dropped
+ * - credential-like variables (AWS_SECRET_ACCESS_KEY, GITHUB_TOKEN, ...) are
+ *   dropped unless `passthrough` lists them by exact name
+ * - `set` is applied last
+ * The proxy and scratch variables srt sets itself always take precedence.
+ *
+ * Wrapped commands only unset variables by name. Values from `set` are
+ * passed through the environment the wrapped command is spawned with
+ * (getSpawnEnv), never as arguments, so they don't show up in `ps`.
+ */
+
+/** Variable names that look like credentials */
+export const CREDENTIAL_ENV_VAR_PATTERN =
+  /TOKEN|SECRET|PASSWORD|PASSWD|CREDENTIAL|API_?KEY|ACCESS_KEY|PRIVATE_KEY/i
+
+/** Inherited even with a passthrough list, unless denied */
+const ESSENTIAL_ENV_VARS = [
+  'PATH',
+  'HOME',
+  'USER',
+  'LOGNAME',
+  'SHELL',
+  'TERM',
+  'LANG',
+  'TZ',
+]
+
+function matchesEnvPattern(pattern: string, name: string): boolean {
+  if (!containsGlobChars(pattern)) {
+    return name === pattern
+  }
+  return new RegExp(globToRegex(pattern)).test(name)
+}
+
+/**
+ * The variables a sandboxed command starts with under an env policy
+ */
+export function resolveSandboxEnv(
+  envConfig: EnvConfig | undefined,
+  hostEnv: NodeJS.ProcessEnv = process.env,
+): Record<string, string> {
+  const passthrough = envConfig?.passthrough
+  const deny = envConfig?.deny ?? []
+  const resolved: Record<string, string> = {}
+
+  for (const [name, value] of Object.entries(hostEnv)) {
+    if (value === undefined) {
+      continue
+    }
+    if (deny.some(pattern => matchesEnvPattern(pattern, name))) {
+      continue
+    }
+    if (CREDENTIAL_ENV_VAR_PATTERN.test(name)) {
+      // Only an explicit opt-in lets a credential through
+      if (!passthrough?.includes(name)) {
+        continue
+      }
+    } else if (
+      passthrough &&
+      !ESSENTIAL_ENV_VARS.includes(name) &&
+      !passthrough.some(pattern => matchesEnvPattern(pattern, name))
+    ) {
+      continue
+    }
+    resolved[name] = value
+  }
+
+  return { ...resolved, ...envConfig?.set }
+}
+
+const ENV_VAR_NAME = /^[A-Za-z_][A-Za-z0-9_]*$/
+
+/**
+ * Parse a `NAME=value` assignment (as given to `srt --env`); a bare `NAME`
+ * takes its value from the host environment
+ */
+export function parseEnvAssignment(
+  assignment: string,
+  hostEnv: NodeJS.ProcessEnv = process.env,
+): [string, string] {
+  const separator = assignment.indexOf('=')
+  const name = separator === -1 ? assignment : assignment.slice(0, separator)
+  if (!ENV_VAR_NAME.test(name)) {
+    throw new Error(`Invalid variable name in '${assignment}'`)
+  }
+  if (separator !== -1) {
+    return [name, assignment.slice(separator + 1)]
+  }
+  const value = hostEnv[name]
+  if (value === undefined) {
+    throw new Error(`${name} is not set in the environment`)
+  }
+  return [name, value]
+}
+
+/** Escapes of double-quoted env file values, besides \" and \\ */
+const ENV_FILE_ESCAPES: Record<string, string> = { n: '\n', r: '\r', t: '\t' }
+
+function unquoteEnvValue(value: string): string {
+  if (value.length >= 2 && value.startsWith("'") && value.endsWith("'")) {
+    return value.slice(1, -1)
+  }
+  if (value.length >= 2 && value.startsWith('"') && value.endsWith('"')) {
+    return value
+      .slice(1, -1)
+      .replace(
+        /\\([nrt"\\])/g,
+        (_, escaped: string) => ENV_FILE_ESCAPES[escaped] ?? escaped,
+      )
+  }
+  // Unquoted values end at a comment
+  return value.replace(/\s+#.*$/, '')
+}
+
+/**
+ * Parse an env file: `NAME=value` lines, optionally prefixed with `export`,
+ * with blank lines and `#` comments ignored. Double-quoted values may use
+ * \n, \r, \t, \" and \\ escapes; single-quoted values are taken as is.
+ */
+export function parseEnvFile(content: string): Record<string, string> {
+  const env: Record<string, string> = {}
+  content.split(/\r?\n/).forEach((line, index) => {
+    const trimmed = line.trim()
+    if (trimmed === '' || trimmed.startsWith('#')) {
+      return
+    }
+    const match = /^(?:export\s+)?([A-Za-z_][A-Za-z0-9_]*)\s*=\s*(.*)$/.exec(
+      trimmed,
+    )
+    if (!match) {
+      throw new Error(`line ${index + 1}: expected NAME=value`)
+    }
+    env[match[1]] = unquoteEnvValue(match[2])
+  })
+  return env
+}
+
+/**
+ * Names of the variables in hostEnv that env leaves out
+ * Wrappers unset these by name (`env -u`), so the environment is filtered
+ * without any value appearing on a command line, where `ps` would show it.
+ */
+export function getUnsetEnvNames(
+  env: Record<string, string>,
+  hostEnv: NodeJS.ProcessEnv = process.env,
+): string[] {
+  return Object.keys(hostEnv).filter(
+    name => hostEnv[name] !== undefined && !(name in env),
+  )
+}
+
+/**
+ * Arguments for `env` that remove the variables env leaves out
+ */
+export function toEnvUnsetArgs(
+  env: Record<string, string>,
+  hostEnv: NodeJS.ProcessEnv = process.env,
+): string[] {
+  return getUnsetEnvNames(env, hostEnv).flatMap(name => ['-u', name])
+}
+
+/**
+ * The environment to spawn a wrapped command with: hostEnv plus the
+ * variables env adds or changes (`set`, `srt --env`). Those values reach the
+ * command through the process environment only, since the wrapper just
+ * unsets variables.
+ */
+export function getSpawnEnv(
+  env: Record<string, string>,
+  hostEnv: NodeJS.ProcessEnv = process.env,
+): NodeJS.ProcessEnv {
+  return { ...hostEnv, ...env }
+}
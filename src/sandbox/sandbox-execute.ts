@@ -0,0 +1,620 @@
+import {
+  spawn,
+  spawnSync,
+  type ChildProcess,
+  type StdioOptions,
+} from 'node:child_process'
+import * as fs from 'node:fs'
+import { constants } from 'node:os'
+import { pipeline } from 'node:stream/promises'
+import { logForDebugging } from '../utils/debug.js'
+import type { SandboxRuntimeConfig } from './sandbox-config.js'
+import type { SandboxViolationEvent } from './macos-sandbox-utils.js'
+
+/**
+ * Running wrapped commands with an execution timeout
+ *
+ * With a timeout the command is spawned in its own process group, and the
+ * whole group is killed with SIGKILL when the timeout fires, so shells and
+ * their background jobs go too. bwrap and nsjail run the command in a new
+ * session but die with their parent, which takes the PID namespace down with
+ * them. A command in its own process group is not in the terminal's
+ * foreground group, so it cannot read from the terminal. A command whose
+ * stdin is an inherited terminal therefore stays in srt's process group, and
+ * signals go to it and each of its descendants instead.
+ *
+ * Aborting with a SandboxViolationKillError as the reason (what the "kill"
+ * violation policy does) kills the command with SIGKILL instead of SIGTERM,
+ * and aborting with a signal name (e.g. 'SIGINT') sends that signal.
+ */
+
+export interface SandboxExecuteOptions {
+  binShell?: string
+  customConfig?: Partial<SandboxRuntimeConfig>
+  /**
+   * Aborting sends SIGTERM to the command (its process group with a timeout),
+   * or the signal named by the abort reason, e.g. abort('SIGINT')
+   */
+  abortSignal?: AbortSignal
+  /** Kill the command's process group after this many seconds (default: config timeoutSeconds) */
+  timeoutSeconds?: number
+  /** stdio of the spawned command (default: 'inherit') */
+  stdio?: StdioOptions
+  /** Called with the spawned process, e.g. to read its piped output */
+  onSpawn?: (child: ChildProcess) => void
+  /** Written to the command's stdin, which is then closed; replaces stdio[0] */
+  stdin?: SandboxStdinInput
+}
+
+/** Input for a command's stdin: text, bytes, or a stream such as a Readable */
+export type SandboxStdinInput =
+  | string
+  | Uint8Array
+  | AsyncIterable<string | Uint8Array>
+
+export interface SandboxExecuteResult {
+  code: number | null
+  signal: NodeJS.Signals | null
+}
+
+/**
+ * A command started with SandboxManager.start
+ */
+export interface SandboxExecutionHandle {
+  /** Settles like execute */
+  exited: Promise<SandboxExecuteResult>
+  /** Kill the command and everything it started with SIGKILL */
+  kill(): void
+  /**
+   * Send a signal to the command and everything it started. Signals sent
+   * before the command is spawned are delivered once it is. After it exited
+   * they still reach the background processes left in its process group,
+   * and are ignored once there are none.
+   */
+  signal(signal: NodeJS.Signals): void
+}
+
+/** Default cap on the output executeWithOutput may buffer, per stream */
+export const DEFAULT_MAX_BUFFER = 64 * 1024 * 1024
+
+/** Options of SandboxManager.executeWithOutput */
+export type SandboxOutputOptions = Omit<
+  SandboxExecuteOptions,
+  'stdio' | 'onSpawn'
+> & {
+  /**
+   * Characters of stdout or stderr to buffer before the command is killed
+   * and the call rejects with SandboxOutputLimitError (default:
+   * DEFAULT_MAX_BUFFER)
+   */
+  maxBuffer?: number
+}
+
+/** Result of SandboxManager.executeWithOutput */
+export interface SandboxCommandOutput extends SandboxExecuteResult {
+  stdout: string
+  stderr: string
+  /** Wall-clock time from wrapping the command to its output closing */
+  durationMs: number
+}
+
+/**
+ * Raised when a sandboxed command is killed by the violation policy
+ */
+export class SandboxViolationKillError extends Error {
+  /** Output captured up to the kill, when run with executeWithOutput */
+  stdout?: string
+  stderr?: string
+
+  constructor(
+    readonly command: string,
+    readonly violation: SandboxViolationEvent,
+  ) {
+    super(
+      `Sandboxed command killed after a violation: ${violation.line} (${command})`,
+    )
+    this.name = 'SandboxViolationKillError'
+  }
+}
+
+/**
+ * Raised when a sandboxed command is killed for running past its timeout
+ */
+export class SandboxTimeoutError extends Error {
+  /** Output captured up to the kill, when run with executeWithOutput */
+  stdout?: string
+  stderr?: string
+
+  constructor(
+    readonly command: string,
+    readonly timeoutSeconds: number,
+  ) {
+    super(`Sandboxed command timed out after ${timeoutSeconds}s: ${command}`)
+    this.name = 'SandboxTimeoutError'
+  }
+}
+
+/**
+ * Raised when a command's captured output grows past its maxBuffer; the
+ * command is killed
+ */
+export class SandboxOutputLimitError extends Error {
+  /** Output captured up to the kill, when run with executeWithOutput */
+  stdout?: string
+  stderr?: string
+
+  constructor(
+    readonly command: string,
+    readonly maxBuffer: number,
+  ) {
+    super(
+      `Sandboxed command output exceeded maxBuffer (${maxBuffer}): ${command}`,
+    )
+    this.name = 'SandboxOutputLimitError'
+  }
+}
+
+/**
+ * Raised when the sandbox wrapper exits without starting the command, e.g.
+ * when bwrap cannot create its namespaces
+ */
+export class SandboxWrapperError extends Error {
+  constructor(
+    readonly command: string,
+    readonly code: number,
+  ) {
+    super(
+      `The sandbox failed to start the command (exit status ${code}): ${command}`,
+    )
+    this.name = 'SandboxWrapperError'
+  }
+}
+
+/** Descriptor bwrap reports on when srt spawns the wrapped command */
+export const WRAPPER_STATUS_FD = 3
+
+/** How long to wait for the wrapper's status after the command exited */
+const WRAPPER_STATUS_WAIT_MS = 1000
+
+/** How long to wait for a command's piped output to close after it exited */
+const OUTPUT_CLOSE_WAIT_MS = 1000
+
+/**
+ * @returns false when there is no such group (any more)
+ */
+function killProcessGroup(pid: number, signal: NodeJS.Signals): boolean {
+  try {
+    process.kill(-pid, signal)
+    return true
+  } catch (error) {
+    // ESRCH: the group already exited
+    if ((error as NodeJS.ErrnoException).code !== 'ESRCH') {
+      throw error
+    }
+    return false
+  }
+}
+
+/** Commands spawned as the leader of their own process group */
+const processGroupLeaders = new WeakSet<ChildProcess>()
+
+/**
+ * Send a signal to a spawned command and everything it started: its process
+ * group, or each of its descendants when it shares srt's group (a command
+ * reading from the terminal). A process group outlives its leader while
+ * background processes remain in it, so it is signalled until none are left.
+ * @returns false once there is nothing left to signal
+ */
+export function signalProcessTree(
+  child: ChildProcess,
+  signal: NodeJS.Signals,
+): boolean {
+  if (child.pid === undefined) {
+    return false
+  }
+  if (processGroupLeaders.has(child)) {
+    return killProcessGroup(child.pid, signal)
+  }
+  if (child.exitCode !== null || child.signalCode !== null) {
+    return false
+  }
+  signalDescendants(child.pid, signal)
+  return true
+}
+
+/** The cgroup v2 path of a process, e.g. /user.slice/.../run-u42.scope */
+function readCgroup(pid: number | 'self'): string | undefined {
+  try {
+    const line = fs
+      .readFileSync(`/proc/${pid}/cgroup`, 'utf8')
+      .split('\n')
+      .find(entry => entry.startsWith('0::'))
+    return line?.slice(3)
+  } catch {
+    return undefined
+  }
+}
+
+/**
+ * The transient systemd scopes (the resources setting) a command's
+ * processes run in, on Linux with cgroup v2
+ */
+export function getProcessTreeScopes(pid: number): string[] {
+  const own = readCgroup('self')
+  if (own === undefined) {
+    return []
+  }
+  const scopes = new Set<string>()
+  for (const p of getProcessTree(pid)) {
+    const cgroup = readCgroup(p)
+    if (cgroup && cgroup !== own && /\/run-[^/]+\.scope$/.test(cgroup)) {
+      scopes.add(cgroup)
+    }
+  }
+  return [...scopes]
+}
+
+/**
+ * Kill every process in a cgroup with SIGKILL, including those that left
+ * the process group or were reparented
+ * @returns false without cgroup.kill (Linux before 5.14) or once it is gone
+ */
+export function killCgroup(cgroup: string): boolean {
+  try {
+    fs.writeFileSync(`/sys/fs/cgroup${cgroup}/cgroup.kill`, '1')
+    return true
+  } catch {
+    return false
+  }
+}
+
+/**
+ * Send a signal to a process and each of its descendants, for commands that
+ * share srt's process group
+ */
+function signalDescendants(pid: number, signal: NodeJS.Signals): void {
+  for (const p of getProcessTree(pid)) {
+    try {
+      process.kill(p, signal)
+    } catch {
+      // Exited in the meantime
+    }
+  }
+}
+
+/**
+ * Whether the command reads from the terminal srt was started on, which it
+ * can only do from the terminal's foreground process group
+ */
+function readsFromTerminal(stdio: StdioOptions): boolean {
+  const stdin = typeof stdio === 'string' ? stdio : stdio[0]
+  return (stdin === 'inherit' || stdin === 0) && process.stdin.isTTY === true
+}
+
+/** Signals the terminal sends to its whole foreground process group */
+const TERMINAL_SIGNALS: ReadonlySet<string> = new Set([
+  'SIGINT',
+  'SIGQUIT',
+  'SIGTSTP',
+])
+
+function isSignalName(reason: unknown): reason is NodeJS.Signals {
+  return typeof reason === 'string' && reason in constants.signals
+}
+
+/** Parent pid of every process, from /proc on Linux and ps elsewhere */
+function getParentPids(): Map<number, number> {
+  const parents = new Map<number, number>()
+  if (fs.existsSync('/proc/self/stat')) {
+    for (const entry of fs.readdirSync('/proc')) {
+      if (!/^\d+$/.test(entry)) {
+        continue
+      }
+      try {
+        const stat = fs.readFileSync(`/proc/${entry}/stat`, 'utf8')
+        // The name in parentheses may contain spaces; ppid is the second
+        // field after it
+        const ppid = stat.slice(stat.lastIndexOf(')') + 2).split(' ')[1]
+        parents.set(Number(entry), Number(ppid))
+      } catch {
+        // The process exited while listing
+      }
+    }
+    return parents
+  }
+  const ps = spawnSync('ps', ['-A', '-o', 'pid=,ppid='], { encoding: 'utf8' })
+  for (const line of ps.stdout?.split('\n') ?? []) {
+    const [pid, ppid] = line.trim().split(/\s+/).map(Number)
+    if (pid > 0) {
+      parents.set(pid, ppid)
+    }
+  }
+  return parents
+}
+
+/** pid and all of its descendants, parents before their children */
+function getProcessTree(pid: number): number[] {
+  const parents = getParentPids()
+  const tree = [pid]
+  for (let i = 0; i < tree.length; i++) {
+    for (const [child, parent] of parents) {
+      if (parent === tree[i]) {
+        tree.push(child)
+      }
+    }
+  }
+  return tree
+}
+
+/**
+ * Stop a process and everything it started with SIGSTOP, including
+ * processes in other sessions such as those bwrap starts, e.g. while srt
+ * asks a question on the terminal they share. Parents are stopped first so
+ * they can't start more; the tree is listed again until nothing new shows up.
+ * @returns A function continuing the stopped processes with SIGCONT
+ */
+export function suspendProcessTree(pid: number): () => void {
+  const stopped = new Set<number>()
+  for (let pass = 0; pass < 5; pass++) {
+    const pending = getProcessTree(pid).filter(p => !stopped.has(p))
+    if (pending.length === 0) {
+      break
+    }
+    for (const p of pending) {
+      try {
+        process.kill(p, 'SIGSTOP')
+        stopped.add(p)
+      } catch {
+        // Exited in the meantime
+      }
+    }
+  }
+  return () => {
+    for (const p of stopped) {
+      try {
+        process.kill(p, 'SIGCONT')
+      } catch {
+        // Exited while stopped, e.g. killed
+      }
+    }
+  }
+}
+
+/** stdio with a pipe at the status descriptor */
+function withStatusPipe(
+  stdio: StdioOptions,
+  statusFd: number | undefined,
+): StdioOptions {
+  if (statusFd === undefined) {
+    return stdio
+  }
+  const streams =
+    typeof stdio === 'string' ? [stdio, stdio, stdio] : [...stdio]
+  while (streams.length < statusFd) {
+    streams.push('ignore')
+  }
+  streams[statusFd] = 'pipe'
+  return streams
+}
+
+/**
+ * Follow bwrap's --json-status-fd reports
+ * @returns Resolves whether the command was started, once the wrapper closed
+ *   the descriptor or a short wait passed
+ */
+function readWrapperStatus(
+  child: ChildProcess,
+  statusFd: number | undefined,
+): (() => Promise<boolean>) | undefined {
+  const status = statusFd === undefined ? undefined : child.stdio[statusFd]
+  if (!status || !('setEncoding' in status)) {
+    return undefined
+  }
+  let started = false
+  let report = ''
+  status.setEncoding('utf8')
+  status.on('data', (chunk: string) => {
+    if (!started) {
+      report += chunk
+      // {"child-pid": 123} comes first once the command is running
+      started = report.includes('"child-pid"')
+    }
+  })
+  status.on('error', () => {})
+  const closed = new Promise<void>(resolve => status.once('close', resolve))
+  return async () => {
+    let timer: NodeJS.Timeout | undefined
+    await Promise.race([
+      closed,
+      new Promise(resolve => {
+        timer = setTimeout(resolve, WRAPPER_STATUS_WAIT_MS)
+      }),
+    ])
+    clearTimeout(timer)
+    status.destroy()
+    return started
+  }
+}
+
+/**
+ * Follow a spawned command's piped output, which can close after it exited
+ * ('exit' may come before the last of the output, 'close' after it)
+ * @returns Resolves once the output closed, or after a short wait when a
+ *   background process still holds the pipes, which are then destroyed
+ */
+export function watchOutputClose(child: ChildProcess): () => Promise<void> {
+  const closed = new Promise<void>(resolve => child.once('close', resolve))
+  return async () => {
+    let timer: NodeJS.Timeout | undefined
+    await Promise.race([
+      closed,
+      new Promise(resolve => {
+        timer = setTimeout(resolve, OUTPUT_CLOSE_WAIT_MS)
+      }),
+    ])
+    clearTimeout(timer)
+    child.stdout?.destroy()
+    child.stderr?.destroy()
+  }
+}
+
+function writeStdin(child: ChildProcess, input: SandboxStdinInput): void {
+  const { stdin } = child
+  if (!stdin) {
+    return
+  }
+  // The command may exit without reading all of its input
+  stdin.on('error', () => {})
+  if (typeof input === 'string' || input instanceof Uint8Array) {
+    stdin.end(input)
+    return
+  }
+  pipeline(input, stdin).catch(error =>
+    logForDebugging(`[Sandbox] Stopped writing stdin: ${error}`),
+  )
+}
+
+/**
+ * Turn the stdin option into a stdin pipe and an onSpawn that writes the
+ * input to it, so it reaches the command with any backend that honours
+ * stdio and onSpawn
+ */
+export function withStdinInput(
+  options: SandboxExecuteOptions,
+): SandboxExecuteOptions {
+  const { stdin: input, stdio = 'inherit', onSpawn } = options
+  if (input === undefined) {
+    return options
+  }
+  const output = typeof stdio === 'string' ? [stdio, stdio] : stdio.slice(1)
+  return {
+    ...options,
+    stdio: ['pipe', ...output],
+    onSpawn: child => {
+      onSpawn?.(child)
+      writeStdin(child, input)
+    },
+  }
+}
+
+/**
+ * Spawn a wrapped sandbox command and wait for it to exit, rejecting with
+ * SandboxTimeoutError if it is killed for running too long
+ */
+export function runSandboxedCommand(
+  sandboxedCommand: string,
+  command: string,
+  options: Pick<
+    SandboxExecuteOptions,
+    'abortSignal' | 'timeoutSeconds' | 'stdio' | 'onSpawn'
+  > & {
+    /** Run in its own process group even without a timeout (not when reading from the terminal) */
+    processGroup?: boolean
+    /** Environment to spawn with (default: srt's environment) */
+    env?: NodeJS.ProcessEnv
+    /**
+     * Descriptor bwrap reports the command's start on; exiting with an
+     * error status without that report rejects with SandboxWrapperError
+     */
+    statusFd?: number
+  } = {},
+): Promise<SandboxExecuteResult> {
+  const { abortSignal, timeoutSeconds, env, statusFd } = options
+  const stdio = withStatusPipe(options.stdio ?? 'inherit', statusFd)
+  const interactive = readsFromTerminal(stdio)
+  const ownProcessGroup =
+    !interactive &&
+    (timeoutSeconds !== undefined || options.processGroup === true)
+
+  return new Promise((resolve, reject) => {
+    const child = spawn(sandboxedCommand, {
+      shell: true,
+      stdio,
+      detached: ownProcessGroup,
+      env,
+    })
+    if (ownProcessGroup) {
+      processGroupLeaders.add(child)
+    }
+    const wrapperStatus = readWrapperStatus(child, statusFd)
+    options.onSpawn?.(child)
+
+    const kill = (signal: NodeJS.Signals) => {
+      if (child.pid === undefined) {
+        return
+      }
+      if (ownProcessGroup) {
+        killProcessGroup(child.pid, signal)
+      } else if (interactive) {
+        signalDescendants(child.pid, signal)
+      } else {
+        child.kill(signal)
+      }
+    }
+
+    let timedOut = false
+    const timer =
+      timeoutSeconds !== undefined
+        ? setTimeout(() => {
+            timedOut = true
+            logForDebugging(
+              `[Sandbox] Killing process group ${child.pid} after ${timeoutSeconds}s timeout`,
+              { level: 'warn' },
+            )
+            kill('SIGKILL')
+          }, timeoutSeconds * 1000)
+        : undefined
+
+    const killForAbort = (afterSpawn: boolean) => {
+      const reason: unknown = abortSignal?.reason
+      if (reason instanceof SandboxViolationKillError) {
+        kill('SIGKILL')
+      } else if (!isSignalName(reason)) {
+        kill('SIGTERM')
+      } else if (!(afterSpawn && interactive && TERMINAL_SIGNALS.has(reason))) {
+        // The terminal already sent its own signals to the command, unless
+        // they came before it was started
+        kill(reason)
+      }
+    }
+    const onAbort = () => killForAbort(true)
+    abortSignal?.addEventListener('abort', onAbort, { once: true })
+    // 'abort' never fires for a signal that was aborted before the spawn
+    if (abortSignal?.aborted) {
+      killForAbort(false)
+    }
+
+    const cleanup = () => {
+      clearTimeout(timer)
+      abortSignal?.removeEventListener('abort', onAbort)
+    }
+
+    child.on('error', error => {
+      cleanup()
+      reject(error)
+    })
+
+    child.on('exit', async (code, signal) => {
+      cleanup()
+      if (timedOut && timeoutSeconds !== undefined) {
+        reject(new SandboxTimeoutError(command, timeoutSeconds))
+        return
+      }
+      if (abortSignal?.reason instanceof SandboxViolationKillError) {
+        reject(abortSignal.reason)
+        return
+      }
+      if (
+        wrapperStatus &&
+        code !== null &&
+        code !== 0 &&
+        !abortSignal?.aborted &&
+        !(await wrapperStatus())
+      ) {
+        reject(new SandboxWrapperError(command, code))
+        return
+      }
+      resolve({ code, signal })
+    })
+  })
+}
@@ -0,0 +1,165 @@
+import type { ConfigPresetName } from './config-presets.js'
+import type { SandboxBackend } from './sandbox-backend.js'
+import {
+  SandboxRuntimeConfigSchema,
+  type LinuxSandboxBackend,
+  type SandboxRuntimeConfig,
+} from './sandbox-config.js'
+import type { ISandboxManager } from './sandbox-manager.js'
+import type { SandboxAskCallback } from './sandbox-schemas.js'
+
+/**
+ * Fluent setup of the sandbox manager (`SandboxManager.builder()`)
+ *
+ * Collects the settings of a config one call at a time, starting from the
+ * most restrictive config (no domains, no writable paths), and validates
+ * them against the config schema when built, so mistakes surface before
+ * anything starts.
+ *
+ * ```typescript
+ * await SandboxManager.builder()
+ *   .allowDomain('registry.npmjs.org')
+ *   .allowWrite('.', '/tmp')
+ *   .timeout(60)
+ *   .build()
+ * ```
+ */
+
+/**
+ * Raised by build() when the collected settings are not a valid config
+ */
+export class SandboxConfigValidationError extends Error {
+  constructor(readonly issues: string[]) {
+    super(`Invalid sandbox configuration:\n  - ${issues.join('\n  - ')}`)
+    this.name = 'SandboxConfigValidationError'
+  }
+}
+
+export class SandboxManagerBuilder {
+  private config: SandboxRuntimeConfig = {
+    network: { allowedDomains: [], deniedDomains: [] },
+    filesystem: { denyRead: [], allowWrite: [], denyWrite: [] },
+  }
+  private customBackend: SandboxBackend | undefined
+  private askCallback: SandboxAskCallback | undefined
+  private logMonitor = false
+
+  constructor(private readonly manager: ISandboxManager) {}
+
+  /** Allow connections to these domains (e.g. "*.npmjs.org") */
+  allowDomain(...domains: string[]): this {
+    const { network } = this.config
+    network.allowedDomains = [...network.allowedDomains, ...domains]
+    return this
+  }
+
+  /** Deny connections to these domains, even if allowed otherwise */
+  denyDomain(...domains: string[]): this {
+    const { network } = this.config
+    network.deniedDomains = [...network.deniedDomains, ...domains]
+    return this
+  }
+
+  /** Allow writing to these paths */
+  allowWrite(...paths: string[]): this {
+    const { filesystem } = this.config
+    filesystem.allowWrite = [...filesystem.allowWrite, ...paths]
+    return this
+  }
+
+  /** Deny reading these paths */
+  denyRead(...paths: string[]): this {
+    const { filesystem } = this.config
+    filesystem.denyRead = [...filesystem.denyRead, ...paths]
+    return this
+  }
+
+  /** Deny writing to these paths, even inside writable ones */
+  denyWrite(...paths: string[]): this {
+    const { filesystem } = this.config
+    filesystem.denyWrite = [...filesystem.denyWrite, ...paths]
+    return this
+  }
+
+  /** Apply a built-in policy, e.g. "node-dev" */
+  preset(name: ConfigPresetName): this {
+    this.config.preset = name
+    return this
+  }
+
+  /** Kill commands run with execute after this many seconds */
+  timeout(seconds: number): this {
+    this.config.timeoutSeconds = seconds
+    return this
+  }
+
+  /**
+   * Run commands with a custom backend, or pick the Linux backend of the
+   * built-in sandbox ("bwrap" or "nsjail")
+   */
+  backend(backend: SandboxBackend | LinuxSandboxBackend): this {
+    if (typeof backend === 'string') {
+      this.config.backend = backend
+    } else {
+      this.customBackend = backend
+    }
+    return this
+  }
+
+  /** Ask about connections to domains that are not allowed */
+  onNetworkAsk(callback: SandboxAskCallback): this {
+    this.askCallback = callback
+    return this
+  }
+
+  /** Record violations in the violation store (off by default) */
+  monitorViolations(enable = true): this {
+    this.logMonitor = enable
+    return this
+  }
+
+  /**
+   * Set any other settings, e.g. `{ allowPty: true }`. Lists given here
+   * replace the lists collected so far.
+   */
+  with(settings: Partial<SandboxRuntimeConfig>): this {
+    this.config = {
+      ...this.config,
+      ...settings,
+      network: { ...this.config.network, ...settings.network },
+      filesystem: { ...this.config.filesystem, ...settings.filesystem },
+    }
+    return this
+  }
+
+  /**
+   * Validate the collected settings and return the config
+   * @throws SandboxConfigValidationError
+   */
+  buildConfig(): SandboxRuntimeConfig {
+    const result = SandboxRuntimeConfigSchema.safeParse(this.config)
+    if (!result.success) {
+      throw new SandboxConfigValidationError(
+        result.error.issues.map(
+          issue => `${issue.path.join('.')}: ${issue.message}`,
+        ),
+      )
+    }
+    return result.data
+  }
+
+  /**
+   * Validate the collected settings, register the backend given (or the
+   * built-in one) and initialize the manager with them. The manager must not
+   * be initialized yet; reset() it to build a new config.
+   * @throws SandboxConfigValidationError
+   */
+  async build(): Promise<ISandboxManager> {
+    const config = this.buildConfig()
+    // Throws once the manager is initialized, and clears a backend
+    // registered before when none was given
+    this.manager.registerBackend(this.customBackend)
+    await this.manager.initialize(config, this.askCallback, this.logMonitor)
+    return this.manager
+  }
+}
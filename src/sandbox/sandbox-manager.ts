@@ -1,11 +1,22 @@
 import { createHttpProxyServer } from './http-proxy.js'
 import { createSocksProxyServer } from './socks-proxy.js'
 import type { SocksProxyWrapper } from './socks-proxy.js'
-import { logForDebugging } from '../utils/debug.js'
+import { logForDebugging, setDebugRedactor } from '../utils/debug.js'
 import { cloneDeep } from 'lodash-es'
 import { getPlatform, type Platform } from '../utils/platform.js'
 import * as fs from 'fs'
-import type { SandboxRuntimeConfig } from './sandbox-config.js'
+import type { ChildProcess } from 'node:child_process'
+import type {
+  DesktopConfig,
+  LinuxCapability,
+  LinuxFilesystemBackend,
+  LinuxSandboxBackend,
+  NetworkConfig,
+  SandboxRuntimeConfig,
+  SeccompConfig,
+  UserMappingConfig,
+  ViolationPolicyConfig,
+} from './sandbox-config.js'
 import type {
   SandboxAskCallback,
   FsReadRestrictionConfig,
@@ -14,22 +25,104 @@ import type {
 } from './sandbox-schemas.js'
 import {
   wrapCommandWithSandboxLinux,
+  wrapCommandWithDegradedSandboxLinux,
   initializeLinuxNetworkBridge,
   type LinuxNetworkBridgeContext,
+  hasLinuxSandboxBackendSync,
   hasLinuxSandboxDependenciesSync,
+  hasCgroupResourceControlSync,
+  LANDLOCK_UNAVAILABLE_MESSAGE,
 } from './linux-sandbox-utils.js'
+import { isLandlockSupported } from './landlock-sandbox.js'
 import {
   wrapCommandWithSandboxMacOS,
   startMacOSSandboxLogMonitor,
+  cleanupMacOSProfiles,
+  type NetworkViolationSubtype,
+  type SandboxViolationEvent,
 } from './macos-sandbox-utils.js'
 import {
+  hasOpenBSDSandboxDependenciesSync,
+  wrapCommandWithSandboxOpenBSD,
+} from './openbsd-sandbox-utils.js'
+import {
+  applyRlimits,
   getDefaultWritePaths,
   containsGlobChars,
   removeTrailingGlobSuffix,
 } from './sandbox-utils.js'
 import { hasRipgrepSync } from '../utils/ripgrep.js'
+import { expandGlobPaths } from './glob-expansion.js'
 import { SandboxViolationStore } from './sandbox-violation-store.js'
+import { ViolationJournal } from './violation-journal.js'
+import { ScratchDir } from './scratch-dir.js'
+import {
+  startDbusProxy,
+  stopDbusProxy,
+  type DbusProxyContext,
+} from './desktop-isolation.js'
+import { LinuxViolationMonitor } from './linux-violation-monitor.js'
+import { startMacOSEndpointSecurityMonitor } from './macos-es-monitor.js'
+import {
+  runSandboxedCommand,
+  getProcessTreeScopes,
+  killCgroup,
+  signalProcessTree,
+  watchOutputClose,
+  WRAPPER_STATUS_FD,
+  withStdinInput,
+  DEFAULT_MAX_BUFFER,
+  SandboxOutputLimitError,
+  SandboxTimeoutError,
+  SandboxViolationKillError,
+  type SandboxCommandOutput,
+  type SandboxExecuteOptions,
+  type SandboxOutputOptions,
+  type SandboxExecuteResult,
+  type SandboxExecutionHandle,
+} from './sandbox-execute.js'
+import { getViolationAction, policyMayKill } from './violation-policy.js'
+import { startViolationNotifier } from './violation-notifier.js'
+import { applyConfigPreset } from './config-presets.js'
+import {
+  OutputLineStream,
+  type SandboxStreamingExecution,
+} from './execution-stream.js'
+import {
+  applyCommandPolicy,
+  commandPolicyChangesNetwork,
+  findCommandPolicy,
+  mergeConfigOverrides,
+  type CommandPolicyMatch,
+} from './command-policy.js'
+import { startViolationFeed, type ViolationFeed } from './violation-feed.js'
+import { getSpawnEnv, resolveSandboxEnv } from './sandbox-env.js'
+import { createRedactor } from './redaction.js'
+import { applyAllowListExclusions } from './allow-list-exclusions.js'
+import {
+  applyTemporaryRules,
+  getTemporaryRuleKey,
+  TemporaryRuleTracker,
+} from './temporary-rules.js'
+import {
+  HostResolver,
+  isCloudMetadataHost,
+  isPrivateAddress,
+} from './host-resolver.js'
+import { isIP } from 'node:net'
 import { EOL } from 'node:os'
+import type { SandboxBackend, SandboxBackendName } from './sandbox-backend.js'
+import { SandboxManagerBuilder } from './sandbox-manager-builder.js'
+import { NamedSandbox } from './named-sandbox.js'
+import {
+  SandboxSession,
+  type SandboxSessionOptions,
+} from './sandbox-session.js'
+import {
+  SandboxCommandVetoedError,
+  type SandboxAfterExecuteContext,
+  type SandboxExecutionHooks,
+} from './execution-hooks.js'
 
 interface HostNetworkManagerContext {
   httpProxyPort: number
@@ -37,18 +130,53 @@ interface HostNetworkManagerContext {
   linuxBridge: LinuxNetworkBridgeContext | undefined
 }
 
+/**
+ * Proxies (and on Linux, bridges) of the commands matching one `commands`
+ * key whose domain lists differ from the base lists
+ */
+interface CommandNetworkScope {
+  context: HostNetworkManagerContext
+  httpProxyServer: ReturnType<typeof createHttpProxyServer>
+  socksProxyServer: SocksProxyWrapper
+}
+
+/** SMTP, SMTPS and mail submission */
+export const SMTP_PORTS = [25, 465, 587]
+
 // ============================================================================
 // Private Module State
 // ============================================================================
 
 let config: SandboxRuntimeConfig | undefined
+/** config before the active temporary rules were added */
+let configWithoutTemporaryRules: SandboxRuntimeConfig | undefined
+let temporaryRuleTracker = new TemporaryRuleTracker()
+let activeTemporaryRuleKeys = ''
 let httpProxyServer: ReturnType<typeof createHttpProxyServer> | undefined
 let socksProxyServer: SocksProxyWrapper | undefined
 let managerContext: HostNetworkManagerContext | undefined
 let initializationPromise: Promise<HostNetworkManagerContext> | undefined
+let networkAskCallback: SandboxAskCallback | undefined
+let registeredBackend: SandboxBackend | undefined
+const commandNetworkScopes = new Map<string, Promise<CommandNetworkScope>>()
+const namedSandboxes = new Map<string, NamedSandbox>()
+const executionHooks = new Set<SandboxExecutionHooks>()
+// Commands the manager is running, to tell whether a network violation can
+// only be one command's
+let runningCommands = 0
 let cleanupRegistered = false
 let logMonitorShutdown: (() => void) | undefined
+let esMonitorShutdown: (() => void) | undefined
+let notifierShutdown: (() => void) | undefined
+let violationFeed: ViolationFeed | undefined
+let linuxViolationMonitor: LinuxViolationMonitor | undefined
+let scratchDir: ScratchDir | undefined
+let degradedSandbox = false
+let dbusProxy: DbusProxyContext | undefined
 const sandboxViolationStore = new SandboxViolationStore()
+// Allowed file accesses recorded in audit mode, kept apart from violations
+const sandboxFileAccessStore = new SandboxViolationStore()
+const hostResolver = new HostResolver()
 // ============================================================================
 // Private Helper Functions (not exported)
 // ============================================================================
@@ -69,7 +197,10 @@ function registerCleanup(): void {
   cleanupRegistered = true
 }
 
-function matchesDomainPattern(hostname: string, pattern: string): boolean {
+export function matchesDomainPattern(
+  hostname: string,
+  pattern: string,
+): boolean {
   // Support wildcard patterns like *.example.com
   // This matches any subdomain but not the base domain itself
   if (pattern.startsWith('*.')) {
@@ -81,28 +212,121 @@ function matchesDomainPattern(hostname: string, pattern: string): boolean {
   return hostname.toLowerCase() === pattern.toLowerCase()
 }
 
+function isDeniedHost(host: string, network = config?.network): boolean {
+  if (!network) {
+    return false
+  }
+  return network.deniedDomains.some(deniedDomain =>
+    matchesDomainPattern(host, deniedDomain),
+  )
+}
+
+function isAllowedHost(host: string, network = config?.network): boolean {
+  if (!network || isDeniedHost(host, network)) {
+    return false
+  }
+  return network.allowedDomains.some(allowedDomain =>
+    matchesDomainPattern(host, allowedDomain),
+  )
+}
+
+function recordNetworkViolation(
+  subtype: NetworkViolationSubtype,
+  host: string,
+  port: number,
+  reason: string,
+): void {
+  sandboxViolationStore.addViolation({
+    line: `Network request to ${host}:${port} blocked: ${reason}`,
+    type: 'network',
+    subtype,
+    host,
+    port,
+    timestamp: new Date(),
+  })
+}
+
+/**
+ * Whether a host was explicitly allowed as a local target
+ * IP literals and localhost cannot be rebound, so allowing them by name is
+ * an explicit request to reach the local address.
+ */
+function isExplicitLocalTarget(
+  host: string,
+  network = config?.network,
+): boolean {
+  return (
+    (isIP(host) !== 0 || host.toLowerCase() === 'localhost') &&
+    isAllowedHost(host, network)
+  )
+}
+
+/**
+ * Decide whether the proxies may connect to host:port
+ * network holds the domain lists to apply: the config's, or those of a
+ * `commands` override for its dedicated proxies.
+ */
 async function filterNetworkRequest(
   port: number,
   host: string,
   sandboxAskCallback?: SandboxAskCallback,
+  commandPattern?: string,
 ): Promise<boolean> {
+  refreshTemporaryRules()
   if (!config) {
     logForDebugging('No config available, denying network request')
     return false
   }
+  const network =
+    commandPattern !== undefined
+      ? getCommandNetwork(commandPattern)
+      : config.network
+
+  // Cloud metadata endpoints hand out instance credentials, so they are
+  // blocked even if an allowed domain (e.g. a wildcard) matches them
+  if (!config.network.allowCloudMetadata && isCloudMetadataHost(host)) {
+    logForDebugging(`Denied cloud metadata endpoint: ${host}:${port}`, {
+      level: 'warn',
+    })
+    recordNetworkViolation(
+      'cloud-metadata',
+      host,
+      port,
+      'cloud metadata endpoint',
+    )
+    return false
+  }
+
+  // Sending mail needs an explicit opt-in; allowing a domain is not enough
+  if (!config.network.allowSmtp && SMTP_PORTS.includes(port)) {
+    logForDebugging(`Denied SMTP port: ${host}:${port}`, { level: 'warn' })
+    recordNetworkViolation('smtp', host, port, 'SMTP port')
+    return false
+  }
 
   // Check denied domains first
-  for (const deniedDomain of config.network.deniedDomains) {
-    if (matchesDomainPattern(host, deniedDomain)) {
-      logForDebugging(`Denied by config rule: ${host}:${port}`)
-      return false
-    }
+  if (isDeniedHost(host, network)) {
+    logForDebugging(`Denied by config rule: ${host}:${port}`)
+    recordNetworkViolation('denied-domain', host, port, 'denied domain')
+    return false
   }
 
   // Check allowed domains
-  for (const allowedDomain of config.network.allowedDomains) {
-    if (matchesDomainPattern(host, allowedDomain)) {
-      logForDebugging(`Allowed by config rule: ${host}:${port}`)
+  if (isAllowedHost(host, network)) {
+    logForDebugging(`Allowed by config rule: ${host}:${port}`)
+    return true
+  }
+
+  // IP literal targets (e.g. from clients that resolve locally) are only allowed
+  // if they forward-confirm reverse-map to an allowed domain
+  if (isIP(host) !== 0) {
+    const verifiedHost = await hostResolver.verifyIpTarget(host, name =>
+      isAllowedHost(name, network),
+    )
+    if (verifiedHost) {
+      logForDebugging(
+        `Allowed IP target ${host}:${port} (reverse-maps to ${verifiedHost})`,
+      )
       return true
     }
   }
@@ -110,6 +334,7 @@ async function filterNetworkRequest(
   // No matching rules - ask user or deny
   if (!sandboxAskCallback) {
     logForDebugging(`No matching config rule, denying: ${host}:${port}`)
+    recordNetworkViolation('not-allowed', host, port, 'not an allowed domain')
     return false
   }
 
@@ -121,32 +346,88 @@ async function filterNetworkRequest(
       return true
     } else {
       logForDebugging(`User denied: ${host}:${port}`)
+      recordNetworkViolation('user-denied', host, port, 'denied by user')
       return false
     }
   } catch (error) {
     logForDebugging(`Error in permission callback: ${error}`, {
       level: 'error',
     })
+    recordNetworkViolation('user-denied', host, port, 'permission check failed')
     return false
   }
 }
 
-async function startHttpProxyServer(
-  sandboxAskCallback?: SandboxAskCallback,
-): Promise<number> {
-  httpProxyServer = createHttpProxyServer({
-    filter: (port: number, host: string) =>
-      filterNetworkRequest(port, host, sandboxAskCallback),
-  })
+/**
+ * Resolve the address the proxies connect to for an allowed host
+ * The proxies connect to this address instead of re-resolving the name.
+ * Resolutions to private ranges are blocked as DNS rebinding unless
+ * network.allowPrivateRanges is set.
+ */
+async function resolveNetworkTarget(
+  host: string,
+  port: number,
+  network = config?.network,
+): Promise<string | undefined> {
+  try {
+    const addresses = await hostResolver.resolve(host)
+    if (addresses.length === 0) {
+      logForDebugging(`No addresses found for ${host}:${port}`)
+      return undefined
+    }
 
-  return new Promise<number>((resolve, reject) => {
-    if (!httpProxyServer) {
-      reject(new Error('HTTP proxy server undefined before listen'))
-      return
+    if (!config?.network.allowCloudMetadata) {
+      const metadataAddress = addresses.find(a =>
+        isCloudMetadataHost(a.address),
+      )
+      if (metadataAddress) {
+        logForDebugging(
+          `Blocked ${host}:${port}: resolved to cloud metadata endpoint ${metadataAddress.address}`,
+          { level: 'warn' },
+        )
+        recordNetworkViolation(
+          'cloud-metadata',
+          host,
+          port,
+          `resolved to cloud metadata endpoint ${metadataAddress.address}`,
+        )
+        return undefined
+      }
+    }
+
+    if (
+      !config?.network.allowPrivateRanges &&
+      !isExplicitLocalTarget(host, network)
+    ) {
+      const privateAddress = addresses.find(a => isPrivateAddress(a.address))
+      if (privateAddress) {
+        logForDebugging(
+          `Blocked DNS rebinding: ${host}:${port} resolved to ${privateAddress.address}`,
+          { level: 'warn' },
+        )
+        recordNetworkViolation(
+          'dns-rebinding',
+          host,
+          port,
+          `resolved to private address ${privateAddress.address}`,
+        )
+        return undefined
+      }
     }
 
-    const server = httpProxyServer
+    return addresses[0].address
+  } catch (error) {
+    logForDebugging(`Failed to resolve ${host}:${port}: ${error}`, {
+      level: 'error',
+    })
+    return undefined
+  }
+}
 
+function listenHttpProxyServer(
+  server: ReturnType<typeof createHttpProxyServer>,
+): Promise<number> {
+  return new Promise<number>((resolve, reject) => {
     server.once('error', reject)
     server.once('listening', () => {
       const address = server.address()
@@ -163,35 +444,143 @@ async function startHttpProxyServer(
   })
 }
 
+async function listenSocksProxyServer(
+  server: SocksProxyWrapper,
+): Promise<number> {
+  const port = await server.listen(0, '127.0.0.1')
+  server.unref()
+  return port
+}
+
+async function startHttpProxyServer(
+  sandboxAskCallback?: SandboxAskCallback,
+): Promise<number> {
+  httpProxyServer = createHttpProxyServer({
+    filter: (port: number, host: string) =>
+      filterNetworkRequest(port, host, sandboxAskCallback),
+    resolveHost: resolveNetworkTarget,
+  })
+  return listenHttpProxyServer(httpProxyServer)
+}
+
 async function startSocksProxyServer(
   sandboxAskCallback?: SandboxAskCallback,
 ): Promise<number> {
   socksProxyServer = createSocksProxyServer({
     filter: (port: number, host: string) =>
       filterNetworkRequest(port, host, sandboxAskCallback),
+    resolveHost: resolveNetworkTarget,
   })
+  return listenSocksProxyServer(socksProxyServer)
+}
 
-  return new Promise<number>((resolve, reject) => {
-    if (!socksProxyServer) {
-      // This is mostly just for the typechecker
-      reject(new Error('SOCKS proxy server undefined before listen'))
-      return
-    }
+/**
+ * The network config of commands matching a `commands` key, read from the
+ * current config so updateConfig also applies to running scopes
+ */
+function getCommandNetwork(pattern: string): NetworkConfig | undefined {
+  const policy = config?.commands?.[pattern]
+  return config && policy
+    ? applyCommandPolicy(config, policy).network
+    : config?.network
+}
 
-    socksProxyServer
-      .listen(0, '127.0.0.1')
-      .then((port: number) => {
-        socksProxyServer?.unref()
-        resolve(port)
-      })
-      .catch(reject)
-  })
+async function startCommandNetworkScope(
+  pattern: string,
+): Promise<CommandNetworkScope> {
+  const options = {
+    filter: (port: number, host: string) =>
+      filterNetworkRequest(port, host, networkAskCallback, pattern),
+    resolveHost: (host: string, port: number) =>
+      resolveNetworkTarget(host, port, getCommandNetwork(pattern)),
+  }
+  const scopeHttpProxyServer = createHttpProxyServer(options)
+  const scopeSocksProxyServer = createSocksProxyServer(options)
+  const httpProxyPort = await listenHttpProxyServer(scopeHttpProxyServer)
+  const socksProxyPort = await listenSocksProxyServer(scopeSocksProxyServer)
+
+  let linuxBridge: LinuxNetworkBridgeContext | undefined
+  if (getActiveBackend() === linuxBackend) {
+    linuxBridge = await initializeLinuxNetworkBridge(
+      httpProxyPort,
+      socksProxyPort,
+    )
+  }
+  logForDebugging(`Started network proxies for commands matching "${pattern}"`)
+  return {
+    context: { httpProxyPort, socksProxyPort, linuxBridge },
+    httpProxyServer: scopeHttpProxyServer,
+    socksProxyServer: scopeSocksProxyServer,
+  }
+}
+
+/**
+ * The proxies a command connects through: those of its `commands` override
+ * when the override changes the domain lists, else the shared proxies
+ */
+async function getCommandNetworkContext(
+  match: CommandPolicyMatch | undefined,
+): Promise<HostNetworkManagerContext | undefined> {
+  if (!match || !commandPolicyChangesNetwork(match.policy)) {
+    return managerContext
+  }
+  // External proxies do their own filtering for every command
+  if (
+    config?.network.httpProxyPort !== undefined ||
+    config?.network.socksProxyPort !== undefined
+  ) {
+    logForDebugging(
+      `External proxies in use; domains of commands matching "${match.pattern}" are not applied`,
+      { level: 'warn' },
+    )
+    return managerContext
+  }
+
+  let scope = commandNetworkScopes.get(match.pattern)
+  if (!scope) {
+    scope = startCommandNetworkScope(match.pattern)
+    commandNetworkScopes.set(match.pattern, scope)
+    // Let a later command retry after a failed start
+    scope.catch(() => commandNetworkScopes.delete(match.pattern))
+  }
+  return (await scope).context
 }
 
 // ============================================================================
 // Public Module Functions (will be exported via namespace)
 // ============================================================================
 
+/**
+ * Use a new config, with the temporary rules that are active now added
+ */
+function setConfig(newConfig: SandboxRuntimeConfig): void {
+  configWithoutTemporaryRules = applyConfigPreset(newConfig)
+  config = applyAllowListExclusions(configWithoutTemporaryRules)
+  activeTemporaryRuleKeys = ''
+  refreshTemporaryRules()
+}
+
+/**
+ * Drop temporary rules that expired since the config was last built
+ * Called for every command and network request, so rules expiring during a
+ * session stop applying without a restart.
+ */
+function refreshTemporaryRules(): void {
+  if (!configWithoutTemporaryRules?.temporaryRules) {
+    return
+  }
+  const active = temporaryRuleTracker.select(
+    configWithoutTemporaryRules.temporaryRules,
+  )
+  const keys = active.map(getTemporaryRuleKey).join(',')
+  if (keys !== activeTemporaryRuleKeys) {
+    activeTemporaryRuleKeys = keys
+    config = applyAllowListExclusions(
+      applyTemporaryRules(configWithoutTemporaryRules, active),
+    )
+  }
+}
+
 async function initialize(
   runtimeConfig: SandboxRuntimeConfig,
   sandboxAskCallback?: SandboxAskCallback,
@@ -203,96 +592,227 @@ async function initialize(
     return
   }
 
-  // Store config for use by other functions
-  config = runtimeConfig
+  // Set before anything is awaited, so a concurrent call waits for this one
+  initializationPromise = initializeSandbox(
+    runtimeConfig,
+    sandboxAskCallback,
+    enableLogMonitor,
+  )
+  await initializationPromise
+}
 
-  // Check dependencies now that we have config with ripgrep info
-  if (!checkDependencies()) {
-    const platform = getPlatform()
-    let errorMessage = 'Sandbox dependencies are not available on this system.'
+async function initializeSandbox(
+  runtimeConfig: SandboxRuntimeConfig,
+  sandboxAskCallback: SandboxAskCallback | undefined,
+  enableLogMonitor: boolean,
+): Promise<HostNetworkManagerContext> {
+  try {
+    // Store config for use by other functions
+    setConfig(runtimeConfig)
+    networkAskCallback = sandboxAskCallback
+
+    // A registered backend checks what it needs itself
+    await registeredBackend?.prepare?.(config)
+
+    // Check dependencies now that we have config with ripgrep info
+    // On Linux, allowDegradedSandbox opts in to a reduced sandbox instead of
+    // failing, but only when bwrap/nsjail itself is missing. Other tools,
+    // like ripgrep or socat, can be installed without privileges, so they
+    // still fail.
+    const hasDependencies =
+      registeredBackend !== undefined || checkDependencies()
+    if (
+      !hasDependencies &&
+      getPlatform() === 'linux' &&
+      config.allowDegradedSandbox &&
+      !hasLinuxSandboxBackendSync(config.backend)
+    ) {
+      degradedSandbox = true
+      logForDebugging(
+        `${config.backend ?? 'bwrap'} is missing; using the DEGRADED sandbox (seccomp, rlimits, ` +
+          'env scrubbing, proxy env). Filesystem and network are NOT isolated.',
+        { level: 'warn' },
+      )
+    } else if (!hasDependencies) {
+      const platform = getPlatform()
+      let errorMessage =
+        'Sandbox dependencies are not available on this system.'
+
+      if (platform === 'linux') {
+        errorMessage +=
+          config.backend === 'nsjail'
+            ? ' Required: ripgrep (rg), nsjail, and socat.'
+            : ' Required: ripgrep (rg), bubblewrap (bwrap), and socat.'
+        if (!hasLinuxSandboxBackendSync(config.backend)) {
+          errorMessage +=
+            ' Set allowDegradedSandbox to run with seccomp and rlimits only.'
+        }
+      } else if (platform === 'macos') {
+        errorMessage += ' Required: ripgrep (rg).'
+      } else if (platform === 'openbsd') {
+        errorMessage +=
+          ' Required: apply-pledge (built from vendor/openbsd-src/apply-pledge.c).'
+      } else {
+        errorMessage += ` Platform '${platform}' is not supported.`
+      }
 
-    if (platform === 'linux') {
-      errorMessage += ' Required: ripgrep (rg), bubblewrap (bwrap), and socat.'
-    } else if (platform === 'macos') {
-      errorMessage += ' Required: ripgrep (rg).'
-    } else {
-      errorMessage += ` Platform '${platform}' is not supported.`
+      throw new Error(errorMessage)
     }
 
-    throw new Error(errorMessage)
-  }
+    // An explicit Landlock backend must not fall back to bind mounts silently
+    if (
+      getActiveBackend() === linuxBackend &&
+      config.linuxFilesystemBackend === 'landlock' &&
+      !isLandlockSupported()
+    ) {
+      throw new Error(LANDLOCK_UNAVAILABLE_MESSAGE)
+    }
 
-  // Start log monitor for macOS if enabled
-  if (enableLogMonitor && getPlatform() === 'macos') {
-    logMonitorShutdown = startMacOSSandboxLogMonitor(
-      sandboxViolationStore.addViolation.bind(sandboxViolationStore),
-      config.ignoreViolations,
-    )
-    logForDebugging('Started macOS sandbox log monitor')
-  }
+    // Create this session's scratch directory
+    if (config.scratch && !scratchDir) {
+      scratchDir = ScratchDir.create(config.scratch.maxSizeMB)
+    }
 
-  // Register cleanup handlers first time
-  registerCleanup()
+    // Filter the session bus for the sandbox through xdg-dbus-proxy
+    if (
+      getActiveBackend() === linuxBackend &&
+      config.desktop?.dbus === 'proxy' &&
+      !dbusProxy
+    ) {
+      dbusProxy = await startDbusProxy(config.desktop.dbusTalk ?? [])
+    }
 
-  // Initialize network infrastructure
-  initializationPromise = (async () => {
-    try {
-      // Conditionally start proxy servers based on config
-      let httpProxyPort: number
-      if (config.network.httpProxyPort !== undefined) {
-        // Use external HTTP proxy (don't start a server)
-        httpProxyPort = config.network.httpProxyPort
-        logForDebugging(`Using external HTTP proxy on port ${httpProxyPort}`)
-      } else {
-        // Start local HTTP proxy
-        httpProxyPort = await startHttpProxyServer(sandboxAskCallback)
-      }
+    // Proxy denials bypass the platform monitors, so the store filters too
+    sandboxViolationStore.setIgnoreViolations(config.ignoreViolations)
+    sandboxViolationStore.setLimits(config.violationStore)
+    sandboxViolationStore.setRedaction(config.redaction)
+    sandboxFileAccessStore.setLimits(config.violationStore)
+    sandboxFileAccessStore.setRedaction(config.redaction)
+    setDebugRedactor(createRedactor(config.redaction))
+
+    // Reload violation history and journal new violations
+    if (config.violationLogPath) {
+      sandboxViolationStore.attachJournal(
+        new ViolationJournal(
+          config.violationLogPath,
+          config.violationRetention,
+        ),
+      )
+    }
 
-      let socksProxyPort: number
-      if (config.network.socksProxyPort !== undefined) {
-        // Use external SOCKS proxy (don't start a server)
-        socksProxyPort = config.network.socksProxyPort
-        logForDebugging(`Using external SOCKS proxy on port ${socksProxyPort}`)
-      } else {
-        // Start local SOCKS proxy
-        socksProxyPort = await startSocksProxyServer(sandboxAskCallback)
+    // Stream new violations to external UIs
+    if (config.violationFeedSocket && !violationFeed) {
+      violationFeed = await startViolationFeed(
+        config.violationFeedSocket,
+        sandboxViolationStore,
+      )
+    }
+
+    // Alert the configured webhook and hook of new violations
+    if (config.notifications && !notifierShutdown) {
+      notifierShutdown = startViolationNotifier(
+        sandboxViolationStore,
+        config.notifications,
+      )
+    }
+
+    // Start log monitor for macOS if enabled
+    if (enableLogMonitor && getActiveBackend() === macOSBackend) {
+      logMonitorShutdown = startMacOSSandboxLogMonitor(
+        sandboxViolationStore.addViolation.bind(sandboxViolationStore),
+        config.ignoreViolations,
+      )
+      logForDebugging('Started macOS sandbox log monitor')
+    }
+
+    // Audit file accesses on macOS through Endpoint Security
+    if (config.auditFileAccess && getActiveBackend() === macOSBackend) {
+      esMonitorShutdown = startMacOSEndpointSecurityMonitor(
+        sandboxFileAccessStore.addViolation.bind(sandboxFileAccessStore),
+      )
+      if (esMonitorShutdown) {
+        logForDebugging('Started macOS Endpoint Security monitor')
       }
+    }
 
-      // Initialize platform-specific infrastructure
-      let linuxBridge: LinuxNetworkBridgeContext | undefined
-      if (getPlatform() === 'linux') {
-        linuxBridge = await initializeLinuxNetworkBridge(
-          httpProxyPort,
-          socksProxyPort,
-        )
+    // Linux has no sandbox log; trace sandboxed commands with strace instead.
+    // Audit mode needs the same tracing, so it starts the monitor on its own.
+    if (
+      (enableLogMonitor || config.auditFileAccess) &&
+      getActiveBackend() === linuxBackend
+    ) {
+      linuxViolationMonitor = LinuxViolationMonitor.start(
+        sandboxViolationStore.addViolation.bind(sandboxViolationStore),
+        config.ignoreViolations,
+        config.auditFileAccess
+          ? sandboxFileAccessStore.addViolation.bind(sandboxFileAccessStore)
+          : undefined,
+      )
+      if (linuxViolationMonitor) {
+        logForDebugging('Started Linux violation monitor')
       }
+    }
+
+    // Register cleanup handlers first time
+    registerCleanup()
+
+    // Initialize network infrastructure
+    // Conditionally start proxy servers based on config
+    let httpProxyPort: number
+    if (config.network.httpProxyPort !== undefined) {
+      // Use external HTTP proxy (don't start a server)
+      httpProxyPort = config.network.httpProxyPort
+      logForDebugging(`Using external HTTP proxy on port ${httpProxyPort}`)
+    } else {
+      // Start local HTTP proxy
+      httpProxyPort = await startHttpProxyServer(sandboxAskCallback)
+    }
 
-      const context: HostNetworkManagerContext = {
+    let socksProxyPort: number
+    if (config.network.socksProxyPort !== undefined) {
+      // Use external SOCKS proxy (don't start a server)
+      socksProxyPort = config.network.socksProxyPort
+      logForDebugging(`Using external SOCKS proxy on port ${socksProxyPort}`)
+    } else {
+      // Start local SOCKS proxy
+      socksProxyPort = await startSocksProxyServer(sandboxAskCallback)
+    }
+
+    // Initialize platform-specific infrastructure
+    let linuxBridge: LinuxNetworkBridgeContext | undefined
+    if (getActiveBackend() === linuxBackend) {
+      linuxBridge = await initializeLinuxNetworkBridge(
         httpProxyPort,
         socksProxyPort,
-        linuxBridge,
-      }
-      managerContext = context
-      logForDebugging('Network infrastructure initialized')
-      return context
-    } catch (error) {
-      // Clear state on error so initialization can be retried
-      initializationPromise = undefined
-      managerContext = undefined
-      reset().catch(e => {
-        logForDebugging(`Cleanup failed in initializationPromise ${e}`, {
-          level: 'error',
-        })
-      })
-      throw error
+      )
     }
-  })()
 
-  await initializationPromise
+    const context: HostNetworkManagerContext = {
+      httpProxyPort,
+      socksProxyPort,
+      linuxBridge,
+    }
+    managerContext = context
+    logForDebugging('Network infrastructure initialized')
+    return context
+  } catch (error) {
+    // Clear state on error so initialization can be retried
+    initializationPromise = undefined
+    managerContext = undefined
+    config = undefined
+    configWithoutTemporaryRules = undefined
+    networkAskCallback = undefined
+    reset().catch(e => {
+      logForDebugging(`Cleanup failed in initializationPromise ${e}`, {
+        level: 'error',
+      })
+    })
+    throw error
+  }
 }
 
 function isSupportedPlatform(platform: Platform): boolean {
-  const supportedPlatforms: Platform[] = ['macos', 'linux']
+  const supportedPlatforms: Platform[] = ['macos', 'linux', 'openbsd']
   return supportedPlatforms.includes(platform)
 }
 
@@ -317,7 +837,7 @@ function checkDependencies(ripgrepConfig?: {
     return false
   }
 
-  // Determine which ripgrep to check:
+  // Determine which ripgrep to check (OpenBSD does not use it):
   // 1. Parameter takes precedence
   // 2. Then config from initialization
   // 3. Finally default to 'rg'
@@ -326,7 +846,7 @@ function checkDependencies(ripgrepConfig?: {
   // Check ripgrep - only check 'rg' if no custom command is configured
   // If custom command is provided, we trust it exists (will fail naturally if not)
   const hasCustomRipgrep = rgToCheck?.command !== undefined
-  if (!hasCustomRipgrep) {
+  if (!hasCustomRipgrep && platform !== 'openbsd') {
     // Only check for default 'rg' command
     if (!hasRipgrepSync()) {
       return false
@@ -336,31 +856,39 @@ function checkDependencies(ripgrepConfig?: {
   // Platform-specific dependency checks
   if (platform === 'linux') {
     const allowAllUnixSockets = config?.network?.allowAllUnixSockets ?? false
-    return hasLinuxSandboxDependenciesSync(allowAllUnixSockets)
+    return hasLinuxSandboxDependenciesSync(
+      allowAllUnixSockets,
+      config?.backend,
+    )
+  }
+
+  if (platform === 'openbsd') {
+    return hasOpenBSDSandboxDependenciesSync()
   }
 
   // macOS only needs ripgrep (already checked above)
   return true
 }
 
+/**
+ * Filesystem rules in the form the platform's sandbox takes
+ * Linux has no glob support, so globs become the paths that match now.
+ */
+function toPlatformPaths(
+  patterns: string[],
+  rule: 'allow' | 'deny',
+): string[] {
+  const paths = patterns.map(path => removeTrailingGlobSuffix(path))
+  return getPlatform() === 'linux' ? expandGlobPaths(paths, rule) : paths
+}
+
 function getFsReadConfig(): FsReadRestrictionConfig {
   if (!config) {
     return { denyOnly: [] }
   }
 
-  // Filter out glob patterns on Linux
-  const denyPaths = config.filesystem.denyRead
-    .map(path => removeTrailingGlobSuffix(path))
-    .filter(path => {
-      if (getPlatform() === 'linux' && containsGlobChars(path)) {
-        logForDebugging(`Skipping glob pattern on Linux: ${path}`)
-        return false
-      }
-      return true
-    })
-
   return {
-    denyOnly: denyPaths,
+    denyOnly: toPlatformPaths(config.filesystem.denyRead, 'deny'),
   }
 }
 
@@ -369,30 +897,15 @@ function getFsWriteConfig(): FsWriteRestrictionConfig {
     return { allowOnly: getDefaultWritePaths(), denyWithinAllow: [] }
   }
 
-  // Filter out glob patterns on Linux for allowWrite
-  const allowPaths = config.filesystem.allowWrite
-    .map(path => removeTrailingGlobSuffix(path))
-    .filter(path => {
-      if (getPlatform() === 'linux' && containsGlobChars(path)) {
-        logForDebugging(`Skipping glob pattern on Linux: ${path}`)
-        return false
-      }
-      return true
-    })
-
-  // Filter out glob patterns on Linux for denyWrite
-  const denyPaths = config.filesystem.denyWrite
-    .map(path => removeTrailingGlobSuffix(path))
-    .filter(path => {
-      if (getPlatform() === 'linux' && containsGlobChars(path)) {
-        logForDebugging(`Skipping glob pattern on Linux: ${path}`)
-        return false
-      }
-      return true
-    })
+  const allowPaths = toPlatformPaths(config.filesystem.allowWrite, 'allow')
+  const denyPaths = toPlatformPaths(config.filesystem.denyWrite, 'deny')
 
-  // Build allowOnly list: default paths + configured allow paths
-  const allowOnly = [...getDefaultWritePaths(), ...allowPaths]
+  // Build allowOnly list: default paths + scratch directory + configured allow paths
+  const allowOnly = [
+    ...getDefaultWritePaths(),
+    ...getScratchPaths(),
+    ...allowPaths,
+  ]
 
   return {
     allowOnly,
@@ -434,6 +947,69 @@ function getEnableWeakerNestedSandbox(): boolean | undefined {
   return config?.enableWeakerNestedSandbox
 }
 
+function getLinuxFilesystemBackend(): LinuxFilesystemBackend | undefined {
+  return config?.linuxFilesystemBackend
+}
+
+function getUserMapping(): UserMappingConfig | undefined {
+  return config?.userMapping
+}
+
+function getTmpfsSizeMB(): number | undefined {
+  return config?.filesystem.tmpfsSizeMB
+}
+
+function getExtraTmpfs(): string[] | undefined {
+  return config?.filesystem.extraTmpfs
+}
+
+function getDesktopConfig(): DesktopConfig | undefined {
+  return config?.desktop
+}
+
+function getMounts(): string[] | undefined {
+  return config?.filesystem.mounts
+}
+
+function getCapabilities(): LinuxCapability[] | undefined {
+  return config?.capabilities
+}
+
+function getAuditFileAccess(): boolean | undefined {
+  return config?.auditFileAccess
+}
+
+function getAllowSensitiveProcEntries(): boolean | undefined {
+  return config?.allowSensitiveProcEntries
+}
+
+function getLinuxBackend(): LinuxSandboxBackend | undefined {
+  return config?.backend
+}
+
+function getSeccompConfig(): SeccompConfig | undefined {
+  return config?.seccomp
+}
+
+function getScratchPaths(): string[] {
+  return scratchDir ? [scratchDir.path] : []
+}
+
+function getScratchDirPath(): string | undefined {
+  return scratchDir?.path
+}
+
+function isDegradedSandbox(): boolean {
+  return degradedSandbox
+}
+
+/**
+ * The backend that sandboxes commands on this host
+ */
+function getSandboxBackend(): SandboxBackendName {
+  return getActiveBackend().name
+}
+
 function getRipgrepConfig(): { command: string; args?: string[] } {
   return config?.ripgrep ?? { command: 'rg' }
 }
@@ -481,27 +1057,222 @@ async function waitForNetworkInitialization(): Promise<boolean> {
   return managerContext !== undefined
 }
 
-async function wrapWithSandbox(
-  command: string,
-  binShell?: string,
-  customConfig?: Partial<SandboxRuntimeConfig>,
-  abortSignal?: AbortSignal,
-): Promise<string> {
-  const platform = getPlatform()
+// ============================================================================
+// Built-in Backends
+// ============================================================================
 
-  // Get configs - use custom if provided, otherwise fall back to main config
-  // If neither exists, defaults to empty arrays (most restrictive)
-  // Always include default system write paths (like /dev/null, /tmp/claude)
-  const userAllowWrite =
-    customConfig?.filesystem?.allowWrite ?? config?.filesystem.allowWrite ?? []
-  const writeConfig = {
-    allowOnly: [...getDefaultWritePaths(), ...userAllowWrite],
-    denyWithinAllow:
-      customConfig?.filesystem?.denyWrite ?? config?.filesystem.denyWrite ?? [],
-  }
-  const readConfig = {
-    denyOnly:
-      customConfig?.filesystem?.denyRead ?? config?.filesystem.denyRead ?? [],
+const macOSBackend: SandboxBackend = {
+  name: 'sandbox-exec',
+  // macOS sandbox profile supports glob patterns directly, no ripgrep needed
+  wrap: async ({ network, overrides, ...context }) =>
+    wrapCommandWithSandboxMacOS({
+      command: context.command,
+      needsNetworkRestriction: network.restricted,
+      // Only pass proxy ports if proxy is running (when there are domains to filter)
+      httpProxyPort: network.proxied ? network.httpProxyPort : undefined,
+      socksProxyPort: network.proxied ? network.socksProxyPort : undefined,
+      readConfig: context.readConfig,
+      writeConfig: context.writeConfig,
+      allowUnixSockets: getAllowUnixSockets(),
+      allowAllUnixSockets: getAllowAllUnixSockets(),
+      allowLocalBinding: getAllowLocalBinding(),
+      allowDns: config?.network?.allowDns,
+      ignoreViolations: getIgnoreViolations(),
+      allowPty: context.allowPty,
+      allowGitConfig: getAllowGitConfig(),
+      binShell: context.binShell,
+      scratchDir: getScratchDirPath(),
+      seatbelt: overrides?.seatbelt ?? config?.seatbelt,
+      environment: context.environment,
+      redact: createRedactor(config?.redaction),
+    }),
+}
+
+const linuxBackend: SandboxBackend = {
+  get name() {
+    return getLinuxBackend() ?? 'bwrap'
+  },
+  wrap: async ({ network, ...context }) =>
+    wrapCommandWithSandboxLinux({
+      command: context.command,
+      needsNetworkRestriction: network.restricted,
+      // Only pass socket paths if proxy is running (when there are domains to filter)
+      httpSocketPath: network.proxied ? network.httpSocketPath : undefined,
+      socksSocketPath: network.proxied ? network.socksSocketPath : undefined,
+      httpProxyPort: network.proxied ? network.httpProxyPort : undefined,
+      socksProxyPort: network.proxied ? network.socksProxyPort : undefined,
+      readConfig: context.readConfig,
+      writeConfig: context.writeConfig,
+      enableWeakerNestedSandbox: getEnableWeakerNestedSandbox(),
+      allowAllUnixSockets: getAllowAllUnixSockets(),
+      binShell: context.binShell,
+      ripgrepConfig: getRipgrepConfig(),
+      mandatoryDenySearchDepth: getMandatoryDenySearchDepth(),
+      allowGitConfig: getAllowGitConfig(),
+      abortSignal: context.abortSignal,
+      resourceLimits: context.resourceLimits,
+      filesystemBackend: getLinuxFilesystemBackend(),
+      userMapping: getUserMapping(),
+      seccomp: getSeccompConfig(),
+      scratchDir: getScratchDirPath(),
+      backend: getLinuxBackend(),
+      allowSensitiveProcEntries: getAllowSensitiveProcEntries(),
+      capabilities: getCapabilities(),
+      tmpfsSizeMB: getTmpfsSizeMB(),
+      extraTmpfs: getExtraTmpfs(),
+      mounts: getMounts(),
+      desktop: getDesktopConfig(),
+      dbusProxySocketPath: dbusProxy?.socketPath,
+      violationTracePath: linuxViolationMonitor?.registerCommand(
+        context.command,
+      ),
+      auditFileAccess: getAuditFileAccess(),
+      environment: context.environment,
+      statusFd: context.statusFd,
+    }),
+}
+
+const degradedLinuxBackend: SandboxBackend = {
+  name: 'degraded',
+  // Commands are always pointed at the proxies, since the network can't be
+  // taken away from them
+  wrap: async ({ network, ...context }) =>
+    wrapCommandWithDegradedSandboxLinux({
+      command: context.command,
+      needsNetworkRestriction: network.restricted,
+      httpProxyPort: network.httpProxyPort,
+      socksProxyPort: network.socksProxyPort,
+      readConfig: context.readConfig,
+      writeConfig: context.writeConfig,
+      allowAllUnixSockets: getAllowAllUnixSockets(),
+      binShell: context.binShell,
+      resourceLimits: context.resourceLimits,
+      seccomp: getSeccompConfig(),
+      scratchDir: getScratchDirPath(),
+      environment: context.environment,
+    }),
+}
+
+const openBSDBackend: SandboxBackend = {
+  name: 'pledge',
+  wrap: async ({ network, ...context }) => {
+    // pledge cannot limit sockets to the proxies, so a restricted network is
+    // blocked entirely and allowedDomains cannot be honored
+    if (network.proxied) {
+      logForDebugging(
+        '[OpenBSD] allowedDomains cannot be enforced with pledge; blocking all network access',
+        { level: 'warn' },
+      )
+    }
+    return wrapCommandWithSandboxOpenBSD({
+      command: context.command,
+      needsNetworkRestriction: network.restricted,
+      readConfig: context.readConfig,
+      writeConfig: context.writeConfig,
+      allowGitConfig: getAllowGitConfig(),
+      allowUnixSockets: getAllowUnixSockets(),
+      allowAllUnixSockets: getAllowAllUnixSockets(),
+      binShell: context.binShell,
+      environment: context.environment,
+    })
+  },
+}
+
+const unsupportedBackend: SandboxBackend = {
+  name: 'none',
+  wrap: async () => {
+    // Unsupported platform - this should not happen since isSandboxingEnabled() checks platform support
+    throw new Error(
+      `Sandbox configuration is not supported on platform: ${getPlatform()}`,
+    )
+  },
+}
+
+/**
+ * The registered backend, or the built-in one for this platform
+ */
+function getActiveBackend(): SandboxBackend {
+  if (registeredBackend) {
+    return registeredBackend
+  }
+  switch (getPlatform()) {
+    case 'linux':
+      return degradedSandbox ? degradedLinuxBackend : linuxBackend
+    case 'macos':
+      return macOSBackend
+    case 'openbsd':
+      return openBSDBackend
+    default:
+      return unsupportedBackend
+  }
+}
+
+/**
+ * Use a backend instead of the built-in one for this platform, or go back
+ * to the built-in one with undefined. Only possible before initialize() or
+ * after reset(); the backend stays registered across resets.
+ */
+function registerBackend(backend: SandboxBackend | undefined): void {
+  if (initializationPromise) {
+    throw new Error(
+      'Sandbox backends must be registered before SandboxManager.initialize()',
+    )
+  }
+  registeredBackend = backend
+}
+
+function wrapWithSandbox(
+  command: string,
+  binShell?: string,
+  customConfig?: Partial<SandboxRuntimeConfig>,
+  abortSignal?: AbortSignal,
+): Promise<string> {
+  return wrapCommand(command, binShell, customConfig, abortSignal)
+}
+
+/**
+ * wrapWithSandbox, optionally asking the wrapper to report the command's
+ * start on statusFd
+ */
+async function wrapCommand(
+  command: string,
+  binShell?: string,
+  customConfig?: Partial<SandboxRuntimeConfig>,
+  abortSignal?: AbortSignal,
+  statusFd?: number,
+): Promise<string> {
+  const platform = getPlatform()
+  const callerSetsNetwork = customConfig?.network !== undefined
+  customConfig = resolveCommandOverrides(command, customConfig)
+
+  // Get configs - use custom if provided, otherwise fall back to main config
+  // If neither exists, defaults to empty arrays (most restrictive)
+  // Always include default system write paths (like /dev/null, /tmp/claude)
+  const userAllowWrite =
+    customConfig?.filesystem?.allowWrite ?? config?.filesystem.allowWrite ?? []
+  const writeConfig = {
+    allowOnly: [
+      ...getDefaultWritePaths(),
+      ...getScratchPaths(),
+      ...userAllowWrite,
+    ],
+    denyWithinAllow:
+      customConfig?.filesystem?.denyWrite ?? config?.filesystem.denyWrite ?? [],
+  }
+  const readConfig = {
+    denyOnly:
+      customConfig?.filesystem?.denyRead ?? config?.filesystem.denyRead ?? [],
+  }
+
+  // Only the macOS profile matches globs itself. Elsewhere they become the
+  // paths that match now, expanded on every wrap to pick up new matches.
+  if (platform !== 'macos') {
+    readConfig.denyOnly = expandGlobPaths(readConfig.denyOnly, 'deny')
+    writeConfig.allowOnly = expandGlobPaths(writeConfig.allowOnly)
+    writeConfig.denyWithinAllow = expandGlobPaths(
+      writeConfig.denyWithinAllow,
+      'deny',
+    )
   }
 
   // Check if network config is specified - this determines if we need network restrictions
@@ -523,74 +1294,674 @@ async function wrapWithSandbox(
   // This includes empty allowedDomains which means "block all network"
   const needsNetworkRestriction = hasNetworkConfig
 
-  // Network PROXY is only needed when there are domains to filter
-  // If allowedDomains is empty, we block all network and don't need the proxy
-  const needsNetworkProxy = allowedDomains.length > 0
+  // Network PROXY is only needed when there are domains to filter, or when
+  // the ask callback is explicitly meant to see every connection
+  // (askUnlistedDomains). Otherwise an empty allowedDomains blocks all
+  // network and doesn't need the proxy, even with an ask callback.
+  const askUnlistedDomains =
+    networkAskCallback !== undefined &&
+    (customConfig?.network?.askUnlistedDomains ??
+      config?.network.askUnlistedDomains) === true
+  const needsNetworkProxy = allowedDomains.length > 0 || askUnlistedDomains
 
   // Wait for network initialization only if proxy is actually needed
-  if (needsNetworkProxy) {
+  // (the degraded sandbox always points commands at the host proxies)
+  if (needsNetworkProxy || (degradedSandbox && needsNetworkRestriction)) {
     await waitForNetworkInitialization()
   }
 
+  // A command whose override changes the domain lists gets proxies of its own
+  const networkContext = needsNetworkProxy
+    ? await getCommandNetworkContext(
+        callerSetsNetwork
+          ? undefined
+          : findCommandPolicy(config?.commands, command),
+      )
+    : managerContext
+
+  // Refuse to start new commands once the scratch directory is over its cap
+  scratchDir?.assertWithinLimit()
+
   // Check custom config to allow pseudo-terminal (can be applied dynamically)
   const allowPty = customConfig?.allowPty ?? config?.allowPty
 
-  switch (platform) {
-    case 'macos':
-      // macOS sandbox profile supports glob patterns directly, no ripgrep needed
-      return wrapCommandWithSandboxMacOS({
-        command,
-        needsNetworkRestriction,
-        // Only pass proxy ports if proxy is running (when there are domains to filter)
-        httpProxyPort: needsNetworkProxy ? getProxyPort() : undefined,
-        socksProxyPort: needsNetworkProxy ? getSocksProxyPort() : undefined,
-        readConfig,
-        writeConfig,
-        allowUnixSockets: getAllowUnixSockets(),
-        allowAllUnixSockets: getAllowAllUnixSockets(),
-        allowLocalBinding: getAllowLocalBinding(),
-        ignoreViolations: getIgnoreViolations(),
-        allowPty,
-        allowGitConfig: getAllowGitConfig(),
-        binShell,
-      })
+  // Resource limits can also be tightened or relaxed per command
+  let resourceLimits = customConfig?.resources ?? config?.resources
+  let limits = customConfig?.limits ?? config?.limits
+
+  // maxProcesses becomes a pids cgroup limit when the sandbox runs in a cgroup
+  // scope; there is nothing else that counts only the sandbox's processes
+  if (limits?.maxProcesses !== undefined) {
+    const { maxProcesses, ...rlimits } = limits
+    limits = rlimits
+    if (canLimitProcessCount()) {
+      resourceLimits = {
+        ...resourceLimits,
+        pids: Math.min(resourceLimits?.pids ?? maxProcesses, maxProcesses),
+      }
+    } else {
+      logForDebugging(MAX_PROCESSES_UNENFORCED_MESSAGE, { level: 'warn' })
+    }
+  }
 
-    case 'linux':
-      return wrapCommandWithSandboxLinux({
-        command,
-        needsNetworkRestriction,
-        // Only pass socket paths if proxy is running (when there are domains to filter)
-        httpSocketPath: needsNetworkProxy
-          ? getLinuxHttpSocketPath()
-          : undefined,
-        socksSocketPath: needsNetworkProxy
-          ? getLinuxSocksSocketPath()
-          : undefined,
-        httpProxyPort: needsNetworkProxy
-          ? managerContext?.httpProxyPort
-          : undefined,
-        socksProxyPort: needsNetworkProxy
-          ? managerContext?.socksProxyPort
-          : undefined,
-        readConfig,
-        writeConfig,
-        enableWeakerNestedSandbox: getEnableWeakerNestedSandbox(),
-        allowAllUnixSockets: getAllowAllUnixSockets(),
-        binShell,
-        ripgrepConfig: getRipgrepConfig(),
-        mandatoryDenySearchDepth: getMandatoryDenySearchDepth(),
-        allowGitConfig: getAllowGitConfig(),
-        abortSignal,
-      })
+  const environment = resolveSandboxEnv(customConfig?.env ?? config?.env)
+
+  const wrappedCommand = await getActiveBackend().wrap({
+    command,
+    binShell,
+    readConfig,
+    writeConfig,
+    network: {
+      restricted: needsNetworkRestriction,
+      proxied: needsNetworkProxy,
+      httpProxyPort: networkContext?.httpProxyPort,
+      socksProxyPort: networkContext?.socksProxyPort,
+      httpSocketPath: networkContext?.linuxBridge?.httpSocketPath,
+      socksSocketPath: networkContext?.linuxBridge?.socksSocketPath,
+    },
+    allowPty,
+    resourceLimits,
+    environment,
+    overrides: customConfig,
+    abortSignal,
+    statusFd,
+  })
 
-    default:
-      // Unsupported platform - this should not happen since isSandboxingEnabled() checks platform support
-      throw new Error(
-        `Sandbox configuration is not supported on platform: ${platform}`,
+  // rlimits are set on the outermost process so every backend inherits them
+  return applyRlimits(wrappedCommand, limits)
+}
+
+/**
+ * Apply the violation policy to the violations of a running command until
+ * stop is aborted. Proxy denials are not attributed to a command, so a
+ * network violation only kills the command while it is the only one the
+ * manager is running; otherwise it is warned about.
+ */
+async function enforceViolationPolicy(
+  command: string,
+  policy: ViolationPolicyConfig,
+  kill: (violation: SandboxViolationEvent) => void,
+  stop: AbortSignal,
+): Promise<void> {
+  const encodedCommand = sandboxViolationStore.encodeCommand(command)
+  for await (const violation of sandboxViolationStore.stream({
+    signal: stop,
+  })) {
+    const unattributed = violation.type === 'network'
+    if (!unattributed && violation.encodedCommand !== encodedCommand) {
+      continue
+    }
+    let action = getViolationAction(violation, policy)
+    if (action === 'kill' && unattributed && runningCommands > 1) {
+      action = 'warn'
+    }
+    if (action === 'warn') {
+      warnAboutViolation(command, violation)
+    } else if (action === 'kill') {
+      logForDebugging(
+        `Killing "${command}" after a violation: ${violation.line}`,
+        { level: 'warn' },
       )
+      kill(violation)
+      return
+    }
   }
 }
 
+/**
+ * Hand a violation the policy warns about to the onViolationWarning hooks
+ */
+function warnAboutViolation(
+  command: string,
+  violation: SandboxViolationEvent,
+): void {
+  logForDebugging(`Sandbox violation by "${command}": ${violation.line}`, {
+    level: 'warn',
+  })
+  for (const { onViolationWarning } of executionHooks) {
+    try {
+      onViolationWarning?.({ command, violation })
+    } catch (error) {
+      logForDebugging(`onViolationWarning hook failed: ${error}`, {
+        level: 'error',
+      })
+    }
+  }
+}
+
+/**
+ * Run a wrapped command with the backend's execute, or spawn it here
+ */
+async function runWrappedCommand(
+  sandboxedCommand: string,
+  command: string,
+  options: Parameters<typeof runSandboxedCommand>[2],
+): Promise<SandboxExecuteResult> {
+  const backend = getActiveBackend()
+  runningCommands++
+  try {
+    return await (backend.execute
+      ? backend.execute(sandboxedCommand, command, options ?? {})
+      : runSandboxedCommand(sandboxedCommand, command, options))
+  } finally {
+    runningCommands--
+    releaseWrappedCommand(sandboxedCommand)
+  }
+}
+
+/**
+ * Free what the sandbox kept for a command wrapped with wrapWithSandbox once
+ * it has exited: on Linux, its violation trace, after reporting the rest of
+ * it. execute and the other run methods do this themselves.
+ */
+function releaseWrappedCommand(sandboxedCommand: string): void {
+  linuxViolationMonitor?.releaseCommand(sandboxedCommand)
+}
+
+/**
+ * Wrap a command with the sandbox, run it and wait for it to exit
+ * Rejects with SandboxTimeoutError when the timeout (options.timeoutSeconds,
+ * else config timeoutSeconds) kills the command's process group, and with
+ * SandboxViolationKillError when the violation policy kills it.
+ */
+function execute(
+  command: string,
+  options: SandboxExecuteOptions = {},
+): Promise<SandboxExecuteResult> {
+  return executeCommand(command, options, false)
+}
+
+/**
+ * Add hooks called around every command run with execute and the functions
+ * built on it
+ * @returns A function removing the hooks
+ */
+function addHooks(hooks: SandboxExecutionHooks): () => void {
+  executionHooks.add(hooks)
+  return () => {
+    executionHooks.delete(hooks)
+  }
+}
+
+/**
+ * The overrides a command runs with on top of the config: customConfig with
+ * the allow-list exclusions applied, and the command policy matching the
+ * command under it. customConfig takes precedence, but can't drop the
+ * policy's denies.
+ */
+function resolveCommandOverrides(
+  command: string,
+  customConfig?: Partial<SandboxRuntimeConfig>,
+): Partial<SandboxRuntimeConfig> | undefined {
+  refreshTemporaryRules()
+
+  if (customConfig) {
+    customConfig = applyAllowListExclusions(customConfig)
+  }
+
+  const commandPolicy = findCommandPolicy(config?.commands, command)
+  if (!config || !commandPolicy) {
+    return customConfig
+  }
+  logForDebugging(
+    `Applying the "${commandPolicy.pattern}" command policy to: ${command}`,
+  )
+  return mergeConfigOverrides(
+    applyCommandPolicy(config, commandPolicy.policy),
+    customConfig,
+  )
+}
+
+/**
+ * The settings a command runs with, as wrapWithSandbox resolves them. The
+ * result is a copy; changing it does not change the config.
+ */
+function resolveCommandConfig(
+  command: string,
+  customConfig?: Partial<SandboxRuntimeConfig>,
+): SandboxRuntimeConfig | undefined {
+  const overrides = resolveCommandOverrides(command, customConfig)
+  if (!config) {
+    return undefined
+  }
+  return cloneDeep({ ...config, ...overrides })
+}
+
+/**
+ * The environment to spawn a command wrapped with wrapWithSandbox with:
+ * srt's environment plus the variables the env settings set. The wrapped
+ * command only unsets variables by name, so set values never show up on a
+ * command line.
+ */
+function getCommandEnv(
+  command: string,
+  customConfig?: Partial<SandboxRuntimeConfig>,
+): NodeJS.ProcessEnv {
+  return getSpawnEnv(
+    resolveSandboxEnv(resolveCommandConfig(command, customConfig)?.env),
+  )
+}
+
+async function runAfterExecuteHooks(
+  hooks: SandboxExecutionHooks[],
+  context: SandboxAfterExecuteContext,
+): Promise<void> {
+  for (const { afterExecute } of hooks) {
+    try {
+      await afterExecute?.(context)
+    } catch (error) {
+      logForDebugging(`afterExecute hook failed: ${error}`, { level: 'error' })
+    }
+  }
+}
+
+/**
+ * Run the beforeExecute hooks for a command
+ * @returns Runs the afterExecute hooks once the command has settled
+ */
+async function runBeforeExecuteHooks(
+  hooks: SandboxExecutionHooks[],
+  command: string,
+  customConfig?: Partial<SandboxRuntimeConfig>,
+): Promise<
+  (outcome: { result?: SandboxExecuteResult; error?: unknown }) => Promise<void>
+> {
+  const context = {
+    command,
+    policy: resolveCommandConfig(command, customConfig),
+    customConfig,
+  }
+  for (const { beforeExecute } of hooks) {
+    if ((await beforeExecute?.(context)) === false) {
+      throw new SandboxCommandVetoedError(command)
+    }
+  }
+  const startedAt = Date.now()
+  return outcome =>
+    runAfterExecuteHooks(hooks, {
+      ...context,
+      ...outcome,
+      durationMs: Date.now() - startedAt,
+    })
+}
+
+/**
+ * Run a command between the execution hooks
+ */
+async function withExecutionHooks<T extends SandboxExecuteResult>(
+  command: string,
+  customConfig: Partial<SandboxRuntimeConfig> | undefined,
+  run: () => Promise<T>,
+): Promise<T> {
+  const hooks = [...executionHooks]
+  if (hooks.length === 0) {
+    return run()
+  }
+
+  const afterExecute = await runBeforeExecuteHooks(hooks, command, customConfig)
+  try {
+    const result = await run()
+    await afterExecute({ result })
+    return result
+  } catch (error) {
+    await afterExecute({ error })
+    throw error
+  }
+}
+
+/**
+ * execute, optionally running the command in its own process group so it
+ * can be signalled with everything it starts
+ */
+function executeCommand(
+  command: string,
+  options: SandboxExecuteOptions,
+  processGroup: boolean,
+): Promise<SandboxExecuteResult> {
+  return withExecutionHooks(command, options.customConfig, () =>
+    runCommand(command, options, processGroup),
+  )
+}
+
+async function runCommand(
+  command: string,
+  options: SandboxExecuteOptions,
+  processGroup: boolean,
+): Promise<SandboxExecuteResult> {
+  const { binShell, customConfig, abortSignal, stdio, onSpawn } =
+    withStdinInput(options)
+  const sandboxedCommand = await wrapCommand(
+    command,
+    binShell,
+    customConfig,
+    abortSignal,
+    WRAPPER_STATUS_FD,
+  )
+  // Only bwrap reports on it; other wrappers leave the descriptor alone
+  const statusFd = sandboxedCommand.includes(
+    `--json-status-fd ${WRAPPER_STATUS_FD}`,
+  )
+    ? WRAPPER_STATUS_FD
+    : undefined
+  const timeoutSeconds =
+    options.timeoutSeconds ??
+    customConfig?.timeoutSeconds ??
+    config?.timeoutSeconds
+
+  const env = getCommandEnv(command, customConfig)
+
+  return runWrappedCommandWithPolicy(
+    sandboxedCommand,
+    command,
+    {
+      abortSignal,
+      stdio,
+      onSpawn,
+      timeoutSeconds,
+      processGroup,
+      env,
+      statusFd,
+    },
+    customConfig?.violationPolicy ?? config?.violationPolicy,
+  )
+}
+
+/**
+ * Run a wrapped command, killing it when the violation policy says so
+ */
+async function runWrappedCommandWithPolicy(
+  sandboxedCommand: string,
+  command: string,
+  options: NonNullable<Parameters<typeof runSandboxedCommand>[2]>,
+  policy: SandboxRuntimeConfig['violationPolicy'],
+): Promise<SandboxExecuteResult> {
+  if (!policy) {
+    return runWrappedCommand(sandboxedCommand, command, options)
+  }
+
+  // The policy aborts the command with the violation as the reason; the
+  // caller's signal is forwarded to the same controller
+  const { abortSignal } = options
+  const controller = new AbortController()
+  const forwardAbort = () => controller.abort(abortSignal?.reason)
+  abortSignal?.addEventListener('abort', forwardAbort, { once: true })
+  if (abortSignal?.aborted) {
+    forwardAbort()
+  }
+  const stopPolicy = new AbortController()
+  void enforceViolationPolicy(
+    command,
+    policy,
+    violation =>
+      controller.abort(new SandboxViolationKillError(command, violation)),
+    stopPolicy.signal,
+  )
+
+  try {
+    return await runWrappedCommand(sandboxedCommand, command, {
+      ...options,
+      abortSignal: controller.signal,
+      // Killing takes the command's background processes too
+      processGroup: options.processGroup || policyMayKill(policy),
+    })
+  } finally {
+    stopPolicy.abort()
+    abortSignal?.removeEventListener('abort', forwardAbort)
+  }
+}
+
+/**
+ * Start a command like execute, in its own process group (unless it reads
+ * from the terminal), and return a handle to signal it with everything it
+ * starts. `exited` settles like execute; abortSignal still cancels the
+ * command.
+ */
+function start(
+  command: string,
+  options: SandboxExecuteOptions = {},
+): SandboxExecutionHandle {
+  let child: ChildProcess | undefined
+  let pendingSignal: NodeJS.Signals | undefined
+  // Scopes seen while the command ran, to kill what is left in them later
+  const scopes = new Set<string>()
+  const signal = (signalName: NodeJS.Signals) => {
+    if (!child) {
+      pendingSignal = signalName
+      return
+    }
+    const running = child.exitCode === null && child.signalCode === null
+    if (child.pid !== undefined && running) {
+      getProcessTreeScopes(child.pid).forEach(scope => scopes.add(scope))
+    }
+    if (signalName === 'SIGKILL') {
+      // Also reaches processes that left the process group, e.g. bwrap's
+      for (const scope of scopes) {
+        if (!killCgroup(scope)) {
+          scopes.delete(scope)
+        }
+      }
+    }
+    signalProcessTree(child, signalName)
+  }
+  const execution = executeCommand(
+    command,
+    {
+      ...options,
+      onSpawn: spawned => {
+        child = spawned
+        options.onSpawn?.(spawned)
+        if (pendingSignal) {
+          signal(pendingSignal)
+        }
+      },
+    },
+    true,
+  )
+  return {
+    exited: execution,
+    kill: () => signal('SIGKILL'),
+    signal,
+  }
+}
+
+/**
+ * Run a command like execute, capturing its output instead of inheriting
+ * stdio. Resolves once the command has exited and its output has closed, or
+ * shortly after the exit when background processes keep it open. Rejects
+ * like execute when the command is killed, with the output captured so far
+ * on the error, and with SandboxOutputLimitError when a stream grows past
+ * maxBuffer.
+ */
+async function executeWithOutput(
+  command: string,
+  options: SandboxOutputOptions = {},
+): Promise<SandboxCommandOutput> {
+  const { maxBuffer = DEFAULT_MAX_BUFFER, ...executeOptions } = options
+  const startedAt = Date.now()
+  const output = { stdout: '', stderr: '' }
+  let overflowed = false
+  let outputClosed = async (): Promise<void> => {}
+  const capture = (child: ChildProcess, stream: 'stdout' | 'stderr') => {
+    child[stream]?.setEncoding('utf8')
+    child[stream]?.on('data', (chunk: string) => {
+      if (overflowed) {
+        return
+      }
+      output[stream] += chunk
+      if (output[stream].length > maxBuffer) {
+        overflowed = true
+        output[stream] = output[stream].slice(0, maxBuffer)
+        signalProcessTree(child, 'SIGKILL')
+      }
+    })
+  }
+
+  let result: SandboxExecuteResult
+  try {
+    result = await execute(command, {
+      ...executeOptions,
+      stdio: ['ignore', 'pipe', 'pipe'],
+      onSpawn: child => {
+        capture(child, 'stdout')
+        capture(child, 'stderr')
+        outputClosed = watchOutputClose(child)
+      },
+    })
+  } catch (error) {
+    await outputClosed()
+    if (
+      error instanceof SandboxTimeoutError ||
+      error instanceof SandboxViolationKillError
+    ) {
+      Object.assign(error, output)
+    }
+    throw error
+  }
+  await outputClosed()
+  if (overflowed) {
+    throw Object.assign(new SandboxOutputLimitError(command, maxBuffer), output)
+  }
+  return { ...result, ...output, durationMs: Date.now() - startedAt }
+}
+
+/**
+ * Start a command like execute, streaming the lines of its stdout and stderr
+ * instead of inheriting stdio. Returns right away; `exited` settles like
+ * execute, after both line streams have ended. Unread lines pause the
+ * command's output while it runs; after it exited the rest is buffered, and
+ * pipes held open by background processes are closed after a short wait.
+ */
+function executeStreaming(
+  command: string,
+  options: Omit<SandboxExecuteOptions, 'stdio' | 'onSpawn'> = {},
+): SandboxStreamingExecution {
+  const stdout = new OutputLineStream()
+  const stderr = new OutputLineStream()
+  let outputClosed = async (): Promise<void> => {}
+  const execution = start(command, {
+    ...options,
+    stdio: ['ignore', 'pipe', 'pipe'],
+    onSpawn: child => {
+      if (child.stdout) {
+        stdout.readFrom(child.stdout)
+      }
+      if (child.stderr) {
+        stderr.readFrom(child.stderr)
+      }
+      outputClosed = watchOutputClose(child)
+    },
+  })
+  const run = async (): Promise<SandboxExecuteResult> => {
+    try {
+      const result = await execution.exited
+      // A paused pipe would not close until the lines are read
+      stdout.readToEnd()
+      stderr.readToEnd()
+      await outputClosed()
+      return result
+    } finally {
+      // Also ends them when the command failed to start or was killed
+      stdout.end()
+      stderr.end()
+    }
+  }
+  return { ...execution, stdout, stderr, exited: run() }
+}
+
+/**
+ * Start a persistent session: one sandboxed shell that runs commands one
+ * after another, keeping its working directory, variables and files between
+ * them. The violation policy applies to the shell, so a kill closes the
+ * session. The sandbox is built once, so a run after the temporary rules
+ * changed closes the session instead of running under the old rules.
+ * Execution hooks see the shell, from opening to exit, and each run.
+ */
+async function openSession(
+  options: Pick<SandboxExecuteOptions, 'binShell' | 'customConfig'> &
+    Pick<SandboxSessionOptions, 'maxBuffer'> = {},
+): Promise<SandboxSession> {
+  const { binShell, customConfig, maxBuffer } = options
+  const shell = binShell ?? 'bash'
+  const hooks = [...executionHooks]
+  const afterExecute =
+    hooks.length > 0
+      ? await runBeforeExecuteHooks(hooks, shell, customConfig)
+      : undefined
+
+  let sandboxedCommand: string
+  try {
+    sandboxedCommand = await wrapWithSandbox(shell, binShell, customConfig)
+  } catch (error) {
+    await afterExecute?.({ error })
+    throw error
+  }
+  const env = getCommandEnv(shell, customConfig)
+  const temporaryRuleKeys = activeTemporaryRuleKeys
+  const session = new SandboxSession(
+    (onSpawn, abortSignal) =>
+      runWrappedCommandWithPolicy(
+        sandboxedCommand,
+        shell,
+        {
+          stdio: ['pipe', 'pipe', 'pipe'],
+          onSpawn,
+          abortSignal,
+          // Closing takes the commands' background processes too
+          processGroup: true,
+          env,
+        },
+        customConfig?.violationPolicy ?? config?.violationPolicy,
+      ),
+    {
+      timeoutSeconds: customConfig?.timeoutSeconds ?? config?.timeoutSeconds,
+      maxBuffer,
+      aroundRun: (command, run) =>
+        withExecutionHooks(command, customConfig, run),
+      beforeRun: () => {
+        refreshTemporaryRules()
+        if (activeTemporaryRuleKeys !== temporaryRuleKeys) {
+          throw new Error(
+            'The temporary rules changed since the session was opened; open a new session',
+          )
+        }
+      },
+    },
+  )
+  if (afterExecute) {
+    session.exited.then(
+      result => afterExecute({ result }),
+      error => afterExecute({ error }),
+    )
+  }
+  return session
+}
+
+/**
+ * Get a named sandbox, creating it on first use. It shares the manager's
+ * proxies and violation store, with the overlay on top of the config.
+ * @param overlay - Replaces the sandbox under this name with a new one when
+ *   given; sandboxes returned before keep their overlay
+ */
+function sandbox(
+  name: string,
+  overlay?: Partial<SandboxRuntimeConfig>,
+): NamedSandbox {
+  const existing = namedSandboxes.get(name)
+  if (existing && !overlay) {
+    return existing
+  }
+  const created = new NamedSandbox(name, SandboxManager, overlay)
+  namedSandboxes.set(name, created)
+  return created
+}
+
+/**
+ * Names of the sandboxes created with sandbox()
+ */
+function getSandboxNames(): string[] {
+  return [...namedSandboxes.keys()]
+}
+
 /**
  * Get the current sandbox configuration
  * @returns The current configuration, or undefined if not initialized
@@ -605,160 +1976,240 @@ function getConfig(): SandboxRuntimeConfig | undefined {
  */
 function updateConfig(newConfig: SandboxRuntimeConfig): void {
   // Deep clone the config to avoid mutations
-  config = cloneDeep(newConfig)
+  setConfig(cloneDeep(newConfig))
   logForDebugging('Sandbox configuration updated')
 }
 
-async function reset(): Promise<void> {
-  // Stop log monitor
-  if (logMonitorShutdown) {
-    logMonitorShutdown()
-    logMonitorShutdown = undefined
-  }
-
-  if (managerContext?.linuxBridge) {
-    const {
-      httpSocketPath,
-      socksSocketPath,
-      httpBridgeProcess,
-      socksBridgeProcess,
-    } = managerContext.linuxBridge
-
-    // Create array to wait for process exits
-    const exitPromises: Promise<void>[] = []
-
-    // Kill HTTP bridge and wait for it to exit
-    if (httpBridgeProcess.pid && !httpBridgeProcess.killed) {
-      try {
-        process.kill(httpBridgeProcess.pid, 'SIGTERM')
-        logForDebugging('Sent SIGTERM to HTTP bridge process')
-
-        // Wait for process to exit
-        exitPromises.push(
-          new Promise<void>(resolve => {
-            httpBridgeProcess.once('exit', () => {
-              logForDebugging('HTTP bridge process exited')
-              resolve()
-            })
-            // Timeout after 5 seconds
-            setTimeout(() => {
-              if (!httpBridgeProcess.killed) {
-                logForDebugging('HTTP bridge did not exit, forcing SIGKILL', {
-                  level: 'warn',
-                })
-                try {
-                  if (httpBridgeProcess.pid) {
-                    process.kill(httpBridgeProcess.pid, 'SIGKILL')
-                  }
-                } catch {
-                  // Process may have already exited
+/**
+ * Stop the socat processes of a Linux network bridge and remove its sockets
+ */
+async function stopLinuxBridge(
+  bridge: LinuxNetworkBridgeContext,
+): Promise<void> {
+  const {
+    httpSocketPath,
+    socksSocketPath,
+    httpBridgeProcess,
+    socksBridgeProcess,
+  } = bridge
+
+  // Create array to wait for process exits
+  const exitPromises: Promise<void>[] = []
+
+  // Kill HTTP bridge and wait for it to exit
+  if (httpBridgeProcess.pid && !httpBridgeProcess.killed) {
+    try {
+      process.kill(httpBridgeProcess.pid, 'SIGTERM')
+      logForDebugging('Sent SIGTERM to HTTP bridge process')
+
+      // Wait for process to exit
+      exitPromises.push(
+        new Promise<void>(resolve => {
+          httpBridgeProcess.once('exit', () => {
+            logForDebugging('HTTP bridge process exited')
+            resolve()
+          })
+          // Timeout after 5 seconds
+          setTimeout(() => {
+            if (!httpBridgeProcess.killed) {
+              logForDebugging('HTTP bridge did not exit, forcing SIGKILL', {
+                level: 'warn',
+              })
+              try {
+                if (httpBridgeProcess.pid) {
+                  process.kill(httpBridgeProcess.pid, 'SIGKILL')
                 }
+              } catch {
+                // Process may have already exited
               }
-              resolve()
-            }, 5000)
-          }),
-        )
-      } catch (err) {
-        if ((err as NodeJS.ErrnoException).code !== 'ESRCH') {
-          logForDebugging(`Error killing HTTP bridge: ${err}`, {
-            level: 'error',
-          })
-        }
+            }
+            resolve()
+          }, 5000)
+        }),
+      )
+    } catch (err) {
+      if ((err as NodeJS.ErrnoException).code !== 'ESRCH') {
+        logForDebugging(`Error killing HTTP bridge: ${err}`, {
+          level: 'error',
+        })
       }
     }
+  }
 
-    // Kill SOCKS bridge and wait for it to exit
-    if (socksBridgeProcess.pid && !socksBridgeProcess.killed) {
-      try {
-        process.kill(socksBridgeProcess.pid, 'SIGTERM')
-        logForDebugging('Sent SIGTERM to SOCKS bridge process')
-
-        // Wait for process to exit
-        exitPromises.push(
-          new Promise<void>(resolve => {
-            socksBridgeProcess.once('exit', () => {
-              logForDebugging('SOCKS bridge process exited')
-              resolve()
-            })
-            // Timeout after 5 seconds
-            setTimeout(() => {
-              if (!socksBridgeProcess.killed) {
-                logForDebugging('SOCKS bridge did not exit, forcing SIGKILL', {
-                  level: 'warn',
-                })
-                try {
-                  if (socksBridgeProcess.pid) {
-                    process.kill(socksBridgeProcess.pid, 'SIGKILL')
-                  }
-                } catch {
-                  // Process may have already exited
+  // Kill SOCKS bridge and wait for it to exit
+  if (socksBridgeProcess.pid && !socksBridgeProcess.killed) {
+    try {
+      process.kill(socksBridgeProcess.pid, 'SIGTERM')
+      logForDebugging('Sent SIGTERM to SOCKS bridge process')
+
+      // Wait for process to exit
+      exitPromises.push(
+        new Promise<void>(resolve => {
+          socksBridgeProcess.once('exit', () => {
+            logForDebugging('SOCKS bridge process exited')
+            resolve()
+          })
+          // Timeout after 5 seconds
+          setTimeout(() => {
+            if (!socksBridgeProcess.killed) {
+              logForDebugging('SOCKS bridge did not exit, forcing SIGKILL', {
+                level: 'warn',
+              })
+              try {
+                if (socksBridgeProcess.pid) {
+                  process.kill(socksBridgeProcess.pid, 'SIGKILL')
                 }
+              } catch {
+                // Process may have already exited
               }
-              resolve()
-            }, 5000)
-          }),
-        )
-      } catch (err) {
-        if ((err as NodeJS.ErrnoException).code !== 'ESRCH') {
-          logForDebugging(`Error killing SOCKS bridge: ${err}`, {
-            level: 'error',
-          })
-        }
+            }
+            resolve()
+          }, 5000)
+        }),
+      )
+    } catch (err) {
+      if ((err as NodeJS.ErrnoException).code !== 'ESRCH') {
+        logForDebugging(`Error killing SOCKS bridge: ${err}`, {
+          level: 'error',
+        })
       }
     }
+  }
 
-    // Wait for both processes to exit
-    await Promise.all(exitPromises)
+  // Wait for both processes to exit
+  await Promise.all(exitPromises)
 
-    // Clean up sockets
-    if (httpSocketPath) {
-      try {
-        fs.rmSync(httpSocketPath, { force: true })
-        logForDebugging('Cleaned up HTTP socket')
-      } catch (err) {
-        logForDebugging(`HTTP socket cleanup error: ${err}`, {
-          level: 'error',
-        })
-      }
+  // Clean up sockets
+  if (httpSocketPath) {
+    try {
+      fs.rmSync(httpSocketPath, { force: true })
+      logForDebugging('Cleaned up HTTP socket')
+    } catch (err) {
+      logForDebugging(`HTTP socket cleanup error: ${err}`, {
+        level: 'error',
+      })
     }
+  }
 
-    if (socksSocketPath) {
-      try {
-        fs.rmSync(socksSocketPath, { force: true })
-        logForDebugging('Cleaned up SOCKS socket')
-      } catch (err) {
-        logForDebugging(`SOCKS socket cleanup error: ${err}`, {
+  if (socksSocketPath) {
+    try {
+      fs.rmSync(socksSocketPath, { force: true })
+      logForDebugging('Cleaned up SOCKS socket')
+    } catch (err) {
+      logForDebugging(`SOCKS socket cleanup error: ${err}`, {
+        level: 'error',
+      })
+    }
+  }
+}
+
+function closeHttpProxyServer(
+  server: ReturnType<typeof createHttpProxyServer>,
+): Promise<void> {
+  return new Promise<void>(resolve => {
+    server.close(error => {
+      if (error && error.message !== 'Server is not running.') {
+        logForDebugging(`Error closing HTTP proxy server: ${error.message}`, {
           level: 'error',
         })
       }
-    }
+      resolve()
+    })
+  })
+}
+
+function closeSocksProxyServer(server: SocksProxyWrapper): Promise<void> {
+  return server.close().catch((error: Error) => {
+    logForDebugging(`Error closing SOCKS proxy server: ${error.message}`, {
+      level: 'error',
+    })
+  })
+}
+
+async function reset(): Promise<void> {
+  namedSandboxes.clear()
+
+  // Stop log monitor
+  if (logMonitorShutdown) {
+    logMonitorShutdown()
+    logMonitorShutdown = undefined
+  }
+  if (esMonitorShutdown) {
+    esMonitorShutdown()
+    esMonitorShutdown = undefined
+  }
+  if (notifierShutdown) {
+    notifierShutdown()
+    notifierShutdown = undefined
+  }
+  if (violationFeed) {
+    await violationFeed.close()
+    violationFeed = undefined
+  }
+  if (linuxViolationMonitor) {
+    linuxViolationMonitor.stop()
+    linuxViolationMonitor = undefined
+  }
+
+  sandboxViolationStore.detachJournal()
+  sandboxViolationStore.setIgnoreViolations(undefined)
+  sandboxViolationStore.setLimits()
+  sandboxViolationStore.setRedaction()
+  sandboxFileAccessStore.setLimits()
+  sandboxFileAccessStore.setRedaction()
+  setDebugRedactor(undefined)
+  degradedSandbox = false
+  // The next session uses another run of each temporary rule
+  temporaryRuleTracker = new TemporaryRuleTracker()
+
+  if (dbusProxy) {
+    stopDbusProxy(dbusProxy)
+    dbusProxy = undefined
   }
 
+  await registeredBackend?.cleanup?.().catch(error => {
+    logForDebugging(`Sandbox backend cleanup failed: ${error}`, {
+      level: 'error',
+    })
+  })
+
+  if (scratchDir) {
+    scratchDir.remove()
+    scratchDir = undefined
+  }
+
+  cleanupMacOSProfiles()
+
+  // Proxies started for `commands` overrides, including ones still starting
+  const scopes = (
+    await Promise.allSettled(commandNetworkScopes.values())
+  ).flatMap(result => (result.status === 'fulfilled' ? [result.value] : []))
+  commandNetworkScopes.clear()
+
+  const bridges = [
+    managerContext?.linuxBridge,
+    ...scopes.map(scope => scope.context.linuxBridge),
+  ].filter(
+    (bridge): bridge is LinuxNetworkBridgeContext => bridge !== undefined,
+  )
+  await Promise.all(bridges.map(stopLinuxBridge))
+
   // Close servers in parallel (only if they exist, i.e., were started by us)
   const closePromises: Promise<void>[] = []
 
   if (httpProxyServer) {
-    const server = httpProxyServer // Capture reference to avoid TypeScript error
-    const httpClose = new Promise<void>(resolve => {
-      server.close(error => {
-        if (error && error.message !== 'Server is not running.') {
-          logForDebugging(`Error closing HTTP proxy server: ${error.message}`, {
-            level: 'error',
-          })
-        }
-        resolve()
-      })
-    })
-    closePromises.push(httpClose)
+    closePromises.push(closeHttpProxyServer(httpProxyServer))
   }
 
   if (socksProxyServer) {
-    const socksClose = socksProxyServer.close().catch((error: Error) => {
-      logForDebugging(`Error closing SOCKS proxy server: ${error.message}`, {
-        level: 'error',
-      })
-    })
-    closePromises.push(socksClose)
+    closePromises.push(closeSocksProxyServer(socksProxyServer))
+  }
+
+  for (const scope of scopes) {
+    closePromises.push(
+      closeHttpProxyServer(scope.httpProxyServer),
+      closeSocksProxyServer(scope.socksProxyServer),
+    )
   }
 
   // Wait for all servers to close
@@ -767,14 +2218,68 @@ async function reset(): Promise<void> {
   // Clear references
   httpProxyServer = undefined
   socksProxyServer = undefined
+  hostResolver.clear()
   managerContext = undefined
   initializationPromise = undefined
+  networkAskCallback = undefined
+}
+
+const MAX_PROCESSES_UNENFORCED_MESSAGE =
+  'limits.maxProcesses is not enforced: it needs the Linux sandbox with cgroup v2 and systemd-run'
+
+/** Whether the sandbox runs in a cgroup scope that can cap its processes */
+function canLimitProcessCount(): boolean {
+  return getActiveBackend() === linuxBackend && hasCgroupResourceControlSync()
+}
+
+/**
+ * Warnings about configured limits this host cannot enforce
+ */
+function getLimitWarnings(): string[] {
+  const warnings: string[] = []
+  if (config?.limits?.maxProcesses !== undefined && !canLimitProcessCount()) {
+    warnings.push(MAX_PROCESSES_UNENFORCED_MESSAGE)
+  }
+  // Without a cgroup scope the degraded sandbox falls back to rlimits
+  const resources = config?.resources
+  if (degradedSandbox && resources && !hasCgroupResourceControlSync()) {
+    for (const limit of ['cpus', 'pids'] as const) {
+      if (resources[limit] !== undefined) {
+        warnings.push(
+          `resources.${limit} is not enforced in the degraded sandbox without cgroup v2 and systemd-run`,
+        )
+      }
+    }
+    if (resources.memoryMb !== undefined) {
+      warnings.push(
+        'resources.memoryMb limits the virtual address space in the degraded sandbox, ' +
+          'which can stop Node, the JVM and other runtimes well below the limit',
+      )
+    }
+  }
+  return warnings
+}
+
+/**
+ * Warnings about temporary rules ignored because they expired or used up
+ * their runs
+ */
+function getTemporaryRuleWarnings(): string[] {
+  return temporaryRuleTracker.getWarnings()
 }
 
 function getSandboxViolationStore() {
   return sandboxViolationStore
 }
 
+/**
+ * The allowed file accesses recorded with auditFileAccess. They are kept
+ * apart from the violation store, so they never count as violations.
+ */
+function getFileAccessStore() {
+  return sandboxFileAccessStore
+}
+
 function annotateStderrWithSandboxFailures(
   command: string,
   stderr: string,
@@ -800,7 +2305,8 @@ function annotateStderrWithSandboxFailures(
 
 /**
  * Returns glob patterns from Edit/Read permission rules that are not
- * fully supported on Linux. Returns empty array on macOS or when
+ * fully supported on Linux, where they only cover the paths that exist
+ * when a command is wrapped. Returns empty array on macOS or when
  * sandboxing is disabled.
  *
  * Patterns ending with /** are excluded since they work as subpaths.
@@ -864,6 +2370,11 @@ export interface ISandboxManager {
   getSocksProxyPort(): number | undefined
   getLinuxHttpSocketPath(): string | undefined
   getLinuxSocksSocketPath(): string | undefined
+  getScratchDirPath(): string | undefined
+  isDegradedSandbox(): boolean
+  getSandboxBackend(): SandboxBackendName
+  registerBackend(backend: SandboxBackend | undefined): void
+  addHooks(hooks: SandboxExecutionHooks): () => void
   waitForNetworkInitialization(): Promise<boolean>
   wrapWithSandbox(
     command: string,
@@ -871,12 +2382,47 @@ export interface ISandboxManager {
     customConfig?: Partial<SandboxRuntimeConfig>,
     abortSignal?: AbortSignal,
   ): Promise<string>
+  releaseWrappedCommand(sandboxedCommand: string): void
+  getCommandEnv(
+    command: string,
+    customConfig?: Partial<SandboxRuntimeConfig>,
+  ): NodeJS.ProcessEnv
+  execute(
+    command: string,
+    options?: SandboxExecuteOptions,
+  ): Promise<SandboxExecuteResult>
+  start(
+    command: string,
+    options?: SandboxExecuteOptions,
+  ): SandboxExecutionHandle
+  executeWithOutput(
+    command: string,
+    options?: SandboxOutputOptions,
+  ): Promise<SandboxCommandOutput>
+  executeStreaming(
+    command: string,
+    options?: Omit<SandboxExecuteOptions, 'stdio' | 'onSpawn'>,
+  ): SandboxStreamingExecution
+  openSession(
+    options?: Pick<SandboxExecuteOptions, 'binShell' | 'customConfig'> &
+      Pick<SandboxSessionOptions, 'maxBuffer'>,
+  ): Promise<SandboxSession>
+  sandbox(
+    name: string,
+    overlay?: Partial<SandboxRuntimeConfig>,
+  ): NamedSandbox
+  getSandboxNames(): string[]
   getSandboxViolationStore(): SandboxViolationStore
+  getFileAccessStore(): SandboxViolationStore
   annotateStderrWithSandboxFailures(command: string, stderr: string): string
   getLinuxGlobPatternWarnings(): string[]
+  getTemporaryRuleWarnings(): string[]
+  getLimitWarnings(): string[]
   getConfig(): SandboxRuntimeConfig | undefined
   updateConfig(newConfig: SandboxRuntimeConfig): void
   reset(): Promise<void>
+  /** Set up and initialize the manager with fluent calls */
+  builder(): SandboxManagerBuilder
 }
 
 // ============================================================================
@@ -903,12 +2449,30 @@ export const SandboxManager: ISandboxManager = {
   getSocksProxyPort,
   getLinuxHttpSocketPath,
   getLinuxSocksSocketPath,
+  getScratchDirPath,
+  isDegradedSandbox,
+  getSandboxBackend,
+  registerBackend,
+  addHooks,
   waitForNetworkInitialization,
   wrapWithSandbox,
+  releaseWrappedCommand,
+  getCommandEnv,
+  execute,
+  start,
+  executeWithOutput,
+  executeStreaming,
+  openSession,
+  sandbox,
+  getSandboxNames,
   reset,
   getSandboxViolationStore,
+  getFileAccessStore,
   annotateStderrWithSandboxFailures,
   getLinuxGlobPatternWarnings,
+  getTemporaryRuleWarnings,
+  getLimitWarnings,
   getConfig,
   updateConfig,
+  builder: () => new SandboxManagerBuilder(SandboxManager),
 } as const
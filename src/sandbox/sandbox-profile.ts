@@ -0,0 +1,93 @@
+import * as fs from 'node:fs'
+import shellquote from 'shell-quote'
+import type { SandboxRuntimeConfig } from './sandbox-config.js'
+import { SandboxManager } from './sandbox-manager.js'
+
+/**
+ * Dry runs of the sandbox wrapper (`srt profile`)
+ *
+ * Wraps a command exactly as `srt` would and describes the result instead of
+ * running it: the full argument list (bwrap, nsjail, systemd-run, env...) and,
+ * on macOS, the seatbelt profile handed to sandbox-exec. The proxy ports in
+ * the output are those of the current session.
+ */
+
+export interface SandboxWrapperDescription {
+  command: string
+  wrappedCommand: string
+  /** The wrapped command split into arguments */
+  argv: string[]
+  /** Path and contents of the seatbelt profile, on macOS */
+  seatbeltProfile?: { path: string; content: string }
+}
+
+/**
+ * Split a wrapped command into its arguments
+ * Wrappers quote every argument, so nothing is expanded or left as an
+ * operator; variables are kept as written.
+ */
+export function splitWrappedCommand(wrappedCommand: string): string[] {
+  return shellquote
+    .parse(wrappedCommand, name => `$${name}`)
+    .filter((entry): entry is string => typeof entry === 'string')
+}
+
+function findSeatbeltProfilePath(argv: string[]): string | undefined {
+  const index = argv.indexOf('sandbox-exec')
+  return index !== -1 && argv[index + 1] === '-f' ? argv[index + 2] : undefined
+}
+
+/**
+ * Wrap a command with the initialized sandbox and describe the wrapper
+ * without running it
+ */
+export async function describeSandboxWrapper(
+  command: string,
+  customConfig?: Partial<SandboxRuntimeConfig>,
+): Promise<SandboxWrapperDescription> {
+  const wrappedCommand = await SandboxManager.wrapWithSandbox(
+    command,
+    undefined,
+    customConfig,
+  )
+  const argv = splitWrappedCommand(wrappedCommand)
+  const profilePath = findSeatbeltProfilePath(argv)
+  return {
+    command,
+    wrappedCommand,
+    argv,
+    // Profiles are removed with the session, so read it while it exists
+    seatbeltProfile: profilePath
+      ? { path: profilePath, content: fs.readFileSync(profilePath, 'utf8') }
+      : undefined,
+  }
+}
+
+/**
+ * Format a wrapper description with one option (and its values) per line
+ */
+export function formatSandboxWrapper(
+  description: SandboxWrapperDescription,
+): string {
+  if (description.wrappedCommand === description.command) {
+    return `No restrictions apply: ${description.command} would run unsandboxed`
+  }
+  const lines: string[][] = []
+  for (const arg of description.argv) {
+    const last = lines[lines.length - 1]
+    if (!last || arg.startsWith('-')) {
+      lines.push([arg])
+    } else {
+      last.push(arg)
+    }
+  }
+  const output = [lines.map(words => shellquote.quote(words)).join(' \\\n  ')]
+  if (description.seatbeltProfile) {
+    output.push(
+      '',
+      `; Seatbelt profile ${description.seatbeltProfile.path}`,
+      description.seatbeltProfile.content,
+    )
+  }
+  return output.join('\n')
+}
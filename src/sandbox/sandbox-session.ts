@@ -0,0 +1,276 @@
+import type { ChildProcess } from 'node:child_process'
+import { randomBytes } from 'node:crypto'
+import shellquote from 'shell-quote'
+import {
+  SandboxOutputLimitError,
+  SandboxTimeoutError,
+  type SandboxCommandOutput,
+  type SandboxExecuteResult,
+} from './sandbox-execute.js'
+
+/**
+ * Persistent sandbox sessions (`SandboxManager.openSession`)
+ *
+ * A session starts one sandboxed shell and runs commands in it one after
+ * another, so the working directory, variables and files a command leaves
+ * behind are there for the next one, and the sandbox is set up once. Each
+ * command is passed to `eval` with stdin from /dev/null, followed by a
+ * marker line on stdout and stderr carrying its exit status; the output
+ * before the markers is the command's. A command that exits the shell, a
+ * timeout or abort, or output past maxBuffer ends the session.
+ */
+
+/** Default cap on the output a run may buffer, per stream */
+export const DEFAULT_SESSION_MAX_BUFFER = 64 * 1024 * 1024
+
+export interface SandboxSessionOptions {
+  /** Timeout of runs that set none */
+  timeoutSeconds?: number
+  /**
+   * Characters of stdout or stderr a run may buffer before the session is
+   * closed (default: DEFAULT_SESSION_MAX_BUFFER)
+   */
+  maxBuffer?: number
+  /** Called before each run; throwing closes the session and fails the run */
+  beforeRun?: (command: string) => void | Promise<void>
+  /** Wraps each run, from before beforeRun until its output is collected */
+  aroundRun?: (
+    command: string,
+    run: () => Promise<SandboxCommandOutput>,
+  ) => Promise<SandboxCommandOutput>
+}
+
+export interface SandboxSessionRunOptions {
+  /** Close the session if the command runs longer (default: config timeoutSeconds) */
+  timeoutSeconds?: number
+  /** Aborting closes the session */
+  abortSignal?: AbortSignal
+}
+
+/**
+ * Raised by run() when the session has closed, or closes before the command
+ * finishes (e.g. the command ran `exit`)
+ */
+export class SandboxSessionClosedError extends Error {
+  constructor(readonly result?: SandboxExecuteResult) {
+    super(
+      result
+        ? `Sandbox session closed (exit code ${result.code}, signal ${result.signal})`
+        : 'Sandbox session closed',
+    )
+    this.name = 'SandboxSessionClosedError'
+  }
+}
+
+/** Starts the session's shell with piped stdio in its own process group */
+export type SandboxSessionLauncher = (
+  onSpawn: (child: ChildProcess) => void,
+  abortSignal: AbortSignal,
+) => Promise<SandboxExecuteResult>
+
+interface RunOutput {
+  code: number
+  stdout: string
+  stderr: string
+}
+
+interface PendingRun {
+  command: string
+  marker: string
+  resolve: (output: RunOutput) => void
+  reject: (error: unknown) => void
+}
+
+export class SandboxSession {
+  /** Settles when the shell exits */
+  readonly exited: Promise<SandboxExecuteResult>
+  private readonly controller = new AbortController()
+  private readonly markerPrefix =
+    `__srt_session_${randomBytes(8).toString('hex')}`
+  private child: ChildProcess | undefined
+  private stdout = ''
+  private stderr = ''
+  private current: PendingRun | undefined
+  private queue: Promise<unknown> = Promise.resolve()
+  private runs = 0
+  private closeError: SandboxSessionClosedError | undefined
+
+  /**
+   * @param launch Starts the sandboxed shell
+   */
+  constructor(
+    launch: SandboxSessionLauncher,
+    private readonly options: SandboxSessionOptions = {},
+  ) {
+    this.exited = launch(child => this.attach(child), this.controller.signal)
+    this.exited.then(
+      result => this.onClosed(new SandboxSessionClosedError(result)),
+      error => {
+        // The shell failed to start, or the violation policy killed it
+        this.current?.reject(error)
+        this.onClosed(new SandboxSessionClosedError())
+      },
+    )
+  }
+
+  /** Whether the shell has exited */
+  get closed(): boolean {
+    return this.closeError !== undefined
+  }
+
+  /**
+   * Run a command in the session's shell, after the commands run before it
+   * @throws SandboxSessionClosedError, SandboxTimeoutError
+   */
+  run(
+    command: string,
+    options: SandboxSessionRunOptions = {},
+  ): Promise<SandboxCommandOutput> {
+    const { aroundRun } = this.options
+    const result = this.queue.then(() =>
+      aroundRun
+        ? aroundRun(command, () => this.runNow(command, options))
+        : this.runNow(command, options),
+    )
+    this.queue = result.catch(() => undefined)
+    return result
+  }
+
+  /**
+   * Kill the session's shell with everything it started
+   */
+  async close(): Promise<void> {
+    this.child?.stdin?.end()
+    this.controller.abort()
+    await this.exited.catch(() => undefined)
+  }
+
+  private attach(child: ChildProcess): void {
+    this.child = child
+    // Writes after the shell exited fail with EPIPE; the exit ends the run
+    child.stdin?.on('error', () => undefined)
+    child.stdout?.setEncoding('utf8')
+    child.stderr?.setEncoding('utf8')
+    child.stdout?.on('data', (chunk: string) => {
+      this.stdout += chunk
+      this.settle()
+    })
+    child.stderr?.on('data', (chunk: string) => {
+      this.stderr += chunk
+      this.settle()
+    })
+  }
+
+  /** Close the session once a stream has buffered more than maxBuffer */
+  private checkBuffers(): void {
+    const maxBuffer = this.options.maxBuffer ?? DEFAULT_SESSION_MAX_BUFFER
+    if (this.stdout.length <= maxBuffer && this.stderr.length <= maxBuffer) {
+      return
+    }
+    this.stdout = ''
+    this.stderr = ''
+    this.current?.reject(
+      new SandboxOutputLimitError(this.current.command, maxBuffer),
+    )
+    this.current = undefined
+    void this.close()
+  }
+
+  private async runNow(
+    command: string,
+    options: SandboxSessionRunOptions,
+  ): Promise<SandboxCommandOutput> {
+    const { abortSignal } = options
+    const timeoutSeconds =
+      options.timeoutSeconds ?? this.options.timeoutSeconds
+    if (this.closeError) {
+      throw this.closeError
+    }
+    try {
+      await this.options.beforeRun?.(command)
+    } catch (error) {
+      await this.close()
+      throw error
+    }
+    const startedAt = Date.now()
+    const marker = `${this.markerPrefix}_${++this.runs}__`
+    const output = new Promise<RunOutput>((resolve, reject) => {
+      this.current = { command, marker, resolve, reject }
+    })
+
+    let timedOut = false
+    const timer =
+      timeoutSeconds !== undefined
+        ? setTimeout(() => {
+            timedOut = true
+            this.controller.abort()
+          }, timeoutSeconds * 1000)
+        : undefined
+    const onAbort = () => this.controller.abort()
+    abortSignal?.addEventListener('abort', onAbort, { once: true })
+    if (abortSignal?.aborted) {
+      onAbort()
+    }
+
+    this.child?.stdin?.write(
+      `eval ${shellquote.quote([command])} </dev/null; ` +
+        `printf '%s %d\\n' ${marker} $?; printf '%s\\n' ${marker} >&2\n`,
+    )
+    try {
+      const { code, stdout, stderr } = await output
+      return {
+        code,
+        signal: null,
+        stdout,
+        stderr,
+        durationMs: Date.now() - startedAt,
+      }
+    } catch (error) {
+      if (timedOut && timeoutSeconds !== undefined) {
+        throw new SandboxTimeoutError(command, timeoutSeconds)
+      }
+      throw error
+    } finally {
+      this.current = undefined
+      clearTimeout(timer)
+      abortSignal?.removeEventListener('abort', onAbort)
+    }
+  }
+
+  /** Finish the current run once both of its markers have arrived */
+  private settle(): void {
+    const run = this.current
+    if (!run) {
+      this.checkBuffers()
+      return
+    }
+    const stdoutMarker = this.stdout.indexOf(`${run.marker} `)
+    const stderrMarker = this.stderr.indexOf(`${run.marker}\n`)
+    if (stdoutMarker === -1 || stderrMarker === -1) {
+      this.checkBuffers()
+      return
+    }
+    const statusEnd = this.stdout.indexOf('\n', stdoutMarker)
+    if (statusEnd === -1) {
+      this.checkBuffers()
+      return
+    }
+    const status = this.stdout.slice(
+      stdoutMarker + run.marker.length + 1,
+      statusEnd,
+    )
+    run.resolve({
+      code: Number(status),
+      stdout: this.stdout.slice(0, stdoutMarker),
+      stderr: this.stderr.slice(0, stderrMarker),
+    })
+    this.stdout = this.stdout.slice(statusEnd + 1)
+    this.stderr = this.stderr.slice(stderrMarker + run.marker.length + 1)
+    this.current = undefined
+  }
+
+  private onClosed(error: SandboxSessionClosedError): void {
+    this.closeError = error
+    this.current?.reject(error)
+  }
+}
@@ -1,7 +1,10 @@
 import { homedir } from 'os'
 import * as path from 'path'
 import * as fs from 'fs'
+import shellquote from 'shell-quote'
 import { getPlatform } from '../utils/platform.js'
+import type { RlimitsConfig } from './sandbox-config.js'
+import { findProjectConfigPath } from './config-layers.js'
 
 /**
  * Dangerous files that should be protected from writes.
@@ -17,8 +20,25 @@ export const DANGEROUS_FILES = [
   '.profile',
   '.ripgreprc',
   '.mcp.json',
+  '.srt.json',
 ] as const
 
+/**
+ * srt's own settings and state outside the working directory, protected from
+ * writes so a sandboxed command can't change the policy of later runs
+ */
+export function getSrtSettingsPaths(): string[] {
+  const home = homedir()
+  const projectConfigPath = findProjectConfigPath(process.cwd())
+  return [
+    path.join(home, '.srt-settings.json'),
+    path.join(home, '.srt', 'policies'),
+    path.join(home, '.srt', 'temporary-rules.json'),
+    // The project file may be in a parent of the working directory
+    ...(projectConfigPath ? [projectConfigPath] : []),
+  ]
+}
+
 /**
  * Dangerous directories that should be protected from writes.
  * These directories contain sensitive configuration or executable files.
@@ -63,6 +83,42 @@ export function containsGlobChars(pathPattern: string): boolean {
   )
 }
 
+/**
+ * Convert a glob pattern to a regular expression (macOS sandbox profiles and
+ * ignoreViolations targets)
+ *
+ * This implements gitignore-style pattern matching to match the behavior of the
+ * `ignore` library used by the permission system/
+ *
+ * Supported patterns:
+ * - * matches any characters except / (e.g., *.ts matches foo.ts but not foo/bar.ts)
+ * - ** matches any characters including / (e.g., src/** /*.ts matches all .ts files in src/)
+ * - ? matches any single character except / (e.g., file?.txt matches file1.txt)
+ * - [abc] matches any character in the set (e.g., file[0-9].txt matches file3.txt)
+ *
+ * Note: This is designed for macOS sandbox (regex ...) syntax. The resulting regex
+ * will be used in sandbox profiles like: (deny file-write* (regex "pattern"))
+ */
+export function globToRegex(globPattern: string): string {
+  return (
+    '^' +
+    globPattern
+      // Escape regex special characters (except glob chars * ? [ ])
+      .replace(/[.^$+{}()|\\]/g, '\\$&')
+      // Escape unclosed brackets (no matching ])
+      .replace(/\[([^\]]*?)$/g, '\\[$1')
+      // Convert glob patterns to regex (order matters - ** before *)
+      .replace(/\*\*\//g, '__GLOBSTAR_SLASH__') // Placeholder for **/
+      .replace(/\*\*/g, '__GLOBSTAR__') // Placeholder for **
+      .replace(/\*/g, '[^/]*') // * matches anything except /
+      .replace(/\?/g, '[^/]') // ? matches single character except /
+      // Restore placeholders
+      .replace(/__GLOBSTAR_SLASH__/g, '(.*/)?') // **/ matches zero or more dirs
+      .replace(/__GLOBSTAR__/g, '.*') + // ** matches anything including /
+    '$'
+  )
+}
+
 /**
  * Remove trailing /** glob suffix from a path pattern
  * Used to normalize path patterns since /** just means "directory and everything under it"
@@ -291,14 +347,43 @@ export function getDefaultWritePaths(): string[] {
   return recommendedPaths
 }
 
+/**
+ * Expand path patterns to every Unicode normalization form they can appear in
+ *
+ * APFS and HFS+ resolve a path regardless of normalization, but sandbox profile
+ * rules match the name as stored on disk. "café" typed in NFC would not match a
+ * directory created with an NFD name, so rules are emitted for both forms.
+ */
+export function expandUnicodeNormalizations(
+  pathPatterns: string[],
+): string[] {
+  const expanded: string[] = []
+  for (const pathPattern of pathPatterns) {
+    expanded.push(
+      pathPattern,
+      pathPattern.normalize('NFC'),
+      pathPattern.normalize('NFD'),
+    )
+  }
+  return [...new Set(expanded)]
+}
+
 /**
  * Generate proxy environment variables for sandboxed processes
+ * When a session scratch directory is given, it also becomes TMPDIR.
  */
 export function generateProxyEnvVars(
   httpProxyPort?: number,
   socksProxyPort?: number,
+  scratchDir?: string,
 ): string[] {
-  const envVars: string[] = [`SANDBOX_RUNTIME=1`, `TMPDIR=/tmp/claude`]
+  const envVars: string[] = [
+    `SANDBOX_RUNTIME=1`,
+    `TMPDIR=${scratchDir ?? '/tmp/claude'}`,
+  ]
+  if (scratchDir) {
+    envVars.push(`SRT_SCRATCH_DIR=${scratchDir}`)
+  }
 
   // If no proxy ports provided, return minimal env vars
   if (!httpProxyPort && !socksProxyPort) {
@@ -408,3 +493,48 @@ export function encodeSandboxedCommand(command: string): string {
 export function decodeSandboxedCommand(encodedCommand: string): string {
   return Buffer.from(encodedCommand, 'base64').toString('utf8')
 }
+
+/**
+ * Build the ulimit commands for the configured rlimits, for /bin/sh
+ * File and core sizes are in the 512-byte blocks of POSIX sh (dash, and bash
+ * in POSIX mode as macOS's /bin/sh runs it). maxProcesses is
+ * left out: RLIMIT_NPROC counts every process of the user, so it can neither
+ * bound the sandbox nor be set without breaking the user's other processes.
+ */
+export function buildUlimitCommands(
+  limits: RlimitsConfig | undefined,
+): string[] {
+  const commands: string[] = []
+  if (limits?.nofile !== undefined) {
+    commands.push(`ulimit -n ${limits.nofile}`)
+  }
+  if (limits?.fsizeMb !== undefined) {
+    commands.push(`ulimit -f ${limits.fsizeMb * 2048}`)
+  }
+  if (limits?.cpuSeconds !== undefined) {
+    commands.push(`ulimit -t ${limits.cpuSeconds}`)
+  }
+  if (limits?.coreMb !== undefined) {
+    commands.push(`ulimit -c ${limits.coreMb * 2048}`)
+  }
+  return commands
+}
+
+/**
+ * Run a wrapped sandbox command under the configured rlimits
+ * ulimit without -S or -H sets both the soft and the hard limit, so the
+ * sandboxed command cannot raise them again. The sandbox itself and every
+ * process it starts inherit the limits. /bin/sh is used because bash is not
+ * installed everywhere srt runs.
+ */
+export function applyRlimits(
+  wrappedCommand: string,
+  limits: RlimitsConfig | undefined,
+): string {
+  const ulimitCommands = buildUlimitCommands(limits)
+  if (ulimitCommands.length === 0) {
+    return wrappedCommand
+  }
+  const script = [...ulimitCommands, `exec ${wrappedCommand}`].join(' && ')
+  return `/bin/sh -c ${shellquote.quote([script])}`
+}
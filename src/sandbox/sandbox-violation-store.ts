@@ -1,22 +1,305 @@
 import { type SandboxViolationEvent } from './macos-sandbox-utils.js'
-import { encodeSandboxedCommand } from './sandbox-utils.js'
+import { logForDebugging } from '../utils/debug.js'
+import type {
+  IgnoreViolationsConfig,
+  RedactionConfig,
+  ViolationStoreConfig,
+} from './sandbox-config.js'
+import type { ViolationJournal } from './violation-journal.js'
+import {
+  exportViolations,
+  type ViolationExportFormat,
+} from './violation-export.js'
+import {
+  isViolationIgnored,
+  matchesCommand,
+  matchesTarget,
+} from './violation-filter.js'
+import {
+  classifyViolation,
+  type ViolationCategory,
+} from './violation-policy.js'
+import {
+  createRedactor,
+  encodeRedactedCommand,
+  redactViolation,
+  type Redactor,
+} from './redaction.js'
+
+export interface ViolationStreamOptions {
+  /** Ends the stream when aborted */
+  signal?: AbortSignal
+  /**
+   * Violations buffered for a consumer that falls behind (default: 1000).
+   * The oldest are dropped beyond this, like a lagging broadcast receiver.
+   */
+  maxBuffered?: number
+}
+
+/**
+ * Filters of SandboxViolationStore.query; every filter given must match
+ */
+export interface ViolationQuery {
+  /** Violation kinds ('network', 'file-read', 'file-write', 'other') */
+  category?: ViolationCategory | ViolationCategory[]
+  /** Only violations at or after this time */
+  since?: Date
+  /** Only violations before this time */
+  until?: Date
+  /** Name of the process that made the access (macOS only) */
+  processName?: string
+  /** Command pattern, matched like an ignoreViolations key */
+  command?: string
+  /** Path, host or host:port pattern, matched like an ignoreViolations path */
+  target?: string
+  /** Matches to skip, oldest first */
+  offset?: number
+  /** Maximum number of matches returned */
+  limit?: number
+}
+
+export interface ViolationQueryResult {
+  violations: SandboxViolationEvent[]
+  /** Number of matches before offset and limit were applied */
+  total: number
+}
+
+/**
+ * Whether a violation matches the filters of a query (offset and limit
+ * aside)
+ */
+export function matchesViolationQuery(
+  violation: SandboxViolationEvent,
+  query: ViolationQuery,
+): boolean {
+  const { category, since, until, processName, command, target } = query
+  if (category !== undefined) {
+    const categories = Array.isArray(category) ? category : [category]
+    const violationCategory = classifyViolation(violation)
+    if (!violationCategory || !categories.includes(violationCategory)) {
+      return false
+    }
+  }
+  return (
+    (since === undefined || violation.timestamp >= since) &&
+    (until === undefined || violation.timestamp < until) &&
+    (processName === undefined || violation.processName === processName) &&
+    (command === undefined || matchesCommand(command, violation.command)) &&
+    (target === undefined || matchesTarget(target, violation))
+  )
+}
+
+/**
+ * Find violations by kind, time, process and target, a page at a time
+ * Violations are returned in the order given.
+ */
+export function queryViolations(
+  violations: SandboxViolationEvent[],
+  query: ViolationQuery = {},
+): ViolationQueryResult {
+  const { offset = 0, limit = Infinity } = query
+  const matches: SandboxViolationEvent[] = []
+  let total = 0
+  for (const violation of violations) {
+    if (!matchesViolationQuery(violation, query)) {
+      continue
+    }
+    if (total >= offset && matches.length < limit) {
+      matches.push(violation)
+    }
+    total++
+  }
+  return { violations: matches, total }
+}
+
+const DEFAULT_MAX_ENTRIES = 100
+const DEFAULT_MAX_BYTES = 1024 * 1024
+
+/** Fixed cost of an event beyond its strings (object, Date, numbers) */
+const EVENT_OVERHEAD_BYTES = 128
+
+/**
+ * Approximate memory held by a violation, counting strings as UTF-16
+ */
+function estimateViolationBytes(violation: SandboxViolationEvent): number {
+  const strings = [
+    violation.line,
+    violation.command,
+    violation.encodedCommand,
+    violation.path,
+    violation.host,
+    violation.processName,
+  ]
+  return strings.reduce(
+    (bytes, value) => bytes + (value?.length ?? 0) * 2,
+    EVENT_OVERHEAD_BYTES,
+  )
+}
+
+/**
+ * Per-consumer queue of a violation stream
+ */
+class ViolationStream implements AsyncIterableIterator<SandboxViolationEvent> {
+  private readonly buffer: SandboxViolationEvent[] = []
+  private waiting:
+    | ((result: IteratorResult<SandboxViolationEvent>) => void)
+    | undefined
+  private done = false
+  /** Violations dropped because the consumer fell behind */
+  dropped = 0
+
+  constructor(
+    private readonly maxBuffered: number,
+    private readonly onClose: () => void,
+  ) {}
+
+  push(violation: SandboxViolationEvent): void {
+    if (this.done) {
+      return
+    }
+    if (this.waiting) {
+      const resolve = this.waiting
+      this.waiting = undefined
+      resolve({ value: violation, done: false })
+      return
+    }
+    this.buffer.push(violation)
+    if (this.buffer.length > this.maxBuffered) {
+      this.buffer.shift()
+      this.dropped++
+    }
+  }
+
+  next(): Promise<IteratorResult<SandboxViolationEvent>> {
+    const violation = this.buffer.shift()
+    if (violation) {
+      return Promise.resolve({ value: violation, done: false })
+    }
+    if (this.done) {
+      return Promise.resolve({ value: undefined, done: true })
+    }
+    return new Promise(resolve => {
+      this.waiting = resolve
+    })
+  }
+
+  return(): Promise<IteratorResult<SandboxViolationEvent>> {
+    this.close()
+    return Promise.resolve({ value: undefined, done: true })
+  }
+
+  close(): void {
+    if (this.done) {
+      return
+    }
+    this.done = true
+    this.buffer.length = 0
+    this.onClose()
+    this.waiting?.({ value: undefined, done: true })
+    this.waiting = undefined
+  }
+
+  [Symbol.asyncIterator](): AsyncIterableIterator<SandboxViolationEvent> {
+    return this
+  }
+}
 
 /**
  * In-memory tail for sandbox violations
+ *
+ * Holds the newest violations up to maxEntries and (approximately) maxBytes,
+ * evicting the oldest first, so a command producing endless denials cannot
+ * exhaust host memory. Evicted violations stay in the journal, if any.
  */
 export class SandboxViolationStore {
   private violations: SandboxViolationEvent[] = []
   private totalCount = 0
-  private readonly maxSize = 100
+  private droppedCount = 0
+  private bytes = 0
+  private maxEntries = DEFAULT_MAX_ENTRIES
+  private maxBytes = DEFAULT_MAX_BYTES
   private listeners: Set<(violations: SandboxViolationEvent[]) => void> =
     new Set()
+  private journal: ViolationJournal | undefined
+  private streams: Set<ViolationStream> = new Set()
+  private ignoreViolations: IgnoreViolationsConfig | undefined
+  private redact: Redactor = createRedactor()
+
+  /**
+   * Drop violations matching the ignoreViolations config from now on
+   */
+  setIgnoreViolations(
+    ignoreViolations: IgnoreViolationsConfig | undefined,
+  ): void {
+    this.ignoreViolations = ignoreViolations
+  }
+
+  /**
+   * Redact secrets in violations added from now on (default: built-in patterns)
+   */
+  setRedaction(redaction?: RedactionConfig): void {
+    this.redact = createRedactor(redaction)
+  }
+
+  /**
+   * Cap the violations held in memory, evicting the oldest beyond the caps
+   */
+  setLimits(limits: ViolationStoreConfig = {}): void {
+    this.maxEntries = limits.maxEntries ?? DEFAULT_MAX_ENTRIES
+    this.maxBytes = limits.maxBytes ?? DEFAULT_MAX_BYTES
+    if (this.evict()) {
+      this.notifyListeners()
+    }
+  }
+
+  /**
+   * Persist violations to a journal and load the history it already holds
+   */
+  attachJournal(journal: ViolationJournal): void {
+    this.detachJournal()
+    const recovered = journal.recover()
+    logForDebugging(
+      `[ViolationStore] Recovered ${recovered.violations.length} violations from ${journal.path}` +
+        ` (${recovered.corruptRecords} corrupt records skipped)`,
+    )
+    this.journal = journal
+    this.violations = recovered.violations.slice(-this.maxEntries)
+    this.bytes = this.violations.reduce(
+      (bytes, violation) => bytes + estimateViolationBytes(violation),
+      0,
+    )
+    this.evict()
+    this.totalCount = recovered.violations.length
+    this.notifyListeners()
+  }
 
-  addViolation(violation: SandboxViolationEvent): void {
+  detachJournal(): void {
+    this.journal?.close()
+    this.journal = undefined
+  }
+
+  addViolation(event: SandboxViolationEvent): void {
+    if (isViolationIgnored(event, this.ignoreViolations)) {
+      return
+    }
+    // Nothing downstream (journal, streams, exports) sees the secrets
+    const violation = redactViolation(event, this.redact)
+    // Write-ahead: the violation is journaled before it becomes visible
+    if (this.journal) {
+      try {
+        this.journal.append(violation)
+      } catch (error) {
+        logForDebugging(
+          `[ViolationStore] Failed to journal violation: ${error}`,
+          { level: 'error' },
+        )
+      }
+    }
     this.violations.push(violation)
+    this.bytes += estimateViolationBytes(violation)
     this.totalCount++
-    if (this.violations.length > this.maxSize) {
-      this.violations = this.violations.slice(-this.maxSize)
-    }
+    this.evict()
+    this.streams.forEach(stream => stream.push(violation))
     this.notifyListeners()
   }
 
@@ -27,6 +310,14 @@ export class SandboxViolationStore {
     return this.violations.slice(-limit)
   }
 
+  /**
+   * Find violations held in memory by kind, time, process and target, a page
+   * at a time, in the order they were added
+   */
+  query(query: ViolationQuery = {}): ViolationQueryResult {
+    return queryViolations(this.violations, query)
+  }
+
   getCount(): number {
     return this.violations.length
   }
@@ -35,13 +326,42 @@ export class SandboxViolationStore {
     return this.totalCount
   }
 
+  /**
+   * Violations evicted from memory to stay within the caps
+   */
+  getDroppedCount(): number {
+    return this.droppedCount
+  }
+
+  /**
+   * Approximate memory held by the violations in the store
+   */
+  getByteSize(): number {
+    return this.bytes
+  }
+
+  /**
+   * The encodedCommand of a command's violations in the store, after redaction
+   */
+  encodeCommand(command: string): string {
+    return encodeRedactedCommand(command, this.redact)
+  }
+
   getViolationsForCommand(command: string): SandboxViolationEvent[] {
-    const commandBase64 = encodeSandboxedCommand(command)
+    const commandBase64 = this.encodeCommand(command)
     return this.violations.filter(v => v.encodedCommand === commandBase64)
   }
 
+  /**
+   * Serialize the violations held in memory as JSON, CSV or SARIF
+   */
+  export(format: ViolationExportFormat): string {
+    return exportViolations(this.violations, format)
+  }
+
   clear(): void {
     this.violations = []
+    this.bytes = 0
     // Don't reset totalCount when clearing
     this.notifyListeners()
   }
@@ -56,6 +376,57 @@ export class SandboxViolationStore {
     }
   }
 
+  /**
+   * Stream violations added from now on, one event at a time
+   *
+   * Unlike subscribe(), consumers are never called back: each one reads from
+   * its own buffer with `for await`, so a slow consumer cannot hold up the
+   * store or the other consumers. Breaking out of the loop or aborting the
+   * signal ends the stream.
+   */
+  stream(
+    options: ViolationStreamOptions = {},
+  ): AsyncIterableIterator<SandboxViolationEvent> & { dropped: number } {
+    const { signal, maxBuffered = 1000 } = options
+    const stream = new ViolationStream(maxBuffered, () => {
+      this.streams.delete(stream)
+      signal?.removeEventListener('abort', onAbort)
+    })
+    const onAbort = () => stream.close()
+    if (signal?.aborted) {
+      stream.close()
+      return stream
+    }
+    signal?.addEventListener('abort', onAbort, { once: true })
+    this.streams.add(stream)
+    return stream
+  }
+
+  /**
+   * Evict the oldest violations beyond the caps, always keeping the newest
+   * Returns whether any were evicted
+   */
+  private evict(): boolean {
+    const before = this.violations.length
+    let excess = Math.max(0, before - this.maxEntries)
+    let bytes = this.bytes
+    for (let i = 0; i < excess; i++) {
+      bytes -= estimateViolationBytes(this.violations[i])
+    }
+    while (bytes > this.maxBytes && excess < before - 1) {
+      bytes -= estimateViolationBytes(this.violations[excess])
+      excess++
+    }
+    if (excess === 0) {
+      return false
+    }
+    // One splice per call keeps eviction linear however many are dropped
+    this.violations.splice(0, excess)
+    this.bytes = bytes
+    this.droppedCount += excess
+    return true
+  }
+
   private notifyListeners(): void {
     // Always notify with all violations so listeners can track the full count
     const violations = this.getViolations()
@@ -0,0 +1,128 @@
+import * as fs from 'node:fs'
+import { homedir } from 'node:os'
+import { join } from 'node:path'
+import { logForDebugging } from '../utils/debug.js'
+
+/**
+ * Per-session scratch directory
+ *
+ * Each session gets its own writable directory under ~/.srt/scratch, exposed
+ * to sandboxed commands as $SRT_SCRATCH_DIR (and $TMPDIR). Tools get temp
+ * space that doesn't depend on the host /tmp policy, and parallel sessions
+ * never share a directory. The directory is removed at session end; leftovers
+ * from sessions that crashed are removed when the next session starts.
+ *
+ * The size cap is soft: the directory is a plain host directory shared by
+ * the session's commands, so nothing stops a running command from writing
+ * past it. It is checked before each command starts instead.
+ */
+
+/** Default size cap for a scratch directory */
+export const DEFAULT_SCRATCH_MAX_SIZE_MB = 1024
+
+export function getScratchRoot(): string {
+  return join(homedir(), '.srt', 'scratch')
+}
+
+function isProcessAlive(pid: number): boolean {
+  try {
+    process.kill(pid, 0)
+    return true
+  } catch (error) {
+    // EPERM means the process exists but belongs to another user
+    return (error as NodeJS.ErrnoException).code === 'EPERM'
+  }
+}
+
+/**
+ * Remove scratch directories left behind by sessions that are no longer running
+ */
+function pruneStaleScratchDirs(root: string): void {
+  let entries: string[]
+  try {
+    entries = fs.readdirSync(root)
+  } catch {
+    return
+  }
+
+  for (const entry of entries) {
+    const pid = Number.parseInt(entry.split('-')[0] ?? '', 10)
+    if (Number.isNaN(pid) || pid === process.pid || isProcessAlive(pid)) {
+      continue
+    }
+    logForDebugging(`[Scratch] Removing stale scratch directory ${entry}`)
+    fs.rmSync(join(root, entry), { recursive: true, force: true })
+  }
+}
+
+/**
+ * Total size in bytes of the regular files under a directory (symlinks are not followed)
+ */
+function getDirectorySize(dir: string): number {
+  let total = 0
+  let entries: fs.Dirent[]
+  try {
+    entries = fs.readdirSync(dir, { withFileTypes: true })
+  } catch {
+    return 0
+  }
+  for (const entry of entries) {
+    const entryPath = join(dir, entry.name)
+    if (entry.isDirectory()) {
+      total += getDirectorySize(entryPath)
+    } else if (entry.isFile()) {
+      try {
+        total += fs.lstatSync(entryPath).size
+      } catch {
+        // Removed while walking
+      }
+    }
+  }
+  return total
+}
+
+export class ScratchDir {
+  private constructor(
+    readonly path: string,
+    readonly maxSizeMB: number,
+  ) {}
+
+  /**
+   * Create a fresh scratch directory for this session (mode 0700)
+   */
+  static create(
+    maxSizeMB: number = DEFAULT_SCRATCH_MAX_SIZE_MB,
+    root: string = getScratchRoot(),
+  ): ScratchDir {
+    fs.mkdirSync(root, { recursive: true, mode: 0o700 })
+    pruneStaleScratchDirs(root)
+    const path = fs.mkdtempSync(join(root, `${process.pid}-`))
+    fs.chmodSync(path, 0o700)
+    logForDebugging(`[Scratch] Created session scratch directory ${path}`)
+    return new ScratchDir(fs.realpathSync(path), maxSizeMB)
+  }
+
+  getUsageBytes(): number {
+    return getDirectorySize(this.path)
+  }
+
+  /**
+   * Throw if the scratch directory is over its size cap
+   * Checked before each sandboxed command starts; commands already running
+   * are not stopped.
+   */
+  assertWithinLimit(): void {
+    const usedMB = this.getUsageBytes() / (1024 * 1024)
+    if (usedMB > this.maxSizeMB) {
+      throw new Error(
+        `Sandbox scratch directory ${this.path} uses ${usedMB.toFixed(1)} MB, ` +
+          `over its ${this.maxSizeMB} MB limit. Free space in $SRT_SCRATCH_DIR to continue.`,
+      )
+    }
+  }
+
+  remove(): void {
+    fs.rmSync(this.path, { recursive: true, force: true })
+    logForDebugging(`[Scratch] Removed session scratch directory ${this.path}`)
+  }
+}
@@ -0,0 +1,285 @@
+/**
+ * In-process seccomp-bpf program construction
+ *
+ * Builds classic BPF programs for SECCOMP_MODE_FILTER without libseccomp or a
+ * C toolchain. The output uses the same instruction layout libseccomp emits for
+ * simple rule sets, so the Unix socket filter is byte-for-byte identical to the
+ * pre-generated vendor/seccomp/{arch}/unix-block.bpf files.
+ */
+
+export type SeccompArch = 'x64' | 'arm64'
+
+/**
+ * A syscall that returns EPERM instead of executing
+ * When arg0 is set, the rule only matches if the first argument equals it.
+ */
+export interface SeccompDenyRule {
+  syscall: number
+  arg0?: number
+}
+
+// BPF instruction classes and fields (linux/filter.h)
+const BPF_LD_W_ABS = 0x20
+const BPF_JMP_JEQ_K = 0x15
+const BPF_JMP_JGE_K = 0x35
+const BPF_RET_K = 0x06
+
+// Return values (linux/seccomp.h)
+const SECCOMP_RET_KILL_THREAD = 0x00000000
+const SECCOMP_RET_ERRNO = 0x00050000
+const SECCOMP_RET_ALLOW = 0x7fff0000
+const EPERM = 1
+
+// struct seccomp_data offsets
+const OFFSET_NR = 0
+const OFFSET_ARCH = 4
+const OFFSET_ARG0_LO = 16
+const OFFSET_ARG0_HI = 20
+
+const AUDIT_ARCH: Record<SeccompArch, number> = {
+  x64: 0xc000003e, // AUDIT_ARCH_X86_64
+  arm64: 0xc00000b7, // AUDIT_ARCH_AARCH64
+}
+
+/** x32 ABI syscalls on x86-64 have this bit set */
+const X32_SYSCALL_BIT = 0x40000000
+
+/** Syscall numbers per architecture (x64: asm/unistd_64.h, arm64: asm-generic) */
+export const SYSCALL_NUMBERS = {
+  acct: { x64: 163, arm64: 89 },
+  add_key: { x64: 248, arm64: 217 },
+  bpf: { x64: 321, arm64: 280 },
+  chroot: { x64: 161, arm64: 51 },
+  clock_settime: { x64: 227, arm64: 112 },
+  delete_module: { x64: 176, arm64: 106 },
+  finit_module: { x64: 313, arm64: 273 },
+  init_module: { x64: 175, arm64: 105 },
+  io_uring_enter: { x64: 426, arm64: 426 },
+  io_uring_register: { x64: 427, arm64: 427 },
+  io_uring_setup: { x64: 425, arm64: 425 },
+  kexec_file_load: { x64: 320, arm64: 294 },
+  kexec_load: { x64: 246, arm64: 104 },
+  keyctl: { x64: 250, arm64: 219 },
+  mount: { x64: 165, arm64: 40 },
+  open_by_handle_at: { x64: 304, arm64: 265 },
+  perf_event_open: { x64: 298, arm64: 241 },
+  personality: { x64: 135, arm64: 92 },
+  pivot_root: { x64: 155, arm64: 41 },
+  process_vm_readv: { x64: 310, arm64: 270 },
+  process_vm_writev: { x64: 311, arm64: 271 },
+  ptrace: { x64: 101, arm64: 117 },
+  reboot: { x64: 169, arm64: 142 },
+  request_key: { x64: 249, arm64: 218 },
+  setdomainname: { x64: 171, arm64: 162 },
+  sethostname: { x64: 170, arm64: 161 },
+  setns: { x64: 308, arm64: 268 },
+  settimeofday: { x64: 164, arm64: 170 },
+  socket: { x64: 41, arm64: 198 },
+  swapoff: { x64: 168, arm64: 225 },
+  swapon: { x64: 167, arm64: 224 },
+  umount2: { x64: 166, arm64: 39 },
+  unshare: { x64: 272, arm64: 97 },
+  userfaultfd: { x64: 323, arm64: 282 },
+} as const
+
+export type SyscallName = keyof typeof SYSCALL_NUMBERS
+
+export const SYSCALL_NAMES = Object.keys(SYSCALL_NUMBERS) as SyscallName[]
+
+/**
+ * Syscalls denied by the 'strict' profile
+ * Debugging other processes, kernel keyrings, eBPF, mounts and namespace changes,
+ * module loading, and host administration.
+ */
+export const STRICT_DENIED_SYSCALLS: readonly SyscallName[] = [
+  'ptrace',
+  'process_vm_readv',
+  'process_vm_writev',
+  'keyctl',
+  'add_key',
+  'request_key',
+  'bpf',
+  'perf_event_open',
+  'userfaultfd',
+  'io_uring_setup',
+  'io_uring_enter',
+  'io_uring_register',
+  'mount',
+  'umount2',
+  'pivot_root',
+  'unshare',
+  'setns',
+  'open_by_handle_at',
+  'init_module',
+  'finit_module',
+  'delete_module',
+  'kexec_load',
+  'kexec_file_load',
+  'reboot',
+  'swapon',
+  'swapoff',
+  'acct',
+  'settimeofday',
+  'clock_settime',
+  'sethostname',
+  'setdomainname',
+]
+
+export const AF_UNIX = 1
+
+type JumpTarget = 'next' | 'allow' | 'deny' | 'kill' | 'nextRule'
+
+interface Instruction {
+  code: number
+  k: number
+  jt?: JumpTarget
+  jf?: JumpTarget
+}
+
+/**
+ * Assemble a filter that denies the given rules with EPERM and allows everything else
+ * Syscalls from a foreign architecture (or the x32 ABI on x64) kill the thread.
+ */
+export function buildSeccompFilter(
+  arch: SeccompArch,
+  rules: SeccompDenyRule[],
+): Buffer {
+  const instructions: Instruction[] = []
+  // Index of the first instruction of each rule, used to resolve 'nextRule'
+  const ruleStarts: number[] = []
+
+  instructions.push({ code: BPF_LD_W_ABS, k: OFFSET_ARCH })
+  instructions.push({
+    code: BPF_JMP_JEQ_K,
+    k: AUDIT_ARCH[arch],
+    jt: 'next',
+    jf: 'kill',
+  })
+  instructions.push({ code: BPF_LD_W_ABS, k: OFFSET_NR })
+
+  if (arch === 'x64') {
+    // Only -1 (used by tracers to skip a syscall) is allowed above the x32 bit
+    instructions.push({
+      code: BPF_JMP_JGE_K,
+      k: X32_SYSCALL_BIT,
+      jt: 'next',
+      jf: 'nextRule',
+    })
+    instructions.push({
+      code: BPF_JMP_JEQ_K,
+      k: 0xffffffff,
+      jt: 'nextRule',
+      jf: 'kill',
+    })
+  }
+
+  rules.forEach((rule, index) => {
+    ruleStarts.push(instructions.length)
+    // Argument checks clobber the accumulator, so reload the syscall number
+    if (index > 0 && rules[index - 1].arg0 !== undefined) {
+      instructions.push({ code: BPF_LD_W_ABS, k: OFFSET_NR })
+    }
+    if (rule.arg0 === undefined) {
+      instructions.push({
+        code: BPF_JMP_JEQ_K,
+        k: rule.syscall,
+        jt: 'deny',
+        jf: 'nextRule',
+      })
+      return
+    }
+
+    // 64-bit argument comparison: high word, then low word
+    instructions.push(
+      { code: BPF_JMP_JEQ_K, k: rule.syscall, jt: 'next', jf: 'nextRule' },
+      { code: BPF_LD_W_ABS, k: OFFSET_ARG0_HI },
+      {
+        code: BPF_JMP_JEQ_K,
+        k: Math.floor(rule.arg0 / 0x100000000) >>> 0,
+        jt: 'next',
+        jf: 'nextRule',
+      },
+      { code: BPF_LD_W_ABS, k: OFFSET_ARG0_LO },
+      {
+        code: BPF_JMP_JEQ_K,
+        k: rule.arg0 >>> 0,
+        jt: 'deny',
+        jf: 'nextRule',
+      },
+    )
+  })
+
+  const allowIndex = instructions.length
+  instructions.push({ code: BPF_RET_K, k: SECCOMP_RET_ALLOW })
+  instructions.push({ code: BPF_RET_K, k: SECCOMP_RET_ERRNO | EPERM })
+  instructions.push({ code: BPF_RET_K, k: SECCOMP_RET_KILL_THREAD })
+
+  const resolve = (from: number, target: JumpTarget | undefined): number => {
+    let to: number
+    switch (target) {
+      case undefined:
+      case 'next':
+        to = from + 1
+        break
+      case 'allow':
+        to = allowIndex
+        break
+      case 'deny':
+        to = allowIndex + 1
+        break
+      case 'kill':
+        to = allowIndex + 2
+        break
+      case 'nextRule':
+        to = ruleStarts.find(start => start > from) ?? allowIndex
+        break
+    }
+    const offset = to - from - 1
+    if (offset < 0 || offset > 0xff) {
+      throw new Error(
+        `Seccomp filter too large: jump offset ${offset} out of range`,
+      )
+    }
+    return offset
+  }
+
+  const buffer = Buffer.alloc(instructions.length * 8)
+  instructions.forEach((instruction, i) => {
+    const isJump =
+      instruction.code === BPF_JMP_JEQ_K || instruction.code === BPF_JMP_JGE_K
+    buffer.writeUInt16LE(instruction.code, i * 8)
+    buffer.writeUInt8(isJump ? resolve(i, instruction.jt) : 0, i * 8 + 2)
+    buffer.writeUInt8(isJump ? resolve(i, instruction.jf) : 0, i * 8 + 3)
+    buffer.writeUInt32LE(instruction.k >>> 0, i * 8 + 4)
+  })
+  return buffer
+}
+
+/**
+ * Build the filter that blocks socket(AF_UNIX, ...) creation
+ */
+export function buildUnixSocketBlockFilter(arch: SeccompArch): Buffer {
+  return buildSeccompFilter(arch, [
+    { syscall: SYSCALL_NUMBERS.socket[arch], arg0: AF_UNIX },
+  ])
+}
+
+/**
+ * Build a filter that denies the given syscalls, optionally also blocking
+ * socket(AF_UNIX, ...). With only the Unix socket rule this is identical to
+ * buildUnixSocketBlockFilter().
+ */
+export function buildSyscallDenyFilter(
+  arch: SeccompArch,
+  syscalls: readonly SyscallName[],
+  blockUnixSockets: boolean,
+): Buffer {
+  const rules: SeccompDenyRule[] = []
+  if (blockUnixSockets) {
+    rules.push({ syscall: SYSCALL_NUMBERS.socket[arch], arg0: AF_UNIX })
+  }
+  for (const name of new Set(syscalls)) {
+    rules.push({ syscall: SYSCALL_NUMBERS[name][arch] })
+  }
+  return buildSeccompFilter(arch, rules)
+}
@@ -0,0 +1,329 @@
+import { spawn, spawnSync } from 'node:child_process'
+import * as fs from 'node:fs'
+import * as net from 'node:net'
+import { homedir, tmpdir } from 'node:os'
+import { join } from 'node:path'
+import shellquote from 'shell-quote'
+import { getPlatform, type Platform } from '../utils/platform.js'
+import { applyAllowListExclusions } from './allow-list-exclusions.js'
+import type { SandboxRuntimeConfig } from './sandbox-config.js'
+import { SandboxManager, matchesDomainPattern } from './sandbox-manager.js'
+import { containsGlobChars, normalizePathForSandbox } from './sandbox-utils.js'
+
+/**
+ * Policy self-test (`srt selftest`)
+ *
+ * Runs a fixed matrix of probe commands inside the sandbox and checks that each
+ * one is allowed or blocked as the current config says it should be. The
+ * expectation for every probe is derived from the config, so the same matrix
+ * can run on any host to catch enforcement regressions after OS or backend
+ * upgrades.
+ */
+
+export type ProbeExpectation = 'allow' | 'deny'
+
+export interface SelftestProbe {
+  name: string
+  expected: ProbeExpectation
+  /** Command to run inside the sandbox; exit code 0 means the action was allowed */
+  command?: string
+  /** Set instead of command when the probe cannot run with this config */
+  skipReason?: string
+}
+
+export interface SelftestResult {
+  name: string
+  expected: ProbeExpectation
+  outcome: 'pass' | 'fail' | 'skip'
+  detail?: string
+}
+
+const PROBE_TIMEOUT_MS = 30_000
+
+// Public hosts tried, in order, for the "denied domain" network probe
+const DENIED_HOST_CANDIDATES = ['example.com', 'example.org', 'example.net']
+
+/**
+ * Build a probe command that runs a small Node.js script
+ * Node is always present where srt runs, unlike curl or python.
+ */
+function nodeProbe(script: string, ...args: string[]): string {
+  return shellquote.quote([process.execPath, '-e', script, ...args])
+}
+
+function isCommandAvailable(command: string): boolean {
+  return spawnSync('which', [command], { stdio: 'ignore' }).status === 0
+}
+
+function isWithinAny(target: string, paths: string[]): boolean {
+  return paths.some(p => target === p || target.startsWith(p + '/'))
+}
+
+/**
+ * Non-glob config paths, normalized the same way the sandbox normalizes them
+ */
+function literalPaths(paths: string[]): string[] {
+  return paths
+    .filter(p => !containsGlobChars(p))
+    .map(p => normalizePathForSandbox(p))
+}
+
+function isHostAllowed(config: SandboxRuntimeConfig, host: string): boolean {
+  const { allowedDomains, deniedDomains } = config.network
+  return (
+    !deniedDomains.some(pattern => matchesDomainPattern(host, pattern)) &&
+    allowedDomains.some(pattern => matchesDomainPattern(host, pattern))
+  )
+}
+
+function buildNetworkProbes(config: SandboxRuntimeConfig): SelftestProbe[] {
+  const curlAvailable = isCommandAvailable('curl')
+  const fetch = (host: string) =>
+    shellquote.quote([
+      'curl',
+      '-sS',
+      '-o',
+      '/dev/null',
+      '--max-time',
+      '15',
+      `https://${host}/`,
+    ])
+
+  const allowedHost = config.network.allowedDomains.find(
+    host => !host.startsWith('*.') && isHostAllowed(config, host),
+  )
+  const deniedHost = DENIED_HOST_CANDIDATES.find(
+    host => !isHostAllowed(config, host),
+  )
+
+  return [
+    {
+      name: `network: fetch allowed domain${allowedHost ? ` (${allowedHost})` : ''}`,
+      expected: 'allow',
+      ...(!curlAvailable
+        ? { skipReason: 'curl is not installed' }
+        : !allowedHost
+          ? { skipReason: 'no non-wildcard domain in allowedDomains' }
+          : { command: fetch(allowedHost) }),
+    },
+    {
+      name: `network: fetch denied domain${deniedHost ? ` (${deniedHost})` : ''}`,
+      expected: 'deny',
+      ...(!curlAvailable
+        ? { skipReason: 'curl is not installed' }
+        : !deniedHost
+          ? { skipReason: 'every candidate domain is allowed' }
+          : { command: fetch(deniedHost) }),
+    },
+  ]
+}
+
+function buildFilesystemProbes(
+  config: SandboxRuntimeConfig,
+  workDir: string,
+): SelftestProbe[] {
+  const readFile = (target: string) =>
+    nodeProbe(
+      `const fs = require('fs'), p = process.argv[1];` +
+        ` fs.statSync(p).isDirectory() ? fs.readdirSync(p) : fs.readFileSync(p)`,
+      target,
+    )
+  const writeFile = (target: string) =>
+    nodeProbe(
+      `const fs = require('fs'), p = process.argv[1];` +
+        ` fs.writeFileSync(p, 'srt selftest'); fs.unlinkSync(p)`,
+      target,
+    )
+
+  const denyRead = literalPaths(config.filesystem.denyRead)
+  const allowWrite = literalPaths(config.filesystem.allowWrite)
+  const denyWrite = literalPaths(config.filesystem.denyWrite)
+
+  // A file the host created outside every deny rule should stay readable
+  const readableFile = join(workDir, 'readable.txt')
+  const deniedReadPath = denyRead.find(p => fs.existsSync(p))
+
+  const probeName = `.srt-selftest-${process.pid}`
+  const writableDir = allowWrite.find(
+    p =>
+      !isWithinAny(p, denyWrite) &&
+      fs.existsSync(p) &&
+      fs.statSync(p).isDirectory(),
+  )
+  const unwritableDir = [homedir(), process.cwd()].find(
+    p => !isWithinAny(p, allowWrite),
+  )
+
+  return [
+    {
+      name: 'filesystem: read allowed file',
+      expected: 'allow',
+      ...(isWithinAny(readableFile, denyRead)
+        ? { skipReason: `${workDir} is covered by denyRead` }
+        : { command: readFile(readableFile) }),
+    },
+    {
+      name: `filesystem: read denied path${deniedReadPath ? ` (${deniedReadPath})` : ''}`,
+      expected: 'deny',
+      ...(deniedReadPath
+        ? { command: readFile(deniedReadPath) }
+        : { skipReason: 'no existing non-glob path in denyRead' }),
+    },
+    {
+      name: `filesystem: write allowed path${writableDir ? ` (${writableDir})` : ''}`,
+      expected: 'allow',
+      ...(writableDir
+        ? { command: writeFile(join(writableDir, probeName)) }
+        : { skipReason: 'no existing non-glob directory in allowWrite' }),
+    },
+    {
+      name: `filesystem: write denied path${unwritableDir ? ` (${unwritableDir})` : ''}`,
+      expected: 'deny',
+      ...(unwritableDir
+        ? { command: writeFile(join(unwritableDir, probeName)) }
+        : { skipReason: 'home and working directory are both in allowWrite' }),
+    },
+  ]
+}
+
+function buildSocketProbes(
+  config: SandboxRuntimeConfig,
+  socketPath: string,
+  platform: Platform,
+): SelftestProbe[] {
+  // Inside the Linux network namespace loopback is private to the sandbox, so
+  // binding is always allowed there; macOS only allows it when configured
+  const localBindExpected: ProbeExpectation =
+    platform === 'linux' || config.network.allowLocalBinding ? 'allow' : 'deny'
+
+  return [
+    {
+      name: 'unix socket: connect to host socket',
+      expected: config.network.allowAllUnixSockets ? 'allow' : 'deny',
+      command: nodeProbe(
+        `require('net').connect(process.argv[1])` +
+          `.on('connect', () => process.exit(0))` +
+          `.on('error', e => { console.error(e.message); process.exit(1) })`,
+        socketPath,
+      ),
+    },
+    {
+      name: 'local bind: listen on 127.0.0.1',
+      expected: localBindExpected,
+      command: nodeProbe(
+        `const s = require('net').createServer();` +
+          ` s.on('error', e => { console.error(e.message); process.exit(1) });` +
+          ` s.listen(0, '127.0.0.1', () => s.close(() => process.exit(0)))`,
+      ),
+    },
+  ]
+}
+
+/**
+ * Build the probe matrix for a config
+ * workDir is a host directory holding the probe's readable file and unix socket.
+ */
+export function buildSelftestProbes(
+  config: SandboxRuntimeConfig,
+  workDir: string,
+  platform: Platform = getPlatform(),
+): SelftestProbe[] {
+  config = applyAllowListExclusions(config)
+  return [
+    ...buildNetworkProbes(config),
+    ...buildFilesystemProbes(config, workDir),
+    ...buildSocketProbes(config, join(workDir, 'probe.sock'), platform),
+  ]
+}
+
+function runSandboxedCommand(
+  command: string,
+  env: NodeJS.ProcessEnv,
+): Promise<{ code: number | null; stderr: string }> {
+  return new Promise(resolve => {
+    const child = spawn(command, {
+      shell: true,
+      stdio: ['ignore', 'ignore', 'pipe'],
+      timeout: PROBE_TIMEOUT_MS,
+      env,
+    })
+    let stderr = ''
+    child.stderr.setEncoding('utf8')
+    child.stderr.on('data', (data: string) => {
+      stderr += data
+    })
+    child.on('error', error => resolve({ code: null, stderr: error.message }))
+    child.on('close', code => resolve({ code, stderr }))
+  })
+}
+
+/**
+ * Run every probe through the initialized SandboxManager
+ */
+export async function runSelftest(
+  config: SandboxRuntimeConfig,
+): Promise<SelftestResult[]> {
+  const workDir = fs.mkdtempSync(join(tmpdir(), 'srt-selftest-'))
+  fs.writeFileSync(join(workDir, 'readable.txt'), 'srt selftest\n')
+  const socketServer = net.createServer(socket => socket.destroy())
+  await new Promise<void>(resolve =>
+    socketServer.listen(join(workDir, 'probe.sock'), resolve),
+  )
+
+  const results: SelftestResult[] = []
+  try {
+    for (const probe of buildSelftestProbes(config, workDir)) {
+      if (!probe.command) {
+        results.push({
+          name: probe.name,
+          expected: probe.expected,
+          outcome: 'skip',
+          detail: probe.skipReason,
+        })
+        continue
+      }
+
+      const wrapped = await SandboxManager.wrapWithSandbox(probe.command)
+      const { code, stderr } = await runSandboxedCommand(
+        wrapped,
+        SandboxManager.getCommandEnv(probe.command),
+      )
+      const actual: ProbeExpectation = code === 0 ? 'allow' : 'deny'
+      const firstErrorLine = stderr.trim().split('\n')[0]
+      results.push({
+        name: probe.name,
+        expected: probe.expected,
+        outcome: actual === probe.expected ? 'pass' : 'fail',
+        detail:
+          actual === probe.expected
+            ? undefined
+            : `was ${actual === 'allow' ? 'allowed' : 'blocked'}${firstErrorLine ? `: ${firstErrorLine}` : ''}`,
+      })
+    }
+  } finally {
+    socketServer.close()
+    fs.rmSync(workDir, { recursive: true, force: true })
+  }
+  return results
+}
+
+/**
+ * Render results as a fixed-width pass/fail table with a summary line
+ */
+export function formatSelftestTable(results: SelftestResult[]): string {
+  const nameWidth = Math.max(5, ...results.map(r => r.name.length))
+  const lines = [
+    `${'PROBE'.padEnd(nameWidth)}  EXPECTED  RESULT`,
+    ...results.map(r => {
+      const row = `${r.name.padEnd(nameWidth)}  ${r.expected.padEnd(8)}  ${r.outcome.toUpperCase()}`
+      return r.detail ? `${row} (${r.detail})` : row
+    }),
+  ]
+  const count = (outcome: SelftestResult['outcome']) =>
+    results.filter(r => r.outcome === outcome).length
+  lines.push(
+    '',
+    `${count('pass')} passed, ${count('fail')} failed, ${count('skip')} skipped`,
+  )
+  return lines.join('\n')
+}
@@ -1,10 +1,17 @@
 import type { Server as NetServer } from 'net'
+import { connect } from 'net'
 import type { Socks5Server } from '@pondwader/socks5-server'
 import { createServer } from '@pondwader/socks5-server'
 import { logForDebugging } from '../utils/debug.js'
 
 export interface SocksProxyServerOptions {
   filter(port: number, host: string): Promise<boolean> | boolean
+  /**
+   * Resolve the address to connect to for an allowed host.
+   * Returning undefined blocks the connection. When omitted, the
+   * library's default connection handler resolves the host itself.
+   */
+  resolveHost?(host: string, port: number): Promise<string | undefined>
 }
 
 export interface SocksProxyWrapper {
@@ -46,6 +53,59 @@ export function createSocksProxyServer(
     }
   })
 
+  const resolveHost = options.resolveHost
+  if (resolveHost) {
+    // Connect to the address resolved by the proxy rather than re-resolving
+    socksServer.setConnectionHandler((conn, sendStatus) => {
+      const hostname = conn.destAddress
+      const port = conn.destPort
+      let granted = false
+
+      const fail = (status: 'CONNECTION_NOT_ALLOWED' | 'GENERAL_FAILURE') => {
+        if (!granted) {
+          sendStatus(status)
+        }
+        conn.socket.destroy()
+      }
+
+      resolveHost(hostname, port).then(
+        address => {
+          if (!address) {
+            logForDebugging(
+              `Connection blocked to ${hostname}:${port} (resolution)`,
+              { level: 'error' },
+            )
+            fail('CONNECTION_NOT_ALLOWED')
+            return
+          }
+
+          const target = connect({ port, host: address })
+          target.once('connect', () => {
+            granted = true
+            sendStatus('REQUEST_GRANTED')
+            conn.socket.pipe(target)
+            target.pipe(conn.socket)
+          })
+          target.on('error', err => {
+            logForDebugging(`SOCKS connection failed: ${err.message}`, {
+              level: 'error',
+            })
+            fail('GENERAL_FAILURE')
+          })
+          conn.socket.on('error', () => target.destroy())
+          conn.socket.on('close', () => target.destroy())
+          target.on('close', () => conn.socket.destroy())
+        },
+        error => {
+          logForDebugging(`Error resolving ${hostname}: ${error}`, {
+            level: 'error',
+          })
+          fail('GENERAL_FAILURE')
+        },
+      )
+    })
+  }
+
   return {
     server: socksServer,
     getPort(): number | undefined {
@@ -0,0 +1,229 @@
+import { createHash } from 'node:crypto'
+import * as fs from 'node:fs'
+import * as os from 'node:os'
+import * as path from 'node:path'
+import { logForDebugging } from '../utils/debug.js'
+import type {
+  SandboxRuntimeConfig,
+  TemporaryRuleConfig,
+} from './sandbox-config.js'
+
+/**
+ * Time-bounded allow rules
+ *
+ * `temporaryRules` allow extra domains and writable paths until a deadline
+ * (`expires`) or for a number of runs (`maxRuns`), so exceptions made while
+ * debugging don't quietly become permanent. A run is one sandbox session, i.e.
+ * one `srt` invocation. Run counts are kept in ~/.srt/temporary-rules.json,
+ * keyed by the rule's allow lists, so editing the reason or the limits does
+ * not reset them. Expired rules are ignored with a warning; a rule that
+ * expires during a session stops applying to new commands and requests.
+ */
+
+export function getTemporaryRuleStatePath(): string {
+  return path.join(os.homedir(), '.srt', 'temporary-rules.json')
+}
+
+/**
+ * Identity of a rule in the run counts
+ */
+export function getTemporaryRuleKey(rule: TemporaryRuleConfig): string {
+  return createHash('sha256')
+    .update(JSON.stringify([rule.allowedDomains ?? [], rule.allowWrite ?? []]))
+    .digest('hex')
+    .slice(0, 16)
+}
+
+function describeTemporaryRule(rule: TemporaryRuleConfig): string {
+  const allows = [
+    ...(rule.allowedDomains ?? []),
+    ...(rule.allowWrite ?? []).map(allowed => `write ${allowed}`),
+  ]
+  const reason = rule.reason ? ` "${rule.reason}"` : ''
+  return `temporary rule${reason} (${allows.join(', ')})`
+}
+
+function readRunCounts(statePath: string): Record<string, number> {
+  try {
+    const parsed: unknown = JSON.parse(fs.readFileSync(statePath, 'utf8'))
+    return typeof parsed === 'object' && parsed !== null
+      ? (parsed as Record<string, number>)
+      : {}
+  } catch {
+    // Missing or unreadable: no runs used yet
+    return {}
+  }
+}
+
+function writeRunCounts(
+  statePath: string,
+  counts: Record<string, number>,
+): void {
+  try {
+    fs.mkdirSync(path.dirname(statePath), { recursive: true })
+    const tempPath = `${statePath}.${process.pid}.tmp`
+    fs.writeFileSync(tempPath, JSON.stringify(counts, null, 2) + '\n')
+    fs.renameSync(tempPath, statePath)
+  } catch (error) {
+    logForDebugging(
+      `Failed to record temporary rule runs in ${statePath}: ${error}`,
+      { level: 'error' },
+    )
+  }
+}
+
+/**
+ * Why a rule no longer applies, given the runs used before this session
+ */
+function getRuleExpiry(
+  rule: TemporaryRuleConfig,
+  now: Date,
+  usedRuns: number,
+): string | undefined {
+  if (rule.expires !== undefined && new Date(rule.expires) <= now) {
+    return `expired at ${rule.expires}`
+  }
+  if (rule.maxRuns !== undefined && usedRuns >= rule.maxRuns) {
+    return `used all ${rule.maxRuns} runs`
+  }
+  return undefined
+}
+
+/**
+ * The rules a new session would apply, without using any of their runs
+ * (for simulations such as `srt test-policy`)
+ */
+export function getActiveTemporaryRules(
+  rules: TemporaryRuleConfig[] | undefined,
+  now: Date = new Date(),
+  statePath: string = getTemporaryRuleStatePath(),
+): TemporaryRuleConfig[] {
+  if (!rules || rules.length === 0) {
+    return []
+  }
+  const counts = rules.some(rule => rule.maxRuns !== undefined)
+    ? readRunCounts(statePath)
+    : {}
+  return rules.filter(
+    rule => !getRuleExpiry(rule, now, counts[getTemporaryRuleKey(rule)] ?? 0),
+  )
+}
+
+/**
+ * Tracks which temporary rules apply during one sandbox session
+ */
+export class TemporaryRuleTracker {
+  /** Runs used before this session, per rule with maxRuns seen so far */
+  private readonly runsBefore = new Map<string, number>()
+  private readonly warned = new Set<string>()
+  private readonly warnings: string[] = []
+
+  constructor(private readonly statePath = getTemporaryRuleStatePath()) {}
+
+  /**
+   * The rules that apply at the given time
+   * The first time a session sees an active rule with maxRuns, one of its
+   * runs is used.
+   */
+  select(
+    rules: TemporaryRuleConfig[] | undefined,
+    now: Date = new Date(),
+  ): TemporaryRuleConfig[] {
+    if (!rules || rules.length === 0) {
+      return []
+    }
+    this.useRuns(rules, now)
+    return rules.filter(rule => {
+      const expiry = this.getExpiry(rule, now)
+      if (expiry) {
+        this.warn(rule, expiry)
+        return false
+      }
+      return true
+    })
+  }
+
+  /**
+   * Warnings about the rules ignored so far, one per rule
+   */
+  getWarnings(): string[] {
+    return [...this.warnings]
+  }
+
+  private useRuns(rules: TemporaryRuleConfig[], now: Date): void {
+    const unseen = rules.filter(
+      rule =>
+        rule.maxRuns !== undefined &&
+        !this.runsBefore.has(getTemporaryRuleKey(rule)),
+    )
+    if (unseen.length === 0) {
+      return
+    }
+    const counts = readRunCounts(this.statePath)
+    let changed = false
+    for (const rule of unseen) {
+      const key = getTemporaryRuleKey(rule)
+      const used = counts[key] ?? 0
+      this.runsBefore.set(key, used)
+      if (!this.getExpiry(rule, now)) {
+        counts[key] = used + 1
+        changed = true
+      }
+    }
+    if (changed) {
+      writeRunCounts(this.statePath, counts)
+    }
+  }
+
+  private getExpiry(rule: TemporaryRuleConfig, now: Date): string | undefined {
+    return getRuleExpiry(
+      rule,
+      now,
+      this.runsBefore.get(getTemporaryRuleKey(rule)) ?? 0,
+    )
+  }
+
+  private warn(rule: TemporaryRuleConfig, expiry: string): void {
+    const key = `${getTemporaryRuleKey(rule)} ${expiry}`
+    if (this.warned.has(key)) {
+      return
+    }
+    this.warned.add(key)
+    const warning = `Ignoring ${describeTemporaryRule(rule)}: ${expiry}`
+    this.warnings.push(warning)
+    logForDebugging(warning, { level: 'warn' })
+  }
+}
+
+function mergeLists(base: string[], extra: string[] | undefined): string[] {
+  return extra ? [...new Set([...base, ...extra])] : base
+}
+
+/**
+ * Add the allow lists of active temporary rules to the config
+ */
+export function applyTemporaryRules(
+  config: SandboxRuntimeConfig,
+  rules: TemporaryRuleConfig[],
+): SandboxRuntimeConfig {
+  if (rules.length === 0) {
+    return config
+  }
+  return {
+    ...config,
+    network: {
+      ...config.network,
+      allowedDomains: rules.reduce(
+        (domains, rule) => mergeLists(domains, rule.allowedDomains),
+        config.network.allowedDomains,
+      ),
+    },
+    filesystem: {
+      ...config.filesystem,
+      allowWrite: rules.reduce(
+        (paths, rule) => mergeLists(paths, rule.allowWrite),
+        config.filesystem.allowWrite,
+      ),
+    },
+  }
+}
@@ -0,0 +1,76 @@
+import * as fs from 'node:fs'
+import type * as net from 'node:net'
+import { tmpdir } from 'node:os'
+import { join } from 'node:path'
+
+/**
+ * Unix socket servers at paths chosen by the user
+ *
+ * The socket is bound in a new private (0700) directory and the chosen path
+ * becomes a symlink to it. Only the current user can connect, even where
+ * socket permissions are ignored (macOS, the BSDs), and there is no window
+ * between binding and chmod in which others can. Whatever was at the path
+ * before is only removed when it is a socket or a link to one.
+ */
+
+/**
+ * Remove a socket left at a path by an earlier run
+ * @throws When something other than a socket, or a link to one, is there
+ */
+export function removeStaleSocket(socketPath: string): void {
+  const stats = fs.lstatSync(socketPath, { throwIfNoEntry: false })
+  if (!stats) {
+    return
+  }
+  if (stats.isSymbolicLink()) {
+    const target = fs.statSync(socketPath, { throwIfNoEntry: false })
+    if (!target || target.isSocket()) {
+      fs.unlinkSync(socketPath)
+      return
+    }
+  } else if (stats.isSocket()) {
+    fs.unlinkSync(socketPath)
+    return
+  }
+  throw new Error(`${socketPath} exists and is not a socket`)
+}
+
+/**
+ * Start a server listening on socketPath, reachable by the current user only
+ * @returns Removes the socket and its directory once the server is closed
+ */
+export async function listenOnPrivateSocket(
+  server: net.Server,
+  socketPath: string,
+): Promise<() => void> {
+  removeStaleSocket(socketPath)
+  const dir = fs.mkdtempSync(join(tmpdir(), 'srt-socket-'))
+  const boundPath = join(dir, 'socket')
+  try {
+    await new Promise<void>((resolve, reject) => {
+      server.once('error', reject)
+      server.listen(boundPath, () => {
+        server.off('error', reject)
+        resolve()
+      })
+    })
+    fs.chmodSync(boundPath, 0o600)
+    fs.symlinkSync(boundPath, socketPath)
+  } catch (error) {
+    server.close()
+    fs.rmSync(dir, { recursive: true, force: true })
+    throw error
+  }
+
+  return () => {
+    // Leave the path alone if something else has replaced the link
+    try {
+      if (fs.readlinkSync(socketPath) === boundPath) {
+        fs.unlinkSync(socketPath)
+      }
+    } catch {
+      // Already removed
+    }
+    fs.rmSync(dir, { recursive: true, force: true })
+  }
+}
@@ -0,0 +1,163 @@
+import { isAbsolute } from 'node:path'
+import { pathToFileURL } from 'node:url'
+import type { SandboxViolationEvent } from './macos-sandbox-utils.js'
+
+/**
+ * Serializing violations for CI artifacts and code-scanning dashboards
+ */
+
+export type ViolationExportFormat = 'json' | 'csv' | 'sarif'
+
+export const VIOLATION_EXPORT_FORMATS: readonly ViolationExportFormat[] = [
+  'json',
+  'csv',
+  'sarif',
+]
+
+const CSV_COLUMNS = [
+  'timestamp',
+  'type',
+  'subtype',
+  'access',
+  'command',
+  'pid',
+  'processName',
+  'path',
+  'host',
+  'port',
+  'line',
+] as const
+
+/** SARIF rule of each violation kind */
+const SARIF_RULES = {
+  'sandbox-denial': 'The sandbox denied a file, process or network operation',
+  'network-denial': 'The network proxy blocked a connection',
+  'file-access': 'A file access was recorded in audit mode',
+} as const
+
+type SarifRuleId = keyof typeof SARIF_RULES
+
+function getSarifRuleId(violation: SandboxViolationEvent): SarifRuleId {
+  switch (violation.type) {
+    case 'network':
+      return 'network-denial'
+    case 'file-access':
+      return 'file-access'
+    default:
+      return 'sandbox-denial'
+  }
+}
+
+/**
+ * A CSV field, quoted when needed
+ * Text that spreadsheets would read as a formula (starting with =, +, -, @,
+ * tab or CR) gets a leading ', since commands and paths come from the
+ * sandboxed command.
+ */
+function csvField(value: unknown): string {
+  if (value === undefined || value === null) {
+    return ''
+  }
+  let text = value instanceof Date ? value.toISOString() : String(value)
+  if (typeof value === 'string' && /^[=+\-@\t\r]/.test(text)) {
+    text = `'${text}`
+  }
+  return /[",\r\n]/.test(text) ? `"${text.replace(/"/g, '""')}"` : text
+}
+
+function toCsv(violations: SandboxViolationEvent[]): string {
+  const rows = violations.map(violation =>
+    CSV_COLUMNS.map(column => csvField(violation[column])).join(','),
+  )
+  return [CSV_COLUMNS.join(','), ...rows].join('\r\n') + '\r\n'
+}
+
+function toSarif(violations: SandboxViolationEvent[]): object {
+  return {
+    $schema: 'https://json.schemastore.org/sarif-2.1.0.json',
+    version: '2.1.0',
+    runs: [
+      {
+        tool: {
+          driver: {
+            name: 'srt',
+            informationUri:
+              'https://github.com/anthropic-experimental/sandbox-runtime',
+            rules: Object.entries(SARIF_RULES).map(([id, text]) => ({
+              id,
+              shortDescription: { text },
+            })),
+          },
+        },
+        results: violations.map(violation => {
+          const ruleId = getSarifRuleId(violation)
+          return {
+            ruleId,
+            // Audited accesses were allowed, so they are informational
+            level: ruleId === 'file-access' ? 'note' : 'warning',
+            message: { text: violation.line },
+            ...(violation.path && isAbsolute(violation.path)
+              ? {
+                  locations: [
+                    {
+                      physicalLocation: {
+                        artifactLocation: {
+                          uri: pathToFileURL(violation.path).href,
+                        },
+                      },
+                    },
+                  ],
+                }
+              : {}),
+            properties: {
+              timestamp: violation.timestamp.toISOString(),
+              command: violation.command,
+              pid: violation.pid,
+              processName: violation.processName,
+              executable: violation.executable,
+              argv: violation.argv,
+              commandLine: violation.commandLine,
+              parents: violation.parents,
+              host: violation.host,
+              port: violation.port,
+              access: violation.access,
+            },
+          }
+        }),
+      },
+    ],
+  }
+}
+
+/**
+ * Serialize violations as JSON (an array of events), CSV (one row per
+ * event, RFC 4180 quoting) or SARIF 2.1.0 (one result per event)
+ */
+export function exportViolations(
+  violations: SandboxViolationEvent[],
+  format: ViolationExportFormat,
+): string {
+  switch (format) {
+    case 'json':
+      return JSON.stringify(violations, null, 2) + '\n'
+    case 'csv':
+      return toCsv(violations)
+    case 'sarif':
+      return JSON.stringify(toSarif(violations), null, 2) + '\n'
+  }
+}
+
+/**
+ * Pick the export format from a file extension (.csv, .sarif), defaulting to JSON
+ */
+export function getViolationExportFormat(
+  filePath: string,
+): ViolationExportFormat {
+  if (filePath.endsWith('.csv')) {
+    return 'csv'
+  }
+  if (filePath.endsWith('.sarif') || filePath.endsWith('.sarif.json')) {
+    return 'sarif'
+  }
+  return 'json'
+}
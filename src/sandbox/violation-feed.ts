@@ -0,0 +1,119 @@
+import { once } from 'node:events'
+import * as net from 'node:net'
+import * as readline from 'node:readline'
+import { logForDebugging } from '../utils/debug.js'
+import type { SandboxViolationEvent } from './macos-sandbox-utils.js'
+import type { SandboxViolationStore } from './sandbox-violation-store.js'
+import { listenOnPrivateSocket } from './unix-socket.js'
+
+/**
+ * Live violation feed over a Unix socket
+ *
+ * Every client that connects receives the violations added to the store from
+ * then on, one JSON event per line (NDJSON). The feed is read-only: anything
+ * clients send is ignored. Each client has its own bounded buffer, so a slow
+ * client loses the oldest events instead of slowing the sandbox down.
+ */
+
+export interface ViolationFeed {
+  socketPath: string
+  close(): Promise<void>
+}
+
+/** Events buffered per client before the oldest are dropped */
+const MAX_BUFFERED_EVENTS = 1000
+
+async function serveClient(
+  connection: net.Socket,
+  store: SandboxViolationStore,
+): Promise<void> {
+  const controller = new AbortController()
+  connection.on('close', () => controller.abort())
+  connection.on('error', error => {
+    logForDebugging(`[ViolationFeed] Client error: ${error.message}`)
+  })
+  // Drain and discard whatever the client sends
+  connection.resume()
+
+  for await (const violation of store.stream({
+    signal: controller.signal,
+    maxBuffered: MAX_BUFFERED_EVENTS,
+  })) {
+    if (connection.destroyed) {
+      break
+    }
+    if (!connection.write(JSON.stringify(violation) + '\n')) {
+      await once(connection, 'drain', { signal: controller.signal }).catch(
+        () => {},
+      )
+    }
+  }
+}
+
+/**
+ * Serve the violation feed on a Unix socket
+ * The socket is only accessible to the current user (see unix-socket.ts).
+ */
+export async function startViolationFeed(
+  socketPath: string,
+  store: SandboxViolationStore,
+): Promise<ViolationFeed> {
+  const connections = new Set<net.Socket>()
+  const server = net.createServer(connection => {
+    connections.add(connection)
+    connection.on('close', () => connections.delete(connection))
+    void serveClient(connection, store)
+  })
+
+  const removeSocket = await listenOnPrivateSocket(server, socketPath)
+  logForDebugging(`[ViolationFeed] Listening on ${socketPath}`)
+
+  return {
+    socketPath,
+    close: () =>
+      new Promise<void>(resolve => {
+        connections.forEach(connection => connection.destroy())
+        server.close(() => {
+          removeSocket()
+          resolve()
+        })
+      }),
+  }
+}
+
+/**
+ * Follow the violation feed served on a Unix socket, e.g. by another run
+ * Yields violations as they arrive and ends when the feed closes or the
+ * signal is aborted.
+ */
+export async function* followViolationFeed(
+  socketPath: string,
+  signal?: AbortSignal,
+): AsyncGenerator<SandboxViolationEvent> {
+  const connection = net.createConnection(socketPath)
+  const onAbort = () => connection.destroy()
+  signal?.addEventListener('abort', onAbort)
+  try {
+    await once(connection, 'connect', { signal })
+    const lines = readline.createInterface({
+      input: connection,
+      crlfDelay: Infinity,
+    })
+    for await (const line of lines) {
+      if (line.length === 0) {
+        continue
+      }
+      let event
+      try {
+        event = JSON.parse(line)
+      } catch {
+        logForDebugging(`[ViolationFeed] Skipping malformed event: ${line}`)
+        continue
+      }
+      yield { ...event, timestamp: new Date(event.timestamp) }
+    }
+  } finally {
+    signal?.removeEventListener('abort', onAbort)
+    connection.destroy()
+  }
+}
@@ -0,0 +1,101 @@
+import type { SandboxViolationEvent } from './macos-sandbox-utils.js'
+import type { IgnoreViolationsConfig } from './sandbox-config.js'
+import { containsGlobChars, globToRegex } from './sandbox-utils.js'
+
+/**
+ * Matching violations against the ignoreViolations config
+ *
+ * Keys are command patterns and values are target patterns. "*" as a key
+ * matches every violation, including proxy denials that have no command.
+ * Patterns without glob characters keep their substring semantics: a command
+ * pattern matches commands containing it, and a target pattern matches
+ * violation lines containing it. Glob patterns must match as a whole: command
+ * globs against the command (where * also matches "/"), and target globs
+ * against the path, the host or host:port (gitignore-style, ** for any depth).
+ */
+
+const regexCache = new Map<string, RegExp>()
+
+function cachedRegex(key: string, build: () => string): RegExp {
+  let regex = regexCache.get(key)
+  if (!regex) {
+    regex = new RegExp(build())
+    regexCache.set(key, regex)
+  }
+  return regex
+}
+
+function commandGlobToRegex(pattern: string): string {
+  return (
+    '^' +
+    pattern
+      .replace(/[.^$+{}()|[\]\\]/g, '\\$&')
+      .replace(/\*/g, '.*')
+      .replace(/\?/g, '.') +
+    '$'
+  )
+}
+
+/**
+ * Whether a violation's command matches a command pattern
+ */
+export function matchesCommand(
+  pattern: string,
+  command: string | undefined,
+): boolean {
+  if (pattern === '*') {
+    return true
+  }
+  if (command === undefined) {
+    return false
+  }
+  if (!containsGlobChars(pattern)) {
+    return command.includes(pattern)
+  }
+  return cachedRegex(`command:${pattern}`, () =>
+    commandGlobToRegex(pattern),
+  ).test(command)
+}
+
+/**
+ * Whether a violation's path, host or host:port matches a target pattern
+ */
+export function matchesTarget(
+  pattern: string,
+  violation: Pick<SandboxViolationEvent, 'line' | 'path' | 'host' | 'port'>,
+): boolean {
+  if (!containsGlobChars(pattern)) {
+    return violation.line.includes(pattern)
+  }
+  const regex = cachedRegex(`target:${pattern}`, () => globToRegex(pattern))
+  const candidates = [
+    violation.path,
+    violation.host,
+    violation.host !== undefined && violation.port !== undefined
+      ? `${violation.host}:${violation.port}`
+      : undefined,
+  ]
+  return candidates.some(
+    candidate => candidate !== undefined && regex.test(candidate),
+  )
+}
+
+/**
+ * Whether a violation is covered by the ignoreViolations config
+ */
+export function isViolationIgnored(
+  violation: Pick<
+    SandboxViolationEvent,
+    'line' | 'command' | 'path' | 'host' | 'port'
+  >,
+  ignoreViolations: IgnoreViolationsConfig | undefined,
+): boolean {
+  if (!ignoreViolations) {
+    return false
+  }
+  return Object.entries(ignoreViolations).some(
+    ([commandPattern, targetPatterns]) =>
+      matchesCommand(commandPattern, violation.command) &&
+      targetPatterns.some(pattern => matchesTarget(pattern, violation)),
+  )
+}
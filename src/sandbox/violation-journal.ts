@@ -0,0 +1,406 @@
+import { randomUUID } from 'node:crypto'
+import * as fs from 'node:fs'
+import { dirname } from 'node:path'
+import { logForDebugging } from '../utils/debug.js'
+import type { SandboxViolationEvent } from './macos-sandbox-utils.js'
+import type { ViolationRetentionConfig } from './sandbox-config.js'
+
+/**
+ * Append-only, crash-safe violation journal
+ *
+ * Each violation is written as one JSON line before it is added to the
+ * in-memory store, so srt crashing never loses a violation that was
+ * reported. Writes are fsynced together shortly after, so a burst of
+ * violations costs one fsync; only the host going down within that delay
+ * can lose them. On startup recover() reads the file line by line, repairs a
+ * torn final line left by a crash mid-write and skips corrupt lines. With a
+ * retention policy, recover() also drops records that are too old or too
+ * many and compacts the file, and append() does the same once twice
+ * maxEntries records were written or an hour passed. Compaction rewrites the
+ * file in place, so other processes appending to it (a daemon, another srt
+ * with the same violationLogPath) keep journaling to the same file.
+ */
+
+/** How long a written record may wait for the fsync it shares with others */
+const SYNC_DELAY_MS = 100
+
+/** Bytes read at a time when reading a journal */
+const READ_CHUNK_BYTES = 64 * 1024
+
+/** How often maxAgeDays is applied to a journal being appended to */
+const RETENTION_INTERVAL_MS = 60 * 60 * 1000
+
+interface JournalRecord {
+  id: string
+  violation: Omit<SandboxViolationEvent, 'timestamp'> & { timestamp: string }
+}
+
+export interface JournalRecoveryResult {
+  violations: SandboxViolationEvent[]
+  /** Bytes of an incomplete trailing record that were truncated */
+  truncatedBytes: number
+  /** Complete lines that could not be parsed and were skipped */
+  corruptRecords: number
+  /** Records removed by the retention policy */
+  expiredRecords: number
+}
+
+function parseRecord(line: string): JournalRecord | undefined {
+  try {
+    const record = JSON.parse(line)
+    if (
+      typeof record?.id === 'string' &&
+      typeof record.violation?.line === 'string' &&
+      typeof record.violation.timestamp === 'string'
+    ) {
+      return record as JournalRecord
+    }
+  } catch {
+    // Fall through to undefined
+  }
+  return undefined
+}
+
+function toViolation(record: JournalRecord): SandboxViolationEvent {
+  return {
+    ...record.violation,
+    timestamp: new Date(record.violation.timestamp),
+  }
+}
+
+/**
+ * Call onLine with each complete line of a file, reading it a chunk at a
+ * time so the whole file is never held in memory
+ * @returns The bytes of complete lines, after which an incomplete last line
+ *   starts, and the file's size; undefined when there is no such file
+ */
+function forEachLine(
+  path: string,
+  onLine: (line: string) => void,
+): { completeBytes: number; size: number } | undefined {
+  let fd: number
+  try {
+    fd = fs.openSync(path, 'r')
+  } catch (error) {
+    if ((error as NodeJS.ErrnoException).code === 'ENOENT') {
+      return undefined
+    }
+    throw error
+  }
+  try {
+    const chunk = Buffer.alloc(READ_CHUNK_BYTES)
+    let pending = Buffer.alloc(0)
+    let completeBytes = 0
+    let bytesRead: number
+    while ((bytesRead = fs.readSync(fd, chunk, 0, chunk.length, null)) > 0) {
+      const data = Buffer.concat([pending, chunk.subarray(0, bytesRead)])
+      let start = 0
+      let newline: number
+      while ((newline = data.indexOf(0x0a, start)) !== -1) {
+        onLine(data.toString('utf8', start, newline))
+        start = newline + 1
+      }
+      completeBytes += start
+      pending = data.subarray(start)
+    }
+    return { completeBytes, size: completeBytes + pending.length }
+  } finally {
+    fs.closeSync(fd)
+  }
+}
+
+/**
+ * Write all of data at position
+ * @returns The bytes written
+ */
+function writeAt(fd: number, data: Buffer, position: number): number {
+  let offset = 0
+  while (offset < data.length) {
+    offset += fs.writeSync(
+      fd,
+      data,
+      offset,
+      data.length - offset,
+      position + offset,
+    )
+  }
+  return data.length
+}
+
+/**
+ * Read the violations in a journal without repairing or compacting it, so it
+ * is safe while another process appends to it. An incomplete last record
+ * and corrupt records are skipped.
+ */
+export function readViolationJournal(path: string): SandboxViolationEvent[] {
+  const violations: SandboxViolationEvent[] = []
+  forEachLine(path, line => {
+    const record = line.length > 0 ? parseRecord(line) : undefined
+    if (record) {
+      violations.push(toViolation(record))
+    }
+  })
+  return violations
+}
+
+/**
+ * Remove every violation from a journal
+ * The file is truncated in place, so a process appending to it keeps
+ * journaling to the same file.
+ */
+export function clearViolationJournal(path: string): void {
+  try {
+    fs.truncateSync(path, 0)
+  } catch (error) {
+    if ((error as NodeJS.ErrnoException).code !== 'ENOENT') {
+      throw error
+    }
+  }
+}
+
+export class ViolationJournal {
+  private fd: number | undefined
+  private syncTimer: NodeJS.Timeout | undefined
+  /** Records in the file, as of the last read plus those appended since */
+  private recordCount = 0
+  /** When retention was last applied */
+  private retainedAt = Date.now()
+
+  constructor(
+    readonly path: string,
+    private readonly retention: ViolationRetentionConfig = {},
+  ) {}
+
+  /**
+   * Read back all journaled violations, repairing the file if the last write
+   * was interrupted. Must be called before append().
+   */
+  recover(): JournalRecoveryResult {
+    const result: JournalRecoveryResult = {
+      violations: [],
+      truncatedBytes: 0,
+      corruptRecords: 0,
+      expiredRecords: 0,
+    }
+    const read = this.readRecords()
+    if (!read) {
+      return result
+    }
+    result.corruptRecords = read.corruptRecords
+
+    // Everything after the last newline is a record that was never completed
+    if (read.completeBytes < read.size) {
+      result.truncatedBytes = read.size - read.completeBytes
+      fs.truncateSync(this.path, read.completeBytes)
+      logForDebugging(
+        `[ViolationJournal] Truncated ${result.truncatedBytes} bytes of an incomplete record in ${this.path}`,
+        { level: 'warn' },
+      )
+    }
+
+    const kept = this.compactToRetention(read)
+    result.expiredRecords = read.recordCount - kept.length
+    result.violations = kept.map(({ record }) => toViolation(record))
+    return result
+  }
+
+  /**
+   * Read the journal's records, applying retention while reading so a long
+   * journal with a short retention is never held in memory whole
+   */
+  private readRecords():
+    | {
+        records: { record: JournalRecord; line: string }[]
+        recordCount: number
+        corruptRecords: number
+        completeBytes: number
+        size: number
+      }
+    | undefined {
+    const { maxEntries } = this.retention
+    let records: { record: JournalRecord; line: string }[] = []
+    let recordCount = 0
+    let corruptRecords = 0
+    const read = forEachLine(this.path, line => {
+      if (line.length === 0) {
+        return
+      }
+      const record = parseRecord(line)
+      if (!record) {
+        corruptRecords++
+        return
+      }
+      recordCount++
+      records.push({ record, line })
+      if (maxEntries !== undefined && records.length >= 2 * maxEntries) {
+        records = this.applyRetention(records)
+      }
+    })
+    return read && { records, recordCount, corruptRecords, ...read }
+  }
+
+  /**
+   * Rewrite the journal without the records past retention, if there are any
+   * @returns The records kept
+   */
+  private compactToRetention(read: {
+    records: { record: JournalRecord; line: string }[]
+    recordCount: number
+    completeBytes: number
+  }): { record: JournalRecord; line: string }[] {
+    const kept = this.applyRetention(read.records)
+    const expired = read.recordCount - kept.length
+    if (expired > 0) {
+      this.compact(
+        kept.map(({ line }) => line),
+        read.completeBytes,
+      )
+      logForDebugging(
+        `[ViolationJournal] Removed ${expired} records past retention from ${this.path}`,
+      )
+    }
+    this.recordCount = kept.length
+    this.retainedAt = Date.now()
+    return kept
+  }
+
+  /**
+   * Apply retention while appending, once the journal holds twice maxEntries
+   * records or RETENTION_INTERVAL_MS passed with maxAgeDays set, so a long
+   * run does not grow the file until the next start
+   */
+  private applyRetentionIfDue(): void {
+    const { maxAgeDays, maxEntries } = this.retention
+    const tooMany =
+      maxEntries !== undefined && this.recordCount >= 2 * maxEntries
+    const ageDue =
+      maxAgeDays !== undefined &&
+      Date.now() - this.retainedAt >= RETENTION_INTERVAL_MS
+    if (!tooMany && !ageDue) {
+      return
+    }
+    try {
+      this.sync()
+      const read = this.readRecords()
+      if (read) {
+        this.compactToRetention(read)
+      }
+    } catch (error) {
+      this.retainedAt = Date.now()
+      logForDebugging(
+        `[ViolationJournal] Failed to apply retention to ${this.path}: ${error}`,
+        { level: 'error' },
+      )
+    }
+  }
+
+  private applyRetention<T extends { record: JournalRecord }>(
+    records: T[],
+  ): T[] {
+    const { maxAgeDays, maxEntries } = this.retention
+    let kept = records
+    if (maxAgeDays !== undefined) {
+      const cutoff = Date.now() - maxAgeDays * 24 * 60 * 60 * 1000
+      kept = kept.filter(
+        ({ record }) => Date.parse(record.violation.timestamp) >= cutoff,
+      )
+    }
+    if (maxEntries !== undefined && kept.length > maxEntries) {
+      kept = kept.slice(kept.length - maxEntries)
+    }
+    return kept
+  }
+
+  /**
+   * Rewrite the journal in place: the given lines, then whatever was appended
+   * after readBytes since it was read
+   * Appends keep landing at the end of the file while it is rewritten, so
+   * they are moved down until none are left before truncating. The file is
+   * never replaced, since a process appending to it would keep writing to
+   * the replaced one; the kept lines come from before readBytes, so they
+   * never overwrite bytes that have not been read yet. A crash while
+   * rewriting can leave some records twice, never lose one.
+   */
+  private compact(lines: string[], readBytes: number): void {
+    const fd = fs.openSync(this.path, 'r+')
+    try {
+      let written = writeAt(
+        fd,
+        Buffer.from(lines.map(line => line + '\n').join('')),
+        0,
+      )
+      let moved = readBytes
+      let size: number
+      while ((size = fs.fstatSync(fd).size) > moved) {
+        const appended = Buffer.alloc(size - moved)
+        let offset = 0
+        while (offset < appended.length) {
+          const bytesRead = fs.readSync(
+            fd,
+            appended,
+            offset,
+            appended.length - offset,
+            moved + offset,
+          )
+          if (bytesRead === 0) {
+            break
+          }
+          offset += bytesRead
+        }
+        written += writeAt(fd, appended.subarray(0, offset), written)
+        moved += offset
+      }
+      fs.ftruncateSync(fd, written)
+      fs.fsyncSync(fd)
+    } finally {
+      fs.closeSync(fd)
+    }
+  }
+
+  /**
+   * Append a violation: written before returning, and fsynced along with
+   * the others written within SYNC_DELAY_MS
+   */
+  append(violation: SandboxViolationEvent): void {
+    if (this.fd === undefined) {
+      fs.mkdirSync(dirname(this.path), { recursive: true })
+      this.fd = fs.openSync(this.path, 'a', 0o600)
+    }
+    const record: JournalRecord = {
+      id: randomUUID(),
+      violation: { ...violation, timestamp: violation.timestamp.toISOString() },
+    }
+    fs.writeSync(this.fd, JSON.stringify(record) + '\n')
+    this.recordCount++
+    if (!this.syncTimer) {
+      this.syncTimer = setTimeout(() => this.sync(), SYNC_DELAY_MS)
+      this.syncTimer.unref()
+    }
+    this.applyRetentionIfDue()
+  }
+
+  /** fsync the records written since the last sync */
+  private sync(): void {
+    clearTimeout(this.syncTimer)
+    this.syncTimer = undefined
+    if (this.fd === undefined) {
+      return
+    }
+    try {
+      fs.fsyncSync(this.fd)
+    } catch (error) {
+      logForDebugging(
+        `[ViolationJournal] Failed to sync ${this.path}: ${error}`,
+        { level: 'error' },
+      )
+    }
+  }
+
+  close(): void {
+    if (this.fd !== undefined) {
+      this.sync()
+      fs.closeSync(this.fd)
+      this.fd = undefined
+    }
+  }
+}
@@ -0,0 +1,255 @@
+import { spawn } from 'node:child_process'
+import type { SandboxViolationEvent } from './macos-sandbox-utils.js'
+import type { NotificationsConfig } from './sandbox-config.js'
+import type { SandboxViolationStore } from './sandbox-violation-store.js'
+import { logForDebugging } from '../utils/debug.js'
+import {
+  classifyViolation,
+  type ViolationCategory,
+} from './violation-policy.js'
+
+/**
+ * Alerting a webhook and a shell hook when violations occur
+ *
+ * Notifications are sent from the host process as violations reach the
+ * store, so they work for every command and are not subject to the sandbox's
+ * network restrictions. Violations are batched: the first one starts a short
+ * delay, and everything recorded until then is sent together. Only a few
+ * batches are sent at once per target; while they are, violations queue up
+ * to a cap and the ones past it are counted as dropped. Failures go to the
+ * debug log and never affect the command.
+ */
+
+export type ViolationSeverity = 'low' | 'medium' | 'high'
+
+const SEVERITY_RANK: Record<ViolationSeverity, number> = {
+  low: 0,
+  medium: 1,
+  high: 2,
+}
+
+const CATEGORY_SEVERITY: Record<ViolationCategory, ViolationSeverity> = {
+  network: 'high',
+  'file-write': 'high',
+  'file-read': 'medium',
+  other: 'low',
+}
+
+/** Webhooks that do not answer in time are abandoned */
+const WEBHOOK_TIMEOUT_MS = 5000
+
+/** Violations recorded this long after the first are sent with it */
+export const NOTIFICATION_BATCH_DELAY_MS = 500
+
+/** Most notifications sent in one batch */
+const MAX_BATCH_SIZE = 100
+
+/** Most notifications waiting to be sent; later ones are dropped */
+const MAX_PENDING_NOTIFICATIONS = 1000
+
+/** Most batches being sent at once, per webhook or command */
+const MAX_CONCURRENT_BATCHES = 2
+
+export interface ViolationNotification {
+  severity: ViolationSeverity
+  category: ViolationCategory
+  violation: SandboxViolationEvent
+}
+
+/**
+ * What the webhook and command are sent
+ */
+export interface ViolationNotificationBatch {
+  /** The highest severity in the batch */
+  severity: ViolationSeverity
+  notifications: ViolationNotification[]
+  /** Violations left out since the previous batch because too many queued */
+  dropped: number
+}
+
+/**
+ * The notification for a violation, or undefined when it is below minSeverity
+ */
+export function getViolationNotification(
+  violation: SandboxViolationEvent,
+  minSeverity: ViolationSeverity = 'low',
+): ViolationNotification | undefined {
+  const category = classifyViolation(violation)
+  if (!category) {
+    return undefined
+  }
+  const severity = CATEGORY_SEVERITY[category]
+  if (SEVERITY_RANK[severity] < SEVERITY_RANK[minSeverity]) {
+    return undefined
+  }
+  return { severity, category, violation }
+}
+
+async function sendWebhook(
+  webhook: NonNullable<NotificationsConfig['webhook']>,
+  batch: ViolationNotificationBatch,
+): Promise<void> {
+  const response = await fetch(webhook.url, {
+    method: 'POST',
+    headers: { 'content-type': 'application/json', ...webhook.headers },
+    body: JSON.stringify(batch),
+    signal: AbortSignal.timeout(WEBHOOK_TIMEOUT_MS),
+  })
+  if (!response.ok) {
+    throw new Error(`HTTP ${response.status}`)
+  }
+}
+
+function runHook(
+  command: string,
+  batch: ViolationNotificationBatch,
+): Promise<void> {
+  // The variables describe the first of the most severe violations
+  const first = batch.notifications.find(
+    notification => notification.severity === batch.severity,
+  )!
+  return new Promise((resolve, reject) => {
+    const child = spawn('/bin/sh', ['-c', command], {
+      stdio: ['pipe', 'ignore', 'inherit'],
+      env: {
+        ...process.env,
+        SRT_VIOLATION_SEVERITY: batch.severity,
+        SRT_VIOLATION_CATEGORY: first.category,
+        SRT_VIOLATION_LINE: first.violation.line,
+        SRT_VIOLATION_COUNT: String(batch.notifications.length),
+      },
+    })
+    child.on('error', reject)
+    child.on('close', code =>
+      code === 0 ? resolve() : reject(new Error(`exit code ${code}`)),
+    )
+    // The hook may exit without reading its input
+    child.stdin.on('error', () => {})
+    child.stdin.end(JSON.stringify(batch) + '\n')
+  })
+}
+
+/**
+ * Batches notifications for one target and sends them with bounded
+ * concurrency
+ */
+class NotificationQueue {
+  private pending: ViolationNotification[] = []
+  private dropped = 0
+  private sending = 0
+  private timer: ReturnType<typeof setTimeout> | undefined
+
+  constructor(
+    private readonly send: (batch: ViolationNotificationBatch) => Promise<void>,
+    private readonly onError: (error: unknown) => void,
+  ) {}
+
+  add(notification: ViolationNotification): void {
+    if (this.pending.length >= MAX_PENDING_NOTIFICATIONS) {
+      this.dropped++
+      return
+    }
+    this.pending.push(notification)
+    this.schedule()
+  }
+
+  /** Send what is pending now, without waiting for the delay */
+  flush(): void {
+    clearTimeout(this.timer)
+    this.timer = undefined
+    while (this.pending.length > 0) {
+      this.sendBatch()
+    }
+  }
+
+  private schedule(): void {
+    if (
+      this.timer ||
+      this.pending.length === 0 ||
+      this.sending >= MAX_CONCURRENT_BATCHES
+    ) {
+      return
+    }
+    this.timer = setTimeout(() => {
+      this.timer = undefined
+      this.sendBatch()
+      this.schedule()
+    }, NOTIFICATION_BATCH_DELAY_MS)
+    this.timer.unref?.()
+  }
+
+  private sendBatch(): void {
+    const notifications = this.pending.splice(0, MAX_BATCH_SIZE)
+    const severity = notifications.reduce<ViolationSeverity>(
+      (highest, { severity }) =>
+        SEVERITY_RANK[severity] > SEVERITY_RANK[highest] ? severity : highest,
+      'low',
+    )
+    const batch = { severity, notifications, dropped: this.dropped }
+    this.dropped = 0
+    this.sending++
+    this.send(batch)
+      .catch(this.onError)
+      .finally(() => {
+        this.sending--
+        this.schedule()
+      })
+  }
+}
+
+/**
+ * Notify the configured webhook and hook of the violations added to the
+ * store, in batches
+ * Returns a function that stops notifying and sends what is still pending
+ */
+export function startViolationNotifier(
+  store: SandboxViolationStore,
+  notifications: NotificationsConfig,
+): () => void {
+  const { webhook, command, minSeverity } = notifications
+  const controller = new AbortController()
+
+  const queues: NotificationQueue[] = []
+  if (webhook) {
+    queues.push(
+      new NotificationQueue(
+        batch => sendWebhook(webhook, batch),
+        error =>
+          logForDebugging(`Sandbox notification webhook failed: ${error}`, {
+            level: 'error',
+          }),
+      ),
+    )
+  }
+  if (command) {
+    queues.push(
+      new NotificationQueue(
+        batch => runHook(command, batch),
+        error =>
+          logForDebugging(`Sandbox notification command failed: ${error}`, {
+            level: 'error',
+          }),
+      ),
+    )
+  }
+
+  void (async () => {
+    for await (const violation of store.stream({
+      signal: controller.signal,
+    })) {
+      const notification = getViolationNotification(violation, minSeverity)
+      if (notification) {
+        for (const queue of queues) {
+          queue.add(notification)
+        }
+      }
+    }
+  })()
+
+  return () => {
+    controller.abort()
+    for (const queue of queues) {
+      queue.flush()
+    }
+  }
+}
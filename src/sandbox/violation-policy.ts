@@ -0,0 +1,88 @@
+import {
+  parseMacOSViolationDetails,
+  type SandboxViolationEvent,
+} from './macos-sandbox-utils.js'
+import type { ViolationPolicyConfig } from './sandbox-config.js'
+
+/**
+ * What happens when a running command causes a violation
+ *
+ * The sandbox has already blocked the operation by the time a violation is
+ * reported, so every action records it. 'warn' also prints it to stderr, and
+ * 'kill' terminates the command that caused it.
+ */
+
+export type ViolationCategory = 'network' | 'file-read' | 'file-write' | 'other'
+
+export const VIOLATION_CATEGORIES: readonly ViolationCategory[] = [
+  'network',
+  'file-read',
+  'file-write',
+  'other',
+]
+
+export type ViolationAction = 'record' | 'warn' | 'kill'
+
+/** Linux denial lines are "deny(<errno>) <syscall> <target>" */
+const LINUX_DENIAL_REGEX = /^deny\(E[A-Z]+\)\s+(\w+)/
+
+const LINUX_NETWORK_SYSCALLS = new Set(['connect', 'sendto', 'sendmsg'])
+
+/**
+ * Classify a violation for its policy, or undefined for audited accesses,
+ * which were allowed
+ */
+export function classifyViolation(
+  violation: SandboxViolationEvent,
+): ViolationCategory | undefined {
+  if (violation.type === 'file-access') {
+    return undefined
+  }
+  if (violation.type === 'network') {
+    return 'network'
+  }
+
+  const macos = parseMacOSViolationDetails(violation.line)
+  if (macos) {
+    const { operation } = macos
+    if (operation.startsWith('network')) {
+      return 'network'
+    }
+    if (operation.startsWith('file-read')) {
+      return 'file-read'
+    }
+    return operation.startsWith('file-write') ? 'file-write' : 'other'
+  }
+
+  if (violation.access) {
+    return violation.access === 'write' ? 'file-write' : 'file-read'
+  }
+  const syscall = LINUX_DENIAL_REGEX.exec(violation.line)?.[1]
+  if (syscall && LINUX_NETWORK_SYSCALLS.has(syscall)) {
+    return 'network'
+  }
+  return 'other'
+}
+
+/**
+ * The configured action for a violation ('record' when none applies)
+ */
+export function getViolationAction(
+  violation: SandboxViolationEvent,
+  policy: ViolationPolicyConfig | undefined,
+): ViolationAction {
+  const category = classifyViolation(violation)
+  if (!category || !policy) {
+    return 'record'
+  }
+  return policy[category] ?? 'record'
+}
+
+/**
+ * Whether any category of the policy kills the command
+ */
+export function policyMayKill(
+  policy: ViolationPolicyConfig | undefined,
+): boolean {
+  return Object.values(policy ?? {}).includes('kill')
+}
@@ -0,0 +1,151 @@
+import type { SandboxViolationEvent } from './macos-sandbox-utils.js'
+import {
+  classifyViolation,
+  type ViolationCategory,
+} from './violation-policy.js'
+
+/**
+ * End-of-run summaries of violations, with the config that would allow them
+ */
+
+/** Proxy denials that allowing the domain would fix */
+const ALLOWABLE_NETWORK_SUBTYPES = new Set([
+  undefined,
+  'not-allowed',
+  'user-denied',
+])
+
+export interface ViolationSummary {
+  /** Denials counted (audited accesses are left out) */
+  total: number
+  counts: Record<ViolationCategory, number>
+  topBlockedDomains: { host: string; count: number }[]
+  topDeniedPaths: { path: string; count: number }[]
+  /** Config entries that would allow the denials; review before applying */
+  suggestedConfig: {
+    network?: { allowedDomains: string[] }
+    filesystem?: { allowWrite: string[] }
+  }
+}
+
+function topEntries(counts: Map<string, number>, top: number) {
+  return [...counts]
+    .sort(([a, countA], [b, countB]) => countB - countA || a.localeCompare(b))
+    .slice(0, top)
+}
+
+/**
+ * Accumulates violations as they occur, holding counts rather than events
+ */
+export class ViolationSummaryBuilder {
+  private readonly counts: Record<ViolationCategory, number> = {
+    network: 0,
+    'file-read': 0,
+    'file-write': 0,
+    other: 0,
+  }
+  private readonly domains = new Map<string, number>()
+  private readonly paths = new Map<string, number>()
+  private readonly allowableDomains = new Set<string>()
+  private readonly allowableWrites = new Set<string>()
+
+  add(violation: SandboxViolationEvent): void {
+    const category = classifyViolation(violation)
+    if (!category) {
+      return
+    }
+    this.counts[category]++
+
+    if (violation.host !== undefined) {
+      const { host } = violation
+      this.domains.set(host, (this.domains.get(host) ?? 0) + 1)
+      if (ALLOWABLE_NETWORK_SUBTYPES.has(violation.subtype)) {
+        this.allowableDomains.add(host)
+      }
+    }
+    if (
+      violation.path !== undefined &&
+      (category === 'file-read' || category === 'file-write')
+    ) {
+      const { path } = violation
+      this.paths.set(path, (this.paths.get(path) ?? 0) + 1)
+      if (category === 'file-write') {
+        this.allowableWrites.add(path)
+      }
+    }
+  }
+
+  build(top = 5): ViolationSummary {
+    const suggestedConfig: ViolationSummary['suggestedConfig'] = {}
+    if (this.allowableDomains.size > 0) {
+      suggestedConfig.network = {
+        allowedDomains: [...this.allowableDomains].sort(),
+      }
+    }
+    if (this.allowableWrites.size > 0) {
+      suggestedConfig.filesystem = {
+        allowWrite: [...this.allowableWrites].sort(),
+      }
+    }
+    return {
+      total: Object.values(this.counts).reduce((sum, n) => sum + n, 0),
+      counts: { ...this.counts },
+      topBlockedDomains: topEntries(this.domains, top).map(([host, count]) => ({
+        host,
+        count,
+      })),
+      topDeniedPaths: topEntries(this.paths, top).map(([path, count]) => ({
+        path,
+        count,
+      })),
+      suggestedConfig,
+    }
+  }
+}
+
+/**
+ * Summarize a list of violations
+ */
+export function summarizeViolations(
+  violations: Iterable<SandboxViolationEvent>,
+  top = 5,
+): ViolationSummary {
+  const builder = new ViolationSummaryBuilder()
+  for (const violation of violations) {
+    builder.add(violation)
+  }
+  return builder.build(top)
+}
+
+/**
+ * Format a summary for the terminal
+ */
+export function formatViolationSummary(summary: ViolationSummary): string {
+  const { counts } = summary
+  const lines = [
+    `Sandbox violations: ${summary.total} (network: ${counts.network}, ` +
+      `file-read: ${counts['file-read']}, file-write: ${counts['file-write']}, ` +
+      `other: ${counts.other})`,
+  ]
+  if (summary.topBlockedDomains.length > 0) {
+    lines.push('Top blocked domains:')
+    for (const { host, count } of summary.topBlockedDomains) {
+      lines.push(`  ${String(count).padStart(5)}  ${host}`)
+    }
+  }
+  if (summary.topDeniedPaths.length > 0) {
+    lines.push('Top denied paths:')
+    for (const { path, count } of summary.topDeniedPaths) {
+      lines.push(`  ${String(count).padStart(5)}  ${path}`)
+    }
+  }
+  if (Object.keys(summary.suggestedConfig).length > 0) {
+    lines.push('Suggested config additions (review before applying):')
+    lines.push(
+      ...JSON.stringify(summary.suggestedConfig, null, 2)
+        .split('\n')
+        .map(line => `  ${line}`),
+    )
+  }
+  return lines.join('\n')
+}
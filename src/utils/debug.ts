@@ -1,3 +1,14 @@
+let redactMessage: ((message: string) => string) | undefined
+
+/**
+ * Redact secrets in debug messages (set when the sandbox is initialized)
+ */
+export function setDebugRedactor(
+  redactor: ((message: string) => string) | undefined,
+): void {
+  redactMessage = redactor
+}
+
 /**
  * Simple debug logging for standalone sandbox
  */
@@ -14,6 +25,7 @@ export function logForDebugging(
 
   const level = options?.level || 'info'
   const prefix = '[SandboxDebug]'
+  message = redactMessage ? redactMessage(message) : message
 
   // Always use stderr to avoid corrupting stdout JSON streams
   switch (level) {
@@ -2,7 +2,7 @@
  * Platform detection utilities
  */
 
-export type Platform = 'macos' | 'linux' | 'windows' | 'unknown'
+export type Platform = 'macos' | 'linux' | 'openbsd' | 'windows' | 'unknown'
 
 export function getPlatform(): Platform {
   switch (process.platform) {
@@ -10,6 +10,8 @@ export function getPlatform(): Platform {
       return 'macos'
     case 'linux':
       return 'linux'
+    case 'openbsd':
+      return 'openbsd'
     case 'win32':
       return 'windows'
     default:
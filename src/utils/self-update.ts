@@ -0,0 +1,427 @@
+import { spawnSync } from 'node:child_process'
+import { createHash, verify as verifySignature } from 'node:crypto'
+import * as fs from 'node:fs'
+import { homedir, tmpdir } from 'node:os'
+import { dirname, join } from 'node:path'
+import { get as httpsGet } from 'node:https'
+import { fileURLToPath, URL } from 'node:url'
+import { logForDebugging } from './debug.js'
+
+export const PACKAGE_NAME = '@anthropic-ai/sandbox-runtime'
+
+const REGISTRY_URL = 'https://registry.npmjs.org'
+
+/**
+ * Release channels map onto npm dist-tags
+ */
+export type ReleaseChannel = 'stable' | 'beta'
+
+const CHANNEL_DIST_TAGS: Record<ReleaseChannel, string> = {
+  stable: 'latest',
+  beta: 'beta',
+}
+
+/**
+ * Registry signature entry from the packument `dist.signatures` field
+ */
+export interface RegistrySignature {
+  keyid: string
+  sig: string
+}
+
+/**
+ * Registry public key from /-/npm/v1/keys
+ */
+export interface RegistryKey {
+  keyid: string
+  key: string
+  expires: string | null
+}
+
+/**
+ * Persisted update state, used for rollback
+ */
+interface UpdateState {
+  channel: ReleaseChannel
+  currentVersion: string
+  previousVersion?: string
+}
+
+/**
+ * The parts of the registry packument used for updates
+ */
+interface Packument {
+  'dist-tags': Record<string, string>
+  versions: Record<
+    string,
+    {
+      dist: {
+        tarball: string
+        integrity: string
+        signatures?: RegistrySignature[]
+      }
+    }
+  >
+  time: Record<string, string>
+}
+
+export interface SelfUpdateOptions {
+  /** Install the channel's release even when it is older than the current one */
+  allowDowngrade?: boolean
+}
+
+export interface SelfUpdateResult {
+  previousVersion: string
+  installedVersion: string
+  updated: boolean
+}
+
+function getUpdateStatePath(): string {
+  return join(homedir(), '.srt', 'update-state.json')
+}
+
+function readUpdateState(): UpdateState | undefined {
+  try {
+    return JSON.parse(fs.readFileSync(getUpdateStatePath(), 'utf-8'))
+  } catch {
+    return undefined
+  }
+}
+
+function writeUpdateState(state: UpdateState): void {
+  const statePath = getUpdateStatePath()
+  fs.mkdirSync(dirname(statePath), { recursive: true })
+  fs.writeFileSync(statePath, JSON.stringify(state, null, 2) + '\n')
+}
+
+function runNpm(args: string[]): string {
+  const result = spawnSync('npm', args, { encoding: 'utf8' })
+  if (result.error) {
+    throw new Error(`Failed to run npm: ${result.error.message}`)
+  }
+  if (result.status !== 0) {
+    throw new Error(
+      `npm ${args.join(' ')} failed with exit code ${result.status}: ${result.stderr.trim()}`,
+    )
+  }
+  return result.stdout
+}
+
+/**
+ * Download a URL over HTTPS, following redirects
+ */
+function download(url: string, redirectsLeft = 5): Promise<Buffer> {
+  return new Promise((resolve, reject) => {
+    httpsGet(url, res => {
+      const { statusCode = 0, headers } = res
+      if (statusCode >= 300 && statusCode < 400 && headers.location) {
+        res.resume()
+        if (redirectsLeft === 0) {
+          reject(new Error(`Too many redirects fetching ${url}`))
+          return
+        }
+        const nextUrl = new URL(headers.location, url)
+        if (nextUrl.protocol !== 'https:') {
+          reject(new Error(`Refusing non-HTTPS redirect to ${nextUrl}`))
+          return
+        }
+        download(nextUrl.toString(), redirectsLeft - 1).then(resolve, reject)
+        return
+      }
+      if (statusCode !== 200) {
+        res.resume()
+        reject(new Error(`Failed to fetch ${url}: HTTP ${statusCode}`))
+        return
+      }
+      const chunks: Buffer[] = []
+      res.on('data', (chunk: Buffer) => chunks.push(chunk))
+      res.on('end', () => resolve(Buffer.concat(chunks)))
+      res.on('error', reject)
+    }).on('error', reject)
+  })
+}
+
+/**
+ * Fetch the package metadata
+ * Releases and the keys that sign them come from the same registry, whatever
+ * registry npm itself is configured with.
+ */
+async function fetchPackument(): Promise<Packument> {
+  const url = `${REGISTRY_URL}/${PACKAGE_NAME.replace('/', '%2f')}`
+  return JSON.parse((await download(url)).toString('utf-8')) as Packument
+}
+
+/**
+ * Get the version of the currently running package
+ * Resolved relative to this module (dist/utils/ or src/utils/)
+ */
+export function getInstalledVersion(): string {
+  const packageJsonPath = join(
+    dirname(fileURLToPath(import.meta.url)),
+    '..',
+    '..',
+    'package.json',
+  )
+  const packageJson = JSON.parse(fs.readFileSync(packageJsonPath, 'utf-8'))
+  return packageJson.version
+}
+
+/**
+ * Resolve the version currently published on a release channel
+ */
+export async function getChannelVersion(
+  channel: ReleaseChannel,
+): Promise<string> {
+  return getPackumentChannelVersion(await fetchPackument(), channel)
+}
+
+function getPackumentChannelVersion(
+  packument: Packument,
+  channel: ReleaseChannel,
+): string {
+  const version = packument['dist-tags'][CHANNEL_DIST_TAGS[channel]]
+  if (!version) {
+    throw new Error(`No release published on the '${channel}' channel`)
+  }
+  return version
+}
+
+/**
+ * Compare two semver versions by precedence
+ * @returns A negative number when a is older than b, 0 when equal, positive otherwise
+ */
+export function compareVersions(a: string, b: string): number {
+  const parse = (version: string) => {
+    const [core, prerelease] = version.replace(/\+.*$/, '').split(/-(.*)/s)
+    return {
+      core: core!.split('.').map(Number),
+      prerelease: prerelease ? prerelease.split('.') : [],
+    }
+  }
+  const left = parse(a)
+  const right = parse(b)
+
+  for (let i = 0; i < 3; i++) {
+    const diff = (left.core[i] ?? 0) - (right.core[i] ?? 0)
+    if (diff !== 0) {
+      return diff
+    }
+  }
+
+  // A release is newer than its prereleases
+  if (left.prerelease.length === 0 || right.prerelease.length === 0) {
+    return right.prerelease.length - left.prerelease.length
+  }
+  for (
+    let i = 0;
+    i < Math.min(left.prerelease.length, right.prerelease.length);
+    i++
+  ) {
+    const l = left.prerelease[i]!
+    const r = right.prerelease[i]!
+    if (l === r) {
+      continue
+    }
+    const lNumeric = /^\d+$/.test(l)
+    const rNumeric = /^\d+$/.test(r)
+    if (lNumeric && rNumeric) {
+      return Number(l) - Number(r)
+    }
+    // Numeric identifiers sort before alphanumeric ones
+    if (lNumeric !== rNumeric) {
+      return lNumeric ? -1 : 1
+    }
+    return l < r ? -1 : 1
+  }
+  return left.prerelease.length - right.prerelease.length
+}
+
+/**
+ * Compute a Subresource Integrity string (sha512) for a tarball
+ */
+export function computeIntegrity(data: Buffer): string {
+  return 'sha512-' + createHash('sha512').update(data).digest('base64')
+}
+
+/**
+ * Verify an npm registry ECDSA signature over `${name}@${version}:${integrity}`
+ * Returns true if at least one signature verifies against a registry key that
+ * had not expired when the version was published. Keys are rotated, so
+ * releases signed before an expiry stay valid after it.
+ */
+export function verifyRegistrySignature(
+  name: string,
+  version: string,
+  integrity: string,
+  signatures: RegistrySignature[],
+  keys: RegistryKey[],
+  publishedAt: Date,
+): boolean {
+  const message = Buffer.from(`${name}@${version}:${integrity}`)
+
+  for (const signature of signatures) {
+    const key = keys.find(k => k.keyid === signature.keyid)
+    if (!key) {
+      logForDebugging(
+        `[SelfUpdate] No registry key found for keyid ${signature.keyid}`,
+        { level: 'warn' },
+      )
+      continue
+    }
+    if (key.expires !== null && new Date(key.expires) <= publishedAt) {
+      logForDebugging(
+        `[SelfUpdate] Registry key ${key.keyid} had expired when ${version} was published`,
+        { level: 'warn' },
+      )
+      continue
+    }
+
+    try {
+      const valid = verifySignature(
+        'sha256',
+        message,
+        { key: Buffer.from(key.key, 'base64'), format: 'der', type: 'spki' },
+        Buffer.from(signature.sig, 'base64'),
+      )
+      if (valid) {
+        return true
+      }
+    } catch (error) {
+      logForDebugging(`[SelfUpdate] Signature verification error: ${error}`, {
+        level: 'error',
+      })
+    }
+  }
+
+  return false
+}
+
+/**
+ * Download a release tarball and verify its integrity and registry signature
+ * Returns the path of the verified tarball in a temporary directory
+ */
+async function downloadVerifiedRelease(
+  packument: Packument,
+  version: string,
+): Promise<string> {
+  const dist = packument.versions[version]?.dist
+  const published = packument.time[version]
+  if (!dist || !published) {
+    throw new Error(`Release ${version} is not published`)
+  }
+
+  if (!dist.signatures || dist.signatures.length === 0) {
+    throw new Error(`Release ${version} has no registry signatures`)
+  }
+
+  const { keys } = JSON.parse(
+    (await download(`${REGISTRY_URL}/-/npm/v1/keys`)).toString('utf-8'),
+  ) as { keys: RegistryKey[] }
+
+  if (
+    !verifyRegistrySignature(
+      PACKAGE_NAME,
+      version,
+      dist.integrity,
+      dist.signatures,
+      keys,
+      new Date(published),
+    )
+  ) {
+    throw new Error(`Registry signature verification failed for ${version}`)
+  }
+
+  const tarball = await download(dist.tarball)
+
+  // The signature covers the integrity string, so the tarball must match it exactly
+  if (computeIntegrity(tarball) !== dist.integrity) {
+    throw new Error(`Integrity mismatch for downloaded release ${version}`)
+  }
+
+  const downloadDir = fs.mkdtempSync(join(tmpdir(), 'srt-update-'))
+  const tarballPath = join(downloadDir, `sandbox-runtime-${version}.tgz`)
+  fs.writeFileSync(tarballPath, tarball)
+  logForDebugging(`[SelfUpdate] Verified release ${version} at ${tarballPath}`)
+  return tarballPath
+}
+
+async function installVersion(
+  packument: Packument,
+  version: string,
+): Promise<void> {
+  const tarballPath = await downloadVerifiedRelease(packument, version)
+  try {
+    runNpm(['install', '--global', tarballPath])
+  } finally {
+    fs.rmSync(dirname(tarballPath), { recursive: true, force: true })
+  }
+}
+
+/**
+ * Update the globally installed CLI to the latest release on a channel
+ * Only installs artifacts whose registry signature and integrity verify, and
+ * refuses older releases (e.g. a stale mirror or a switch from beta to
+ * stable) unless allowDowngrade is set.
+ */
+export async function selfUpdate(
+  channel: ReleaseChannel,
+  options: SelfUpdateOptions = {},
+): Promise<SelfUpdateResult> {
+  const currentVersion = getInstalledVersion()
+  const packument = await fetchPackument()
+  const targetVersion = getPackumentChannelVersion(packument, channel)
+
+  if (targetVersion === currentVersion) {
+    return {
+      previousVersion: currentVersion,
+      installedVersion: currentVersion,
+      updated: false,
+    }
+  }
+
+  if (
+    compareVersions(targetVersion, currentVersion) < 0 &&
+    !options.allowDowngrade
+  ) {
+    throw new Error(
+      `The ${channel} release ${targetVersion} is older than the installed ${currentVersion}; ` +
+        'pass --allow-downgrade to install it anyway',
+    )
+  }
+
+  await installVersion(packument, targetVersion)
+  writeUpdateState({
+    channel,
+    currentVersion: targetVersion,
+    previousVersion: currentVersion,
+  })
+
+  return {
+    previousVersion: currentVersion,
+    installedVersion: targetVersion,
+    updated: true,
+  }
+}
+
+/**
+ * Reinstall the version that was active before the last self-update
+ */
+export async function rollbackSelfUpdate(): Promise<SelfUpdateResult> {
+  const state = readUpdateState()
+  if (!state?.previousVersion) {
+    throw new Error('No previous version recorded, nothing to roll back to')
+  }
+
+  const currentVersion = getInstalledVersion()
+  await installVersion(await fetchPackument(), state.previousVersion)
+  writeUpdateState({
+    channel: state.channel,
+    currentVersion: state.previousVersion,
+  })
+
+  return {
+    previousVersion: currentVersion,
+    installedVersion: state.previousVersion,
+    updated: true,
+  }
+}
@@ -1,5 +1,7 @@
 import { describe, test, expect } from 'bun:test'
 import { spawnSync } from 'node:child_process'
+import { mkdtempSync, writeFileSync } from 'node:fs'
+import { tmpdir } from 'node:os'
 import { join } from 'node:path'
 
 /**
@@ -98,6 +100,13 @@ describe('CLI', () => {
       expect(result.stdout).toBe('no newline')
       expect(result.status).toBe(0)
     })
+
+    test('passes flags srt also has to the command', () => {
+      const result = runCli(['printf', '%s|', '-e', 'x', '-w', 'y', '-d'])
+      expect(result.stdout).toBe('-e|x|-w|y|-d|')
+      expect(result.stderr).not.toContain('[SandboxDebug]')
+      expect(result.status).toBe(0)
+    })
   })
 
   describe('error handling', () => {
@@ -136,4 +145,18 @@ describe('CLI', () => {
       expect(result.status).toBe(0)
     })
   })
+
+  describe('--env-file', () => {
+    test('sets the variables without putting the values in the command', () => {
+      const envFile = join(mkdtempSync(join(tmpdir(), 'srt-cli-')), '.env')
+      writeFileSync(envFile, 'CLI_TEST_TOKEN=s3cret-value\n')
+      const result = runCli(
+        ['--env-file', envFile, '-c', 'echo "$CLI_TEST_TOKEN"'],
+        { debug: true },
+      )
+      expect(result.stdout.trim()).toBe('s3cret-value')
+      expect(result.stderr).not.toContain('s3cret-value')
+      expect(result.status).toBe(0)
+    })
+  })
 })
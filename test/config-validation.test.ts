@@ -95,6 +95,9 @@ describe('Config Validation', () => {
         allowUnixSockets: ['/var/run/docker.sock'],
         allowAllUnixSockets: false,
         allowLocalBinding: true,
+        allowPrivateRanges: false,
+        allowCloudMetadata: false,
+        allowSmtp: false,
       },
       filesystem: {
         denyRead: ['/etc/shadow'],
@@ -225,4 +228,176 @@ describe('Config Validation', () => {
       expect(result.data.ripgrep).toBeUndefined()
     }
   })
+
+  test('should validate config with resource limits', () => {
+    const config = {
+      network: { allowedDomains: [], deniedDomains: [] },
+      filesystem: { denyRead: [], allowWrite: [], denyWrite: [] },
+      resources: {
+        cpus: 1.5,
+        memoryMb: 2048,
+        pids: 256,
+      },
+    }
+
+    const result = SandboxRuntimeConfigSchema.safeParse(config)
+    expect(result.success).toBe(true)
+  })
+
+  test('should reject non-positive resource limits', () => {
+    for (const resources of [{ cpus: 0 }, { memoryMb: -1 }, { pids: 1.5 }]) {
+      const config = {
+        network: { allowedDomains: [], deniedDomains: [] },
+        filesystem: { denyRead: [], allowWrite: [], denyWrite: [] },
+        resources,
+      }
+      const result = SandboxRuntimeConfigSchema.safeParse(config)
+      expect(result.success).toBe(false)
+    }
+  })
+
+  test('should validate linux filesystem backend values', () => {
+    for (const backend of ['bwrap', 'landlock', 'auto']) {
+      const config = {
+        network: { allowedDomains: [], deniedDomains: [] },
+        filesystem: { denyRead: [], allowWrite: [], denyWrite: [] },
+        linuxFilesystemBackend: backend,
+      }
+      const result = SandboxRuntimeConfigSchema.safeParse(config)
+      expect(result.success).toBe(true)
+    }
+
+    const result = SandboxRuntimeConfigSchema.safeParse({
+      network: { allowedDomains: [], deniedDomains: [] },
+      filesystem: { denyRead: [], allowWrite: [], denyWrite: [] },
+      linuxFilesystemBackend: 'apparmor',
+    })
+    expect(result.success).toBe(false)
+  })
+
+  test('should validate seccomp profile and syscall names', () => {
+    const base = {
+      network: { allowedDomains: [], deniedDomains: [] },
+      filesystem: { denyRead: [], allowWrite: [], denyWrite: [] },
+    }
+
+    const valid = SandboxRuntimeConfigSchema.safeParse({
+      ...base,
+      seccomp: { profile: 'strict', deny: ['ptrace', 'mount', 'keyctl'] },
+    })
+    expect(valid.success).toBe(true)
+
+    const unknownSyscall = SandboxRuntimeConfigSchema.safeParse({
+      ...base,
+      seccomp: { deny: ['not_a_syscall'] },
+    })
+    expect(unknownSyscall.success).toBe(false)
+
+    const unknownProfile = SandboxRuntimeConfigSchema.safeParse({
+      ...base,
+      seccomp: { profile: 'paranoid' },
+    })
+    expect(unknownProfile.success).toBe(false)
+  })
+
+  test('should accept allowDegradedSandbox', () => {
+    const result = SandboxRuntimeConfigSchema.safeParse({
+      network: { allowedDomains: [], deniedDomains: [] },
+      filesystem: { denyRead: [], allowWrite: [], denyWrite: [] },
+      allowDegradedSandbox: true,
+    })
+    expect(result.success).toBe(true)
+  })
+
+  test('should validate capability names', () => {
+    const base = {
+      network: { allowedDomains: [], deniedDomains: [] },
+      filesystem: { denyRead: [], allowWrite: [], denyWrite: [] },
+    }
+
+    const valid = SandboxRuntimeConfigSchema.safeParse({
+      ...base,
+      capabilities: ['CAP_NET_BIND_SERVICE', 'CAP_CHOWN'],
+    })
+    expect(valid.success).toBe(true)
+
+    const unknown = SandboxRuntimeConfigSchema.safeParse({
+      ...base,
+      capabilities: ['CAP_EVERYTHING'],
+    })
+    expect(unknown.success).toBe(false)
+  })
+
+  test('should validate rlimits', () => {
+    const base = {
+      network: { allowedDomains: [], deniedDomains: [] },
+      filesystem: { denyRead: [], allowWrite: [], denyWrite: [] },
+    }
+
+    const valid = SandboxRuntimeConfigSchema.safeParse({
+      ...base,
+      limits: { nofile: 1024, fsizeMb: 100, cpuSeconds: 60, coreMb: 0 },
+    })
+    expect(valid.success).toBe(true)
+
+    const negative = SandboxRuntimeConfigSchema.safeParse({
+      ...base,
+      limits: { nofile: -1 },
+    })
+    expect(negative.success).toBe(false)
+  })
+
+  test('should validate tmpfs options', () => {
+    const network = { allowedDomains: [], deniedDomains: [] }
+    const filesystem = { denyRead: [], allowWrite: [], denyWrite: [] }
+
+    const valid = SandboxRuntimeConfigSchema.safeParse({
+      network,
+      filesystem: { ...filesystem, tmpfsSizeMB: 256, extraTmpfs: ['/tmp'] },
+    })
+    expect(valid.success).toBe(true)
+
+    const invalidSize = SandboxRuntimeConfigSchema.safeParse({
+      network,
+      filesystem: { ...filesystem, tmpfsSizeMB: 0 },
+    })
+    expect(invalidSize.success).toBe(false)
+  })
+
+  test('should validate mount specs', () => {
+    const network = { allowedDomains: [], deniedDomains: [] }
+    const filesystem = { denyRead: [], allowWrite: [], denyWrite: [] }
+    const parse = (mounts: string[]) =>
+      SandboxRuntimeConfigSchema.safeParse({
+        network,
+        filesystem: { ...filesystem, mounts },
+      }).success
+
+    expect(parse(['/data/cache:/cache', '~/models:/models:rw'])).toBe(true)
+    expect(parse(['/data/cache:cache'])).toBe(false)
+    expect(parse(['/data/cache:/cache:rx'])).toBe(false)
+  })
+
+  test('should validate desktop options', () => {
+    const base = {
+      network: { allowedDomains: [], deniedDomains: [] },
+      filesystem: { denyRead: [], allowWrite: [], denyWrite: [] },
+    }
+
+    const valid = SandboxRuntimeConfigSchema.safeParse({
+      ...base,
+      desktop: {
+        x11: true,
+        dbus: 'proxy',
+        dbusTalk: ['org.freedesktop.Notifications'],
+      },
+    })
+    expect(valid.success).toBe(true)
+
+    const unknownMode = SandboxRuntimeConfigSchema.safeParse({
+      ...base,
+      desktop: { dbus: 'session' },
+    })
+    expect(unknownMode.success).toBe(false)
+  })
 })
@@ -0,0 +1,68 @@
+import { describe, it, expect } from 'bun:test'
+import {
+  applyAllowListExclusions,
+  splitAllowList,
+} from '../../src/sandbox/allow-list-exclusions.js'
+import { applyCommandPolicy } from '../../src/sandbox/command-policy.js'
+import { SandboxRuntimeConfigSchema } from '../../src/sandbox/sandbox-config.js'
+
+function parseConfig(allowedDomains: string[], allowWrite: string[]) {
+  return SandboxRuntimeConfigSchema.parse({
+    network: { allowedDomains, deniedDomains: ['evil.com'] },
+    filesystem: { denyRead: [], allowWrite, denyWrite: ['./.git'] },
+  })
+}
+
+describe('splitAllowList', () => {
+  it('separates grants from exclusions', () => {
+    expect(splitAllowList(['.', '!./.git', 'src/', '!./secrets'])).toEqual({
+      allow: ['.', 'src/'],
+      exclude: ['./.git', './secrets'],
+    })
+  })
+})
+
+describe('applyAllowListExclusions', () => {
+  it('moves exclusions to the deny lists', () => {
+    const config = parseConfig(
+      ['*.example.com', '!admin.example.com'],
+      ['.', '!./.git', '!./secrets'],
+    )
+    const applied = applyAllowListExclusions(config)
+    expect(applied.network.allowedDomains).toEqual(['*.example.com'])
+    expect(applied.network.deniedDomains).toEqual([
+      'evil.com',
+      'admin.example.com',
+    ])
+    expect(applied.filesystem.allowWrite).toEqual(['.'])
+    expect(applied.filesystem.denyWrite).toEqual(['./.git', './secrets'])
+  })
+
+  it('returns configs without exclusions unchanged', () => {
+    const config = parseConfig(['example.com'], ['.'])
+    expect(applyAllowListExclusions(config)).toBe(config)
+  })
+
+  it('applies to the lists a command policy adds', () => {
+    const config = parseConfig(['example.com'], ['.'])
+    const applied = applyCommandPolicy(config, {
+      allowedDomains: ['*.npmjs.org', '!private.npmjs.org'],
+      allowWrite: ['.', '!./node_modules/.bin'],
+    })
+    expect(applied.network.allowedDomains).toEqual([
+      'example.com',
+      '*.npmjs.org',
+    ])
+    expect(applied.network.deniedDomains).toContain('private.npmjs.org')
+    expect(applied.filesystem.allowWrite).toEqual(['.'])
+    expect(applied.filesystem.denyWrite).toContain('./node_modules/.bin')
+  })
+})
+
+describe('allow list entry validation', () => {
+  it('validates the pattern after the !', () => {
+    expect(() => parseConfig(['!admin.example.com'], ['!./.git'])).not.toThrow()
+    expect(() => parseConfig(['!*.com'], [])).toThrow()
+    expect(() => parseConfig(['!'], [])).toThrow()
+  })
+})
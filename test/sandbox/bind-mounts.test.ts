@@ -0,0 +1,98 @@
+import { describe, it, expect, beforeAll, afterAll } from 'bun:test'
+import { mkdirSync, rmSync } from 'node:fs'
+import { tmpdir } from 'node:os'
+import { join } from 'node:path'
+import { getPlatform } from '../../src/utils/platform.js'
+import {
+  parseMountSpec,
+  wrapCommandWithSandboxLinux,
+} from '../../src/sandbox/linux-sandbox-utils.js'
+
+function skipIfNotLinux(): boolean {
+  return getPlatform() !== 'linux'
+}
+
+describe('parseMountSpec', () => {
+  it('defaults to read-only', () => {
+    expect(parseMountSpec('/data/cache:/cache')).toEqual({
+      source: '/data/cache',
+      destination: '/cache',
+      readOnly: true,
+    })
+  })
+
+  it('accepts an explicit mode', () => {
+    expect(parseMountSpec('/data/cache:/cache:rw').readOnly).toBe(false)
+    expect(parseMountSpec('/data/cache:/cache:ro').readOnly).toBe(true)
+  })
+
+  it('rejects relative destinations and unknown modes', () => {
+    expect(() => parseMountSpec('/data:cache')).toThrow('Invalid mount')
+    expect(() => parseMountSpec('/data:/cache:rx')).toThrow('Invalid mount')
+    expect(() => parseMountSpec('/data')).toThrow('Invalid mount')
+  })
+})
+
+describe('remapped mounts on Linux', () => {
+  const baseDir = join(tmpdir(), `srt-bind-mounts-${process.pid}`)
+  const cacheDir = join(baseDir, 'cache')
+  const secretDir = join(baseDir, 'secret')
+
+  beforeAll(() => {
+    mkdirSync(cacheDir, { recursive: true })
+    mkdirSync(secretDir, { recursive: true })
+  })
+
+  afterAll(() => {
+    rmSync(baseDir, { recursive: true, force: true })
+  })
+
+  it('binds the source at the destination', async () => {
+    if (skipIfNotLinux()) {
+      return
+    }
+
+    const result = await wrapCommandWithSandboxLinux({
+      command: 'ls /mnt',
+      needsNetworkRestriction: false,
+      readConfig: { denyOnly: [] },
+      writeConfig: { allowOnly: [], denyWithinAllow: [] },
+      mounts: [`${cacheDir}:/mnt:ro`, `${cacheDir}:/opt:rw`],
+    })
+
+    expect(result).toContain(`--ro-bind ${cacheDir} /mnt`)
+    expect(result).toContain(`--bind ${cacheDir} /opt`)
+  })
+
+  it('refuses to expose denied paths through a mount', async () => {
+    if (skipIfNotLinux()) {
+      return
+    }
+
+    await expect(
+      wrapCommandWithSandboxLinux({
+        command: 'ls /mnt',
+        needsNetworkRestriction: false,
+        readConfig: { denyOnly: [secretDir] },
+        writeConfig: { allowOnly: [], denyWithinAllow: [] },
+        mounts: [`${baseDir}:/mnt:ro`],
+      }),
+    ).rejects.toThrow('overlaps denied path')
+  })
+
+  it('refuses writable mounts over denyWrite paths', async () => {
+    if (skipIfNotLinux()) {
+      return
+    }
+
+    await expect(
+      wrapCommandWithSandboxLinux({
+        command: 'ls /mnt',
+        needsNetworkRestriction: false,
+        readConfig: { denyOnly: [] },
+        writeConfig: { allowOnly: [], denyWithinAllow: [cacheDir] },
+        mounts: [`${cacheDir}:/mnt:rw`],
+      }),
+    ).rejects.toThrow('overlaps denied path')
+  })
+})
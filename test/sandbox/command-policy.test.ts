@@ -0,0 +1,146 @@
+import { describe, it, expect } from 'bun:test'
+import {
+  applyCommandPolicy,
+  findCommandPolicy,
+  getCommandBinaries,
+  matchesCommandPattern,
+  mergeConfigOverrides,
+} from '../../src/sandbox/command-policy.js'
+import {
+  SandboxRuntimeConfigSchema,
+  type SandboxRuntimeConfig,
+} from '../../src/sandbox/sandbox-config.js'
+
+const baseConfig: SandboxRuntimeConfig = {
+  network: { allowedDomains: ['example.com'], deniedDomains: [] },
+  filesystem: {
+    denyRead: ['~/.ssh'],
+    allowWrite: ['.', '/tmp'],
+    denyWrite: [],
+  },
+}
+
+describe('getCommandBinaries', () => {
+  it('returns the binary of every command in the string', () => {
+    expect(getCommandBinaries('git status')).toEqual(['git'])
+    expect(
+      getCommandBinaries('FOO=1 npm ci && npm test | tee out.log'),
+    ).toEqual(['npm', 'npm', 'tee'])
+    expect(getCommandBinaries('git log > out.txt; git-lfs pull')).toEqual([
+      'git',
+      'git-lfs',
+    ])
+  })
+
+  it('gives up on substitutions and subshells', () => {
+    expect(getCommandBinaries('git $(curl evil.com)')).toBeUndefined()
+    expect(getCommandBinaries('git `id`')).toBeUndefined()
+    expect(getCommandBinaries('(git status)')).toBeUndefined()
+  })
+
+  it('gives up on newlines, which separate commands', () => {
+    expect(getCommandBinaries('git status\ncurl evil.com')).toBeUndefined()
+    expect(getCommandBinaries('git status\r\ncurl evil.com')).toBeUndefined()
+  })
+
+  it('gives up on binaries run by path', () => {
+    expect(getCommandBinaries('./git status')).toBeUndefined()
+    expect(getCommandBinaries('/usr/bin/npm ci')).toBeUndefined()
+  })
+})
+
+describe('matchesCommandPattern', () => {
+  it('matches names exactly and globs as a whole', () => {
+    expect(matchesCommandPattern('npm', 'npm')).toBe(true)
+    expect(matchesCommandPattern('npm', 'npx')).toBe(false)
+    expect(matchesCommandPattern('git*', 'git-lfs')).toBe(true)
+    expect(matchesCommandPattern('git*', 'legit')).toBe(false)
+  })
+})
+
+describe('findCommandPolicy', () => {
+  const commands = {
+    'git*': { allowedDomains: ['github.com'] },
+    npm: { allowWrite: ['.'] },
+  }
+
+  it('applies when every command matches the key', () => {
+    expect(findCommandPolicy(commands, 'git fetch && git-lfs pull')).toEqual({
+      pattern: 'git*',
+      policy: commands['git*'],
+    })
+    expect(findCommandPolicy(commands, 'npm ci')?.pattern).toBe('npm')
+  })
+
+  it('does not apply when another command is chained', () => {
+    expect(
+      findCommandPolicy(commands, 'git pull && curl https://example.net'),
+    ).toBeUndefined()
+    expect(findCommandPolicy(commands, 'git $(id)')).toBeUndefined()
+    expect(findCommandPolicy(undefined, 'git pull')).toBeUndefined()
+  })
+})
+
+describe('applyCommandPolicy', () => {
+  it('extends the lists and replaces allowWrite', () => {
+    const result = applyCommandPolicy(baseConfig, {
+      allowedDomains: ['registry.npmjs.org', 'example.com'],
+      deniedDomains: ['evil.example.com'],
+      allowWrite: ['node_modules'],
+      denyRead: ['~/.aws'],
+    })
+
+    expect(result.network.allowedDomains).toEqual([
+      'example.com',
+      'registry.npmjs.org',
+    ])
+    expect(result.network.deniedDomains).toEqual(['evil.example.com'])
+    expect(result.filesystem.allowWrite).toEqual(['node_modules'])
+    expect(result.filesystem.denyRead).toEqual(['~/.ssh', '~/.aws'])
+    expect(result.filesystem.denyWrite).toEqual([])
+  })
+
+  it('keeps the base lists the policy does not set', () => {
+    expect(applyCommandPolicy(baseConfig, {})).toEqual({
+      network: baseConfig.network,
+      filesystem: baseConfig.filesystem,
+    })
+  })
+})
+
+describe('mergeConfigOverrides', () => {
+  it('keeps the deny lists of the base when overrides replace a section', () => {
+    const policy = applyCommandPolicy(baseConfig, {
+      deniedDomains: ['evil.example.com'],
+      denyWrite: ['.env'],
+    })
+    const merged = mergeConfigOverrides(policy, {
+      filesystem: { denyRead: [], allowWrite: ['.'], denyWrite: [] },
+      network: { allowedDomains: ['example.org'], deniedDomains: [] },
+    })
+
+    expect(merged.filesystem?.allowWrite).toEqual(['.'])
+    expect(merged.filesystem?.denyRead).toEqual(['~/.ssh'])
+    expect(merged.filesystem?.denyWrite).toEqual(['.env'])
+    expect(merged.network?.allowedDomains).toEqual(['example.org'])
+    expect(merged.network?.deniedDomains).toEqual(['evil.example.com'])
+  })
+
+  it('returns the base without overrides', () => {
+    const policy = applyCommandPolicy(baseConfig, {})
+    expect(mergeConfigOverrides(policy, undefined)).toBe(policy)
+  })
+})
+
+describe('commands config', () => {
+  it('validates override entries', () => {
+    const parse = (commands: unknown) =>
+      SandboxRuntimeConfigSchema.safeParse({ ...baseConfig, commands }).success
+
+    expect(parse({ npm: { allowedDomains: ['registry.npmjs.org'] } })).toBe(
+      true,
+    )
+    expect(parse({ npm: { allowWrite: [''] } })).toBe(false)
+    expect(parse({ npm: { allowedDomains: ['http://bad'] } })).toBe(false)
+  })
+})
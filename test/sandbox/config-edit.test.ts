@@ -0,0 +1,174 @@
+import { describe, it, expect, beforeAll, afterAll } from 'bun:test'
+import * as fs from 'node:fs'
+import { tmpdir } from 'node:os'
+import { join } from 'node:path'
+import {
+  addConfigListEntry,
+  describeBlockedAccesses,
+  formatConfigNote,
+  resolveConfigListEntry,
+} from '../../src/sandbox/config-edit.js'
+import type { SandboxViolationEvent } from '../../src/sandbox/macos-sandbox-utils.js'
+
+describe('addConfigListEntry', () => {
+  const root = join(tmpdir(), `srt-config-edit-${process.pid}`)
+
+  beforeAll(() => {
+    fs.mkdirSync(root, { recursive: true })
+  })
+
+  afterAll(() => {
+    fs.rmSync(root, { recursive: true, force: true })
+  })
+
+  it('creates the file and the list', () => {
+    const file = join(root, 'new.json')
+    const added = addConfigListEntry(file, 'network.allowedDomains', 'a.com')
+    expect(added).toBe(true)
+    expect(JSON.parse(fs.readFileSync(file, 'utf8'))).toEqual({
+      network: { allowedDomains: ['a.com'] },
+    })
+  })
+
+  it('appends to an existing list and keeps the other settings', () => {
+    const file = join(root, 'existing.json')
+    fs.writeFileSync(
+      file,
+      JSON.stringify({
+        network: { allowedDomains: ['github.com'], deniedDomains: ['x.com'] },
+        allowPty: true,
+      }),
+    )
+
+    const list = 'network.allowedDomains'
+    expect(addConfigListEntry(file, list, 'npmjs.org')).toBe(true)
+    expect(addConfigListEntry(file, list, 'github.com')).toBe(false)
+    expect(JSON.parse(fs.readFileSync(file, 'utf8'))).toEqual({
+      network: {
+        allowedDomains: ['github.com', 'npmjs.org'],
+        deniedDomains: ['x.com'],
+      },
+      allowPty: true,
+    })
+  })
+
+  it('adds a note with the entry', () => {
+    const file = join(root, 'notes.json')
+    const note = 'added ./dist'
+    addConfigListEntry(file, 'filesystem.allowWrite', './dist', note)
+    addConfigListEntry(file, 'filesystem.allowWrite', './dist', note)
+
+    expect(JSON.parse(fs.readFileSync(file, 'utf8'))).toEqual({
+      filesystem: { allowWrite: ['./dist'] },
+      notes: ['added ./dist'],
+    })
+  })
+
+  it('replaces the file without leaving a temporary file', () => {
+    const file = join(root, 'replaced.json')
+    fs.writeFileSync(file, '{ "allowPty": true }')
+    addConfigListEntry(file, 'network.allowedDomains', 'a.com')
+
+    expect(JSON.parse(fs.readFileSync(file, 'utf8'))).toEqual({
+      allowPty: true,
+      network: { allowedDomains: ['a.com'] },
+    })
+    const leftovers = fs.readdirSync(root).filter(f => f.endsWith('.tmp'))
+    expect(leftovers).toEqual([])
+  })
+
+  it('rejects entries the config would not accept', () => {
+    const file = join(root, 'rejected.json')
+    expect(() =>
+      addConfigListEntry(file, 'network.allowedDomains', 'https://a.com'),
+    ).toThrow(/Invalid network.allowedDomains entry/)
+    expect(fs.existsSync(file)).toBe(false)
+  })
+
+  it('refuses files that are not settings objects', () => {
+    const file = join(root, 'invalid.json')
+    fs.writeFileSync(file, '{ "network": ')
+    expect(() =>
+      addConfigListEntry(file, 'filesystem.allowWrite', './dist'),
+    ).toThrow(/Cannot edit/)
+    expect(fs.readFileSync(file, 'utf8')).toBe('{ "network": ')
+  })
+})
+
+describe('resolveConfigListEntry', () => {
+  it('makes relative paths absolute', () => {
+    const list = 'filesystem.allowWrite'
+    expect(resolveConfigListEntry(list, './dist', '/work/app')).toBe(
+      '/work/app/dist',
+    )
+    expect(resolveConfigListEntry(list, '../out', '/work/app')).toBe(
+      '/work/out',
+    )
+    expect(resolveConfigListEntry(list, '/tmp', '/work/app')).toBe('/tmp')
+    expect(resolveConfigListEntry(list, '~/cache', '/work/app')).toBe(
+      '~/cache',
+    )
+  })
+
+  it('keeps domains as given', () => {
+    expect(
+      resolveConfigListEntry('network.allowedDomains', 'a.com', '/work'),
+    ).toBe('a.com')
+  })
+})
+
+describe('formatConfigNote', () => {
+  it('starts with the date', () => {
+    const date = new Date('2026-10-17T09:00:00Z')
+    expect(
+      formatConfigNote('network.allowedDomains', 'a.com', 'needed', date),
+    ).toBe('2026-10-17: added a.com to network.allowedDomains (needed)')
+  })
+})
+
+describe('describeBlockedAccesses', () => {
+  const violations: SandboxViolationEvent[] = [
+    {
+      line: 'blocked registry.npmjs.org:443',
+      timestamp: new Date('2026-10-16T10:00:00Z'),
+      type: 'network',
+      host: 'registry.npmjs.org',
+      port: 443,
+    },
+    {
+      line: 'blocked registry.npmjs.org:443',
+      timestamp: new Date('2026-10-17T10:00:00Z'),
+      type: 'network',
+      host: 'registry.npmjs.org',
+      port: 443,
+    },
+    {
+      line: 'openat /work/dist/index.js EROFS',
+      timestamp: new Date('2026-10-17T11:00:00Z'),
+      access: 'write',
+      path: '/work/dist/index.js',
+    },
+  ]
+
+  it('counts the denials a domain would have allowed', () => {
+    expect(
+      describeBlockedAccesses(
+        violations,
+        'network.allowedDomains',
+        '*.npmjs.org',
+      ),
+    ).toBe('blocked 2 times, last at 2026-10-17T10:00:00.000Z')
+  })
+
+  it('counts the denied writes below a path', () => {
+    expect(
+      describeBlockedAccesses(violations, 'filesystem.allowWrite', '/work'),
+    ).toBe('blocked once, last at 2026-10-17T11:00:00.000Z')
+  })
+
+  it('is undefined without matching denials', () => {
+    expect(
+      describeBlockedAccesses(violations, 'network.allowedDomains', 'a.com'),
+    ).toBeUndefined()
+  })
+})
@@ -0,0 +1,55 @@
+import { describe, it, expect, beforeEach, afterEach } from 'bun:test'
+import * as fs from 'node:fs'
+import { tmpdir } from 'node:os'
+import { join } from 'node:path'
+import {
+  buildStarterConfig,
+  detectProjects,
+  formatStarterConfigProposal,
+} from '../../src/sandbox/config-init.js'
+import { CONFIG_PRESETS } from '../../src/sandbox/config-presets.js'
+import { SandboxRuntimeConfigSchema } from '../../src/sandbox/sandbox-config.js'
+
+describe('srt init', () => {
+  let dir: string
+
+  beforeEach(() => {
+    dir = fs.mkdtempSync(join(tmpdir(), 'srt-init-'))
+  })
+
+  afterEach(() => {
+    fs.rmSync(dir, { recursive: true, force: true })
+  })
+
+  it('detects project manifests', () => {
+    fs.writeFileSync(join(dir, 'package.json'), '{}')
+    fs.writeFileSync(join(dir, 'requirements.txt'), 'requests\n')
+    expect(detectProjects(dir)).toEqual([
+      { file: 'package.json', preset: 'node-dev' },
+      { file: 'requirements.txt', preset: 'python-dev' },
+    ])
+  })
+
+  it('merges the presets of every detected project', () => {
+    fs.writeFileSync(join(dir, 'package.json'), '{}')
+    fs.writeFileSync(join(dir, 'Cargo.toml'), '[package]\n')
+    const config = buildStarterConfig(detectProjects(dir))
+
+    expect(SandboxRuntimeConfigSchema.safeParse(config).success).toBe(true)
+    expect(config.network.allowedDomains).toContain('registry.npmjs.org')
+    expect(config.network.allowedDomains).toContain('crates.io')
+    expect(config.filesystem.allowWrite).toContain('~/.cargo/registry')
+    expect(config.filesystem.allowWrite.filter(p => p === '.')).toHaveLength(1)
+  })
+
+  it('proposes the git-only policy without a manifest', () => {
+    const projects = detectProjects(dir)
+    const config = buildStarterConfig(projects)
+    expect(config.network.allowedDomains).toEqual(
+      CONFIG_PRESETS['git-only'].allowedDomains,
+    )
+    expect(formatStarterConfigProposal(projects, config)).toContain(
+      'No package manifest found',
+    )
+  })
+})
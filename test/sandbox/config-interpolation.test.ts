@@ -0,0 +1,75 @@
+import { describe, it, expect } from 'bun:test'
+import {
+  ConfigInterpolationError,
+  interpolateConfigLayer,
+  interpolateConfigString,
+  type ConfigInterpolationContext,
+} from '../../src/sandbox/config-interpolation.js'
+
+const context: ConfigInterpolationContext = {
+  home: '/home/dev',
+  projectDir: '/work/app',
+  env: { REGISTRY: 'registry.example.com', EMPTY: '' },
+}
+
+describe('interpolateConfigString', () => {
+  it('replaces HOME, PROJECT_DIR and env placeholders', () => {
+    expect(
+      interpolateConfigString('${HOME}/.cache/${env:EMPTY}x', context, 'a'),
+    ).toBe('/home/dev/.cache/x')
+    expect(interpolateConfigString('${PROJECT_DIR}/build', context, 'a')).toBe(
+      '/work/app/build',
+    )
+    expect(interpolateConfigString('${env:REGISTRY}', context, 'a')).toBe(
+      'registry.example.com',
+    )
+  })
+
+  it('leaves strings without placeholders alone', () => {
+    expect(interpolateConfigString('~/.ssh', context, 'a')).toBe('~/.ssh')
+    expect(interpolateConfigString('$HOME', context, 'a')).toBe('$HOME')
+  })
+
+  it('rejects undefined variables and unknown placeholders', () => {
+    expect(() =>
+      interpolateConfigString('${env:MISSING}/npm', context, 'a'),
+    ).toThrow('a: environment variable MISSING in ${env:MISSING}')
+    expect(() => interpolateConfigString('${USER}', context, 'a')).toThrow(
+      ConfigInterpolationError,
+    )
+  })
+})
+
+describe('interpolateConfigLayer', () => {
+  it('interpolates the network and filesystem sections only', () => {
+    const layer = interpolateConfigLayer(
+      {
+        network: { allowedDomains: ['${env:REGISTRY}'] },
+        filesystem: { allowWrite: ['${PROJECT_DIR}'], mounts: [] },
+        violationFeedSocket: '${HOME}/feed.sock',
+      },
+      context,
+    )
+
+    expect(layer).toEqual({
+      network: { allowedDomains: ['registry.example.com'] },
+      filesystem: { allowWrite: ['/work/app'], mounts: [] },
+      violationFeedSocket: '${HOME}/feed.sock',
+    })
+  })
+
+  it('names the offending value in errors', () => {
+    try {
+      interpolateConfigLayer(
+        { filesystem: { denyRead: ['~/.ssh', '${env:MISSING}'] } },
+        context,
+      )
+      throw new Error('expected an error')
+    } catch (error) {
+      expect(error).toBeInstanceOf(ConfigInterpolationError)
+      expect((error as ConfigInterpolationError).location).toBe(
+        'filesystem.denyRead[1]',
+      )
+    }
+  })
+})
@@ -0,0 +1,247 @@
+import { describe, it, expect, beforeAll, afterAll } from 'bun:test'
+import * as fs from 'node:fs'
+import { tmpdir } from 'node:os'
+import { join } from 'node:path'
+import {
+  ConfigExtendsError,
+  findProjectConfigPath,
+  getConfigLayerPaths,
+  getSystemConfigPaths,
+  loadConfigWithExtends,
+  mergeConfigLayers,
+  readConfigLayers,
+  resolveExtendsReference,
+} from '../../src/sandbox/config-layers.js'
+
+describe('mergeConfigLayers', () => {
+  it('concatenates lists and lets later layers win for other values', () => {
+    const merged = mergeConfigLayers([
+      {
+        network: { allowedDomains: ['github.com'], httpProxyPort: 8080 },
+        filesystem: { denyRead: ['~/.ssh'] },
+        enableWeakerNestedSandbox: false,
+      },
+      {
+        network: {
+          allowedDomains: ['github.com', 'registry.npmjs.org'],
+          httpProxyPort: 9090,
+        },
+        enableWeakerNestedSandbox: true,
+      },
+    ])
+
+    expect(merged).toEqual({
+      network: {
+        allowedDomains: ['github.com', 'registry.npmjs.org'],
+        httpProxyPort: 9090,
+      },
+      filesystem: { denyRead: ['~/.ssh'] },
+      enableWeakerNestedSandbox: true,
+    })
+  })
+
+  it('does not modify the layers', () => {
+    const user = { network: { allowedDomains: ['a.example.com'] } }
+    mergeConfigLayers([
+      user,
+      { network: { allowedDomains: ['b.example.com'] } },
+    ])
+    expect(user.network.allowedDomains).toEqual(['a.example.com'])
+  })
+})
+
+describe('project settings lookup', () => {
+  const root = join(tmpdir(), `srt-config-layers-${process.pid}`)
+  const nested = join(root, 'packages', 'app')
+
+  beforeAll(() => {
+    fs.mkdirSync(nested, { recursive: true })
+    fs.mkdirSync(join(root, '.git'))
+    fs.writeFileSync(join(root, '.srt.json'), '{}')
+  })
+
+  afterAll(() => {
+    fs.rmSync(root, { recursive: true, force: true })
+  })
+
+  it('finds the closest .srt.json in a parent directory', () => {
+    expect(findProjectConfigPath(nested)).toBe(join(root, '.srt.json'))
+
+    fs.writeFileSync(join(nested, '.srt.json'), '{}')
+    expect(findProjectConfigPath(nested)).toBe(join(nested, '.srt.json'))
+    fs.rmSync(join(nested, '.srt.json'))
+  })
+
+  it('does not look above the repository root', () => {
+    const repo = join(root, 'packages')
+    fs.mkdirSync(join(repo, '.git'))
+    try {
+      expect(findProjectConfigPath(nested)).toBeUndefined()
+    } finally {
+      fs.rmSync(join(repo, '.git'), { recursive: true })
+    }
+  })
+
+  it('only checks the directory itself outside a repository', () => {
+    fs.rmSync(join(root, '.git'), { recursive: true })
+    try {
+      expect(findProjectConfigPath(nested)).toBeUndefined()
+      expect(findProjectConfigPath(root)).toBe(join(root, '.srt.json'))
+    } finally {
+      fs.mkdirSync(join(root, '.git'))
+    }
+  })
+
+  it('orders the explicit settings file before the project file', () => {
+    const settingsPath = join(root, 'user.json')
+    fs.writeFileSync(settingsPath, '{}')
+
+    const paths = getConfigLayerPaths({ settingsPath, cwd: nested })
+    expect(paths.slice(-2)).toEqual([settingsPath, join(root, '.srt.json')])
+  })
+
+  it('skips settings files that do not exist', () => {
+    const paths = getConfigLayerPaths({
+      settingsPath: join(root, 'missing.json'),
+      cwd: nested,
+    })
+    expect(paths).not.toContain(join(root, 'missing.json'))
+  })
+})
+
+describe('system settings files', () => {
+  const dir = join(tmpdir(), `srt-config-system-${process.pid}`)
+
+  beforeAll(() => {
+    fs.mkdirSync(dir, { recursive: true })
+    for (const name of [
+      'config.json',
+      'config.50-proxy.json',
+      'config.local',
+      'config.json.bak',
+      'config.json~',
+      'other.json',
+    ]) {
+      fs.writeFileSync(join(dir, name), '{}')
+    }
+  })
+
+  afterAll(() => {
+    fs.rmSync(dir, { recursive: true, force: true })
+  })
+
+  it('reads config.json, then the other config.* files in name order', () => {
+    expect(getSystemConfigPaths(dir)).toEqual([
+      join(dir, 'config.json'),
+      join(dir, 'config.50-proxy.json'),
+      join(dir, 'config.local'),
+    ])
+  })
+
+  it('puts the system files first', () => {
+    const paths = getConfigLayerPaths({ systemConfigDir: dir, cwd: dir })
+    expect(paths.slice(0, 3)).toEqual(getSystemConfigPaths(dir))
+  })
+
+  it('has no system files when the directory is missing', () => {
+    expect(getSystemConfigPaths(join(dir, 'missing'))).toEqual([])
+  })
+})
+
+describe('extends', () => {
+  const root = join(tmpdir(), `srt-config-extends-${process.pid}`)
+
+  function write(name: string, content: unknown): string {
+    const filePath = join(root, name)
+    fs.writeFileSync(filePath, JSON.stringify(content))
+    return filePath
+  }
+
+  const readLayer = (filePath: string) =>
+    JSON.parse(fs.readFileSync(filePath, 'utf-8')) as Record<string, unknown>
+
+  beforeAll(() => {
+    fs.mkdirSync(join(root, 'shared'), { recursive: true })
+  })
+
+  afterAll(() => {
+    fs.rmSync(root, { recursive: true, force: true })
+  })
+
+  it('merges extended files depth-first in listed order', () => {
+    write('shared/base.json', {
+      network: { allowedDomains: ['base.example.com'] },
+      timeoutSeconds: 10,
+    })
+    write('shared/node.json', {
+      extends: './base.json',
+      network: { allowedDomains: ['registry.npmjs.org'] },
+      timeoutSeconds: 20,
+    })
+    const top = write('top.json', {
+      extends: ['./shared/node.json', './shared/base.json'],
+      network: { allowedDomains: ['api.example.com'] },
+    })
+
+    expect(loadConfigWithExtends(top, readLayer)).toEqual({
+      network: {
+        allowedDomains: [
+          'base.example.com',
+          'registry.npmjs.org',
+          'api.example.com',
+        ],
+      },
+      // base.json is listed last, so its value wins over node.json
+      timeoutSeconds: 10,
+    })
+  })
+
+  it('lists each file with its own settings in merge order', () => {
+    const base = write('layers-base.json', { timeoutSeconds: 10 })
+    const top = write('layers-top.json', {
+      extends: './layers-base.json',
+      network: { allowedDomains: ['api.example.com'] },
+    })
+
+    expect(readConfigLayers(top, readLayer)).toEqual([
+      { file: base, config: { timeoutSeconds: 10 } },
+      {
+        file: top,
+        config: { network: { allowedDomains: ['api.example.com'] } },
+      },
+    ])
+  })
+
+  it('detects cycles', () => {
+    write('a.json', { extends: './b.json' })
+    write('b.json', { extends: './a.json' })
+
+    expect(() =>
+      loadConfigWithExtends(join(root, 'a.json'), readLayer),
+    ).toThrow(ConfigExtendsError)
+  })
+
+  it('rejects missing and invalid extended files', () => {
+    const missing = write('missing.json', { extends: './nope.json' })
+    expect(() => loadConfigWithExtends(missing, readLayer)).toThrow(
+      'does not exist',
+    )
+
+    write('broken.json', {})
+    const invalid = write('invalid.json', { extends: './broken.json' })
+    expect(() =>
+      loadConfigWithExtends(invalid, filePath =>
+        filePath.endsWith('broken.json') ? null : readLayer(filePath),
+      ),
+    ).toThrow('is invalid')
+  })
+
+  it('resolves names in the policy directories only', () => {
+    expect(() =>
+      resolveExtendsReference('srt-test-no-such-policy', join(root, 'x.json')),
+    ).toThrow('no srt-test-no-such-policy.json')
+    expect(() =>
+      resolveExtendsReference('shared/base.json', join(root, 'x.json')),
+    ).toThrow('use a path starting with')
+  })
+})
@@ -0,0 +1,72 @@
+import { describe, it, expect } from 'bun:test'
+import {
+  applyConfigPreset,
+  CONFIG_PRESETS,
+  CONFIG_PRESET_NAMES,
+} from '../../src/sandbox/config-presets.js'
+import {
+  SandboxRuntimeConfigSchema,
+  type SandboxRuntimeConfig,
+} from '../../src/sandbox/sandbox-config.js'
+
+function baseConfig(
+  overrides: Partial<SandboxRuntimeConfig> = {},
+): SandboxRuntimeConfig {
+  return {
+    network: { allowedDomains: [], deniedDomains: [] },
+    filesystem: { denyRead: [], allowWrite: [], denyWrite: [] },
+    ...overrides,
+  }
+}
+
+describe('config presets', () => {
+  it('only contain entries the config schema accepts', () => {
+    for (const name of CONFIG_PRESET_NAMES) {
+      const result = SandboxRuntimeConfigSchema.safeParse(
+        applyConfigPreset(baseConfig({ preset: name })),
+      )
+      expect(result.success).toBe(true)
+    }
+  })
+
+  it('leaves configs without a preset unchanged', () => {
+    const config = baseConfig()
+    expect(applyConfigPreset(config)).toBe(config)
+  })
+
+  it('merges preset entries before the config entries without duplicates', () => {
+    const config = applyConfigPreset(
+      baseConfig({
+        preset: 'rust-build',
+        network: {
+          allowedDomains: ['crates.io', 'my-registry.example.com'],
+          deniedDomains: ['evil.example.com'],
+        },
+        filesystem: { denyRead: [], allowWrite: ['target'], denyWrite: [] },
+      }),
+    )
+
+    expect(config.network.allowedDomains).toEqual([
+      ...CONFIG_PRESETS['rust-build'].allowedDomains,
+      'my-registry.example.com',
+    ])
+    expect(config.network.deniedDomains).toEqual(['evil.example.com'])
+    expect(config.filesystem.allowWrite).toEqual([
+      ...CONFIG_PRESETS['rust-build'].allowWrite,
+      'target',
+    ])
+    expect(config.filesystem.denyRead).toContain('~/.aws')
+  })
+
+  it('keeps SSH keys readable for git-only', () => {
+    expect(CONFIG_PRESETS['git-only'].denyRead).not.toContain('~/.ssh')
+    expect(CONFIG_PRESETS['node-dev'].denyRead).toContain('~/.ssh')
+  })
+
+  it('rejects unknown preset names', () => {
+    expect(
+      SandboxRuntimeConfigSchema.safeParse({ ...baseConfig(), preset: 'nope' })
+        .success,
+    ).toBe(false)
+  })
+})
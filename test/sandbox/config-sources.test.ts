@@ -0,0 +1,80 @@
+import { describe, it, expect } from 'bun:test'
+import {
+  formatConfigWithSources,
+  resolveConfigSources,
+} from '../../src/sandbox/config-sources.js'
+import type { SandboxRuntimeConfig } from '../../src/sandbox/sandbox-config.js'
+
+const layers = [
+  {
+    file: '/etc/srt/config.json',
+    config: {
+      network: { allowedDomains: ['github.com'] },
+      allowDegradedSandbox: false,
+    },
+  },
+  {
+    file: '/repo/.srt.json',
+    config: {
+      network: { allowedDomains: ['github.com', 'api.example.com'] },
+      allowDegradedSandbox: true,
+    },
+  },
+]
+
+const config: SandboxRuntimeConfig = {
+  network: {
+    allowedDomains: ['github.com', 'api.example.com'],
+    deniedDomains: [],
+  },
+  filesystem: { denyRead: [], allowWrite: [], denyWrite: [] },
+  allowDegradedSandbox: true,
+  timeoutSeconds: 30,
+}
+
+describe('resolveConfigSources', () => {
+  it('attributes list entries to the first file and values to the last', () => {
+    const { sources } = resolveConfigSources(config, layers)
+    expect(sources.get('network.allowedDomains["github.com"]')).toBe(
+      '/etc/srt/config.json',
+    )
+    expect(sources.get('network.allowedDomains["api.example.com"]')).toBe(
+      '/repo/.srt.json',
+    )
+    expect(sources.get('allowDegradedSandbox')).toBe('/repo/.srt.json')
+    expect(sources.has('timeoutSeconds')).toBe(false)
+  })
+
+  it('applies the preset and attributes its entries to it', () => {
+    const { config: resolved, sources } = resolveConfigSources(
+      { ...config, preset: 'node-dev' },
+      layers,
+    )
+    expect(resolved.network.allowedDomains).toContain('registry.npmjs.org')
+    expect(sources.get('network.allowedDomains["registry.npmjs.org"]')).toBe(
+      'preset node-dev',
+    )
+    expect(sources.get('network.allowedDomains["github.com"]')).toBe(
+      '/etc/srt/config.json',
+    )
+  })
+})
+
+describe('formatConfigWithSources', () => {
+  it('prints JSON with a source comment on every value', () => {
+    const { config: resolved, sources } = resolveConfigSources(config, layers)
+    const lines = formatConfigWithSources(resolved, sources).split('\n')
+
+    expect(lines).toContain('    "github.com",  // /etc/srt/config.json')
+    expect(lines).toContain('    "api.example.com"  // /repo/.srt.json')
+    expect(lines).toContain(
+      '  "allowDegradedSandbox": true,  // /repo/.srt.json',
+    )
+    expect(lines).toContain('  "timeoutSeconds": 30  // default')
+    expect(lines).toContain('    "deniedDomains": []')
+
+    // Without the comments, the output is the config as JSON
+    const json = lines.map(line => line.replace(/ {2}\/\/ .*$/, '')).join('\n')
+    expect(JSON.parse(json)).toEqual(resolved)
+  })
+})
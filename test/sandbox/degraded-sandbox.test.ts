@@ -0,0 +1,157 @@
+import { describe, it, expect } from 'bun:test'
+import { spawnSync } from 'node:child_process'
+import { getPlatform } from '../../src/utils/platform.js'
+import {
+  buildRlimitCommands,
+  getScrubbedEnvVarNames,
+  hasCgroupResourceControlSync,
+  wrapCommandWithDegradedSandboxLinux,
+} from '../../src/sandbox/linux-sandbox-utils.js'
+import { getSpawnEnv } from '../../src/sandbox/sandbox-env.js'
+
+function skipIfNotLinux(): boolean {
+  return getPlatform() !== 'linux'
+}
+
+describe('getScrubbedEnvVarNames', () => {
+  it('selects credentials and agent sockets', () => {
+    const names = getScrubbedEnvVarNames({
+      GITHUB_TOKEN: 'x',
+      AWS_SECRET_ACCESS_KEY: 'x',
+      OPENAI_API_KEY: 'x',
+      SSH_AUTH_SOCK: '/tmp/agent.sock',
+      PATH: '/usr/bin',
+      HOME: '/home/user',
+    })
+    expect(names.sort()).toEqual([
+      'AWS_SECRET_ACCESS_KEY',
+      'GITHUB_TOKEN',
+      'OPENAI_API_KEY',
+      'SSH_AUTH_SOCK',
+    ])
+  })
+})
+
+describe('buildRlimitCommands', () => {
+  it('always disables core dumps', () => {
+    expect(buildRlimitCommands(undefined)).toEqual(['ulimit -c 0'])
+  })
+
+  it('maps the memory limit to an rlimit and leaves pids to cgroups', () => {
+    const commands = buildRlimitCommands({ cpus: 2, memoryMb: 512, pids: 64 })
+    expect(commands).toEqual(['ulimit -c 0', 'ulimit -v 524288'])
+  })
+})
+
+describe('wrapCommandWithDegradedSandboxLinux', () => {
+  it('returns the command unchanged without restrictions', async () => {
+    const wrapped = await wrapCommandWithDegradedSandboxLinux({
+      command: 'echo hi',
+      needsNetworkRestriction: false,
+    })
+    expect(wrapped).toBe('echo hi')
+  })
+
+  it('does not use bwrap and points proxies at the host ports', async () => {
+    if (skipIfNotLinux()) {
+      return
+    }
+    const wrapped = await wrapCommandWithDegradedSandboxLinux({
+      command: 'echo hi',
+      needsNetworkRestriction: true,
+      httpProxyPort: 8888,
+      socksProxyPort: 9999,
+    })
+    expect(wrapped.startsWith('env ')).toBe(true)
+    expect(wrapped).not.toContain('bwrap')
+    expect(wrapped).toContain('HTTP_PROXY=http://localhost:8888')
+    expect(wrapped).toContain('ALL_PROXY=socks5h://localhost:9999')
+  })
+
+  it('uses a cgroup scope for resource limits when the host has one', async () => {
+    if (skipIfNotLinux()) {
+      return
+    }
+    const wrapped = await wrapCommandWithDegradedSandboxLinux({
+      command: 'echo hi',
+      needsNetworkRestriction: true,
+      allowAllUnixSockets: true,
+      resourceLimits: { cpus: 1, memoryMb: 512 },
+    })
+    if (hasCgroupResourceControlSync()) {
+      expect(wrapped.startsWith('systemd-run ')).toBe(true)
+      expect(wrapped).toContain('MemoryMax=512M')
+      expect(wrapped).not.toContain('ulimit -v')
+    } else {
+      expect(wrapped.startsWith('env ')).toBe(true)
+      expect(wrapped).toContain('ulimit -v 524288')
+    }
+  })
+
+  it('scrubs credentials and disables core dumps', async () => {
+    if (skipIfNotLinux()) {
+      return
+    }
+    process.env.SRT_TEST_API_KEY = 'leaked'
+    try {
+      const wrapped = await wrapCommandWithDegradedSandboxLinux({
+        command: 'echo "key=${SRT_TEST_API_KEY:-unset} core=$(ulimit -c)"',
+        needsNetworkRestriction: true,
+        allowAllUnixSockets: true,
+      })
+      const result = spawnSync(wrapped, { shell: true, encoding: 'utf8' })
+      expect(result.status).toBe(0)
+      expect(result.stdout.trim()).toBe('key=unset core=0')
+    } finally {
+      delete process.env.SRT_TEST_API_KEY
+    }
+  })
+
+  it('applies the env policy without putting values on the command line', async () => {
+    if (skipIfNotLinux()) {
+      return
+    }
+    process.env.SRT_TEST_HOST_ONLY = 'host'
+    try {
+      const environment = {
+        PATH: process.env.PATH ?? '/usr/bin:/bin',
+        SRT_TEST_SET: 'set-value',
+        SSH_AUTH_SOCK: '/tmp/agent.sock',
+      }
+      const wrapped = await wrapCommandWithDegradedSandboxLinux({
+        command:
+          'echo "${SRT_TEST_HOST_ONLY:-unset} $SRT_TEST_SET ${SSH_AUTH_SOCK:-hidden}"',
+        needsNetworkRestriction: true,
+        allowAllUnixSockets: true,
+        environment,
+      })
+      expect(wrapped).not.toContain('set-value')
+      expect(wrapped).not.toContain('/tmp/agent.sock')
+      const result = spawnSync(wrapped, {
+        shell: true,
+        encoding: 'utf8',
+        env: getSpawnEnv(environment),
+      })
+      expect(result.status).toBe(0)
+      expect(result.stdout.trim()).toBe('unset set-value hidden')
+    } finally {
+      delete process.env.SRT_TEST_HOST_ONLY
+    }
+  })
+})
+
+describe('degraded sandbox privileges', () => {
+  it('sets no_new_privs without a seccomp filter', async () => {
+    if (skipIfNotLinux()) {
+      return
+    }
+    const wrapped = await wrapCommandWithDegradedSandboxLinux({
+      command: 'grep NoNewPrivs /proc/self/status',
+      needsNetworkRestriction: true,
+      allowAllUnixSockets: true,
+    })
+    const result = spawnSync(wrapped, { shell: true, encoding: 'utf8' })
+    expect(result.status).toBe(0)
+    expect(result.stdout).toMatch(/NoNewPrivs:\s+1/)
+  })
+})
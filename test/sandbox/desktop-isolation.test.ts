@@ -0,0 +1,126 @@
+import { describe, it, expect, beforeAll, afterAll } from 'bun:test'
+import { mkdirSync, rmSync, writeFileSync } from 'node:fs'
+import { tmpdir } from 'node:os'
+import { join } from 'node:path'
+import { getPlatform } from '../../src/utils/platform.js'
+import {
+  buildDesktopIsolation,
+  getSessionBusSocketPath,
+  getWaylandSocketPath,
+} from '../../src/sandbox/desktop-isolation.js'
+import {
+  wrapCommandWithSandboxLinux,
+} from '../../src/sandbox/linux-sandbox-utils.js'
+
+describe('desktop socket paths', () => {
+  it('parses the session bus path from DBUS_SESSION_BUS_ADDRESS', () => {
+    expect(
+      getSessionBusSocketPath({
+        DBUS_SESSION_BUS_ADDRESS: 'unix:path=/run/user/1000/bus,guid=abc',
+      }),
+    ).toBe('/run/user/1000/bus')
+    expect(
+      getSessionBusSocketPath({
+        DBUS_SESSION_BUS_ADDRESS: 'unix:abstract=/tmp/dbus-XYZ',
+      }),
+    ).toBeUndefined()
+  })
+
+  it('falls back to the runtime directory', () => {
+    expect(getSessionBusSocketPath({ XDG_RUNTIME_DIR: '/run/user/1000' })).toBe(
+      '/run/user/1000/bus',
+    )
+    expect(getWaylandSocketPath({ XDG_RUNTIME_DIR: '/run/user/1000' })).toBe(
+      '/run/user/1000/wayland-0',
+    )
+  })
+})
+
+describe('buildDesktopIsolation', () => {
+  const runtimeDir = join(tmpdir(), `srt-desktop-${process.pid}`)
+  const env = {
+    XDG_RUNTIME_DIR: runtimeDir,
+    WAYLAND_DISPLAY: 'wayland-1',
+    DISPLAY: ':0',
+  }
+
+  beforeAll(() => {
+    mkdirSync(runtimeDir, { recursive: true })
+    // Regular files stand in for the sockets
+    writeFileSync(join(runtimeDir, 'bus'), '')
+    writeFileSync(join(runtimeDir, 'wayland-1'), '')
+  })
+
+  afterAll(() => {
+    rmSync(runtimeDir, { recursive: true, force: true })
+  })
+
+  it('hides everything by default', () => {
+    const { bwrapArgs, unsetEnv } = buildDesktopIsolation(
+      undefined,
+      undefined,
+      env,
+    )
+    expect(bwrapArgs.join(' ')).toContain(
+      `--ro-bind /dev/null ${join(runtimeDir, 'bus')}`,
+    )
+    expect(bwrapArgs.join(' ')).toContain(
+      `--ro-bind /dev/null ${join(runtimeDir, 'wayland-1')}`,
+    )
+    expect(unsetEnv).toEqual([
+      'DISPLAY',
+      'XAUTHORITY',
+      'WAYLAND_DISPLAY',
+      'DBUS_SESSION_BUS_ADDRESS',
+    ])
+  })
+
+  it('exposes what is enabled', () => {
+    const { bwrapArgs, unsetEnv } = buildDesktopIsolation(
+      { x11: true, wayland: true, dbus: 'host' },
+      undefined,
+      env,
+    )
+    expect(bwrapArgs).toEqual([])
+    expect(unsetEnv).toEqual([])
+  })
+
+  it('replaces the bus socket with the proxy socket', () => {
+    const { bwrapArgs, unsetEnv } = buildDesktopIsolation(
+      { dbus: 'proxy' },
+      '/tmp/srt-dbus-proxy.sock',
+      env,
+    )
+    const busPath = join(runtimeDir, 'bus')
+    expect(bwrapArgs.join(' ')).toContain(
+      `--bind /tmp/srt-dbus-proxy.sock ${busPath}`,
+    )
+    expect(bwrapArgs.join(' ')).toContain(
+      `--setenv DBUS_SESSION_BUS_ADDRESS unix:path=${busPath}`,
+    )
+    expect(unsetEnv).not.toContain('DBUS_SESSION_BUS_ADDRESS')
+  })
+
+  it('requires a running proxy in proxy mode', () => {
+    expect(() =>
+      buildDesktopIsolation({ dbus: 'proxy' }, undefined, env),
+    ).toThrow('D-Bus proxy')
+  })
+})
+
+describe('desktop isolation in the Linux sandbox', () => {
+  it('removes display variables before the sandbox starts', async () => {
+    if (getPlatform() !== 'linux') {
+      return
+    }
+
+    const result = await wrapCommandWithSandboxLinux({
+      command: 'echo hello',
+      needsNetworkRestriction: false,
+      readConfig: { denyOnly: ['/secret'] },
+      writeConfig: undefined,
+    })
+
+    expect(result).toContain('env -u DISPLAY -u XAUTHORITY')
+  })
+})
@@ -0,0 +1,63 @@
+import { describe, it, expect } from 'bun:test'
+import {
+  formatDoctorReport,
+  getBackendStatuses,
+  hasUsableBackend,
+  runDoctor,
+  type DoctorCheck,
+  type DoctorCheckName,
+} from '../../src/sandbox/doctor.js'
+
+function linuxChecks(missing: DoctorCheckName[]): DoctorCheck[] {
+  const names: DoctorCheckName[] = [
+    'ripgrep',
+    'bwrap',
+    'nsjail',
+    'socat',
+    'user-namespaces',
+    'seccomp',
+    'cgroups',
+  ]
+  return names.map(name =>
+    missing.includes(name)
+      ? { name, ok: false, detail: `${name} not found on PATH` }
+      : { name, ok: true, detail: `/usr/bin/${name}` },
+  )
+}
+
+describe('getBackendStatuses', () => {
+  it('explains why Linux backends are not usable', () => {
+    const backends = getBackendStatuses('linux', linuxChecks(['nsjail']))
+    const bwrap = backends.find(b => b.name === 'bwrap')
+    const nsjail = backends.find(b => b.name === 'nsjail')
+    expect(bwrap?.usable).toBe(true)
+    expect(bwrap?.warnings).toEqual([])
+    expect(nsjail?.usable).toBe(false)
+    expect(nsjail?.reasons).toEqual(['nsjail: nsjail not found on PATH'])
+  })
+
+  it('warns about missing optional features', () => {
+    const backends = getBackendStatuses('linux', linuxChecks(['seccomp']))
+    expect(backends[0]?.usable).toBe(true)
+    expect(backends[0]?.warnings[0]).toContain('Unix socket')
+  })
+
+  it('does not count the degraded backend as isolating', () => {
+    const checks = linuxChecks(['bwrap', 'nsjail'])
+    const report = {
+      platform: 'linux' as const,
+      checks,
+      backends: getBackendStatuses('linux', checks),
+    }
+    expect(report.backends.find(b => b.name === 'degraded')?.usable).toBe(true)
+    expect(hasUsableBackend(report)).toBe(false)
+    expect(formatDoctorReport(report)).toContain('bwrap: not usable')
+  })
+
+  it('reports unsupported platforms', () => {
+    const report = runDoctor('windows')
+    expect(report.backends).toEqual([])
+    expect(hasUsableBackend(report)).toBe(false)
+    expect(formatDoctorReport(report)).toContain('not supported')
+  })
+})
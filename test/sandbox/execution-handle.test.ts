@@ -0,0 +1,75 @@
+import { describe, it, expect, beforeEach, afterEach } from 'bun:test'
+import { existsSync, rmSync } from 'node:fs'
+import { tmpdir } from 'node:os'
+import { join } from 'node:path'
+import type { SandboxRuntimeConfig } from '../../src/sandbox/sandbox-config.js'
+import { SandboxManager } from '../../src/sandbox/sandbox-manager.js'
+import { getPlatform } from '../../src/utils/platform.js'
+
+const config: SandboxRuntimeConfig = {
+  network: { allowedDomains: [], deniedDomains: [] },
+  filesystem: { denyRead: [], allowWrite: ['.'], denyWrite: [] },
+}
+
+describe.skipIf(getPlatform() === 'windows')('execution handles', () => {
+  beforeEach(async () => {
+    // Runs commands unconfined, so the tests need no sandbox dependencies
+    SandboxManager.registerBackend({
+      name: 'test-runner',
+      wrap: async context => context.command,
+    })
+    await SandboxManager.initialize(config)
+  })
+
+  afterEach(async () => {
+    await SandboxManager.reset()
+    SandboxManager.registerBackend(undefined)
+  })
+
+  it('kills the command with SIGKILL', async () => {
+    const execution = SandboxManager.start('sleep 10', { stdio: 'ignore' })
+    setTimeout(() => execution.kill(), 100)
+    expect(await execution.exited).toEqual({ code: null, signal: 'SIGKILL' })
+  })
+
+  it('delivers a signal sent before the command was spawned', async () => {
+    const execution = SandboxManager.start('sleep 10', { stdio: 'ignore' })
+    execution.signal('SIGTERM')
+    expect((await execution.exited).signal).toBe('SIGTERM')
+  })
+
+  it('signals the background processes of the command too', async () => {
+    const execution = SandboxManager.executeStreaming(
+      '(sleep 2; echo late) & echo started; wait',
+    )
+    expect((await execution.stdout.next()).value).toBe('started')
+    execution.signal('SIGTERM')
+    await execution.exited
+
+    // A surviving background process would have kept stdout open until late
+    const rest: string[] = []
+    for await (const line of execution.stdout) {
+      rest.push(line)
+    }
+    expect(rest).toEqual([])
+  })
+
+  it('signals background processes left after the command exited', async () => {
+    const marker = join(tmpdir(), `srt-handle-${process.pid}`)
+    rmSync(marker, { force: true })
+    const execution = SandboxManager.start(`(sleep 1; touch ${marker}) &`, {
+      stdio: 'ignore',
+    })
+    expect(await execution.exited).toEqual({ code: 0, signal: null })
+    execution.kill()
+
+    await new Promise(resolve => setTimeout(resolve, 1500))
+    expect(existsSync(marker)).toBe(false)
+  })
+
+  it('ignores signals sent after the command exited', async () => {
+    const execution = SandboxManager.start('true', { stdio: 'ignore' })
+    expect(await execution.exited).toEqual({ code: 0, signal: null })
+    expect(() => execution.kill()).not.toThrow()
+  })
+})
@@ -0,0 +1,151 @@
+import { describe, it, expect, beforeEach, afterEach } from 'bun:test'
+import {
+  SandboxCommandVetoedError,
+  type SandboxAfterExecuteContext,
+  type SandboxBeforeExecuteContext,
+} from '../../src/sandbox/execution-hooks.js'
+import type { SandboxRuntimeConfig } from '../../src/sandbox/sandbox-config.js'
+import { SandboxManager } from '../../src/sandbox/sandbox-manager.js'
+import { getPlatform } from '../../src/utils/platform.js'
+
+const config: SandboxRuntimeConfig = {
+  network: { allowedDomains: [], deniedDomains: [] },
+  filesystem: { denyRead: [], allowWrite: ['.'], denyWrite: [] },
+  commands: {
+    npm: { allowedDomains: ['registry.npmjs.org'] },
+  },
+}
+
+describe.skipIf(getPlatform() === 'windows')('execution hooks', () => {
+  const removers: Array<() => void> = []
+
+  beforeEach(async () => {
+    // Runs commands unconfined, so the tests need no sandbox dependencies
+    SandboxManager.registerBackend({
+      name: 'test-runner',
+      wrap: async context => context.command,
+    })
+    await SandboxManager.initialize(config)
+  })
+
+  afterEach(async () => {
+    for (const remove of removers.splice(0)) {
+      remove()
+    }
+    await SandboxManager.reset()
+    SandboxManager.registerBackend(undefined)
+  })
+
+  it('calls the hooks with the resolved policy and the result', async () => {
+    const before: SandboxBeforeExecuteContext[] = []
+    const after: SandboxAfterExecuteContext[] = []
+    removers.push(
+      SandboxManager.addHooks({
+        beforeExecute: context => {
+          before.push(context)
+        },
+        afterExecute: context => {
+          after.push(context)
+        },
+      }),
+    )
+
+    await SandboxManager.execute('npm --version >/dev/null 2>&1; exit 2', {
+      stdio: 'ignore',
+      customConfig: { allowPty: true },
+    })
+
+    expect(before[0]?.policy?.network.allowedDomains).toEqual([
+      'registry.npmjs.org',
+    ])
+    expect(before[0]?.policy?.allowPty).toBe(true)
+    expect(after[0]?.result).toEqual({ code: 2, signal: null })
+    expect(after[0]?.error).toBeUndefined()
+    expect(after[0]?.durationMs).toBeGreaterThanOrEqual(0)
+  })
+
+  it('vetoes a command when beforeExecute returns false', async () => {
+    const afterCalls: string[] = []
+    removers.push(
+      SandboxManager.addHooks({
+        beforeExecute: ({ command }) => !command.startsWith('rm '),
+        afterExecute: ({ command }) => {
+          afterCalls.push(command)
+        },
+      }),
+    )
+
+    await expect(
+      SandboxManager.execute('rm -rf ./build', { stdio: 'ignore' }),
+    ).rejects.toBeInstanceOf(SandboxCommandVetoedError)
+    expect(afterCalls).toEqual([])
+  })
+
+  it('reports failures to afterExecute and ignores its errors', async () => {
+    const errors: unknown[] = []
+    removers.push(
+      SandboxManager.addHooks({
+        afterExecute: ({ error }) => {
+          errors.push(error)
+          throw new Error('hook failed')
+        },
+      }),
+    )
+
+    await expect(
+      SandboxManager.execute('sleep 5', {
+        stdio: 'ignore',
+        timeoutSeconds: 0.2,
+      }),
+    ).rejects.toThrow('timed out')
+    expect(errors).toHaveLength(1)
+  })
+
+  it('stops calling removed hooks', async () => {
+    let calls = 0
+    const remove = SandboxManager.addHooks({
+      beforeExecute: () => {
+        calls++
+      },
+    })
+    await SandboxManager.execute('true', { stdio: 'ignore' })
+    remove()
+    await SandboxManager.execute('true', { stdio: 'ignore' })
+    expect(calls).toBe(1)
+  })
+
+  it('passes a copy of the policy that hooks cannot change', async () => {
+    removers.push(
+      SandboxManager.addHooks({
+        beforeExecute: ({ policy }) => {
+          policy?.network.allowedDomains.push('evil.example.com')
+        },
+      }),
+    )
+    await SandboxManager.execute('npm --version', { stdio: 'ignore' })
+    expect(SandboxManager.getConfig()?.network.allowedDomains).toEqual([])
+    expect(SandboxManager.getConfig()?.commands?.npm?.allowedDomains).toEqual([
+      'registry.npmjs.org',
+    ])
+  })
+
+  it('sees a session as its shell and each run', async () => {
+    const commands: string[] = []
+    removers.push(
+      SandboxManager.addHooks({
+        afterExecute: ({ command }) => {
+          commands.push(command)
+        },
+        beforeExecute: ({ command }) => command !== 'rm -rf ./build',
+      }),
+    )
+
+    const session = await SandboxManager.openSession()
+    await session.run('echo hi')
+    await expect(session.run('rm -rf ./build')).rejects.toBeInstanceOf(
+      SandboxCommandVetoedError,
+    )
+    await session.close()
+    expect(commands).toEqual(['echo hi', 'bash'])
+  })
+})
@@ -0,0 +1,128 @@
+import { describe, it, expect, afterEach } from 'bun:test'
+import { PassThrough } from 'node:stream'
+import { OutputLineStream } from '../../src/sandbox/execution-stream.js'
+import type { SandboxRuntimeConfig } from '../../src/sandbox/sandbox-config.js'
+import { SandboxManager } from '../../src/sandbox/sandbox-manager.js'
+import { getPlatform } from '../../src/utils/platform.js'
+
+async function collect(stream: AsyncIterable<string>): Promise<string[]> {
+  const lines: string[] = []
+  for await (const line of stream) {
+    lines.push(line)
+  }
+  return lines
+}
+
+describe('OutputLineStream', () => {
+  it('splits chunks into lines, joining lines split across chunks', async () => {
+    const stream = new OutputLineStream()
+    stream.write('first\nsec')
+    stream.write('ond\r\nthi')
+    stream.write('rd')
+    stream.end()
+    expect(await collect(stream)).toEqual(['first', 'second', 'third'])
+  })
+
+  it('yields lines written while a consumer waits', async () => {
+    const stream = new OutputLineStream()
+    const pending = stream.next()
+    stream.write('late\n')
+    expect(await pending).toEqual({ value: 'late', done: false })
+    stream.end()
+    expect((await stream.next()).done).toBe(true)
+  })
+
+  it('queues consumers waiting at the same time', async () => {
+    const stream = new OutputLineStream()
+    const first = stream.next()
+    const second = stream.next()
+    const third = stream.next()
+    stream.write('a\nb\n')
+    stream.end()
+    expect(await first).toEqual({ value: 'a', done: false })
+    expect(await second).toEqual({ value: 'b', done: false })
+    expect((await third).done).toBe(true)
+  })
+
+  it('pauses its source while too much output is unread', async () => {
+    const source = new PassThrough()
+    const stream = new OutputLineStream(4)
+    stream.readFrom(source)
+    source.write('abc\ndef\n')
+    await new Promise(resolve => setImmediate(resolve))
+    expect(source.isPaused()).toBe(true)
+
+    expect((await stream.next()).value).toBe('abc')
+    expect(source.isPaused()).toBe(false)
+    expect((await stream.next()).value).toBe('def')
+  })
+
+  it('yields a line longer than the high water mark in pieces', async () => {
+    const stream = new OutputLineStream(4)
+    stream.write('abcdefghij')
+    stream.end()
+    expect(await collect(stream)).toEqual(['abcd', 'efgh', 'ij'])
+  })
+
+  it('drops the rest of the output once returned from', async () => {
+    const stream = new OutputLineStream()
+    stream.write('a\nb\n')
+    await stream.return()
+    stream.write('c\n')
+    expect((await stream.next()).done).toBe(true)
+  })
+})
+
+describe('SandboxManager.executeStreaming', () => {
+  const config: SandboxRuntimeConfig = {
+    network: { allowedDomains: [], deniedDomains: [] },
+    filesystem: { denyRead: [], allowWrite: ['.'], denyWrite: [] },
+  }
+
+  afterEach(async () => {
+    await SandboxManager.reset()
+    SandboxManager.registerBackend(undefined)
+  })
+
+  it('streams stdout and stderr lines and resolves with the exit status', async () => {
+    if (getPlatform() === 'windows') {
+      return
+    }
+
+    // Runs commands unconfined, so the test needs no sandbox dependencies
+    SandboxManager.registerBackend({
+      name: 'test-runner',
+      wrap: async context => context.command,
+    })
+    await SandboxManager.initialize(config)
+
+    const execution = SandboxManager.executeStreaming(
+      'echo one; echo oops >&2; echo two; exit 4',
+    )
+    const [stdout, stderr] = await Promise.all([
+      collect(execution.stdout),
+      collect(execution.stderr),
+    ])
+    expect(stdout).toEqual(['one', 'two'])
+    expect(stderr).toEqual(['oops'])
+    expect(await execution.exited).toEqual({ code: 4, signal: null })
+  })
+
+  it('does not wait for output held open by background processes', async () => {
+    if (getPlatform() === 'windows') {
+      return
+    }
+
+    SandboxManager.registerBackend({
+      name: 'test-runner',
+      wrap: async context => context.command,
+    })
+    await SandboxManager.initialize(config)
+
+    const startedAt = Date.now()
+    const execution = SandboxManager.executeStreaming('echo one; sleep 30 &')
+    expect(await execution.exited).toEqual({ code: 0, signal: null })
+    expect(Date.now() - startedAt).toBeLessThan(10_000)
+    expect(await collect(execution.stdout)).toEqual(['one'])
+  })
+})
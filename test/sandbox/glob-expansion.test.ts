@@ -0,0 +1,135 @@
+import { describe, it, expect, beforeAll, afterAll } from 'bun:test'
+import * as fs from 'node:fs'
+import { tmpdir } from 'node:os'
+import { join } from 'node:path'
+import {
+  expandGlobPath,
+  expandGlobPaths,
+  GlobExpansionLimitError,
+} from '../../src/sandbox/glob-expansion.js'
+
+describe('expandGlobPath', () => {
+  let root: string
+
+  function mkdir(...parts: string[]): string {
+    const dir = join(root, ...parts)
+    fs.mkdirSync(dir, { recursive: true })
+    return dir
+  }
+
+  beforeAll(() => {
+    root = fs.realpathSync(fs.mkdtempSync(join(tmpdir(), 'srt-glob-')))
+    mkdir('projects', 'app', 'target')
+    mkdir('projects', 'lib', 'target')
+    mkdir('projects', '.hidden', 'target')
+    mkdir('projects', 'docs')
+    mkdir('repo', 'node_modules', 'dep', 'node_modules')
+    mkdir('repo', 'packages', 'web', 'node_modules')
+    fs.writeFileSync(join(root, 'repo', 'packages', 'web', '.env'), '')
+  })
+
+  afterAll(() => {
+    fs.rmSync(root, { recursive: true, force: true })
+  })
+
+  it('returns rules without globs unchanged', () => {
+    expect(expandGlobPath('~/.ssh')).toEqual(['~/.ssh'])
+    expect(expandGlobPath(join(root, 'missing'))).toEqual([
+      join(root, 'missing'),
+    ])
+    // A trailing /** means the directory itself
+    expect(expandGlobPath(join(root, 'projects/**'))).toEqual([
+      join(root, 'projects'),
+    ])
+  })
+
+  it('expands single-segment globs, including dot directories', () => {
+    expect(expandGlobPath(join(root, 'projects/*/target')).sort()).toEqual(
+      [
+        join(root, 'projects', '.hidden', 'target'),
+        join(root, 'projects', 'app', 'target'),
+        join(root, 'projects', 'lib', 'target'),
+      ].sort(),
+    )
+    expect(expandGlobPath(join(root, 'projects/[al]*'))).toHaveLength(2)
+  })
+
+  it('stops ** at directories that already match', () => {
+    expect(expandGlobPath(join(root, 'repo/**/node_modules')).sort()).toEqual(
+      [
+        join(root, 'repo', 'node_modules'),
+        join(root, 'repo', 'packages', 'web', 'node_modules'),
+      ].sort(),
+    )
+    expect(expandGlobPath(join(root, 'repo/**/.env'))).toEqual([
+      join(root, 'repo', 'packages', 'web', '.env'),
+    ])
+  })
+
+  it('resolves a symlinked parent before the first glob', () => {
+    const link = join(root, 'linked-projects')
+    fs.symlinkSync(join(root, 'projects'), link)
+    try {
+      expect(expandGlobPath(join(link, '*/target')).sort()).toEqual(
+        expandGlobPath(join(root, 'projects/*/target')).sort(),
+      )
+    } finally {
+      fs.unlinkSync(link)
+    }
+  })
+
+  it('does not follow symlinks with ** so loops terminate', () => {
+    const loop = join(root, 'repo', 'packages', 'loop')
+    fs.symlinkSync(join(root, 'repo'), loop)
+    try {
+      const matches = expandGlobPath(join(root, 'repo/**/node_modules'))
+      expect(matches.every(match => !match.includes('/loop/'))).toBe(true)
+      expect(matches).toHaveLength(2)
+    } finally {
+      fs.unlinkSync(loop)
+    }
+  })
+
+  it('returns nothing when no path matches', () => {
+    expect(expandGlobPath(join(root, 'projects/*/dist'))).toEqual([])
+  })
+
+  it('picks up paths created after an earlier expansion', () => {
+    const pattern = join(root, 'projects/*/cache')
+    expect(expandGlobPath(pattern)).toEqual([])
+    const cache = join(root, 'projects', 'app', 'cache')
+    fs.mkdirSync(cache)
+    try {
+      expect(expandGlobPath(pattern)).toEqual([cache])
+    } finally {
+      fs.rmdirSync(cache)
+    }
+    expect(expandGlobPath(pattern)).toEqual([])
+  })
+
+  it('refuses deny rules whose expansion stops at the directory limit', () => {
+    const wide = join(root, 'wide')
+    fs.mkdirSync(wide)
+    for (let i = 0; i < 10_001; i++) {
+      fs.mkdirSync(join(wide, `d${i}`))
+    }
+    try {
+      const pattern = join(wide, '**/.env')
+      expect(() => expandGlobPath(pattern, 'deny')).toThrow(
+        GlobExpansionLimitError,
+      )
+      expect(() => expandGlobPath(pattern, 'allow')).not.toThrow()
+    } finally {
+      fs.rmSync(wide, { recursive: true, force: true })
+    }
+  })
+
+  it('merges the expansions of a list without duplicates', () => {
+    expect(
+      expandGlobPaths([
+        join(root, 'projects/app/target'),
+        join(root, 'projects/a*/target'),
+      ]),
+    ).toEqual([join(root, 'projects', 'app', 'target')])
+  })
+})
@@ -0,0 +1,174 @@
+import { describe, it, expect } from 'bun:test'
+import type { LookupAddress } from 'node:dns'
+import {
+  HostResolver,
+  createPinnedLookup,
+  isCloudMetadataHost,
+  isPrivateAddress,
+} from '../../src/sandbox/host-resolver.js'
+
+function createResolver(
+  records: Record<string, string[]>,
+  ptrRecords: Record<string, string[]> = {},
+  ttlMs = 30_000,
+) {
+  const lookups: string[] = []
+  const resolver = new HostResolver(
+    ttlMs,
+    async host => {
+      lookups.push(host)
+      return (records[host] ?? []).map(address => ({
+        address,
+        family: address.includes(':') ? 6 : 4,
+      }))
+    },
+    async address => ptrRecords[address] ?? [],
+  )
+  return { resolver, lookups }
+}
+
+describe('HostResolver', () => {
+  it('returns IP literals without a lookup', async () => {
+    const { resolver, lookups } = createResolver({})
+
+    expect(await resolver.resolve('10.0.0.1')).toEqual([
+      { address: '10.0.0.1', family: 4 },
+    ])
+    expect(await resolver.resolve('::1')).toEqual([
+      { address: '::1', family: 6 },
+    ])
+    expect(lookups).toEqual([])
+  })
+
+  it('caches resolutions until the TTL expires', async () => {
+    const { resolver, lookups } = createResolver({
+      'example.com': ['93.184.216.34'],
+    })
+
+    await resolver.resolve('example.com')
+    await resolver.resolve('EXAMPLE.com')
+    expect(lookups).toEqual(['example.com'])
+
+    resolver.clear()
+    await resolver.resolve('example.com')
+    expect(lookups).toHaveLength(2)
+  })
+
+  it('re-resolves after the TTL', async () => {
+    const { resolver, lookups } = createResolver(
+      { 'example.com': ['93.184.216.34'] },
+      {},
+      0,
+    )
+
+    await resolver.resolve('example.com')
+    await new Promise(resolve => setTimeout(resolve, 5))
+    await resolver.resolve('example.com')
+    expect(lookups).toHaveLength(2)
+  })
+
+  it('accepts IP targets that forward-confirm to an allowed host', async () => {
+    const { resolver } = createResolver(
+      { 'api.github.com': ['140.82.112.6'] },
+      { '140.82.112.6': ['api.github.com'] },
+    )
+
+    expect(
+      await resolver.verifyIpTarget('140.82.112.6', host =>
+        host.endsWith('github.com'),
+      ),
+    ).toBe('api.github.com')
+  })
+
+  it('rejects IP targets whose PTR name does not resolve back', async () => {
+    // Attacker-controlled PTR record claiming to be an allowed host
+    const { resolver } = createResolver(
+      { 'api.github.com': ['140.82.112.6'] },
+      { '203.0.113.7': ['api.github.com'] },
+    )
+
+    expect(
+      await resolver.verifyIpTarget('203.0.113.7', host =>
+        host.endsWith('github.com'),
+      ),
+    ).toBeUndefined()
+  })
+
+  it('rejects IP targets that reverse-map to disallowed hosts', async () => {
+    const { resolver } = createResolver(
+      { 'evil.example': ['198.51.100.1'] },
+      { '198.51.100.1': ['evil.example'] },
+    )
+
+    expect(
+      await resolver.verifyIpTarget('198.51.100.1', host =>
+        host.endsWith('github.com'),
+      ),
+    ).toBeUndefined()
+  })
+})
+
+describe('createPinnedLookup', () => {
+  it('always returns the pinned address', () => {
+    const lookup = createPinnedLookup('93.184.216.34')
+
+    let single: unknown
+    lookup('example.com', {}, (_err, address) => {
+      single = address
+    })
+    expect(single).toBe('93.184.216.34')
+
+    let all: LookupAddress[] | undefined
+    lookup('example.com', { all: true }, (_err, addresses) => {
+      all = addresses as LookupAddress[]
+    })
+    expect(all).toEqual([{ address: '93.184.216.34', family: 4 }])
+  })
+})
+
+describe('isPrivateAddress', () => {
+  it('flags private, loopback and link-local addresses', () => {
+    for (const address of [
+      '10.1.2.3',
+      '127.0.0.1',
+      '169.254.169.254',
+      '172.16.0.1',
+      '192.168.1.1',
+      '100.64.0.1',
+      '0.0.0.0',
+      '::1',
+      'fd00::1',
+      'fe80::1',
+    ]) {
+      expect(isPrivateAddress(address)).toBe(true)
+    }
+  })
+
+  it('checks IPv4-mapped IPv6 addresses as IPv4', () => {
+    expect(isPrivateAddress('::ffff:127.0.0.1')).toBe(true)
+    expect(isPrivateAddress('::ffff:7f00:1')).toBe(true)
+    expect(isPrivateAddress('::ffff:93.184.216.34')).toBe(false)
+  })
+
+  it('allows public addresses and ignores hostnames', () => {
+    expect(isPrivateAddress('93.184.216.34')).toBe(false)
+    expect(isPrivateAddress('2606:2800:220:1::1')).toBe(false)
+    expect(isPrivateAddress('localhost')).toBe(false)
+  })
+})
+
+describe('isCloudMetadataHost', () => {
+  it('matches metadata addresses, including IPv4-mapped forms', () => {
+    expect(isCloudMetadataHost('169.254.169.254')).toBe(true)
+    expect(isCloudMetadataHost('::ffff:169.254.169.254')).toBe(true)
+    expect(isCloudMetadataHost('fd00:ec2::254')).toBe(true)
+    expect(isCloudMetadataHost('100.100.100.200')).toBe(true)
+    expect(isCloudMetadataHost('169.254.169.253')).toBe(false)
+  })
+
+  it('matches metadata hostnames case-insensitively', () => {
+    expect(isCloudMetadataHost('metadata.google.internal')).toBe(true)
+    expect(isCloudMetadataHost('Metadata.Google.Internal.')).toBe(true)
+    expect(isCloudMetadataHost('metadata.example.com')).toBe(false)
+  })
+})
@@ -0,0 +1,142 @@
+import { describe, it, expect, beforeAll, afterAll } from 'bun:test'
+import {
+  mkdirSync,
+  realpathSync,
+  rmSync,
+  symlinkSync,
+  writeFileSync,
+} from 'node:fs'
+import { tmpdir } from 'node:os'
+import { join } from 'node:path'
+import {
+  buildLandlockArgs,
+  expandAllowedPaths,
+  isLandlockSupported,
+} from '../../src/sandbox/landlock-sandbox.js'
+import { wrapCommandWithSandboxLinux } from '../../src/sandbox/linux-sandbox-utils.js'
+import { getPlatform } from '../../src/utils/platform.js'
+
+describe('Landlock rule generation', () => {
+  // Resolve symlinks (e.g. /var on macOS) since expansion never follows them
+  const TEST_DIR = join(realpathSync(tmpdir()), `landlock-rules-${Date.now()}`)
+
+  beforeAll(() => {
+    mkdirSync(join(TEST_DIR, 'project', 'src'), { recursive: true })
+    mkdirSync(join(TEST_DIR, 'project', '.git', 'hooks'), { recursive: true })
+    mkdirSync(join(TEST_DIR, 'secrets'), { recursive: true })
+    writeFileSync(join(TEST_DIR, 'project', '.git', 'HEAD'), 'ref')
+    writeFileSync(join(TEST_DIR, 'project', 'README.md'), 'readme')
+    symlinkSync(join(TEST_DIR, 'secrets'), join(TEST_DIR, 'project', 'link'))
+  })
+
+  afterAll(() => {
+    rmSync(TEST_DIR, { recursive: true, force: true })
+  })
+
+  it('returns the root when nothing is excluded', () => {
+    expect(expandAllowedPaths(TEST_DIR, [])).toEqual([TEST_DIR])
+    expect(expandAllowedPaths(TEST_DIR, ['/somewhere/else'])).toEqual([
+      TEST_DIR,
+    ])
+  })
+
+  it('returns nothing when the root itself is excluded', () => {
+    expect(expandAllowedPaths(TEST_DIR, [TEST_DIR])).toEqual([])
+  })
+
+  it('allows siblings along the path to an excluded directory', () => {
+    const allowed = expandAllowedPaths(TEST_DIR, [
+      join(TEST_DIR, 'project', '.git', 'hooks'),
+    ])
+
+    expect(allowed.sort()).toEqual(
+      [
+        join(TEST_DIR, 'secrets'),
+        join(TEST_DIR, 'project', 'src'),
+        join(TEST_DIR, 'project', 'README.md'),
+        join(TEST_DIR, 'project', '.git', 'HEAD'),
+      ].sort(),
+    )
+  })
+
+  it('skips symlinks so they cannot widen access', () => {
+    const allowed = expandAllowedPaths(TEST_DIR, [
+      join(TEST_DIR, 'project', 'README.md'),
+    ])
+
+    expect(allowed).not.toContain(join(TEST_DIR, 'project', 'link'))
+    expect(allowed).not.toContain(join(TEST_DIR, 'project', 'README.md'))
+    expect(allowed).toContain(join(TEST_DIR, 'project', '.git'))
+  })
+
+  it('builds no arguments when nothing is restricted', () => {
+    expect(
+      buildLandlockArgs({ readDenyPaths: [], writeDenyPaths: [] }),
+    ).toEqual([])
+  })
+
+  it('builds write rules from allowed paths minus denied paths', () => {
+    const args = buildLandlockArgs({
+      readDenyPaths: [],
+      writeAllowPaths: [join(TEST_DIR, 'project')],
+      writeDenyPaths: [join(TEST_DIR, 'project', '.git')],
+    })
+
+    const writePaths = args.filter((_, i) => args[i - 1] === '--write')
+    expect(args).not.toContain('--read')
+    expect(writePaths.sort()).toEqual(
+      [
+        '/dev',
+        join(TEST_DIR, 'project', 'src'),
+        join(TEST_DIR, 'project', 'README.md'),
+      ].sort(),
+    )
+  })
+
+  it('keeps /dev writable even when no write paths are allowed', () => {
+    const args = buildLandlockArgs({
+      readDenyPaths: [],
+      writeAllowPaths: [],
+      writeDenyPaths: [],
+    })
+
+    expect(args).toEqual(['--write', '/dev'])
+  })
+
+  it('builds read rules that exclude denied paths', () => {
+    const secrets = join(TEST_DIR, 'secrets')
+    const args = buildLandlockArgs({
+      readDenyPaths: [secrets],
+      writeDenyPaths: [],
+    })
+
+    const readPaths = args.filter((_, i) => args[i - 1] === '--read')
+    expect(readPaths.length).toBeGreaterThan(0)
+    expect(readPaths).not.toContain(secrets)
+    expect(readPaths).not.toContain('/')
+    expect(readPaths).toContain(join(TEST_DIR, 'project'))
+  })
+
+  it('refuses read rules that would leave nothing readable', () => {
+    expect(() =>
+      buildLandlockArgs({ readDenyPaths: ['/'], writeDenyPaths: [] }),
+    ).toThrow(/cannot enforce denyRead/)
+  })
+})
+
+describe.skipIf(getPlatform() !== 'linux' || isLandlockSupported())(
+  'explicit Landlock backend without Landlock',
+  () => {
+    it('fails instead of falling back to bind mounts', async () => {
+      await expect(
+        wrapCommandWithSandboxLinux({
+          command: 'true',
+          needsNetworkRestriction: false,
+          readConfig: { denyOnly: [] },
+          writeConfig: { allowOnly: [], denyWithinAllow: [] },
+          filesystemBackend: 'landlock',
+        }),
+      ).rejects.toThrow('Landlock')
+    })
+  },
+)
@@ -0,0 +1,345 @@
+import { describe, it, expect } from 'bun:test'
+import { spawnSync } from 'node:child_process'
+import { existsSync, statSync, writeFileSync } from 'node:fs'
+import { dirname } from 'node:path'
+import { getPlatform } from '../../src/utils/platform.js'
+import {
+  LinuxViolationMonitor,
+  parseStraceFileAccess,
+  parseStraceLine,
+  parseStraceProcessEvent,
+} from '../../src/sandbox/linux-violation-monitor.js'
+import type { SandboxViolationEvent } from '../../src/sandbox/macos-sandbox-utils.js'
+import { wrapCommandWithSandboxLinux } from '../../src/sandbox/linux-sandbox-utils.js'
+
+function isStraceAvailable(): boolean {
+  return spawnSync('which', ['strace'], { stdio: 'ignore' }).status === 0
+}
+
+describe('parseStraceLine', () => {
+  it('reports denied file access with its path', () => {
+    expect(
+      parseStraceLine(
+        'openat(AT_FDCWD, "/etc/shadow", O_RDONLY|O_CLOEXEC) = -1 EACCES (Permission denied)',
+      ),
+    ).toEqual({
+      syscall: 'openat',
+      target: '/etc/shadow',
+      errno: 'EACCES',
+      access: 'read',
+    })
+    expect(
+      parseStraceLine(
+        'mkdir("/home/user/.ssh/x", 0777) = -1 EROFS (Read-only file system)',
+      ),
+    ).toEqual({
+      syscall: 'mkdir',
+      target: '/home/user/.ssh/x',
+      errno: 'EROFS',
+      access: 'write',
+    })
+  })
+
+  it('reports blocked connects with address and port', () => {
+    expect(
+      parseStraceLine(
+        'connect(3, {sa_family=AF_INET, sin_port=htons(443), sin_addr=inet_addr("93.184.216.34")}, 16) = -1 ENETUNREACH (Network is unreachable)',
+      ),
+    ).toEqual({
+      syscall: 'connect',
+      target: '93.184.216.34:443',
+      errno: 'ENETUNREACH',
+    })
+    expect(
+      parseStraceLine(
+        'socket(AF_UNIX, SOCK_STREAM|SOCK_CLOEXEC, 0) = -1 EPERM (Operation not permitted)',
+      ),
+    ).toEqual({ syscall: 'socket', target: 'AF_UNIX', errno: 'EPERM' })
+  })
+
+  it('ignores failures the sandbox did not cause', () => {
+    expect(
+      parseStraceLine(
+        'openat(AT_FDCWD, "/missing", O_RDONLY) = -1 ENOENT (No such file or directory)',
+      ),
+    ).toBeUndefined()
+    expect(
+      parseStraceLine(
+        'stat("/tmp", {st_mode=S_IFDIR|S_ISVTX|0777, st_size=4096, ...}) = 0',
+      ),
+    ).toBeUndefined()
+  })
+})
+
+describe('parseStraceFileAccess', () => {
+  it('classifies opens by their flags', () => {
+    expect(
+      parseStraceFileAccess(
+        'openat(AT_FDCWD, "/etc/hosts", O_RDONLY|O_CLOEXEC) = 3',
+      ),
+    ).toEqual({ syscall: 'openat', path: '/etc/hosts', access: 'read' })
+    expect(
+      parseStraceFileAccess(
+        'openat(AT_FDCWD, "/tmp/out", O_WRONLY|O_CREAT|O_TRUNC, 0666) = 4',
+      ),
+    ).toEqual({ syscall: 'openat', path: '/tmp/out', access: 'write' })
+  })
+
+  it('reports execs as reads and modifications as writes', () => {
+    expect(
+      parseStraceFileAccess(
+        'execve("/usr/bin/cat", ["cat", "/etc/hosts"], 0x7ffd /* 20 vars */) = 0',
+      ),
+    ).toEqual({ syscall: 'execve', path: '/usr/bin/cat', access: 'read' })
+    expect(
+      parseStraceFileAccess('unlinkat(AT_FDCWD, "/tmp/out", 0) = 0'),
+    ).toEqual({ syscall: 'unlinkat', path: '/tmp/out', access: 'write' })
+  })
+
+  it('ignores metadata lookups and failed syscalls', () => {
+    expect(
+      parseStraceFileAccess(
+        'newfstatat(AT_FDCWD, "/tmp", {st_mode=S_IFDIR|0777, ...}, 0) = 0',
+      ),
+    ).toBeUndefined()
+    expect(
+      parseStraceFileAccess(
+        'openat(AT_FDCWD, "/etc/shadow", O_RDONLY) = -1 EACCES (Permission denied)',
+      ),
+    ).toBeUndefined()
+  })
+})
+
+describe('parseStraceProcessEvent', () => {
+  it('reports execs with the executable and argv', () => {
+    expect(
+      parseStraceProcessEvent(
+        'execve("/usr/bin/cat", ["cat", "/etc/hosts"], 0x7ffd /* 20 vars */) = 0',
+      ),
+    ).toEqual({
+      kind: 'exec',
+      executable: '/usr/bin/cat',
+      argv: ['cat', '/etc/hosts'],
+    })
+  })
+
+  it('reports forks with the child pid', () => {
+    expect(
+      parseStraceProcessEvent(
+        'clone(child_stack=NULL, flags=CLONE_CHILD_CLEARTID|SIGCHLD, child_tidptr=0x7f) = 12',
+      ),
+    ).toEqual({ kind: 'fork', child: 12 })
+    expect(parseStraceProcessEvent('vfork() = 13')).toEqual({
+      kind: 'fork',
+      child: 13,
+    })
+  })
+
+  it('ignores failed execs and other syscalls', () => {
+    expect(
+      parseStraceProcessEvent(
+        'execve("/nope", ["nope"], 0x7ffd /* 20 vars */) = -1 ENOENT (No such file or directory)',
+      ),
+    ).toBeUndefined()
+    expect(
+      parseStraceProcessEvent('openat(AT_FDCWD, "/etc/hosts", O_RDONLY) = 3'),
+    ).toBeUndefined()
+  })
+})
+
+describe('LinuxViolationMonitor', () => {
+  it('reports denials from trace files with pid and command', () => {
+    if (getPlatform() !== 'linux' || !isStraceAvailable()) {
+      return
+    }
+
+    const violations: SandboxViolationEvent[] = []
+    const monitor = LinuxViolationMonitor.start(v => violations.push(v), {
+      '*': ['/ignored'],
+    })
+    expect(monitor).toBeDefined()
+    if (!monitor) return
+
+    try {
+      const prefix = monitor.registerCommand('cat /etc/shadow')
+      writeFileSync(
+        `${prefix}.4242`,
+        [
+          'openat(AT_FDCWD, "/etc/shadow", O_RDONLY) = -1 EACCES (Permission denied)',
+          'openat(AT_FDCWD, "/ignored/file", O_RDONLY) = -1 EACCES (Permission denied)',
+          'openat(AT_FDCWD, "/partial", O_RDONLY) = -1 EACC',
+        ].join('\n'),
+      )
+      monitor.poll()
+
+      expect(violations).toHaveLength(1)
+      expect(violations[0]!.line).toBe('deny(EACCES) openat /etc/shadow')
+      expect(violations[0]!.pid).toBe(4242)
+      expect(violations[0]!.path).toBe('/etc/shadow')
+      expect(violations[0]!.command).toBe('cat /etc/shadow')
+    } finally {
+      monitor.stop()
+    }
+  })
+
+  it('keeps each command in its own directory and deletes it on release', () => {
+    if (getPlatform() !== 'linux' || !isStraceAvailable()) {
+      return
+    }
+
+    const violations: SandboxViolationEvent[] = []
+    const monitor = LinuxViolationMonitor.start(v => violations.push(v))
+    if (!monitor) return
+
+    try {
+      const first = monitor.registerCommand('cat a')!
+      const second = monitor.registerCommand('cat b')!
+      expect(dirname(first)).not.toBe(dirname(second))
+      expect(statSync(dirname(first)).mode & 0o777).toBe(0o700)
+
+      // Lines written before the release are still reported
+      writeFileSync(
+        `${first}.5`,
+        'openat(AT_FDCWD, "/a", O_RDONLY) = -1 EACCES (Permission denied)\n',
+      )
+      monitor.releaseCommand(`strace -ff -o ${first} sh -c 'cat a'`)
+      expect(violations.map(v => v.command)).toEqual(['cat a'])
+      expect(existsSync(dirname(first))).toBe(false)
+      expect(existsSync(dirname(second))).toBe(true)
+    } finally {
+      monitor.stop()
+    }
+  })
+})
+
+describe('LinuxViolationMonitor audit mode', () => {
+  it('reports allowed file accesses alongside denials', () => {
+    if (getPlatform() !== 'linux' || !isStraceAvailable()) {
+      return
+    }
+
+    const denials: SandboxViolationEvent[] = []
+    const accesses: SandboxViolationEvent[] = []
+    const monitor = LinuxViolationMonitor.start(
+      v => denials.push(v),
+      undefined,
+      v => accesses.push(v),
+    )
+    expect(monitor).toBeDefined()
+    if (!monitor) return
+
+    try {
+      const prefix = monitor.registerCommand('cp a b')
+      writeFileSync(
+        `${prefix}.7`,
+        [
+          'openat(AT_FDCWD, "/work/a", O_RDONLY) = 3',
+          'openat(AT_FDCWD, "/work/b", O_WRONLY|O_CREAT|O_TRUNC, 0644) = 4',
+          'openat(AT_FDCWD, "/etc/shadow", O_RDONLY) = -1 EACCES (Permission denied)',
+          '',
+        ].join('\n'),
+      )
+      monitor.poll()
+
+      // Allowed accesses never reach the violation callback
+      expect(accesses.map(e => e.line)).toEqual([
+        'allow(read) openat /work/a',
+        'allow(write) openat /work/b',
+      ])
+      expect(denials.map(e => e.line)).toEqual([
+        'deny(EACCES) openat /etc/shadow',
+      ])
+      expect(accesses[1]!.type).toBe('file-access')
+      expect(accesses[1]!.access).toBe('write')
+      expect(accesses[1]!.pid).toBe(7)
+      expect(denials[0]!.type).toBeUndefined()
+    } finally {
+      monitor.stop()
+    }
+  })
+
+  it('attributes events to the executable and parent chain', () => {
+    if (getPlatform() !== 'linux' || !isStraceAvailable()) {
+      return
+    }
+
+    const events: SandboxViolationEvent[] = []
+    const monitor = LinuxViolationMonitor.start(
+      v => events.push(v),
+      undefined,
+      () => {},
+    )
+    if (!monitor) return
+
+    try {
+      const prefix = monitor.registerCommand('make')
+      // The child's file is written first; attribution must not depend on it
+      writeFileSync(
+        `${prefix}.3`,
+        [
+          'execve("/usr/bin/cc", ["cc", "-c", "x.c"], 0x7ffd /* 5 vars */) = 0',
+          'openat(AT_FDCWD, "/etc/shadow", O_RDONLY) = -1 EACCES (Permission denied)',
+          '',
+        ].join('\n'),
+      )
+      writeFileSync(
+        `${prefix}.2`,
+        [
+          'execve("/usr/bin/make", ["make"], 0x7ffd /* 5 vars */) = 0',
+          'vfork() = 3',
+          '',
+        ].join('\n'),
+      )
+      monitor.poll()
+
+      const denial = events.find(e => e.type === undefined)!
+      expect(denial.pid).toBe(3)
+      expect(denial.processName).toBe('cc')
+      expect(denial.executable).toBe('/usr/bin/cc')
+      expect(denial.argv).toEqual(['cc', '-c', 'x.c'])
+      expect(denial.parents).toEqual([
+        { pid: 2, executable: '/usr/bin/make', argv: ['make'] },
+      ])
+    } finally {
+      monitor.stop()
+    }
+  })
+})
+
+describe('violation tracing in the Linux sandbox', () => {
+  it('runs the command under strace with the trace directory bound', async () => {
+    if (getPlatform() !== 'linux') {
+      return
+    }
+
+    const result = await wrapCommandWithSandboxLinux({
+      command: 'echo hello',
+      needsNetworkRestriction: false,
+      readConfig: { denyOnly: ['/secret'] },
+      writeConfig: undefined,
+      violationTracePath: '/tmp/srt-trace-test/cmd0',
+    })
+
+    expect(result).toContain('strace -ff')
+    expect(result).toContain('status=failed')
+    expect(result).toContain('--bind /tmp/srt-trace-test /tmp/srt-trace-test')
+  })
+
+  it('traces successful syscalls in audit mode', async () => {
+    if (getPlatform() !== 'linux') {
+      return
+    }
+
+    const result = await wrapCommandWithSandboxLinux({
+      command: 'echo hello',
+      needsNetworkRestriction: false,
+      readConfig: { denyOnly: ['/secret'] },
+      writeConfig: undefined,
+      violationTracePath: '/tmp/srt-trace-test/cmd0',
+      auditFileAccess: true,
+    })
+
+    expect(result).toContain('strace -ff')
+    expect(result).not.toContain('status=failed')
+  })
+})
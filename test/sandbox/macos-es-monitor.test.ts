@@ -0,0 +1,129 @@
+import { describe, it, expect } from 'bun:test'
+import shellquote from 'shell-quote'
+import {
+  EndpointSecurityEventHandler,
+  getEsFileAccess,
+} from '../../src/sandbox/macos-es-monitor.js'
+import {
+  wrapCommandWithSandboxMacOS,
+  type SandboxViolationEvent,
+} from '../../src/sandbox/macos-sandbox-utils.js'
+
+/**
+ * The profile file of this session for a command
+ */
+function getProfilePath(command: string): string {
+  const args = shellquote.parse(
+    wrapCommandWithSandboxMacOS({
+      command,
+      needsNetworkRestriction: true,
+      readConfig: undefined,
+      writeConfig: undefined,
+    }),
+  )
+  return String(args[args.indexOf('-f') + 1])
+}
+
+function proc(pid: number, path = '/bin/sh') {
+  return { audit_token: { pid }, executable: { path } }
+}
+
+describe('getEsFileAccess', () => {
+  it('classifies opens by their fflag', () => {
+    expect(
+      getEsFileAccess({
+        event: { open: { file: { path: '/etc/hosts' }, fflag: 1 } },
+      }),
+    ).toEqual({ syscall: 'open', path: '/etc/hosts', access: 'read' })
+    expect(
+      getEsFileAccess({
+        event: { open: { file: { path: '/tmp/out' }, fflag: 3 } },
+      }),
+    ).toEqual({ syscall: 'open', path: '/tmp/out', access: 'write' })
+  })
+
+  it('joins the directory and name of new files', () => {
+    expect(
+      getEsFileAccess({
+        event: {
+          create: {
+            destination: {
+              new_path: { dir: { path: '/tmp' }, filename: 'new.txt' },
+            },
+          },
+        },
+      }),
+    ).toEqual({ syscall: 'create', path: '/tmp/new.txt', access: 'write' })
+  })
+})
+
+describe('EndpointSecurityEventHandler', () => {
+  it('reports file events of the sandboxed process tree only', () => {
+    const events: SandboxViolationEvent[] = []
+    const handler = new EndpointSecurityEventHandler(e => events.push(e))
+
+    handler.handle({
+      process: proc(100, '/usr/bin/sandbox-exec'),
+      event: {
+        exec: {
+          target: proc(100, '/usr/bin/sandbox-exec'),
+          args: [
+            'sandbox-exec',
+            '-f',
+            getProfilePath('cat /etc/hosts'),
+            'bash',
+          ],
+        },
+      },
+    })
+    handler.handle({
+      process: proc(100),
+      event: { fork: { child: proc(101) } },
+    })
+    handler.handle({
+      process: proc(101, '/bin/cat'),
+      event: { open: { file: { path: '/etc/hosts' }, fflag: 1 } },
+    })
+    // Unrelated process
+    handler.handle({
+      process: proc(555, '/usr/bin/vim'),
+      event: { open: { file: { path: '/etc/passwd' }, fflag: 1 } },
+    })
+    handler.handle({ process: proc(101), event: { exit: {} } })
+    handler.handle({
+      process: proc(101, '/bin/cat'),
+      event: { open: { file: { path: '/after/exit' }, fflag: 1 } },
+    })
+
+    expect(events).toHaveLength(1)
+    expect(events[0]!.line).toBe('allow(read) open /etc/hosts')
+    expect(events[0]!.pid).toBe(101)
+    expect(events[0]!.processName).toBe('cat')
+    expect(events[0]!.command).toBe('cat /etc/hosts')
+    expect(events[0]!.type).toBe('file-access')
+  })
+
+  it('ignores sandbox-exec runs of other sessions', () => {
+    const events: SandboxViolationEvent[] = []
+    const handler = new EndpointSecurityEventHandler(e => events.push(e))
+
+    handler.handle({
+      process: proc(200, '/usr/bin/sandbox-exec'),
+      event: {
+        exec: {
+          args: [
+            'sandbox-exec',
+            '-p',
+            '(deny default (with message "CMD64_bHM=_END__other_SBX"))',
+          ],
+        },
+      },
+    })
+    handler.handle({
+      process: proc(200),
+      event: { open: { file: { path: '/etc/hosts' }, fflag: 1 } },
+    })
+
+    expect(events).toHaveLength(0)
+  })
+})
@@ -0,0 +1,103 @@
+import { describe, it, expect } from 'bun:test'
+import {
+  mkdtempSync,
+  realpathSync,
+  rmSync,
+  symlinkSync,
+  writeFileSync,
+} from 'node:fs'
+import { tmpdir } from 'node:os'
+import { join } from 'node:path'
+import { generateNetworkRules } from '../../src/sandbox/macos-sandbox-utils.js'
+
+describe('generateNetworkRules', () => {
+  it('allows all networking without restrictions', () => {
+    expect(generateNetworkRules({ needsNetworkRestriction: false })).toEqual([
+      '(allow network*)',
+    ])
+  })
+
+  it('only allows outbound TCP to the proxy ports', () => {
+    const rules = generateNetworkRules({
+      needsNetworkRestriction: true,
+      httpProxyPort: 3128,
+      socksProxyPort: 1080,
+    })
+
+    expect(rules).toEqual([
+      '(allow network-outbound (remote tcp "localhost:3128"))',
+      '(allow network-outbound (remote tcp "localhost:1080"))',
+    ])
+    expect(rules.join('\n')).not.toContain('remote ip "localhost:3128"')
+    expect(rules.join('\n')).not.toContain('network-bind')
+  })
+
+  it('denies everything when no domain is allowed', () => {
+    expect(generateNetworkRules({ needsNetworkRestriction: true })).toEqual(
+      [],
+    )
+  })
+
+  it('matches loopback connections on the remote address', () => {
+    const rules = generateNetworkRules({
+      needsNetworkRestriction: true,
+      allowLocalBinding: true,
+    })
+
+    expect(rules).toContain('(allow network-bind (local ip "localhost:*"))')
+    expect(rules).toContain(
+      '(allow network-outbound (remote ip "localhost:*"))',
+    )
+    expect(rules).not.toContain(
+      '(allow network-outbound (local ip "localhost:*"))',
+    )
+  })
+
+  it('adds DNS rules only when allowed', () => {
+    const without = generateNetworkRules({ needsNetworkRestriction: true })
+    expect(without.join('\n')).not.toContain('dnssd')
+
+    const withDns = generateNetworkRules({
+      needsNetworkRestriction: true,
+      allowDns: true,
+    })
+    expect(withDns).toContain(
+      '(allow mach-lookup (global-name "com.apple.dnssd.service"))',
+    )
+    expect(withDns).toContain('(allow network-outbound (remote udp "*:53"))')
+  })
+
+  it('allows each configured Unix socket for bind and connect', () => {
+    const rules = generateNetworkRules({
+      needsNetworkRestriction: true,
+      allowUnixSockets: ['/nonexistent/agent.sock', '/nonexistent/run/*.sock'],
+    })
+
+    expect(rules).toEqual([
+      '(allow network* (local unix-socket (path-literal "/nonexistent/agent.sock")) ' +
+        '(remote unix-socket (path-literal "/nonexistent/agent.sock")))',
+      expect.stringMatching(
+        /^\(allow network\* \(local unix-socket \(path-regex "\^\/nonexistent\/run\/.*"\)\) \(remote unix-socket \(path-regex/,
+      ),
+    ])
+  })
+
+  it('allows a symlinked socket under both names', () => {
+    const dir = mkdtempSync(join(tmpdir(), 'srt-sock-'))
+    const target = join(dir, 'real.sock')
+    const link = join(dir, 'link.sock')
+    writeFileSync(target, '')
+    symlinkSync(target, link)
+    try {
+      const rules = generateNetworkRules({
+        needsNetworkRestriction: true,
+        allowUnixSockets: [link],
+      }).join('\n')
+
+      expect(rules).toContain(`(path-literal "${realpathSync(target)}")`)
+      expect(rules).toContain(`(path-literal "${link}")`)
+    } finally {
+      rmSync(dir, { recursive: true, force: true })
+    }
+  })
+})
@@ -0,0 +1,34 @@
+import { describe, it, expect } from 'bun:test'
+import { spawn } from 'node:child_process'
+import { getMacOSProcessAttribution } from '../../src/sandbox/macos-process-info.js'
+
+describe('getMacOSProcessAttribution', () => {
+  it('describes a running process and stops at this process', async () => {
+    const child = spawn('sleep', ['5'])
+    try {
+      await new Promise(resolve => child.once('spawn', resolve))
+      const attribution = await getMacOSProcessAttribution(child.pid!)
+
+      expect(attribution.executable).toContain('sleep')
+      expect(attribution.commandLine).toBe('sleep 5')
+      expect(attribution.parents).toEqual([])
+    } finally {
+      child.kill()
+    }
+  })
+
+  it('returns nothing for processes that have exited', async () => {
+    expect(await getMacOSProcessAttribution(2 ** 22 + 1)).toEqual({})
+  })
+
+  it('keeps arguments containing spaces whole', async () => {
+    const child = spawn('sleep', ['5'], { argv0: 'sleep  with spaces' })
+    try {
+      await new Promise(resolve => child.once('spawn', resolve))
+      const attribution = await getMacOSProcessAttribution(child.pid!)
+      expect(attribution.commandLine).toBe('sleep  with spaces 5')
+    } finally {
+      child.kill()
+    }
+  })
+})
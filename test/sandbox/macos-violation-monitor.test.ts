@@ -0,0 +1,101 @@
+import { describe, it, expect } from 'bun:test'
+import {
+  createMacOSLogStreamHandler,
+  parseMacOSViolationDetails,
+  wrapCommandWithSandboxMacOS,
+  type SandboxViolationEvent,
+} from '../../src/sandbox/macos-sandbox-utils.js'
+import { readMacOSProfile } from './read-macos-profile.js'
+
+/**
+ * The log tag this session puts in the profile rules for a command
+ */
+function getLogTag(command: string): string {
+  const profile = readMacOSProfile(
+    wrapCommandWithSandboxMacOS({
+      command,
+      needsNetworkRestriction: true,
+      readConfig: undefined,
+      writeConfig: undefined,
+    }),
+  )
+  const tag = /CMD64_\S+?_SBX/.exec(profile)?.[0]
+  if (!tag) {
+    throw new Error('no log tag in the generated profile')
+  }
+  return tag
+}
+
+describe('parseMacOSViolationDetails', () => {
+  it('extracts the process, pid, operation and target', () => {
+    expect(
+      parseMacOSViolationDetails(
+        'curl(4242) deny(1) network-outbound 93.184.216.34:443',
+      ),
+    ).toEqual({
+      processName: 'curl',
+      pid: 4242,
+      operation: 'network-outbound',
+      target: '93.184.216.34:443',
+    })
+    expect(
+      parseMacOSViolationDetails(
+        'Google Chrome Helper(77) deny(1) file-read-data /Users/me/.ssh/id_rsa',
+      )?.processName,
+    ).toBe('Google Chrome Helper')
+  })
+
+  it('rejects lines that are not violations', () => {
+    expect(parseMacOSViolationDetails('Filtering the log data')).toBeUndefined()
+  })
+})
+
+describe('createMacOSLogStreamHandler', () => {
+  it('attributes violations to the offending process', () => {
+    const tag = getLogTag('cat ~/.ssh/id_rsa')
+    const violations: SandboxViolationEvent[] = []
+    const handle = createMacOSLogStreamHandler(v => violations.push(v))
+
+    handle(
+      '2026-01-01 10:00:00.000 E  kernel[0:1] (Sandbox) Sandbox: cat(4242) deny(1) file-read-data /Users/me/.ssh/id_rsa\n',
+    )
+    handle(`${tag}\n`)
+
+    expect(violations).toHaveLength(1)
+    expect(violations[0]!.pid).toBe(4242)
+    expect(violations[0]!.processName).toBe('cat')
+    expect(violations[0]!.path).toBe('/Users/me/.ssh/id_rsa')
+    expect(violations[0]!.command).toBe('cat ~/.ssh/id_rsa')
+  })
+
+  it('drops violations without a tag from this session', () => {
+    const tag = getLogTag('ls')
+    const violations: SandboxViolationEvent[] = []
+    const handle = createMacOSLogStreamHandler(v => violations.push(v))
+
+    handle(
+      [
+        'kernel (Sandbox) Sandbox: Safari(12) deny(1) file-read-data /private/var/x',
+        'CMD64_bHM=_END__othersession_SBX',
+        'kernel (Sandbox) Sandbox: ls(13) deny(1) file-read-data /secret',
+        tag,
+        '',
+      ].join('\n'),
+    )
+
+    expect(violations.map(v => v.processName)).toEqual(['ls'])
+  })
+
+  it('handles lines split across chunks', () => {
+    const tag = getLogTag('touch /etc/x')
+    const violations: SandboxViolationEvent[] = []
+    const handle = createMacOSLogStreamHandler(v => violations.push(v))
+
+    handle('kernel (Sandbox) Sandbox: touch(9) deny(1) file-wr')
+    handle(`ite-create /etc/x\n${tag.slice(0, 10)}`)
+    handle(`${tag.slice(10)}\n`)
+
+    expect(violations).toHaveLength(1)
+    expect(violations[0]!.line).toBe('touch(9) deny(1) file-write-create /etc/x')
+  })
+})
@@ -1,7 +1,7 @@
 import { describe, it, expect, beforeAll, afterAll, beforeEach } from 'bun:test'
 import { spawnSync } from 'node:child_process'
 import { mkdirSync, rmSync, writeFileSync, readFileSync } from 'node:fs'
-import { tmpdir } from 'node:os'
+import { homedir, tmpdir } from 'node:os'
 import { join } from 'node:path'
 import { getPlatform } from '../../src/utils/platform.js'
 import {
@@ -48,6 +48,7 @@ describe('Mandatory Deny Paths - Integration Tests', () => {
     writeFileSync(join(TEST_DIR, '.profile'), ORIGINAL_CONTENT)
     writeFileSync(join(TEST_DIR, '.ripgreprc'), ORIGINAL_CONTENT)
     writeFileSync(join(TEST_DIR, '.mcp.json'), ORIGINAL_CONTENT)
+    writeFileSync(join(TEST_DIR, '.srt.json'), ORIGINAL_CONTENT)
 
     // Create .git with hooks and config
     mkdirSync(join(TEST_DIR, '.git', 'hooks'), { recursive: true })
@@ -192,6 +193,15 @@ describe('Mandatory Deny Paths - Integration Tests', () => {
       expect(readFileSync('.mcp.json', 'utf8')).toBe(ORIGINAL_CONTENT)
     })
 
+    it('blocks writes to .srt.json', async () => {
+      if (skipIfUnsupportedPlatform()) return
+
+      const result = await runSandboxedWrite('.srt.json', MODIFIED_CONTENT)
+
+      expect(result.success).toBe(false)
+      expect(readFileSync('.srt.json', 'utf8')).toBe(ORIGINAL_CONTENT)
+    })
+
     it('blocks writes to .bash_profile', async () => {
       if (skipIfUnsupportedPlatform()) return
 
@@ -517,6 +527,14 @@ describe('macGetMandatoryDenyPatterns - Unit Tests', () => {
     expect(hasHooksPatternTrue).toBe(true)
   })
 
+  it("includes srt's settings and state in the home directory", () => {
+    const patterns = macGetMandatoryDenyPatterns()
+
+    expect(patterns).toContain(join(homedir(), '.srt-settings.json'))
+    expect(patterns).toContain(join(homedir(), '.srt', 'policies'))
+    expect(patterns).toContain(join(homedir(), '.srt', 'temporary-rules.json'))
+  })
+
   it('defaults to blocking .git/config when no argument provided', () => {
     const patterns = macGetMandatoryDenyPatterns()
 
@@ -0,0 +1,90 @@
+import { describe, it, expect, afterEach } from 'bun:test'
+import type { SandboxWrapContext } from '../../src/sandbox/sandbox-backend.js'
+import type { SandboxRuntimeConfig } from '../../src/sandbox/sandbox-config.js'
+import { SandboxManager } from '../../src/sandbox/sandbox-manager.js'
+
+const config: SandboxRuntimeConfig = {
+  network: { allowedDomains: ['example.com'], deniedDomains: [] },
+  filesystem: { denyRead: [], allowWrite: ['.'], denyWrite: [] },
+}
+
+/** Registers a backend that records the contexts commands are wrapped with */
+async function initializeRecording(): Promise<SandboxWrapContext[]> {
+  const contexts: SandboxWrapContext[] = []
+  SandboxManager.registerBackend({
+    name: 'test-runner',
+    wrap: async context => {
+      contexts.push(context)
+      return context.command
+    },
+  })
+  await SandboxManager.initialize(config)
+  return contexts
+}
+
+describe('named sandboxes', () => {
+  afterEach(async () => {
+    await SandboxManager.reset()
+    SandboxManager.registerBackend(undefined)
+  })
+
+  it('wraps commands with the overlay of each sandbox', async () => {
+    const contexts = await initializeRecording()
+    const build = SandboxManager.sandbox('build', {
+      filesystem: { denyRead: [], allowWrite: ['/tmp/build'], denyWrite: [] },
+    })
+    const test = SandboxManager.sandbox('test', {
+      filesystem: { denyRead: ['/secrets'], allowWrite: [], denyWrite: [] },
+    })
+
+    await build.wrapWithSandbox('make')
+    await test.wrapWithSandbox('make check')
+    await SandboxManager.wrapWithSandbox('ls')
+
+    const [buildContext, testContext, plainContext] = contexts
+    expect(buildContext?.writeConfig.allowOnly).toContain('/tmp/build')
+    expect(buildContext?.readConfig.denyOnly).toEqual([])
+    expect(testContext?.writeConfig.allowOnly).not.toContain('/tmp/build')
+    expect(testContext?.readConfig.denyOnly).toContain('/secrets')
+    expect(plainContext?.overrides).toBeUndefined()
+  })
+
+  it('lets a call override settings of the overlay', async () => {
+    const contexts = await initializeRecording()
+    const build = SandboxManager.sandbox('build', { allowPty: false })
+
+    await build.wrapWithSandbox('make', undefined, { allowPty: true })
+    expect(contexts[0]?.allowPty).toBe(true)
+  })
+
+  it('keeps the deny lists of the overlay under a call override', async () => {
+    const contexts = await initializeRecording()
+    const test = SandboxManager.sandbox('test', {
+      filesystem: { denyRead: ['/secrets'], allowWrite: [], denyWrite: [] },
+    })
+
+    await test.wrapWithSandbox('make check', undefined, {
+      filesystem: { denyRead: [], allowWrite: ['/tmp'], denyWrite: [] },
+    })
+    expect(contexts[0]?.readConfig.denyOnly).toContain('/secrets')
+    expect(contexts[0]?.writeConfig.allowOnly).toContain('/tmp')
+  })
+
+  it('returns the same sandbox for a name until reset', async () => {
+    await initializeRecording()
+    const build = SandboxManager.sandbox('build', { allowPty: true })
+    expect(SandboxManager.sandbox('build')).toBe(build)
+    expect(build.overlay).toEqual({ allowPty: true })
+
+    // An overlay given again registers a new sandbox under the name
+    const rebuilt = SandboxManager.sandbox('build', { allowPty: false })
+    expect(rebuilt).not.toBe(build)
+    expect(SandboxManager.sandbox('build')).toBe(rebuilt)
+    expect(build.overlay).toEqual({ allowPty: true })
+    expect(rebuilt.overlay).toEqual({ allowPty: false })
+    expect(SandboxManager.getSandboxNames()).toEqual(['build'])
+
+    await SandboxManager.reset()
+    expect(SandboxManager.getSandboxNames()).toEqual([])
+  })
+})
@@ -0,0 +1,87 @@
+import { describe, it, expect, beforeAll, afterAll } from 'bun:test'
+import * as net from 'node:net'
+import { SandboxManager } from '../../src/sandbox/sandbox-manager.js'
+import type { SandboxRuntimeConfig } from '../../src/sandbox/sandbox-config.js'
+
+/**
+ * Send a CONNECT request to the HTTP proxy and return the status line
+ */
+function connectThroughProxy(
+  proxyPort: number,
+  target: string,
+): Promise<string> {
+  return new Promise((resolve, reject) => {
+    const socket = net.connect(proxyPort, '127.0.0.1', () => {
+      socket.write(`CONNECT ${target} HTTP/1.1\r\nHost: ${target}\r\n\r\n`)
+    })
+    socket.setEncoding('utf8')
+    socket.once('data', (data: string) => {
+      socket.destroy()
+      resolve(data.split('\r\n')[0] ?? '')
+    })
+    socket.once('error', reject)
+  })
+}
+
+describe('Network policy defaults', () => {
+  let proxyPort: number
+
+  beforeAll(async () => {
+    const config: SandboxRuntimeConfig = {
+      network: {
+        allowedDomains: ['*.example.com', '169.254.169.254'],
+        deniedDomains: ['blocked.example.com'],
+      },
+      filesystem: { denyRead: [], allowWrite: [], denyWrite: [] },
+    }
+    await SandboxManager.initialize(config)
+    proxyPort = SandboxManager.getProxyPort()!
+  })
+
+  afterAll(async () => {
+    await SandboxManager.reset()
+  })
+
+  it('blocks SMTP ports for allowed domains', async () => {
+    const status = await connectThroughProxy(proxyPort, 'mail.example.com:587')
+    expect(status).toContain('403')
+
+    const violation = SandboxManager.getSandboxViolationStore()
+      .getViolations()
+      .find(v => v.subtype === 'smtp')
+    expect(violation?.type).toBe('network')
+    expect(violation?.host).toBe('mail.example.com')
+    expect(violation?.port).toBe(587)
+  })
+
+  it('blocks cloud metadata endpoints even when explicitly allowed', async () => {
+    const status = await connectThroughProxy(proxyPort, '169.254.169.254:80')
+    expect(status).toContain('403')
+
+    const violation = SandboxManager.getSandboxViolationStore()
+      .getViolations()
+      .find(v => v.subtype === 'cloud-metadata')
+    expect(violation?.host).toBe('169.254.169.254')
+  })
+
+  it('records denied and unlisted domains as violations', async () => {
+    expect(
+      await connectThroughProxy(proxyPort, 'blocked.example.com:443'),
+    ).toContain('403')
+    expect(await connectThroughProxy(proxyPort, 'example.org:443')).toContain(
+      '403',
+    )
+
+    const violations = SandboxManager.getSandboxViolationStore().getViolations()
+    expect(violations.find(v => v.subtype === 'denied-domain')).toMatchObject({
+      type: 'network',
+      host: 'blocked.example.com',
+      port: 443,
+    })
+    expect(violations.find(v => v.subtype === 'not-allowed')).toMatchObject({
+      type: 'network',
+      host: 'example.org',
+      port: 443,
+    })
+  })
+})
@@ -0,0 +1,91 @@
+import { describe, it, expect } from 'bun:test'
+import {
+  formatNetworkPrompt,
+  NetworkPrompter,
+  parseNetworkPromptAnswer,
+} from '../../src/sandbox/network-prompt.js'
+
+/** A question function answering from a list, recording the prompts */
+function scripted(answers: string[]) {
+  const prompts: string[] = []
+  const question = async (prompt: string) => {
+    prompts.push(prompt)
+    const answer = answers.shift()
+    if (answer === undefined) {
+      throw new Error('no answer left')
+    }
+    return answer
+  }
+  return { prompts, question }
+}
+
+describe('parseNetworkPromptAnswer', () => {
+  it('accepts the short and long answers, and enter as deny', () => {
+    expect(parseNetworkPromptAnswer('y')).toBe('once')
+    expect(parseNetworkPromptAnswer(' Always ')).toBe('always')
+    expect(parseNetworkPromptAnswer('s')).toBe('save')
+    expect(parseNetworkPromptAnswer('no')).toBe('deny')
+    expect(parseNetworkPromptAnswer('')).toBe('deny')
+    expect(parseNetworkPromptAnswer('maybe')).toBeUndefined()
+  })
+})
+
+describe('formatNetworkPrompt', () => {
+  it('names the host and port', () => {
+    const prompt = formatNetworkPrompt({ host: 'api.example.com', port: 443 })
+    expect(prompt).toStartWith('Allow connection to api.example.com:443?')
+  })
+})
+
+describe('NetworkPrompter', () => {
+  it('asks again after allowing once', async () => {
+    const { prompts, question } = scripted(['y', 'n'])
+    const prompter = new NetworkPrompter(question)
+
+    expect(await prompter.ask({ host: 'example.com', port: 443 })).toBe(true)
+    expect(await prompter.ask({ host: 'example.com', port: 443 })).toBe(false)
+    expect(prompts).toHaveLength(2)
+  })
+
+  it('remembers always and deny for the rest of the run', async () => {
+    const { prompts, question } = scripted(['a', 'n'])
+    const prompter = new NetworkPrompter(question)
+
+    expect(await prompter.ask({ host: 'good.com', port: 443 })).toBe(true)
+    expect(await prompter.ask({ host: 'bad.com', port: 443 })).toBe(false)
+    expect(await prompter.ask({ host: 'GOOD.com', port: 80 })).toBe(true)
+    expect(await prompter.ask({ host: 'bad.com', port: 80 })).toBe(false)
+    expect(prompts).toHaveLength(2)
+  })
+
+  it('repeats the question until the answer is understood', async () => {
+    const { prompts, question } = scripted(['what', 'y'])
+    const prompter = new NetworkPrompter(question)
+
+    expect(await prompter.ask({ host: 'example.com', port: 443 })).toBe(true)
+    expect(prompts).toHaveLength(2)
+  })
+
+  it('saves domains answered with save', async () => {
+    const saved: string[] = []
+    const { question } = scripted(['s'])
+    const prompter = new NetworkPrompter(question, host => saved.push(host))
+
+    expect(await prompter.ask({ host: 'example.com', port: 443 })).toBe(true)
+    expect(await prompter.ask({ host: 'example.com', port: 443 })).toBe(true)
+    expect(saved).toEqual(['example.com'])
+  })
+
+  it('asks once for concurrent connections to the same host', async () => {
+    const { prompts, question } = scripted(['y', 'n'])
+    const prompter = new NetworkPrompter(question)
+
+    const results = await Promise.all([
+      prompter.ask({ host: 'example.com', port: 443 }),
+      prompter.ask({ host: 'example.com', port: 443 }),
+      prompter.ask({ host: 'other.com', port: 443 }),
+    ])
+    expect(results).toEqual([true, true, false])
+    expect(prompts).toHaveLength(2)
+  })
+})
@@ -0,0 +1,177 @@
+import { describe, it, expect } from 'bun:test'
+import { bwrapArgsToNsjailArgs } from '../../src/sandbox/nsjail-sandbox.js'
+
+describe('bwrapArgsToNsjailArgs', () => {
+  it('translates mounts, environment and the command', () => {
+    const args = bwrapArgsToNsjailArgs(
+      [
+        '--new-session',
+        '--die-with-parent',
+        '--unshare-net',
+        '--setenv',
+        'HTTP_PROXY',
+        'http://localhost:3128',
+        '--ro-bind',
+        '/',
+        '/',
+        '--bind',
+        '/work',
+        '/work',
+        '--ro-bind',
+        '/work/.git',
+        '/work/.git',
+        '--tmpfs',
+        '/home/user/.ssh',
+        '--dev',
+        '/dev',
+        '--unshare-pid',
+        '--proc',
+        '/proc',
+        '--',
+        '/bin/bash',
+        '-c',
+        'echo hi',
+      ],
+      '/work',
+    )
+
+    expect(args).toEqual([
+      '--mode',
+      'o',
+      '--quiet',
+      '--keep_env',
+      '--disable_rlimits',
+      '--time_limit',
+      '0',
+      '--cwd',
+      '/work',
+      '--env',
+      'HTTP_PROXY=http://localhost:3128',
+      '--bindmount_ro',
+      '/:/',
+      '--bindmount',
+      '/work:/work',
+      '--bindmount_ro',
+      '/work/.git:/work/.git',
+      '--tmpfsmount',
+      '/home/user/.ssh',
+      '--tmpfsmount',
+      '/dev',
+      '--bindmount',
+      '/dev/null:/dev/null',
+      '--bindmount',
+      '/dev/zero:/dev/zero',
+      '--bindmount',
+      '/dev/full:/dev/full',
+      '--bindmount',
+      '/dev/random:/dev/random',
+      '--bindmount',
+      '/dev/urandom:/dev/urandom',
+      '--bindmount',
+      '/dev/tty:/dev/tty',
+      '--symlink',
+      '/proc/self/fd:/dev/fd',
+      '--symlink',
+      '/proc/self/fd/0:/dev/stdin',
+      '--symlink',
+      '/proc/self/fd/1:/dev/stdout',
+      '--symlink',
+      '/proc/self/fd/2:/dev/stderr',
+      '--tmpfsmount',
+      '/dev/shm',
+      '--',
+      '/bin/bash',
+      '-c',
+      'echo hi',
+    ])
+  })
+
+  it('builds a minimal /dev instead of binding the host /dev', () => {
+    const args = bwrapArgsToNsjailArgs(['--dev', '/dev', '--', 'true'])
+    const bound = args.filter((_, i) => args[i - 1]?.startsWith('--bindmount'))
+    expect(bound).toEqual([
+      '/dev/null:/dev/null',
+      '/dev/zero:/dev/zero',
+      '/dev/full:/dev/full',
+      '/dev/random:/dev/random',
+      '/dev/urandom:/dev/urandom',
+      '/dev/tty:/dev/tty',
+    ])
+    expect(args).not.toContain('/dev:/dev')
+  })
+
+  it('keeps the host network and skips /proc when bwrap would', () => {
+    const args = bwrapArgsToNsjailArgs(['--bind', '/', '/', '--', 'true'])
+    expect(args).toContain('--disable_clone_newnet')
+    expect(args).toContain('--disable_proc')
+  })
+
+  it('passes only the --setenv variables after --clearenv', () => {
+    const args = bwrapArgsToNsjailArgs([
+      '--clearenv',
+      '--setenv',
+      'PATH',
+      '/usr/bin',
+      '--',
+      'true',
+    ])
+    expect(args).not.toContain('--keep_env')
+    expect(args[args.indexOf('--env') + 1]).toBe('PATH=/usr/bin')
+  })
+
+  it('maps user namespace ids', () => {
+    const args = bwrapArgsToNsjailArgs([
+      '--unshare-user',
+      '--uid',
+      '65534',
+      '--gid',
+      '65534',
+      '--',
+      'true',
+    ])
+    expect(args).toContain('--user')
+    expect(args[args.indexOf('--user') + 1]).toBe('65534')
+    expect(args[args.indexOf('--group') + 1]).toBe('65534')
+  })
+
+  it('rejects options it cannot translate', () => {
+    expect(() =>
+      bwrapArgsToNsjailArgs(['--cap-add', 'ALL', '--', 'true']),
+    ).toThrow(/does not support bwrap option --cap-add/)
+    expect(() => bwrapArgsToNsjailArgs(['--bind', '/', '/'])).toThrow(
+      /do not contain a command/,
+    )
+  })
+
+  it('keeps only the capabilities bwrap adds back', () => {
+    const args = bwrapArgsToNsjailArgs(
+      [
+        '--cap-drop',
+        'ALL',
+        '--cap-add',
+        'CAP_NET_BIND_SERVICE',
+        '--unshare-net',
+        '--proc',
+        '/proc',
+        '--',
+        'true',
+      ],
+      '/work',
+    )
+    expect(args).toContain('--cap')
+    expect(args[args.indexOf('--cap') + 1]).toBe('CAP_NET_BIND_SERVICE')
+    expect(() =>
+      bwrapArgsToNsjailArgs(['--cap-drop', 'CAP_SYS_ADMIN', '--', 'true']),
+    ).toThrow('--cap-drop ALL')
+  })
+
+  it('maps tmpfs sizes to the nsjail tmpfs size', () => {
+    const args = bwrapArgsToNsjailArgs(
+      ['--size', '1048576', '--tmpfs', '/tmp', '--', 'true'],
+      '/work',
+    )
+    expect(args).toContain('--tmpfs_size')
+    expect(args[args.indexOf('--tmpfs_size') + 1]).toBe('1048576')
+    expect(args[args.indexOf('--tmpfsmount') + 1]).toBe('/tmp')
+  })
+})
@@ -0,0 +1,99 @@
+import { describe, it, expect } from 'bun:test'
+import {
+  mkdirSync,
+  mkdtempSync,
+  realpathSync,
+  rmSync,
+  writeFileSync,
+} from 'node:fs'
+import { tmpdir } from 'node:os'
+import { join } from 'node:path'
+import {
+  buildUnveilRules,
+  getPledgePromises,
+} from '../../src/sandbox/openbsd-sandbox-utils.js'
+
+describe('buildUnveilRules', () => {
+  it('leaves the whole filesystem writable without write restrictions', () => {
+    expect(buildUnveilRules({ denyOnly: [] }, undefined)).toEqual(['rwxc:/'])
+  })
+
+  it('maps write and read restrictions onto unveil permissions', () => {
+    const rules = buildUnveilRules(
+      { denyOnly: ['/home/user/.ssh'] },
+      { allowOnly: ['/tmp/work'], denyWithinAllow: ['/tmp/work/keep'] },
+    )
+
+    expect(rules).toContain('rx:/')
+    expect(rules).toContain('rwxc:/tmp/work')
+    expect(rules).toContain('rx:/tmp/work/keep')
+    expect(rules).toContain(':/home/user/.ssh')
+  })
+
+  it('keeps the mandatory deny paths read-only', () => {
+    const rules = buildUnveilRules(undefined, {
+      allowOnly: [process.cwd()],
+      denyWithinAllow: [],
+    })
+
+    expect(rules).toContain(`rx:${process.cwd()}/.git/hooks`)
+    expect(rules).toContain(`rx:${process.cwd()}/.bashrc`)
+  })
+
+  it('unveils the paths glob patterns match', () => {
+    const dir = realpathSync(mkdtempSync(join(tmpdir(), 'srt-unveil-')))
+    writeFileSync(join(dir, 'a.key'), '')
+    writeFileSync(join(dir, 'b.txt'), '')
+    try {
+      const rules = buildUnveilRules({ denyOnly: [`${dir}/*.key`] }, undefined)
+      expect(rules).toEqual(['rwxc:/', `:${dir}/a.key`])
+    } finally {
+      rmSync(dir, { recursive: true, force: true })
+    }
+  })
+
+  it('keeps files matching the mandatory deny globs read-only', () => {
+    const dir = realpathSync(mkdtempSync(join(tmpdir(), 'srt-unveil-')))
+    mkdirSync(join(dir, 'pkg'))
+    writeFileSync(join(dir, 'pkg', '.bashrc'), '')
+    const cwd = process.cwd()
+    process.chdir(dir)
+    try {
+      const rules = buildUnveilRules(undefined, {
+        allowOnly: [dir],
+        denyWithinAllow: [],
+      })
+      expect(rules).toContain(`rx:${dir}/pkg/.bashrc`)
+    } finally {
+      process.chdir(cwd)
+      rmSync(dir, { recursive: true, force: true })
+    }
+  })
+
+  it('unveils allowed Unix sockets writable', () => {
+    const rules = buildUnveilRules(undefined, undefined, false, [
+      '/var/run/app.sock',
+    ])
+    expect(rules).toContain('rw:/var/run/app.sock')
+  })
+})
+
+describe('getPledgePromises', () => {
+  it('drops the network promises when the network is restricted', () => {
+    expect(getPledgePromises(true)).not.toContain('inet')
+    expect(getPledgePromises(true)).not.toContain('dns')
+    expect(getPledgePromises(false)).toContain('inet')
+  })
+
+  it('only grants the unix promise when Unix sockets are allowed', () => {
+    expect(getPledgePromises(true).split(' ')).not.toContain('unix')
+    expect(getPledgePromises(true, true).split(' ')).toContain('unix')
+  })
+
+  it('never grants privileged promises', () => {
+    const promises = getPledgePromises(false).split(' ')
+    for (const promise of ['id', 'settime', 'pf', 'route', 'wroute']) {
+      expect(promises).not.toContain(promise)
+    }
+  })
+})
@@ -0,0 +1,56 @@
+import { describe, it, expect } from 'bun:test'
+import {
+  escapePath,
+  wrapCommandWithSandboxMacOS,
+} from '../../src/sandbox/macos-sandbox-utils.js'
+import { expandUnicodeNormalizations } from '../../src/sandbox/sandbox-utils.js'
+import { readMacOSProfile } from './read-macos-profile.js'
+
+const NFC = '/tmp/caf\u00e9'
+const NFD = '/tmp/cafe\u0301'
+
+describe('expandUnicodeNormalizations', () => {
+  it('returns both NFC and NFD forms of a path', () => {
+    const expanded = expandUnicodeNormalizations([NFC])
+    expect(expanded).toContain(NFC)
+    expect(expanded).toContain(NFD)
+    expect(expanded).toHaveLength(2)
+  })
+
+  it('does not duplicate ASCII paths', () => {
+    expect(expandUnicodeNormalizations(['/tmp/plain'])).toEqual(['/tmp/plain'])
+  })
+})
+
+describe('escapePath', () => {
+  it('quotes plain paths', () => {
+    expect(escapePath('/tmp/a b')).toBe('"/tmp/a b"')
+  })
+
+  it('escapes quotes and backslashes', () => {
+    expect(escapePath('/tmp/a"b\\c')).toBe('"/tmp/a\\"b\\\\c"')
+  })
+
+  it('keeps non-ASCII characters literal', () => {
+    expect(escapePath('/tmp/日本語')).toBe('"/tmp/日本語"')
+  })
+
+  it('rejects control characters', () => {
+    expect(() => escapePath('/tmp/a\nb')).toThrow('control characters')
+  })
+})
+
+describe('macOS profile with non-ASCII paths', () => {
+  it('emits deny rules for both normalization forms', () => {
+    const profile = readMacOSProfile(
+      wrapCommandWithSandboxMacOS({
+        command: 'true',
+        needsNetworkRestriction: false,
+        readConfig: { denyOnly: [NFC] },
+        writeConfig: undefined,
+      }),
+    )
+    expect(profile).toContain(NFC)
+    expect(profile).toContain(NFD)
+  })
+})
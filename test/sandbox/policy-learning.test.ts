@@ -0,0 +1,98 @@
+import { describe, it, expect } from 'bun:test'
+import { homedir } from 'node:os'
+import { join } from 'node:path'
+import {
+  formatLearningReport,
+  getLearningConfig,
+  PolicyLearner,
+} from '../../src/sandbox/policy-learning.js'
+import type { SandboxRuntimeConfig } from '../../src/sandbox/sandbox-config.js'
+
+const base: SandboxRuntimeConfig = {
+  network: {
+    allowedDomains: ['github.com'],
+    deniedDomains: ['evil.example.com'],
+  },
+  filesystem: {
+    denyRead: ['~/.ssh'],
+    allowWrite: ['.'],
+    denyWrite: ['./.env'],
+  },
+  violationPolicy: { network: 'kill' },
+}
+
+function write(path: string) {
+  return {
+    line: `allow(write) openat ${path}`,
+    timestamp: new Date(),
+    type: 'file-access' as const,
+    access: 'write' as const,
+    path,
+  }
+}
+
+describe('getLearningConfig', () => {
+  it('opens the allow lists and keeps the deny lists', () => {
+    const config = getLearningConfig(base)
+    expect(config.network.allowedDomains).toEqual([])
+    expect(config.network.askUnlistedDomains).toBe(true)
+    expect(config.network.deniedDomains).toEqual(['evil.example.com'])
+    expect(config.filesystem.allowWrite).toEqual(['/'])
+    expect(config.filesystem.denyRead).toEqual(['~/.ssh'])
+    expect(config.auditFileAccess).toBe(true)
+    expect(config.violationPolicy).toBeUndefined()
+  })
+})
+
+describe('PolicyLearner', () => {
+  const cwd = '/work/project'
+
+  it('allows the domains connected to', () => {
+    const learner = new PolicyLearner(cwd)
+    learner.recordConnection('registry.npmjs.org', 443)
+    learner.recordConnection('github.com', 443)
+    learner.recordConnection('registry.npmjs.org', 80)
+
+    expect(learner.getConnections()).toEqual([
+      { host: 'github.com', ports: [443] },
+      { host: 'registry.npmjs.org', ports: [80, 443] },
+    ])
+    expect(learner.buildConfig(base).network).toEqual({
+      allowedDomains: ['github.com', 'registry.npmjs.org'],
+      deniedDomains: ['evil.example.com'],
+    })
+  })
+
+  it('allows the outermost directories written to', () => {
+    const learner = new PolicyLearner(cwd)
+    learner.add(write('/work/project/dist/index.js'))
+    learner.add(write('/work/project/dist/lib/util.js'))
+    learner.add(write('node_modules/.cache/x'))
+    learner.add(write(join(homedir(), '.cache/tool/data')))
+    learner.add(write('/var/tmp/build.log'))
+    learner.add(write('/dev/null'))
+    learner.add({ ...write('/work/project/README.md'), access: 'read' })
+
+    expect(learner.getWriteDirectories().sort()).toEqual([
+      './dist',
+      './node_modules/.cache',
+      '/var/tmp',
+      '~/.cache/tool',
+    ])
+  })
+
+  it('reports blocked accesses and missing audit events', () => {
+    const learner = new PolicyLearner(cwd)
+    learner.add({
+      line: 'deny(EACCES) openat /home/me/.ssh/id_rsa',
+      timestamp: new Date(),
+      path: '/home/me/.ssh/id_rsa',
+      access: 'read',
+    })
+
+    const report = formatLearningReport(learner)
+    expect(report).toContain('deny(EACCES) openat /home/me/.ssh/id_rsa')
+    expect(report).toContain('no file accesses were audited')
+    expect(learner.buildConfig(base).filesystem.allowWrite).toEqual([])
+  })
+})
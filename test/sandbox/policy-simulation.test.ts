@@ -0,0 +1,166 @@
+import { describe, it, expect } from 'bun:test'
+import { mkdtempSync, readFileSync, rmSync, writeFileSync } from 'node:fs'
+import { homedir, tmpdir } from 'node:os'
+import { join } from 'node:path'
+import {
+  formatPolicyDecision,
+  parseConnectTarget,
+  simulatePolicy,
+} from '../../src/sandbox/policy-simulation.js'
+import type { SandboxRuntimeConfig } from '../../src/sandbox/sandbox-config.js'
+import { getTemporaryRuleKey } from '../../src/sandbox/temporary-rules.js'
+
+const config: SandboxRuntimeConfig = {
+  network: {
+    allowedDomains: ['*.github.com', 'registry.npmjs.org', '!gist.github.com'],
+    deniedDomains: ['evil.example.com'],
+  },
+  filesystem: {
+    denyRead: ['~/.ssh', '/opt/secrets/*.key'],
+    allowWrite: ['/opt/work', '!/opt/work/locked'],
+    denyWrite: ['/opt/work/**/*.lock'],
+  },
+}
+
+describe('simulatePolicy', () => {
+  it('denies reads under denyRead and names the entry', () => {
+    const decision = simulatePolicy(
+      config,
+      'read',
+      join(homedir(), '.ssh/id_rsa'),
+    )
+    expect(decision).toMatchObject({
+      allowed: false,
+      rule: 'filesystem.denyRead',
+      entry: '~/.ssh',
+    })
+    expect(formatPolicyDecision(decision)).toStartWith('deny read ')
+  })
+
+  it('matches glob entries', () => {
+    expect(simulatePolicy(config, 'read', '/opt/secrets/db.key').entry).toBe(
+      '/opt/secrets/*.key',
+    )
+    expect(simulatePolicy(config, 'read', '/opt/secrets/db.txt').allowed).toBe(
+      true,
+    )
+  })
+
+  it('allows writes only under allowWrite, minus denyWrite and exclusions', () => {
+    expect(simulatePolicy(config, 'write', '/opt/work/a.ts')).toMatchObject({
+      allowed: true,
+      rule: 'filesystem.allowWrite',
+      entry: '/opt/work',
+    })
+    expect(
+      simulatePolicy(config, 'write', '/opt/work/locked/a.ts'),
+    ).toMatchObject({
+      allowed: false,
+      rule: 'filesystem.denyWrite',
+      entry: '/opt/work/locked',
+    })
+    expect(simulatePolicy(config, 'write', '/opt/work/pkg/x.lock').entry).toBe(
+      '/opt/work/**/*.lock',
+    )
+    expect(simulatePolicy(config, 'write', '/opt/other')).toMatchObject({
+      allowed: false,
+      reason: 'not in filesystem.allowWrite',
+    })
+  })
+
+  it('always denies writes to dangerous files in the working directory', () => {
+    const decision = simulatePolicy(
+      { ...config, filesystem: { ...config.filesystem, allowWrite: ['.'] } },
+      'write',
+      join(process.cwd(), '.git/hooks/pre-commit'),
+    )
+    expect(decision).toMatchObject({ allowed: false, rule: 'mandatory deny' })
+  })
+
+  it('checks denied domains before allowed domains', () => {
+    expect(simulatePolicy(config, 'connect', 'api.github.com')).toMatchObject({
+      allowed: true,
+      entry: '*.github.com',
+    })
+    expect(simulatePolicy(config, 'connect', 'gist.github.com')).toMatchObject({
+      allowed: false,
+      rule: 'network.deniedDomains',
+      entry: 'gist.github.com',
+    })
+    expect(simulatePolicy(config, 'connect', 'example.org').allowed).toBe(
+      false,
+    )
+  })
+
+  it('blocks cloud metadata and SMTP even when allowed', () => {
+    const open = {
+      ...config,
+      network: {
+        ...config.network,
+        allowedDomains: ['169.254.169.254', 'mail.github.com'],
+      },
+    }
+    expect(simulatePolicy(open, 'connect', '169.254.169.254:80').rule).toBe(
+      'network.allowCloudMetadata',
+    )
+    expect(simulatePolicy(open, 'connect', 'mail.github.com:587').rule).toBe(
+      'network.allowSmtp',
+    )
+  })
+
+  it('applies unexpired temporary rules', () => {
+    const withRules: SandboxRuntimeConfig = {
+      ...config,
+      temporaryRules: [
+        { allowedDomains: ['pypi.org'], expires: '2026-01-01T00:00:00Z' },
+        { allowedDomains: ['crates.io'], expires: '2099-01-01T00:00:00Z' },
+      ],
+    }
+    const now = new Date('2026-06-01T00:00:00Z')
+    expect(simulatePolicy(withRules, 'connect', 'pypi.org', now).allowed).toBe(
+      false,
+    )
+    expect(simulatePolicy(withRules, 'connect', 'crates.io', now).allowed).toBe(
+      true,
+    )
+  })
+
+  it('skips temporary rules that used up their runs, using none', () => {
+    const dir = mkdtempSync(join(tmpdir(), 'srt-simulation-'))
+    const statePath = join(dir, 'temporary-rules.json')
+    const usedUp = { allowedDomains: ['pypi.org'], maxRuns: 2 }
+    const fresh = { allowedDomains: ['crates.io'], maxRuns: 2 }
+    const counts = { [getTemporaryRuleKey(usedUp)]: 2 }
+    writeFileSync(statePath, JSON.stringify(counts))
+    try {
+      const withRules = { ...config, temporaryRules: [usedUp, fresh] }
+      const now = new Date()
+      const connect = (host: string) =>
+        simulatePolicy(withRules, 'connect', host, now, statePath).allowed
+      expect(connect('pypi.org')).toBe(false)
+      expect(connect('crates.io')).toBe(true)
+      expect(JSON.parse(readFileSync(statePath, 'utf8'))).toEqual(counts)
+    } finally {
+      rmSync(dir, { recursive: true, force: true })
+    }
+  })
+})
+
+describe('parseConnectTarget', () => {
+  it('splits hosts, bracketed IPv6 addresses and ports', () => {
+    expect(parseConnectTarget('api.github.com:8443')).toEqual({
+      host: 'api.github.com',
+      port: 8443,
+    })
+    expect(parseConnectTarget('[::1]:80')).toEqual({ host: '::1', port: 80 })
+    expect(parseConnectTarget('example.com')).toEqual({
+      host: 'example.com',
+      port: 443,
+    })
+  })
+
+  it('rejects invalid targets', () => {
+    expect(() => parseConnectTarget('example.com:99999')).toThrow()
+    expect(() => parseConnectTarget('::1')).toThrow()
+  })
+})
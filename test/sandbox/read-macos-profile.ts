@@ -0,0 +1,16 @@
+import { readFileSync } from 'node:fs'
+import shellquote from 'shell-quote'
+
+/**
+ * The seatbelt profile a command wrapped by wrapCommandWithSandboxMacOS runs
+ * under, read from the file passed to sandbox-exec -f
+ */
+export function readMacOSProfile(wrappedCommand: string): string {
+  const args = shellquote.parse(wrappedCommand)
+  const index = args.indexOf('-f')
+  const profilePath = args[index + 1]
+  if (index === -1 || typeof profilePath !== 'string') {
+    throw new Error(`no profile file in: ${wrappedCommand}`)
+  }
+  return readFileSync(profilePath, 'utf8')
+}
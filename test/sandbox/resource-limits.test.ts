@@ -0,0 +1,91 @@
+import { describe, it, expect } from 'bun:test'
+import { spawnSync } from 'node:child_process'
+import { rmSync, statSync } from 'node:fs'
+import { tmpdir } from 'node:os'
+import { join } from 'node:path'
+import { buildResourceLimitArgs } from '../../src/sandbox/linux-sandbox-utils.js'
+import {
+  applyRlimits,
+  buildUlimitCommands,
+} from '../../src/sandbox/sandbox-utils.js'
+import { getPlatform } from '../../src/utils/platform.js'
+
+describe('buildResourceLimitArgs', () => {
+  it('returns no prefix when no limits are configured', () => {
+    expect(buildResourceLimitArgs(undefined)).toEqual([])
+    expect(buildResourceLimitArgs({})).toEqual([])
+  })
+
+  it('maps limits onto systemd scope properties', () => {
+    const args = buildResourceLimitArgs({ cpus: 1.5, memoryMb: 512, pids: 64 })
+
+    expect(args.slice(0, 4)).toEqual([
+      'systemd-run',
+      '--user',
+      '--scope',
+      '--quiet',
+    ])
+    expect(args).toContain('CPUQuota=150%')
+    expect(args).toContain('MemoryMax=512M')
+    expect(args).toContain('TasksMax=64')
+    expect(args[args.length - 1]).toBe('--')
+  })
+
+  it('disables swap when a memory limit is set', () => {
+    const args = buildResourceLimitArgs({ memoryMb: 256 })
+    expect(args).toContain('MemorySwapMax=0')
+  })
+
+  it('passes each property with its own -p flag', () => {
+    const args = buildResourceLimitArgs({ pids: 10 })
+    const index = args.indexOf('TasksMax=10')
+    expect(args[index - 1]).toBe('-p')
+  })
+})
+
+describe('applyRlimits', () => {
+  it('leaves the command alone when no limits are configured', () => {
+    expect(applyRlimits('bwrap echo hi', undefined)).toBe('bwrap echo hi')
+    expect(applyRlimits('bwrap echo hi', {})).toBe('bwrap echo hi')
+  })
+
+  it('maps limits onto ulimit in 512-byte blocks', () => {
+    expect(
+      buildUlimitCommands({
+        nofile: 256,
+        fsizeMb: 10,
+        cpuSeconds: 30,
+        coreMb: 0,
+      }),
+    ).toEqual([
+      'ulimit -n 256',
+      'ulimit -f 20480',
+      'ulimit -t 30',
+      'ulimit -c 0',
+    ])
+  })
+
+  it('does not fall back to RLIMIT_NPROC for maxProcesses', () => {
+    expect(buildUlimitCommands({ maxProcesses: 128 })).toEqual([])
+  })
+
+  it('execs the wrapped command under /bin/sh with the limits set', () => {
+    const wrapped = applyRlimits('bwrap echo hi', { nofile: 64 })
+    expect(wrapped).toBe(`/bin/sh -c 'ulimit -n 64 && exec bwrap echo hi'`)
+  })
+
+  it('enforces the file size limit', () => {
+    if (getPlatform() === 'windows') {
+      return
+    }
+
+    const out = join(tmpdir(), `srt-fsize-${process.pid}`)
+    const result = spawnSync(
+      applyRlimits(`head -c 2097152 /dev/zero > ${out}`, { fsizeMb: 1 }),
+      { shell: true },
+    )
+    expect(result.status).not.toBe(0)
+    expect(statSync(out).size).toBe(1024 * 1024)
+    rmSync(out, { force: true })
+  })
+})
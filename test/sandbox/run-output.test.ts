@@ -0,0 +1,68 @@
+import { describe, it, expect } from 'bun:test'
+import {
+  formatRunEvent,
+  SANDBOX_EXIT_CODES,
+} from '../../src/sandbox/run-output.js'
+
+describe('formatRunEvent', () => {
+  const time = new Date('2026-01-02T03:04:05.000Z')
+
+  it('serializes an event as one line with an ISO time', () => {
+    const line = formatRunEvent({
+      event: 'start',
+      time,
+      command: 'echo "a\nb"',
+      backend: 'bwrap',
+    })
+    expect(line.endsWith('\n')).toBe(true)
+    expect(line.trimEnd()).not.toContain('\n')
+    expect(JSON.parse(line)).toEqual({
+      event: 'start',
+      time: '2026-01-02T03:04:05.000Z',
+      command: 'echo "a\nb"',
+      backend: 'bwrap',
+    })
+  })
+
+  it('includes the violation of violation events', () => {
+    const line = formatRunEvent({
+      event: 'violation',
+      time,
+      violation: {
+        line: 'Network request to example.com:443 blocked',
+        timestamp: time,
+        type: 'network',
+        host: 'example.com',
+        port: 443,
+      },
+    })
+    expect(JSON.parse(line).violation).toMatchObject({
+      host: 'example.com',
+      timestamp: '2026-01-02T03:04:05.000Z',
+    })
+  })
+
+  it('keeps a null signal in exit events', () => {
+    const event = JSON.parse(
+      formatRunEvent({
+        event: 'exit',
+        time,
+        exitCode: 0,
+        signal: null,
+        durationMs: 12,
+      }),
+    )
+    expect(event).toMatchObject({ exitCode: 0, signal: null, durationMs: 12 })
+  })
+})
+
+describe('SANDBOX_EXIT_CODES', () => {
+  it('reserves distinct statuses below the signal range', () => {
+    const codes = Object.values(SANDBOX_EXIT_CODES)
+    expect(new Set(codes).size).toBe(codes.length)
+    for (const code of codes) {
+      expect(code).toBeGreaterThan(1)
+      expect(code).toBeLessThan(128)
+    }
+  })
+})
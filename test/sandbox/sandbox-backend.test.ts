@@ -0,0 +1,176 @@
+import { describe, it, expect, afterEach } from 'bun:test'
+import type {
+  SandboxBackend,
+  SandboxWrapContext,
+} from '../../src/sandbox/sandbox-backend.js'
+import type { SandboxRuntimeConfig } from '../../src/sandbox/sandbox-config.js'
+import { SandboxOutputLimitError } from '../../src/sandbox/sandbox-execute.js'
+import { SandboxManager } from '../../src/sandbox/sandbox-manager.js'
+import { getPlatform } from '../../src/utils/platform.js'
+
+const config: SandboxRuntimeConfig = {
+  network: { allowedDomains: ['example.com'], deniedDomains: [] },
+  filesystem: { denyRead: ['~/.ssh'], allowWrite: ['.'], denyWrite: [] },
+}
+
+/** A backend that runs commands unconfined, recording what it is asked */
+function createRecordingBackend(overrides: Partial<SandboxBackend> = {}) {
+  const calls: string[] = []
+  const contexts: SandboxWrapContext[] = []
+  const backend: SandboxBackend = {
+    name: 'test-runner',
+    prepare: async () => {
+      calls.push('prepare')
+    },
+    wrap: async context => {
+      calls.push('wrap')
+      contexts.push(context)
+      return context.command
+    },
+    cleanup: async () => {
+      calls.push('cleanup')
+    },
+    ...overrides,
+  }
+  return { backend, calls, contexts }
+}
+
+describe('registered sandbox backends', () => {
+  afterEach(async () => {
+    await SandboxManager.reset()
+    SandboxManager.registerBackend(undefined)
+  })
+
+  it('prepares, wraps with and cleans up the registered backend', async () => {
+    const { backend, calls, contexts } = createRecordingBackend()
+    SandboxManager.registerBackend(backend)
+    await SandboxManager.initialize(config)
+
+    expect(SandboxManager.getSandboxBackend()).toBe('test-runner')
+    expect(await SandboxManager.wrapWithSandbox('echo hi')).toBe('echo hi')
+
+    const [context] = contexts
+    expect(context?.command).toBe('echo hi')
+    expect(context?.readConfig.denyOnly).toHaveLength(1)
+    expect(context?.network.restricted).toBe(true)
+    expect(context?.network.proxied).toBe(true)
+    expect(context?.network.httpProxyPort).toBeNumber()
+
+    await SandboxManager.reset()
+    expect(calls).toEqual(['prepare', 'wrap', 'cleanup'])
+  })
+
+  it('runs wrapped commands with the shell unless the backend executes them', async () => {
+    if (getPlatform() === 'windows') {
+      return
+    }
+
+    SandboxManager.registerBackend(createRecordingBackend().backend)
+    await SandboxManager.initialize(config)
+    expect(
+      await SandboxManager.execute('exit 3', { stdio: 'ignore' }),
+    ).toEqual({ code: 3, signal: null })
+    await SandboxManager.reset()
+
+    const executed: string[] = []
+    const { backend } = createRecordingBackend({
+      execute: async wrappedCommand => {
+        executed.push(wrappedCommand)
+        return { code: 7, signal: null }
+      },
+    })
+    SandboxManager.registerBackend(backend)
+    await SandboxManager.initialize(config)
+    expect(await SandboxManager.execute('exit 3')).toEqual({
+      code: 7,
+      signal: null,
+    })
+    expect(executed).toEqual(['exit 3'])
+  })
+
+  it('captures the output of commands run with executeWithOutput', async () => {
+    if (getPlatform() === 'windows') {
+      return
+    }
+
+    SandboxManager.registerBackend(createRecordingBackend().backend)
+    await SandboxManager.initialize(config)
+    const output = await SandboxManager.executeWithOutput(
+      'echo out; echo err >&2; exit 2',
+    )
+    expect(output).toMatchObject({
+      code: 2,
+      signal: null,
+      stdout: 'out\n',
+      stderr: 'err\n',
+    })
+    expect(output.durationMs).toBeGreaterThanOrEqual(0)
+  })
+
+  it('bounds the output executeWithOutput captures and waits for', async () => {
+    if (getPlatform() === 'windows') {
+      return
+    }
+
+    SandboxManager.registerBackend(createRecordingBackend().backend)
+    await SandboxManager.initialize(config)
+
+    // A background process holding the pipes does not keep the call waiting
+    const startedAt = Date.now()
+    const output = await SandboxManager.executeWithOutput(
+      'echo done; sleep 30 &',
+    )
+    expect(output.stdout).toBe('done\n')
+    expect(Date.now() - startedAt).toBeLessThan(10_000)
+
+    const overflow = SandboxManager.executeWithOutput('yes', { maxBuffer: 10 })
+    await expect(overflow).rejects.toBeInstanceOf(SandboxOutputLimitError)
+    await expect(overflow).rejects.toMatchObject({ stdout: 'y\ny\ny\ny\ny\n' })
+
+    await expect(
+      SandboxManager.executeWithOutput('echo before; sleep 30', {
+        timeoutSeconds: 1,
+      }),
+    ).rejects.toMatchObject({
+      name: 'SandboxTimeoutError',
+      stdout: 'before\n',
+    })
+  })
+
+  it('fails initialization when the backend cannot prepare', async () => {
+    const { backend } = createRecordingBackend({
+      prepare: async () => {
+        throw new Error('no VM available')
+      },
+    })
+    SandboxManager.registerBackend(backend)
+    await expect(SandboxManager.initialize(config)).rejects.toThrow(
+      'no VM available',
+    )
+    expect(SandboxManager.isSandboxingEnabled()).toBe(false)
+    expect(SandboxManager.getConfig()).toBeUndefined()
+  })
+
+  it('prepares once for concurrent initialize calls', async () => {
+    const { backend, calls } = createRecordingBackend({
+      prepare: async () => {
+        calls.push('prepare')
+        await new Promise(resolve => setTimeout(resolve, 50))
+      },
+    })
+    SandboxManager.registerBackend(backend)
+    await Promise.all([
+      SandboxManager.initialize(config),
+      SandboxManager.initialize(config),
+    ])
+    expect(calls).toEqual(['prepare'])
+  })
+
+  it('cannot be registered once the manager is initialized', async () => {
+    SandboxManager.registerBackend(createRecordingBackend().backend)
+    await SandboxManager.initialize(config)
+    expect(() => SandboxManager.registerBackend(undefined)).toThrow(
+      /before SandboxManager.initialize/,
+    )
+  })
+})
@@ -0,0 +1,131 @@
+import { describe, it, expect, afterEach } from 'bun:test'
+import { statSync } from 'node:fs'
+import * as net from 'node:net'
+import { dirname } from 'node:path'
+import {
+  BrokerClient,
+  createBrokerServer,
+  forwardBrokerViolations,
+  generateBrokerSocketPath,
+  type BrokerServer,
+} from '../../src/sandbox/sandbox-broker.js'
+import { SandboxViolationStore } from '../../src/sandbox/sandbox-violation-store.js'
+
+describe('Sandbox broker control socket', () => {
+  let server: BrokerServer | undefined
+
+  afterEach(async () => {
+    await server?.close()
+    server = undefined
+  })
+
+  it('serves proxy ports and violations to clients', async () => {
+    const socketPath = generateBrokerSocketPath()
+    server = await createBrokerServer(socketPath, {
+      getProxyPorts: () => ({ httpProxyPort: 3128, socksProxyPort: 1080 }),
+      getViolations: () => [
+        { line: 'deny file-read-data /etc/shadow', timestamp: new Date(0) },
+      ],
+      shutdown: () => {},
+    })
+
+    const client = await BrokerClient.connect(socketPath)
+    try {
+      expect(await client.getProxyPorts()).toEqual({
+        httpProxyPort: 3128,
+        socksProxyPort: 1080,
+      })
+      const violations = await client.getViolations()
+      expect(violations).toHaveLength(1)
+      expect(violations[0]?.line).toBe('deny file-read-data /etc/shadow')
+    } finally {
+      client.close()
+    }
+  })
+
+  it('forwards the network violations of the broker once', async () => {
+    const socketPath = generateBrokerSocketPath()
+    server = await createBrokerServer(socketPath, {
+      getProxyPorts: () => ({ httpProxyPort: 1, socksProxyPort: 2 }),
+      getViolations: () => [
+        { line: 'deny file-read-data /etc/shadow', timestamp: new Date(0) },
+        {
+          line: 'Network request to evil.com:443 blocked',
+          type: 'network',
+          host: 'evil.com',
+          timestamp: new Date(0),
+        },
+      ],
+      shutdown: () => {},
+    })
+
+    const client = await BrokerClient.connect(socketPath)
+    try {
+      const store = new SandboxViolationStore()
+      const stop = forwardBrokerViolations(client, store)
+      // Polls during the run, then once more when stopped
+      await new Promise(resolve => setTimeout(resolve, 700))
+      await stop()
+      const violations = store.getViolations()
+      expect(violations.map(v => v.host)).toEqual(['evil.com'])
+      expect(violations[0]?.timestamp).toEqual(new Date(0))
+    } finally {
+      client.close()
+    }
+  })
+
+  it('creates the control socket with owner-only permissions', async () => {
+    const socketPath = generateBrokerSocketPath()
+    server = await createBrokerServer(socketPath, {
+      getProxyPorts: () => ({ httpProxyPort: 1, socksProxyPort: 2 }),
+      getViolations: () => [],
+      shutdown: () => {},
+    })
+
+    expect(statSync(socketPath).mode & 0o777).toBe(0o600)
+    expect(statSync(dirname(socketPath)).mode & 0o777).toBe(0o700)
+  })
+
+  it('reports handler errors to the client', async () => {
+    const socketPath = generateBrokerSocketPath()
+    server = await createBrokerServer(socketPath, {
+      getProxyPorts: () => {
+        throw new Error('proxies not running')
+      },
+      getViolations: () => [],
+      shutdown: () => {},
+    })
+
+    const client = await BrokerClient.connect(socketPath)
+    try {
+      await expect(client.getProxyPorts()).rejects.toThrow(
+        'proxies not running',
+      )
+    } finally {
+      client.close()
+    }
+  })
+
+  it('rejects unknown methods', async () => {
+    const socketPath = generateBrokerSocketPath()
+    server = await createBrokerServer(socketPath, {
+      getProxyPorts: () => ({ httpProxyPort: 1, socksProxyPort: 2 }),
+      getViolations: () => [],
+      shutdown: () => {},
+    })
+
+    const response = await new Promise<string>((resolve, reject) => {
+      const socket = net.createConnection(socketPath, () => {
+        socket.write(JSON.stringify({ id: 1, method: 'updateConfig' }) + '\n')
+      })
+      socket.setEncoding('utf8')
+      socket.once('data', (data: string) => {
+        socket.end()
+        resolve(data)
+      })
+      socket.once('error', reject)
+    })
+
+    expect(JSON.parse(response)).toEqual({ id: -1, error: 'Invalid request' })
+  })
+})
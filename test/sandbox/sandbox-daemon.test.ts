@@ -0,0 +1,112 @@
+import { describe, it, expect, beforeAll, afterAll } from 'bun:test'
+import { readlinkSync, statSync } from 'node:fs'
+import { tmpdir } from 'node:os'
+import { dirname, join } from 'node:path'
+import {
+  DaemonClient,
+  startSandboxDaemon,
+  type DaemonServer,
+} from '../../src/sandbox/sandbox-daemon.js'
+import { getPlatform } from '../../src/utils/platform.js'
+
+function skipIfNotLinux(): boolean {
+  return getPlatform() !== 'linux'
+}
+
+describe('Sandbox daemon', () => {
+  const socketPath = join(tmpdir(), `srt-daemon-test-${process.pid}.sock`)
+  let daemon: DaemonServer | undefined
+  let client: DaemonClient | undefined
+
+  beforeAll(async () => {
+    if (skipIfNotLinux()) {
+      return
+    }
+    daemon = await startSandboxDaemon(
+      {
+        network: { allowedDomains: [], deniedDomains: [] },
+        filesystem: { denyRead: [], allowWrite: [], denyWrite: [] },
+      },
+      socketPath,
+    )
+    client = await DaemonClient.connect(socketPath)
+  })
+
+  afterAll(async () => {
+    client?.close()
+    await daemon?.close()
+  })
+
+  it('creates the socket with owner-only permissions', () => {
+    if (skipIfNotLinux()) {
+      return
+    }
+    expect(statSync(socketPath).mode & 0o777).toBe(0o600)
+    const boundPath = readlinkSync(socketPath)
+    expect(statSync(dirname(boundPath)).mode & 0o777).toBe(0o700)
+  })
+
+  it('runs commands and streams their output', async () => {
+    if (skipIfNotLinux()) {
+      return
+    }
+    let stdout = ''
+    const result = await client!.execute('cat; echo done; exit 3', {
+      stdin: 'from stdin\n',
+      onOutput: (stream, data) => {
+        if (stream === 'stdout') {
+          stdout += data
+        }
+      },
+    })
+    expect(stdout).toBe('from stdin\ndone\n')
+    expect(result.code).toBe(3)
+    expect(result.killedBy).toBeUndefined()
+  })
+
+  it('reports commands killed for running too long', async () => {
+    if (skipIfNotLinux()) {
+      return
+    }
+    const result = await client!.execute('sleep 30', { timeoutSeconds: 0.5 })
+    expect(result.killedBy).toBe('timeout')
+  })
+
+  it('cancels a command when the signal is aborted', async () => {
+    if (skipIfNotLinux()) {
+      return
+    }
+    const controller = new AbortController()
+    const running = client!.execute('sleep 30', { signal: controller.signal })
+    setTimeout(() => controller.abort(), 200)
+    const result = await running
+    expect(result.signal).toBe('SIGTERM')
+  })
+
+  it('queues runs of the same command line', async () => {
+    if (skipIfNotLinux()) {
+      return
+    }
+    const command = 'sleep 0.3; date +%s%N'
+    const outputs = ['', '']
+    const runs = [0, 1].map(index =>
+      client!.execute(command, {
+        onOutput: (stream, data) => {
+          outputs[index] += data
+        },
+      }),
+    )
+    const results = await Promise.all(runs)
+    expect(results.map(result => result.code)).toEqual([0, 0])
+    // The second run only started once the first had finished
+    const [first, second] = outputs.map(output => BigInt(output.trim()))
+    expect(second! - first!).toBeGreaterThanOrEqual(300_000_000n)
+  })
+
+  it('rejects invalid requests', async () => {
+    if (skipIfNotLinux()) {
+      return
+    }
+    await expect(client!.execute('')).rejects.toThrow('command')
+  })
+})
@@ -0,0 +1,181 @@
+import { describe, it, expect, afterEach } from 'bun:test'
+import {
+  parseEnvAssignment,
+  parseEnvFile,
+  getSpawnEnv,
+  resolveSandboxEnv,
+  toEnvUnsetArgs,
+} from '../../src/sandbox/sandbox-env.js'
+import { SandboxManager } from '../../src/sandbox/sandbox-manager.js'
+import { getPlatform } from '../../src/utils/platform.js'
+
+const HOST_ENV = {
+  PATH: '/usr/bin:/bin',
+  HOME: '/home/user',
+  NODE_ENV: 'test',
+  CARGO_HOME: '/home/user/.cargo',
+  KUBECONFIG: '/home/user/.kube/config',
+  AWS_REGION: 'eu-west-1',
+  AWS_SECRET_ACCESS_KEY: 'secret',
+  GITHUB_TOKEN: 'token',
+}
+
+describe('resolveSandboxEnv', () => {
+  it('inherits everything but credentials by default', () => {
+    expect(resolveSandboxEnv(undefined, HOST_ENV)).toEqual({
+      PATH: '/usr/bin:/bin',
+      HOME: '/home/user',
+      NODE_ENV: 'test',
+      CARGO_HOME: '/home/user/.cargo',
+      KUBECONFIG: '/home/user/.kube/config',
+      AWS_REGION: 'eu-west-1',
+    })
+  })
+
+  it('keeps only passthrough matches and essential variables', () => {
+    expect(
+      resolveSandboxEnv({ passthrough: ['CARGO_*', 'NODE_ENV'] }, HOST_ENV),
+    ).toEqual({
+      PATH: '/usr/bin:/bin',
+      HOME: '/home/user',
+      NODE_ENV: 'test',
+      CARGO_HOME: '/home/user/.cargo',
+    })
+  })
+
+  it('inherits credentials only when listed by exact name', () => {
+    const env = resolveSandboxEnv(
+      { passthrough: ['*', 'GITHUB_TOKEN'] },
+      HOST_ENV,
+    )
+    expect(env.GITHUB_TOKEN).toBe('token')
+    expect(env.AWS_SECRET_ACCESS_KEY).toBeUndefined()
+    expect(
+      resolveSandboxEnv({ passthrough: ['AWS_*'] }, HOST_ENV),
+    ).not.toHaveProperty('AWS_SECRET_ACCESS_KEY')
+  })
+
+  it('drops denied variables, even when passed through', () => {
+    const env = resolveSandboxEnv(
+      {
+        passthrough: ['GITHUB_TOKEN'],
+        deny: ['AWS_*', 'GITHUB_TOKEN', 'HOME'],
+      },
+      HOST_ENV,
+    )
+    expect(env).toEqual({ PATH: '/usr/bin:/bin' })
+  })
+
+  it('applies set last', () => {
+    const env = resolveSandboxEnv(
+      { deny: ['NODE_ENV'], set: { NODE_ENV: 'production', CI: '1' } },
+      HOST_ENV,
+    )
+    expect(env.NODE_ENV).toBe('production')
+    expect(env.CI).toBe('1')
+  })
+})
+
+describe('toEnvUnsetArgs', () => {
+  it('unsets the variables the env policy leaves out, by name only', () => {
+    const env = resolveSandboxEnv({ set: { GREETING: 'hello' } }, HOST_ENV)
+    const args = toEnvUnsetArgs(env, HOST_ENV)
+    expect(args).toEqual([
+      '-u',
+      'AWS_SECRET_ACCESS_KEY',
+      '-u',
+      'GITHUB_TOKEN',
+    ])
+    expect(args.join(' ')).not.toContain('secret')
+    expect(args.join(' ')).not.toContain('hello')
+  })
+})
+
+describe('getSpawnEnv', () => {
+  it('adds the set variables to the host environment', () => {
+    const env = resolveSandboxEnv(
+      { set: { GREETING: 'hello', NODE_ENV: 'production' } },
+      HOST_ENV,
+    )
+    const spawnEnv = getSpawnEnv(env, HOST_ENV)
+    expect(spawnEnv.GREETING).toBe('hello')
+    expect(spawnEnv.NODE_ENV).toBe('production')
+    expect(spawnEnv.PATH).toBe(HOST_ENV.PATH)
+  })
+})
+
+describe('parseEnvAssignment', () => {
+  it('splits at the first =', () => {
+    expect(parseEnvAssignment('URL=a=b')).toEqual(['URL', 'a=b'])
+    expect(parseEnvAssignment('EMPTY=')).toEqual(['EMPTY', ''])
+  })
+
+  it('takes bare names from the host environment', () => {
+    expect(parseEnvAssignment('GITHUB_TOKEN', HOST_ENV)).toEqual([
+      'GITHUB_TOKEN',
+      'token',
+    ])
+    expect(() => parseEnvAssignment('MISSING', HOST_ENV)).toThrow('not set')
+  })
+
+  it('rejects invalid names', () => {
+    expect(() => parseEnvAssignment('1FOO=x')).toThrow('Invalid variable')
+    expect(() => parseEnvAssignment('=x')).toThrow('Invalid variable')
+  })
+})
+
+describe('parseEnvFile', () => {
+  it('parses assignments, quotes and comments', () => {
+    const content = [
+      '# test settings',
+      '',
+      'CI=1',
+      'export NODE_ENV=test',
+      'NAME = plain value # comment',
+      "SINGLE='$HOME # kept'",
+      'DOUBLE="line\\none \\"quoted\\""',
+    ].join('\n')
+    expect(parseEnvFile(content)).toEqual({
+      CI: '1',
+      NODE_ENV: 'test',
+      NAME: 'plain value',
+      SINGLE: '$HOME # kept',
+      DOUBLE: 'line\none "quoted"',
+    })
+  })
+
+  it('reports the line of invalid entries', () => {
+    expect(() => parseEnvFile('CI=1\nnot an assignment')).toThrow('line 2')
+  })
+})
+
+describe('SandboxManager.execute environment', () => {
+  afterEach(async () => {
+    await SandboxManager.reset()
+    SandboxManager.registerBackend(undefined)
+  })
+
+  it('passes env.set through the spawn environment', async () => {
+    if (getPlatform() === 'windows') {
+      return
+    }
+
+    // Runs commands unconfined, so the test needs no sandbox dependencies
+    SandboxManager.registerBackend({
+      name: 'test-runner',
+      wrap: async context => context.command,
+    })
+    await SandboxManager.initialize({
+      network: { allowedDomains: [], deniedDomains: [] },
+      filesystem: { denyRead: [], allowWrite: ['.'], denyWrite: [] },
+      env: { set: { SRT_TEST_SET: 'set-value' } },
+    })
+
+    const command = 'echo "$SRT_TEST_SET"'
+    expect(await SandboxManager.wrapWithSandbox(command)).not.toContain(
+      'set-value',
+    )
+    const { stdout } = await SandboxManager.executeWithOutput(command)
+    expect(stdout.trim()).toBe('set-value')
+  })
+})
@@ -0,0 +1,194 @@
+import { describe, it, expect } from 'bun:test'
+import { existsSync, rmSync } from 'node:fs'
+import { tmpdir } from 'node:os'
+import { join } from 'node:path'
+import { Readable } from 'node:stream'
+import {
+  runSandboxedCommand,
+  withStdinInput,
+  SandboxTimeoutError,
+  SandboxViolationKillError,
+  SandboxWrapperError,
+} from '../../src/sandbox/sandbox-execute.js'
+import { getPlatform } from '../../src/utils/platform.js'
+
+describe('runSandboxedCommand', () => {
+  it('resolves with the exit code', async () => {
+    if (getPlatform() === 'windows') {
+      return
+    }
+
+    const result = await runSandboxedCommand('exit 3', 'exit 3', {
+      stdio: 'ignore',
+      timeoutSeconds: 10,
+    })
+    expect(result).toEqual({ code: 3, signal: null })
+  })
+
+  it('kills the whole process group on timeout', async () => {
+    if (getPlatform() === 'windows') {
+      return
+    }
+
+    const marker = join(tmpdir(), `srt-timeout-${process.pid}`)
+    rmSync(marker, { force: true })
+    // The background job would create the marker if it survived the timeout
+    const command = `(sleep 2 && touch ${marker}) & sleep 30`
+
+    const started = Date.now()
+    const error = await runSandboxedCommand(command, command, {
+      stdio: 'ignore',
+      timeoutSeconds: 0.5,
+    }).catch(e => e)
+    expect(error).toBeInstanceOf(SandboxTimeoutError)
+    expect(error.timeoutSeconds).toBe(0.5)
+    expect(Date.now() - started).toBeLessThan(10_000)
+
+    await new Promise(resolve => setTimeout(resolve, 2500))
+    expect(existsSync(marker)).toBe(false)
+  })
+
+  it('terminates the command when aborted', async () => {
+    if (getPlatform() === 'windows') {
+      return
+    }
+
+    const controller = new AbortController()
+    const run = runSandboxedCommand('exec sleep 30', 'sleep 30', {
+      stdio: 'ignore',
+      abortSignal: controller.signal,
+    })
+    setTimeout(() => controller.abort(), 100)
+    const result = await run
+    expect(result.signal).toBe('SIGTERM')
+  })
+
+  it('terminates the command when aborted before it started', async () => {
+    if (getPlatform() === 'windows') {
+      return
+    }
+
+    const controller = new AbortController()
+    controller.abort()
+    const started = Date.now()
+    const result = await runSandboxedCommand('exec sleep 30', 'sleep 30', {
+      stdio: 'ignore',
+      abortSignal: controller.signal,
+      timeoutSeconds: 10,
+    })
+    expect(result.signal).toBe('SIGTERM')
+    expect(Date.now() - started).toBeLessThan(5_000)
+  })
+
+  it('sends the signal named by the abort reason', async () => {
+    if (getPlatform() === 'windows') {
+      return
+    }
+
+    const controller = new AbortController()
+    const run = runSandboxedCommand('exec sleep 30', 'sleep 30', {
+      stdio: 'ignore',
+      abortSignal: controller.signal,
+      timeoutSeconds: 10,
+    })
+    setTimeout(() => controller.abort('SIGINT'), 100)
+    const result = await run
+    expect(result.signal).toBe('SIGINT')
+  })
+
+  it('kills the process group when the violation policy aborts', async () => {
+    if (getPlatform() === 'windows') {
+      return
+    }
+
+    const controller = new AbortController()
+    const run = runSandboxedCommand('sleep 30', 'sleep 30', {
+      stdio: 'ignore',
+      abortSignal: controller.signal,
+      processGroup: true,
+    })
+    const violation = {
+      line: 'deny(EACCES) openat /etc/shadow',
+      timestamp: new Date(),
+    }
+    setTimeout(
+      () =>
+        controller.abort(new SandboxViolationKillError('sleep 30', violation)),
+      100,
+    )
+    const error = await run.catch(e => e)
+    expect(error).toBeInstanceOf(SandboxViolationKillError)
+    expect(error.violation).toBe(violation)
+  })
+})
+
+describe('wrapper status', () => {
+  it('rejects when the wrapper fails before starting the command', async () => {
+    if (getPlatform() === 'windows') {
+      return
+    }
+
+    const error = await runSandboxedCommand('exit 1', 'true', {
+      stdio: 'ignore',
+      statusFd: 3,
+    }).catch(e => e)
+    expect(error).toBeInstanceOf(SandboxWrapperError)
+    expect(error.code).toBe(1)
+  })
+
+  it('passes on the status of a command the wrapper started', async () => {
+    if (getPlatform() === 'windows') {
+      return
+    }
+
+    const command = `echo '{"child-pid": 2}' >&3; exit 1`
+    const result = await runSandboxedCommand(command, 'false', {
+      stdio: 'ignore',
+      statusFd: 3,
+    })
+    expect(result).toEqual({ code: 1, signal: null })
+  })
+})
+
+describe('withStdinInput', () => {
+  it('replaces the stdin of stdio with a pipe', () => {
+    expect(withStdinInput({ stdin: 'x' }).stdio).toEqual([
+      'pipe',
+      'inherit',
+      'inherit',
+    ])
+    expect(
+      withStdinInput({ stdin: 'x', stdio: ['ignore', 'pipe', 'ignore'] }).stdio,
+    ).toEqual(['pipe', 'pipe', 'ignore'])
+    expect(withStdinInput({ stdio: 'ignore' }).stdio).toBe('ignore')
+  })
+
+  it('writes text, bytes and streams to the command and closes stdin', async () => {
+    if (getPlatform() === 'windows') {
+      return
+    }
+
+    const inputs = [
+      'one\ntwo\n',
+      new TextEncoder().encode('one\ntwo\n'),
+      Readable.from(['one\n', 'two\n']),
+    ]
+    for (const stdin of inputs) {
+      let output = ''
+      const { stdio, onSpawn } = withStdinInput({
+        stdin,
+        stdio: ['ignore', 'pipe', 'ignore'],
+        onSpawn: child => {
+          child.stdout?.setEncoding('utf8')
+          child.stdout?.on('data', (chunk: string) => (output += chunk))
+        },
+      })
+      const result = await runSandboxedCommand('wc -l', 'wc -l', {
+        stdio,
+        onSpawn,
+      })
+      expect(result.code).toBe(0)
+      expect(output.trim()).toBe('2')
+    }
+  })
+})
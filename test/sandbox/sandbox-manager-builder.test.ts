@@ -0,0 +1,98 @@
+import { describe, it, expect, afterEach } from 'bun:test'
+import type { SandboxBackend } from '../../src/sandbox/sandbox-backend.js'
+import { SandboxManager } from '../../src/sandbox/sandbox-manager.js'
+import { SandboxConfigValidationError } from '../../src/sandbox/sandbox-manager-builder.js'
+
+describe('SandboxManager.builder', () => {
+  afterEach(async () => {
+    await SandboxManager.reset()
+    SandboxManager.registerBackend(undefined)
+  })
+
+  it('collects the settings into a config', () => {
+    const config = SandboxManager.builder()
+      .allowDomain('registry.npmjs.org')
+      .allowDomain('*.github.com')
+      .denyDomain('gist.github.com')
+      .allowWrite('.', '/tmp')
+      .denyRead('~/.ssh')
+      .denyWrite('./.env')
+      .timeout(60)
+      .backend('nsjail')
+      .with({ allowPty: true })
+      .buildConfig()
+
+    expect(config).toEqual({
+      network: {
+        allowedDomains: ['registry.npmjs.org', '*.github.com'],
+        deniedDomains: ['gist.github.com'],
+      },
+      filesystem: {
+        denyRead: ['~/.ssh'],
+        allowWrite: ['.', '/tmp'],
+        denyWrite: ['./.env'],
+      },
+      timeoutSeconds: 60,
+      backend: 'nsjail',
+      allowPty: true,
+    })
+  })
+
+  it('starts from the most restrictive config', () => {
+    expect(SandboxManager.builder().buildConfig()).toEqual({
+      network: { allowedDomains: [], deniedDomains: [] },
+      filesystem: { denyRead: [], allowWrite: [], denyWrite: [] },
+    })
+  })
+
+  it('reports every invalid setting at build time', async () => {
+    const builder = SandboxManager.builder().allowDomain('*.com')
+    builder.timeout(-1)
+
+    const error = await builder.build().catch(e => e)
+    expect(error).toBeInstanceOf(SandboxConfigValidationError)
+    expect(error.issues).toHaveLength(2)
+    expect(error.issues[0]).toStartWith('network.allowedDomains.0:')
+    expect(error.issues[1]).toStartWith('timeoutSeconds:')
+  })
+
+  it('registers the backend and initializes the manager', async () => {
+    const backend: SandboxBackend = {
+      name: 'test-runner',
+      wrap: async ({ command }) => command,
+    }
+
+    const manager = await SandboxManager.builder()
+      .allowDomain('example.com')
+      .backend(backend)
+      .build()
+
+    expect(manager).toBe(SandboxManager)
+    expect(manager.getSandboxBackend()).toBe('test-runner')
+    expect(manager.getConfig()?.network.allowedDomains).toEqual([
+      'example.com',
+    ])
+  })
+  it('refuses to build over an initialized manager', async () => {
+    const backend: SandboxBackend = {
+      name: 'test-runner',
+      wrap: async ({ command }) => command,
+    }
+    await SandboxManager.builder().backend(backend).build()
+
+    await expect(
+      SandboxManager.builder().allowDomain('example.com').build(),
+    ).rejects.toThrow(/before SandboxManager.initialize/)
+    expect(SandboxManager.getConfig()?.network.allowedDomains).toEqual([])
+  })
+
+  it('clears a backend registered before', async () => {
+    SandboxManager.registerBackend({
+      name: 'test-runner',
+      wrap: async ({ command }) => command,
+    })
+    const builder = SandboxManager.builder()
+    await builder.build().catch(() => undefined)
+    expect(SandboxManager.getSandboxBackend()).not.toBe('test-runner')
+  })
+})
@@ -0,0 +1,44 @@
+import { describe, it, expect } from 'bun:test'
+import shellquote from 'shell-quote'
+import {
+  formatSandboxWrapper,
+  splitWrappedCommand,
+} from '../../src/sandbox/sandbox-profile.js'
+
+describe('splitWrappedCommand', () => {
+  it('recovers the quoted arguments without expanding them', () => {
+    const argv = ['bwrap', '--ro-bind', '/', '/', 'bash', '-c', 'echo $HOME']
+    expect(splitWrappedCommand(shellquote.quote(argv))).toEqual(argv)
+  })
+})
+
+describe('formatSandboxWrapper', () => {
+  it('puts each option and its values on one line', () => {
+    const argv = ['bwrap', '--ro-bind', '/', '/', '--unshare-net', 'bash']
+    const wrappedCommand = shellquote.quote(argv)
+    expect(formatSandboxWrapper({ command: 'ls', wrappedCommand, argv })).toBe(
+      ['bwrap \\', '  --ro-bind / / \\', '  --unshare-net bash'].join('\n'),
+    )
+  })
+
+  it('appends the seatbelt profile', () => {
+    const argv = ['env', 'sandbox-exec', '-f', '/tmp/p.sb', 'bash', '-c', 'ls']
+    const output = formatSandboxWrapper({
+      command: 'ls',
+      wrappedCommand: shellquote.quote(argv),
+      argv,
+      seatbeltProfile: { path: '/tmp/p.sb', content: '(version 1)' },
+    })
+    expect(output).toContain('-f /tmp/p.sb bash \\')
+    expect(output).toEndWith('; Seatbelt profile /tmp/p.sb\n(version 1)')
+  })
+
+  it('says when the command would run unsandboxed', () => {
+    const output = formatSandboxWrapper({
+      command: 'ls',
+      wrappedCommand: 'ls',
+      argv: ['ls'],
+    })
+    expect(output).toContain('would run unsandboxed')
+  })
+})
@@ -0,0 +1,93 @@
+import { describe, it, expect, beforeEach, afterEach } from 'bun:test'
+import type { SandboxRuntimeConfig } from '../../src/sandbox/sandbox-config.js'
+import {
+  SandboxOutputLimitError,
+  SandboxTimeoutError,
+} from '../../src/sandbox/sandbox-execute.js'
+import { SandboxManager } from '../../src/sandbox/sandbox-manager.js'
+import { SandboxSessionClosedError } from '../../src/sandbox/sandbox-session.js'
+import { getPlatform } from '../../src/utils/platform.js'
+
+const config: SandboxRuntimeConfig = {
+  network: { allowedDomains: [], deniedDomains: [] },
+  filesystem: { denyRead: [], allowWrite: ['.'], denyWrite: [] },
+}
+
+describe.skipIf(getPlatform() === 'windows')('sandbox sessions', () => {
+  beforeEach(async () => {
+    // Runs the shell unconfined, so the tests need no sandbox dependencies
+    SandboxManager.registerBackend({
+      name: 'test-runner',
+      wrap: async context => context.command,
+    })
+    await SandboxManager.initialize(config)
+  })
+
+  afterEach(async () => {
+    await SandboxManager.reset()
+    SandboxManager.registerBackend(undefined)
+  })
+
+  it('keeps the working directory and variables between runs', async () => {
+    const session = await SandboxManager.openSession()
+    try {
+      await session.run('cd /tmp && export SESSION_VALUE=kept')
+      const output = await session.run(
+        'pwd; echo "$SESSION_VALUE"; echo oops >&2; exit_code=3; (exit $exit_code)',
+      )
+      expect(output).toMatchObject({
+        code: 3,
+        signal: null,
+        stdout: '/tmp\nkept\n',
+        stderr: 'oops\n',
+      })
+    } finally {
+      await session.close()
+    }
+  })
+
+  it('runs commands queued at once one after another', async () => {
+    const session = await SandboxManager.openSession()
+    try {
+      const outputs = await Promise.all([
+        session.run('sleep 0.1; echo first'),
+        session.run('echo second'),
+      ])
+      expect(outputs.map(output => output.stdout)).toEqual([
+        'first\n',
+        'second\n',
+      ])
+    } finally {
+      await session.close()
+    }
+  })
+
+  it('ends the session when a command exits the shell', async () => {
+    const session = await SandboxManager.openSession()
+    await expect(session.run('exit 4')).rejects.toBeInstanceOf(
+      SandboxSessionClosedError,
+    )
+    expect(session.closed).toBe(true)
+    await expect(session.run('true')).rejects.toBeInstanceOf(
+      SandboxSessionClosedError,
+    )
+  })
+
+  it('kills the session when a run times out', async () => {
+    const session = await SandboxManager.openSession()
+    await expect(
+      session.run('sleep 5', { timeoutSeconds: 0.2 }),
+    ).rejects.toBeInstanceOf(SandboxTimeoutError)
+    await session.exited
+    expect(session.closed).toBe(true)
+  })
+
+  it('closes the session when a run buffers past maxBuffer', async () => {
+    const session = await SandboxManager.openSession({ maxBuffer: 1024 })
+    await expect(
+      session.run('head -c 4096 /dev/zero | tr "\\0" x'),
+    ).rejects.toBeInstanceOf(SandboxOutputLimitError)
+    await session.exited
+    expect(session.closed).toBe(true)
+  })
+})
@@ -0,0 +1,65 @@
+import { describe, it, expect, beforeEach, afterEach } from 'bun:test'
+import * as fs from 'node:fs'
+import { tmpdir } from 'node:os'
+import { join } from 'node:path'
+import { ScratchDir } from '../../src/sandbox/scratch-dir.js'
+import { generateProxyEnvVars } from '../../src/sandbox/sandbox-utils.js'
+
+describe('ScratchDir', () => {
+  let root: string
+
+  beforeEach(() => {
+    root = fs.mkdtempSync(join(tmpdir(), 'srt-scratch-test-'))
+  })
+
+  afterEach(() => {
+    fs.rmSync(root, { recursive: true, force: true })
+  })
+
+  it('creates a private directory per session', () => {
+    const first = ScratchDir.create(10, root)
+    const second = ScratchDir.create(10, root)
+
+    expect(first.path).not.toBe(second.path)
+    expect(fs.statSync(first.path).mode & 0o777).toBe(0o700)
+
+    first.remove()
+    expect(fs.existsSync(first.path)).toBe(false)
+    expect(fs.existsSync(second.path)).toBe(true)
+  })
+
+  it('removes scratch directories of sessions that are no longer running', () => {
+    // PIDs are capped well below this value on Linux and macOS
+    const stale = join(root, '99999999-abcdef')
+    fs.mkdirSync(stale)
+
+    ScratchDir.create(10, root)
+    expect(fs.existsSync(stale)).toBe(false)
+  })
+
+  it('enforces the size cap', () => {
+    const scratch = ScratchDir.create(1, root)
+    fs.writeFileSync(join(scratch.path, 'small'), Buffer.alloc(1024))
+    expect(() => scratch.assertWithinLimit()).not.toThrow()
+
+    fs.mkdirSync(join(scratch.path, 'nested'))
+    fs.writeFileSync(
+      join(scratch.path, 'nested', 'large'),
+      Buffer.alloc(2 * 1024 * 1024),
+    )
+    expect(scratch.getUsageBytes()).toBeGreaterThan(2 * 1024 * 1024)
+    expect(() => scratch.assertWithinLimit()).toThrow(/over its 1 MB limit/)
+  })
+})
+
+describe('generateProxyEnvVars with a scratch directory', () => {
+  it('points TMPDIR and SRT_SCRATCH_DIR at the scratch directory', () => {
+    const env = generateProxyEnvVars(undefined, undefined, '/scratch/abc')
+    expect(env).toContain('TMPDIR=/scratch/abc')
+    expect(env).toContain('SRT_SCRATCH_DIR=/scratch/abc')
+  })
+
+  it('keeps the default TMPDIR without one', () => {
+    expect(generateProxyEnvVars()).toContain('TMPDIR=/tmp/claude')
+  })
+})
@@ -0,0 +1,139 @@
+import { describe, it, expect } from 'bun:test'
+import { existsSync, readFileSync, statSync } from 'node:fs'
+import { dirname } from 'node:path'
+import shellquote from 'shell-quote'
+import {
+  cleanupMacOSProfiles,
+  DEFAULT_MACH_SERVICES,
+  escapePath,
+  generateMachLookupRules,
+  wrapCommandWithSandboxMacOS,
+} from '../../src/sandbox/macos-sandbox-utils.js'
+import {
+  SandboxRuntimeConfigSchema,
+  type SeatbeltConfig,
+} from '../../src/sandbox/sandbox-config.js'
+import { readMacOSProfile } from './read-macos-profile.js'
+
+function wrap(seatbelt?: SeatbeltConfig): string {
+  return readMacOSProfile(
+    wrapCommandWithSandboxMacOS({
+      command: 'echo hi',
+      needsNetworkRestriction: true,
+      readConfig: undefined,
+      writeConfig: { allowOnly: ['/tmp'], denyWithinAllow: [] },
+      seatbelt,
+    }),
+  )
+}
+
+describe('seatbelt template', () => {
+  it('includes desktop services in the default template', () => {
+    const profile = wrap()
+    expect(profile).toContain('(allow user-preference-read)')
+    expect(profile).toContain('(allow ipc-posix-shm)')
+    expect(profile).toContain('(allow process-fork)')
+  })
+
+  it('keeps only the core rules in the minimal template', () => {
+    const profile = wrap({ template: 'minimal' })
+    expect(profile).not.toContain('(allow user-preference-read)')
+    expect(profile).not.toContain('(allow ipc-posix-shm)')
+    expect(profile).not.toContain('iokit-open')
+    expect(profile).toContain('(allow process-fork)')
+    expect(profile).toContain('(allow sysctl-read')
+  })
+})
+
+describe('seatbelt extraRules', () => {
+  it('appends custom rules after the generated ones', () => {
+    const rule = '(allow mach-lookup (global-name "com.example.service"))'
+    const profile = wrap({ extraRules: [rule] })
+    expect(profile).toContain(rule)
+    expect(profile.indexOf(rule)).toBeGreaterThan(
+      profile.indexOf('; File write'),
+    )
+  })
+
+  it('only accepts single balanced forms', () => {
+    const parse = (extraRules: string[]) =>
+      SandboxRuntimeConfigSchema.safeParse({
+        network: { allowedDomains: [], deniedDomains: [] },
+        filesystem: { denyRead: [], allowWrite: [], denyWrite: [] },
+        seatbelt: { extraRules },
+      }).success
+
+    expect(parse(['(allow file-read* (literal "/opt/tool"))'])).toBe(true)
+    expect(parse(['(allow file-read* (regex #"^/opt/(a|b)"))'])).toBe(true)
+    expect(parse(['(allow network*))(allow default'])).toBe(false)
+    expect(parse(['(allow file-read*'])).toBe(false)
+    expect(parse(['allow default'])).toBe(false)
+    expect(parse(['(allow default) ; )'])).toBe(false)
+  })
+})
+
+describe('seatbelt machServices', () => {
+  it('allows the default services one by one', () => {
+    const profile = wrap()
+    for (const service of DEFAULT_MACH_SERVICES) {
+      expect(profile).toContain(`(global-name "${service}")`)
+    }
+    expect(profile).not.toContain('(allow mach*)')
+    expect(profile).not.toContain('(allow mach-lookup)')
+  })
+
+  it('replaces the default set when configured', () => {
+    const profile = wrap({ machServices: ['com.example.helper'] })
+    expect(profile).toContain('(global-name "com.example.helper")')
+    expect(profile).not.toContain('(global-name "com.apple.fonts")')
+  })
+
+  it('allows no services in the minimal template by default', () => {
+    expect(generateMachLookupRules([])).toEqual([])
+    expect(wrap({ template: 'minimal' })).not.toContain('mach-lookup')
+  })
+
+  it('rejects malformed service names', () => {
+    const result = SandboxRuntimeConfigSchema.safeParse({
+      network: { allowedDomains: [], deniedDomains: [] },
+      filesystem: { denyRead: [], allowWrite: [], denyWrite: [] },
+      seatbelt: { machServices: ['com.apple.fonts") (allow default'] },
+    })
+    expect(result.success).toBe(false)
+  })
+})
+
+describe('seatbelt profile files', () => {
+  function profilePath(command: string): string {
+    const args = shellquote.parse(
+      wrapCommandWithSandboxMacOS({
+        command,
+        needsNetworkRestriction: true,
+        readConfig: undefined,
+        writeConfig: undefined,
+      }),
+    )
+    return String(args[args.indexOf('-f') + 1])
+  }
+
+  it('reuses the file of an identical profile', () => {
+    const first = profilePath('echo cached')
+    expect(profilePath('echo cached')).toBe(first)
+    expect(profilePath('echo other')).not.toBe(first)
+    expect(statSync(first).mode & 0o777).toBe(0o600)
+  })
+
+  it('denies writes to the profile directory', () => {
+    const path = profilePath('echo hi')
+    expect(readFileSync(path, 'utf8')).toContain(
+      `(deny file-write* (subpath ${escapePath(dirname(path))})`,
+    )
+  })
+
+  it('removes the profiles on cleanup', () => {
+    const path = profilePath('echo hi')
+    cleanupMacOSProfiles()
+    expect(existsSync(path)).toBe(false)
+    expect(existsSync(dirname(path))).toBe(false)
+  })
+})
@@ -0,0 +1,138 @@
+import { describe, it, expect } from 'bun:test'
+import { existsSync, readFileSync, statSync } from 'node:fs'
+import { dirname, join } from 'node:path'
+import {
+  buildSeccompFilter,
+  buildSyscallDenyFilter,
+  buildUnixSocketBlockFilter,
+  STRICT_DENIED_SYSCALLS,
+  SYSCALL_NUMBERS,
+} from '../../src/sandbox/seccomp-bpf.js'
+import {
+  cleanupSeccompFilter,
+  writeSeccompFilter,
+} from '../../src/sandbox/generate-seccomp-filter.js'
+
+const VENDOR_DIR = join(process.cwd(), 'vendor', 'seccomp')
+
+// struct sock_filter { u16 code; u8 jt; u8 jf; u32 k }
+function decode(filter: Buffer) {
+  const instructions = []
+  for (let offset = 0; offset < filter.length; offset += 8) {
+    instructions.push({
+      code: filter.readUInt16LE(offset),
+      jt: filter.readUInt8(offset + 2),
+      jf: filter.readUInt8(offset + 3),
+      k: filter.readUInt32LE(offset + 4),
+    })
+  }
+  return instructions
+}
+
+describe('buildUnixSocketBlockFilter', () => {
+  for (const arch of ['x64', 'arm64'] as const) {
+    it(`should match the pre-generated ${arch} filter byte for byte`, () => {
+      const vendorPath = join(VENDOR_DIR, arch, 'unix-block.bpf')
+      if (!existsSync(vendorPath)) {
+        return
+      }
+
+      const filter = buildUnixSocketBlockFilter(arch)
+      expect(filter.equals(readFileSync(vendorPath))).toBe(true)
+    })
+  }
+
+  it('should check the architecture before anything else', () => {
+    const [loadArch, checkArch] = decode(buildUnixSocketBlockFilter('arm64'))
+    expect(loadArch.k).toBe(4)
+    expect(checkArch.k).toBe(0xc00000b7)
+  })
+})
+
+describe('buildSeccompFilter', () => {
+  it('should end with allow, EPERM and kill returns', () => {
+    const instructions = decode(buildSeccompFilter('x64', []))
+    const returns = instructions.slice(-3).map(i => i.k)
+    expect(returns).toEqual([0x7fff0000, 0x00050001, 0])
+  })
+
+  it('should jump every matching rule to the EPERM return', () => {
+    const filter = buildSeccompFilter('x64', [
+      { syscall: 101 },
+      { syscall: SYSCALL_NUMBERS.socket.x64, arg0: 1 },
+      { syscall: 165 },
+    ])
+    const instructions = decode(filter)
+    const denyIndex = instructions.length - 2
+
+    // Each rule's final comparison must land on the deny return when true
+    for (const [index, syscall] of [
+      [5, 101],
+      [12, 165],
+    ]) {
+      expect(instructions[index].k).toBe(syscall)
+      expect(index + 1 + instructions[index].jt).toBe(denyIndex)
+    }
+
+    // The syscall number is reloaded after the argument check
+    expect(instructions[11]).toEqual({ code: 0x20, jt: 0, jf: 0, k: 0 })
+  })
+})
+
+describe('writeSeccompFilter', () => {
+  it('should reuse the file for identical filters and clean it up', () => {
+    const filter = buildUnixSocketBlockFilter('x64')
+    const first = writeSeccompFilter(filter)
+    const second = writeSeccompFilter(Buffer.from(filter))
+
+    expect(first).toBeTruthy()
+    expect(second).toBe(first)
+    expect(readFileSync(first!).equals(filter)).toBe(true)
+
+    cleanupSeccompFilter(first!)
+    expect(existsSync(first!)).toBe(false)
+  })
+
+  it('should write filters to a private directory', () => {
+    const filterPath = writeSeccompFilter(buildUnixSocketBlockFilter('x64'))!
+    try {
+      expect(statSync(dirname(filterPath)).mode & 0o777).toBe(0o700)
+      expect(statSync(filterPath).mode & 0o777).toBe(0o600)
+    } finally {
+      cleanupSeccompFilter(filterPath)
+    }
+    expect(existsSync(dirname(filterPath))).toBe(false)
+  })
+
+describe('buildSyscallDenyFilter', () => {
+  it('should equal the Unix socket filter with no extra syscalls', () => {
+    expect(
+      buildSyscallDenyFilter('arm64', [], true).equals(
+        buildUnixSocketBlockFilter('arm64'),
+      ),
+    ).toBe(true)
+  })
+
+  it('should deny each listed syscall once', () => {
+    const instructions = decode(
+      buildSyscallDenyFilter('arm64', ['ptrace', 'mount', 'ptrace'], false),
+    )
+    const comparisons = instructions
+      .filter(i => i.code === 0x15)
+      .map(i => i.k)
+      .slice(1) // skip the architecture check
+    expect(comparisons).toEqual([
+      SYSCALL_NUMBERS.ptrace.arm64,
+      SYSCALL_NUMBERS.mount.arm64,
+    ])
+  })
+
+  it('should fit the strict profile within BPF jump range', () => {
+    for (const arch of ['x64', 'arm64'] as const) {
+      expect(() =>
+        buildSyscallDenyFilter(arch, STRICT_DENIED_SYSCALLS, true),
+      ).not.toThrow()
+    }
+  })
+})
+})
@@ -0,0 +1,119 @@
+import { describe, it, expect, beforeEach, afterEach } from 'bun:test'
+import * as fs from 'node:fs'
+import { tmpdir } from 'node:os'
+import { join } from 'node:path'
+import {
+  applyTemporaryRules,
+  TemporaryRuleTracker,
+} from '../../src/sandbox/temporary-rules.js'
+import {
+  SandboxRuntimeConfigSchema,
+  type TemporaryRuleConfig,
+} from '../../src/sandbox/sandbox-config.js'
+
+const NOW = new Date('2026-06-01T12:00:00Z')
+
+describe('TemporaryRuleTracker', () => {
+  let dir: string
+  let statePath: string
+
+  beforeEach(() => {
+    dir = fs.mkdtempSync(join(tmpdir(), 'srt-temporary-rules-'))
+    statePath = join(dir, 'temporary-rules.json')
+  })
+
+  afterEach(() => {
+    fs.rmSync(dir, { recursive: true, force: true })
+  })
+
+  it('ignores rules past their expiry time, with one warning', () => {
+    const rules: TemporaryRuleConfig[] = [
+      { allowedDomains: ['old.internal'], expires: '2026-05-01T00:00:00Z' },
+      {
+        allowedDomains: ['staging.internal'],
+        expires: '2026-07-01T00:00:00Z',
+      },
+    ]
+    const tracker = new TemporaryRuleTracker(statePath)
+    expect(tracker.select(rules, NOW)).toEqual([rules[1]])
+    tracker.select(rules, NOW)
+    expect(tracker.getWarnings()).toEqual([
+      'Ignoring temporary rule (old.internal): expired at 2026-05-01T00:00:00Z',
+    ])
+  })
+
+  it('stops applying a rule that expires during a session', () => {
+    const rule = {
+      allowedDomains: ['staging.internal'],
+      expires: '2026-06-01T13:00:00Z',
+      reason: 'debugging',
+    }
+    const tracker = new TemporaryRuleTracker(statePath)
+    expect(tracker.select([rule], NOW)).toEqual([rule])
+    expect(tracker.select([rule], new Date('2026-06-01T13:00:00Z'))).toEqual(
+      [],
+    )
+    expect(tracker.getWarnings()[0]).toContain(
+      'temporary rule "debugging" (staging.internal)',
+    )
+  })
+
+  it('counts runs across sessions, once per session', () => {
+    const rule = { allowWrite: ['/var/tmp/trace'], maxRuns: 2 }
+    for (let session = 0; session < 2; session++) {
+      const tracker = new TemporaryRuleTracker(statePath)
+      expect(tracker.select([rule], NOW)).toEqual([rule])
+      expect(tracker.select([rule], NOW)).toEqual([rule])
+    }
+
+    const tracker = new TemporaryRuleTracker(statePath)
+    expect(tracker.select([rule], NOW)).toEqual([])
+    expect(tracker.getWarnings()).toEqual([
+      'Ignoring temporary rule (write /var/tmp/trace): used all 2 runs',
+    ])
+    const counts = JSON.parse(fs.readFileSync(statePath, 'utf8'))
+    expect(Object.values(counts)).toEqual([2])
+  })
+
+  it('keeps counting when the limits of a rule change', () => {
+    new TemporaryRuleTracker(statePath).select(
+      [{ allowedDomains: ['a.internal'], maxRuns: 1 }],
+      NOW,
+    )
+    const raised = { allowedDomains: ['a.internal'], maxRuns: 2 }
+    expect(new TemporaryRuleTracker(statePath).select([raised], NOW)).toEqual([
+      raised,
+    ])
+    expect(new TemporaryRuleTracker(statePath).select([raised], NOW)).toEqual(
+      [],
+    )
+  })
+})
+
+describe('applyTemporaryRules', () => {
+  it('adds the allow lists of active rules to the config', () => {
+    const config = SandboxRuntimeConfigSchema.parse({
+      network: { allowedDomains: ['example.com'], deniedDomains: [] },
+      filesystem: { denyRead: [], allowWrite: ['.'], denyWrite: [] },
+    })
+    const applied = applyTemporaryRules(config, [
+      { allowedDomains: ['staging.internal'], maxRuns: 1 },
+      { allowWrite: ['/var/tmp/trace', '.'], maxRuns: 1 },
+    ])
+    expect(applied.network.allowedDomains).toEqual([
+      'example.com',
+      'staging.internal',
+    ])
+    expect(applied.filesystem.allowWrite).toEqual(['.', '/var/tmp/trace'])
+    expect(applyTemporaryRules(config, [])).toBe(config)
+  })
+
+  it('rejects rules without an expiry', () => {
+    const result = SandboxRuntimeConfigSchema.safeParse({
+      network: { allowedDomains: [], deniedDomains: [] },
+      filesystem: { denyRead: [], allowWrite: [], denyWrite: [] },
+      temporaryRules: [{ allowedDomains: ['staging.internal'] }],
+    })
+    expect(result.success).toBe(false)
+  })
+})
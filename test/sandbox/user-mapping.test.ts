@@ -0,0 +1,56 @@
+import { describe, it, expect } from 'bun:test'
+import { buildUserNamespaceArgs } from '../../src/sandbox/linux-sandbox-utils.js'
+import { SandboxRuntimeConfigSchema } from '../../src/sandbox/sandbox-config.js'
+
+describe('buildUserNamespaceArgs', () => {
+  it('returns no arguments when no mapping is configured', () => {
+    expect(buildUserNamespaceArgs(undefined)).toEqual([])
+  })
+
+  it('maps to nobody', () => {
+    expect(buildUserNamespaceArgs('nobody')).toEqual([
+      '--unshare-user',
+      '--uid',
+      '65534',
+      '--gid',
+      '65534',
+    ])
+  })
+
+  it('maps to a custom uid and gid', () => {
+    expect(buildUserNamespaceArgs({ uid: 1000, gid: 100 })).toEqual([
+      '--unshare-user',
+      '--uid',
+      '1000',
+      '--gid',
+      '100',
+    ])
+  })
+
+  it('never maps the current user to root', () => {
+    const args = buildUserNamespaceArgs('current')
+    const uid = args[args.indexOf('--uid') + 1]
+    const gid = args[args.indexOf('--gid') + 1]
+
+    expect(args[0]).toBe('--unshare-user')
+    expect(uid).not.toBe('0')
+    expect(gid).not.toBe('0')
+    if (process.getuid && process.getuid() !== 0) {
+      expect(uid).toBe(String(process.getuid()))
+    }
+  })
+
+  it('rejects root in custom mappings', () => {
+    for (const userMapping of [
+      { uid: 0, gid: 1000 },
+      { uid: 1000, gid: 0 },
+    ]) {
+      const result = SandboxRuntimeConfigSchema.safeParse({
+        network: { allowedDomains: [], deniedDomains: [] },
+        filesystem: { denyRead: [], allowWrite: [], denyWrite: [] },
+        userMapping,
+      })
+      expect(result.success).toBe(false)
+    }
+  })
+})
@@ -0,0 +1,100 @@
+import { describe, it, expect } from 'bun:test'
+import {
+  exportViolations,
+  getViolationExportFormat,
+} from '../../src/sandbox/violation-export.js'
+import { SandboxViolationStore } from '../../src/sandbox/sandbox-violation-store.js'
+import type { SandboxViolationEvent } from '../../src/sandbox/macos-sandbox-utils.js'
+
+const timestamp = new Date('2026-01-01T00:00:00.000Z')
+
+const violations: SandboxViolationEvent[] = [
+  {
+    line: 'cat(42) deny(1) file-read-data /etc/shadow',
+    command: 'cat /etc/shadow',
+    timestamp,
+    pid: 42,
+    processName: 'cat',
+    path: '/etc/shadow',
+  },
+  {
+    line: 'Blocked connection to "evil.com", port 443',
+    timestamp,
+    type: 'network',
+    host: 'evil.com',
+    port: 443,
+  },
+]
+
+describe('exportViolations', () => {
+  it('exports JSON with ISO timestamps', () => {
+    const parsed = JSON.parse(exportViolations(violations, 'json'))
+    expect(parsed).toHaveLength(2)
+    expect(parsed[0].timestamp).toBe('2026-01-01T00:00:00.000Z')
+    expect(parsed[1].host).toBe('evil.com')
+  })
+
+  it('exports CSV with a header and quoted fields', () => {
+    const lines = exportViolations(violations, 'csv').trimEnd().split('\r\n')
+    expect(lines[0]).toBe(
+      'timestamp,type,subtype,access,command,pid,processName,path,host,port,line',
+    )
+    expect(lines[1]).toBe(
+      '2026-01-01T00:00:00.000Z,,,,cat /etc/shadow,42,cat,/etc/shadow,,,cat(42) deny(1) file-read-data /etc/shadow',
+    )
+    expect(lines[2]).toEndWith(
+      ',evil.com,443,"Blocked connection to ""evil.com"", port 443"',
+    )
+  })
+
+  it('neutralizes CSV fields that spreadsheets would run as formulas', () => {
+    const csv = exportViolations(
+      [
+        {
+          line: '=HYPERLINK("http://evil.com")',
+          command: '+cmd',
+          path: '@SUM(A1)',
+          processName: '-x',
+          timestamp: new Date('2026-01-01T00:00:00.000Z'),
+        },
+      ],
+      'csv',
+    )
+    const row = csv.trimEnd().split('\r\n')[1]
+    expect(row).toContain(`,'+cmd,`)
+    expect(row).toContain(`,'-x,`)
+    expect(row).toContain(`,'@SUM(A1),`)
+    expect(row).toEndWith(`,"'=HYPERLINK(""http://evil.com"")"`)
+  })
+
+  it('exports one SARIF result per violation', () => {
+    const sarif = JSON.parse(exportViolations(violations, 'sarif'))
+    expect(sarif.version).toBe('2.1.0')
+    const [run] = sarif.runs
+    expect(run.tool.driver.name).toBe('srt')
+    expect(run.results.map((r: { ruleId: string }) => r.ruleId)).toEqual([
+      'sandbox-denial',
+      'network-denial',
+    ])
+    expect(
+      run.results[0].locations[0].physicalLocation.artifactLocation.uri,
+    ).toBe('file:///etc/shadow')
+    expect(run.results[1].locations).toBeUndefined()
+    expect(run.results[1].properties.host).toBe('evil.com')
+  })
+
+  it('exports from the violation store', () => {
+    const store = new SandboxViolationStore()
+    store.addViolation(violations[0]!)
+    expect(JSON.parse(store.export('json'))).toHaveLength(1)
+  })
+})
+
+describe('getViolationExportFormat', () => {
+  it('picks the format from the file extension', () => {
+    expect(getViolationExportFormat('out.csv')).toBe('csv')
+    expect(getViolationExportFormat('out.sarif')).toBe('sarif')
+    expect(getViolationExportFormat('out.json')).toBe('json')
+    expect(getViolationExportFormat('report')).toBe('json')
+  })
+})
@@ -0,0 +1,156 @@
+import { describe, it, expect } from 'bun:test'
+import * as fs from 'node:fs'
+import * as net from 'node:net'
+import { tmpdir } from 'node:os'
+import { dirname, join } from 'node:path'
+import { SandboxViolationStore } from '../../src/sandbox/sandbox-violation-store.js'
+import {
+  followViolationFeed,
+  startViolationFeed,
+} from '../../src/sandbox/violation-feed.js'
+import { violation } from './violation.js'
+
+function connect(socketPath: string): Promise<net.Socket> {
+  return new Promise((resolve, reject) => {
+    const socket = net.connect(socketPath, () => resolve(socket))
+    socket.once('error', reject)
+  })
+}
+
+/**
+ * Read NDJSON lines from the socket until count have arrived
+ */
+function readLines(socket: net.Socket, count: number): Promise<string[]> {
+  return new Promise(resolve => {
+    let buffer = ''
+    socket.setEncoding('utf8')
+    socket.on('data', (chunk: string) => {
+      buffer += chunk
+      const lines = buffer.split('\n').filter(Boolean)
+      if (lines.length >= count) {
+        resolve(lines)
+      }
+    })
+  })
+}
+
+describe('startViolationFeed', () => {
+  const socketPath = join(tmpdir(), `srt-feed-test-${process.pid}.sock`)
+
+  it('streams violations to every connected client as NDJSON', async () => {
+    const store = new SandboxViolationStore()
+    store.addViolation(violation('before anyone connected'))
+    const feed = await startViolationFeed(socketPath, store)
+    try {
+      expect(fs.statSync(socketPath).mode & 0o777).toBe(0o600)
+      const boundPath = fs.readlinkSync(socketPath)
+      expect(fs.statSync(dirname(boundPath)).mode & 0o777).toBe(0o700)
+
+      const first = await connect(socketPath)
+      const second = await connect(socketPath)
+      const firstLines = readLines(first, 2)
+      const secondLines = readLines(second, 2)
+
+      // Clients are registered once the server has accepted them
+      await new Promise(resolve => setTimeout(resolve, 50))
+      store.addViolation(violation('deny one'))
+      store.addViolation(violation('deny two'))
+
+      for (const lines of [await firstLines, await secondLines]) {
+        expect(lines.map(line => JSON.parse(line).line)).toEqual([
+          'deny one',
+          'deny two',
+        ])
+      }
+      first.destroy()
+      second.destroy()
+    } finally {
+      await feed.close()
+    }
+    expect(fs.existsSync(socketPath)).toBe(false)
+  })
+
+  it('replaces a stale socket but not other files', async () => {
+    const stale = net.createServer()
+    await new Promise<void>(resolve => stale.listen(socketPath, resolve))
+    const feed = await startViolationFeed(
+      socketPath,
+      new SandboxViolationStore(),
+    )
+    await feed.close()
+    await new Promise(resolve => stale.close(resolve))
+
+    fs.writeFileSync(socketPath, 'not a socket')
+    try {
+      await expect(
+        startViolationFeed(socketPath, new SandboxViolationStore()),
+      ).rejects.toThrow('is not a socket')
+      expect(fs.readFileSync(socketPath, 'utf8')).toBe('not a socket')
+    } finally {
+      fs.rmSync(socketPath, { force: true })
+    }
+  })
+
+  it('ignores what clients send', async () => {
+    const store = new SandboxViolationStore()
+    const feed = await startViolationFeed(socketPath, store)
+    try {
+      const client = await connect(socketPath)
+      const lines = readLines(client, 1)
+      client.write('{"method":"shutdown"}\n')
+      await new Promise(resolve => setTimeout(resolve, 50))
+      store.addViolation(violation('still streaming'))
+
+      expect(JSON.parse((await lines)[0]!).line).toBe('still streaming')
+      client.destroy()
+    } finally {
+      await feed.close()
+    }
+  })
+})
+
+describe('followViolationFeed', () => {
+  const socketPath = join(tmpdir(), `srt-follow-test-${process.pid}.sock`)
+
+  it('yields violations until the feed closes', async () => {
+    const store = new SandboxViolationStore()
+    const feed = await startViolationFeed(socketPath, store)
+    const received: string[] = []
+    const following = (async () => {
+      for await (const event of followViolationFeed(socketPath)) {
+        expect(event.timestamp).toBeInstanceOf(Date)
+        received.push(event.line)
+      }
+    })()
+
+    await new Promise(resolve => setTimeout(resolve, 50))
+    store.addViolation(violation('deny one'))
+    store.addViolation(violation('deny two'))
+    await new Promise(resolve => setTimeout(resolve, 50))
+    await feed.close()
+    await following
+
+    expect(received).toEqual(['deny one', 'deny two'])
+  })
+
+  it('stops when the signal is aborted', async () => {
+    const store = new SandboxViolationStore()
+    const feed = await startViolationFeed(socketPath, store)
+    try {
+      const controller = new AbortController()
+      const following = (async () => {
+        for await (const _ of followViolationFeed(
+          socketPath,
+          controller.signal,
+        )) {
+          // Nothing is reported
+        }
+      })()
+      await new Promise(resolve => setTimeout(resolve, 50))
+      controller.abort()
+      await following
+    } finally {
+      await feed.close()
+    }
+  })
+})
@@ -0,0 +1,125 @@
+import { describe, it, expect } from 'bun:test'
+import { isViolationIgnored } from '../../src/sandbox/violation-filter.js'
+import { SandboxViolationStore } from '../../src/sandbox/sandbox-violation-store.js'
+import { violation } from './violation.js'
+
+describe('isViolationIgnored', () => {
+  it('ignores nothing without a config', () => {
+    expect(isViolationIgnored(violation('deny /x'), undefined)).toBe(false)
+  })
+
+  it('keeps substring matching for plain patterns', () => {
+    const config = { 'git push': ['/usr/bin/nc'] }
+    expect(
+      isViolationIgnored(
+        violation('nc(1) deny(1) file-read-data /usr/bin/nc', {
+          command: 'cd repo && git push origin main',
+        }),
+        config,
+      ),
+    ).toBe(true)
+    expect(
+      isViolationIgnored(
+        violation('nc(1) deny(1) file-read-data /usr/bin/nc', {
+          command: 'git fetch',
+        }),
+        config,
+      ),
+    ).toBe(false)
+  })
+
+  it('matches command globs against the whole command', () => {
+    const config = { '*mdworker*': ['/Users'] }
+    expect(
+      isViolationIgnored(
+        violation('mdworker(5) deny(1) file-read-data /Users/me/a', {
+          command: '/usr/libexec/mdworker_shared -s',
+        }),
+        config,
+      ),
+    ).toBe(true)
+    expect(
+      isViolationIgnored(
+        violation('ls(5) deny(1) file-read-data /Users/me/a', {
+          command: 'ls /Users',
+        }),
+        { 'l?': ['/Users'] },
+      ),
+    ).toBe(false)
+  })
+
+  it('matches path globs against the whole path', () => {
+    const config = { '*': ['**/.DS_Store'] }
+    expect(
+      isViolationIgnored(
+        violation('deny(EROFS) openat /home/me/project/.DS_Store', {
+          command: 'touch x',
+          path: '/home/me/project/.DS_Store',
+        }),
+        config,
+      ),
+    ).toBe(true)
+    expect(
+      isViolationIgnored(
+        violation('deny(EROFS) openat /home/me/.DS_Store.bak', {
+          command: 'touch x',
+          path: '/home/me/.DS_Store.bak',
+        }),
+        config,
+      ),
+    ).toBe(false)
+  })
+
+  it('matches host globs against network denials without a command', () => {
+    const network = violation(
+      'Network request to api.example.com:443 blocked: not allowed',
+      { type: 'network', host: 'api.example.com', port: 443 },
+    )
+    expect(isViolationIgnored(network, { '*': ['*.example.com'] })).toBe(true)
+    expect(isViolationIgnored(network, { '*': ['api.example.com:4*'] })).toBe(
+      true,
+    )
+    expect(isViolationIgnored(network, { '*': ['*.example.org'] })).toBe(
+      false,
+    )
+    // Command patterns never match violations without a command
+    expect(isViolationIgnored(network, { curl: ['*.example.com'] })).toBe(
+      false,
+    )
+  })
+})
+
+describe('SandboxViolationStore ignoreViolations', () => {
+  it('drops ignored violations and keeps the rest', () => {
+    const store = new SandboxViolationStore()
+    store.setIgnoreViolations({ '*': ['*.example.com'] })
+
+    store.addViolation(
+      violation('Network request to a.example.com:443 blocked', {
+        type: 'network',
+        host: 'a.example.com',
+        port: 443,
+      }),
+    )
+    store.addViolation(
+      violation('Network request to a.example.org:443 blocked', {
+        type: 'network',
+        host: 'a.example.org',
+        port: 443,
+      }),
+    )
+
+    expect(store.getTotalCount()).toBe(1)
+    expect(store.getViolations()[0]!.host).toBe('a.example.org')
+
+    store.setIgnoreViolations(undefined)
+    store.addViolation(
+      violation('Network request to a.example.com:443 blocked', {
+        type: 'network',
+        host: 'a.example.com',
+        port: 443,
+      }),
+    )
+    expect(store.getTotalCount()).toBe(2)
+  })
+})
@@ -0,0 +1,187 @@
+import { describe, it, expect, beforeEach, afterEach } from 'bun:test'
+import * as fs from 'node:fs'
+import { tmpdir } from 'node:os'
+import { join } from 'node:path'
+import {
+  clearViolationJournal,
+  readViolationJournal,
+  ViolationJournal,
+} from '../../src/sandbox/violation-journal.js'
+import { SandboxViolationStore } from '../../src/sandbox/sandbox-violation-store.js'
+import { violation } from './violation.js'
+
+describe('ViolationJournal', () => {
+  let dir: string
+  let path: string
+
+  beforeEach(() => {
+    dir = fs.mkdtempSync(join(tmpdir(), 'srt-journal-'))
+    path = join(dir, 'violations.jsonl')
+  })
+
+  afterEach(() => {
+    fs.rmSync(dir, { recursive: true, force: true })
+  })
+
+  it('returns nothing for a missing journal', () => {
+    expect(new ViolationJournal(path).recover().violations).toEqual([])
+  })
+
+  it('round-trips violations across restarts', () => {
+    const denial = violation('deny file-read /etc/shadow')
+    const smtp = violation('smtp', { type: 'network', port: 25 })
+    const journal = new ViolationJournal(path)
+    journal.append(denial)
+    journal.append(smtp)
+    journal.close()
+
+    const recovered = new ViolationJournal(path).recover()
+    expect(recovered.violations).toEqual([denial, smtp])
+  })
+
+  it('reads violations without repairing the journal', () => {
+    const first = violation('first')
+    const journal = new ViolationJournal(path)
+    journal.append(first)
+    journal.close()
+    fs.appendFileSync(path, '{"id":"torn","violation":{"li')
+    const size = fs.statSync(path).size
+
+    expect(readViolationJournal(path)).toEqual([first])
+    expect(fs.statSync(path).size).toBe(size)
+    expect(readViolationJournal(join(dir, 'missing.jsonl'))).toEqual([])
+  })
+
+  it('clears the journal in place for a process still appending', () => {
+    const journal = new ViolationJournal(path)
+    journal.append(violation('old'))
+    clearViolationJournal(path)
+    journal.append(violation('new'))
+    journal.close()
+
+    expect(readViolationJournal(path).map(v => v.line)).toEqual(['new'])
+  })
+
+  it('truncates an incomplete trailing record', () => {
+    const journal = new ViolationJournal(path)
+    journal.append(violation('first'))
+    journal.close()
+    fs.appendFileSync(path, '{"id":"torn","violation":{"li')
+
+    const recovered = new ViolationJournal(path).recover()
+    expect(recovered.violations.map(v => v.line)).toEqual(['first'])
+    expect(recovered.truncatedBytes).toBeGreaterThan(0)
+    expect(fs.readFileSync(path, 'utf8').endsWith('\n')).toBe(true)
+
+    // New records start on a clean line after recovery
+    const reopened = new ViolationJournal(path)
+    reopened.append(violation('second'))
+    reopened.close()
+    expect(
+      new ViolationJournal(path).recover().violations.map(v => v.line),
+    ).toEqual(['first', 'second'])
+  })
+
+  it('skips corrupt records', () => {
+    const journal = new ViolationJournal(path)
+    journal.append(violation('kept'))
+    journal.close()
+    fs.appendFileSync(path, 'not json\n')
+
+    const recovered = new ViolationJournal(path).recover()
+    expect(recovered.violations.map(v => v.line)).toEqual(['kept'])
+    expect(recovered.corruptRecords).toBe(1)
+  })
+
+  it('reads journals larger than one chunk', () => {
+    const journal = new ViolationJournal(path)
+    const lines = Array.from({ length: 2000 }, (_, i) => `${i} `.repeat(20))
+    for (const line of lines) {
+      journal.append(violation(line))
+    }
+    journal.close()
+    fs.appendFileSync(path, '{"id":"torn"')
+
+    const recovered = new ViolationJournal(path).recover()
+    expect(recovered.violations.map(v => v.line)).toEqual(lines)
+    expect(recovered.truncatedBytes).toBe('{"id":"torn"'.length)
+    expect(readViolationJournal(path)).toHaveLength(2000)
+  })
+
+  it('drops records past the retention policy and compacts the file', () => {
+    const journal = new ViolationJournal(path)
+    journal.append(
+      violation('old', { timestamp: new Date('2026-01-01T00:00:00.000Z') }),
+    )
+    for (const line of ['a', 'b', 'c']) {
+      journal.append(violation(line))
+    }
+    journal.close()
+
+    const recovered = new ViolationJournal(path, {
+      maxAgeDays: 30,
+      maxEntries: 2,
+    }).recover()
+    expect(recovered.violations.map(v => v.line)).toEqual(['b', 'c'])
+    expect(recovered.expiredRecords).toBe(2)
+    expect(
+      new ViolationJournal(path).recover().violations.map(v => v.line),
+    ).toEqual(['b', 'c'])
+  })
+
+  it('applies maxEntries while appending', () => {
+    const journal = new ViolationJournal(path, { maxEntries: 2 })
+    journal.recover()
+    for (const line of ['a', 'b', 'c', 'd', 'e']) {
+      journal.append(violation(line))
+    }
+    journal.close()
+
+    // Compacted to the newest 2 once 4 were written, then e was appended
+    expect(readViolationJournal(path).map(v => v.line)).toEqual([
+      'c',
+      'd',
+      'e',
+    ])
+  })
+
+  it('compacts in place for another process still appending', () => {
+    const other = new ViolationJournal(path)
+    other.append(violation('other-1'))
+    const journal = new ViolationJournal(path, { maxEntries: 2 })
+    journal.recover()
+    for (const line of ['a', 'b', 'c']) {
+      journal.append(violation(line))
+    }
+    other.append(violation('other-2'))
+    other.close()
+    journal.close()
+
+    expect(readViolationJournal(path).map(v => v.line)).toEqual([
+      'b',
+      'c',
+      'other-2',
+    ])
+  })
+})
+
+describe('SandboxViolationStore with a journal', () => {
+  it('reloads journaled history when attached', () => {
+    const dir = fs.mkdtempSync(join(tmpdir(), 'srt-journal-'))
+    const path = join(dir, 'violations.jsonl')
+    try {
+      const first = new SandboxViolationStore()
+      first.attachJournal(new ViolationJournal(path))
+      first.addViolation(violation('persisted'))
+      first.detachJournal()
+
+      const second = new SandboxViolationStore()
+      second.attachJournal(new ViolationJournal(path))
+      expect(second.getViolations().map(v => v.line)).toEqual(['persisted'])
+      expect(second.getTotalCount()).toBe(1)
+      second.detachJournal()
+    } finally {
+      fs.rmSync(dir, { recursive: true, force: true })
+    }
+  })
+})
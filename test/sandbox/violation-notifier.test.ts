@@ -0,0 +1,148 @@
+import { describe, it, expect } from 'bun:test'
+import { createServer } from 'node:http'
+import type { AddressInfo } from 'node:net'
+import * as fs from 'node:fs'
+import { tmpdir } from 'node:os'
+import { join } from 'node:path'
+import { SandboxViolationStore } from '../../src/sandbox/sandbox-violation-store.js'
+import {
+  getViolationNotification,
+  startViolationNotifier,
+} from '../../src/sandbox/violation-notifier.js'
+import { violation } from './violation.js'
+
+async function waitFor(check: () => boolean): Promise<void> {
+  for (let i = 0; i < 100 && !check(); i++) {
+    await new Promise(resolve => setTimeout(resolve, 20))
+  }
+}
+
+describe('getViolationNotification', () => {
+  it('rates violations by category', () => {
+    expect(
+      getViolationNotification(
+        violation('cat(1) deny(1) file-read-data /etc/shadow'),
+      )?.severity,
+    ).toBe('medium')
+    expect(
+      getViolationNotification(
+        violation('Network request to evil.com:443 blocked', {
+          type: 'network',
+        }),
+      )?.severity,
+    ).toBe('high')
+    expect(
+      getViolationNotification(violation('ps(3) deny(1) process-info-pidinfo'))
+        ?.severity,
+    ).toBe('low')
+  })
+
+  it('skips violations below minSeverity and audited accesses', () => {
+    const read = violation('cat(1) deny(1) file-read-data /etc/shadow')
+    expect(getViolationNotification(read, 'medium')).toBeDefined()
+    expect(getViolationNotification(read, 'high')).toBeUndefined()
+    expect(
+      getViolationNotification(
+        violation('open /etc/hosts', { type: 'file-access' }),
+      ),
+    ).toBeUndefined()
+  })
+})
+
+describe('startViolationNotifier', () => {
+  it('POSTs violations to the webhook', async () => {
+    const bodies: unknown[] = []
+    const headers: (string | undefined)[] = []
+    const server = createServer((req, res) => {
+      let body = ''
+      req.on('data', chunk => (body += chunk))
+      req.on('end', () => {
+        bodies.push(JSON.parse(body))
+        headers.push(req.headers.authorization)
+        res.end()
+      })
+    })
+    await new Promise<void>(resolve => server.listen(0, '127.0.0.1', resolve))
+    const { port } = server.address() as AddressInfo
+
+    const store = new SandboxViolationStore()
+    const stop = startViolationNotifier(store, {
+      webhook: {
+        url: `http://127.0.0.1:${port}/hook`,
+        headers: { Authorization: 'Bearer token' },
+      },
+      minSeverity: 'high',
+    })
+    try {
+      store.addViolation(violation('ps(3) deny(1) process-info-pidinfo'))
+      store.addViolation(violation('touch(7) deny(1) file-write-create /etc/x'))
+      store.addViolation(
+        violation('Network request to evil.com:443 blocked', {
+          type: 'network',
+        }),
+      )
+      await waitFor(() => bodies.length > 0)
+
+      // Both violations recorded within the delay arrive in one request
+      expect(bodies).toHaveLength(1)
+      expect(bodies[0]).toMatchObject({
+        severity: 'high',
+        dropped: 0,
+        notifications: [
+          {
+            severity: 'high',
+            category: 'file-write',
+            violation: { line: 'touch(7) deny(1) file-write-create /etc/x' },
+          },
+          { severity: 'high', category: 'network' },
+        ],
+      })
+      expect(headers[0]).toBe('Bearer token')
+    } finally {
+      stop()
+      server.close()
+    }
+  })
+
+  it('runs the command with the violation on stdin', async () => {
+    const dir = fs.mkdtempSync(join(tmpdir(), 'srt-notify-'))
+    const output = join(dir, 'out')
+    const store = new SandboxViolationStore()
+    const stop = startViolationNotifier(store, {
+      command: `{ echo "$SRT_VIOLATION_SEVERITY"; cat; } > '${output}'`,
+    })
+    try {
+      store.addViolation(violation('cat(1) deny(1) file-read-data /etc/x'))
+      // The JSON line is complete once the file ends with it
+      await waitFor(
+        () =>
+          fs.existsSync(output) &&
+          fs.readFileSync(output, 'utf8').endsWith('}\n'),
+      )
+
+      const [severity, json] = fs.readFileSync(output, 'utf8').split('\n')
+      expect(severity).toBe('medium')
+      expect(JSON.parse(json!).notifications[0].violation.line).toBe(
+        'cat(1) deny(1) file-read-data /etc/x',
+      )
+    } finally {
+      stop()
+      fs.rmSync(dir, { recursive: true, force: true })
+    }
+  })
+
+  it('stops notifying once stopped', async () => {
+    const dir = fs.mkdtempSync(join(tmpdir(), 'srt-notify-'))
+    const output = join(dir, 'out')
+    const store = new SandboxViolationStore()
+    const stop = startViolationNotifier(store, {
+      command: `touch '${output}'`,
+    })
+    stop()
+    store.addViolation(violation('cat(1) deny(1) file-read-data /etc/x'))
+    await new Promise(resolve => setTimeout(resolve, 200))
+
+    expect(fs.existsSync(output)).toBe(false)
+    fs.rmSync(dir, { recursive: true, force: true })
+  })
+})
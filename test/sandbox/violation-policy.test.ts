@@ -0,0 +1,138 @@
+import { describe, it, expect, beforeEach, afterEach } from 'bun:test'
+import type { SandboxViolationWarningContext } from '../../src/sandbox/execution-hooks.js'
+import { SandboxViolationKillError } from '../../src/sandbox/sandbox-execute.js'
+import { SandboxManager } from '../../src/sandbox/sandbox-manager.js'
+import { getPlatform } from '../../src/utils/platform.js'
+import {
+  classifyViolation,
+  getViolationAction,
+  policyMayKill,
+} from '../../src/sandbox/violation-policy.js'
+import { violation } from './violation.js'
+
+describe('classifyViolation', () => {
+  it('classifies macOS denials by operation', () => {
+    expect(
+      classifyViolation(
+        violation('cat(42) deny(1) file-read-data /etc/shadow'),
+      ),
+    ).toBe('file-read')
+    expect(
+      classifyViolation(violation('touch(7) deny(1) file-write-create /x')),
+    ).toBe('file-write')
+    expect(
+      classifyViolation(
+        violation('curl(9) deny(1) network-outbound 93.184.216.34:443'),
+      ),
+    ).toBe('network')
+    expect(
+      classifyViolation(violation('ps(3) deny(1) process-info-pidinfo')),
+    ).toBe('other')
+  })
+
+  it('classifies Linux denials by access and syscall', () => {
+    expect(
+      classifyViolation(
+        violation('deny(EROFS) mkdir /home/user/x', { access: 'write' }),
+      ),
+    ).toBe('file-write')
+    expect(
+      classifyViolation(violation('deny(ENETUNREACH) connect 1.1.1.1:443')),
+    ).toBe('network')
+    expect(classifyViolation(violation('deny(EPERM) socket AF_UNIX'))).toBe(
+      'other',
+    )
+  })
+
+  it('classifies proxy denials as network and skips audited accesses', () => {
+    expect(classifyViolation(violation('blocked', { type: 'network' }))).toBe(
+      'network',
+    )
+    expect(
+      classifyViolation(
+        violation('allow(read) openat /etc/hosts', { type: 'file-access' }),
+      ),
+    ).toBeUndefined()
+  })
+})
+
+describe('getViolationAction', () => {
+  it('falls back to record', () => {
+    const policy = { network: 'kill' as const }
+    expect(
+      getViolationAction(violation('blocked', { type: 'network' }), policy),
+    ).toBe('kill')
+    expect(
+      getViolationAction(violation('deny(EACCES) openat /x'), policy),
+    ).toBe('record')
+    expect(getViolationAction(violation('blocked'), undefined)).toBe('record')
+  })
+
+  it('reports whether a policy can kill', () => {
+    expect(policyMayKill({ 'file-read': 'warn' })).toBe(false)
+    expect(policyMayKill({ other: 'kill' })).toBe(true)
+    expect(policyMayKill(undefined)).toBe(false)
+  })
+})
+
+describe.skipIf(getPlatform() === 'windows')('network violation policy', () => {
+  beforeEach(async () => {
+    // Runs commands unconfined, so the tests need no sandbox dependencies
+    SandboxManager.registerBackend({
+      name: 'test-runner',
+      wrap: async context => context.command,
+    })
+    await SandboxManager.initialize({
+      network: { allowedDomains: [], deniedDomains: [] },
+      filesystem: { denyRead: [], allowWrite: ['.'], denyWrite: [] },
+      violationPolicy: { network: 'kill' },
+    })
+  })
+
+  afterEach(async () => {
+    await SandboxManager.reset()
+    SandboxManager.registerBackend(undefined)
+  })
+
+  function addNetworkViolation(): void {
+    setTimeout(
+      () =>
+        SandboxManager.getSandboxViolationStore().addViolation({
+          line: 'Network request to example.com:443 blocked',
+          type: 'network',
+          timestamp: new Date(),
+        }),
+      200,
+    )
+  }
+
+  it('kills the only running command', async () => {
+    addNetworkViolation()
+    await expect(
+      SandboxManager.execute('sleep 5', { stdio: 'ignore' }),
+    ).rejects.toBeInstanceOf(SandboxViolationKillError)
+  })
+
+  it('only warns while other commands run', async () => {
+    const warnings: SandboxViolationWarningContext[] = []
+    const removeHooks = SandboxManager.addHooks({
+      onViolationWarning: context => {
+        warnings.push(context)
+      },
+    })
+    try {
+      addNetworkViolation()
+      const results = await Promise.all([
+        SandboxManager.execute('sleep 1', { stdio: 'ignore' }),
+        SandboxManager.execute('sleep 1', { stdio: 'ignore' }),
+      ])
+      expect(results).toEqual([
+        { code: 0, signal: null },
+        { code: 0, signal: null },
+      ])
+      expect(warnings).toHaveLength(2)
+    } finally {
+      removeHooks()
+    }
+  })
+})
@@ -0,0 +1,105 @@
+import { describe, it, expect } from 'bun:test'
+import {
+  queryViolations,
+  SandboxViolationStore,
+} from '../../src/sandbox/sandbox-violation-store.js'
+import { violation } from './violation.js'
+
+function createStore(): SandboxViolationStore {
+  const store = new SandboxViolationStore()
+  store.addViolation(
+    violation('cat(10) deny(1) file-read-data /etc/shadow', {
+      timestamp: new Date(1_000),
+      command: 'cat /etc/shadow',
+      processName: 'cat',
+      path: '/etc/shadow',
+    }),
+  )
+  store.addViolation(
+    violation('touch(11) deny(1) file-write-create /etc/x', {
+      timestamp: new Date(2_000),
+      command: 'touch /etc/x',
+      processName: 'touch',
+      path: '/etc/x',
+    }),
+  )
+  store.addViolation(
+    violation('Network request to api.example.com:443 blocked', {
+      timestamp: new Date(3_000),
+      type: 'network',
+      host: 'api.example.com',
+      port: 443,
+    }),
+  )
+  store.addViolation(
+    violation('cat(12) deny(1) file-read-data /home/me/.ssh/id_rsa', {
+      timestamp: new Date(4_000),
+      command: 'cat ~/.ssh/id_rsa',
+      processName: 'cat',
+      path: '/home/me/.ssh/id_rsa',
+    }),
+  )
+  return store
+}
+
+function lines(result: { violations: { line: string }[] }): string[] {
+  return result.violations.map(v => v.line)
+}
+
+describe('SandboxViolationStore.query', () => {
+  it('returns everything without filters', () => {
+    const result = createStore().query()
+    expect(result.total).toBe(4)
+    expect(result.violations).toHaveLength(4)
+  })
+
+  it('filters by category', () => {
+    const store = createStore()
+    expect(store.query({ category: 'network' }).total).toBe(1)
+    expect(store.query({ category: ['file-read', 'file-write'] }).total).toBe(
+      3,
+    )
+  })
+
+  it('filters by time window', () => {
+    const result = createStore().query({
+      since: new Date(2000),
+      until: new Date(4000),
+    })
+    expect(result.total).toBe(2)
+    expect(lines(result)).toEqual([
+      'touch(11) deny(1) file-write-create /etc/x',
+      'Network request to api.example.com:443 blocked',
+    ])
+  })
+
+  it('filters by process, command and target', () => {
+    const store = createStore()
+    expect(store.query({ processName: 'cat' }).total).toBe(2)
+    expect(store.query({ command: 'touch' }).total).toBe(1)
+    expect(store.query({ target: '/etc/**' }).total).toBe(2)
+    expect(store.query({ target: '*.example.com' }).total).toBe(1)
+    expect(store.query({ processName: 'cat', target: '**/.ssh/*' }).total).toBe(
+      1,
+    )
+  })
+
+  it('pages through matches', () => {
+    const store = createStore()
+    const first = store.query({ limit: 3 })
+    const second = store.query({ offset: 3, limit: 3 })
+    expect(first.total).toBe(4)
+    expect(second.total).toBe(4)
+    expect(first.violations).toHaveLength(3)
+    expect(lines(second)).toEqual([
+      'cat(12) deny(1) file-read-data /home/me/.ssh/id_rsa',
+    ])
+  })
+
+  it('queries violations read from elsewhere, e.g. a journal', () => {
+    const violations = createStore().getViolations()
+    const result = queryViolations(violations, { category: 'network' })
+    expect(result.total).toBe(1)
+    expect(queryViolations(violations).violations).toEqual(violations)
+  })
+})
@@ -0,0 +1,58 @@
+import { describe, it, expect } from 'bun:test'
+import { SandboxViolationStore } from '../../src/sandbox/sandbox-violation-store.js'
+import { violation } from './violation.js'
+
+describe('SandboxViolationStore limits', () => {
+  it('keeps the newest 100 violations by default', () => {
+    const store = new SandboxViolationStore()
+    for (let i = 0; i < 150; i++) {
+      store.addViolation(violation(`deny ${i}`))
+    }
+    expect(store.getCount()).toBe(100)
+    expect(store.getTotalCount()).toBe(150)
+    expect(store.getDroppedCount()).toBe(50)
+    expect(store.getViolations()[0]!.line).toBe('deny 50')
+  })
+
+  it('evicts the oldest beyond maxEntries', () => {
+    const store = new SandboxViolationStore()
+    store.setLimits({ maxEntries: 3 })
+    for (let i = 0; i < 5; i++) {
+      store.addViolation(violation(`deny ${i}`))
+    }
+    expect(store.getViolations().map(v => v.line)).toEqual([
+      'deny 2',
+      'deny 3',
+      'deny 4',
+    ])
+    expect(store.getDroppedCount()).toBe(2)
+  })
+
+  it('evicts the oldest beyond maxBytes', () => {
+    const store = new SandboxViolationStore()
+    store.setLimits({ maxEntries: 1000, maxBytes: 10_000 })
+    for (let i = 0; i < 100; i++) {
+      store.addViolation(violation('x'.repeat(1000)))
+    }
+    expect(store.getByteSize()).toBeLessThanOrEqual(10_000)
+    expect(store.getCount()).toBeLessThan(10)
+    expect(store.getDroppedCount()).toBe(100 - store.getCount())
+  })
+
+  it('always keeps the newest violation', () => {
+    const store = new SandboxViolationStore()
+    store.setLimits({ maxBytes: 100 })
+    store.addViolation(violation('x'.repeat(1000)))
+    expect(store.getCount()).toBe(1)
+  })
+
+  it('applies lowered limits to violations already held', () => {
+    const store = new SandboxViolationStore()
+    for (let i = 0; i < 10; i++) {
+      store.addViolation(violation(`deny ${i}`))
+    }
+    store.setLimits({ maxEntries: 4 })
+    expect(store.getCount()).toBe(4)
+    expect(store.getDroppedCount()).toBe(6)
+  })
+})
@@ -0,0 +1,48 @@
+import { describe, it, expect } from 'bun:test'
+import { SandboxViolationStore } from '../../src/sandbox/sandbox-violation-store.js'
+import { violation } from './violation.js'
+
+describe('SandboxViolationStore.stream', () => {
+  it('yields violations added after it was opened', async () => {
+    const store = new SandboxViolationStore()
+    store.addViolation(violation('before'))
+    const stream = store.stream()
+
+    store.addViolation(violation('first'))
+    setTimeout(() => store.addViolation(violation('second')), 10)
+
+    const lines: string[] = []
+    for await (const event of stream) {
+      lines.push(event.line)
+      if (lines.length === 2) break
+    }
+    expect(lines).toEqual(['first', 'second'])
+  })
+
+  it('ends when the signal is aborted', async () => {
+    const store = new SandboxViolationStore()
+    const controller = new AbortController()
+    const stream = store.stream({ signal: controller.signal })
+
+    const pending = stream.next()
+    controller.abort()
+    expect((await pending).done).toBe(true)
+
+    // Closed streams no longer buffer
+    store.addViolation(violation('after'))
+    expect((await stream.next()).done).toBe(true)
+  })
+
+  it('drops the oldest violations for a lagging consumer', async () => {
+    const store = new SandboxViolationStore()
+    const stream = store.stream({ maxBuffered: 2 })
+    for (const line of ['a', 'b', 'c']) {
+      store.addViolation(violation(line))
+    }
+
+    expect(stream.dropped).toBe(1)
+    expect((await stream.next()).value?.line).toBe('b')
+    expect((await stream.next()).value?.line).toBe('c')
+    await stream.return?.()
+  })
+})
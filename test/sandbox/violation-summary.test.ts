@@ -0,0 +1,89 @@
+import { describe, it, expect } from 'bun:test'
+import {
+  formatViolationSummary,
+  summarizeViolations,
+} from '../../src/sandbox/violation-summary.js'
+import type { NetworkViolationSubtype } from '../../src/sandbox/macos-sandbox-utils.js'
+import { violation } from './violation.js'
+
+function blocked(host: string, subtype?: NetworkViolationSubtype) {
+  return violation(`Network request to ${host}:443 blocked`, {
+    type: 'network',
+    subtype,
+    host,
+    port: 443,
+  })
+}
+
+const violations = [
+  blocked('registry.npmjs.org', 'not-allowed'),
+  blocked('registry.npmjs.org', 'not-allowed'),
+  blocked('telemetry.example.com', 'denied-domain'),
+  blocked('169.254.169.254', 'cloud-metadata'),
+  violation('deny(EROFS) openat /home/me/.cache/x', {
+    access: 'write',
+    path: '/home/me/.cache/x',
+  }),
+  violation('deny(EACCES) openat /home/me/.ssh/id_rsa', {
+    access: 'read',
+    path: '/home/me/.ssh/id_rsa',
+  }),
+  violation('ps(3) deny(1) process-info-pidinfo'),
+  violation('allow(read) open /etc/hosts', {
+    type: 'file-access',
+    path: '/etc/hosts',
+  }),
+]
+
+describe('summarizeViolations', () => {
+  it('counts denials by category, leaving out audited accesses', () => {
+    const summary = summarizeViolations(violations)
+    expect(summary.total).toBe(7)
+    expect(summary.counts).toEqual({
+      network: 4,
+      'file-read': 1,
+      'file-write': 1,
+      other: 1,
+    })
+  })
+
+  it('ranks blocked domains and denied paths', () => {
+    const summary = summarizeViolations(violations, 2)
+    expect(summary.topBlockedDomains).toEqual([
+      { host: 'registry.npmjs.org', count: 2 },
+      { host: '169.254.169.254', count: 1 },
+    ])
+    expect(summary.topDeniedPaths.map(p => p.path)).toEqual([
+      '/home/me/.cache/x',
+      '/home/me/.ssh/id_rsa',
+    ])
+  })
+
+  it('only suggests domains and writes that config could allow', () => {
+    expect(summarizeViolations(violations).suggestedConfig).toEqual({
+      network: { allowedDomains: ['registry.npmjs.org'] },
+      filesystem: { allowWrite: ['/home/me/.cache/x'] },
+    })
+  })
+})
+
+describe('formatViolationSummary', () => {
+  it('prints counts, top entries and suggestions', () => {
+    const text = formatViolationSummary(summarizeViolations(violations))
+    expect(text).toContain(
+      'Sandbox violations: 7 (network: 4, file-read: 1, file-write: 1, other: 1)',
+    )
+    expect(text).toContain('Top blocked domains:')
+    expect(text).toContain('registry.npmjs.org')
+    expect(text).toContain('Suggested config additions')
+    expect(text).toContain('"allowWrite"')
+  })
+
+  it('leaves out empty sections', () => {
+    const text = formatViolationSummary(
+      summarizeViolations([violation('ps(3) deny(1) process-info-pidinfo')]),
+    )
+    expect(text).not.toContain('Top blocked domains')
+    expect(text).not.toContain('Suggested')
+  })
+})
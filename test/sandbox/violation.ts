@@ -0,0 +1,12 @@
+import type { SandboxViolationEvent } from '../../src/sandbox/macos-sandbox-utils.js'
+
+/**
+ * A violation with the given log line, recorded now unless extra sets the
+ * timestamp
+ */
+export function violation(
+  line: string,
+  extra: Partial<SandboxViolationEvent> = {},
+): SandboxViolationEvent {
+  return { line, timestamp: new Date(), ...extra }
+}
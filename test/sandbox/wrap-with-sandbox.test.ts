@@ -1,4 +1,5 @@
 import { describe, it, expect, beforeAll, afterAll } from 'bun:test'
+import { existsSync } from 'node:fs'
 import { SandboxManager } from '../../src/sandbox/sandbox-manager.js'
 import type { SandboxRuntimeConfig } from '../../src/sandbox/sandbox-config.js'
 import { getPlatform } from '../../src/utils/platform.js'
@@ -651,3 +652,112 @@ describe('empty allowedDomains network blocking (CVE fix)', () => {
     })
   })
 })
+
+describe('proc masking on Linux', () => {
+  const command = 'cat /proc/keys'
+
+  it('masks sensitive /proc entries by default', async () => {
+    if (getPlatform() !== 'linux' || !existsSync('/proc/keys')) {
+      return
+    }
+
+    const result = await wrapCommandWithSandboxLinux({
+      command,
+      needsNetworkRestriction: false,
+      readConfig: { denyOnly: ['/secret'] },
+      writeConfig: undefined,
+    })
+
+    expect(result).toContain('--unshare-pid')
+    expect(result).toContain('--ro-bind /dev/null /proc/keys')
+  })
+
+  it('leaves /proc entries visible with allowSensitiveProcEntries', async () => {
+    if (getPlatform() !== 'linux') {
+      return
+    }
+
+    const result = await wrapCommandWithSandboxLinux({
+      command,
+      needsNetworkRestriction: false,
+      readConfig: { denyOnly: ['/secret'] },
+      writeConfig: undefined,
+      allowSensitiveProcEntries: true,
+    })
+
+    expect(result).not.toContain('/proc/keys')
+  })
+})
+
+describe('capabilities on Linux', () => {
+  const command = 'echo hello'
+
+  it('drops all capabilities by default', async () => {
+    if (getPlatform() !== 'linux') {
+      return
+    }
+
+    const result = await wrapCommandWithSandboxLinux({
+      command,
+      needsNetworkRestriction: false,
+      readConfig: { denyOnly: ['/secret'] },
+      writeConfig: undefined,
+    })
+
+    expect(result).toContain('--cap-drop ALL')
+    expect(result).not.toContain('--cap-add')
+  })
+
+  it('adds back configured capabilities', async () => {
+    if (getPlatform() !== 'linux') {
+      return
+    }
+
+    const result = await wrapCommandWithSandboxLinux({
+      command,
+      needsNetworkRestriction: false,
+      readConfig: { denyOnly: ['/secret'] },
+      writeConfig: undefined,
+      capabilities: ['CAP_NET_BIND_SERVICE'],
+    })
+
+    expect(result).toContain('--cap-drop ALL --cap-add CAP_NET_BIND_SERVICE')
+  })
+})
+
+describe('tmpfs mounts on Linux', () => {
+  const command = 'echo hello'
+
+  it('caps tmpfs size and mounts extra tmpfs directories', async () => {
+    if (getPlatform() !== 'linux') {
+      return
+    }
+
+    const result = await wrapCommandWithSandboxLinux({
+      command,
+      needsNetworkRestriction: false,
+      readConfig: { denyOnly: [] },
+      writeConfig: { allowOnly: [], denyWithinAllow: [] },
+      tmpfsSizeMB: 64,
+      extraTmpfs: ['/tmp'],
+    })
+
+    expect(result).toContain(`--size ${64 * 1024 * 1024} --tmpfs /tmp`)
+  })
+
+  it('skips extra tmpfs paths that do not exist', async () => {
+    if (getPlatform() !== 'linux') {
+      return
+    }
+
+    const result = await wrapCommandWithSandboxLinux({
+      command,
+      needsNetworkRestriction: false,
+      readConfig: { denyOnly: [] },
+      writeConfig: { allowOnly: [], denyWithinAllow: [] },
+      extraTmpfs: ['/does/not/exist'],
+    })
+
+    expect(result).not.toContain('/does/not/exist')
+  })
+})
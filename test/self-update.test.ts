@@ -0,0 +1,149 @@
+import { describe, test, expect } from 'bun:test'
+import { generateKeyPairSync, sign } from 'node:crypto'
+import {
+  compareVersions,
+  computeIntegrity,
+  verifyRegistrySignature,
+  type RegistryKey,
+} from '../src/utils/self-update.js'
+
+const NAME = '@anthropic-ai/sandbox-runtime'
+const VERSION = '1.2.3'
+const PUBLISHED = new Date('2025-01-01T00:00:00.000Z')
+
+/**
+ * Create a registry-style P-256 key and a signature over name@version:integrity
+ */
+function createSignedRelease(integrity: string, keyid = 'SHA256:test') {
+  const { publicKey, privateKey } = generateKeyPairSync('ec', {
+    namedCurve: 'prime256v1',
+  })
+  const key: RegistryKey = {
+    keyid,
+    key: publicKey.export({ format: 'der', type: 'spki' }).toString('base64'),
+    expires: null,
+  }
+  const sig = sign(
+    'sha256',
+    Buffer.from(`${NAME}@${VERSION}:${integrity}`),
+    privateKey,
+  ).toString('base64')
+  return { key, signature: { keyid, sig } }
+}
+
+describe('Self-update verification', () => {
+  test('computes sha512 integrity strings', () => {
+    const integrity = computeIntegrity(Buffer.from('hello'))
+    expect(integrity).toStartWith('sha512-')
+    expect(integrity).toBe(computeIntegrity(Buffer.from('hello')))
+    expect(integrity).not.toBe(computeIntegrity(Buffer.from('hello!')))
+  })
+
+  test('accepts a valid registry signature', () => {
+    const integrity = computeIntegrity(Buffer.from('tarball'))
+    const { key, signature } = createSignedRelease(integrity)
+
+    expect(
+      verifyRegistrySignature(
+        NAME,
+        VERSION,
+        integrity,
+        [signature],
+        [key],
+        PUBLISHED,
+      ),
+    ).toBe(true)
+  })
+
+  test('rejects a signature over a different integrity', () => {
+    const integrity = computeIntegrity(Buffer.from('tarball'))
+    const { key, signature } = createSignedRelease(integrity)
+    const tampered = computeIntegrity(Buffer.from('tampered'))
+
+    expect(
+      verifyRegistrySignature(
+        NAME,
+        VERSION,
+        tampered,
+        [signature],
+        [key],
+        PUBLISHED,
+      ),
+    ).toBe(false)
+  })
+
+  test('rejects a signature for a different version', () => {
+    const integrity = computeIntegrity(Buffer.from('tarball'))
+    const { key, signature } = createSignedRelease(integrity)
+
+    expect(
+      verifyRegistrySignature(
+        NAME,
+        '9.9.9',
+        integrity,
+        [signature],
+        [key],
+        PUBLISHED,
+      ),
+    ).toBe(false)
+  })
+
+  test('rejects signatures from unknown or expired keys', () => {
+    const integrity = computeIntegrity(Buffer.from('tarball'))
+    const { key, signature } = createSignedRelease(integrity)
+
+    expect(
+      verifyRegistrySignature(
+        NAME,
+        VERSION,
+        integrity,
+        [{ ...signature, keyid: 'SHA256:other' }],
+        [key],
+        PUBLISHED,
+      ),
+    ).toBe(false)
+
+    const expiredKey = { ...key, expires: '2000-01-01T00:00:00.000Z' }
+    expect(
+      verifyRegistrySignature(
+        NAME,
+        VERSION,
+        integrity,
+        [signature],
+        [expiredKey],
+        PUBLISHED,
+      ),
+    ).toBe(false)
+  })
+
+  test('accepts keys that expired after the release was published', () => {
+    const integrity = computeIntegrity(Buffer.from('tarball'))
+    const { key, signature } = createSignedRelease(integrity)
+    const rotatedKey = { ...key, expires: '2025-06-01T00:00:00.000Z' }
+
+    expect(
+      verifyRegistrySignature(
+        NAME,
+        VERSION,
+        integrity,
+        [signature],
+        [rotatedKey],
+        PUBLISHED,
+      ),
+    ).toBe(true)
+  })
+})
+
+describe('compareVersions', () => {
+  test('orders releases by major, minor and patch', () => {
+    expect(compareVersions('1.2.3', '1.2.4')).toBeLessThan(0)
+    expect(compareVersions('1.10.0', '1.9.0')).toBeGreaterThan(0)
+    expect(compareVersions('2.0.0', '2.0.0')).toBe(0)
+  })
+
+  test('orders prereleases before their release', () => {
+    expect(compareVersions('1.3.0-beta.1', '1.3.0')).toBeLessThan(0)
+    expect(compareVersions('1.3.0-beta.1', '1.2.0')).toBeGreaterThan(0)
+    expect(compareVersions('1.3.0-beta.2', '1.3.0-beta.10')).toBeLessThan(0)
+  })
+})
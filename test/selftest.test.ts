@@ -0,0 +1,127 @@
+import { describe, it, expect } from 'bun:test'
+import { homedir } from 'node:os'
+import {
+  buildSelftestProbes,
+  formatSelftestTable,
+  type SelftestProbe,
+} from '../src/sandbox/selftest.js'
+import type { SandboxRuntimeConfig } from '../src/sandbox/sandbox-config.js'
+
+function makeConfig(
+  overrides: Partial<SandboxRuntimeConfig['network']> = {},
+): SandboxRuntimeConfig {
+  return {
+    network: { allowedDomains: [], deniedDomains: [], ...overrides },
+    filesystem: { denyRead: [], allowWrite: [], denyWrite: [] },
+  }
+}
+
+function findProbe(probes: SelftestProbe[], prefix: string): SelftestProbe {
+  const probe = probes.find(p => p.name.startsWith(prefix))
+  if (!probe) {
+    throw new Error(`No probe named ${prefix}`)
+  }
+  return probe
+}
+
+describe('buildSelftestProbes', () => {
+  it('covers network, filesystem, unix socket and local bind', () => {
+    const probes = buildSelftestProbes(makeConfig(), '/tmp/srt-selftest')
+    const names = probes.map(p => p.name.split(':')[0])
+    expect(new Set(names)).toEqual(
+      new Set(['network', 'filesystem', 'unix socket', 'local bind']),
+    )
+  })
+
+  it('picks an allowed and a denied domain from the config', () => {
+    const probes = buildSelftestProbes(
+      makeConfig({ allowedDomains: ['example.com', '*.github.com'] }),
+      '/tmp/srt-selftest',
+    )
+    const allowed = findProbe(probes, 'network: fetch allowed')
+    const denied = findProbe(probes, 'network: fetch denied')
+    if (allowed.command) {
+      expect(allowed.command).toContain('https://example.com/')
+      expect(denied.command).toContain('https://example.org/')
+    } else {
+      // curl is not installed on this host
+      expect(allowed.skipReason).toBeDefined()
+    }
+  })
+
+  it('skips the allowed fetch when only wildcard domains are allowed', () => {
+    const probes = buildSelftestProbes(
+      makeConfig({ allowedDomains: ['*.github.com'] }),
+      '/tmp/srt-selftest',
+    )
+    expect(findProbe(probes, 'network: fetch allowed').command).toBeUndefined()
+  })
+
+  it('expects writes to allowWrite to succeed and home to be blocked', () => {
+    const config = makeConfig()
+    config.filesystem.allowWrite = ['/tmp']
+    const probes = buildSelftestProbes(config, '/tmp/srt-selftest')
+
+    const allowed = findProbe(probes, 'filesystem: write allowed')
+    expect(allowed.expected).toBe('allow')
+    expect(allowed.command).toBeDefined()
+
+    const denied = findProbe(probes, 'filesystem: write denied')
+    expect(denied.expected).toBe('deny')
+    expect(denied.command).toContain(homedir())
+  })
+
+  it('skips the denied read when denyRead is empty', () => {
+    const probes = buildSelftestProbes(makeConfig(), '/tmp/srt-selftest')
+    const probe = findProbe(probes, 'filesystem: read denied')
+    expect(probe.command).toBeUndefined()
+    expect(probe.skipReason).toBeDefined()
+  })
+
+  it('derives socket expectations from the network config', () => {
+    const strict = buildSelftestProbes(makeConfig(), '/tmp/x', 'macos')
+    expect(findProbe(strict, 'unix socket').expected).toBe('deny')
+    expect(findProbe(strict, 'local bind').expected).toBe('deny')
+
+    const relaxed = buildSelftestProbes(
+      makeConfig({ allowAllUnixSockets: true, allowLocalBinding: true }),
+      '/tmp/x',
+      'macos',
+    )
+    expect(findProbe(relaxed, 'unix socket').expected).toBe('allow')
+    expect(findProbe(relaxed, 'local bind').expected).toBe('allow')
+
+    // Loopback is private to the network namespace on Linux
+    const linux = buildSelftestProbes(makeConfig(), '/tmp/x', 'linux')
+    expect(findProbe(linux, 'local bind').expected).toBe('allow')
+  })
+})
+
+describe('formatSelftestTable', () => {
+  it('renders one row per probe and a summary', () => {
+    const table = formatSelftestTable([
+      {
+        name: 'network: fetch denied domain',
+        expected: 'deny',
+        outcome: 'pass',
+      },
+      {
+        name: 'filesystem: write denied path',
+        expected: 'deny',
+        outcome: 'fail',
+        detail: 'was allowed',
+      },
+      {
+        name: 'filesystem: read denied path',
+        expected: 'deny',
+        outcome: 'skip',
+        detail: 'no existing non-glob path in denyRead',
+      },
+    ])
+    const lines = table.split('\n')
+    expect(lines[0]).toMatch(/^PROBE\s+EXPECTED\s+RESULT$/)
+    expect(lines[1]).toMatch(/PASS$/)
+    expect(lines[2]).toContain('FAIL (was allowed)')
+    expect(lines[lines.length - 1]).toBe('1 passed, 1 failed, 1 skipped')
+  })
+})